@@ -0,0 +1,147 @@
+use std::{
+    sync::atomic::{AtomicU64, AtomicUsize},
+    time::{Duration, Instant},
+};
+
+pub const ADAPTIVE_FPS_MAX_DROPS_PER_SEC: u64 = 5;
+pub const ADAPTIVE_FPS_DOWNGRADE_AFTER: Duration = Duration::from_secs(3);
+pub const ADAPTIVE_FPS_RESTORE_AFTER: Duration = Duration::from_secs(10);
+pub const ADAPTIVE_FPS_MIN_FPS: u32 = 10;
+
+#[derive(Debug, Default)]
+pub struct PipelineLoad {
+    pub dropped_frames: AtomicU64,
+    pub paced_frames: AtomicU64,
+    pub queued_frames: AtomicUsize,
+}
+
+#[derive(Debug)]
+pub struct AdaptiveFpsController {
+    original_fps: u32,
+    applied_fps: u32,
+    max_drops_per_sec: u64,
+    last_sample: Option<(Instant, u64)>,
+    overloaded_since: Option<Instant>,
+    idle_since: Option<Instant>,
+}
+
+impl AdaptiveFpsController {
+    pub fn new(fps: u32, max_drops_per_sec: u64) -> Self {
+        Self {
+            original_fps: fps,
+            applied_fps: fps,
+            max_drops_per_sec,
+            last_sample: None,
+            overloaded_since: None,
+            idle_since: None,
+        }
+    }
+
+    pub fn observe(
+        &mut self,
+        now: Instant,
+        total_dropped: u64,
+        queued_frames: usize,
+    ) -> Option<u32> {
+        let (sampled_at, dropped_before) = self.last_sample.replace((now, total_dropped))?;
+
+        let elapsed_ms = now.saturating_duration_since(sampled_at).as_millis() as u64;
+        let dropped = total_dropped.saturating_sub(dropped_before);
+        let overloaded = dropped.saturating_mul(1_000) > self.max_drops_per_sec * elapsed_ms.max(1);
+
+        if overloaded {
+            self.idle_since = None;
+            let since = *self.overloaded_since.get_or_insert(sampled_at);
+            let floor = ADAPTIVE_FPS_MIN_FPS.min(self.original_fps);
+            if now.saturating_duration_since(since) < ADAPTIVE_FPS_DOWNGRADE_AFTER
+                || self.applied_fps <= floor
+            {
+                return None;
+            }
+            self.overloaded_since = None;
+            self.applied_fps = (self.applied_fps * 2 / 3).max(floor);
+            return Some(self.applied_fps);
+        }
+
+        self.overloaded_since = None;
+        if queued_frames > 0 || self.applied_fps == self.original_fps {
+            self.idle_since = None;
+            return None;
+        }
+
+        let since = *self.idle_since.get_or_insert(now);
+        if now.saturating_duration_since(since) < ADAPTIVE_FPS_RESTORE_AFTER {
+            return None;
+        }
+        self.idle_since = None;
+        self.applied_fps = self.original_fps;
+        Some(self.applied_fps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(start: Instant, ms: u64) -> Instant {
+        start + Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn baja_los_fps_tras_descartes_sostenidos() {
+        let mut controller = AdaptiveFpsController::new(60, 5);
+        let start = Instant::now();
+
+        assert_eq!(controller.observe(start, 0, 6), None);
+        assert_eq!(controller.observe(at(start, 1_000), 20, 6), None);
+        assert_eq!(controller.observe(at(start, 2_000), 40, 6), None);
+        assert_eq!(controller.observe(at(start, 3_000), 60, 6), Some(40));
+        assert_eq!(controller.observe(at(start, 4_000), 80, 6), None);
+    }
+
+    #[test]
+    fn descartes_aislados_no_bajan_los_fps() {
+        let mut controller = AdaptiveFpsController::new(30, 5);
+        let start = Instant::now();
+
+        controller.observe(start, 0, 0);
+        for second in 1..=10 {
+            assert_eq!(
+                controller.observe(at(start, second * 1_000), second * 2, 1),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn vuelve_a_los_fps_originales_con_la_cola_vacia() {
+        let mut controller = AdaptiveFpsController::new(30, 5);
+        let start = Instant::now();
+        controller.observe(start, 0, 6);
+        controller.observe(at(start, 1_000), 30, 6);
+        controller.observe(at(start, 2_000), 60, 6);
+        assert_eq!(controller.observe(at(start, 3_000), 90, 6), Some(20));
+
+        assert_eq!(controller.observe(at(start, 8_000), 90, 0), None);
+        assert_eq!(controller.observe(at(start, 12_000), 90, 1), None);
+        assert_eq!(controller.observe(at(start, 13_000), 90, 0), None);
+        assert_eq!(controller.observe(at(start, 22_000), 90, 0), None);
+        assert_eq!(controller.observe(at(start, 23_000), 90, 0), Some(30));
+    }
+
+    #[test]
+    fn no_baja_de_los_fps_minimos() {
+        let mut controller = AdaptiveFpsController::new(12, 1);
+        let start = Instant::now();
+        controller.observe(start, 0, 6);
+        controller.observe(at(start, 1_000), 10, 6);
+        controller.observe(at(start, 2_000), 20, 6);
+        assert_eq!(controller.observe(at(start, 3_000), 30, 6), Some(10));
+        for second in 4..12 {
+            assert_eq!(
+                controller.observe(at(start, second * 1_000), second * 10, 6),
+                None
+            );
+        }
+    }
+}
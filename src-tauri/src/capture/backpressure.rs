@@ -0,0 +1,137 @@
+use std::{
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+pub const EVENT_ENCODER_BACKPRESSURE: &str = "encoder-backpressure";
+pub const BACKPRESSURE_WINDOW: Duration = Duration::from_secs(5);
+pub const BACKPRESSURE_DROP_THRESHOLD: u64 = 30;
+
+const BACKPRESSURE_SUGGESTION: &str =
+    "El encoder no da abasto: baja la resolución o los FPS, o cambia al modo Performance";
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncoderBackpressure {
+    pub total_dropped: u64,
+    pub dropped_in_window: u64,
+    pub queue_depth: usize,
+    pub finished: bool,
+    pub suggestion: String,
+}
+
+impl EncoderBackpressure {
+    pub fn new(
+        total_dropped: u64,
+        dropped_in_window: u64,
+        queue_depth: usize,
+        finished: bool,
+    ) -> Self {
+        Self {
+            total_dropped,
+            dropped_in_window,
+            queue_depth,
+            finished,
+            suggestion: BACKPRESSURE_SUGGESTION.to_string(),
+        }
+    }
+}
+
+pub type EncoderBackpressureSink = Arc<dyn Fn(EncoderBackpressure) + Send + Sync>;
+
+fn backpressure_sink_slot() -> &'static Mutex<Option<EncoderBackpressureSink>> {
+    static SLOT: OnceLock<Mutex<Option<EncoderBackpressureSink>>> = OnceLock::new();
+    SLOT.get_or_init(|| Mutex::new(None))
+}
+
+pub fn set_encoder_backpressure_sink(sink: EncoderBackpressureSink) {
+    if let Ok(mut guard) = backpressure_sink_slot().lock() {
+        *guard = Some(sink);
+    }
+}
+
+pub(crate) fn notify_encoder_backpressure(event: EncoderBackpressure) {
+    let sink = backpressure_sink_slot()
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone());
+    if let Some(sink) = sink {
+        sink(event);
+    }
+}
+
+#[derive(Debug)]
+pub struct BackpressureWindow {
+    window: Duration,
+    threshold: u64,
+    started_at: Option<Instant>,
+    dropped_before: u64,
+    notified: bool,
+}
+
+impl BackpressureWindow {
+    pub fn new(window: Duration, threshold: u64) -> Self {
+        Self {
+            window,
+            threshold,
+            started_at: None,
+            dropped_before: 0,
+            notified: false,
+        }
+    }
+
+    pub fn record_drop(&mut self, now: Instant, total_dropped: u64) -> Option<u64> {
+        let expired = self
+            .started_at
+            .is_none_or(|started_at| now.saturating_duration_since(started_at) >= self.window);
+        if expired {
+            self.started_at = Some(now);
+            self.dropped_before = total_dropped.saturating_sub(1);
+            self.notified = false;
+        }
+
+        let dropped_in_window = total_dropped.saturating_sub(self.dropped_before);
+        if self.notified || dropped_in_window <= self.threshold {
+            return None;
+        }
+        self.notified = true;
+        Some(dropped_in_window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn avisa_una_vez_al_superar_el_umbral_en_la_ventana() {
+        let mut window = BackpressureWindow::new(Duration::from_secs(5), 3);
+        let start = Instant::now();
+
+        assert_eq!(window.record_drop(start, 1), None);
+        assert_eq!(window.record_drop(start + Duration::from_secs(1), 2), None);
+        assert_eq!(window.record_drop(start + Duration::from_secs(2), 3), None);
+        assert_eq!(
+            window.record_drop(start + Duration::from_secs(3), 4),
+            Some(4)
+        );
+        assert_eq!(window.record_drop(start + Duration::from_secs(4), 5), None);
+    }
+
+    #[test]
+    fn la_ventana_nueva_empieza_a_contar_de_cero() {
+        let mut window = BackpressureWindow::new(Duration::from_secs(5), 2);
+        let start = Instant::now();
+
+        for total in 1..=3 {
+            window.record_drop(start, total);
+        }
+        assert_eq!(window.record_drop(start + Duration::from_secs(6), 4), None);
+        assert_eq!(window.record_drop(start + Duration::from_secs(12), 5), None);
+        assert_eq!(window.record_drop(start + Duration::from_secs(13), 6), None);
+        assert_eq!(
+            window.record_drop(start + Duration::from_secs(14), 7),
+            Some(3)
+        );
+    }
+}
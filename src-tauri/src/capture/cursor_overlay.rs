@@ -0,0 +1,357 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::capture::models::{FramePixelFormat, RawFrame};
+
+pub const MIN_CURSOR_SCALE: f32 = 0.5;
+pub const MAX_CURSOR_SCALE: f32 = 4.0;
+
+pub fn default_cursor_scale() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CursorOverlayConfig {
+    pub scale_percent: u32,
+    pub origin_x: i32,
+    pub origin_y: i32,
+}
+
+impl CursorOverlayConfig {
+    pub fn from_scale(scale: f32, origin_x: i32, origin_y: i32) -> Result<Option<Self>, String> {
+        if !(MIN_CURSOR_SCALE..=MAX_CURSOR_SCALE).contains(&scale) {
+            return Err(format!(
+                "Escala de cursor inválida: {scale}. Debe estar entre {MIN_CURSOR_SCALE} y {MAX_CURSOR_SCALE}"
+            ));
+        }
+        let scale_percent = (scale * 100.0).round() as u32;
+        Ok((scale_percent != 100).then_some(Self {
+            scale_percent,
+            origin_x,
+            origin_y,
+        }))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorSprite {
+    pub width: u32,
+    pub height: u32,
+    pub hotspot_x: i32,
+    pub hotspot_y: i32,
+    pub pixels: Vec<u8>,
+}
+
+impl CursorSprite {
+    /// GDI no deja un alfa confiable al dibujar cursores monocromos, así que
+    /// el cursor se dibuja sobre negro y sobre blanco: lo que cambia entre
+    /// ambos es la transparencia y el dibujo sobre negro ya es el color
+    /// premultiplicado.
+    pub fn from_black_and_white(
+        width: u32,
+        height: u32,
+        on_black: &[u8],
+        on_white: &[u8],
+    ) -> Result<Self, String> {
+        let size = (width * height * 4) as usize;
+        if on_black.len() < size || on_white.len() < size {
+            return Err("El cursor renderizado está incompleto".to_string());
+        }
+
+        let mut pixels = Vec::with_capacity(size);
+        for (black, white) in on_black[..size]
+            .chunks_exact(4)
+            .zip(on_white[..size].chunks_exact(4))
+        {
+            let see_through: u32 = (0..3)
+                .map(|channel| u32::from(white[channel].saturating_sub(black[channel])))
+                .sum();
+            let alpha = 255 - (see_through / 3) as u8;
+            // Un cursor que invierte la pantalla queda opaco con su color
+            // sobre negro; el premultiplicado no puede pasar del alfa.
+            pixels.extend_from_slice(&[
+                black[0].min(alpha),
+                black[1].min(alpha),
+                black[2].min(alpha),
+                alpha,
+            ]);
+        }
+        Ok(Self {
+            width,
+            height,
+            hotspot_x: 0,
+            hotspot_y: 0,
+            pixels,
+        })
+    }
+
+    pub fn blend_onto(&self, frame: &mut RawFrame, x: i32, y: i32) {
+        if frame.pixel_format != FramePixelFormat::Bgra8 || !frame.is_cpu_layout_valid() {
+            return;
+        }
+        let stride = frame.row_stride_bytes as usize;
+        let start_col = (-x).max(0);
+        let end_col = (frame.width as i32 - x).min(self.width as i32);
+        for row in (-y).max(0)..(frame.height as i32 - y).min(self.height as i32) {
+            for col in start_col..end_col {
+                let src = ((row as u32 * self.width + col as u32) * 4) as usize;
+                let alpha = u32::from(self.pixels[src + 3]);
+                if alpha == 0 {
+                    continue;
+                }
+                let dst = (y + row) as usize * stride + (x + col) as usize * 4;
+                for channel in 0..3 {
+                    let under = u32::from(frame.data[dst + channel]);
+                    let over = u32::from(self.pixels[src + channel]);
+                    frame.data[dst + channel] = (over + under * (255 - alpha) / 255).min(255) as u8;
+                }
+            }
+        }
+    }
+}
+
+pub struct CursorOverlay {
+    config: CursorOverlayConfig,
+    cached: Option<(usize, Option<CursorSprite>)>,
+    drawn: Option<CursorState>,
+}
+
+impl CursorOverlay {
+    pub fn new(config: CursorOverlayConfig) -> Self {
+        Self {
+            config,
+            cached: None,
+            drawn: None,
+        }
+    }
+
+    pub fn draw(&mut self, frame: &mut RawFrame) {
+        self.drawn = platform::current_cursor();
+        let Some(cursor) = self.drawn else {
+            return;
+        };
+        if self.cached.as_ref().map(|(handle, _)| *handle) != Some(cursor.handle) {
+            let sprite = platform::render_cursor(cursor.handle, self.config.scale_percent)
+                .map_err(|err| eprintln!("[capture] No se pudo dibujar el cursor: {err}"))
+                .ok();
+            self.cached = Some((cursor.handle, sprite));
+        }
+        let Some((_, Some(sprite))) = &self.cached else {
+            return;
+        };
+        sprite.blend_onto(
+            frame,
+            cursor.screen_x - self.config.origin_x - sprite.hotspot_x,
+            cursor.screen_y - self.config.origin_y - sprite.hotspot_y,
+        );
+    }
+
+    pub fn is_stale(&self) -> bool {
+        platform::current_cursor() != self.drawn
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CursorState {
+    handle: usize,
+    screen_x: i32,
+    screen_y: i32,
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{ffi::c_void, mem, ptr};
+
+    use windows::Win32::{
+        Graphics::Gdi::{
+            CreateCompatibleDC, CreateDIBSection, DeleteDC, DeleteObject, GetObjectW, SelectObject,
+            BITMAP, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, HBITMAP,
+        },
+        UI::WindowsAndMessaging::{
+            DrawIconEx, GetCursorInfo, GetIconInfo, CURSORINFO, CURSOR_SHOWING, DI_NORMAL, HCURSOR,
+            HICON, ICONINFO,
+        },
+    };
+
+    use super::{CursorSprite, CursorState};
+
+    pub(super) fn current_cursor() -> Option<CursorState> {
+        let mut info = CURSORINFO {
+            cbSize: mem::size_of::<CURSORINFO>() as u32,
+            ..Default::default()
+        };
+        unsafe { GetCursorInfo(&mut info) }.ok()?;
+        if info.flags.0 & CURSOR_SHOWING.0 == 0 || info.hCursor.is_invalid() {
+            return None;
+        }
+        Some(CursorState {
+            handle: info.hCursor.0 as usize,
+            screen_x: info.ptScreenPos.x,
+            screen_y: info.ptScreenPos.y,
+        })
+    }
+
+    pub(super) fn render_cursor(handle: usize, scale_percent: u32) -> Result<CursorSprite, String> {
+        let icon = HICON::from(HCURSOR(handle as *mut c_void));
+        let (width, height, hotspot_x, hotspot_y) = cursor_geometry(icon)?;
+        let scaled = |value: u32| (value * scale_percent).div_ceil(100).max(1);
+        let (scaled_width, scaled_height) = (scaled(width), scaled(height));
+
+        let on_black = draw_on_background(icon, scaled_width, scaled_height, 0x00)?;
+        let on_white = draw_on_background(icon, scaled_width, scaled_height, 0xff)?;
+        let mut sprite =
+            CursorSprite::from_black_and_white(scaled_width, scaled_height, &on_black, &on_white)?;
+        sprite.hotspot_x = (hotspot_x * scale_percent / 100) as i32;
+        sprite.hotspot_y = (hotspot_y * scale_percent / 100) as i32;
+        Ok(sprite)
+    }
+
+    fn cursor_geometry(icon: HICON) -> Result<(u32, u32, u32, u32), String> {
+        let mut info = ICONINFO::default();
+        unsafe { GetIconInfo(icon, &mut info) }
+            .map_err(|err| format!("No se pudo leer el cursor: {err}"))?;
+
+        let mut mask = BITMAP::default();
+        let read = unsafe {
+            GetObjectW(
+                info.hbmMask.into(),
+                mem::size_of::<BITMAP>() as i32,
+                Some(&mut mask as *mut BITMAP as *mut c_void),
+            )
+        };
+        let monochrome = info.hbmColor.is_invalid();
+        unsafe {
+            let _ = DeleteObject(info.hbmMask.into());
+            if !monochrome {
+                let _ = DeleteObject(info.hbmColor.into());
+            }
+        }
+        if read == 0 || mask.bmWidth <= 0 || mask.bmHeight <= 0 {
+            return Err("El cursor no tiene un tamaño válido".to_string());
+        }
+
+        // Sin bitmap de color la máscara lleva AND y XOR apiladas.
+        let height = if monochrome {
+            mask.bmHeight / 2
+        } else {
+            mask.bmHeight
+        };
+        Ok((
+            mask.bmWidth as u32,
+            height.max(1) as u32,
+            info.xHotspot,
+            info.yHotspot,
+        ))
+    }
+
+    fn draw_on_background(
+        icon: HICON,
+        width: u32,
+        height: u32,
+        background: u8,
+    ) -> Result<Vec<u8>, String> {
+        let header = BITMAPINFOHEADER {
+            biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width as i32,
+            // Alto negativo: filas de arriba hacia abajo, como los frames.
+            biHeight: -(height as i32),
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        };
+        let bitmap_info = BITMAPINFO {
+            bmiHeader: header,
+            ..Default::default()
+        };
+        let size = (width * height * 4) as usize;
+
+        // SAFETY: el DIB vive hasta el `DeleteObject` del final y sus bits
+        // miden `size` bytes.
+        unsafe {
+            let dc = CreateCompatibleDC(None);
+            if dc.is_invalid() {
+                return Err("No se pudo crear el contexto para dibujar el cursor".to_string());
+            }
+            let mut bits: *mut c_void = ptr::null_mut();
+            let bitmap: HBITMAP = match CreateDIBSection(
+                Some(dc),
+                &bitmap_info,
+                DIB_RGB_COLORS,
+                &mut bits,
+                None,
+                0,
+            ) {
+                Ok(bitmap) if !bits.is_null() => bitmap,
+                _ => {
+                    let _ = DeleteDC(dc);
+                    return Err("No se pudo crear el bitmap del cursor".to_string());
+                }
+            };
+            let previous = SelectObject(dc, bitmap.into());
+            ptr::write_bytes(bits as *mut u8, background, size);
+            let drawn = DrawIconEx(
+                dc,
+                0,
+                0,
+                icon,
+                width as i32,
+                height as i32,
+                0,
+                None,
+                DI_NORMAL,
+            );
+            let pixels = std::slice::from_raw_parts(bits as *const u8, size).to_vec();
+            SelectObject(dc, previous);
+            let _ = DeleteObject(bitmap.into());
+            let _ = DeleteDC(dc);
+            drawn.map_err(|err| format!("No se pudo dibujar el cursor: {err}"))?;
+            Ok(pixels)
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::{CursorSprite, CursorState};
+
+    pub(super) fn current_cursor() -> Option<CursorState> {
+        None
+    }
+
+    pub(super) fn render_cursor(
+        _handle: usize,
+        _scale_percent: u32,
+    ) -> Result<CursorSprite, String> {
+        Err("El cursor escalado solo está disponible en Windows".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escala_uno_usa_el_cursor_de_la_captura() {
+        assert_eq!(CursorOverlayConfig::from_scale(1.0, 0, 0), Ok(None));
+        let config = CursorOverlayConfig::from_scale(2.0, -1920, 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.scale_percent, 200);
+        assert!(CursorOverlayConfig::from_scale(0.2, 0, 0).is_err());
+        assert!(CursorOverlayConfig::from_scale(f32::NAN, 0, 0).is_err());
+    }
+
+    #[test]
+    fn sprite_saca_el_alfa_de_los_dos_fondos_y_se_recorta_en_el_borde() {
+        let on_black = [0, 0, 200, 255, 0, 0, 0, 255];
+        let on_white = [0, 0, 200, 255, 255, 255, 255, 255];
+        let sprite = CursorSprite::from_black_and_white(2, 1, &on_black, &on_white).unwrap();
+        assert_eq!(sprite.pixels, vec![0, 0, 200, 255, 0, 0, 0, 0]);
+
+        let mut frame = RawFrame::new(vec![50; 8], 2, 1, 8, 0);
+        sprite.blend_onto(&mut frame, -1, 0);
+        assert_eq!(frame.data, vec![50; 8]);
+        sprite.blend_onto(&mut frame, 1, 0);
+        assert_eq!(frame.data, vec![50, 50, 50, 50, 0, 0, 200, 50]);
+    }
+}
@@ -0,0 +1,150 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+pub const EVENT_CAPTURE_TARGETS_CHANGED: &str = "capture-targets-changed";
+
+const DISPLAY_CHANGE_SETTLE_MS: u32 = 500;
+
+pub type DisplayChangeSink = Arc<dyn Fn() + Send + Sync>;
+
+fn display_change_sink_slot() -> &'static Mutex<Option<DisplayChangeSink>> {
+    static SLOT: OnceLock<Mutex<Option<DisplayChangeSink>>> = OnceLock::new();
+    SLOT.get_or_init(|| Mutex::new(None))
+}
+
+pub fn set_display_change_sink(sink: DisplayChangeSink) {
+    if let Ok(mut guard) = display_change_sink_slot().lock() {
+        *guard = Some(sink);
+    }
+}
+
+fn notify_display_change() {
+    let sink = display_change_sink_slot()
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone());
+    if let Some(sink) = sink {
+        sink();
+    }
+}
+
+pub fn watch_display_changes() -> Result<(), String> {
+    platform::spawn_display_watcher()
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{sync::mpsc, thread};
+
+    use windows::{
+        core::PCWSTR,
+        Win32::{
+            Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM},
+            System::LibraryLoader::GetModuleHandleW,
+            UI::WindowsAndMessaging::{
+                CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, KillTimer,
+                RegisterClassW, SetTimer, TranslateMessage, DBT_DEVNODES_CHANGED, MSG,
+                WM_DEVICECHANGE, WM_DISPLAYCHANGE, WM_TIMER, WNDCLASSW, WS_EX_TOOLWINDOW, WS_POPUP,
+            },
+        },
+    };
+
+    use super::{notify_display_change, DISPLAY_CHANGE_SETTLE_MS};
+
+    const CLASS_NAME: &str = "CapturistDisplayWatcher";
+    const SETTLE_TIMER_ID: usize = 1;
+
+    pub fn spawn_display_watcher() -> Result<(), String> {
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<(), String>>();
+        thread::Builder::new()
+            .name("capturist-display-watcher".into())
+            .spawn(move || {
+                if let Err(err) = unsafe { create_watcher_window() } {
+                    let _ = ready_tx.send(Err(err));
+                    return;
+                }
+                let _ = ready_tx.send(Ok(()));
+
+                let mut msg = MSG::default();
+                unsafe {
+                    while GetMessageW(&mut msg, None, 0, 0).0 > 0 {
+                        let _ = TranslateMessage(&msg);
+                        DispatchMessageW(&msg);
+                    }
+                }
+            })
+            .map_err(|err| format!("No se pudo iniciar el hilo de cambios de monitor: {err}"))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "El hilo de cambios de monitor se cerró al crearse".to_string())?
+    }
+
+    /// Ventana de nivel superior que nunca se muestra: las de solo mensajes
+    /// (`HWND_MESSAGE`) no reciben los broadcasts de pantalla.
+    unsafe fn create_watcher_window() -> Result<HWND, String> {
+        let class_name: Vec<u16> = CLASS_NAME.encode_utf16().chain([0]).collect();
+        let instance = GetModuleHandleW(PCWSTR::null())
+            .map(HINSTANCE::from)
+            .unwrap_or_default();
+        let wc = WNDCLASSW {
+            lpfnWndProc: Some(watcher_wnd_proc),
+            hInstance: instance,
+            lpszClassName: PCWSTR(class_name.as_ptr()),
+            ..Default::default()
+        };
+        if RegisterClassW(&wc) == 0 {
+            return Err(format!(
+                "No se pudo registrar la clase de ventana '{CLASS_NAME}'"
+            ));
+        }
+
+        CreateWindowExW(
+            WS_EX_TOOLWINDOW,
+            PCWSTR(class_name.as_ptr()),
+            PCWSTR(class_name.as_ptr()),
+            WS_POPUP,
+            0,
+            0,
+            0,
+            0,
+            None,
+            None,
+            Some(instance),
+            None,
+        )
+        .map_err(|err| format!("No se pudo crear la ventana de cambios de monitor: {err}"))
+    }
+
+    unsafe extern "system" fn watcher_wnd_proc(
+        hwnd: HWND,
+        msg: u32,
+        w: WPARAM,
+        l: LPARAM,
+    ) -> LRESULT {
+        match msg {
+            WM_DISPLAYCHANGE => {
+                SetTimer(Some(hwnd), SETTLE_TIMER_ID, DISPLAY_CHANGE_SETTLE_MS, None);
+                LRESULT(0)
+            }
+            WM_DEVICECHANGE if w.0 == DBT_DEVNODES_CHANGED as usize => {
+                SetTimer(Some(hwnd), SETTLE_TIMER_ID, DISPLAY_CHANGE_SETTLE_MS, None);
+                LRESULT(1)
+            }
+            WM_TIMER if w.0 == SETTLE_TIMER_ID => {
+                let _ = KillTimer(Some(hwnd), SETTLE_TIMER_ID);
+                notify_display_change();
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, msg, w, l),
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    pub fn spawn_display_watcher() -> Result<(), String> {
+        Err("Detectar cambios de monitor solo está disponible en Windows".to_string())
+    }
+}
@@ -0,0 +1,158 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+// Margen para el jitter de los timestamps de Windows.Graphics.Capture.
+const FRAME_PACING_TOLERANCE_NS: u64 = 2_000_000;
+const UNSET: u64 = u64::MAX;
+/// Un panel de 59,94 Hz se informa como 59: pedir 60 no es duplicar frames.
+const REFRESH_RATE_TOLERANCE_HZ: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FpsClampWarning {
+    pub requested_fps: u32,
+    pub applied_fps: u32,
+    pub refresh_rate_hz: u32,
+}
+
+/// Windows.Graphics.Capture no entrega más frames que el refresco del
+/// monitor; por encima solo se repetirían frames.
+pub fn clamp_fps_to_refresh_rate(
+    fps: u32,
+    refresh_rate_hz: Option<u32>,
+) -> (u32, Option<FpsClampWarning>) {
+    match refresh_rate_hz {
+        Some(refresh_rate_hz)
+            if refresh_rate_hz > 1
+                && fps > refresh_rate_hz.saturating_add(REFRESH_RATE_TOLERANCE_HZ) =>
+        {
+            (
+                refresh_rate_hz,
+                Some(FpsClampWarning {
+                    requested_fps: fps,
+                    applied_fps: refresh_rate_hz,
+                    refresh_rate_hz,
+                }),
+            )
+        }
+        _ => (fps, None),
+    }
+}
+
+/// Deja pasar como mucho `fps` frames por segundo aunque la fuente entregue
+/// más (p. ej. un juego a 240 Hz que ignora el intervalo mínimo pedido). Los
+/// turnos siguen una grilla fija, así que en grabaciones largas el promedio
+/// queda en los FPS pedidos; si la fuente se atrasa un intervalo entero la
+/// grilla se reinicia en vez de soltar una ráfaga.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+#[derive(Debug)]
+pub struct FramePacingGate {
+    original_fps: u32,
+    target_fps: AtomicU32,
+    next_due_ns: AtomicU64,
+    last_admitted_ns: AtomicU64,
+}
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+impl FramePacingGate {
+    pub fn new(fps: u32) -> Self {
+        let fps = fps.max(1);
+        Self {
+            original_fps: fps,
+            target_fps: AtomicU32::new(fps),
+            next_due_ns: AtomicU64::new(UNSET),
+            last_admitted_ns: AtomicU64::new(UNSET),
+        }
+    }
+
+    pub fn set_target_fps(&self, fps: u32) {
+        self.target_fps
+            .store(fps.clamp(1, self.original_fps), Ordering::Release);
+    }
+
+    pub fn admit(&self, timestamp_ms: u64) -> bool {
+        let now_ns = timestamp_ms.saturating_mul(1_000_000);
+        let interval_ns = 1_000_000_000 / u64::from(self.target_fps.load(Ordering::Acquire));
+        let next_due_ns = self.next_due_ns.load(Ordering::Acquire);
+        let last_ns = self.last_admitted_ns.load(Ordering::Acquire);
+
+        // Un timestamp que retrocede (p. ej. al cambiar de target) reinicia la grilla.
+        let restart = next_due_ns == UNSET || now_ns < last_ns;
+        if !restart && now_ns.saturating_add(FRAME_PACING_TOLERANCE_NS) < next_due_ns {
+            return false;
+        }
+
+        let next_due_ns = if restart || now_ns >= next_due_ns.saturating_add(interval_ns) {
+            now_ns.saturating_add(interval_ns)
+        } else {
+            next_due_ns + interval_ns
+        };
+        self.next_due_ns.store(next_due_ns, Ordering::Release);
+        self.last_admitted_ns.store(now_ns, Ordering::Release);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn admitted(gate: &FramePacingGate, source_hz: u64, frames: std::ops::Range<u64>) -> usize {
+        frames
+            .map(|frame| frame * 1_000 / source_hz)
+            .filter(|&ts| gate.admit(ts))
+            .count()
+    }
+
+    #[test]
+    fn fuente_a_240_hz_se_limita_a_los_fps_pedidos_sin_deriva() {
+        let gate = FramePacingGate::new(60);
+        let count = admitted(&gate, 240, 0..240 * 600);
+        assert_eq!(count, 60 * 600);
+    }
+
+    #[test]
+    fn fuente_a_los_mismos_fps_pasa_completa_pese_al_jitter() {
+        let gate = FramePacingGate::new(60);
+        assert_eq!(admitted(&gate, 60, 0..600), 600);
+    }
+
+    #[test]
+    fn bajar_los_fps_en_vivo_y_no_superar_los_originales() {
+        let gate = FramePacingGate::new(60);
+        assert_eq!(admitted(&gate, 60, 0..12), 12);
+
+        gate.set_target_fps(30);
+        assert_eq!(admitted(&gate, 60, 12..24), 6);
+
+        gate.set_target_fps(120);
+        assert_eq!(admitted(&gate, 240, 120..240), 30);
+    }
+
+    #[test]
+    fn fps_por_encima_del_refresco_se_recortan() {
+        let (fps, warning) = clamp_fps_to_refresh_rate(120, Some(60));
+        assert_eq!(fps, 60);
+        assert_eq!(
+            warning,
+            Some(FpsClampWarning {
+                requested_fps: 120,
+                applied_fps: 60,
+                refresh_rate_hz: 60,
+            })
+        );
+
+        assert_eq!(clamp_fps_to_refresh_rate(60, Some(59)), (60, None));
+        assert_eq!(clamp_fps_to_refresh_rate(120, Some(144)), (120, None));
+        assert_eq!(clamp_fps_to_refresh_rate(120, None), (120, None));
+    }
+
+    #[test]
+    fn tras_un_hueco_no_suelta_una_rafaga() {
+        let gate = FramePacingGate::new(30);
+        assert!(gate.admit(0));
+        assert!(gate.admit(500));
+        assert!(!gate.admit(504));
+        assert!(!gate.admit(508));
+        assert!(gate.admit(533));
+    }
+}
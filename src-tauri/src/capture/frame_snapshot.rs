@@ -0,0 +1,285 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::capture::{
+    models::{FramePixelFormat, RawFrame, Region},
+    runtime,
+};
+
+const BITMAP_INFO_HEADER_SIZE: u32 = 40;
+const RECORDED_FRAME_WAIT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameSnapshot {
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    pub row_stride_bytes: u32,
+}
+
+impl From<RawFrame> for FrameSnapshot {
+    fn from(mut frame: RawFrame) -> Self {
+        Self {
+            data: std::mem::take(&mut frame.data),
+            width: frame.width,
+            height: frame.height,
+            row_stride_bytes: frame.row_stride_bytes,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum SnapshotDelivery {
+    Frame(FrameSnapshot),
+    Unavailable,
+}
+
+#[derive(Debug, Default)]
+struct SnapshotSlot {
+    pending: AtomicBool,
+    delivery: Mutex<Option<SnapshotDelivery>>,
+    delivered: Condvar,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FrameSnapshotRequest {
+    slot: Arc<SnapshotSlot>,
+}
+
+impl FrameSnapshotRequest {
+    pub fn is_pending(&self) -> bool {
+        self.slot.pending.load(Ordering::Acquire)
+    }
+
+    pub fn fulfill(&self, frame: Option<&RawFrame>) {
+        if !self.slot.pending.swap(false, Ordering::AcqRel) {
+            return;
+        }
+        let delivery = match frame {
+            Some(frame)
+                if !frame.data.is_empty() && frame.pixel_format == FramePixelFormat::Bgra8 =>
+            {
+                SnapshotDelivery::Frame(FrameSnapshot {
+                    data: frame.data.clone(),
+                    width: frame.width,
+                    height: frame.height,
+                    row_stride_bytes: frame.row_stride_bytes,
+                })
+            }
+            _ => SnapshotDelivery::Unavailable,
+        };
+        if let Ok(mut slot) = self.slot.delivery.lock() {
+            *slot = Some(delivery);
+            self.slot.delivered.notify_all();
+        }
+    }
+
+    pub fn request(&self, timeout: Duration) -> Option<FrameSnapshot> {
+        let mut slot = self.slot.delivery.lock().ok()?;
+        *slot = None;
+        self.slot.pending.store(true, Ordering::Release);
+        let (mut slot, _) = self
+            .slot
+            .delivered
+            .wait_timeout_while(slot, timeout, |delivery| delivery.is_none())
+            .ok()?;
+        self.slot.pending.store(false, Ordering::Release);
+        match slot.take() {
+            Some(SnapshotDelivery::Frame(frame)) => Some(frame),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct FrameSnapshotSource {
+    pub request: FrameSnapshotRequest,
+    pub target_id: u32,
+    pub crop_region: Option<Region>,
+    pub has_blur_regions: bool,
+}
+
+impl FrameSnapshotSource {
+    pub fn grab(&self) -> Result<FrameSnapshot, String> {
+        if let Some(frame) = self.request.request(RECORDED_FRAME_WAIT) {
+            return Ok(frame);
+        }
+        if self.has_blur_regions {
+            return Err(
+                "No se pudo tomar el frame de la grabación y una captura aparte mostraría las regiones difuminadas; vuelve a intentarlo"
+                    .to_string(),
+            );
+        }
+        runtime::capture_single_frame(self.target_id, self.crop_region.clone())
+            .map(FrameSnapshot::from)
+    }
+}
+
+pub fn frame_to_dib(frame: &FrameSnapshot) -> Result<Vec<u8>, String> {
+    let row_bytes = RawFrame::min_row_stride_bytes(frame.width) as usize;
+    let stride = frame.row_stride_bytes as usize;
+    if frame.width == 0 || frame.height == 0 || stride < row_bytes {
+        return Err("Frame inválido para copiar al portapapeles".to_string());
+    }
+    if frame.data.len() < RawFrame::expected_size(frame.height, frame.row_stride_bytes) {
+        return Err("El frame capturado está incompleto".to_string());
+    }
+
+    let image_size = row_bytes * frame.height as usize;
+    let mut dib = Vec::with_capacity(BITMAP_INFO_HEADER_SIZE as usize + image_size);
+    dib.extend_from_slice(&BITMAP_INFO_HEADER_SIZE.to_le_bytes());
+    dib.extend_from_slice(&(frame.width as i32).to_le_bytes());
+    // Alto positivo: las filas van de abajo hacia arriba.
+    dib.extend_from_slice(&(frame.height as i32).to_le_bytes());
+    dib.extend_from_slice(&1_u16.to_le_bytes());
+    dib.extend_from_slice(&32_u16.to_le_bytes());
+    dib.extend_from_slice(&0_u32.to_le_bytes());
+    dib.extend_from_slice(&(image_size as u32).to_le_bytes());
+    dib.extend_from_slice(&[0; 16]);
+
+    for row in frame.data.chunks(stride).take(frame.height as usize).rev() {
+        dib.extend_from_slice(&row[..row_bytes]);
+    }
+    Ok(dib)
+}
+
+pub fn copy_frame_to_clipboard(frame: &FrameSnapshot) -> Result<(), String> {
+    platform::set_clipboard_dib(&frame_to_dib(frame)?)
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{ptr, thread, time::Duration};
+
+    use windows_sys::Win32::{
+        Foundation::GlobalFree,
+        System::{
+            DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData},
+            Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE},
+        },
+    };
+
+    const CF_DIB: u32 = 8;
+    const OPEN_CLIPBOARD_ATTEMPTS: u32 = 10;
+    const OPEN_CLIPBOARD_RETRY: Duration = Duration::from_millis(20);
+
+    pub fn set_clipboard_dib(dib: &[u8]) -> Result<(), String> {
+        // SAFETY: el bloque se reserva, se llena dentro de su tamaño y, si
+        // `SetClipboardData` lo acepta, pasa a ser del sistema.
+        unsafe {
+            let memory = GlobalAlloc(GMEM_MOVEABLE, dib.len());
+            if memory.is_null() {
+                return Err("No se pudo reservar memoria para el portapapeles".to_string());
+            }
+            let target = GlobalLock(memory) as *mut u8;
+            if target.is_null() {
+                GlobalFree(memory);
+                return Err("No se pudo reservar memoria para el portapapeles".to_string());
+            }
+            ptr::copy_nonoverlapping(dib.as_ptr(), target, dib.len());
+            GlobalUnlock(memory);
+
+            if !open_clipboard() {
+                GlobalFree(memory);
+                return Err("El portapapeles está en uso por otra aplicación".to_string());
+            }
+            EmptyClipboard();
+            let stored = !SetClipboardData(CF_DIB, memory).is_null();
+            CloseClipboard();
+            if !stored {
+                GlobalFree(memory);
+                return Err("No se pudo copiar el frame al portapapeles".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    unsafe fn open_clipboard() -> bool {
+        for _ in 0..OPEN_CLIPBOARD_ATTEMPTS {
+            if OpenClipboard(ptr::null_mut()) != 0 {
+                return true;
+            }
+            thread::sleep(OPEN_CLIPBOARD_RETRY);
+        }
+        false
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    pub fn set_clipboard_dib(_dib: &[u8]) -> Result<(), String> {
+        Err("Copiar frames al portapapeles solo está disponible en Windows".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dib_invierte_filas_y_descarta_el_padding() {
+        let frame = FrameSnapshot {
+            data: vec![1, 2, 3, 255, 0, 0, 0, 0, 4, 5, 6, 255, 0, 0, 0, 0],
+            width: 1,
+            height: 2,
+            row_stride_bytes: 8,
+        };
+        let dib = frame_to_dib(&frame).unwrap();
+
+        assert_eq!(dib.len(), 40 + 8);
+        assert_eq!(&dib[..4], &40_u32.to_le_bytes());
+        assert_eq!(&dib[8..12], &2_i32.to_le_bytes());
+        assert_eq!(&dib[14..16], &32_u16.to_le_bytes());
+        assert_eq!(&dib[40..], &[4, 5, 6, 255, 1, 2, 3, 255]);
+
+        let truncated = FrameSnapshot {
+            data: vec![0; 8],
+            ..frame
+        };
+        assert!(frame_to_dib(&truncated).is_err());
+    }
+
+    #[test]
+    fn el_worker_solo_copia_el_frame_pedido() {
+        let request = FrameSnapshotRequest::default();
+        request.fulfill(Some(&RawFrame::new(vec![9; 8], 2, 1, 8, 0)));
+        assert!(!request.is_pending());
+        assert_eq!(request.request(Duration::from_millis(10)), None);
+
+        let worker = request.clone();
+        let serve = std::thread::spawn(move || {
+            while !worker.is_pending() {
+                std::thread::yield_now();
+            }
+            worker.fulfill(Some(&RawFrame::new(vec![7; 8], 2, 1, 8, 33)));
+        });
+        let frame = request.request(Duration::from_secs(5)).unwrap();
+        serve.join().unwrap();
+        assert_eq!(frame.data, vec![7; 8]);
+        assert_eq!((frame.width, frame.height), (2, 1));
+        assert!(!request.is_pending());
+    }
+
+    #[test]
+    fn pipeline_sin_frame_cpu_responde_enseguida() {
+        let request = FrameSnapshotRequest::default();
+        let worker = request.clone();
+        let serve = std::thread::spawn(move || {
+            while !worker.is_pending() {
+                std::thread::yield_now();
+            }
+            worker.fulfill(Some(&RawFrame::new(Vec::new(), 2, 1, 8, 0)));
+        });
+        let started = std::time::Instant::now();
+        assert_eq!(request.request(Duration::from_secs(5)), None);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        serve.join().unwrap();
+    }
+}
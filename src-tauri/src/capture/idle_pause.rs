@@ -0,0 +1,224 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+pub const MIN_AUTO_PAUSE_IDLE_MS: u64 = 1_000;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+// Se muestrea una fila de cada dos y un píxel de cada dos: basta para notar
+// texto o un cursor moviéndose sin recorrer el frame completo.
+const FINGERPRINT_ROW_STEP: usize = 2;
+const FINGERPRINT_PIXEL_STEP: usize = 2;
+const BYTES_PER_PIXEL: usize = 4;
+
+pub fn frame_fingerprint(bytes: &[u8], row_stride_bytes: usize) -> u64 {
+    let mut hash = FNV_OFFSET_BASIS;
+    if row_stride_bytes == 0 {
+        return hash;
+    }
+
+    for row in bytes.chunks(row_stride_bytes).step_by(FINGERPRINT_ROW_STEP) {
+        for pixel in row
+            .chunks_exact(BYTES_PER_PIXEL)
+            .step_by(FINGERPRINT_PIXEL_STEP)
+        {
+            let value = u32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+            hash ^= u64::from(value);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+    hash
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameActivity {
+    Active { resumed_from: Option<Instant> },
+    Idle,
+}
+
+#[derive(Debug)]
+struct IdleState {
+    last_change_at: Instant,
+    last_fingerprint: Option<u64>,
+    idle_total: Duration,
+    suspended: bool,
+}
+
+#[derive(Debug)]
+pub struct IdleTracker {
+    threshold: Duration,
+    state: Mutex<IdleState>,
+}
+
+impl IdleTracker {
+    pub fn new(threshold: Duration, now: Instant) -> Self {
+        Self {
+            threshold,
+            state: Mutex::new(IdleState {
+                last_change_at: now,
+                last_fingerprint: None,
+                idle_total: Duration::ZERO,
+                suspended: false,
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, IdleState> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn idle_started_at(&self, state: &IdleState, now: Instant) -> Option<Instant> {
+        let idle_since = state.last_change_at + self.threshold;
+        (!state.suspended && now > idle_since).then_some(idle_since)
+    }
+
+    /// `fingerprint` es `None` cuando no se puede calcular (frames GPU): se
+    /// toma como cambio, porque windows-capture solo los entrega si hubo uno.
+    pub fn observe_frame(&self, now: Instant, fingerprint: Option<u64>) -> FrameActivity {
+        let mut state = self.lock();
+        let changed = fingerprint.is_none() || fingerprint != state.last_fingerprint;
+        let idle_since = self.idle_started_at(&state, now);
+
+        if !changed {
+            return match idle_since {
+                Some(_) => FrameActivity::Idle,
+                None => FrameActivity::Active { resumed_from: None },
+            };
+        }
+
+        if let Some(since) = idle_since {
+            state.idle_total += now - since;
+        }
+        state.last_change_at = now;
+        state.last_fingerprint = fingerprint;
+        FrameActivity::Active {
+            resumed_from: idle_since,
+        }
+    }
+
+    pub fn is_idle(&self, now: Instant) -> bool {
+        let state = self.lock();
+        self.idle_started_at(&state, now).is_some()
+    }
+
+    pub fn idle_ms(&self, now: Instant) -> u64 {
+        let state = self.lock();
+        let ongoing = self
+            .idle_started_at(&state, now)
+            .map(|since| now - since)
+            .unwrap_or_default();
+        (state.idle_total + ongoing).as_millis() as u64
+    }
+
+    pub fn suspend(&self, now: Instant) -> Option<Instant> {
+        let mut state = self.lock();
+        let idle_since = self.idle_started_at(&state, now);
+        if let Some(since) = idle_since {
+            state.idle_total += now - since;
+        }
+        state.suspended = true;
+        idle_since
+    }
+
+    pub fn resume(&self, now: Instant) {
+        let mut state = self.lock();
+        state.suspended = false;
+        state.last_change_at = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: Duration = Duration::from_secs(5);
+
+    fn ms(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    #[test]
+    fn frames_iguales_pausan_tras_el_umbral_y_el_cambio_reanuda() {
+        let start = Instant::now();
+        let tracker = IdleTracker::new(THRESHOLD, start);
+
+        assert_eq!(
+            tracker.observe_frame(start + ms(100), Some(1)),
+            FrameActivity::Active { resumed_from: None }
+        );
+        assert_eq!(
+            tracker.observe_frame(start + ms(4_000), Some(1)),
+            FrameActivity::Active { resumed_from: None }
+        );
+        assert_eq!(
+            tracker.observe_frame(start + ms(6_000), Some(1)),
+            FrameActivity::Idle
+        );
+        assert!(tracker.is_idle(start + ms(6_000)));
+        assert_eq!(tracker.idle_ms(start + ms(7_100)), 2_000);
+
+        assert_eq!(
+            tracker.observe_frame(start + ms(9_100), Some(2)),
+            FrameActivity::Active {
+                resumed_from: Some(start + ms(5_100))
+            }
+        );
+        assert!(!tracker.is_idle(start + ms(9_100)));
+        assert_eq!(tracker.idle_ms(start + ms(10_000)), 4_000);
+    }
+
+    #[test]
+    fn un_hueco_sin_frames_tambien_cuenta_como_pausa() {
+        let start = Instant::now();
+        let tracker = IdleTracker::new(THRESHOLD, start);
+
+        assert_eq!(tracker.idle_ms(start + ms(3_000)), 0);
+        assert_eq!(
+            tracker.observe_frame(start + ms(12_000), None),
+            FrameActivity::Active {
+                resumed_from: Some(start + ms(5_000))
+            }
+        );
+        assert_eq!(tracker.idle_ms(start + ms(12_000)), 7_000);
+    }
+
+    #[test]
+    fn pausa_manual_cierra_el_tramo_y_no_acumula() {
+        let start = Instant::now();
+        let tracker = IdleTracker::new(THRESHOLD, start);
+
+        assert_eq!(tracker.suspend(start + ms(8_000)), Some(start + ms(5_000)));
+        assert_eq!(tracker.idle_ms(start + ms(60_000)), 3_000);
+        assert!(!tracker.is_idle(start + ms(60_000)));
+
+        tracker.resume(start + ms(60_000));
+        assert_eq!(tracker.idle_ms(start + ms(64_000)), 3_000);
+        assert_eq!(tracker.suspend(start + ms(64_000)), None);
+    }
+
+    #[test]
+    fn huella_detecta_cambios_en_pixeles_muestreados() {
+        let width = 8;
+        let stride = width * BYTES_PER_PIXEL;
+        let frame = vec![0_u8; stride * 4];
+        let mut changed = frame.clone();
+        changed[0] = 255;
+
+        assert_eq!(
+            frame_fingerprint(&frame, stride),
+            frame_fingerprint(&frame.clone(), stride)
+        );
+        assert_ne!(
+            frame_fingerprint(&frame, stride),
+            frame_fingerprint(&changed, stride)
+        );
+        assert_eq!(frame_fingerprint(&frame, 0), FNV_OFFSET_BASIS);
+    }
+}
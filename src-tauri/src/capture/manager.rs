@@ -1,25 +1,71 @@
 use std::{
+    path::PathBuf,
     sync::{
         atomic::{AtomicU64, AtomicUsize, Ordering},
-        mpsc::{self, SyncSender, TrySendError},
+        mpsc::{self, RecvTimeoutError, SyncSender, TrySendError},
         Arc, Mutex,
     },
     thread::{self, JoinHandle},
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use crate::capture::{
-    models::{CaptureResolutionPreset, CaptureState, CaptureTarget, RawFrame, Region},
-    provider::{ScreenProvider, WindowsCaptureScreenProvider},
+    adaptive_fps::{AdaptiveFpsController, PipelineLoad, ADAPTIVE_FPS_MAX_DROPS_PER_SEC},
+    backpressure::{
+        notify_encoder_backpressure, BackpressureWindow, EncoderBackpressure,
+        BACKPRESSURE_DROP_THRESHOLD, BACKPRESSURE_WINDOW,
+    },
+    cursor_overlay::{default_cursor_scale, CursorOverlayConfig},
+    frame_snapshot::{FrameSnapshotRequest, FrameSnapshotSource},
+    idle_pause::{IdleTracker, MIN_AUTO_PAUSE_IDLE_MS},
+    models::{
+        CaptureResolutionPreset, CaptureState, CaptureTarget, RawFrame, Region, StopReason,
+        TargetKind,
+    },
+    monitor_follow::{
+        MonitorFollowEvent, MonitorFollowSink, MonitorFollower, MONITOR_FOLLOW_INTERVAL,
+    },
+    provider::{
+        display_hdr_metadata, ScreenProvider, WindowMonitorLookup, WindowsCaptureScreenProvider,
+    },
+    recording_clock::{
+        RecordingClock, RecordingTickSink, RecordingTicker, RECORDING_TICK_INTERVAL,
+    },
     runtime::{
         self, CaptureRuntimeHandle, FrameArrivedCallback, RuntimeStartConfig,
         SessionFinishedCallback,
     },
+    session_stop::{is_session_stop_requested, take_requested_stop_reason},
+    window_exclusion::WindowExclusion,
 };
 use crate::encoder::{
-    config::{EncoderConfig, VideoCodec, VideoEncoderPreference},
+    chapters::{ChapterMarker, ChapterMarkerLog},
+    config::{
+        BitDepth, ChromaSubsampling, ColorRange, EncoderConfig, FrameRateMode, OutputResolution,
+        VideoCodec, VideoEncoderPreference,
+    },
     consumer::FfmpegEncoderConsumer,
+    filesystem_limits::SessionParts,
+    gpu_adapter::{enumerate_adapters, predict_adapter_mismatch},
+    gpu_scaler::GpuFrameRoute,
+    output_sink::redact_stream_url,
+    pipeline_latency::PipelineLatencySnapshot,
+    post_process::PostProcessJobSnapshot,
 };
+use crate::region::{RecordingBorder, ScreenRect};
+
+const WINDOW_LOST_WARNING: &str =
+    "La ventana seguida se cerró; se sigue grabando el último monitor";
+
+fn auto_stop_error(reason: Option<StopReason>) -> Option<String> {
+    match reason {
+        Some(StopReason::SourceLost) => Some(
+            "La fuente de captura desapareció (monitor desconectado o ventana cerrada). Se guardó lo grabado hasta ese momento"
+                .to_string(),
+        ),
+        Some(StopReason::DiskFull | StopReason::FileSizeLimit) | None => None,
+    }
+}
 
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -29,17 +75,51 @@ pub struct CaptureManagerSnapshot {
     pub last_error: Option<String>,
     pub video_encoder_label: Option<String>,
     pub is_processing: bool,
+    pub processing_eta_ms: Option<u64>,
+    pub pipeline_latency_ms: Option<PipelineLatencySnapshot>,
+    pub stop_reason: Option<StopReason>,
+    pub disk_space_remaining_bytes: Option<u64>,
+    pub estimated_minutes_left: Option<u64>,
+    pub post_processing_jobs: Vec<PostProcessJobSnapshot>,
+    pub auto_paused: bool,
+    pub warning: Option<String>,
+    pub applied_fps: Option<u32>,
+    pub captured_frames: u64,
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionConfig {
     pub target_id: u32,
+    #[serde(default)]
+    pub secondary_target_id: Option<u32>,
     #[serde(default = "default_fps")]
     pub fps: u32,
     pub crop_region: Option<Region>,
     #[serde(default)]
     pub capture_resolution_preset: Option<CaptureResolutionPreset>,
+    #[serde(default)]
+    pub auto_pause_idle_ms: Option<u64>,
+    #[serde(skip)]
+    pub idle_tracker: Option<Arc<IdleTracker>>,
+    #[serde(default)]
+    pub follow_window_monitor: bool,
+    #[serde(default)]
+    pub adaptive_fps: bool,
+    #[serde(default = "default_cursor_scale")]
+    pub cursor_scale: f32,
+    #[serde(default)]
+    pub show_region_border: bool,
+    #[serde(default)]
+    pub exclude_window_ids: Vec<u64>,
+    #[serde(default)]
+    pub include_secondary_windows: Option<bool>,
+    #[serde(default)]
+    pub restore_if_minimized: bool,
+    #[serde(skip)]
+    pub pipeline_load: Option<Arc<PipelineLoad>>,
+    #[serde(skip)]
+    pub recording_clock: Option<RecordingClock>,
     pub encoder_config: EncoderConfig,
 }
 
@@ -75,39 +155,161 @@ type RuntimeBuilder =
 
 struct ActiveSession {
     state: CaptureState,
-    elapsed_before_pause_ms: u64,
-    last_resume_at: Option<Instant>,
+    clock: RecordingClock,
+    ticker: Option<RecordingTicker>,
     last_error: Option<String>,
+    stop_reason: Option<StopReason>,
     runtime: Option<Box<dyn CaptureRuntimeHandle>>,
+    frame_counter: Option<Arc<AtomicU64>>,
+    chapter_markers: ChapterMarkerLog,
+    follower: Option<MonitorFollower>,
+    warning: Arc<Mutex<Option<String>>>,
+    output_path: PathBuf,
+    session_parts: SessionParts,
+    applied_fps: u32,
+    adaptive_fps: Option<AdaptiveFps>,
+    frame_source: FrameSnapshotSource,
+    region_border: Option<RecordingBorder>,
+    /// Se suelta cuando el runtime ya terminó, así los últimos frames
+    /// tampoco muestran las ventanas excluidas.
+    window_exclusion: Option<WindowExclusion>,
+    recorded_monitor_ids: Vec<u32>,
+}
+
+struct AdaptiveFps {
+    controller: AdaptiveFpsController,
+    load: Arc<PipelineLoad>,
+}
+
+struct WindowFollow {
+    window_id: u32,
+    monitor_id: u32,
+    lookup: WindowMonitorLookup,
 }
 
 impl ActiveSession {
-    fn new(runtime: Box<dyn CaptureRuntimeHandle>) -> Self {
+    fn new(
+        runtime: Box<dyn CaptureRuntimeHandle>,
+        chapter_markers: ChapterMarkerLog,
+        clock: RecordingClock,
+        tick_sink: Option<RecordingTickSink>,
+        output_path: PathBuf,
+        fps: u32,
+        frame_source: FrameSnapshotSource,
+    ) -> Self {
+        clock.resume();
+        let ticker = tick_sink.and_then(|sink| {
+            RecordingTicker::spawn(clock.clone(), RECORDING_TICK_INTERVAL, sink)
+                .map_err(|err| eprintln!("[capture] {err}"))
+                .ok()
+        });
+
         Self {
             state: CaptureState::Running,
-            elapsed_before_pause_ms: 0,
-            last_resume_at: Some(Instant::now()),
+            clock,
+            ticker,
             last_error: None,
+            stop_reason: None,
+            frame_counter: runtime.frame_counter(),
             runtime: Some(runtime),
+            chapter_markers,
+            follower: None,
+            warning: Arc::new(Mutex::new(None)),
+            output_path,
+            session_parts: SessionParts::default(),
+            applied_fps: fps,
+            adaptive_fps: None,
+            frame_source,
+            region_border: None,
+            window_exclusion: None,
+            recorded_monitor_ids: Vec::new(),
         }
     }
 
-    fn accumulate_elapsed(&mut self) {
-        if let Some(since) = self.last_resume_at.take() {
-            self.elapsed_before_pause_ms += since.elapsed().as_millis() as u64;
+    fn start_following(&mut self, follow: WindowFollow) {
+        let Some(switcher) = self
+            .runtime
+            .as_ref()
+            .and_then(|runtime| runtime.target_switcher())
+        else {
+            self.set_warning("La captura no permite cambiar de monitor; no se sigue la ventana");
+            return;
+        };
+
+        let chapter_markers = self.chapter_markers.clone();
+        let clock = self.clock.clone();
+        let warning = Arc::clone(&self.warning);
+        let sink: MonitorFollowSink = Arc::new(move |event| match event {
+            MonitorFollowEvent::Switched { .. } => {
+                let label = Some("Cambio de monitor".to_string());
+                if let Err(err) = chapter_markers.push(clock.elapsed_ms(), label) {
+                    eprintln!("[capture] {err}");
+                }
+            }
+            MonitorFollowEvent::SwitchFailed(err) => {
+                eprintln!("[capture] No se pudo pasar la captura al nuevo monitor: {err}");
+            }
+            MonitorFollowEvent::WindowLost => {
+                if let Ok(mut warning) = warning.lock() {
+                    *warning = Some(WINDOW_LOST_WARNING.to_string());
+                }
+            }
+        });
+
+        match MonitorFollower::spawn(
+            follow.window_id,
+            follow.monitor_id,
+            MONITOR_FOLLOW_INTERVAL,
+            follow.lookup,
+            switcher,
+            sink,
+        ) {
+            Ok(follower) => self.follower = Some(follower),
+            Err(err) => self.set_warning(&err),
+        }
+    }
+
+    fn set_warning(&self, message: &str) {
+        if let Ok(mut warning) = self.warning.lock() {
+            *warning = Some(message.to_string());
         }
     }
 
+    fn warning(&self) -> Option<String> {
+        self.warning.lock().ok().and_then(|warning| warning.clone())
+    }
+
+    fn stop_session_threads(&mut self) {
+        self.clock.pause();
+        self.ticker = None;
+        self.follower = None;
+        self.region_border = None;
+    }
+
     fn elapsed_ms(&self) -> u64 {
-        match self.state {
-            CaptureState::Running => {
-                if let Some(since) = self.last_resume_at {
-                    self.elapsed_before_pause_ms + since.elapsed().as_millis() as u64
-                } else {
-                    self.elapsed_before_pause_ms
-                }
+        self.clock.elapsed_ms()
+    }
+
+    fn adjust_adaptive_fps(&mut self, now: Instant) {
+        if self.state != CaptureState::Running {
+            return;
+        }
+        let (Some(adaptive), Some(runtime)) = (self.adaptive_fps.as_mut(), self.runtime.as_ref())
+        else {
+            return;
+        };
+
+        let dropped = adaptive.load.dropped_frames.load(Ordering::Relaxed);
+        let queued = adaptive.load.queued_frames.load(Ordering::Acquire);
+        let Some(fps) = adaptive.controller.observe(now, dropped, queued) else {
+            return;
+        };
+        match runtime.set_target_fps(fps) {
+            Ok(()) => self.applied_fps = fps,
+            Err(err) => {
+                eprintln!("[capture] {err}");
+                self.adaptive_fps = None;
             }
-            _ => self.elapsed_before_pause_ms,
         }
     }
 
@@ -123,6 +325,10 @@ pub struct CaptureManager {
     active_session: Option<ActiveSession>,
     provider: Box<dyn ScreenProvider + Send>,
     runtime_factory: RuntimeFactory,
+    session_serial: u64,
+    tick_sink: Option<RecordingTickSink>,
+    last_output_path: Option<PathBuf>,
+    last_session_parts: SessionParts,
 }
 
 impl CaptureManager {
@@ -130,26 +336,60 @@ impl CaptureManager {
         Self::with_dependencies(
             Box::new(WindowsCaptureScreenProvider::new()),
             RuntimeFactory::new(|config: SessionConfig| {
-                let prefer_gpu_frames =
-                    should_prefer_gpu_frames(&config.encoder_config, &config.crop_region);
+                // Otro monitor puede tener otro tamaño y solo la ruta CPU
+                // rehace el escalador a mitad de sesión. Con dos monitores la
+                // composición también se hace sobre los bytes de la ruta CPU.
+                let prefer_gpu_frames = !config.follow_window_monitor
+                    && config.secondary_target_id.is_none()
+                    && should_prefer_gpu_frames(&config.encoder_config, &config.crop_region);
                 let SessionConfig {
                     target_id,
+                    secondary_target_id,
                     fps,
                     crop_region,
                     capture_resolution_preset: _,
-                    encoder_config,
+                    auto_pause_idle_ms: _,
+                    idle_tracker,
+                    follow_window_monitor: _,
+                    adaptive_fps: _,
+                    cursor_scale: _,
+                    show_region_border: _,
+                    exclude_window_ids: _,
+                    include_secondary_windows,
+                    restore_if_minimized,
+                    pipeline_load,
+                    recording_clock,
+                    mut encoder_config,
                 } = config;
+                let gpu_frames = GpuFrameRoute::new(
+                    prefer_gpu_frames,
+                    encoder_config.resolution != OutputResolution::Native,
+                );
+                encoder_config.gpu_frame_route = gpu_frames.clone();
 
-                let frame_callbacks = build_runtime_callbacks(encoder_config)?;
+                let hdr = encoder_config.hdr;
+                let capture_cursor = encoder_config.cursor_overlay.is_none();
+                let frame_callbacks = build_runtime_callbacks(
+                    encoder_config,
+                    pipeline_load.unwrap_or_default(),
+                    recording_clock,
+                )?;
                 runtime::start_runtime(RuntimeStartConfig {
                     target_id,
+                    secondary_target_id,
                     fps,
                     crop_region,
-                    prefer_gpu_frames,
+                    gpu_frames,
+                    hdr,
+                    capture_cursor,
+                    include_secondary_windows,
+                    restore_if_minimized,
+                    idle_tracker,
                     should_accept_frame: frame_callbacks.0,
                     on_frame_dropped: frame_callbacks.1,
-                    on_frame_arrived: frame_callbacks.2,
-                    on_session_finished: frame_callbacks.3,
+                    on_frame_paced: frame_callbacks.2,
+                    on_frame_arrived: frame_callbacks.3,
+                    on_session_finished: frame_callbacks.4,
                 })
             }),
         )
@@ -163,9 +403,17 @@ impl CaptureManager {
             active_session: None,
             provider,
             runtime_factory,
+            session_serial: 0,
+            tick_sink: None,
+            last_output_path: None,
+            last_session_parts: SessionParts::default(),
         }
     }
 
+    pub fn set_tick_sink(&mut self, sink: RecordingTickSink) {
+        self.tick_sink = Some(sink);
+    }
+
     fn cleanup_stopped_session_if_any(&mut self) {
         let should_cleanup = self
             .active_session
@@ -179,34 +427,88 @@ impl CaptureManager {
     }
 
     fn finalize_finished_runtime_if_any(&mut self) {
-        let should_finalize = self
+        let Some(runtime_finished) = self
             .active_session
             .as_ref()
-            .map(|session| {
-                matches!(session.state, CaptureState::Running | CaptureState::Paused)
-                    && session.runtime_finished()
-            })
-            .unwrap_or(false);
-
-        if !should_finalize {
+            .filter(|session| matches!(session.state, CaptureState::Running | CaptureState::Paused))
+            .map(ActiveSession::runtime_finished)
+        else {
             return;
+        };
+
+        // Un pedido del pipeline (p. ej. disco lleno) se aplica aquí: con la
+        // pantalla quieta no llega otro frame que corte la captura.
+        if runtime_finished {
+            self.finish_active_session(take_requested_stop_reason(), |runtime| runtime.wait());
+        } else if is_session_stop_requested() {
+            self.finish_active_session(take_requested_stop_reason(), |runtime| runtime.stop());
         }
+    }
 
+    fn finish_active_session(
+        &mut self,
+        stop_reason: Option<StopReason>,
+        finish_runtime: impl FnOnce(Box<dyn CaptureRuntimeHandle>) -> Result<u64, String>,
+    ) {
         if let Some(session) = self.active_session.as_mut() {
-            session.accumulate_elapsed();
+            session.stop_session_threads();
             session.state = CaptureState::Stopped;
-            session.last_resume_at = None;
+            session.stop_reason = stop_reason;
+            self.last_output_path = Some(session.output_path.clone());
+            self.last_session_parts = session.session_parts.clone();
 
             if let Some(runtime) = session.runtime.take() {
-                if let Err(err) = runtime.wait() {
+                if let Err(err) = finish_runtime(runtime) {
                     session.last_error = Some(err);
                 }
             }
+            session.window_exclusion = None;
+            if session.last_error.is_none() {
+                session.last_error = auto_stop_error(session.stop_reason);
+            }
+        }
+    }
+
+    /// Se llama al conectar o desconectar un monitor. Windows no siempre
+    /// cierra la captura de un monitor que desaparece y la sesión quedaría
+    /// esperando frames; si falta alguno de los que se graban se detiene
+    /// como fuente perdida. Devuelve `true` si detuvo la sesión.
+    pub fn handle_display_change(&mut self) -> bool {
+        self.finalize_finished_runtime_if_any();
+        let recorded_monitor_ids = match &self.active_session {
+            Some(session)
+                if matches!(session.state, CaptureState::Running | CaptureState::Paused) =>
+            {
+                session.recorded_monitor_ids.clone()
+            }
+            _ => return false,
+        };
+        if recorded_monitor_ids.is_empty() {
+            return false;
         }
+        let Ok(targets) = self.provider.get_targets() else {
+            return false;
+        };
+        let monitor_lost = recorded_monitor_ids.iter().any(|monitor_id| {
+            !targets
+                .iter()
+                .any(|target| target.id == *monitor_id && target.kind == TargetKind::Monitor)
+        });
+        if monitor_lost {
+            self.finish_active_session(Some(StopReason::SourceLost), |runtime| runtime.stop());
+        }
+        monitor_lost
     }
 
     pub fn refresh_runtime_state(&mut self) {
+        self.refresh_runtime_state_at(Instant::now());
+    }
+
+    fn refresh_runtime_state_at(&mut self, now: Instant) {
         self.finalize_finished_runtime_if_any();
+        if let Some(session) = self.active_session.as_mut() {
+            session.adjust_adaptive_fps(now);
+        }
     }
 
     pub fn get_targets(&self) -> Result<Vec<CaptureTarget>, String> {
@@ -217,7 +519,7 @@ impl CaptureManager {
         self.provider.is_supported()
     }
 
-    pub fn start(&mut self, config: SessionConfig) -> Result<(), String> {
+    pub fn start(&mut self, mut config: SessionConfig) -> Result<u64, String> {
         self.finalize_finished_runtime_if_any();
         self.cleanup_stopped_session_if_any();
 
@@ -229,19 +531,157 @@ impl CaptureManager {
             return Err("FPS inválido. Debe estar entre 1 y 120".to_string());
         }
 
-        let target = self
-            .get_targets()?
-            .into_iter()
+        let targets = self.get_targets()?;
+        let target = targets
+            .iter()
             .find(|target| target.id == config.target_id)
+            .cloned()
             .ok_or_else(|| format!("No se encontró un target con id {}", config.target_id))?;
 
         if let Some(region) = &config.crop_region {
             region.validate_against_target(&target)?;
         }
 
+        if config
+            .exclude_window_ids
+            .contains(&u64::from(config.target_id))
+        {
+            return Err("No se puede excluir de la captura la ventana que se graba".to_string());
+        }
+
+        if let Some(secondary_id) = config.secondary_target_id {
+            validate_secondary_target(&targets, &target, secondary_id, &config)?;
+        }
+
+        let window_follow = if config.follow_window_monitor {
+            let follow = self.resolve_window_follow(&target, &config)?;
+            config.target_id = follow.monitor_id;
+            Some(follow)
+        } else {
+            None
+        };
+
+        config.encoder_config.cursor_overlay = resolve_cursor_overlay(&target, &config)?;
+        if config.encoder_config.hdr {
+            config.encoder_config.hdr_metadata = display_hdr_metadata(config.target_id);
+        }
+
+        if let Some(idle_ms) = config.auto_pause_idle_ms {
+            if idle_ms < MIN_AUTO_PAUSE_IDLE_MS {
+                return Err(format!(
+                    "Pausa por inactividad inválida: {idle_ms} ms. Debe ser de al menos {MIN_AUTO_PAUSE_IDLE_MS} ms"
+                ));
+            }
+        }
+
+        // Un pedido de parada pendiente de una sesión anterior no debe cortar la nueva.
+        let _ = take_requested_stop_reason();
+        let chapter_markers = ChapterMarkerLog::default();
+        config.encoder_config.chapter_markers = chapter_markers.clone();
+        let idle_tracker = config.auto_pause_idle_ms.map(|idle_ms| {
+            Arc::new(IdleTracker::new(
+                Duration::from_millis(idle_ms),
+                Instant::now(),
+            ))
+        });
+        config.idle_tracker = idle_tracker.clone();
+        let pipeline_load = config
+            .adaptive_fps
+            .then(|| Arc::new(PipelineLoad::default()));
+        config.pipeline_load = pipeline_load.clone();
+        let clock = RecordingClock::new(idle_tracker);
+        config.recording_clock = Some(clock.clone());
+        let frame_snapshot = FrameSnapshotRequest::default();
+        config.encoder_config.frame_snapshot = frame_snapshot.clone();
+        let session_parts = SessionParts::default();
+        config.encoder_config.session_parts = session_parts.clone();
+        let frame_source = FrameSnapshotSource {
+            request: frame_snapshot,
+            target_id: config.target_id,
+            crop_region: config.crop_region.clone(),
+            has_blur_regions: !config.encoder_config.blur_regions.is_empty(),
+        };
+        let fps = config.fps;
+        // La clave de un stream RTMP no sale de la sesión: ni `stop` ni la
+        // última ruta la devuelven.
+        let output_path = match config.encoder_config.stream_output_url() {
+            Some(url) => PathBuf::from(redact_stream_url(url)),
+            None => config.encoder_config.output_path.clone(),
+        };
+        let window_exclusion = WindowExclusion::apply(&config.exclude_window_ids)?;
+        let border_region = config
+            .crop_region
+            .as_ref()
+            .filter(|_| config.show_region_border)
+            .map(|region| ScreenRect::of_region(&target, region));
+        let recorded_monitor_ids = if window_follow.is_some() {
+            Vec::new()
+        } else {
+            std::iter::once(&target)
+                .filter(|target| target.kind == TargetKind::Monitor)
+                .map(|target| target.id)
+                .chain(config.secondary_target_id)
+                .collect()
+        };
         let runtime = self.runtime_factory.build(config)?;
-        self.active_session = Some(ActiveSession::new(runtime));
-        Ok(())
+        let mut session = ActiveSession::new(
+            runtime,
+            chapter_markers,
+            clock,
+            self.tick_sink.clone(),
+            output_path,
+            fps,
+            frame_source,
+        );
+        session.adaptive_fps = pipeline_load.map(|load| AdaptiveFps {
+            controller: AdaptiveFpsController::new(fps, ADAPTIVE_FPS_MAX_DROPS_PER_SEC),
+            load,
+        });
+        session.window_exclusion = Some(window_exclusion);
+        session.recorded_monitor_ids = recorded_monitor_ids;
+        session.session_parts = session_parts;
+        if let Some(follow) = window_follow {
+            session.start_following(follow);
+        }
+        if let Some(region) = border_region {
+            match RecordingBorder::show(region) {
+                Ok(border) => session.region_border = Some(border),
+                Err(err) => session.set_warning(&err),
+            }
+        }
+        self.active_session = Some(session);
+        self.session_serial = self.session_serial.wrapping_add(1);
+        Ok(self.session_serial)
+    }
+
+    fn resolve_window_follow(
+        &self,
+        target: &CaptureTarget,
+        config: &SessionConfig,
+    ) -> Result<WindowFollow, String> {
+        if target.kind != TargetKind::Window {
+            return Err(
+                "Para seguir el monitor de una ventana el target debe ser una ventana".to_string(),
+            );
+        }
+        if config.crop_region.is_some() {
+            return Err(
+                "No se puede recortar una región al seguir el monitor de una ventana".to_string(),
+            );
+        }
+
+        let lookup = self.provider.window_monitor_lookup();
+        let monitor_id = lookup(target.id)?
+            .ok_or_else(|| format!("La ventana '{}' ya no existe", target.name))?;
+        Ok(WindowFollow {
+            window_id: target.id,
+            monitor_id,
+            lookup,
+        })
+    }
+
+    pub fn session_serial(&self) -> u64 {
+        self.session_serial
     }
 
     pub fn pause(&mut self) -> Result<(), String> {
@@ -263,7 +703,7 @@ impl CaptureManager {
             runtime.pause();
         }
 
-        session.accumulate_elapsed();
+        session.clock.pause();
         session.state = CaptureState::Paused;
         Ok(())
     }
@@ -288,11 +728,11 @@ impl CaptureManager {
         }
 
         session.state = CaptureState::Running;
-        session.last_resume_at = Some(Instant::now());
+        session.clock.resume();
         Ok(())
     }
 
-    pub fn stop(&mut self) -> Result<(), String> {
+    pub fn stop(&mut self) -> Result<PathBuf, String> {
         self.finalize_finished_runtime_if_any();
 
         let mut session = self
@@ -301,7 +741,7 @@ impl CaptureManager {
             .ok_or_else(|| "No hay una grabación activa".to_string())?;
 
         if session.state.can_stop() {
-            session.accumulate_elapsed();
+            session.stop_session_threads();
             session.state = CaptureState::Stopped;
         } else if session.state != CaptureState::Stopped {
             self.active_session = Some(session);
@@ -322,11 +762,46 @@ impl CaptureManager {
             }
         }
 
-        Ok(())
+        self.last_output_path = Some(session.output_path.clone());
+        self.last_session_parts = session.session_parts;
+        Ok(session.output_path)
     }
 
     pub fn cancel(&mut self) -> Result<(), String> {
-        self.stop()
+        self.stop().map(|_| ())
+    }
+
+    pub fn last_output_path(&self) -> Option<PathBuf> {
+        self.last_output_path.clone()
+    }
+
+    pub fn last_output_paths(&self) -> Vec<PathBuf> {
+        self.last_output_path
+            .as_deref()
+            .map(|path| self.last_session_parts.paths(path))
+            .unwrap_or_default()
+    }
+
+    pub fn add_chapter_marker(&mut self, label: Option<String>) -> Result<ChapterMarker, String> {
+        self.finalize_finished_runtime_if_any();
+
+        let session = self
+            .active_session
+            .as_ref()
+            .filter(|session| matches!(session.state, CaptureState::Running | CaptureState::Paused))
+            .ok_or_else(|| "No hay una grabación activa para marcar un capítulo".to_string())?;
+
+        session.chapter_markers.push(session.elapsed_ms(), label)
+    }
+
+    pub fn frame_snapshot_source(&mut self) -> Result<FrameSnapshotSource, String> {
+        self.finalize_finished_runtime_if_any();
+
+        self.active_session
+            .as_ref()
+            .filter(|session| matches!(session.state, CaptureState::Running | CaptureState::Paused))
+            .map(|session| session.frame_source.clone())
+            .ok_or_else(|| "No hay una grabación activa de la que copiar el frame".to_string())
     }
 
     pub fn snapshot(&self) -> CaptureManagerSnapshot {
@@ -337,6 +812,21 @@ impl CaptureManager {
                 last_error: session.last_error.clone(),
                 video_encoder_label: None,
                 is_processing: false,
+                processing_eta_ms: None,
+                pipeline_latency_ms: None,
+                stop_reason: session.stop_reason,
+                disk_space_remaining_bytes: None,
+                estimated_minutes_left: None,
+                post_processing_jobs: Vec::new(),
+                auto_paused: session.state == CaptureState::Running
+                    && session.clock.is_auto_paused(),
+                warning: session.warning(),
+                applied_fps: Some(session.applied_fps),
+                captured_frames: session
+                    .frame_counter
+                    .as_ref()
+                    .map(|counter| counter.load(Ordering::Relaxed))
+                    .unwrap_or(0),
             },
             None => CaptureManagerSnapshot {
                 state: CaptureState::Idle,
@@ -344,6 +834,16 @@ impl CaptureManager {
                 last_error: None,
                 video_encoder_label: None,
                 is_processing: false,
+                processing_eta_ms: None,
+                pipeline_latency_ms: None,
+                stop_reason: None,
+                disk_space_remaining_bytes: None,
+                estimated_minutes_left: None,
+                post_processing_jobs: Vec::new(),
+                auto_paused: false,
+                warning: None,
+                applied_fps: None,
+                captured_frames: 0,
             },
         }
     }
@@ -362,12 +862,92 @@ impl Default for CaptureManager {
     }
 }
 
+fn resolve_cursor_overlay(
+    target: &CaptureTarget,
+    config: &SessionConfig,
+) -> Result<Option<CursorOverlayConfig>, String> {
+    let (crop_x, crop_y) = config
+        .crop_region
+        .as_ref()
+        .map(|region| (region.x as i32, region.y as i32))
+        .unwrap_or_default();
+    let overlay = CursorOverlayConfig::from_scale(
+        config.cursor_scale,
+        target.origin_x + crop_x,
+        target.origin_y + crop_y,
+    )?;
+    if overlay.is_some()
+        && (config.follow_window_monitor
+            || config.secondary_target_id.is_some()
+            || config.encoder_config.hdr)
+    {
+        return Err(
+            "El cursor escalado no admite seguir ventana, dos monitores ni HDR".to_string(),
+        );
+    }
+    Ok(overlay)
+}
+
+fn validate_secondary_target(
+    targets: &[CaptureTarget],
+    primary: &CaptureTarget,
+    secondary_id: u32,
+    config: &SessionConfig,
+) -> Result<(), String> {
+    let secondary = targets
+        .iter()
+        .find(|target| target.id == secondary_id)
+        .ok_or_else(|| format!("No se encontró un target con id {secondary_id}"))?;
+    if secondary.id == primary.id {
+        return Err("El segundo monitor debe ser distinto del primero".to_string());
+    }
+    if primary.kind != TargetKind::Monitor || secondary.kind != TargetKind::Monitor {
+        return Err("La grabación de dos pantallas solo admite monitores".to_string());
+    }
+    if primary.height != secondary.height {
+        return Err(format!(
+            "Los monitores deben tener el mismo alto para grabarse lado a lado ('{}' mide {} px y '{}' {} px)",
+            primary.name, primary.height, secondary.name, secondary.height
+        ));
+    }
+    if config.crop_region.is_some()
+        || config.follow_window_monitor
+        || config.auto_pause_idle_ms.is_some()
+    {
+        return Err(
+            "La grabación de dos monitores no admite recorte, seguir ventana ni pausa por inactividad"
+                .to_string(),
+        );
+    }
+    if config.encoder_config.hdr {
+        return Err("La grabación de dos monitores no admite HDR".to_string());
+    }
+    Ok(())
+}
+
 fn should_prefer_gpu_frames(encoder_config: &EncoderConfig, crop_region: &Option<Region>) -> bool {
-    should_prefer_gpu_frames_with_flag(
+    if !should_prefer_gpu_frames_with_flag(
         encoder_config,
         crop_region,
         is_experimental_d3d11_input_enabled(),
-    )
+    ) {
+        return false;
+    }
+
+    let backend_label = match encoder_config.video_encoder_preference {
+        VideoEncoderPreference::Nvenc => "NVENC",
+        VideoEncoderPreference::Amf => "AMF",
+        VideoEncoderPreference::Qsv => "QSV",
+        _ => return false,
+    };
+    // En GPU híbrida la textura no llega al encoder; se usa la ruta CPU.
+    if let Some(mismatch) = predict_adapter_mismatch(&enumerate_adapters(), backend_label) {
+        eprintln!("[capture] {mismatch}. Se usa la ruta CPU.");
+        return false;
+    }
+    // Si hay que reescalar, la captura prueba el procesador de video en su
+    // propio device y sin él pasa la ruta a CPU.
+    true
 }
 
 fn should_prefer_gpu_frames_with_flag(
@@ -385,11 +965,38 @@ fn should_prefer_gpu_frames_with_flag(
         return false;
     }
 
+    // CFR repite el último frame convertido, que solo existe en la ruta CPU.
+    if encoder_config.frame_rate_mode == FrameRateMode::Cfr {
+        return false;
+    }
+
+    // La conversión a PQ y a 10 bits se hace sobre los bytes de la ruta CPU.
+    if encoder_config.bit_depth() == BitDepth::Ten {
+        return false;
+    }
+
+    // La superficie D3D11 va en NV12: el 4:4:4 pedido explícitamente sale del
+    // escalador CPU.
+    if encoder_config.chroma_subsampling == Some(ChromaSubsampling::Yuv444) {
+        return false;
+    }
+
+    // El cursor escalado y el difuminado se aplican sobre los bytes del frame.
+    if encoder_config.cursor_overlay.is_some() || !encoder_config.blur_regions.is_empty() {
+        return false;
+    }
+
     let codec = encoder_config.effective_codec();
     if matches!(codec, VideoCodec::Vp9) {
         return false;
     }
 
+    // La conversión BGRA->NV12 del encoder hardware no permite elegir el rango;
+    // el rango completo solo se garantiza con el escalador de la ruta CPU.
+    if encoder_config.color_range == ColorRange::Full {
+        return false;
+    }
+
     matches!(
         encoder_config.video_encoder_preference,
         VideoEncoderPreference::Nvenc | VideoEncoderPreference::Amf | VideoEncoderPreference::Qsv
@@ -407,6 +1014,7 @@ fn is_experimental_d3d11_input_enabled() -> bool {
 }
 
 const VIDEO_PIPELINE_QUEUE_CAPACITY: usize = 6;
+const VIDEO_WORKER_IDLE_WAKE: Duration = Duration::from_millis(100);
 
 enum VideoWorkerMessage {
     Frame(RawFrame),
@@ -417,16 +1025,64 @@ struct AsyncVideoPipeline {
     sender: SyncSender<VideoWorkerMessage>,
     worker: Mutex<Option<JoinHandle<()>>>,
     worker_error: Arc<Mutex<Option<String>>>,
-    queued_frames: Arc<AtomicUsize>,
-    dropped_frames: AtomicU64,
+    load: Arc<PipelineLoad>,
+    backpressure_window: Mutex<BackpressureWindow>,
+}
+
+impl AsyncVideoPipeline {
+    fn record_dropped_frame(&self) {
+        let total_dropped = self.load.dropped_frames.fetch_add(1, Ordering::Relaxed) + 1;
+        let dropped_in_window = match self.backpressure_window.lock() {
+            Ok(mut window) => window.record_drop(Instant::now(), total_dropped),
+            Err(_) => None,
+        };
+
+        if let Some(dropped_in_window) = dropped_in_window {
+            notify_encoder_backpressure(EncoderBackpressure::new(
+                total_dropped,
+                dropped_in_window,
+                self.load.queued_frames.load(Ordering::Acquire),
+                false,
+            ));
+        }
+    }
+}
+
+struct SessionFrameClock {
+    clock: RecordingClock,
+    origin_ms: Option<u64>,
+}
+
+impl SessionFrameClock {
+    fn new(clock: RecordingClock) -> Self {
+        Self {
+            clock,
+            origin_ms: None,
+        }
+    }
+
+    fn observe_frame(&mut self, frame: &RawFrame) {
+        if self.origin_ms.is_none() {
+            let queued_ms = frame.captured_at.elapsed().as_millis() as u64;
+            self.origin_ms = Some(self.clock.elapsed_ms().saturating_sub(queued_ms));
+        }
+    }
+
+    fn elapsed_ms(&self) -> Option<u64> {
+        let origin_ms = self.origin_ms?;
+        Some(self.clock.elapsed_ms().saturating_sub(origin_ms))
+    }
 }
 
 fn build_runtime_callbacks(
     encoder_config: EncoderConfig,
+    load: Arc<PipelineLoad>,
+    recording_clock: Option<RecordingClock>,
 ) -> Result<
     (
         runtime::ShouldAcceptFrameCallback,
         runtime::FrameDroppedCallback,
+        runtime::FramePacedCallback,
         FrameArrivedCallback,
         SessionFinishedCallback,
     ),
@@ -436,8 +1092,20 @@ fn build_runtime_callbacks(
         mpsc::sync_channel::<VideoWorkerMessage>(VIDEO_PIPELINE_QUEUE_CAPACITY);
     let worker_error = Arc::new(Mutex::new(None::<String>));
     let worker_error_for_thread = Arc::clone(&worker_error);
-    let queued_frames = Arc::new(AtomicUsize::new(0));
-    let queued_frames_for_thread = Arc::clone(&queued_frames);
+    let load_for_thread = Arc::clone(&load);
+    // Con CFR, o con el cursor propio (que la captura no redibuja), el worker
+    // no espera más de un frame sin revisar si toca repetir; si no, se
+    // despierta igual para copias de frame y el audio de un stream con la
+    // pantalla quieta.
+    let repeats_frames = recording_clock.is_some()
+        && (encoder_config.frame_rate_mode == FrameRateMode::Cfr
+            || encoder_config.cursor_overlay.is_some());
+    let wake_interval = if repeats_frames {
+        Duration::from_millis(1_000 / u64::from(encoder_config.fps.max(1)))
+    } else {
+        VIDEO_WORKER_IDLE_WAKE
+    };
+    let mut frame_clock = recording_clock.map(SessionFrameClock::new);
 
     let worker = thread::Builder::new()
         .name("video-encoder-worker".to_string())
@@ -452,10 +1120,48 @@ fn build_runtime_callbacks(
                 }
             };
 
-            while let Ok(message) = receiver.recv() {
+            loop {
+                let message = match receiver.recv_timeout(wake_interval) {
+                    Ok(message) => message,
+                    Err(RecvTimeoutError::Timeout) => {
+                        let filled = frame_clock
+                            .as_ref()
+                            .and_then(SessionFrameClock::elapsed_ms)
+                            .map_or(Ok(()), |elapsed_ms| {
+                                consumer.fill_cfr_gap(elapsed_ms)?;
+                                consumer.redraw_moved_cursor(elapsed_ms)
+                            });
+                        if let Err(err) = filled {
+                            set_worker_error(
+                                &worker_error_for_thread,
+                                format!("Error repitiendo frame de video: {err}"),
+                            );
+                            break;
+                        }
+                        if let Err(err) = consumer.pump_live_audio() {
+                            set_worker_error(
+                                &worker_error_for_thread,
+                                format!("Error codificando audio en vivo: {err}"),
+                            );
+                            break;
+                        }
+                        consumer.serve_frame_snapshot();
+                        consumer.check_disk_space();
+                        if let Err(err) = consumer.check_stall(Instant::now()) {
+                            set_worker_error(&worker_error_for_thread, err);
+                            break;
+                        }
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+
                 match message {
                     VideoWorkerMessage::Frame(raw_frame) => {
-                        decrement_queued_frames(&queued_frames_for_thread);
+                        decrement_queued_frames(&load_for_thread.queued_frames);
+                        if let Some(frame_clock) = frame_clock.as_mut() {
+                            frame_clock.observe_frame(&raw_frame);
+                        }
                         if let Err(err) = consumer.on_frame(raw_frame) {
                             set_worker_error(
                                 &worker_error_for_thread,
@@ -463,12 +1169,19 @@ fn build_runtime_callbacks(
                             );
                             break;
                         }
+                        if let Err(err) = consumer.check_stall(Instant::now()) {
+                            set_worker_error(&worker_error_for_thread, err);
+                            break;
+                        }
                     }
                     VideoWorkerMessage::Stop => break,
                 }
             }
 
-            if let Err(err) = consumer.on_stop() {
+            // La duración final debe llegar al tiempo grabado aunque el final
+            // haya sido una pantalla quieta.
+            let stop_at_ms = frame_clock.as_ref().and_then(SessionFrameClock::elapsed_ms);
+            if let Err(err) = consumer.on_stop(stop_at_ms) {
                 set_worker_error(
                     &worker_error_for_thread,
                     format!("Error cerrando encoder de video: {err}"),
@@ -481,8 +1194,11 @@ fn build_runtime_callbacks(
         sender,
         worker: Mutex::new(Some(worker)),
         worker_error,
-        queued_frames,
-        dropped_frames: AtomicU64::new(0),
+        load,
+        backpressure_window: Mutex::new(BackpressureWindow::new(
+            BACKPRESSURE_WINDOW,
+            BACKPRESSURE_DROP_THRESHOLD,
+        )),
     });
 
     let should_accept_frame: runtime::ShouldAcceptFrameCallback = {
@@ -492,15 +1208,20 @@ fn build_runtime_callbacks(
                 return Err(err);
             }
 
-            let queued = pipeline.queued_frames.load(Ordering::Acquire);
+            let queued = pipeline.load.queued_frames.load(Ordering::Acquire);
             Ok(queued < VIDEO_PIPELINE_QUEUE_CAPACITY)
         })
     };
 
     let on_frame_dropped: runtime::FrameDroppedCallback = {
+        let pipeline = Arc::clone(&pipeline);
+        Arc::new(move || pipeline.record_dropped_frame())
+    };
+
+    let on_frame_paced: runtime::FramePacedCallback = {
         let pipeline = Arc::clone(&pipeline);
         Arc::new(move || {
-            pipeline.dropped_frames.fetch_add(1, Ordering::Relaxed);
+            pipeline.load.paced_frames.fetch_add(1, Ordering::Relaxed);
         })
     };
 
@@ -511,20 +1232,20 @@ fn build_runtime_callbacks(
                 return Err(err);
             }
 
-            pipeline.queued_frames.fetch_add(1, Ordering::AcqRel);
+            pipeline.load.queued_frames.fetch_add(1, Ordering::AcqRel);
             match pipeline
                 .sender
                 .try_send(VideoWorkerMessage::Frame(raw_frame))
             {
                 Ok(()) => Ok(()),
                 Err(TrySendError::Full(_)) => {
-                    decrement_queued_frames(&pipeline.queued_frames);
+                    decrement_queued_frames(&pipeline.load.queued_frames);
                     // Mantiene la captura fluida cuando el encoder va atrasado.
-                    pipeline.dropped_frames.fetch_add(1, Ordering::Relaxed);
+                    pipeline.record_dropped_frame();
                     Ok(())
                 }
                 Err(TrySendError::Disconnected(_)) => {
-                    decrement_queued_frames(&pipeline.queued_frames);
+                    decrement_queued_frames(&pipeline.load.queued_frames);
                     if let Some(err) = read_worker_error(&pipeline.worker_error)? {
                         return Err(err);
                     }
@@ -556,11 +1277,18 @@ fn build_runtime_callbacks(
                 }
             }
 
-            let dropped = pipeline.dropped_frames.load(Ordering::Relaxed);
+            let dropped = pipeline.load.dropped_frames.load(Ordering::Relaxed);
             if dropped > 0 {
                 eprintln!(
                     "[capture] Se descartaron {dropped} frames por backpressure del encoder."
                 );
+                notify_encoder_backpressure(EncoderBackpressure::new(dropped, 0, 0, true));
+            }
+            let paced = pipeline.load.paced_frames.load(Ordering::Relaxed);
+            if paced > 0 {
+                eprintln!(
+                    "[capture] Se omitieron {paced} frames que llegaron antes del intervalo de los FPS pedidos."
+                );
             }
 
             if let Some(err) = take_worker_error(&pipeline.worker_error)? {
@@ -574,6 +1302,7 @@ fn build_runtime_callbacks(
     Ok((
         should_accept_frame,
         on_frame_dropped,
+        on_frame_paced,
         frame_callback,
         session_finished_callback,
     ))
@@ -629,146 +1358,442 @@ fn set_worker_error(error_slot: &Arc<Mutex<Option<String>>>, message: String) {
 
 #[cfg(test)]
 mod tests {
-    use std::sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    };
-
     use super::*;
-    use crate::capture::models::TargetKind;
+    use crate::capture::test_support::{
+        make_mock_manager, make_session_config, MockRuntimeHandle, MockScreenProvider,
+    };
     use crate::encoder::config::{VideoCodec, VideoEncoderPreference};
 
-    struct MockScreenProvider {
-        supported: bool,
-        targets: Vec<CaptureTarget>,
-    }
-
-    impl MockScreenProvider {
-        fn with_single_monitor() -> Self {
-            Self {
-                supported: true,
-                targets: vec![CaptureTarget {
-                    id: 1,
-                    name: "Monitor de prueba".to_string(),
-                    width: 1920,
-                    height: 1080,
-                    origin_x: 0,
-                    origin_y: 0,
-                    screen_width: 1920,
-                    screen_height: 1080,
-                    is_primary: true,
-                    kind: TargetKind::Monitor,
-                }],
-            }
-        }
+    #[test]
+    fn manager_nuevo_esta_en_idle() {
+        let manager = make_mock_manager();
+        let snapshot = manager.snapshot();
+
+        assert_eq!(snapshot.state, CaptureState::Idle);
+        assert_eq!(snapshot.elapsed_ms, 0);
+        assert!(snapshot.last_error.is_none());
     }
 
-    impl ScreenProvider for MockScreenProvider {
-        fn get_targets(&self) -> Result<Vec<CaptureTarget>, String> {
-            Ok(self.targets.clone())
-        }
+    #[test]
+    fn refleja_si_el_backend_esta_soportado() {
+        let manager = make_mock_manager();
+        assert!(manager.is_supported());
+    }
 
-        fn is_supported(&self) -> bool {
-            self.supported
-        }
+    #[test]
+    fn start_pause_resume_stop_actualiza_estado() {
+        let mut manager = make_mock_manager();
+
+        manager.start(make_session_config(1)).unwrap();
+        assert_eq!(manager.snapshot().state, CaptureState::Running);
+
+        manager.pause().unwrap();
+        assert_eq!(manager.snapshot().state, CaptureState::Paused);
+
+        manager.resume().unwrap();
+        assert_eq!(manager.snapshot().state, CaptureState::Running);
+
+        manager.stop().unwrap();
+        assert_eq!(manager.snapshot().state, CaptureState::Idle);
     }
 
-    struct MockRuntimeHandle {
-        paused: Arc<AtomicBool>,
-        finished: Arc<AtomicBool>,
+    #[test]
+    fn no_puede_iniciar_dos_veces() {
+        let mut manager = make_mock_manager();
+
+        manager.start(make_session_config(1)).unwrap();
+        let err = manager.start(make_session_config(1)).unwrap_err();
+
+        assert!(err.contains("grabación en curso"));
     }
 
-    impl MockRuntimeHandle {
-        fn new() -> Self {
-            Self {
-                paused: Arc::new(AtomicBool::new(false)),
-                finished: Arc::new(AtomicBool::new(false)),
-            }
-        }
+    #[test]
+    fn ticks_de_grabacion_se_detienen_en_pausa_y_al_detener() {
+        let ticks = Arc::new(Mutex::new(Vec::new()));
+        let collected = Arc::clone(&ticks);
+        let mut manager = make_mock_manager();
+        manager.set_tick_sink(Arc::new(move |tick| collected.lock().unwrap().push(tick)));
+
+        manager.start(make_session_config(1)).unwrap();
+        thread::sleep(RECORDING_TICK_INTERVAL * 2 + RECORDING_TICK_INTERVAL / 2);
+        manager.pause().unwrap();
+        let ticks_before_pause = ticks.lock().unwrap().len();
+        assert!(ticks_before_pause >= 1);
+
+        thread::sleep(RECORDING_TICK_INTERVAL * 2);
+        assert_eq!(ticks.lock().unwrap().len(), ticks_before_pause);
+
+        manager.resume().unwrap();
+        manager.stop().unwrap();
+        thread::sleep(RECORDING_TICK_INTERVAL * 2);
+        assert_eq!(ticks.lock().unwrap().len(), ticks_before_pause);
     }
 
-    impl CaptureRuntimeHandle for MockRuntimeHandle {
-        fn pause(&self) {
-            self.paused.store(true, Ordering::Relaxed);
-        }
+    #[test]
+    fn marcador_requiere_sesion_activa() {
+        let mut manager = make_mock_manager();
 
-        fn resume(&self) {
-            self.paused.store(false, Ordering::Relaxed);
-        }
+        let err = manager.add_chapter_marker(None).unwrap_err();
+        assert!(err.contains("No hay una grabación activa"));
 
-        fn is_finished(&self) -> bool {
-            self.finished.load(Ordering::Relaxed)
-        }
+        manager.start(make_session_config(1)).unwrap();
+        let marker = manager.add_chapter_marker(None).unwrap();
+        assert_eq!(marker.label, "Capítulo 1");
+        let marker = manager
+            .add_chapter_marker(Some("Demo".to_string()))
+            .unwrap();
+        assert_eq!(marker.label, "Demo");
 
-        fn stop(self: Box<Self>) -> Result<u64, String> {
-            self.finished.store(true, Ordering::Relaxed);
-            Ok(0)
-        }
+        manager.stop().unwrap();
+        assert!(manager.add_chapter_marker(None).is_err());
+    }
 
-        fn wait(self: Box<Self>) -> Result<u64, String> {
-            self.finished.store(true, Ordering::Relaxed);
-            Ok(0)
-        }
+    #[test]
+    fn pausa_por_inactividad_valida_el_umbral_y_no_descuenta_sin_quietud() {
+        let mut manager = make_mock_manager();
+        let mut config = make_session_config(1);
+        config.auto_pause_idle_ms = Some(200);
+
+        let err = manager.start(config.clone()).unwrap_err();
+        assert!(err.contains("Pausa por inactividad inválida"));
+
+        config.auto_pause_idle_ms = Some(60_000);
+        manager.start(config).unwrap();
+        let snapshot = manager.snapshot();
+        assert_eq!(snapshot.state, CaptureState::Running);
+        assert!(!snapshot.auto_paused);
+        manager.stop().unwrap();
     }
 
-    fn make_mock_manager() -> CaptureManager {
-        CaptureManager::with_dependencies(
-            Box::new(MockScreenProvider::with_single_monitor()),
+    #[test]
+    fn seguir_monitor_requiere_ventana_y_graba_su_monitor() {
+        let started_targets = Arc::new(Mutex::new(Vec::new()));
+        let recorded_targets = Arc::clone(&started_targets);
+        let provider = MockScreenProvider::with_monitor_and_window();
+        let window_monitor = Arc::clone(&provider.window_monitor);
+        let mut manager = CaptureManager::with_dependencies(
+            Box::new(provider),
+            RuntimeFactory::new(move |config: SessionConfig| {
+                recorded_targets.lock().unwrap().push(config.target_id);
+                Ok(Box::new(MockRuntimeHandle::new()))
+            }),
+        );
+
+        let mut monitor_config = make_session_config(1);
+        monitor_config.follow_window_monitor = true;
+        let err = manager.start(monitor_config).unwrap_err();
+        assert!(err.contains("el target debe ser una ventana"));
+
+        let mut window_config = make_session_config(10);
+        window_config.follow_window_monitor = true;
+        *window_monitor.lock().unwrap() = None;
+        let err = manager.start(window_config.clone()).unwrap_err();
+        assert!(err.contains("ya no existe"));
+
+        *window_monitor.lock().unwrap() = Some(1);
+        manager.start(window_config).unwrap();
+        assert_eq!(*started_targets.lock().unwrap(), vec![1]);
+        assert_eq!(manager.snapshot().warning, None);
+        manager.stop().unwrap();
+    }
+
+    #[test]
+    fn no_se_excluye_la_ventana_que_se_graba() {
+        let mut manager = CaptureManager::with_dependencies(
+            Box::new(MockScreenProvider::with_monitor_and_window()),
             RuntimeFactory::new(|_config| Ok(Box::new(MockRuntimeHandle::new()))),
-        )
+        );
+        let mut config = make_session_config(10);
+        config.exclude_window_ids = vec![10];
+        let err = manager.start(config).unwrap_err();
+        assert!(err.contains("la ventana que se graba"), "{err}");
+        assert_eq!(manager.snapshot().state, CaptureState::Idle);
     }
 
-    fn make_session_config(target_id: u32) -> SessionConfig {
-        SessionConfig {
-            target_id,
-            fps: 30,
-            crop_region: None,
-            capture_resolution_preset: None,
-            encoder_config: EncoderConfig::default(),
+    #[test]
+    fn dos_monitores_requieren_el_mismo_alto_y_sin_recorte() {
+        let started = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&started);
+        let mut manager = CaptureManager::with_dependencies(
+            Box::new(MockScreenProvider::with_side_monitor(2560, 1080)),
+            RuntimeFactory::new(move |config: SessionConfig| {
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push((config.target_id, config.secondary_target_id));
+                Ok(Box::new(MockRuntimeHandle::new()))
+            }),
+        );
+
+        let mut config = make_session_config(1);
+        config.secondary_target_id = Some(1);
+        let err = manager.start(config.clone()).unwrap_err();
+        assert!(err.contains("distinto"), "{err}");
+
+        config.secondary_target_id = Some(2);
+        config.crop_region = Some(Region {
+            x: 0,
+            y: 0,
+            width: 640,
+            height: 480,
+        });
+        let err = manager.start(config.clone()).unwrap_err();
+        assert!(err.contains("recorte"), "{err}");
+
+        config.crop_region = None;
+        manager.start(config).unwrap();
+        assert_eq!(*started.lock().unwrap(), vec![(1, Some(2))]);
+        manager.stop().unwrap();
+
+        let mut manager = CaptureManager::with_dependencies(
+            Box::new(MockScreenProvider::with_side_monitor(1280, 1024)),
+            RuntimeFactory::new(|_config: SessionConfig| Ok(Box::new(MockRuntimeHandle::new()))),
+        );
+        let mut config = make_session_config(1);
+        config.secondary_target_id = Some(2);
+        let err = manager.start(config).unwrap_err();
+        assert!(err.contains("mismo alto"), "{err}");
+    }
+
+    #[test]
+    fn cursor_escalado_usa_el_origen_del_recorte() {
+        let overlays = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&overlays);
+        let mut manager = CaptureManager::with_dependencies(
+            Box::new(MockScreenProvider::with_side_monitor(1920, 1080)),
+            RuntimeFactory::new(move |config: SessionConfig| {
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(config.encoder_config.cursor_overlay);
+                Ok(Box::new(MockRuntimeHandle::new()))
+            }),
+        );
+
+        let mut config = make_session_config(2);
+        config.cursor_scale = 10.0;
+        let err = manager.start(config.clone()).unwrap_err();
+        assert!(err.contains("Escala de cursor inválida"), "{err}");
+
+        config.cursor_scale = 2.0;
+        config.secondary_target_id = Some(1);
+        let err = manager.start(config.clone()).unwrap_err();
+        assert!(err.contains("cursor escalado"), "{err}");
+
+        config.secondary_target_id = None;
+        config.crop_region = Some(Region {
+            x: 100,
+            y: 50,
+            width: 640,
+            height: 480,
+        });
+        manager.start(config).unwrap();
+        manager.stop().unwrap();
+        manager.start(make_session_config(2)).unwrap();
+        manager.stop().unwrap();
+
+        assert_eq!(
+            *overlays.lock().unwrap(),
+            vec![
+                Some(CursorOverlayConfig {
+                    scale_percent: 200,
+                    origin_x: 2020,
+                    origin_y: 50,
+                }),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn adaptive_fps_baja_y_recupera_los_fps_del_snapshot() {
+        let shared_load = Arc::new(Mutex::new(None::<Arc<PipelineLoad>>));
+        let captured_load = Arc::clone(&shared_load);
+        let mut manager = CaptureManager::with_dependencies(
+            Box::new(MockScreenProvider::with_single_monitor()),
+            RuntimeFactory::new(move |config: SessionConfig| {
+                *captured_load.lock().unwrap() = config.pipeline_load.clone();
+                Ok(Box::new(MockRuntimeHandle::new()))
+            }),
+        );
+
+        manager.start(make_session_config(1)).unwrap();
+        assert!(shared_load.lock().unwrap().is_none());
+        assert_eq!(manager.snapshot().applied_fps, Some(30));
+        manager.stop().unwrap();
+
+        let mut config = make_session_config(1);
+        config.adaptive_fps = true;
+        manager.start(config).unwrap();
+        let load = shared_load.lock().unwrap().clone().unwrap();
+        let start = Instant::now();
+        for second in 0..=3_u64 {
+            load.dropped_frames.store(second * 20, Ordering::Relaxed);
+            manager.refresh_runtime_state_at(start + Duration::from_secs(second));
+        }
+        assert_eq!(manager.snapshot().applied_fps, Some(20));
+
+        for second in 4..=14_u64 {
+            manager.refresh_runtime_state_at(start + Duration::from_secs(second));
         }
+        assert_eq!(manager.snapshot().applied_fps, Some(30));
+        manager.stop().unwrap();
+        assert_eq!(manager.snapshot().applied_fps, None);
     }
 
     #[test]
-    fn manager_nuevo_esta_en_idle() {
-        let manager = make_mock_manager();
-        let snapshot = manager.snapshot();
+    fn snapshot_expone_los_frames_capturados_en_vivo() {
+        let frame_counter = Arc::new(AtomicU64::new(0));
+        let runtime_counter = Arc::clone(&frame_counter);
+        let mut manager = CaptureManager::with_dependencies(
+            Box::new(MockScreenProvider::with_single_monitor()),
+            RuntimeFactory::new(move |_config| {
+                Ok(Box::new(
+                    MockRuntimeHandle::new().with_frame_counter(Arc::clone(&runtime_counter)),
+                ))
+            }),
+        );
+        assert_eq!(manager.snapshot().captured_frames, 0);
 
-        assert_eq!(snapshot.state, CaptureState::Idle);
-        assert_eq!(snapshot.elapsed_ms, 0);
-        assert!(snapshot.last_error.is_none());
+        manager.start(make_session_config(1)).unwrap();
+        frame_counter.store(42, Ordering::Relaxed);
+        assert_eq!(manager.snapshot().captured_frames, 42);
+        frame_counter.fetch_add(3, Ordering::Relaxed);
+        assert_eq!(manager.snapshot().captured_frames, 45);
+
+        manager.stop().unwrap();
+        assert_eq!(manager.snapshot().captured_frames, 0);
     }
 
     #[test]
-    fn refleja_si_el_backend_esta_soportado() {
-        let manager = make_mock_manager();
-        assert!(manager.is_supported());
+    fn start_acepta_region_fisica_en_monitor_4k_escalado() {
+        let mut manager = CaptureManager::with_dependencies(
+            Box::new(MockScreenProvider::with_scaled_4k_monitor()),
+            RuntimeFactory::new(|_config| Ok(Box::new(MockRuntimeHandle::new()))),
+        );
+
+        let mut config = make_session_config(1);
+        config.crop_region = Some(Region {
+            x: 1920,
+            y: 1080,
+            width: 1920,
+            height: 1080,
+        });
+        manager.start(config).unwrap();
+        manager.stop().unwrap();
+
+        let mut outside = make_session_config(1);
+        outside.crop_region = Some(Region {
+            x: 3840,
+            y: 0,
+            width: 10,
+            height: 10,
+        });
+        let err = manager.start(outside).unwrap_err();
+        assert!(err.contains("fuera del target"));
     }
 
     #[test]
-    fn start_pause_resume_stop_actualiza_estado() {
+    fn start_devuelve_el_serial_de_la_sesion_que_creo() {
         let mut manager = make_mock_manager();
 
-        manager.start(make_session_config(1)).unwrap();
-        assert_eq!(manager.snapshot().state, CaptureState::Running);
+        let first = manager.start(make_session_config(1)).unwrap();
+        assert_eq!(first, manager.session_serial());
+        manager.stop().unwrap();
 
-        manager.pause().unwrap();
-        assert_eq!(manager.snapshot().state, CaptureState::Paused);
+        let second = manager.start(make_session_config(1)).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(second, manager.session_serial());
+    }
 
-        manager.resume().unwrap();
-        assert_eq!(manager.snapshot().state, CaptureState::Running);
+    #[test]
+    fn stop_devuelve_la_ruta_final_y_la_recuerda() {
+        let mut manager = make_mock_manager();
+        assert_eq!(manager.last_output_path(), None);
+
+        let mut config = make_session_config(1);
+        config.encoder_config.output_path = PathBuf::from("videos/demo.mp4");
+        manager.start(config).unwrap();
+        assert_eq!(manager.last_output_path(), None);
+
+        let output_path = manager.stop().unwrap();
+        assert_eq!(output_path, PathBuf::from("videos/demo.mp4"));
+        assert_eq!(manager.last_output_path(), Some(output_path));
+    }
+
+    #[test]
+    fn las_rutas_finales_incluyen_las_partes_abiertas_por_el_encoder() {
+        let mut manager = CaptureManager::with_dependencies(
+            Box::new(MockScreenProvider::with_single_monitor()),
+            RuntimeFactory::new(|config| {
+                config.encoder_config.session_parts.record(2);
+                Ok(Box::new(MockRuntimeHandle::new()))
+            }),
+        );
+        assert!(manager.last_output_paths().is_empty());
 
+        let mut config = make_session_config(1);
+        config.encoder_config.output_path = PathBuf::from("videos/demo.mp4");
+        manager.start(config).unwrap();
         manager.stop().unwrap();
-        assert_eq!(manager.snapshot().state, CaptureState::Idle);
+
+        assert_eq!(
+            manager.last_output_paths(),
+            vec![
+                PathBuf::from("videos/demo.mp4"),
+                PathBuf::from("videos/demo.part2.mp4"),
+            ]
+        );
     }
 
     #[test]
-    fn no_puede_iniciar_dos_veces() {
+    fn la_ruta_de_un_stream_rtmp_no_lleva_la_clave() {
         let mut manager = make_mock_manager();
+        let mut config = make_session_config(1);
+        config.encoder_config.output_path =
+            PathBuf::from("rtmp://live.example.com/app/clave-secreta");
+        manager.start(config).unwrap();
+
+        let output_path = manager.stop().unwrap();
+        assert_eq!(
+            output_path,
+            PathBuf::from("rtmp://live.example.com/app/***")
+        );
+        assert_eq!(manager.last_output_path(), Some(output_path));
+    }
 
+    #[test]
+    fn desconectar_el_monitor_grabado_detiene_la_sesion() {
+        let provider = MockScreenProvider::with_side_monitor(1920, 1080);
+        let disconnected = Arc::clone(&provider.disconnected_ids);
+        let mut manager = CaptureManager::with_dependencies(
+            Box::new(provider),
+            RuntimeFactory::new(|_config| Ok(Box::new(MockRuntimeHandle::new()))),
+        );
         manager.start(make_session_config(1)).unwrap();
-        let err = manager.start(make_session_config(1)).unwrap_err();
+        assert!(!manager.handle_display_change());
 
-        assert!(err.contains("grabación en curso"));
+        disconnected.lock().unwrap().push(2);
+        assert!(!manager.handle_display_change());
+        assert_eq!(manager.snapshot().state, CaptureState::Running);
+
+        disconnected.lock().unwrap().push(1);
+        assert!(manager.handle_display_change());
+        let snapshot = manager.snapshot();
+        assert_eq!(snapshot.state, CaptureState::Stopped);
+        assert_eq!(snapshot.stop_reason, Some(StopReason::SourceLost));
+        assert!(snapshot.last_error.unwrap().contains("desapareció"));
+        assert!(manager.last_output_path().is_some());
+        assert!(!manager.handle_display_change());
+    }
+
+    #[test]
+    fn perder_la_fuente_se_informa_como_error() {
+        assert!(auto_stop_error(Some(StopReason::SourceLost))
+            .unwrap()
+            .contains("La fuente de captura desapareció"));
+        assert_eq!(auto_stop_error(Some(StopReason::DiskFull)), None);
+        assert_eq!(auto_stop_error(None), None);
     }
 
     #[test]
@@ -821,6 +1846,37 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn no_prefiere_frames_gpu_con_rango_de_color_completo() {
+        let config = EncoderConfig {
+            video_encoder_preference: VideoEncoderPreference::Nvenc,
+            color_range: ColorRange::Full,
+            ..EncoderConfig::default()
+        };
+        assert!(!should_prefer_gpu_frames_with_flag(&config, &None, true));
+    }
+
+    #[test]
+    fn no_prefiere_frames_gpu_con_cfr() {
+        let config = EncoderConfig {
+            video_encoder_preference: VideoEncoderPreference::Nvenc,
+            frame_rate_mode: FrameRateMode::Cfr,
+            ..EncoderConfig::default()
+        };
+        assert!(!should_prefer_gpu_frames_with_flag(&config, &None, true));
+    }
+
+    #[test]
+    fn no_prefiere_frames_gpu_en_hdr() {
+        let config = EncoderConfig {
+            video_encoder_preference: VideoEncoderPreference::Nvenc,
+            codec: Some(VideoCodec::H265),
+            hdr: true,
+            ..EncoderConfig::default()
+        };
+        assert!(!should_prefer_gpu_frames_with_flag(&config, &None, true));
+    }
+
     #[test]
     fn no_prefiere_frames_gpu_si_feature_experimental_esta_deshabilitada() {
         let config = EncoderConfig {
@@ -1,4 +1,21 @@
+pub mod adaptive_fps;
+pub mod backpressure;
+pub mod cursor_overlay;
+pub mod display_watch;
+pub mod frame_pacing;
+pub mod frame_snapshot;
+pub mod idle_pause;
 pub mod manager;
 pub mod models;
+pub mod monitor_follow;
+pub mod pause_timeline;
 pub mod provider;
+pub mod recording_clock;
 pub mod runtime;
+pub mod session_stop;
+pub mod side_by_side;
+pub mod target_id;
+pub mod target_thumbnail;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod window_exclusion;
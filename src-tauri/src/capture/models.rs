@@ -1,3 +1,5 @@
+use std::time::Instant;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -20,6 +22,57 @@ pub struct CaptureTarget {
     pub screen_height: u32,
     pub is_primary: bool,
     pub kind: TargetKind,
+    #[serde(default)]
+    pub process_name: Option<String>,
+    #[serde(default)]
+    pub process_id: Option<u32>,
+    #[serde(default)]
+    pub window_title: Option<String>,
+    #[serde(default)]
+    pub refresh_rate_hz: Option<u32>,
+    #[serde(default)]
+    pub dpi_scale_percent: Option<u32>,
+    #[serde(default)]
+    pub is_minimized: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TargetKindFilter {
+    Monitors,
+    Windows,
+    #[default]
+    All,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TargetFilter {
+    pub kind: TargetKindFilter,
+    pub process_name_contains: Option<String>,
+    pub include_minimized: bool,
+}
+
+impl TargetFilter {
+    pub fn matches(&self, target: &CaptureTarget) -> bool {
+        let kind_matches = match self.kind {
+            TargetKindFilter::Monitors => target.kind == TargetKind::Monitor,
+            TargetKindFilter::Windows => target.kind == TargetKind::Window,
+            TargetKindFilter::All => true,
+        };
+        let needle = self
+            .process_name_contains
+            .as_deref()
+            .map(str::trim)
+            .filter(|needle| !needle.is_empty());
+        let process_matches = match needle {
+            Some(needle) => target.process_name.as_deref().is_some_and(|process_name| {
+                process_name.to_lowercase().contains(&needle.to_lowercase())
+            }),
+            None => true,
+        };
+        kind_matches && process_matches && (self.include_minimized || !target.is_minimized)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,26 +85,90 @@ pub struct Region {
 }
 
 impl Region {
+    /// La región está en píxeles físicos del frame (`target.width`/`height`),
+    /// no en los lógicos de `screen_width`/`screen_height`: se valida igual que
+    /// la recorta el runtime, así lo que pasa aquí no falla al llegar el frame.
     pub fn validate_against_target(&self, target: &CaptureTarget) -> Result<(), String> {
         if self.width == 0 || self.height == 0 {
             return Err("La región de captura debe tener ancho y alto mayores a 0".to_string());
         }
 
-        if self.x + self.width > target.width {
+        if self.x >= target.width || self.y >= target.height {
             return Err(format!(
-                "La región excede el ancho del target: x({}) + width({}) > target_width({})",
-                self.x, self.width, target.width
+                "La región empieza fuera del target: ({}, {}) no está dentro de {}x{}",
+                self.x, self.y, target.width, target.height
             ));
         }
 
-        if self.y + self.height > target.height {
+        // Una selección lógica redondeada puede pasarse hasta `ceil(escala)`
+        // píxeles físicos; eso se recorta, más que eso es un error.
+        let tolerance = rounding_tolerance_px(target);
+        let end_x = self.x.saturating_add(self.width);
+        let end_y = self.y.saturating_add(self.height);
+        if end_x > target.width.saturating_add(tolerance)
+            || end_y > target.height.saturating_add(tolerance)
+        {
             return Err(format!(
-                "La región excede el alto del target: y({}) + height({}) > target_height({})",
-                self.y, self.height, target.height
+                "La región excede el target: termina en ({end_x}, {end_y}) y el target mide {}x{}",
+                target.width, target.height
             ));
         }
 
-        Ok(())
+        self.clamp_to_frame(target.width, target.height).map(|_| ())
+    }
+
+    pub fn clamp_to_frame(
+        &self,
+        frame_width: u32,
+        frame_height: u32,
+    ) -> Result<(u32, u32, u32, u32), String> {
+        if frame_width == 0 || frame_height == 0 {
+            return Err("Frame inválido: dimensiones 0x0".to_string());
+        }
+
+        let start_x = self.x.min(frame_width - 1);
+        let start_y = self.y.min(frame_height - 1);
+
+        let end_x = self.x.saturating_add(self.width).min(frame_width);
+        let end_y = self.y.saturating_add(self.height).min(frame_height);
+
+        if end_x <= start_x || end_y <= start_y {
+            return Err(
+                "La región de recorte no intersecta con el frame capturado en tiempo real"
+                    .to_string(),
+            );
+        }
+
+        Ok((start_x, start_y, end_x, end_y))
+    }
+}
+
+fn rounding_tolerance_px(target: &CaptureTarget) -> u32 {
+    let from_screen = target
+        .width
+        .div_ceil(target.screen_width.max(1))
+        .max(target.height.div_ceil(target.screen_height.max(1)));
+    target
+        .dpi_scale_percent
+        .map_or(from_screen, |percent| percent.div_ceil(100))
+        .max(1)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FramePixelFormat {
+    #[default]
+    Bgra8,
+    /// RGBA en half float lineal (scRGB, 1.0 = 80 nits); lo entrega la
+    /// captura HDR.
+    Rgba16F,
+}
+
+impl FramePixelFormat {
+    pub fn bytes_per_pixel(self) -> u32 {
+        match self {
+            FramePixelFormat::Bgra8 => 4,
+            FramePixelFormat::Rgba16F => 8,
+        }
     }
 }
 
@@ -62,8 +179,10 @@ pub struct RawFrame {
     pub width: u32,
     pub height: u32,
     pub row_stride_bytes: u32,
+    pub pixel_format: FramePixelFormat,
     pub gpu_texture_ptr: Option<usize>,
     pub timestamp_ms: u64,
+    pub captured_at: Instant,
 }
 
 #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
@@ -81,8 +200,10 @@ impl RawFrame {
             width,
             height,
             row_stride_bytes: row_stride_bytes.max(min_row_stride),
+            pixel_format: FramePixelFormat::Bgra8,
             gpu_texture_ptr: None,
             timestamp_ms,
+            captured_at: Instant::now(),
         }
     }
 
@@ -98,15 +219,30 @@ impl RawFrame {
             width,
             height,
             row_stride_bytes: 0,
+            pixel_format: FramePixelFormat::Bgra8,
             gpu_texture_ptr: (texture_ptr != 0).then_some(texture_ptr),
             timestamp_ms,
+            captured_at: Instant::now(),
         }
     }
 
+    pub fn with_pixel_format(mut self, pixel_format: FramePixelFormat) -> Self {
+        self.pixel_format = pixel_format;
+        self.row_stride_bytes = self
+            .row_stride_bytes
+            .max(self.width.saturating_mul(pixel_format.bytes_per_pixel()));
+        self
+    }
+
     pub fn min_row_stride_bytes(width: u32) -> u32 {
         width.saturating_mul(4)
     }
 
+    fn min_stride_for_format(&self) -> u32 {
+        self.width
+            .saturating_mul(self.pixel_format.bytes_per_pixel())
+    }
+
     pub fn expected_size(height: u32, row_stride_bytes: u32) -> usize {
         height.saturating_mul(row_stride_bytes) as usize
     }
@@ -129,7 +265,7 @@ impl RawFrame {
                 return false;
             }
 
-            if self.row_stride_bytes < Self::min_row_stride_bytes(self.width) {
+            if self.row_stride_bytes < self.min_stride_for_format() {
                 return false;
             }
 
@@ -173,7 +309,7 @@ impl RawFrame {
             return false;
         }
 
-        if self.row_stride_bytes < Self::min_row_stride_bytes(self.width) {
+        if self.row_stride_bytes < self.min_stride_for_format() {
             return false;
         }
 
@@ -199,6 +335,14 @@ pub enum CaptureResolutionPreset {
     R4320p,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StopReason {
+    DiskFull,
+    FileSizeLimit,
+    SourceLost,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum CaptureState {
@@ -236,3 +380,149 @@ impl std::fmt::Display for CaptureState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor_4k_al_150() -> CaptureTarget {
+        CaptureTarget {
+            id: 1,
+            name: "Monitor".to_string(),
+            width: 3840,
+            height: 2160,
+            origin_x: 0,
+            origin_y: 0,
+            screen_width: 2560,
+            screen_height: 1440,
+            is_primary: true,
+            kind: TargetKind::Monitor,
+            process_name: None,
+            process_id: None,
+            window_title: None,
+            refresh_rate_hz: None,
+            dpi_scale_percent: None,
+            is_minimized: false,
+        }
+    }
+
+    fn ventana(process_name: Option<&str>) -> CaptureTarget {
+        CaptureTarget {
+            kind: TargetKind::Window,
+            is_primary: false,
+            process_name: process_name.map(str::to_string),
+            process_id: process_name.map(|_| 4321),
+            window_title: None,
+            refresh_rate_hz: None,
+            dpi_scale_percent: None,
+            is_minimized: false,
+            ..monitor_4k_al_150()
+        }
+    }
+
+    fn region(x: u32, y: u32, width: u32, height: u32) -> Region {
+        Region {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn valida_en_pixeles_fisicos_como_el_runtime() {
+        let target = monitor_4k_al_150();
+
+        assert!(region(0, 0, 3840, 2160)
+            .validate_against_target(&target)
+            .is_ok());
+        assert!(region(3000, 1800, 840, 360)
+            .validate_against_target(&target)
+            .is_ok());
+        let overshoot = region(1920, 1080, 1921, 1081);
+        assert!(overshoot.validate_against_target(&target).is_ok());
+        assert_eq!(
+            overshoot.clamp_to_frame(target.width, target.height),
+            Ok((1920, 1080, 3840, 2160))
+        );
+    }
+
+    #[test]
+    fn rechaza_region_vacia_o_fuera_del_target() {
+        let target = monitor_4k_al_150();
+
+        assert!(region(0, 0, 0, 10)
+            .validate_against_target(&target)
+            .unwrap_err()
+            .contains("mayores a 0"));
+        assert!(region(3840, 0, 10, 10)
+            .validate_against_target(&target)
+            .unwrap_err()
+            .contains("fuera del target"));
+        assert!(region(0, 2200, 10, 10)
+            .validate_against_target(&target)
+            .is_err());
+    }
+
+    #[test]
+    fn rechaza_region_que_excede_el_target_mas_que_el_redondeo() {
+        let target = monitor_4k_al_150();
+
+        assert!(region(0, 0, 100_000, 10)
+            .validate_against_target(&target)
+            .unwrap_err()
+            .contains("excede el target"));
+        assert!(region(1920, 1080, 1920, 1090)
+            .validate_against_target(&target)
+            .is_err());
+        assert!(region(1920, 1080, 1922, 1082)
+            .validate_against_target(&target)
+            .is_ok());
+    }
+
+    #[test]
+    fn filtro_de_targets_por_tipo_y_proceso() {
+        let monitor = monitor_4k_al_150();
+        let chrome = ventana(Some("chrome.exe"));
+        let sin_proceso = ventana(None);
+
+        let todos = TargetFilter::default();
+        assert!(todos.matches(&monitor) && todos.matches(&sin_proceso));
+
+        let ventanas = TargetFilter {
+            kind: TargetKindFilter::Windows,
+            process_name_contains: None,
+            include_minimized: false,
+        };
+        assert!(!ventanas.matches(&monitor));
+        assert!(ventanas.matches(&chrome));
+
+        let filter: TargetFilter =
+            serde_json::from_str(r#"{ "processNameContains": "CHROME" }"#).unwrap();
+        assert_eq!(filter.kind, TargetKindFilter::All);
+        assert!(filter.matches(&chrome));
+        assert!(!filter.matches(&monitor));
+        assert!(!filter.matches(&sin_proceso));
+
+        let vacio = TargetFilter {
+            kind: TargetKindFilter::Monitors,
+            process_name_contains: Some("  ".to_string()),
+            include_minimized: false,
+        };
+        assert!(vacio.matches(&monitor));
+    }
+
+    #[test]
+    fn ventanas_minimizadas_solo_se_listan_si_se_piden() {
+        let minimizada = CaptureTarget {
+            is_minimized: true,
+            ..ventana(Some("obs64.exe"))
+        };
+        assert!(!TargetFilter::default().matches(&minimizada));
+
+        let filter: TargetFilter =
+            serde_json::from_str(r#"{ "kind": "windows", "includeMinimized": true }"#).unwrap();
+        assert!(filter.matches(&minimizada));
+        assert!(filter.matches(&ventana(Some("obs64.exe"))));
+    }
+}
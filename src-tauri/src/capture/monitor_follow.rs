@@ -0,0 +1,153 @@
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::capture::{provider::WindowMonitorLookup, runtime::TargetSwitcher};
+
+pub const MONITOR_FOLLOW_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonitorFollowEvent {
+    Switched { monitor_id: u32 },
+    SwitchFailed(String),
+    WindowLost,
+}
+
+pub type MonitorFollowSink = Arc<dyn Fn(MonitorFollowEvent) + Send + Sync>;
+
+pub struct MonitorFollower {
+    stop_tx: Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MonitorFollower {
+    pub fn spawn(
+        window_id: u32,
+        monitor_id: u32,
+        interval: Duration,
+        lookup: WindowMonitorLookup,
+        switcher: TargetSwitcher,
+        sink: MonitorFollowSink,
+    ) -> Result<Self, String> {
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let thread = thread::Builder::new()
+            .name("capturist-monitor-follow".into())
+            .spawn(move || {
+                let mut current_monitor = monitor_id;
+                loop {
+                    match stop_rx.recv_timeout(interval) {
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Ok(()) | Err(RecvTimeoutError::Disconnected) => return,
+                    }
+
+                    match lookup(window_id) {
+                        Ok(Some(monitor)) if monitor != current_monitor => {
+                            match switcher(monitor) {
+                                Ok(()) => {
+                                    current_monitor = monitor;
+                                    sink(MonitorFollowEvent::Switched {
+                                        monitor_id: monitor,
+                                    });
+                                }
+                                Err(err) => sink(MonitorFollowEvent::SwitchFailed(err)),
+                            }
+                        }
+                        Ok(Some(_)) => {}
+                        Ok(None) => {
+                            sink(MonitorFollowEvent::WindowLost);
+                            return;
+                        }
+                        Err(err) => eprintln!("[capture] {err}"),
+                    }
+                }
+            })
+            .map_err(|err| format!("No se pudo iniciar el seguimiento de la ventana: {err}"))?;
+
+        Ok(Self {
+            stop_tx,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for MonitorFollower {
+    fn drop(&mut self) {
+        let _ = self.stop_tx.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    fn collecting_sink() -> (MonitorFollowSink, Arc<Mutex<Vec<MonitorFollowEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let collected = Arc::clone(&events);
+        let sink: MonitorFollowSink = Arc::new(move |event| collected.lock().unwrap().push(event));
+        (sink, events)
+    }
+
+    #[test]
+    fn cambia_de_monitor_y_se_detiene_si_la_ventana_desaparece() {
+        let window_monitor = Arc::new(Mutex::new(Some(1_u32)));
+        let lookup_monitor = Arc::clone(&window_monitor);
+        let lookup: WindowMonitorLookup =
+            Arc::new(move |_window_id| Ok(*lookup_monitor.lock().unwrap()));
+        let switched = Arc::new(Mutex::new(Vec::new()));
+        let switched_to = Arc::clone(&switched);
+        let switcher: TargetSwitcher = Arc::new(move |monitor_id| {
+            switched_to.lock().unwrap().push(monitor_id);
+            Ok(())
+        });
+        let (sink, events) = collecting_sink();
+
+        let follower =
+            MonitorFollower::spawn(7, 1, Duration::from_millis(10), lookup, switcher, sink)
+                .unwrap();
+
+        thread::sleep(Duration::from_millis(40));
+        *window_monitor.lock().unwrap() = Some(2);
+        thread::sleep(Duration::from_millis(60));
+        *window_monitor.lock().unwrap() = None;
+        thread::sleep(Duration::from_millis(60));
+        drop(follower);
+
+        assert_eq!(*switched.lock().unwrap(), vec![2]);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                MonitorFollowEvent::Switched { monitor_id: 2 },
+                MonitorFollowEvent::WindowLost,
+            ]
+        );
+    }
+
+    #[test]
+    fn un_cambio_fallido_se_reintenta() {
+        let lookup: WindowMonitorLookup = Arc::new(|_window_id| Ok(Some(2)));
+        let switcher: TargetSwitcher = Arc::new(|_monitor_id| Err("sin monitor".to_string()));
+        let (sink, events) = collecting_sink();
+
+        let follower =
+            MonitorFollower::spawn(7, 1, Duration::from_millis(10), lookup, switcher, sink)
+                .unwrap();
+        thread::sleep(Duration::from_millis(60));
+        drop(follower);
+
+        let events = events.lock().unwrap();
+        assert!(events.len() >= 2);
+        assert!(events
+            .iter()
+            .all(|event| matches!(event, MonitorFollowEvent::SwitchFailed(_))));
+    }
+}
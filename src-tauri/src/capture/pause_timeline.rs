@@ -0,0 +1,88 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Instant,
+};
+
+/// Estado de pausa del runtime. Los timestamps de windows-capture siguen
+/// avanzando mientras se descartan frames en pausa; se acumula ese tiempo para
+/// descontarlo y que el video no quede congelado al reanudar.
+#[derive(Debug, Default)]
+pub struct PauseTimeline {
+    paused: AtomicBool,
+    paused_at: Mutex<Option<Instant>>,
+    paused_total_ms: AtomicU64,
+}
+
+impl PauseTimeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn pause_at(&self, now: Instant) {
+        if let Ok(mut paused_at) = self.paused_at.lock() {
+            paused_at.get_or_insert(now);
+        }
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume_at(&self, now: Instant) {
+        if let Ok(mut paused_at) = self.paused_at.lock() {
+            if let Some(started) = paused_at.take() {
+                let span_ms = now.saturating_duration_since(started).as_millis() as u64;
+                self.paused_total_ms.fetch_add(span_ms, Ordering::Relaxed);
+            }
+        }
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn paused_total_ms(&self) -> u64 {
+        self.paused_total_ms.load(Ordering::Relaxed)
+    }
+
+    pub fn adjust_timestamp_ms(&self, timestamp_ms: u64) -> u64 {
+        timestamp_ms.saturating_sub(self.paused_total_ms())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn descuenta_los_tramos_pausados_del_timestamp() {
+        let timeline = PauseTimeline::new();
+        let start = Instant::now();
+
+        assert_eq!(timeline.adjust_timestamp_ms(1_000), 1_000);
+
+        timeline.pause_at(start);
+        assert!(timeline.is_paused());
+        timeline.resume_at(start + Duration::from_millis(5_000));
+        assert!(!timeline.is_paused());
+        assert_eq!(timeline.adjust_timestamp_ms(7_000), 2_000);
+
+        timeline.pause_at(start + Duration::from_millis(8_000));
+        timeline.pause_at(start + Duration::from_millis(9_000));
+        timeline.resume_at(start + Duration::from_millis(10_000));
+        assert_eq!(timeline.paused_total_ms(), 7_000);
+        assert_eq!(timeline.adjust_timestamp_ms(3_000), 0);
+    }
+
+    #[test]
+    fn reanudar_sin_pausa_no_acumula() {
+        let timeline = PauseTimeline::new();
+        timeline.resume_at(Instant::now());
+        assert_eq!(timeline.paused_total_ms(), 0);
+    }
+}
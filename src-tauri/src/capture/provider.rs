@@ -1,10 +1,16 @@
-use crate::capture::models::CaptureTarget;
+use std::sync::Arc;
+
 #[cfg(any(target_os = "windows", test))]
 use crate::capture::models::TargetKind;
+use crate::capture::{models::CaptureTarget, window_exclusion::WindowHandle};
+use crate::encoder::hdr_metadata::HdrMasteringMetadata;
+
+pub type WindowMonitorLookup = Arc<dyn Fn(u32) -> Result<Option<u32>, String> + Send + Sync>;
 
 pub trait ScreenProvider {
     fn get_targets(&self) -> Result<Vec<CaptureTarget>, String>;
     fn is_supported(&self) -> bool;
+    fn window_monitor_lookup(&self) -> WindowMonitorLookup;
 }
 
 pub struct WindowsCaptureScreenProvider;
@@ -29,6 +35,23 @@ impl ScreenProvider for WindowsCaptureScreenProvider {
     fn is_supported(&self) -> bool {
         platform::is_supported()
     }
+
+    fn window_monitor_lookup(&self) -> WindowMonitorLookup {
+        Arc::new(platform::window_monitor)
+    }
+}
+
+pub fn enumerate_window_handles() -> Result<Vec<WindowHandle>, String> {
+    platform::window_handles()
+}
+
+pub fn display_hdr_metadata(target_id: u32) -> Option<HdrMasteringMetadata> {
+    platform::display_hdr_metadata(target_id)
+}
+
+#[cfg(any(target_os = "windows", test))]
+fn dpi_scale_percent(dpi: u32) -> u32 {
+    (dpi * 100 + 48) / 96
 }
 
 #[cfg(any(target_os = "windows", test))]
@@ -39,12 +62,19 @@ fn kind_rank(kind: &TargetKind) -> u8 {
     }
 }
 
+#[cfg(any(target_os = "windows", test))]
+fn process_sort_key(target: &CaptureTarget) -> (bool, Option<String>) {
+    let process_name = target.process_name.as_deref().map(str::to_lowercase);
+    (process_name.is_none(), process_name)
+}
+
 #[cfg(any(target_os = "windows", test))]
 fn sort_targets(mut targets: Vec<CaptureTarget>) -> Vec<CaptureTarget> {
     targets.sort_by(|left, right| {
         kind_rank(&left.kind)
             .cmp(&kind_rank(&right.kind))
             .then_with(|| right.is_primary.cmp(&left.is_primary))
+            .then_with(|| process_sort_key(left).cmp(&process_sort_key(right)))
             .then_with(|| left.name.to_lowercase().cmp(&right.name.to_lowercase()))
             .then_with(|| left.id.cmp(&right.id))
     });
@@ -153,27 +183,39 @@ fn format_monitor_label(
 mod platform {
     use std::ffi::c_void;
 
-    use windows::Win32::{
-        Foundation::HWND,
-        Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED},
-        UI::WindowsAndMessaging::IsIconic,
+    use windows::{
+        core::Interface,
+        Win32::{
+            Foundation::HWND,
+            Graphics::{
+                Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED},
+                Dxgi::{CreateDXGIFactory1, IDXGIFactory1, IDXGIOutput6},
+            },
+            UI::{
+                HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI},
+                WindowsAndMessaging::{GetWindowPlacement, IsIconic, WINDOWPLACEMENT},
+            },
+        },
     };
     use windows_capture::{monitor::Monitor, window::Window};
     use windows_sys::Win32::{
         Foundation::RECT,
-        Graphics::Gdi::{GetMonitorInfoW, HMONITOR, MONITORINFO},
+        Graphics::Gdi::{
+            GetMonitorInfoW, MonitorFromWindow, HMONITOR, MONITORINFO, MONITOR_DEFAULTTONULL,
+        },
     };
 
     use crate::capture::{
         models::{CaptureTarget, TargetKind},
         provider::{
-            format_monitor_label, resolve_window_label, should_exclude_window_process,
-            should_exclude_window_title, sort_targets,
+            dpi_scale_percent, format_monitor_label, resolve_window_label,
+            should_exclude_window_process, should_exclude_window_title, sort_targets,
         },
+        target_id::{monitor_id, window_target_id},
+        window_exclusion::WindowHandle,
     };
+    use crate::encoder::hdr_metadata::HdrMasteringMetadata;
 
-    const MONITOR_SALT: u64 = 0x045D_9F3B;
-    const WINDOW_SALT: u64 = 0x27D4_EB2D;
     const MONITORINFOF_PRIMARY_FLAG: u32 = 0x0000_0001;
     const MIN_WINDOW_EDGE_PX: u32 = 32;
 
@@ -212,7 +254,7 @@ mod platform {
             let name = format_monitor_label(&friendly_name, display_name.as_deref(), is_primary);
 
             targets.push(CaptureTarget {
-                id: stable_target_id(raw_handle as usize as u64, MONITOR_SALT),
+                id: monitor_id(raw_handle),
                 name,
                 width,
                 height,
@@ -222,6 +264,12 @@ mod platform {
                 screen_height,
                 is_primary,
                 kind: TargetKind::Monitor,
+                process_name: None,
+                process_id: None,
+                window_title: None,
+                refresh_rate_hz: monitor.refresh_rate().ok().filter(|hz| *hz > 1),
+                dpi_scale_percent: monitor_dpi(raw_handle).map(dpi_scale_percent),
+                is_minimized: false,
             });
         }
 
@@ -237,25 +285,35 @@ mod platform {
             }
 
             let process_name = window.process_name().ok();
+            let process_id = window.process_id().ok();
             if let Some(process_name) = process_name.as_deref() {
                 if should_exclude_window_process(process_name) {
                     continue;
                 }
             }
 
-            let rect = match window.rect() {
-                Ok(value) => value,
-                Err(_) => continue,
-            };
-
-            let width = (rect.right - rect.left).max(1) as u32;
-            let height = (rect.bottom - rect.top).max(1) as u32;
-            if width < MIN_WINDOW_EDGE_PX || height < MIN_WINDOW_EDGE_PX {
+            if is_window_cloaked(window.as_raw_hwnd()) {
                 continue;
             }
 
-            if is_window_minimized(window.as_raw_hwnd()) || is_window_cloaked(window.as_raw_hwnd())
-            {
+            // Minimizada, `rect` es el ícono fuera de pantalla; se informa el
+            // tamaño que tendrá al restaurarla.
+            let is_minimized = is_window_minimized(window.as_raw_hwnd());
+            let rect = if is_minimized {
+                restored_window_rect(window.as_raw_hwnd())
+            } else {
+                window
+                    .rect()
+                    .ok()
+                    .map(|rect| (rect.left, rect.top, rect.right, rect.bottom))
+            };
+            let Some((left, top, right, bottom)) = rect else {
+                continue;
+            };
+
+            let width = (right - left).max(1) as u32;
+            let height = (bottom - top).max(1) as u32;
+            if width < MIN_WINDOW_EDGE_PX || height < MIN_WINDOW_EDGE_PX {
                 continue;
             }
 
@@ -264,16 +322,22 @@ mod platform {
             };
 
             targets.push(CaptureTarget {
-                id: stable_target_id(window.as_raw_hwnd() as usize as u64, WINDOW_SALT),
+                id: window_target_id(window.as_raw_hwnd() as usize as u64),
                 name: window_name,
                 width,
                 height,
-                origin_x: rect.left,
-                origin_y: rect.top,
+                origin_x: left,
+                origin_y: top,
                 screen_width: width,
                 screen_height: height,
                 is_primary: false,
                 kind: TargetKind::Window,
+                process_name,
+                process_id,
+                window_title: (!title.is_empty()).then_some(title),
+                refresh_rate_hz: None,
+                dpi_scale_percent: None,
+                is_minimized,
             });
         }
 
@@ -284,16 +348,76 @@ mod platform {
         Ok(sort_targets(targets))
     }
 
-    fn stable_target_id(base: u64, salt: u64) -> u32 {
-        // Mezcla estable sin depender del hasher del proceso.
-        let mut value = base ^ salt;
-        value ^= value >> 33;
-        value = value.wrapping_mul(0xff51_afd7_ed55_8ccd);
-        value ^= value >> 33;
-        value = value.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
-        value ^= value >> 33;
+    pub fn window_monitor(window_id: u32) -> Result<Option<u32>, String> {
+        let windows = Window::enumerate()
+            .map_err(|err| format!("No se pudieron enumerar ventanas: {err}"))?;
+        let Some(window) = windows
+            .into_iter()
+            .find(|window| window_target_id(window.as_raw_hwnd() as usize as u64) == window_id)
+        else {
+            return Ok(None);
+        };
+
+        // SAFETY: consulta Win32 de solo lectura sobre un HWND recién enumerado.
+        let monitor = unsafe { MonitorFromWindow(window.as_raw_hwnd(), MONITOR_DEFAULTTONULL) };
+        if monitor.is_null() {
+            return Ok(None);
+        }
+
+        Ok(Some(monitor_id(monitor)))
+    }
+
+    pub fn window_handles() -> Result<Vec<WindowHandle>, String> {
+        let windows = Window::enumerate()
+            .map_err(|err| format!("No se pudieron enumerar ventanas: {err}"))?;
+        Ok(windows
+            .into_iter()
+            .map(|window| {
+                let hwnd = window.as_raw_hwnd() as usize as u64;
+                WindowHandle {
+                    target_id: window_target_id(hwnd),
+                    hwnd,
+                }
+            })
+            .collect())
+    }
 
-        (value as u32).max(1)
+    pub fn display_hdr_metadata(target_id: u32) -> Option<HdrMasteringMetadata> {
+        let target_monitor_id = window_monitor(target_id)
+            .ok()
+            .flatten()
+            .unwrap_or(target_id);
+        // SAFETY: consultas DXGI de solo lectura; las interfaces se sueltan al salir.
+        let factory = unsafe { CreateDXGIFactory1::<IDXGIFactory1>() }.ok()?;
+        let mut adapter_index = 0;
+        while let Ok(adapter) = unsafe { factory.EnumAdapters1(adapter_index) } {
+            adapter_index += 1;
+            let mut output_index = 0;
+            while let Ok(output) = unsafe { adapter.EnumOutputs(output_index) } {
+                output_index += 1;
+                let Ok(desc) = output
+                    .cast::<IDXGIOutput6>()
+                    .and_then(|output| unsafe { output.GetDesc1() })
+                else {
+                    continue;
+                };
+                if monitor_id(desc.Monitor.0) != target_monitor_id {
+                    continue;
+                }
+                return HdrMasteringMetadata::from_display(
+                    [
+                        desc.RedPrimary,
+                        desc.GreenPrimary,
+                        desc.BluePrimary,
+                        desc.WhitePoint,
+                    ],
+                    desc.MinLuminance,
+                    desc.MaxLuminance,
+                    desc.MaxFullFrameLuminance,
+                );
+            }
+        }
+        None
     }
 
     fn monitor_info(raw_monitor: *mut c_void) -> Result<(i32, i32, u32, u32, bool), String> {
@@ -333,10 +457,36 @@ mod platform {
         ))
     }
 
+    fn monitor_dpi(raw_monitor: *mut c_void) -> Option<u32> {
+        let (mut dpi_x, mut dpi_y) = (0_u32, 0_u32);
+        // SAFETY: consulta Win32 de solo lectura sobre un HMONITOR válido entregado por Windows.
+        unsafe {
+            GetDpiForMonitor(
+                windows::Win32::Graphics::Gdi::HMONITOR(raw_monitor),
+                MDT_EFFECTIVE_DPI,
+                &mut dpi_x,
+                &mut dpi_y,
+            )
+        }
+        .ok()?;
+        (dpi_x > 0).then_some(dpi_x)
+    }
+
     fn is_window_minimized(raw_hwnd: *mut c_void) -> bool {
         unsafe { IsIconic(HWND(raw_hwnd)).as_bool() }
     }
 
+    fn restored_window_rect(raw_hwnd: *mut c_void) -> Option<(i32, i32, i32, i32)> {
+        let mut placement = WINDOWPLACEMENT {
+            length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+            ..Default::default()
+        };
+        // SAFETY: consulta Win32 de solo lectura sobre un HWND recién enumerado.
+        unsafe { GetWindowPlacement(HWND(raw_hwnd), &mut placement) }.ok()?;
+        let rect = placement.rcNormalPosition;
+        Some((rect.left, rect.top, rect.right, rect.bottom))
+    }
+
     fn is_window_cloaked(raw_hwnd: *mut c_void) -> bool {
         let mut cloaked: u32 = 0;
         let result = unsafe {
@@ -354,7 +504,8 @@ mod platform {
 
 #[cfg(not(target_os = "windows"))]
 mod platform {
-    use crate::capture::models::CaptureTarget;
+    use crate::capture::{models::CaptureTarget, window_exclusion::WindowHandle};
+    use crate::encoder::hdr_metadata::HdrMasteringMetadata;
 
     pub fn is_supported() -> bool {
         false
@@ -363,14 +514,26 @@ mod platform {
     pub fn get_targets() -> Result<Vec<CaptureTarget>, String> {
         Err("El backend windows-capture solo está disponible en Windows".to_string())
     }
+
+    pub fn window_monitor(_window_id: u32) -> Result<Option<u32>, String> {
+        Err("El backend windows-capture solo está disponible en Windows".to_string())
+    }
+
+    pub fn window_handles() -> Result<Vec<WindowHandle>, String> {
+        Err("El backend windows-capture solo está disponible en Windows".to_string())
+    }
+
+    pub fn display_hdr_metadata(_target_id: u32) -> Option<HdrMasteringMetadata> {
+        None
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        format_monitor_label, format_process_window_label, normalize_display_device_name,
-        resolve_window_label, should_exclude_window_process, should_exclude_window_title,
-        sort_targets,
+        dpi_scale_percent, format_monitor_label, format_process_window_label,
+        normalize_display_device_name, resolve_window_label, should_exclude_window_process,
+        should_exclude_window_title, sort_targets,
     };
     use crate::capture::models::{CaptureTarget, TargetKind};
 
@@ -388,6 +551,12 @@ mod tests {
                 screen_height: 100,
                 is_primary: false,
                 kind: TargetKind::Window,
+                process_name: None,
+                process_id: None,
+                window_title: None,
+                refresh_rate_hz: None,
+                dpi_scale_percent: None,
+                is_minimized: false,
             },
             CaptureTarget {
                 id: 2,
@@ -400,6 +569,12 @@ mod tests {
                 screen_height: 100,
                 is_primary: false,
                 kind: TargetKind::Monitor,
+                process_name: None,
+                process_id: None,
+                window_title: None,
+                refresh_rate_hz: None,
+                dpi_scale_percent: None,
+                is_minimized: false,
             },
             CaptureTarget {
                 id: 1,
@@ -412,6 +587,12 @@ mod tests {
                 screen_height: 100,
                 is_primary: true,
                 kind: TargetKind::Monitor,
+                process_name: None,
+                process_id: None,
+                window_title: None,
+                refresh_rate_hz: None,
+                dpi_scale_percent: None,
+                is_minimized: false,
             },
         ];
 
@@ -423,6 +604,37 @@ mod tests {
         assert_eq!(sorted[2].kind, TargetKind::Window);
     }
 
+    #[test]
+    fn agrupa_ventanas_del_mismo_proceso() {
+        let window = |id: u32, name: &str, process_name: Option<&str>| CaptureTarget {
+            id,
+            name: name.to_string(),
+            width: 100,
+            height: 100,
+            origin_x: 0,
+            origin_y: 0,
+            screen_width: 100,
+            screen_height: 100,
+            is_primary: false,
+            kind: TargetKind::Window,
+            process_name: process_name.map(str::to_string),
+            process_id: None,
+            window_title: None,
+            refresh_rate_hz: None,
+            dpi_scale_percent: None,
+            is_minimized: false,
+        };
+        let sorted = sort_targets(vec![
+            window(1, "Sin proceso", None),
+            window(2, "YouTube", Some("chrome.exe")),
+            window(3, "Main.rs", Some("Code.exe")),
+            window(4, "Gmail", Some("chrome.exe")),
+        ]);
+
+        let ids: Vec<u32> = sorted.iter().map(|target| target.id).collect();
+        assert_eq!(ids, vec![4, 2, 3, 1]);
+    }
+
     #[test]
     fn filtra_titulos_de_windows_input_experience() {
         assert!(should_exclude_window_title("Windows Input Experience"));
@@ -444,6 +656,15 @@ mod tests {
         assert_eq!(normalize_display_device_name(r"\\.\DISPLAY1"), "DISPLAY1");
     }
 
+    #[test]
+    fn escala_de_windows_desde_el_dpi() {
+        assert_eq!(dpi_scale_percent(96), 100);
+        assert_eq!(dpi_scale_percent(120), 125);
+        assert_eq!(dpi_scale_percent(144), 150);
+        assert_eq!(dpi_scale_percent(168), 175);
+        assert_eq!(dpi_scale_percent(192), 200);
+    }
+
     #[test]
     fn etiqueta_monitor_principal_incluye_display() {
         let label = format_monitor_label("Generic Monitor", Some(r"\\.\DISPLAY1"), true);
@@ -0,0 +1,251 @@
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    capture::idle_pause::IdleTracker, encoder::encoded_duration::get_live_encoded_duration_ms,
+};
+
+pub const EVENT_RECORDING_TICK: &str = "recording-tick";
+pub const RECORDING_TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingTick {
+    pub elapsed_ms: u64,
+    pub encoded_duration_ms: u64,
+}
+
+pub type RecordingTickSink = Arc<dyn Fn(RecordingTick) + Send + Sync>;
+
+#[derive(Debug, Default)]
+struct ClockState {
+    elapsed_before_pause_ms: u64,
+    last_resume_at: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RecordingClock {
+    state: Arc<Mutex<ClockState>>,
+    idle: Option<Arc<IdleTracker>>,
+}
+
+impl RecordingClock {
+    pub fn new(idle: Option<Arc<IdleTracker>>) -> Self {
+        Self {
+            idle,
+            ..Self::default()
+        }
+    }
+
+    pub fn start_running(idle: Option<Arc<IdleTracker>>) -> Self {
+        let clock = Self::new(idle);
+        clock.resume();
+        clock
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ClockState> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    pub fn pause(&self) {
+        let now = Instant::now();
+        let mut state = self.lock();
+        if let Some(since) = state.last_resume_at.take() {
+            state.elapsed_before_pause_ms +=
+                now.saturating_duration_since(since).as_millis() as u64;
+        }
+        if let Some(idle) = &self.idle {
+            idle.suspend(now);
+        }
+    }
+
+    pub fn resume(&self) {
+        let now = Instant::now();
+        let mut state = self.lock();
+        if state.last_resume_at.is_none() {
+            state.last_resume_at = Some(now);
+            if let Some(idle) = &self.idle {
+                idle.resume(now);
+            }
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.lock().last_resume_at.is_some()
+    }
+
+    pub fn is_auto_paused(&self) -> bool {
+        self.is_running()
+            && self
+                .idle
+                .as_ref()
+                .is_some_and(|idle| idle.is_idle(Instant::now()))
+    }
+
+    pub fn elapsed_ms(&self) -> u64 {
+        let now = Instant::now();
+        let state = self.lock();
+        let running_ms = state
+            .last_resume_at
+            .map(|since| now.saturating_duration_since(since).as_millis() as u64)
+            .unwrap_or(0);
+        let idle_ms = self
+            .idle
+            .as_ref()
+            .map(|idle| idle.idle_ms(now))
+            .unwrap_or(0);
+        (state.elapsed_before_pause_ms + running_ms).saturating_sub(idle_ms)
+    }
+}
+
+/// Hilo que emite `RecordingTick` cada `interval` mientras el reloj corre.
+/// Los instantes se calculan desde el arranque y no desde el tick anterior,
+/// así la latencia de cada emisión no se acumula. Se detiene al soltarlo.
+pub struct RecordingTicker {
+    stop_tx: Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RecordingTicker {
+    pub fn spawn(
+        clock: RecordingClock,
+        interval: Duration,
+        sink: RecordingTickSink,
+    ) -> Result<Self, String> {
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let thread = thread::Builder::new()
+            .name("capturist-recording-tick".into())
+            .spawn(move || {
+                let started_at = Instant::now();
+                let mut ticks: u32 = 0;
+                loop {
+                    ticks += 1;
+                    let mut next_tick_at = started_at + interval * ticks;
+                    let now = Instant::now();
+                    // Si el hilo se atrasó más de un intervalo se saltan los
+                    // ticks perdidos en lugar de emitirlos en ráfaga.
+                    if next_tick_at + interval <= now {
+                        let behind = (now - started_at).as_nanos() / interval.as_nanos().max(1);
+                        ticks = behind as u32 + 1;
+                        next_tick_at = started_at + interval * ticks;
+                    }
+
+                    match stop_rx.recv_timeout(next_tick_at.saturating_duration_since(now)) {
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Ok(()) | Err(RecvTimeoutError::Disconnected) => return,
+                    }
+
+                    if clock.is_running() && !clock.is_auto_paused() {
+                        sink(RecordingTick {
+                            elapsed_ms: clock.elapsed_ms(),
+                            encoded_duration_ms: get_live_encoded_duration_ms(),
+                        });
+                    }
+                }
+            })
+            .map_err(|err| format!("No se pudo iniciar el reloj de grabación: {err}"))?;
+
+        Ok(Self {
+            stop_tx,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for RecordingTicker {
+    fn drop(&mut self) {
+        let _ = self.stop_tx.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collecting_sink() -> (RecordingTickSink, Arc<Mutex<Vec<RecordingTick>>>) {
+        let ticks = Arc::new(Mutex::new(Vec::new()));
+        let collected = Arc::clone(&ticks);
+        let sink: RecordingTickSink = Arc::new(move |tick| collected.lock().unwrap().push(tick));
+        (sink, ticks)
+    }
+
+    #[test]
+    fn reloj_no_cuenta_el_tiempo_en_pausa() {
+        let clock = RecordingClock::start_running(None);
+        thread::sleep(Duration::from_millis(30));
+        clock.pause();
+        let paused_at_ms = clock.elapsed_ms();
+        assert!(paused_at_ms >= 30);
+
+        thread::sleep(Duration::from_millis(30));
+        assert_eq!(clock.elapsed_ms(), paused_at_ms);
+        assert!(!clock.is_running());
+
+        clock.resume();
+        thread::sleep(Duration::from_millis(10));
+        assert!(clock.elapsed_ms() >= paused_at_ms + 10);
+    }
+
+    #[test]
+    fn reloj_descuenta_la_pausa_automatica() {
+        let idle = Arc::new(IdleTracker::new(Duration::from_millis(20), Instant::now()));
+        let clock = RecordingClock::start_running(Some(Arc::clone(&idle)));
+
+        thread::sleep(Duration::from_millis(100));
+        assert!(clock.is_auto_paused());
+        assert!(clock.elapsed_ms() < 60, "{} ms", clock.elapsed_ms());
+    }
+
+    #[test]
+    fn ticker_emite_con_cadencia_y_valores_crecientes() {
+        let (sink, ticks) = collecting_sink();
+        let ticker = RecordingTicker::spawn(
+            RecordingClock::start_running(None),
+            Duration::from_millis(20),
+            sink,
+        )
+        .unwrap();
+
+        thread::sleep(Duration::from_millis(210));
+        drop(ticker);
+
+        let ticks = ticks.lock().unwrap();
+        assert!((7..=11).contains(&ticks.len()), "{} ticks", ticks.len());
+        assert!(ticks
+            .windows(2)
+            .all(|pair| pair[0].elapsed_ms <= pair[1].elapsed_ms));
+    }
+
+    #[test]
+    fn ticker_no_emite_en_pausa_ni_tras_soltarlo() {
+        let (sink, ticks) = collecting_sink();
+        let clock = RecordingClock::start_running(None);
+        clock.pause();
+        let ticker =
+            RecordingTicker::spawn(clock.clone(), Duration::from_millis(10), sink).unwrap();
+
+        thread::sleep(Duration::from_millis(80));
+        assert!(ticks.lock().unwrap().is_empty());
+
+        clock.resume();
+        thread::sleep(Duration::from_millis(60));
+        drop(ticker);
+        let emitted = ticks.lock().unwrap().len();
+        assert!(emitted > 0);
+
+        thread::sleep(Duration::from_millis(40));
+        assert_eq!(ticks.lock().unwrap().len(), emitted);
+    }
+}
@@ -1,20 +1,40 @@
-use std::sync::Arc;
-
-use crate::capture::models::{RawFrame, Region};
+use std::{
+    sync::{atomic::AtomicU64, Arc},
+    time::Duration,
+};
+
+use crate::{
+    capture::{
+        idle_pause::IdleTracker,
+        models::{RawFrame, Region},
+    },
+    encoder::gpu_scaler::GpuFrameRoute,
+};
 
 pub type FrameArrivedCallback = Arc<dyn Fn(RawFrame) -> Result<(), String> + Send + Sync>;
 pub type SessionFinishedCallback = Arc<dyn Fn() -> Result<(), String> + Send + Sync>;
 pub type ShouldAcceptFrameCallback = Arc<dyn Fn() -> Result<bool, String> + Send + Sync>;
 pub type FrameDroppedCallback = Arc<dyn Fn() + Send + Sync>;
+pub type FramePacedCallback = Arc<dyn Fn() + Send + Sync>;
+pub type TargetSwitcher = Arc<dyn Fn(u32) -> Result<(), String> + Send + Sync>;
+
+const SINGLE_FRAME_TIMEOUT: Duration = Duration::from_secs(2);
 
 #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
 pub struct RuntimeStartConfig {
     pub target_id: u32,
+    pub secondary_target_id: Option<u32>,
     pub fps: u32,
     pub crop_region: Option<Region>,
-    pub prefer_gpu_frames: bool,
+    pub gpu_frames: GpuFrameRoute,
+    pub hdr: bool,
+    pub capture_cursor: bool,
+    pub include_secondary_windows: Option<bool>,
+    pub restore_if_minimized: bool,
+    pub idle_tracker: Option<Arc<IdleTracker>>,
     pub should_accept_frame: ShouldAcceptFrameCallback,
     pub on_frame_dropped: FrameDroppedCallback,
+    pub on_frame_paced: FramePacedCallback,
     pub on_frame_arrived: FrameArrivedCallback,
     pub on_session_finished: SessionFinishedCallback,
 }
@@ -25,27 +45,60 @@ pub trait CaptureRuntimeHandle: Send {
     fn is_finished(&self) -> bool;
     fn stop(self: Box<Self>) -> Result<u64, String>;
     fn wait(self: Box<Self>) -> Result<u64, String>;
+
+    fn target_switcher(&self) -> Option<TargetSwitcher> {
+        None
+    }
+
+    fn frame_counter(&self) -> Option<Arc<AtomicU64>> {
+        None
+    }
+
+    fn set_target_fps(&self, _fps: u32) -> Result<(), String> {
+        Err("La captura no permite cambiar los FPS en vivo".to_string())
+    }
 }
 
 pub fn start_runtime(config: RuntimeStartConfig) -> Result<Box<dyn CaptureRuntimeHandle>, String> {
     platform::start_runtime(config)
 }
 
+pub fn capture_single_frame(
+    target_id: u32,
+    crop_region: Option<Region>,
+) -> Result<RawFrame, String> {
+    platform::capture_single_frame(target_id, crop_region, SINGLE_FRAME_TIMEOUT)
+}
+
+pub fn capture_single_frame_within(
+    target_id: u32,
+    crop_region: Option<Region>,
+    timeout: Duration,
+) -> Result<RawFrame, String> {
+    platform::capture_single_frame(target_id, crop_region, timeout)
+}
+
 #[cfg(target_os = "windows")]
 mod platform {
     use std::{
         sync::{
-            atomic::{AtomicBool, AtomicU64, Ordering},
-            Arc,
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex, MutexGuard,
         },
-        time::Duration,
+        time::{Duration, Instant},
     };
 
-    use windows::core::Interface;
+    use windows::{
+        core::Interface,
+        Win32::{
+            Foundation::HWND,
+            UI::WindowsAndMessaging::{IsIconic, ShowWindow, SW_RESTORE},
+        },
+    };
     use windows_capture::{
         capture::{CaptureControl, Context, GraphicsCaptureApiHandler},
         frame::Frame,
-        graphics_capture_api::InternalCaptureControl,
+        graphics_capture_api::{GraphicsCaptureApi, InternalCaptureControl},
         monitor::Monitor,
         settings::{
             ColorFormat, CursorCaptureSettings, DirtyRegionSettings, DrawBorderSettings,
@@ -55,75 +108,201 @@ mod platform {
     };
 
     use crate::capture::{
-        models::{RawFrame, Region},
+        frame_pacing::FramePacingGate,
+        idle_pause::{frame_fingerprint, FrameActivity, IdleTracker},
+        models::{FramePixelFormat, RawFrame, Region, StopReason},
+        pause_timeline::PauseTimeline,
         runtime::{
-            CaptureRuntimeHandle, FrameArrivedCallback, FrameDroppedCallback, RuntimeStartConfig,
-            SessionFinishedCallback, ShouldAcceptFrameCallback,
+            CaptureRuntimeHandle, FrameArrivedCallback, FrameDroppedCallback, FramePacedCallback,
+            RuntimeStartConfig, SessionFinishedCallback, ShouldAcceptFrameCallback, TargetSwitcher,
         },
+        session_stop::{is_session_stop_requested, request_session_stop},
+        side_by_side::{compose_side_by_side, MonitorSide, SideBySideFrames},
+        target_id::{monitor_id, window_target_id},
     };
-
-    const MONITOR_SALT: u64 = 0x045D_9F3B;
-    const WINDOW_SALT: u64 = 0x27D4_EB2D;
+    use crate::encoder::gpu_scaler::{prepare_capture_device, GpuFrameRoute};
 
     pub fn start_runtime(
         config: RuntimeStartConfig,
     ) -> Result<Box<dyn CaptureRuntimeHandle>, String> {
-        let paused = Arc::new(AtomicBool::new(false));
+        let pause_timeline = Arc::new(PauseTimeline::new());
         let frame_counter = Arc::new(AtomicU64::new(0));
 
         let flags = HandlerFlags {
-            paused: paused.clone(),
+            pause_timeline: pause_timeline.clone(),
             frame_counter: frame_counter.clone(),
             crop_region: config.crop_region,
-            prefer_gpu_frames: config.prefer_gpu_frames,
+            gpu_frames: config.gpu_frames,
+            hdr: config.hdr,
+            capture_cursor: config.capture_cursor,
+            include_secondary_windows: config.include_secondary_windows,
+            restore_if_minimized: config.restore_if_minimized,
+            idle_tracker: config.idle_tracker.clone(),
+            frame_pacing: Arc::new(FramePacingGate::new(config.fps)),
             should_accept_frame: config.should_accept_frame,
             on_frame_dropped: config.on_frame_dropped,
+            on_frame_paced: config.on_frame_paced,
             on_frame_arrived: config.on_frame_arrived,
+            side_by_side: config
+                .secondary_target_id
+                .map(|_| Arc::new(SideBySideFrames::default())),
+        };
+
+        let secondary_control = match config.secondary_target_id {
+            Some(target_id) => Some(start_secondary_capture(
+                target_id,
+                config.fps,
+                flags.clone(),
+            )?),
+            None => None,
+        };
+        let control = match start_capture(config.target_id, config.fps, flags.clone()) {
+            Ok(control) => control,
+            Err(err) => {
+                if let Some(secondary) = secondary_control {
+                    let _ = secondary.stop();
+                }
+                return Err(err);
+            }
         };
 
-        let min_update_interval_ms = ((1000_u64) / (config.fps.max(1) as u64)).max(1);
+        Ok(Box::new(WindowsCaptureRuntime {
+            control: Arc::new(Mutex::new(Some(control))),
+            secondary_control: Mutex::new(secondary_control),
+            pause_timeline,
+            idle_tracker: config.idle_tracker,
+            frame_counter,
+            fps: config.fps,
+            flags,
+            on_session_finished: Some(config.on_session_finished),
+        }))
+    }
+
+    type LiveCaptureControl = CaptureControl<LiveCaptureHandler, String>;
+
+    fn start_capture(
+        target_id: u32,
+        fps: u32,
+        flags: HandlerFlags,
+    ) -> Result<LiveCaptureControl, String> {
+        if !flags.hdr {
+            return start_capture_as(target_id, fps, ColorFormat::Bgra8, flags);
+        }
+        // El consumer también convierte BGRA a PQ, así que se sigue grabando.
+        start_capture_as(target_id, fps, ColorFormat::Rgba16F, flags.clone()).or_else(|err| {
+            eprintln!("[capture] La captura en half float no está disponible ({err}); se captura en 8 bits");
+            start_capture_as(target_id, fps, ColorFormat::Bgra8, flags)
+        })
+    }
+
+    fn start_capture_as(
+        target_id: u32,
+        fps: u32,
+        color_format: ColorFormat,
+        flags: HandlerFlags,
+    ) -> Result<LiveCaptureControl, String> {
+        let min_update_interval_ms = ((1000_u64) / (fps.max(1) as u64)).max(1);
         let min_update_interval =
             MinimumUpdateIntervalSettings::Custom(Duration::from_millis(min_update_interval_ms));
+        let cursor = if flags.capture_cursor {
+            CursorCaptureSettings::WithCursor
+        } else {
+            CursorCaptureSettings::WithoutCursor
+        };
+        let secondary_windows = secondary_window_settings(flags.include_secondary_windows)?;
 
-        let control = match resolve_capture_item(config.target_id)? {
+        match resolve_capture_item(target_id)? {
             CaptureItem::Monitor(monitor) => {
                 let settings = Settings::new(
                     monitor,
-                    CursorCaptureSettings::WithCursor,
+                    cursor,
                     DrawBorderSettings::Default,
-                    SecondaryWindowSettings::Default,
+                    secondary_windows,
                     min_update_interval,
                     DirtyRegionSettings::Default,
-                    ColorFormat::Bgra8,
+                    color_format,
                     flags,
                 );
 
                 LiveCaptureHandler::start_free_threaded(settings)
-                    .map_err(|err| format!("No se pudo iniciar captura en monitor: {err}"))?
+                    .map_err(|err| format!("No se pudo iniciar captura en monitor: {err}"))
             }
             CaptureItem::Window(window) => {
+                let hwnd = HWND(window.as_raw_hwnd());
+                // SAFETY: consulta Win32 sobre un HWND recién enumerado.
+                let mut minimized = unsafe { IsIconic(hwnd).as_bool() };
+                if minimized && flags.restore_if_minimized {
+                    // SAFETY: mismo HWND; restaurar no invalida el capture item.
+                    let _ = unsafe { ShowWindow(hwnd, SW_RESTORE) };
+                    minimized = false;
+                }
                 let settings = Settings::new(
                     window,
-                    CursorCaptureSettings::WithCursor,
+                    cursor,
                     DrawBorderSettings::Default,
-                    SecondaryWindowSettings::Default,
+                    secondary_windows,
                     min_update_interval,
                     DirtyRegionSettings::Default,
-                    ColorFormat::Bgra8,
+                    color_format,
                     flags,
                 );
 
-                LiveCaptureHandler::start_free_threaded(settings)
-                    .map_err(|err| format!("No se pudo iniciar captura en ventana: {err}"))?
+                LiveCaptureHandler::start_free_threaded(settings).map_err(|err| {
+                    if minimized {
+                        format!(
+                            "No se pudo iniciar captura en la ventana minimizada: {err}. Windows no siempre puede capturar ventanas minimizadas; restáurala o usa restoreIfMinimized"
+                        )
+                    } else {
+                        format!("No se pudo iniciar captura en ventana: {err}")
+                    }
+                })
             }
+        }
+    }
+
+    /// Windows 10 no tiene la opción: elegir algo explícito ahí falla con un
+    /// mensaje claro en vez del error en inglés de windows-capture.
+    fn secondary_window_settings(include: Option<bool>) -> Result<SecondaryWindowSettings, String> {
+        let Some(include) = include else {
+            return Ok(SecondaryWindowSettings::Default);
         };
+        if !GraphicsCaptureApi::is_secondary_windows_supported().unwrap_or(false) {
+            return Err(
+                "Esta versión de Windows no permite elegir si se graban los popups \
+                 de la ventana; quita includeSecondaryWindows"
+                    .to_string(),
+            );
+        }
+        Ok(if include {
+            SecondaryWindowSettings::Include
+        } else {
+            SecondaryWindowSettings::Exclude
+        })
+    }
 
-        Ok(Box::new(WindowsCaptureRuntime {
-            control: Some(control),
-            paused,
-            frame_counter,
-            on_session_finished: Some(config.on_session_finished),
-        }))
+    type SecondaryCaptureControl = CaptureControl<SecondaryCaptureHandler, String>;
+
+    fn start_secondary_capture(
+        target_id: u32,
+        fps: u32,
+        flags: HandlerFlags,
+    ) -> Result<SecondaryCaptureControl, String> {
+        let CaptureItem::Monitor(monitor) = resolve_capture_item(target_id)? else {
+            return Err("El segundo target de la grabación debe ser un monitor".to_string());
+        };
+        let min_update_interval_ms = ((1000_u64) / (fps.max(1) as u64)).max(1);
+        let settings = Settings::new(
+            monitor,
+            CursorCaptureSettings::WithCursor,
+            DrawBorderSettings::Default,
+            SecondaryWindowSettings::Default,
+            MinimumUpdateIntervalSettings::Custom(Duration::from_millis(min_update_interval_ms)),
+            DirtyRegionSettings::Default,
+            ColorFormat::Bgra8,
+            flags,
+        );
+        SecondaryCaptureHandler::start_free_threaded(settings)
+            .map_err(|err| format!("No se pudo iniciar captura en el segundo monitor: {err}"))
     }
 
     enum CaptureItem {
@@ -135,9 +314,7 @@ mod platform {
         let monitors = Monitor::enumerate()
             .map_err(|err| format!("No se pudieron enumerar monitores: {err}"))?;
         for monitor in monitors {
-            let stable_id =
-                stable_target_id(monitor.as_raw_hmonitor() as usize as u64, MONITOR_SALT);
-            if stable_id == target_id {
+            if monitor_id(monitor.as_raw_hmonitor()) == target_id {
                 return Ok(CaptureItem::Monitor(monitor));
             }
         }
@@ -145,8 +322,7 @@ mod platform {
         let windows = Window::enumerate()
             .map_err(|err| format!("No se pudieron enumerar ventanas: {err}"))?;
         for window in windows {
-            let stable_id = stable_target_id(window.as_raw_hwnd() as usize as u64, WINDOW_SALT);
-            if stable_id == target_id {
+            if window_target_id(window.as_raw_hwnd() as usize as u64) == target_id {
                 return Ok(CaptureItem::Window(window));
             }
         }
@@ -157,25 +333,76 @@ mod platform {
         ))
     }
 
-    fn stable_target_id(base: u64, salt: u64) -> u32 {
-        let mut value = base ^ salt;
-        value ^= value >> 33;
-        value = value.wrapping_mul(0xff51_afd7_ed55_8ccd);
-        value ^= value >> 33;
-        value = value.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
-        value ^= value >> 33;
-
-        (value as u32).max(1)
-    }
-
+    #[derive(Clone)]
     struct HandlerFlags {
-        paused: Arc<AtomicBool>,
+        pause_timeline: Arc<PauseTimeline>,
         frame_counter: Arc<AtomicU64>,
         crop_region: Option<Region>,
-        prefer_gpu_frames: bool,
+        gpu_frames: GpuFrameRoute,
+        hdr: bool,
+        capture_cursor: bool,
+        include_secondary_windows: Option<bool>,
+        restore_if_minimized: bool,
+        idle_tracker: Option<Arc<IdleTracker>>,
+        frame_pacing: Arc<FramePacingGate>,
         should_accept_frame: ShouldAcceptFrameCallback,
         on_frame_dropped: FrameDroppedCallback,
+        on_frame_paced: FramePacedCallback,
         on_frame_arrived: FrameArrivedCallback,
+        side_by_side: Option<Arc<SideBySideFrames>>,
+    }
+
+    impl HandlerFlags {
+        fn observe_activity(&self, fingerprint: Option<u64>) -> bool {
+            let Some(idle_tracker) = &self.idle_tracker else {
+                return true;
+            };
+
+            let now = Instant::now();
+            match idle_tracker.observe_frame(now, fingerprint) {
+                FrameActivity::Idle => false,
+                FrameActivity::Active { resumed_from } => {
+                    if let Some(idle_since) = resumed_from {
+                        self.pause_timeline.pause_at(idle_since);
+                        self.pause_timeline.resume_at(now);
+                    }
+                    true
+                }
+            }
+        }
+
+        fn push_side_by_side(
+            &self,
+            frames: &SideBySideFrames,
+            side: MonitorSide,
+            frame: RawFrame,
+            raw_timestamp_ms: u64,
+        ) -> Result<(), String> {
+            let emitted = frames.push(side, frame, |left, right| {
+                if !self.frame_pacing.admit(raw_timestamp_ms) {
+                    (self.on_frame_paced)();
+                    return Ok(());
+                }
+                let should_accept_frame = (self.should_accept_frame)()
+                    .map_err(|err| format!("Error validando backpressure del encoder: {err}"))?;
+                if !should_accept_frame {
+                    (self.on_frame_dropped)();
+                    return Ok(());
+                }
+                (self.on_frame_arrived)(compose_side_by_side(left, right)?)
+                    .map_err(|err| format!("Error procesando frame en encoder: {err}"))?;
+                self.frame_counter.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            });
+            emitted.unwrap_or(Ok(()))
+        }
+
+        fn read_side_frame(&self, frame: &mut Frame) -> Result<(RawFrame, u64), String> {
+            let mut raw_frame = read_cpu_frame(frame, None)?;
+            let raw_timestamp_ms = raw_frame.timestamp_ms;
+            raw_frame.timestamp_ms = self.pause_timeline.adjust_timestamp_ms(raw_timestamp_ms);
+            Ok((raw_frame, raw_timestamp_ms))
+        }
     }
 
     struct LiveCaptureHandler {
@@ -187,21 +414,49 @@ mod platform {
         type Error = String;
 
         fn new(ctx: Context<Self::Flags>) -> Result<Self, Self::Error> {
+            prepare_capture_device(&ctx.device, &ctx.flags.gpu_frames);
             Ok(Self { flags: ctx.flags })
         }
 
         fn on_frame_arrived(
             &mut self,
             frame: &mut Frame,
-            _capture_control: InternalCaptureControl,
+            capture_control: InternalCaptureControl,
         ) -> Result<(), Self::Error> {
-            if self.flags.paused.load(Ordering::Relaxed) {
+            // El pipeline pidió cerrar la sesión (p. ej. disco lleno): se corta
+            // la captura sin error para que el encoder finalice el archivo.
+            if is_session_stop_requested() {
+                capture_control.stop();
                 return Ok(());
             }
 
+            if self.flags.pause_timeline.is_paused() {
+                return Ok(());
+            }
+
+            if let Some(side_by_side) = &self.flags.side_by_side {
+                let (left, raw_timestamp_ms) = self.flags.read_side_frame(frame)?;
+                return self.flags.push_side_by_side(
+                    side_by_side,
+                    MonitorSide::Left,
+                    left,
+                    raw_timestamp_ms,
+                );
+            }
+
             let frame_width = frame.width();
             let frame_height = frame.height();
-            let timestamp_ms = frame_timestamp_ms(frame);
+            let pixel_format = match frame.color_format() {
+                ColorFormat::Rgba16F => FramePixelFormat::Rgba16F,
+                _ => FramePixelFormat::Bgra8,
+            };
+            let frame_timestamp_ms = frame_timestamp_ms(frame);
+            // Algunos drivers ignoran el intervalo mínimo pedido: se descarta
+            // antes de convertir lo que el encoder no va a usar.
+            if !self.flags.frame_pacing.admit(frame_timestamp_ms) {
+                (self.flags.on_frame_paced)();
+                return Ok(());
+            }
             let should_accept_frame = (self.flags.should_accept_frame)()
                 .map_err(|err| format!("Error validando backpressure del encoder: {err}"))?;
             if !should_accept_frame {
@@ -210,8 +465,15 @@ mod platform {
             }
 
             let should_use_gpu_surface =
-                self.flags.prefer_gpu_frames && self.flags.crop_region.is_none();
+                self.flags.gpu_frames.is_gpu() && self.flags.crop_region.is_none();
             if should_use_gpu_surface {
+                if !self.flags.observe_activity(None) {
+                    return Ok(());
+                }
+                let timestamp_ms = self
+                    .flags
+                    .pause_timeline
+                    .adjust_timestamp_ms(frame_timestamp_ms);
                 let texture_ptr = clone_frame_texture_ptr(frame)?;
                 let raw_frame = RawFrame::from_gpu_texture(
                     frame_width,
@@ -228,7 +490,7 @@ mod platform {
 
             let mut frame_buffer = if let Some(region) = &self.flags.crop_region {
                 let (start_x, start_y, end_x, end_y) =
-                    clamp_crop_region(region, frame_width, frame_height)?;
+                    region.clamp_to_frame(frame_width, frame_height)?;
                 frame
                     .buffer_crop(start_x, start_y, end_x, end_y)
                     .map_err(|err| format!("Error extrayendo frame recortado: {err}"))?
@@ -248,13 +510,27 @@ mod platform {
                 return Err("Se recibió un frame vacío desde windows-capture".to_string());
             }
 
+            let fingerprint = self
+                .flags
+                .idle_tracker
+                .as_ref()
+                .map(|_| frame_fingerprint(bytes, row_stride_bytes as usize));
+            if !self.flags.observe_activity(fingerprint) {
+                return Ok(());
+            }
+            let timestamp_ms = self
+                .flags
+                .pause_timeline
+                .adjust_timestamp_ms(frame_timestamp_ms);
+
             let raw_frame = RawFrame::new(
                 bytes.to_vec(),
                 width,
                 height,
                 row_stride_bytes,
                 timestamp_ms,
-            );
+            )
+            .with_pixel_format(pixel_format);
             (self.flags.on_frame_arrived)(raw_frame)
                 .map_err(|err| format!("Error procesando frame en encoder: {err}"))?;
 
@@ -262,11 +538,165 @@ mod platform {
             Ok(())
         }
 
+        /// Windows cierra el item al desconectar el monitor o cerrar la
+        /// ventana; el manager ve la sesión terminada y finaliza el encoder
+        /// igual, así el archivo queda reproducible.
+        fn on_closed(&mut self) -> Result<(), Self::Error> {
+            request_session_stop(StopReason::SourceLost);
+            Ok(())
+        }
+    }
+
+    struct SecondaryCaptureHandler {
+        flags: HandlerFlags,
+    }
+
+    impl GraphicsCaptureApiHandler for SecondaryCaptureHandler {
+        type Flags = HandlerFlags;
+        type Error = String;
+
+        fn new(ctx: Context<Self::Flags>) -> Result<Self, Self::Error> {
+            Ok(Self { flags: ctx.flags })
+        }
+
+        fn on_frame_arrived(
+            &mut self,
+            frame: &mut Frame,
+            _capture_control: InternalCaptureControl,
+        ) -> Result<(), Self::Error> {
+            let Some(side_by_side) = &self.flags.side_by_side else {
+                return Ok(());
+            };
+            if is_session_stop_requested() || self.flags.pause_timeline.is_paused() {
+                return Ok(());
+            }
+            let (right, raw_timestamp_ms) = self.flags.read_side_frame(frame)?;
+            self.flags
+                .push_side_by_side(side_by_side, MonitorSide::Right, right, raw_timestamp_ms)
+        }
+
         fn on_closed(&mut self) -> Result<(), Self::Error> {
+            request_session_stop(StopReason::SourceLost);
+            Ok(())
+        }
+    }
+
+    type SingleFrameSlot = Arc<Mutex<Option<Result<RawFrame, String>>>>;
+
+    #[derive(Clone)]
+    struct SingleFrameFlags {
+        crop_region: Option<Region>,
+        slot: SingleFrameSlot,
+    }
+
+    struct SingleFrameHandler {
+        flags: SingleFrameFlags,
+    }
+
+    impl GraphicsCaptureApiHandler for SingleFrameHandler {
+        type Flags = SingleFrameFlags;
+        type Error = String;
+
+        fn new(ctx: Context<Self::Flags>) -> Result<Self, Self::Error> {
+            Ok(Self { flags: ctx.flags })
+        }
+
+        fn on_frame_arrived(
+            &mut self,
+            frame: &mut Frame,
+            capture_control: InternalCaptureControl,
+        ) -> Result<(), Self::Error> {
+            let result = read_cpu_frame(frame, self.flags.crop_region.as_ref());
+            if let Ok(mut slot) = self.flags.slot.lock() {
+                slot.get_or_insert(result);
+            }
+            capture_control.stop();
             Ok(())
         }
     }
 
+    pub fn capture_single_frame(
+        target_id: u32,
+        crop_region: Option<Region>,
+        timeout: Duration,
+    ) -> Result<RawFrame, String> {
+        let slot: SingleFrameSlot = Arc::new(Mutex::new(None));
+        let flags = SingleFrameFlags {
+            crop_region,
+            slot: Arc::clone(&slot),
+        };
+
+        let control = match resolve_capture_item(target_id)? {
+            CaptureItem::Monitor(monitor) => {
+                let settings = Settings::new(
+                    monitor,
+                    CursorCaptureSettings::WithCursor,
+                    DrawBorderSettings::Default,
+                    SecondaryWindowSettings::Default,
+                    MinimumUpdateIntervalSettings::Default,
+                    DirtyRegionSettings::Default,
+                    ColorFormat::Bgra8,
+                    flags,
+                );
+                SingleFrameHandler::start_free_threaded(settings)
+            }
+            CaptureItem::Window(window) => {
+                let settings = Settings::new(
+                    window,
+                    CursorCaptureSettings::WithCursor,
+                    DrawBorderSettings::Default,
+                    SecondaryWindowSettings::Default,
+                    MinimumUpdateIntervalSettings::Default,
+                    DirtyRegionSettings::Default,
+                    ColorFormat::Bgra8,
+                    flags,
+                );
+                SingleFrameHandler::start_free_threaded(settings)
+            }
+        }
+        .map_err(|err| format!("No se pudo capturar el frame actual: {err}"))?;
+
+        let deadline = Instant::now() + timeout;
+        while !control.is_finished() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let _ = control.stop();
+
+        let captured = slot.lock().ok().and_then(|mut slot| slot.take());
+        captured.unwrap_or_else(|| Err("La captura no entregó ningún frame a tiempo".to_string()))
+    }
+
+    fn read_cpu_frame(frame: &mut Frame, crop_region: Option<&Region>) -> Result<RawFrame, String> {
+        let timestamp_ms = frame_timestamp_ms(frame);
+        let mut frame_buffer = match crop_region {
+            Some(region) => {
+                let (start_x, start_y, end_x, end_y) =
+                    region.clamp_to_frame(frame.width(), frame.height())?;
+                frame
+                    .buffer_crop(start_x, start_y, end_x, end_y)
+                    .map_err(|err| format!("Error extrayendo frame recortado: {err}"))?
+            }
+            None => frame
+                .buffer()
+                .map_err(|err| format!("Error extrayendo frame de captura: {err}"))?,
+        };
+
+        let width = frame_buffer.width();
+        let height = frame_buffer.height();
+        let row_stride_bytes = frame_buffer.row_pitch();
+        let bytes = frame_buffer.as_raw_buffer();
+        if bytes.is_empty() {
+            return Err("Se recibió un frame vacío desde windows-capture".to_string());
+        }
+        Ok(RawFrame::new(
+            bytes.to_vec(),
+            width,
+            height,
+            row_stride_bytes,
+            timestamp_ms,
+        ))
+    }
+
     fn frame_timestamp_ms(frame: &Frame) -> u64 {
         let raw_duration_100ns = frame.timestamp().Duration;
         if raw_duration_100ns <= 0 {
@@ -288,39 +718,62 @@ mod platform {
         Ok(texture_ptr)
     }
 
-    fn clamp_crop_region(
-        region: &Region,
-        frame_width: u32,
-        frame_height: u32,
-    ) -> Result<(u32, u32, u32, u32), String> {
-        if frame_width == 0 || frame_height == 0 {
-            return Err("Frame inválido: dimensiones 0x0".to_string());
-        }
-
-        let start_x = region.x.min(frame_width - 1);
-        let start_y = region.y.min(frame_height - 1);
+    type SharedCaptureControl = Arc<Mutex<Option<LiveCaptureControl>>>;
 
-        let end_x = region.x.saturating_add(region.width).min(frame_width);
-        let end_y = region.y.saturating_add(region.height).min(frame_height);
+    struct WindowsCaptureRuntime {
+        control: SharedCaptureControl,
+        secondary_control: Mutex<Option<SecondaryCaptureControl>>,
+        pause_timeline: Arc<PauseTimeline>,
+        idle_tracker: Option<Arc<IdleTracker>>,
+        frame_counter: Arc<AtomicU64>,
+        fps: u32,
+        flags: HandlerFlags,
+        on_session_finished: Option<SessionFinishedCallback>,
+    }
 
-        if end_x <= start_x || end_y <= start_y {
-            return Err(
-                "La región de recorte no intersecta con el frame capturado en tiempo real"
-                    .to_string(),
-            );
+    fn lock_control(control: &SharedCaptureControl) -> MutexGuard<'_, Option<LiveCaptureControl>> {
+        match control.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
         }
-
-        Ok((start_x, start_y, end_x, end_y))
     }
 
-    struct WindowsCaptureRuntime {
-        control: Option<CaptureControl<LiveCaptureHandler, String>>,
-        paused: Arc<AtomicBool>,
-        frame_counter: Arc<AtomicU64>,
-        on_session_finished: Option<SessionFinishedCallback>,
+    /// Arranca la captura del nuevo target con los mismos callbacks y recién
+    /// entonces cierra la anterior, así el encoder no ve un corte. Si el nuevo
+    /// target falla se sigue grabando el anterior.
+    fn switch_capture_target(
+        control: &SharedCaptureControl,
+        target_id: u32,
+        fps: u32,
+        flags: HandlerFlags,
+    ) -> Result<(), String> {
+        let mut current = lock_control(control);
+        if current.is_none() {
+            return Err("La captura ya terminó; no se puede cambiar de fuente".to_string());
+        }
+
+        let next = start_capture(target_id, fps, flags)?;
+        if let Some(previous) = current.replace(next) {
+            previous
+                .stop()
+                .map_err(|err| format!("Error cerrando la captura anterior: {err}"))?;
+        }
+        Ok(())
     }
 
     impl WindowsCaptureRuntime {
+        fn stop_secondary_capture(&self) {
+            let secondary = match self.secondary_control.lock() {
+                Ok(mut guard) => guard.take(),
+                Err(poisoned) => poisoned.into_inner().take(),
+            };
+            if let Some(secondary) = secondary {
+                if let Err(err) = secondary.stop() {
+                    eprintln!("[capture] Error deteniendo la captura del segundo monitor: {err}");
+                }
+            }
+        }
+
         fn finalize_encoder(&mut self) -> Result<(), String> {
             if let Some(callback) = self.on_session_finished.take() {
                 callback()?;
@@ -331,27 +784,60 @@ mod platform {
 
     impl CaptureRuntimeHandle for WindowsCaptureRuntime {
         fn pause(&self) {
-            self.paused.store(true, Ordering::Relaxed);
+            let now = Instant::now();
+            let paused_from = self
+                .idle_tracker
+                .as_ref()
+                .and_then(|idle_tracker| idle_tracker.suspend(now))
+                .unwrap_or(now);
+            self.pause_timeline.pause_at(paused_from);
         }
 
         fn resume(&self) {
-            self.paused.store(false, Ordering::Relaxed);
+            let now = Instant::now();
+            self.pause_timeline.resume_at(now);
+            if let Some(idle_tracker) = &self.idle_tracker {
+                idle_tracker.resume(now);
+            }
         }
 
         fn is_finished(&self) -> bool {
-            self.control
+            lock_control(&self.control)
                 .as_ref()
                 .map(CaptureControl::is_finished)
                 .unwrap_or(true)
         }
 
+        fn target_switcher(&self) -> Option<TargetSwitcher> {
+            if self.flags.side_by_side.is_some() {
+                return None;
+            }
+            let control = Arc::clone(&self.control);
+            let fps = self.fps;
+            let flags = self.flags.clone();
+            Some(Arc::new(move |target_id| {
+                switch_capture_target(&control, target_id, fps, flags.clone())
+            }))
+        }
+
+        fn set_target_fps(&self, fps: u32) -> Result<(), String> {
+            self.flags.frame_pacing.set_target_fps(fps);
+            Ok(())
+        }
+
+        fn frame_counter(&self) -> Option<Arc<AtomicU64>> {
+            Some(Arc::clone(&self.frame_counter))
+        }
+
         fn stop(mut self: Box<Self>) -> Result<u64, String> {
-            let stop_result = match self.control.take() {
+            let control = lock_control(&self.control).take();
+            let stop_result = match control {
                 Some(control) => control
                     .stop()
                     .map_err(|err| format!("Error deteniendo sesión de windows-capture: {err}")),
                 None => Err("Control de captura no disponible para detener sesión".to_string()),
             };
+            self.stop_secondary_capture();
 
             let finalize_result = self.finalize_encoder();
 
@@ -366,12 +852,14 @@ mod platform {
         }
 
         fn wait(mut self: Box<Self>) -> Result<u64, String> {
-            let wait_result = match self.control.take() {
+            let control = lock_control(&self.control).take();
+            let wait_result = match control {
                 Some(control) => control.wait().map_err(|err| {
                     format!("Error esperando finalización de windows-capture: {err}")
                 }),
                 None => Err("Control de captura no disponible para esperar sesión".to_string()),
             };
+            self.stop_secondary_capture();
 
             let finalize_result = self.finalize_encoder();
 
@@ -401,11 +889,24 @@ mod platform {
 
 #[cfg(not(target_os = "windows"))]
 mod platform {
-    use crate::capture::runtime::{CaptureRuntimeHandle, RuntimeStartConfig};
+    use std::time::Duration;
+
+    use crate::capture::{
+        models::{RawFrame, Region},
+        runtime::{CaptureRuntimeHandle, RuntimeStartConfig},
+    };
 
     pub fn start_runtime(
         _config: RuntimeStartConfig,
     ) -> Result<Box<dyn CaptureRuntimeHandle>, String> {
         Err("La captura de pantalla real solo está disponible en Windows".to_string())
     }
+
+    pub fn capture_single_frame(
+        _target_id: u32,
+        _crop_region: Option<Region>,
+        _timeout: Duration,
+    ) -> Result<RawFrame, String> {
+        Err("La captura de pantalla real solo está disponible en Windows".to_string())
+    }
 }
@@ -0,0 +1,30 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::sync::{Mutex, OnceLock};
+
+use crate::capture::models::StopReason;
+
+fn requested_stop_reason() -> &'static Mutex<Option<StopReason>> {
+    static REQUESTED_STOP_REASON: OnceLock<Mutex<Option<StopReason>>> = OnceLock::new();
+    REQUESTED_STOP_REASON.get_or_init(|| Mutex::new(None))
+}
+
+pub fn request_session_stop(reason: StopReason) {
+    if let Ok(mut guard) = requested_stop_reason().lock() {
+        guard.get_or_insert(reason);
+    }
+}
+
+pub fn is_session_stop_requested() -> bool {
+    requested_stop_reason()
+        .lock()
+        .map(|guard| guard.is_some())
+        .unwrap_or(false)
+}
+
+pub fn take_requested_stop_reason() -> Option<StopReason> {
+    requested_stop_reason()
+        .lock()
+        .ok()
+        .and_then(|mut guard| guard.take())
+}
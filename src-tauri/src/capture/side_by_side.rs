@@ -0,0 +1,138 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::sync::Mutex;
+
+use crate::capture::models::{FramePixelFormat, RawFrame};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonitorSide {
+    Left,
+    Right,
+}
+
+/// Último frame de cada monitor. Windows solo entrega frames cuando cambia
+/// la pantalla, así que el que llega se compone con el último del otro
+/// aunque sea viejo.
+#[derive(Debug, Default)]
+pub struct SideBySideFrames {
+    latest: Mutex<LatestFrames>,
+}
+
+#[derive(Debug, Default)]
+struct LatestFrames {
+    left: Option<RawFrame>,
+    right: Option<RawFrame>,
+}
+
+impl SideBySideFrames {
+    /// Guarda `frame` como el último de su lado y llama a `emit` con los dos;
+    /// `None` si el otro monitor todavía no entregó nada. `emit` corre con el
+    /// lock tomado para que los frames de las dos capturas salgan en orden.
+    pub fn push<R>(
+        &self,
+        side: MonitorSide,
+        frame: RawFrame,
+        emit: impl FnOnce(&RawFrame, &RawFrame) -> R,
+    ) -> Option<R> {
+        let mut latest = self.latest.lock().ok()?;
+        match side {
+            MonitorSide::Left => latest.left = Some(frame),
+            MonitorSide::Right => latest.right = Some(frame),
+        }
+        match (&latest.left, &latest.right) {
+            (Some(left), Some(right)) => Some(emit(left, right)),
+            _ => None,
+        }
+    }
+}
+
+pub fn compose_side_by_side(left: &RawFrame, right: &RawFrame) -> Result<RawFrame, String> {
+    if left.pixel_format != FramePixelFormat::Bgra8 || right.pixel_format != FramePixelFormat::Bgra8
+    {
+        return Err("La composición de dos monitores solo admite frames BGRA".to_string());
+    }
+    if !left.is_cpu_layout_valid() || !right.is_cpu_layout_valid() {
+        return Err("Frame inválido para la composición de dos monitores".to_string());
+    }
+    if left.height != right.height {
+        return Err(format!(
+            "Los monitores deben tener el mismo alto para grabarse lado a lado ({} y {} px)",
+            left.height, right.height
+        ));
+    }
+
+    let left_row = RawFrame::min_row_stride_bytes(left.width) as usize;
+    let right_row = RawFrame::min_row_stride_bytes(right.width) as usize;
+    let mut data = Vec::with_capacity((left_row + right_row) * left.height as usize);
+    let rows = left
+        .data
+        .chunks(left.row_stride_bytes as usize)
+        .zip(right.data.chunks(right.row_stride_bytes as usize))
+        .take(left.height as usize);
+    for (left_bytes, right_bytes) in rows {
+        data.extend_from_slice(&left_bytes[..left_row]);
+        data.extend_from_slice(&right_bytes[..right_row]);
+    }
+
+    let newest = if right.timestamp_ms > left.timestamp_ms {
+        right
+    } else {
+        left
+    };
+    let mut composed = RawFrame::new(
+        data,
+        left.width + right.width,
+        left.height,
+        (left_row + right_row) as u32,
+        newest.timestamp_ms,
+    );
+    composed.captured_at = newest.captured_at;
+    Ok(composed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compone_filas_sin_padding() {
+        let left = RawFrame::new(vec![1, 1, 1, 1, 0, 0, 2, 2, 2, 2, 0, 0], 1, 2, 6, 40);
+        let right = RawFrame::new((10..26).collect(), 2, 2, 8, 45);
+        let composed = compose_side_by_side(&left, &right).unwrap();
+
+        assert_eq!((composed.width, composed.height), (3, 2));
+        assert_eq!(composed.row_stride_bytes, 12);
+        assert_eq!(composed.timestamp_ms, 45);
+        assert_eq!(
+            &composed.data[..12],
+            &[1, 1, 1, 1, 10, 11, 12, 13, 14, 15, 16, 17]
+        );
+        assert_eq!(
+            &composed.data[12..],
+            &[2, 2, 2, 2, 18, 19, 20, 21, 22, 23, 24, 25]
+        );
+
+        let taller = RawFrame::new(vec![0; 12], 1, 3, 4, 0);
+        let err = compose_side_by_side(&left, &taller).expect_err("debio fallar");
+        assert!(err.contains("mismo alto"), "{err}");
+    }
+
+    #[test]
+    fn compone_cuando_llega_cualquiera_de_los_dos_lados() {
+        let frames = SideBySideFrames::default();
+        let timestamp = |left: &RawFrame, right: &RawFrame| {
+            compose_side_by_side(left, right).unwrap().timestamp_ms
+        };
+
+        let right = RawFrame::new(vec![0; 4], 1, 1, 4, 10);
+        assert_eq!(frames.push(MonitorSide::Right, right, timestamp), None);
+
+        let left = RawFrame::new(vec![0; 4], 1, 1, 4, 20);
+        assert_eq!(frames.push(MonitorSide::Left, left, timestamp), Some(20));
+
+        let right = RawFrame::new(vec![0; 4], 1, 1, 4, 500);
+        assert_eq!(frames.push(MonitorSide::Right, right, timestamp), Some(500));
+        let right = RawFrame::new(vec![0; 4], 1, 1, 4, 900);
+        assert_eq!(frames.push(MonitorSide::Right, right, timestamp), Some(900));
+    }
+}
@@ -0,0 +1,142 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+const MONITOR_SALT: u64 = 0x045D_9F3B;
+const WINDOW_SALT: u64 = 0x27D4_EB2D;
+
+/// Id de un monitor a partir de la ruta de interfaz del dispositivo
+/// (`\\?\DISPLAY#DEL4109#5&2b3c1a0&0&UID4353#{e6f07b5f-...}`). A diferencia
+/// del HMONITOR, se mantiene entre reinicios mientras el monitor siga
+/// conectado al mismo puerto. Windows no es parejo con las mayúsculas de la
+/// ruta, así que no cuentan.
+pub fn monitor_target_id(device_path: &str) -> u32 {
+    let path = device_path.trim().to_ascii_lowercase();
+    stable_target_id(fnv1a(path.as_bytes()), MONITOR_SALT)
+}
+
+/// Respaldo para monitores sin ruta de dispositivo (algunos drivers y
+/// escritorios remotos): el id cambia al reiniciar.
+pub fn monitor_handle_target_id(hmonitor: u64) -> u32 {
+    stable_target_id(hmonitor, MONITOR_SALT)
+}
+
+pub fn window_target_id(hwnd: u64) -> u32 {
+    stable_target_id(hwnd, WINDOW_SALT)
+}
+
+/// Mezcla estable sin depender del hasher del proceso. Al reducir a `u32`
+/// dos targets pueden chocar (con decenas de targets es del orden de uno en
+/// diez millones); si pasa, al resolver el id gana el monitor sobre la
+/// ventana y, entre iguales, el primero que enumera Windows.
+fn stable_target_id(base: u64, salt: u64) -> u32 {
+    let mut value = base ^ salt;
+    value ^= value >> 33;
+    value = value.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    value ^= value >> 33;
+    value = value.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    value ^= value >> 33;
+
+    (value as u32).max(1)
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(target_os = "windows")]
+pub use platform::monitor_id;
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{ffi::c_void, mem};
+
+    use windows::{
+        core::PCWSTR,
+        Win32::{
+            Graphics::Gdi::{
+                EnumDisplayDevicesW, GetMonitorInfoW, DISPLAY_DEVICEW, HMONITOR, MONITORINFO,
+                MONITORINFOEXW,
+            },
+            UI::WindowsAndMessaging::EDD_GET_DEVICE_INTERFACE_NAME,
+        },
+    };
+
+    use super::{monitor_handle_target_id, monitor_target_id};
+
+    pub fn monitor_id(hmonitor: *mut c_void) -> u32 {
+        match monitor_device_path(hmonitor) {
+            Some(path) => monitor_target_id(&path),
+            None => monitor_handle_target_id(hmonitor as usize as u64),
+        }
+    }
+
+    fn monitor_device_path(hmonitor: *mut c_void) -> Option<String> {
+        let mut info = MONITORINFOEXW::default();
+        info.monitorInfo.cbSize = mem::size_of::<MONITORINFOEXW>() as u32;
+        // SAFETY: consultas Win32 de solo lectura sobre estructuras propias.
+        unsafe {
+            let info_ptr = &mut info as *mut MONITORINFOEXW as *mut MONITORINFO;
+            if !GetMonitorInfoW(HMONITOR(hmonitor), info_ptr).as_bool() {
+                return None;
+            }
+            let mut device = DISPLAY_DEVICEW {
+                cb: mem::size_of::<DISPLAY_DEVICEW>() as u32,
+                ..Default::default()
+            };
+            if !EnumDisplayDevicesW(
+                PCWSTR(info.szDevice.as_ptr()),
+                0,
+                &mut device,
+                EDD_GET_DEVICE_INTERFACE_NAME,
+            )
+            .as_bool()
+            {
+                return None;
+            }
+            let len = device
+                .DeviceID
+                .iter()
+                .position(|ch| *ch == 0)
+                .unwrap_or(device.DeviceID.len());
+            let path = String::from_utf16_lossy(&device.DeviceID[..len]);
+            (!path.trim().is_empty()).then_some(path)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DELL_HDMI: &str =
+        r"\\?\DISPLAY#DEL4109#5&2b3c1a0&0&UID4353#{e6f07b5f-ee97-4a90-b076-33f57bf4eaa7}";
+    const DELL_DP: &str =
+        r"\\?\DISPLAY#DEL4109#5&2b3c1a0&0&UID4354#{e6f07b5f-ee97-4a90-b076-33f57bf4eaa7}";
+    const LAPTOP_PANEL: &str =
+        r"\\?\DISPLAY#BOE0A81#4&1c6e1b0e&0&UID8388688#{e6f07b5f-ee97-4a90-b076-33f57bf4eaa7}";
+
+    #[test]
+    fn ids_de_monitor_salen_de_la_ruta_del_dispositivo() {
+        assert_eq!(monitor_target_id(DELL_HDMI), 0x4394_28e2);
+        assert_eq!(monitor_target_id(LAPTOP_PANEL), 0x2b79_4b63);
+
+        assert_eq!(
+            monitor_target_id(&DELL_HDMI.to_ascii_uppercase()),
+            monitor_target_id(DELL_HDMI)
+        );
+        assert_eq!(
+            monitor_target_id(&format!(" {DELL_HDMI}\n")),
+            monitor_target_id(DELL_HDMI)
+        );
+        assert_ne!(monitor_target_id(DELL_DP), monitor_target_id(DELL_HDMI));
+        assert_ne!(monitor_target_id(""), 0);
+    }
+
+    #[test]
+    fn ids_de_ventana_siguen_usando_el_hwnd() {
+        assert_eq!(window_target_id(0x0003_04A2), window_target_id(0x0003_04A2));
+        assert_ne!(window_target_id(0x0003_04A2), window_target_id(0x0003_04A4));
+        assert_ne!(window_target_id(0x10001), monitor_handle_target_id(0x10001));
+    }
+}
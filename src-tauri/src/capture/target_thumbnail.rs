@@ -0,0 +1,92 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use crate::{capture::runtime, encoder::thumbnail};
+
+pub const TARGET_THUMBNAIL_TIMEOUT: Duration = Duration::from_millis(500);
+pub const TARGET_THUMBNAIL_TTL: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+pub struct TargetThumbnailCache {
+    ttl: Duration,
+    entries: HashMap<(u32, u32), (Instant, PathBuf)>,
+}
+
+impl TargetThumbnailCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, target_id: u32, max_width: u32, now: Instant) -> Option<PathBuf> {
+        self.entries
+            .get(&(target_id, max_width))
+            .filter(|(captured_at, _)| now.saturating_duration_since(*captured_at) < self.ttl)
+            .map(|(_, path)| path.clone())
+    }
+
+    pub fn insert(&mut self, target_id: u32, max_width: u32, path: PathBuf, now: Instant) {
+        let ttl = self.ttl;
+        self.entries
+            .retain(|_, (captured_at, _)| now.saturating_duration_since(*captured_at) < ttl);
+        self.entries.insert((target_id, max_width), (now, path));
+    }
+}
+
+fn cache() -> &'static Mutex<TargetThumbnailCache> {
+    static CACHE: OnceLock<Mutex<TargetThumbnailCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(TargetThumbnailCache::new(TARGET_THUMBNAIL_TTL)))
+}
+
+pub fn target_thumbnail(target_id: u32, max_width: u32) -> Result<PathBuf, String> {
+    if let Some(path) = cache()
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(target_id, max_width, Instant::now()))
+    {
+        return Ok(path);
+    }
+
+    let frame = runtime::capture_single_frame_within(target_id, None, TARGET_THUMBNAIL_TIMEOUT)?;
+    let path = thumbnail::target_thumbnail_path(target_id, max_width);
+    thumbnail::write_frame_thumbnail(&frame, max_width, &path)?;
+
+    if let Ok(mut cache) = cache().lock() {
+        cache.insert(target_id, max_width, path.clone(), Instant::now());
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reusa_la_miniatura_hasta_que_vence() {
+        let start = Instant::now();
+        let mut cache = TargetThumbnailCache::new(Duration::from_secs(2));
+        cache.insert(7, 320, PathBuf::from("target-7-320.jpg"), start);
+
+        let later = start + Duration::from_millis(1_500);
+        assert_eq!(
+            cache.get(7, 320, later),
+            Some(PathBuf::from("target-7-320.jpg"))
+        );
+        assert_eq!(cache.get(7, 480, later), None);
+        assert_eq!(cache.get(7, 320, start + Duration::from_secs(2)), None);
+
+        cache.insert(
+            8,
+            320,
+            PathBuf::from("target-8-320.jpg"),
+            start + Duration::from_secs(3),
+        );
+        assert_eq!(cache.entries.len(), 1);
+    }
+}
@@ -0,0 +1,210 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use crate::capture::{
+    manager::{CaptureManager, RuntimeFactory, SessionConfig},
+    models::{CaptureTarget, TargetKind},
+    provider::{ScreenProvider, WindowMonitorLookup},
+    runtime::{CaptureRuntimeHandle, TargetSwitcher},
+};
+use crate::encoder::config::EncoderConfig;
+
+pub(crate) struct MockScreenProvider {
+    supported: bool,
+    targets: Vec<CaptureTarget>,
+    pub(crate) window_monitor: Arc<Mutex<Option<u32>>>,
+    pub(crate) disconnected_ids: Arc<Mutex<Vec<u32>>>,
+}
+
+impl MockScreenProvider {
+    pub(crate) fn with_single_monitor() -> Self {
+        Self {
+            supported: true,
+            targets: vec![CaptureTarget {
+                id: 1,
+                name: "Monitor de prueba".to_string(),
+                width: 1920,
+                height: 1080,
+                origin_x: 0,
+                origin_y: 0,
+                screen_width: 1920,
+                screen_height: 1080,
+                is_primary: true,
+                kind: TargetKind::Monitor,
+                process_name: None,
+                process_id: None,
+                window_title: None,
+                refresh_rate_hz: None,
+                dpi_scale_percent: None,
+                is_minimized: false,
+            }],
+            window_monitor: Arc::new(Mutex::new(Some(1))),
+            disconnected_ids: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub(crate) fn with_scaled_4k_monitor() -> Self {
+        let mut provider = Self::with_single_monitor();
+        let monitor = &mut provider.targets[0];
+        monitor.width = 3840;
+        monitor.height = 2160;
+        monitor.screen_width = 2560;
+        monitor.screen_height = 1440;
+        provider
+    }
+
+    pub(crate) fn with_monitor_and_window() -> Self {
+        let mut provider = Self::with_single_monitor();
+        provider.targets.push(CaptureTarget {
+            id: 10,
+            name: "Ventana de prueba".to_string(),
+            width: 800,
+            height: 600,
+            origin_x: 100,
+            origin_y: 100,
+            screen_width: 800,
+            screen_height: 600,
+            is_primary: false,
+            kind: TargetKind::Window,
+            process_name: None,
+            process_id: None,
+            window_title: None,
+            refresh_rate_hz: None,
+            dpi_scale_percent: None,
+            is_minimized: false,
+        });
+        provider
+    }
+
+    pub(crate) fn with_side_monitor(width: u32, height: u32) -> Self {
+        let mut provider = Self::with_single_monitor();
+        provider.targets.push(CaptureTarget {
+            id: 2,
+            name: "Monitor secundario".to_string(),
+            width,
+            height,
+            origin_x: 1920,
+            origin_y: 0,
+            screen_width: width,
+            screen_height: height,
+            is_primary: false,
+            kind: TargetKind::Monitor,
+            process_name: None,
+            process_id: None,
+            window_title: None,
+            refresh_rate_hz: None,
+            dpi_scale_percent: None,
+            is_minimized: false,
+        });
+        provider
+    }
+}
+
+impl ScreenProvider for MockScreenProvider {
+    fn get_targets(&self) -> Result<Vec<CaptureTarget>, String> {
+        let disconnected = self.disconnected_ids.lock().unwrap();
+        Ok(self
+            .targets
+            .iter()
+            .filter(|target| !disconnected.contains(&target.id))
+            .cloned()
+            .collect())
+    }
+
+    fn is_supported(&self) -> bool {
+        self.supported
+    }
+
+    fn window_monitor_lookup(&self) -> WindowMonitorLookup {
+        let window_monitor = Arc::clone(&self.window_monitor);
+        Arc::new(move |_window_id| Ok(*window_monitor.lock().unwrap()))
+    }
+}
+
+pub(crate) struct MockRuntimeHandle {
+    paused: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+    frame_counter: Arc<AtomicU64>,
+}
+
+impl MockRuntimeHandle {
+    pub(crate) fn new() -> Self {
+        Self {
+            paused: Arc::new(AtomicBool::new(false)),
+            finished: Arc::new(AtomicBool::new(false)),
+            frame_counter: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub(crate) fn with_frame_counter(mut self, frame_counter: Arc<AtomicU64>) -> Self {
+        self.frame_counter = frame_counter;
+        self
+    }
+}
+
+impl CaptureRuntimeHandle for MockRuntimeHandle {
+    fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Relaxed)
+    }
+
+    fn stop(self: Box<Self>) -> Result<u64, String> {
+        self.finished.store(true, Ordering::Relaxed);
+        Ok(0)
+    }
+
+    fn wait(self: Box<Self>) -> Result<u64, String> {
+        self.finished.store(true, Ordering::Relaxed);
+        Ok(0)
+    }
+
+    fn target_switcher(&self) -> Option<TargetSwitcher> {
+        Some(Arc::new(|_target_id| Ok(())))
+    }
+
+    fn set_target_fps(&self, _fps: u32) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn frame_counter(&self) -> Option<Arc<AtomicU64>> {
+        Some(Arc::clone(&self.frame_counter))
+    }
+}
+
+pub(crate) fn make_mock_manager() -> CaptureManager {
+    CaptureManager::with_dependencies(
+        Box::new(MockScreenProvider::with_single_monitor()),
+        RuntimeFactory::new(|_config| Ok(Box::new(MockRuntimeHandle::new()))),
+    )
+}
+
+pub(crate) fn make_session_config(target_id: u32) -> SessionConfig {
+    SessionConfig {
+        target_id,
+        secondary_target_id: None,
+        fps: 30,
+        crop_region: None,
+        capture_resolution_preset: None,
+        auto_pause_idle_ms: None,
+        idle_tracker: None,
+        follow_window_monitor: false,
+        adaptive_fps: false,
+        cursor_scale: 1.0,
+        show_region_border: false,
+        exclude_window_ids: Vec::new(),
+        include_secondary_windows: None,
+        restore_if_minimized: false,
+        pipeline_load: None,
+        recording_clock: None,
+        encoder_config: EncoderConfig::default(),
+    }
+}
@@ -0,0 +1,141 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::capture::provider::enumerate_window_handles;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowHandle {
+    pub target_id: u32,
+    pub hwnd: u64,
+}
+
+pub fn resolve_excluded_windows(ids: &[u64], windows: &[WindowHandle]) -> Result<Vec<u64>, String> {
+    let mut handles = Vec::with_capacity(ids.len());
+    for id in ids {
+        let hwnd = windows
+            .iter()
+            .find(|window| u64::from(window.target_id) == *id)
+            .or_else(|| windows.iter().find(|window| window.hwnd == *id))
+            .map(|window| window.hwnd)
+            .ok_or_else(|| format!("No se encontró la ventana {id} para excluir de la captura"))?;
+        if !handles.contains(&hwnd) {
+            handles.push(hwnd);
+        }
+    }
+    Ok(handles)
+}
+
+pub struct WindowExclusion {
+    restored: Vec<(u64, u32)>,
+}
+
+impl WindowExclusion {
+    /// Windows solo deja cambiar la afinidad de ventanas del propio proceso,
+    /// así que cualquier otra se rechaza.
+    pub fn apply(ids: &[u64]) -> Result<Self, String> {
+        let mut exclusion = Self {
+            restored: Vec::new(),
+        };
+        if ids.is_empty() {
+            return Ok(exclusion);
+        }
+
+        for hwnd in resolve_excluded_windows(ids, &enumerate_window_handles()?)? {
+            if !platform::is_own_window(hwnd) {
+                return Err(
+                    "Solo se pueden excluir de la captura ventanas de Capturist".to_string()
+                );
+            }
+            let previous = platform::exclude_from_capture(hwnd)?;
+            exclusion.restored.push((hwnd, previous));
+        }
+        Ok(exclusion)
+    }
+}
+
+impl Drop for WindowExclusion {
+    fn drop(&mut self) {
+        for (hwnd, previous) in self.restored.drain(..) {
+            platform::restore_affinity(hwnd, previous);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::ffi::c_void;
+
+    use windows::Win32::{
+        Foundation::HWND,
+        UI::WindowsAndMessaging::{
+            GetWindowDisplayAffinity, GetWindowThreadProcessId, SetWindowDisplayAffinity,
+            WDA_EXCLUDEFROMCAPTURE, WINDOW_DISPLAY_AFFINITY,
+        },
+    };
+
+    fn hwnd(raw: u64) -> HWND {
+        HWND(raw as usize as *mut c_void)
+    }
+
+    pub fn is_own_window(raw: u64) -> bool {
+        let mut process_id = 0_u32;
+        // SAFETY: consulta de solo lectura; un HWND inválido devuelve 0.
+        let thread_id = unsafe { GetWindowThreadProcessId(hwnd(raw), Some(&mut process_id)) };
+        thread_id != 0 && process_id == std::process::id()
+    }
+
+    pub fn exclude_from_capture(raw: u64) -> Result<u32, String> {
+        let mut previous = 0_u32;
+        // SAFETY: el HWND es una ventana de este proceso recién enumerada.
+        unsafe {
+            let _ = GetWindowDisplayAffinity(hwnd(raw), &mut previous);
+            SetWindowDisplayAffinity(hwnd(raw), WDA_EXCLUDEFROMCAPTURE)
+        }
+        .map_err(|err| format!("No se pudo excluir la ventana de la captura: {err}"))?;
+        Ok(previous)
+    }
+
+    pub fn restore_affinity(raw: u64, previous: u32) {
+        // SAFETY: si la ventana ya se cerró la llamada falla sin efectos.
+        let _ = unsafe { SetWindowDisplayAffinity(hwnd(raw), WINDOW_DISPLAY_AFFINITY(previous)) };
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    pub fn is_own_window(_raw: u64) -> bool {
+        false
+    }
+
+    pub fn exclude_from_capture(_raw: u64) -> Result<u32, String> {
+        Err("Excluir ventanas de la captura solo está disponible en Windows".to_string())
+    }
+
+    pub fn restore_affinity(_raw: u64, _previous: u32) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resuelve_ids_de_target_y_hwnd_sin_repetir() {
+        let windows = [
+            WindowHandle {
+                target_id: 11,
+                hwnd: 0x1_0000,
+            },
+            WindowHandle {
+                target_id: 22,
+                hwnd: 0x2_0000,
+            },
+        ];
+        assert_eq!(
+            resolve_excluded_windows(&[22, 0x1_0000, 11], &windows).unwrap(),
+            vec![0x2_0000, 0x1_0000]
+        );
+
+        let err = resolve_excluded_windows(&[33], &windows).unwrap_err();
+        assert!(err.contains("33"), "{err}");
+        assert!(WindowExclusion::apply(&[]).is_ok());
+    }
+}
@@ -1,26 +1,84 @@
-use std::path::PathBuf;
+use std::{
+    env,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
 
 use crate::{
     capture::{
+        cursor_overlay::default_cursor_scale,
+        frame_pacing::{clamp_fps_to_refresh_rate, FpsClampWarning},
+        frame_snapshot,
         manager::{CaptureManager, CaptureManagerSnapshot, SessionConfig},
-        models::{CaptureResolutionPreset, CaptureState, CaptureTarget, Region},
+        models::{CaptureResolutionPreset, CaptureState, CaptureTarget, Region, TargetFilter},
+        target_thumbnail,
     },
     encoder::{
+        animation_export::{self, AnimationExportRequest, AnimationFormat},
         audio_capture::{
             apply_audio_capture_config, get_live_audio_status, list_microphone_input_devices,
+            list_system_audio_output_devices, resolve_microphone_device,
+            resolve_system_audio_device, set_live_microphone_gain, set_live_microphone_monitoring,
             update_live_audio_capture, LiveAudioStatusSnapshot,
         },
+        bitrate::estimate_target_bitrate_kbps,
+        chapters::{ChapterMarker, ChapterMarkerLog},
         config::{
-            AudioCaptureConfig, EncoderConfig, EncoderPreset, OutputFormat, OutputResolution,
-            QualityMode, VideoCodec, VideoEncoderPreference,
+            default_gif_fps, default_gif_max_width, default_hls_segment_seconds,
+            default_never_upscale, AudioCaptureConfig, AudioCodec, BitDepth, ChromaSubsampling,
+            ColorRange, EncoderConfig, EncoderPreset, Fat32Policy, FrameRateMode, GifExportConfig,
+            MicNoiseSuppression, OutputFormat, OutputResolution, QualityMode, RecordingMetadata,
+            ScalerAlgorithm, VideoCodec, VideoEncoderPreference,
+        },
+        consumer::{probe_video_encoder, VideoEncoderCapabilities},
+        disk_space::{
+            get_live_disk_space_status, preflight_disk_space, set_live_disk_space_status,
+            DiskSpaceWarning, DEFAULT_DISK_SPACE_THRESHOLD_MB,
+        },
+        encoded_duration::set_live_encoded_duration_ms,
+        ffmpeg_info::{self, FfmpegInfo},
+        ffmpeg_paths::{resolve_ffmpeg_bin_with, set_configured_ffmpeg_bin},
+        filesystem_limits::{check_file_size_limits, FileSizeLimitWarning, SystemVolumeInfo},
+        gpu_scaler::GpuFrameRoute,
+        media_info::{probe_output_file, OutputFileInfo},
+        output_naming::{
+            is_directory_output_path, resolve_templated_output_path, OutputNameContext,
+            DEFAULT_OUTPUT_TEMPLATE,
+        },
+        output_paths::temp_output_base_dir,
+        output_sink::{stream_output_url, OutputSink, OutputTarget},
+        pipeline_latency::{get_pipeline_latency, reset_pipeline_latency},
+        post_process::{cancel_post_process_jobs, get_post_process_jobs},
+        preflight::{
+            check_audio_device, check_ffmpeg_bin, check_output_dir_writable,
+            check_preferred_encoder, encoder_probe_size, SessionCheck, SessionCheckReport,
+            ValidationIssue,
+        },
+        processing_status::{
+            is_processing, processing_eta_ms, set_processing, wait_for_processing_idle,
+        },
+        project_takes::{
+            list_project_takes, peek_next_take_path, project_date_dir, project_folder_name,
+            release_take_reservation, reserve_take_path, ProjectTake,
+        },
+        quality_analysis::{self, QualityReport, DEFAULT_REFERENCE_SECONDS},
+        recording_history::{self, RecordingHistoryEntry, MAX_HISTORY_ENTRIES},
+        recording_trim::{self, TrimResult},
+        remux::{self, RemuxResult},
+        session_recovery::{
+            find_recoverable_sessions, recover_session, session_temp_roots, RecoverableSession,
+            RecoveredSession, RECOVERY_DIR_NAME,
+        },
+        thumbnail::{self, default_thumbnail_at_ms, DEFAULT_THUMBNAIL_MAX_WIDTH},
+        trim::TrimRange,
+        video_encoder_status::{
+            get_live_video_encoder_label, get_video_encoder_capabilities_cached,
+            set_live_video_encoder_label,
         },
-        consumer::detect_video_encoder_capabilities,
-        processing_status::{is_processing, set_processing},
-        video_encoder_status::{get_live_video_encoder_label, set_live_video_encoder_label},
     },
-    region,
+    file_manager, region,
     shortcuts::ShortcutBindings,
     AppState,
 };
@@ -29,10 +87,12 @@ const CAPTURE_LOCK_ERR: &str =
     "No se pudo acceder al estado de captura (lock interno en estado inválido)";
 const SHORTCUTS_LOCK_ERR: &str =
     "No se pudo acceder al estado de atajos globales (lock interno en estado inválido)";
+const SCHEDULER_LOCK_ERR: &str =
+    "No se pudo acceder al programador de grabaciones (lock interno en estado inválido)";
 
-fn lock_capture<'a>(
-    state: &'a State<'_, AppState>,
-) -> Result<std::sync::MutexGuard<'a, CaptureManager>, String> {
+pub(crate) fn lock_capture(
+    state: &AppState,
+) -> Result<std::sync::MutexGuard<'_, CaptureManager>, String> {
     state
         .capture
         .lock()
@@ -40,28 +100,39 @@ fn lock_capture<'a>(
 }
 
 #[tauri::command]
-pub fn select_region_native(target: Option<CaptureTarget>) -> Result<Option<Region>, String> {
+pub fn select_region_native(
+    target: Option<CaptureTarget>,
+    style: Option<region::OverlayStyle>,
+) -> Result<Option<Region>, String> {
+    let style = style.unwrap_or_default();
+    style.validate()?;
     let Some(target) = target else {
-        return region::select_region();
+        return region::select_region(style);
     };
 
+    let layout = region::monitor_layout();
     let bounds = region::SelectionBounds {
         origin_x: target.origin_x,
         origin_y: target.origin_y,
         width: target.screen_width,
         height: target.screen_height,
+        scale_factor: region::scale_factor_at(&layout, target.origin_x, target.origin_y),
     };
 
-    let Some(selected_region) = region::select_region_with_bounds(bounds)? else {
+    let Some(selected_region) = region::select_region_with_bounds(bounds, style)? else {
         return Ok(None);
     };
 
-    normalize_native_region_for_target(selected_region, &target).map(Some)
+    normalize_native_region_for_target(selected_region, &target, bounds.scale_factor).map(Some)
 }
 
+/// `selected_region` está en el espacio del overlay, que mide
+/// `screen_width`/`screen_height` por `scale_factor`; con 1.0 el overlay usó
+/// las coordenadas lógicas del target.
 fn normalize_native_region_for_target(
     selected_region: Region,
     target: &CaptureTarget,
+    scale_factor: f64,
 ) -> Result<Region, String> {
     if target.width == 0
         || target.height == 0
@@ -74,27 +145,31 @@ fn normalize_native_region_for_target(
     if selected_region.width == 0 || selected_region.height == 0 {
         return Err("La region seleccionada no tiene un area valida".to_string());
     }
+    if !scale_factor.is_finite() || scale_factor <= 0.0 {
+        return Err("La escala del monitor no es valida".to_string());
+    }
 
-    let source_start_x = selected_region.x.min(target.screen_width.saturating_sub(1));
-    let source_start_y = selected_region
-        .y
-        .min(target.screen_height.saturating_sub(1));
+    let source_width = ((target.screen_width as f64 * scale_factor).round() as u32).max(1);
+    let source_height = ((target.screen_height as f64 * scale_factor).round() as u32).max(1);
+
+    let source_start_x = selected_region.x.min(source_width.saturating_sub(1));
+    let source_start_y = selected_region.y.min(source_height.saturating_sub(1));
     let source_end_x = selected_region
         .x
         .saturating_add(selected_region.width)
-        .clamp(source_start_x.saturating_add(1), target.screen_width);
+        .clamp(source_start_x.saturating_add(1), source_width);
     let source_end_y = selected_region
         .y
         .saturating_add(selected_region.height)
-        .clamp(source_start_y.saturating_add(1), target.screen_height);
+        .clamp(source_start_y.saturating_add(1), source_height);
 
     let mapped_start_x =
-        scale_coordinate(source_start_x, target.screen_width, target.width).min(target.width - 1);
-    let mapped_start_y = scale_coordinate(source_start_y, target.screen_height, target.height)
-        .min(target.height - 1);
-    let mapped_end_x = scale_coordinate(source_end_x, target.screen_width, target.width)
+        scale_coordinate(source_start_x, source_width, target.width).min(target.width - 1);
+    let mapped_start_y =
+        scale_coordinate(source_start_y, source_height, target.height).min(target.height - 1);
+    let mapped_end_x = scale_coordinate(source_end_x, source_width, target.width)
         .clamp(mapped_start_x.saturating_add(1), target.width);
-    let mapped_end_y = scale_coordinate(source_end_y, target.screen_height, target.height)
+    let mapped_end_y = scale_coordinate(source_end_y, source_height, target.height)
         .clamp(mapped_start_y.saturating_add(1), target.height);
 
     Ok(Region {
@@ -113,14 +188,56 @@ fn scale_coordinate(value: u32, source_extent: u32, target_extent: u32) -> u32 {
 #[serde(rename_all = "camelCase")]
 pub struct RecordingSessionConfig {
     pub target_id: u32,
+    #[serde(default)]
+    pub secondary_target_id: Option<u32>,
     pub fps: u32,
     pub crop_region: Option<Region>,
+    #[serde(default)]
+    pub auto_pause_idle_ms: Option<u64>,
+    #[serde(default)]
+    pub follow_window_monitor: bool,
+    #[serde(default)]
+    pub adaptive_fps: bool,
+    #[serde(default = "default_cursor_scale")]
+    pub cursor_scale: f32,
+    #[serde(default)]
+    pub show_region_border: bool,
+    #[serde(default)]
+    pub exclude_window_ids: Vec<u64>,
+    #[serde(default)]
+    pub include_secondary_windows: Option<bool>,
+    #[serde(default)]
+    pub restore_if_minimized: bool,
+    #[serde(default)]
+    pub output_target: OutputTarget,
+    #[serde(default)]
+    pub outputs: Vec<OutputSink>,
+    #[serde(default)]
+    pub trim_start_ms: Option<u64>,
+    #[serde(default)]
+    pub trim_end_ms: Option<u64>,
+    #[serde(default = "default_hls_segment_seconds")]
+    pub hls_segment_seconds: u32,
+    #[serde(default)]
+    pub ffmpeg_bin_path: Option<String>,
     pub output_path: String,
+    #[serde(default)]
+    pub output_template: Option<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
     pub format: OutputFormat,
     pub codec: Option<VideoCodec>,
     #[serde(default = "default_video_encoder_preference")]
     pub video_encoder_preference: VideoEncoderPreference,
     pub resolution: OutputResolution,
+    #[serde(default = "default_never_upscale")]
+    pub never_upscale: bool,
     #[serde(default = "default_crf")]
     pub crf: u32,
     #[serde(default = "default_preset")]
@@ -128,6 +245,22 @@ pub struct RecordingSessionConfig {
     #[serde(default = "default_quality_mode")]
     pub quality_mode: QualityMode,
     #[serde(default)]
+    pub color_range: ColorRange,
+    #[serde(default)]
+    pub hdr: bool,
+    #[serde(default)]
+    pub scaler: Option<ScalerAlgorithm>,
+    #[serde(default)]
+    pub chroma_subsampling: Option<ChromaSubsampling>,
+    #[serde(default)]
+    pub bit_depth: Option<BitDepth>,
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub level: Option<String>,
+    #[serde(default)]
+    pub blur_regions: Vec<Region>,
+    #[serde(default)]
     pub capture_system_audio: bool,
     #[serde(default)]
     pub capture_microphone_audio: bool,
@@ -137,6 +270,46 @@ pub struct RecordingSessionConfig {
     pub microphone_device: Option<String>,
     #[serde(default = "default_microphone_gain_percent")]
     pub microphone_gain_percent: u16,
+    #[serde(default)]
+    pub audio_sync_offset_ms: i64,
+    #[serde(default)]
+    pub audio_sample_rate: Option<u32>,
+    #[serde(default)]
+    pub audio_channels: Option<u16>,
+    #[serde(default)]
+    pub mic_noise_suppression: Option<MicNoiseSuppression>,
+    #[serde(default)]
+    pub normalize_loudness: bool,
+    #[serde(default)]
+    pub loudness_target_lufs: Option<i32>,
+    #[serde(default)]
+    pub audio_codec: Option<AudioCodec>,
+    #[serde(default)]
+    pub audio_bitrate_kbps: Option<u32>,
+    #[serde(default)]
+    pub allow_opus_in_mp4: bool,
+    #[serde(default)]
+    pub monitor_microphone: bool,
+    #[serde(default = "default_disk_space_threshold_mb")]
+    pub disk_space_threshold_mb: u64,
+    #[serde(default)]
+    pub fat32_policy: Fat32Policy,
+    #[serde(default = "default_gif_fps")]
+    pub gif_fps: u32,
+    #[serde(default = "default_gif_max_width")]
+    pub gif_max_width: u32,
+    #[serde(default)]
+    pub fit_to_size_mb: Option<u64>,
+    #[serde(default)]
+    pub interpolate_to_fps: Option<u32>,
+    #[serde(default)]
+    pub mp4_faststart: bool,
+    #[serde(default)]
+    pub keyframe_interval_frames: Option<u32>,
+    #[serde(default)]
+    pub force_cfr: bool,
+    #[serde(default)]
+    pub frame_rate_mode: FrameRateMode,
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
@@ -148,13 +321,32 @@ pub struct RecordingAudioCaptureUpdate {
 
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct VideoEncoderCapabilitiesSnapshot {
+pub struct RecordingStartReport {
+    pub disk_space_warnings: Vec<DiskSpaceWarning>,
+    pub file_size_limit_warnings: Vec<FileSizeLimitWarning>,
+    pub fps_warning: Option<FpsClampWarning>,
+    #[serde(skip)]
+    pub session_serial: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodecBackendSupport {
     pub nvenc: bool,
     pub amf: bool,
     pub qsv: bool,
     pub software: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoEncoderCapabilitiesSnapshot {
+    pub h264: CodecBackendSupport,
+    pub hevc: CodecBackendSupport,
+    pub vp9: CodecBackendSupport,
+    pub av1: CodecBackendSupport,
+}
+
 fn default_crf() -> u32 {
     23
 }
@@ -171,6 +363,10 @@ fn default_microphone_gain_percent() -> u16 {
     100
 }
 
+fn default_disk_space_threshold_mb() -> u64 {
+    DEFAULT_DISK_SPACE_THRESHOLD_MB
+}
+
 fn default_quality_mode() -> QualityMode {
     QualityMode::Balanced
 }
@@ -219,8 +415,32 @@ pub fn is_capture_supported(state: State<AppState>) -> bool {
 }
 
 #[tauri::command]
-pub fn get_targets(state: State<AppState>) -> Result<Vec<CaptureTarget>, String> {
-    lock_capture(&state)?.get_targets()
+pub fn get_targets(
+    state: State<AppState>,
+    filter: Option<TargetFilter>,
+) -> Result<Vec<CaptureTarget>, String> {
+    let filter = filter.unwrap_or_default();
+    Ok(lock_capture(&state)?
+        .get_targets()?
+        .into_iter()
+        .filter(|target| filter.matches(target))
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_target_thumbnail(
+    target_id: u32,
+    max_width: Option<u32>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        target_thumbnail::target_thumbnail(
+            target_id,
+            max_width.unwrap_or(DEFAULT_THUMBNAIL_MAX_WIDTH),
+        )
+        .map(|thumbnail_path| thumbnail_path.to_string_lossy().into_owned())
+    })
+    .await
+    .map_err(|err| format!("No se pudo generar la miniatura: {err}"))?
 }
 
 #[tauri::command]
@@ -229,13 +449,76 @@ pub fn get_audio_input_devices() -> Result<Vec<String>, String> {
 }
 
 #[tauri::command]
-pub fn get_video_encoder_capabilities() -> VideoEncoderCapabilitiesSnapshot {
-    let capabilities = detect_video_encoder_capabilities();
+pub fn get_audio_output_devices() -> Result<Vec<String>, String> {
+    list_system_audio_output_devices()
+}
+
+#[tauri::command]
+pub fn get_ffmpeg_info() -> FfmpegInfo {
+    ffmpeg_info::get_ffmpeg_info()
+}
+
+#[tauri::command]
+pub fn estimate_bitrate_kbps(
+    width: u32,
+    height: u32,
+    fps: u32,
+    codec: VideoCodec,
+    quality_mode: QualityMode,
+) -> Result<u32, String> {
+    if width == 0 || height == 0 {
+        return Err("El ancho y el alto deben ser mayores a 0".to_string());
+    }
+    Ok(estimate_target_bitrate_kbps(
+        width,
+        height,
+        fps,
+        &codec,
+        &quality_mode,
+    ))
+}
+
+#[tauri::command]
+pub fn get_video_encoder_capabilities(refresh: Option<bool>) -> VideoEncoderCapabilitiesSnapshot {
+    video_encoder_capabilities_snapshot(get_video_encoder_capabilities_cached(
+        refresh.unwrap_or(false),
+    ))
+}
+
+#[tauri::command]
+pub fn redetect_video_encoder_capabilities() -> VideoEncoderCapabilitiesSnapshot {
+    video_encoder_capabilities_snapshot(get_video_encoder_capabilities_cached(true))
+}
+
+fn video_encoder_capabilities_snapshot(
+    capabilities: VideoEncoderCapabilities,
+) -> VideoEncoderCapabilitiesSnapshot {
     VideoEncoderCapabilitiesSnapshot {
-        nvenc: capabilities.nvenc,
-        amf: capabilities.amf,
-        qsv: capabilities.qsv,
-        software: capabilities.software,
+        h264: CodecBackendSupport {
+            nvenc: capabilities.nvenc,
+            amf: capabilities.amf,
+            qsv: capabilities.qsv,
+            software: capabilities.software,
+        },
+        hevc: CodecBackendSupport {
+            nvenc: capabilities.hevc_nvenc,
+            amf: capabilities.hevc_amf,
+            qsv: capabilities.hevc_qsv,
+            software: capabilities.hevc_software,
+        },
+        // NVENC y AMF no tienen encoder VP9 en FFmpeg.
+        vp9: CodecBackendSupport {
+            nvenc: false,
+            amf: false,
+            qsv: capabilities.vp9_qsv,
+            software: capabilities.vp9_software,
+        },
+        av1: CodecBackendSupport {
+            nvenc: capabilities.av1_nvenc,
+            amf: capabilities.av1_amf,
+            qsv: capabilities.av1_qsv,
+            software: capabilities.av1_software,
+        },
     }
 }
 
@@ -263,7 +546,11 @@ pub fn set_global_shortcuts(
 
 #[cfg(test)]
 mod tests {
-    use super::normalize_native_region_for_target;
+    use super::{
+        build_encoder_config, clamp_session_fps, normalize_native_region_for_target,
+        stop_wait_timeout_ms, video_encoder_capabilities_snapshot, CodecBackendSupport,
+        RecordingSessionConfig, VideoEncoderCapabilities, DEFAULT_STOP_WAIT_TIMEOUT_MS,
+    };
     use crate::capture::models::{CaptureTarget, Region, TargetKind};
 
     fn monitor_target(
@@ -283,9 +570,47 @@ mod tests {
             screen_height,
             is_primary: true,
             kind: TargetKind::Monitor,
+            process_name: None,
+            process_id: None,
+            window_title: None,
+            refresh_rate_hz: None,
+            dpi_scale_percent: None,
+            is_minimized: false,
         }
     }
 
+    #[test]
+    fn valida_la_config_con_los_fps_ya_recortados_al_refresco() {
+        let mut config: RecordingSessionConfig = serde_json::from_value(serde_json::json!({
+            "targetId": 1,
+            "fps": 120,
+            "cropRegion": null,
+            "outputPath": "C:/Videos/toma.mp4",
+            "format": "mp4",
+            "codec": null,
+            "resolution": "native",
+            "interpolateToFps": 100,
+        }))
+        .expect("la config de prueba debe deserializar");
+        let mut target = monitor_target(1920, 1080, 1920, 1080);
+        target.refresh_rate_hz = Some(60);
+
+        assert!(build_encoder_config(&config).validate().is_err());
+        let warning = clamp_session_fps(&mut config, Some(&target));
+
+        assert_eq!(warning.map(|warning| warning.applied_fps), Some(60));
+        assert_eq!(config.fps, 60);
+        assert!(build_encoder_config(&config).validate().is_ok());
+    }
+
+    #[test]
+    fn espera_al_detener_usa_plazo_por_defecto_y_acotado() {
+        assert_eq!(stop_wait_timeout_ms(None), Ok(DEFAULT_STOP_WAIT_TIMEOUT_MS));
+        assert_eq!(stop_wait_timeout_ms(Some(5_000)), Ok(5_000));
+        assert!(stop_wait_timeout_ms(Some(0)).is_err());
+        assert!(stop_wait_timeout_ms(Some(7_200_000)).is_err());
+    }
+
     #[test]
     fn normaliza_region_de_monitor_con_escala_dpi() {
         let target = monitor_target(3840, 2160, 1920, 1080);
@@ -296,7 +621,7 @@ mod tests {
             height: 300,
         };
 
-        let normalized = normalize_native_region_for_target(selected_region, &target)
+        let normalized = normalize_native_region_for_target(selected_region, &target, 1.0)
             .expect("la region debe normalizarse");
 
         assert_eq!(normalized.x, 240);
@@ -315,7 +640,7 @@ mod tests {
             height: 40,
         };
 
-        let normalized = normalize_native_region_for_target(selected_region, &target)
+        let normalized = normalize_native_region_for_target(selected_region, &target, 1.0)
             .expect("la region debe ajustarse al borde");
 
         assert_eq!(normalized.x, 1910);
@@ -324,6 +649,98 @@ mod tests {
         assert_eq!(normalized.height, 5);
     }
 
+    #[test]
+    fn seleccion_en_monitor_4k_al_150_pasa_la_validacion() {
+        let target = monitor_target(3840, 2160, 2560, 1440);
+
+        let full = normalize_native_region_for_target(
+            Region {
+                x: 0,
+                y: 0,
+                width: 2560,
+                height: 1440,
+            },
+            &target,
+            1.0,
+        )
+        .expect("la region debe normalizarse");
+        assert_eq!(
+            (full.x, full.y, full.width, full.height),
+            (0, 0, 3840, 2160)
+        );
+        full.validate_against_target(&target)
+            .expect("la pantalla completa no debe rechazarse");
+
+        let corner = normalize_native_region_for_target(
+            Region {
+                x: 2333,
+                y: 1311,
+                width: 227,
+                height: 129,
+            },
+            &target,
+            1.0,
+        )
+        .expect("la region debe normalizarse");
+        assert_eq!(corner.x + corner.width, 3840);
+        assert_eq!(corner.y + corner.height, 2160);
+        corner
+            .validate_against_target(&target)
+            .expect("la esquina inferior derecha no debe rechazarse");
+    }
+
+    #[test]
+    fn seleccion_fisica_se_mapea_exacta_con_cada_escala() {
+        let selected_region = Region {
+            x: 301,
+            y: 157,
+            width: 999,
+            height: 555,
+        };
+        for (scale_factor, screen_width, screen_height) in [
+            (1.0, 1920, 1080),
+            (1.25, 1536, 864),
+            (1.5, 1280, 720),
+            (2.0, 960, 540),
+        ] {
+            let target = monitor_target(1920, 1080, screen_width, screen_height);
+            let normalized =
+                normalize_native_region_for_target(selected_region.clone(), &target, scale_factor)
+                    .expect("la region debe normalizarse");
+            assert_eq!(
+                (
+                    normalized.x,
+                    normalized.y,
+                    normalized.width,
+                    normalized.height
+                ),
+                (301, 157, 999, 555),
+                "escala {scale_factor}"
+            );
+        }
+
+        let target = monitor_target(3840, 2160, 2560, 1440);
+        let corner = normalize_native_region_for_target(
+            Region {
+                x: 3500,
+                y: 2000,
+                width: 340,
+                height: 160,
+            },
+            &target,
+            1.5,
+        )
+        .expect("la region debe normalizarse");
+        assert_eq!(
+            (corner.x, corner.y, corner.width, corner.height),
+            (3500, 2000, 340, 160)
+        );
+
+        let err = normalize_native_region_for_target(selected_region, &target, 0.0)
+            .expect_err("una escala nula debe rechazarse");
+        assert!(err.contains("escala"));
+    }
+
     #[test]
     fn rechaza_target_con_dimensiones_invalidas() {
         let target = monitor_target(1920, 1080, 0, 1080);
@@ -334,65 +751,847 @@ mod tests {
             height: 10,
         };
 
-        let err = normalize_native_region_for_target(selected_region, &target)
+        let err = normalize_native_region_for_target(selected_region, &target, 1.0)
             .expect_err("debe fallar cuando el target es invalido");
 
         assert!(err.contains("dimensiones invalidas"));
     }
+
+    #[test]
+    fn capacidades_se_agrupan_por_codec() {
+        let snapshot = video_encoder_capabilities_snapshot(VideoEncoderCapabilities {
+            nvenc: true,
+            software: true,
+            hevc_nvenc: true,
+            av1_qsv: true,
+            vp9_software: true,
+            ..VideoEncoderCapabilities::default()
+        });
+
+        assert_eq!(
+            snapshot.h264,
+            CodecBackendSupport {
+                nvenc: true,
+                amf: false,
+                qsv: false,
+                software: true,
+            }
+        );
+        assert!(snapshot.hevc.nvenc && !snapshot.hevc.software);
+        assert!(snapshot.av1.qsv && !snapshot.av1.nvenc);
+        assert!(snapshot.vp9.software && !snapshot.vp9.nvenc && !snapshot.vp9.amf);
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["hevc"]["nvenc"], true);
+        assert_eq!(json["h264"]["software"], true);
+    }
 }
 
 #[tauri::command]
 pub fn start_recording(
     state: State<AppState>,
     config: RecordingSessionConfig,
-) -> Result<(), String> {
-    let encoder_config = EncoderConfig {
+) -> Result<RecordingStartReport, String> {
+    start_recording_session(&state, config)
+}
+
+fn build_encoder_config(config: &RecordingSessionConfig) -> EncoderConfig {
+    EncoderConfig {
         output_path: PathBuf::from(&config.output_path),
-        format: config.format,
-        codec: config.codec,
-        video_encoder_preference: config.video_encoder_preference,
-        resolution: config.resolution,
+        output_template: config.output_template.clone(),
+        format: config.format.clone(),
+        codec: config.codec.clone(),
+        video_encoder_preference: config.video_encoder_preference.clone(),
+        resolution: config.resolution.clone(),
+        never_upscale: config.never_upscale,
         crf: config.crf,
-        preset: config.preset,
-        quality_mode: config.quality_mode,
+        preset: config.preset.clone(),
+        quality_mode: config.quality_mode.clone(),
+        color_range: config.color_range.clone(),
+        hdr: config.hdr,
+        scaler: config.scaler,
+        chroma_subsampling: config.chroma_subsampling,
+        bit_depth: config.bit_depth,
+        profile: config.profile.clone(),
+        level: config.level.clone(),
+        blur_regions: config.blur_regions.clone(),
         fps: config.fps,
         audio: AudioCaptureConfig {
             capture_system_audio: config.capture_system_audio,
             capture_microphone_audio: config.capture_microphone_audio,
-            system_audio_device: config.system_audio_device,
-            microphone_device: config.microphone_device,
+            system_audio_device: config.system_audio_device.clone(),
+            microphone_device: config.microphone_device.clone(),
             microphone_gain_percent: config.microphone_gain_percent,
+            audio_sync_offset_ms: config.audio_sync_offset_ms,
+            audio_sample_rate: config.audio_sample_rate,
+            audio_channels: config.audio_channels,
+            mic_noise_suppression: config.mic_noise_suppression,
+            normalize_loudness: config.normalize_loudness,
+            loudness_target_lufs: config.loudness_target_lufs,
+            audio_codec: config.audio_codec,
+            audio_bitrate_kbps: config.audio_bitrate_kbps,
+            allow_opus_in_mp4: config.allow_opus_in_mp4,
+            monitor_microphone: config.monitor_microphone,
+        },
+        disk_space_threshold_mb: config.disk_space_threshold_mb,
+        fat32_policy: config.fat32_policy.clone(),
+        gif: GifExportConfig {
+            fps: config.gif_fps,
+            max_width: config.gif_max_width,
+        },
+        fit_to_size_mb: config.fit_to_size_mb,
+        interpolate_to_fps: config.interpolate_to_fps,
+        mp4_faststart: config.mp4_faststart,
+        keyframe_interval_frames: config.keyframe_interval_frames,
+        frame_rate_mode: if config.force_cfr {
+            FrameRateMode::Cfr
+        } else {
+            config.frame_rate_mode
         },
+        outputs: config.outputs.clone(),
+        trim_start_ms: config.trim_start_ms,
+        trim_end_ms: config.trim_end_ms,
+        hls_segment_seconds: config.hls_segment_seconds,
+        target_name: None,
+        project: config.project.clone(),
+        metadata: RecordingMetadata {
+            title: config.title.clone(),
+            comment: config.comment.clone(),
+            author: config.author.clone(),
+        },
+        chapter_markers: ChapterMarkerLog::default(),
+        frame_snapshot: frame_snapshot::FrameSnapshotRequest::default(),
+        cursor_overlay: None,
+        hdr_metadata: None,
+        gpu_frame_route: GpuFrameRoute::default(),
+    }
+}
+
+pub(crate) fn validate_recording_session(
+    state: &AppState,
+    config: &RecordingSessionConfig,
+) -> Result<(), String> {
+    let target = find_target(state, config.target_id)?;
+    let mut config = config.clone();
+    clamp_session_fps(&mut config, Some(&target));
+    let encoder_config = build_encoder_config(&config);
+    encoder_config.validate()?;
+    check_session_file_size_limits(&encoder_config)?;
+    if let Some(project) = &config.project {
+        project_folder_name(project)?;
+    }
+
+    if let Some(region) = &config.crop_region {
+        region.validate_against_target(&target)?;
+    }
+
+    Ok(())
+}
+
+/// Baja `config.fps` al refresco del monitor del target. Tiene que ir antes
+/// de armar y validar el `EncoderConfig`: la interpolación y el intervalo de
+/// keyframes se validan contra los FPS con los que se va a grabar.
+fn clamp_session_fps(
+    config: &mut RecordingSessionConfig,
+    target: Option<&CaptureTarget>,
+) -> Option<FpsClampWarning> {
+    let (fps, warning) =
+        clamp_fps_to_refresh_rate(config.fps, target.and_then(|target| target.refresh_rate_hz));
+    config.fps = fps;
+    warning
+}
+
+fn collect_recording_config_issues(
+    state: &AppState,
+    config: &RecordingSessionConfig,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let target = find_target(state, config.target_id);
+    let mut config = config.clone();
+    let fps_warning = clamp_session_fps(&mut config, target.as_ref().ok());
+    let config = &config;
+    let encoder_config = build_encoder_config(config);
+
+    if let Err(err) = encoder_config.validate() {
+        issues.push(ValidationIssue::error("invalidConfig", err));
+    }
+    if let Some(target_fps) = encoder_config.interpolate_to_fps {
+        issues.push(ValidationIssue::warning(
+            "interpolationArtifacts",
+            format!(
+                "La interpolación a {target_fps} FPS inventa frames intermedios: puede haber fantasmas o deformaciones en texto y movimientos rápidos"
+            ),
+        ));
+    }
+    if config.capture_microphone_audio
+        && config
+            .mic_noise_suppression
+            .is_some_and(|level| level != MicNoiseSuppression::Off)
+    {
+        issues.push(ValidationIssue::warning(
+            "noiseSuppressionCpu",
+            "La supresión de ruido del micrófono (afftdn) agrega carga de CPU al procesar el audio al terminar",
+        ));
+    }
+    if let Some(project) = &config.project {
+        if let Err(err) = project_folder_name(project) {
+            issues.push(ValidationIssue::error("invalidProject", err));
+        }
+    }
+
+    let target_found = match target {
+        Ok(target) => {
+            if let Some(warning) = fps_warning {
+                issues.push(ValidationIssue::warning(
+                    "fpsAboveRefreshRate",
+                    format!(
+                        "El monitor refresca a {} Hz: se grabará a {} FPS en vez de {}, porque por encima solo se repetirían frames",
+                        warning.refresh_rate_hz, warning.applied_fps, warning.requested_fps
+                    ),
+                ));
+            }
+            if let Some(region) = &config.crop_region {
+                if let Err(err) = region.validate_against_target(&target) {
+                    issues.push(ValidationIssue::error("regionOutOfBounds", err));
+                }
+            }
+            true
+        }
+        Err(err) => {
+            issues.push(ValidationIssue::error("targetNotFound", err));
+            false
+        }
     };
 
+    issues.extend(check_preferred_encoder(
+        &encoder_config.effective_codec(),
+        &encoder_config.video_encoder_preference,
+        &get_video_encoder_capabilities_cached(false),
+    ));
+
+    match resolve_session_output_path(state, config, false) {
+        Ok(output_path) if stream_output_url(&output_path).is_some() => {}
+        Ok(output_path) => {
+            let output_dir = if is_directory_output_path(&output_path) {
+                output_path.clone()
+            } else {
+                output_path
+                    .parent()
+                    .map(Path::to_path_buf)
+                    .unwrap_or_default()
+            };
+            issues.extend(check_output_dir_writable(&output_dir));
+
+            let resolved_config = EncoderConfig {
+                output_path,
+                ..encoder_config.clone()
+            };
+            match check_session_file_size_limits(&resolved_config) {
+                Ok(warnings) => issues.extend(warnings.into_iter().map(|warning| {
+                    ValidationIssue::warning(
+                        "fileSizeLimit",
+                        format!(
+                            "'{}' está en {} y no admite archivos de más de {} MB; la grabación se partirá en otro archivo al acercarse al límite",
+                            warning.path,
+                            warning.filesystem,
+                            warning.limit_bytes / (1024 * 1024)
+                        ),
+                    )
+                })),
+                Err(err) => issues.push(ValidationIssue::error("fileSizeLimit", err)),
+            }
+
+            if target_found {
+                match preflight_session_disk_space(state, config, &resolved_config) {
+                    Ok(warnings) => issues.extend(warnings.into_iter().map(|warning| {
+                        ValidationIssue::warning(
+                            "lowDiskSpace",
+                            format!(
+                                "Quedan {} MB libres en '{}'; se estiman {} MB para {} minutos de grabación",
+                                warning.available_bytes / (1024 * 1024),
+                                warning.path,
+                                warning.estimated_required_bytes / (1024 * 1024),
+                                warning.assumed_duration_minutes
+                            ),
+                        )
+                    })),
+                    Err(err) => issues.push(ValidationIssue::error("lowDiskSpace", err)),
+                }
+            }
+        }
+        Err(err) => issues.push(ValidationIssue::error("outputPathInvalid", err)),
+    }
+
+    if config.capture_system_audio {
+        match list_system_audio_output_devices() {
+            Ok(devices) => issues.extend(check_audio_device(
+                "systemAudioDeviceNotFound",
+                "audio del sistema",
+                config.system_audio_device.as_deref(),
+                &devices,
+            )),
+            Err(err) => issues.push(ValidationIssue::error("systemAudioDeviceNotFound", err)),
+        }
+    }
+    if config.capture_microphone_audio {
+        match list_microphone_input_devices() {
+            Ok(devices) => issues.extend(check_audio_device(
+                "microphoneDeviceNotFound",
+                "micrófono",
+                config.microphone_device.as_deref(),
+                &devices,
+            )),
+            Err(err) => issues.push(ValidationIssue::error("microphoneDeviceNotFound", err)),
+        }
+    }
+
+    let needs_ffmpeg_cli = encoder_config.format == OutputFormat::Gif
+        || encoder_config.fit_to_size_mb.is_some()
+        || encoder_config.interpolate_to_fps.is_some();
+    if needs_ffmpeg_cli {
+        issues.extend(check_ffmpeg_bin(
+            &resolve_ffmpeg_bin_with(config.ffmpeg_bin_path.as_deref().map(Path::new)),
+            env::var_os("PATH").as_deref(),
+        ));
+    }
+
+    issues
+}
+
+fn find_target(state: &AppState, target_id: u32) -> Result<CaptureTarget, String> {
+    lock_capture(state)?
+        .get_targets()?
+        .into_iter()
+        .find(|target| target.id == target_id)
+        .ok_or_else(|| format!("No se encontró un target con id {target_id}"))
+}
+
+fn resolve_session_output_path(
+    state: &AppState,
+    config: &RecordingSessionConfig,
+    reserve_take: bool,
+) -> Result<PathBuf, String> {
+    let output_path = match config.output_target.publish_url()? {
+        Some(publish_url) => PathBuf::from(publish_url),
+        None => PathBuf::from(&config.output_path),
+    };
+    if stream_output_url(&output_path).is_some() {
+        if config.project.is_some() {
+            return Err(
+                "Un proyecto guarda tomas en disco; no se puede usar con una salida de stream"
+                    .to_string(),
+            );
+        }
+        return Ok(output_path);
+    }
+
+    if let Some(project) = &config.project {
+        let base_dir = if is_directory_output_path(&output_path) {
+            output_path
+        } else {
+            output_path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default()
+        };
+        let date_dir = project_date_dir(&base_dir, project, chrono::Local::now().date_naive())?;
+        let extension = config.format.file_extension();
+
+        return if reserve_take {
+            reserve_take_path(&date_dir, extension)
+        } else {
+            Ok(peek_next_take_path(&date_dir, extension))
+        };
+    }
+
+    if !is_directory_output_path(&output_path) {
+        return Ok(output_path);
+    }
+
+    let target = find_target(state, config.target_id)?;
+    let (width, height) = session_output_dimensions(state, &target, config)?;
+
+    resolve_templated_output_path(
+        &output_path,
+        config
+            .output_template
+            .as_deref()
+            .unwrap_or(DEFAULT_OUTPUT_TEMPLATE),
+        config.format.file_extension(),
+        &OutputNameContext {
+            target_name: &target.name,
+            fps: config.fps,
+            width,
+            height,
+            now: chrono::Local::now(),
+        },
+    )
+}
+
+fn session_output_dimensions(
+    state: &AppState,
+    target: &CaptureTarget,
+    config: &RecordingSessionConfig,
+) -> Result<(u32, u32), String> {
+    let (mut source_width, source_height) = config
+        .crop_region
+        .as_ref()
+        .map(|region| (region.width, region.height))
+        .unwrap_or((target.width, target.height));
+    if let Some(secondary_id) = config.secondary_target_id {
+        source_width += find_target(state, secondary_id)?.width;
+    }
+    Ok(config
+        .resolution
+        .output_dimensions(source_width, source_height, config.never_upscale))
+}
+
+fn preflight_session_disk_space(
+    state: &AppState,
+    config: &RecordingSessionConfig,
+    encoder_config: &EncoderConfig,
+) -> Result<Vec<DiskSpaceWarning>, String> {
+    if encoder_config.stream_output_url().is_some() {
+        return Ok(Vec::new());
+    }
+
+    let target = find_target(state, config.target_id)?;
+    let (width, height) = session_output_dimensions(state, &target, config)?;
+    let bitrate_kbps = estimate_target_bitrate_kbps(
+        width,
+        height,
+        encoder_config.fps,
+        &encoder_config.effective_codec(),
+        &encoder_config.quality_mode,
+    );
+
+    preflight_disk_space(
+        &[encoder_config.output_path.clone(), temp_output_base_dir()],
+        bitrate_kbps,
+        encoder_config.disk_space_threshold_mb,
+    )
+}
+
+fn check_session_file_size_limits(
+    encoder_config: &EncoderConfig,
+) -> Result<Vec<FileSizeLimitWarning>, String> {
+    if encoder_config.stream_output_url().is_some() {
+        return Ok(Vec::new());
+    }
+
+    check_file_size_limits(
+        &[encoder_config.output_path.clone(), temp_output_base_dir()],
+        &encoder_config.fat32_policy,
+        &SystemVolumeInfo,
+    )
+}
+
+#[tauri::command]
+pub fn validate_recording_config(
+    state: State<AppState>,
+    config: RecordingSessionConfig,
+) -> Vec<ValidationIssue> {
+    collect_recording_config_issues(&state, &config)
+}
+
+#[tauri::command]
+pub async fn validate_session(
+    state: State<'_, AppState>,
+    mut config: RecordingSessionConfig,
+) -> Result<SessionCheckReport, String> {
+    let target = find_target(&state, config.target_id);
+    clamp_session_fps(&mut config, target.as_ref().ok());
+    let encoder_config = build_encoder_config(&config);
+    let target = target.and_then(|target| {
+        let source = match &config.crop_region {
+            Some(region) => {
+                region.validate_against_target(&target)?;
+                (region.width, region.height)
+            }
+            None => (target.width, target.height),
+        };
+        Ok((target.name, source))
+    });
+
+    tauri::async_runtime::spawn_blocking(move || session_checks(&encoder_config, target))
+        .await
+        .map_err(|err| format!("No se pudo validar la sesión: {err}"))
+}
+
+fn session_checks(
+    config: &EncoderConfig,
+    target: Result<(String, (u32, u32)), String>,
+) -> SessionCheckReport {
+    let mut checks = vec![SessionCheck::from_result(
+        "config",
+        config
+            .validate()
+            .map(|_| "La configuración es válida".to_string()),
+    )];
+
+    let source = target.as_ref().ok().map(|(_, source)| *source);
+    checks.push(SessionCheck::from_result(
+        "target",
+        target.map(|(name, _)| name),
+    ));
+
+    let encoder = match source {
+        Some(source) => encoder_probe_size(config, source).and_then(|(width, height)| {
+            probe_video_encoder(config, width, height)
+                .map(|encoder_name| format!("{encoder_name} a {width}x{height}"))
+        }),
+        None => Err("Sin target no se puede probar el encoder".to_string()),
+    };
+    checks.push(SessionCheck::from_result("videoEncoder", encoder));
+
+    if config.audio.capture_system_audio {
+        checks.push(SessionCheck::from_result(
+            "systemAudio",
+            resolve_system_audio_device(config.audio.system_audio_device.as_deref()),
+        ));
+    }
+    if config.audio.capture_microphone_audio {
+        checks.push(SessionCheck::from_result(
+            "microphone",
+            resolve_microphone_device(config.audio.microphone_device.as_deref()),
+        ));
+    }
+
+    SessionCheckReport::new(checks)
+}
+
+#[tauri::command]
+pub fn resolve_output_path(
+    state: State<AppState>,
+    config: RecordingSessionConfig,
+) -> Result<String, String> {
+    resolve_session_output_path(&state, &config, false)
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+pub(crate) fn start_recording_session(
+    state: &AppState,
+    mut config: RecordingSessionConfig,
+) -> Result<RecordingStartReport, String> {
+    let target = find_target(state, config.target_id).ok();
+    let fps_warning = clamp_session_fps(&mut config, target.as_ref());
+    let mut encoder_config = build_encoder_config(&config);
+
     encoder_config.validate()?;
+    set_configured_ffmpeg_bin(config.ffmpeg_bin_path.as_ref().map(PathBuf::from));
+    encoder_config.output_path = resolve_session_output_path(state, &config, true)?;
+    encoder_config.output_template = None;
+
+    let reserved_take = config
+        .project
+        .is_some()
+        .then(|| encoder_config.output_path.clone());
+    encoder_config.target_name = target.map(|target| target.name);
+
+    let result = start_with_output_path(state, config, encoder_config, fps_warning);
+    if result.is_err() {
+        if let Some(path) = reserved_take {
+            release_take_reservation(&path);
+        }
+    }
+
+    result
+}
+
+fn start_with_output_path(
+    state: &AppState,
+    config: RecordingSessionConfig,
+    encoder_config: EncoderConfig,
+    fps_warning: Option<FpsClampWarning>,
+) -> Result<RecordingStartReport, String> {
+    let disk_space_warnings = preflight_session_disk_space(state, &config, &encoder_config)?;
+    let file_size_limit_warnings = check_session_file_size_limits(&encoder_config)?;
+
+    let mut manager = lock_capture(state)?;
+    manager.refresh_runtime_state();
+    if manager.is_active() {
+        return Err("Ya existe una grabación en curso".to_string());
+    }
 
     apply_audio_capture_config(&encoder_config.audio);
     // La etiqueta del backend debe reflejar el encoder realmente abierto,
     // no solo la preferencia seleccionada por el usuario.
     set_live_video_encoder_label(None);
     set_processing(false);
+    reset_pipeline_latency();
+    set_live_disk_space_status(None);
+    set_live_encoded_duration_ms(0);
 
     let session_config = SessionConfig {
         target_id: config.target_id,
+        secondary_target_id: config.secondary_target_id,
         fps: config.fps,
         crop_region: config.crop_region,
         capture_resolution_preset: resolve_capture_resolution_preset(
             &encoder_config.resolution,
             &encoder_config.quality_mode,
         ),
+        auto_pause_idle_ms: config.auto_pause_idle_ms,
+        idle_tracker: None,
+        follow_window_monitor: config.follow_window_monitor,
+        adaptive_fps: config.adaptive_fps,
+        cursor_scale: config.cursor_scale,
+        show_region_border: config.show_region_border,
+        exclude_window_ids: config.exclude_window_ids,
+        include_secondary_windows: config.include_secondary_windows,
+        restore_if_minimized: config.restore_if_minimized,
+        pipeline_load: None,
+        recording_clock: None,
         encoder_config,
     };
 
-    let mut manager = lock_capture(&state)?;
-    if let Err(err) = manager.start(session_config) {
-        set_live_video_encoder_label(None);
-        return Err(err);
-    }
+    let session_serial = match manager.start(session_config) {
+        Ok(session_serial) => session_serial,
+        Err(err) => {
+            set_live_video_encoder_label(None);
+            return Err(err);
+        }
+    };
+
+    Ok(RecordingStartReport {
+        disk_space_warnings,
+        file_size_limit_warnings,
+        fps_warning,
+        session_serial,
+    })
+}
+
+#[tauri::command]
+pub fn schedule_recording(
+    state: State<AppState>,
+    config: RecordingSessionConfig,
+    start_at_epoch_ms: u64,
+    max_duration_ms: Option<u64>,
+) -> Result<u64, String> {
+    validate_recording_session(&state, &config)?;
+
+    let guard = state
+        .recording_scheduler
+        .lock()
+        .map_err(|_| SCHEDULER_LOCK_ERR.to_string())?;
+    let scheduler = guard
+        .as_ref()
+        .ok_or_else(|| "El programador de grabaciones no está inicializado".to_string())?;
+
+    scheduler.schedule(config, start_at_epoch_ms, max_duration_ms)
+}
+
+#[tauri::command]
+pub fn cancel_scheduled_recording(state: State<AppState>, id: u64) -> Result<(), String> {
+    let guard = state
+        .recording_scheduler
+        .lock()
+        .map_err(|_| SCHEDULER_LOCK_ERR.to_string())?;
+    let scheduler = guard
+        .as_ref()
+        .ok_or_else(|| "El programador de grabaciones no está inicializado".to_string())?;
+
+    scheduler.cancel(id)
+}
+
+#[tauri::command]
+pub async fn analyze_recording_quality(
+    path: String,
+    reference_seconds: Option<u32>,
+) -> Result<QualityReport, String> {
+    let reference_seconds = reference_seconds.unwrap_or(DEFAULT_REFERENCE_SECONDS);
+    tauri::async_runtime::spawn_blocking(move || {
+        quality_analysis::analyze_recording_quality(Path::new(&path), reference_seconds)
+    })
+    .await
+    .map_err(|err| format!("No se pudo analizar la calidad: {err}"))?
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn export_animation(
+    input_path: String,
+    output_path: String,
+    start_ms: Option<u64>,
+    end_ms: Option<u64>,
+    format: AnimationFormat,
+    fps: Option<u32>,
+    max_width: Option<u32>,
+) -> Result<String, String> {
+    let request = AnimationExportRequest {
+        start_ms,
+        end_ms,
+        format,
+        fps,
+        max_width,
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        animation_export::export_animation(
+            Path::new(&input_path),
+            Path::new(&output_path),
+            &request,
+        )
+        .map(|_| output_path)
+    })
+    .await
+    .map_err(|err| format!("No se pudo exportar la animación: {err}"))?
+}
+
+#[tauri::command]
+pub async fn trim_recording(
+    input_path: String,
+    start_ms: Option<u64>,
+    end_ms: Option<u64>,
+    output_path: String,
+    precise: Option<bool>,
+) -> Result<TrimResult, String> {
+    let range = TrimRange { start_ms, end_ms };
+    tauri::async_runtime::spawn_blocking(move || {
+        recording_trim::trim_recording(
+            Path::new(&input_path),
+            Path::new(&output_path),
+            range,
+            precise.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|err| format!("No se pudo recortar la grabación: {err}"))?
+}
+
+#[tauri::command]
+pub async fn remux_recording(
+    input_path: String,
+    target_format: OutputFormat,
+    output_path: String,
+) -> Result<RemuxResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        remux::remux_recording(
+            Path::new(&input_path),
+            &target_format,
+            Path::new(&output_path),
+        )
+    })
+    .await
+    .map_err(|err| format!("No se pudo cambiar el contenedor: {err}"))?
+}
+
+#[tauri::command]
+pub async fn get_project_takes(
+    app: AppHandle,
+    project: String,
+    base_dir: Option<String>,
+) -> Result<Vec<ProjectTake>, String> {
+    let base_dir = match base_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => app
+            .path()
+            .video_dir()
+            .map_err(|err| format!("No se pudo resolver la carpeta de videos: {err}"))?,
+    };
 
+    tauri::async_runtime::spawn_blocking(move || list_project_takes(&base_dir, &project))
+        .await
+        .map_err(|err| format!("No se pudieron listar las tomas: {err}"))?
+}
+
+/// Las carpetas temporales de la sesión en curso o de un mux pendiente se
+/// verían como abandonadas: solo se buscan con la app en reposo.
+fn ensure_no_session_in_progress(state: &AppState) -> Result<(), String> {
+    if lock_capture(state)?.is_active() || is_processing() {
+        return Err(
+            "Hay una grabación o un procesamiento en curso; espera a que termine para recuperar sesiones"
+                .to_string(),
+        );
+    }
     Ok(())
 }
 
+#[tauri::command]
+pub async fn list_recoverable_sessions(
+    state: State<'_, AppState>,
+) -> Result<Vec<RecoverableSession>, String> {
+    ensure_no_session_in_progress(&state)?;
+    tauri::async_runtime::spawn_blocking(|| find_recoverable_sessions(&session_temp_roots()))
+        .await
+        .map_err(|err| format!("No se pudieron buscar sesiones: {err}"))
+}
+
+#[tauri::command]
+pub async fn recover_sessions(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    destination: Option<String>,
+) -> Result<Vec<RecoveredSession>, String> {
+    ensure_no_session_in_progress(&state)?;
+    let destination = match destination {
+        Some(dir) => PathBuf::from(dir),
+        None => app
+            .path()
+            .video_dir()
+            .map_err(|err| format!("No se pudo resolver la carpeta de videos: {err}"))?
+            .join(RECOVERY_DIR_NAME),
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        find_recoverable_sessions(&session_temp_roots())
+            .iter()
+            .map(|session| recover_session(session, &destination))
+            .collect()
+    })
+    .await
+    .map_err(|err| format!("No se pudieron recuperar las sesiones: {err}"))
+}
+
+#[tauri::command]
+pub fn get_recent_recordings(limit: Option<usize>) -> Result<Vec<RecordingHistoryEntry>, String> {
+    recording_history::get_recent_recordings(limit.unwrap_or(MAX_HISTORY_ENTRIES))
+}
+
+#[tauri::command]
+pub fn clear_recording_history() -> Result<(), String> {
+    recording_history::clear_recording_history()
+}
+
+#[tauri::command]
+pub fn reveal_in_file_manager(path: String) -> Result<(), String> {
+    file_manager::reveal_in_file_manager(&path)
+}
+
+#[tauri::command]
+pub async fn get_output_file_info(path: String) -> Result<OutputFileInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || probe_output_file(Path::new(&path)))
+        .await
+        .map_err(|err| format!("No se pudo inspeccionar el archivo: {err}"))?
+}
+
+#[tauri::command]
+pub async fn generate_thumbnail(
+    path: String,
+    at_ms: Option<u64>,
+    max_width: Option<u32>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let video_path = Path::new(&path);
+        let at_ms = match at_ms {
+            Some(at_ms) => at_ms,
+            None => default_thumbnail_at_ms(probe_output_file(video_path)?.duration_ms),
+        };
+        thumbnail::generate_thumbnail(
+            video_path,
+            at_ms,
+            max_width.unwrap_or(DEFAULT_THUMBNAIL_MAX_WIDTH),
+        )
+        .map(|thumbnail_path| thumbnail_path.to_string_lossy().into_owned())
+    })
+    .await
+    .map_err(|err| format!("No se pudo generar la miniatura: {err}"))?
+}
+
+#[tauri::command]
+pub fn cancel_post_processing(id: Option<u64>) -> Result<usize, String> {
+    cancel_post_process_jobs(id)
+}
+
 #[tauri::command]
 pub fn update_recording_audio_capture(
     state: State<AppState>,
@@ -408,6 +1607,31 @@ pub fn update_recording_audio_capture(
     update_live_audio_capture(config.capture_system_audio, config.capture_microphone_audio)
 }
 
+#[tauri::command]
+pub fn set_recording_microphone_monitoring(
+    state: State<AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut manager = lock_capture(&state)?;
+    manager.refresh_runtime_state();
+    if !manager.is_active() {
+        return Err("No hay una grabación activa para escuchar el micrófono".to_string());
+    }
+
+    set_live_microphone_monitoring(enabled)
+}
+
+#[tauri::command]
+pub fn set_microphone_gain(state: State<AppState>, percent: u16) -> Result<(), String> {
+    let mut manager = lock_capture(&state)?;
+    manager.refresh_runtime_state();
+    if !manager.is_active() {
+        return Err("No hay una grabación activa para ajustar el micrófono".to_string());
+    }
+
+    set_live_microphone_gain(percent)
+}
+
 #[tauri::command]
 pub fn pause_recording(state: State<AppState>) -> Result<(), String> {
     lock_capture(&state)?.pause()
@@ -419,11 +1643,71 @@ pub fn resume_recording(state: State<AppState>) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub fn stop_recording(state: State<AppState>) -> Result<(), String> {
-    lock_capture(&state)?.stop()?;
+pub fn stop_recording(state: State<AppState>) -> Result<String, String> {
+    stop_recording_session(&state).map(|path| path.to_string_lossy().into_owned())
+}
+
+const DEFAULT_STOP_WAIT_TIMEOUT_MS: u64 = 300_000;
+const MAX_STOP_WAIT_TIMEOUT_MS: u64 = 3_600_000;
+
+#[tauri::command]
+pub async fn stop_recording_blocking(
+    state: State<'_, AppState>,
+    timeout_ms: Option<u64>,
+) -> Result<String, String> {
+    let timeout_ms = stop_wait_timeout_ms(timeout_ms)?;
+    let output_path = stop_recording_session(&state)?
+        .to_string_lossy()
+        .into_owned();
+    let finished = tauri::async_runtime::spawn_blocking(move || {
+        wait_for_processing_idle(Duration::from_millis(timeout_ms))
+    })
+    .await
+    .map_err(|err| format!("No se pudo esperar el procesamiento: {err}"))?;
+
+    if !finished {
+        return Err(format!(
+            "El procesamiento no terminó en {timeout_ms} ms; el archivo final quedará en {output_path}"
+        ));
+    }
+    Ok(output_path)
+}
+
+fn stop_wait_timeout_ms(timeout_ms: Option<u64>) -> Result<u64, String> {
+    let timeout_ms = timeout_ms.unwrap_or(DEFAULT_STOP_WAIT_TIMEOUT_MS);
+    if !(1..=MAX_STOP_WAIT_TIMEOUT_MS).contains(&timeout_ms) {
+        return Err(format!(
+            "timeoutMs debe estar entre 1 y {MAX_STOP_WAIT_TIMEOUT_MS}"
+        ));
+    }
+    Ok(timeout_ms)
+}
+
+pub(crate) fn stop_recording_session(state: &AppState) -> Result<PathBuf, String> {
+    let output_path = lock_capture(state)?.stop()?;
     set_live_video_encoder_label(None);
     set_processing(false);
-    Ok(())
+    Ok(output_path)
+}
+
+#[tauri::command]
+pub fn get_last_output_path(state: State<AppState>) -> Result<Option<String>, String> {
+    let mut manager = lock_capture(&state)?;
+    manager.refresh_runtime_state();
+    Ok(manager
+        .last_output_path()
+        .map(|path| path.to_string_lossy().into_owned()))
+}
+
+#[tauri::command]
+pub fn get_last_output_paths(state: State<AppState>) -> Result<Vec<String>, String> {
+    let mut manager = lock_capture(&state)?;
+    manager.refresh_runtime_state();
+    Ok(manager
+        .last_output_paths()
+        .into_iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
 }
 
 #[tauri::command]
@@ -434,6 +1718,39 @@ pub fn cancel_recording(state: State<AppState>) -> Result<(), String> {
     Ok(())
 }
 
+#[tauri::command]
+pub fn add_chapter_marker(
+    state: State<AppState>,
+    label: Option<String>,
+) -> Result<ChapterMarker, String> {
+    add_chapter_marker_to_session(&state, label)
+}
+
+pub(crate) fn add_chapter_marker_to_session(
+    state: &AppState,
+    label: Option<String>,
+) -> Result<ChapterMarker, String> {
+    lock_capture(state)?.add_chapter_marker(label)
+}
+
+#[tauri::command]
+pub async fn copy_frame_to_clipboard(state: State<'_, AppState>) -> Result<(), String> {
+    let source = lock_capture(&state)?.frame_snapshot_source()?;
+    tauri::async_runtime::spawn_blocking(move || copy_session_frame(&source))
+        .await
+        .map_err(|err| format!("No se pudo copiar el frame: {err}"))?
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(crate) fn copy_frame_of_session_to_clipboard(state: &AppState) -> Result<(), String> {
+    let source = lock_capture(state)?.frame_snapshot_source()?;
+    copy_session_frame(&source)
+}
+
+fn copy_session_frame(source: &frame_snapshot::FrameSnapshotSource) -> Result<(), String> {
+    frame_snapshot::copy_frame_to_clipboard(&source.grab()?)
+}
+
 #[tauri::command]
 pub fn get_recording_status(state: State<AppState>) -> CaptureManagerSnapshot {
     match lock_capture(&state) {
@@ -442,6 +1759,15 @@ pub fn get_recording_status(state: State<AppState>) -> CaptureManagerSnapshot {
             let mut snapshot = manager.snapshot();
             snapshot.video_encoder_label = get_live_video_encoder_label();
             snapshot.is_processing = is_processing();
+            snapshot.processing_eta_ms = processing_eta_ms();
+            snapshot.post_processing_jobs = get_post_process_jobs();
+            if snapshot.state != CaptureState::Idle {
+                snapshot.pipeline_latency_ms = get_pipeline_latency();
+                if let Some(disk_space) = get_live_disk_space_status() {
+                    snapshot.disk_space_remaining_bytes = Some(disk_space.remaining_bytes);
+                    snapshot.estimated_minutes_left = disk_space.estimated_minutes_left;
+                }
+            }
             snapshot
         }
         Err(err) => CaptureManagerSnapshot {
@@ -450,6 +1776,16 @@ pub fn get_recording_status(state: State<AppState>) -> CaptureManagerSnapshot {
             last_error: Some(err),
             video_encoder_label: None,
             is_processing: is_processing(),
+            processing_eta_ms: processing_eta_ms(),
+            pipeline_latency_ms: None,
+            stop_reason: None,
+            disk_space_remaining_bytes: None,
+            estimated_minutes_left: None,
+            post_processing_jobs: get_post_process_jobs(),
+            auto_paused: false,
+            warning: None,
+            applied_fps: None,
+            captured_frames: 0,
         },
     }
 }
@@ -0,0 +1,327 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    ffi::OsString,
+    fs,
+    io::{BufRead, BufReader},
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use tempfile::Builder as TempBuilder;
+
+use crate::encoder::{
+    config::{default_gif_fps, GifExportConfig},
+    gif_export::{encode_pass_args, gif_scale_filter, input_args, palette_pass_args},
+    media_info::probe_output_file,
+    post_process::{run_ffmpeg_cancellable_with_stdout, PostProcessKind, PostProcessTask},
+    processing_status::ProcessingProgress,
+    progress_eta::parse_progress_out_time_ms,
+    trim::ResolvedTrim,
+};
+
+pub const EVENT_ANIMATION_EXPORT_PROGRESS: &str = "animation-export-progress";
+pub const MAX_ANIMATION_FPS: u32 = 50;
+pub const MAX_FULL_RES_ANIMATION_MS: u64 = 60_000;
+const WEBP_QUALITY: u32 = 75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AnimationFormat {
+    Gif,
+    Webp,
+}
+
+impl AnimationFormat {
+    fn label(self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "GIF",
+            AnimationFormat::Webp => "WebP",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnimationExportRequest {
+    pub start_ms: Option<u64>,
+    pub end_ms: Option<u64>,
+    pub format: AnimationFormat,
+    pub fps: Option<u32>,
+    pub max_width: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnimationExportProgress {
+    pub output_path: String,
+    pub processed_ms: u64,
+    pub total_ms: u64,
+}
+
+pub type AnimationExportProgressSink = Arc<dyn Fn(AnimationExportProgress) + Send + Sync>;
+
+fn progress_sink_slot() -> &'static Mutex<Option<AnimationExportProgressSink>> {
+    static SLOT: OnceLock<Mutex<Option<AnimationExportProgressSink>>> = OnceLock::new();
+    SLOT.get_or_init(|| Mutex::new(None))
+}
+
+pub fn set_animation_export_progress_sink(sink: AnimationExportProgressSink) {
+    if let Ok(mut guard) = progress_sink_slot().lock() {
+        *guard = Some(sink);
+    }
+}
+
+fn notify_progress(event: AnimationExportProgress) {
+    let sink = progress_sink_slot()
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone());
+    if let Some(sink) = sink {
+        sink(event);
+    }
+}
+
+pub fn resolve_clip(
+    start_ms: Option<u64>,
+    end_ms: Option<u64>,
+    total_ms: u64,
+) -> Result<ResolvedTrim, String> {
+    let start_ms = start_ms.unwrap_or(0);
+    if total_ms == 0 {
+        return Err("No se pudo determinar la duración del video".to_string());
+    }
+    if start_ms >= total_ms {
+        return Err(format!(
+            "El inicio ({start_ms} ms) supera la duración del video ({total_ms} ms)"
+        ));
+    }
+    let end_ms = end_ms.unwrap_or(total_ms).min(total_ms);
+    if end_ms <= start_ms {
+        return Err(format!(
+            "Tramo inválido: el inicio ({start_ms} ms) debe ser anterior al final ({end_ms} ms)"
+        ));
+    }
+    Ok(ResolvedTrim { start_ms, end_ms })
+}
+
+pub fn validate_animation_size(
+    clip: &ResolvedTrim,
+    source_width: u32,
+    max_width: Option<u32>,
+) -> Result<u32, String> {
+    let output_width = max_width.map_or(source_width, |max| max.min(source_width));
+    if output_width < 2 {
+        return Err("El ancho máximo de la animación debe ser al menos 2 px".to_string());
+    }
+    let duration_ms = clip.end_ms - clip.start_ms;
+    if duration_ms > MAX_FULL_RES_ANIMATION_MS && output_width >= source_width {
+        return Err(format!(
+            "Una animación de {} s a resolución completa ({source_width} px) es demasiado grande; elige un tramo de hasta {} s o reduce el ancho máximo",
+            duration_ms / 1_000,
+            MAX_FULL_RES_ANIMATION_MS / 1_000
+        ));
+    }
+    Ok(output_width)
+}
+
+fn webp_args(input: &[OsString], output_path: &Path, config: &GifExportConfig) -> Vec<OsString> {
+    let mut args = input.to_vec();
+    args.extend([
+        "-vf".into(),
+        gif_scale_filter(config).into(),
+        "-an".into(),
+        "-c:v".into(),
+        "libwebp_anim".into(),
+        "-lossless".into(),
+        "0".into(),
+        "-q:v".into(),
+        WEBP_QUALITY.to_string().into(),
+        "-loop".into(),
+        "0".into(),
+        "-f".into(),
+        "webp".into(),
+        output_path.into(),
+    ]);
+    args
+}
+
+pub fn export_animation(
+    input_path: &Path,
+    output_path: &Path,
+    request: &AnimationExportRequest,
+) -> Result<(), String> {
+    let fps = request.fps.unwrap_or_else(default_gif_fps);
+    if fps == 0 || fps > MAX_ANIMATION_FPS {
+        return Err(format!(
+            "FPS de la animación inválidos: {fps}. Debe estar entre 1 y {MAX_ANIMATION_FPS}"
+        ));
+    }
+    if input_path == output_path {
+        return Err("La animación no puede sobrescribir el video de origen".to_string());
+    }
+
+    let info = probe_output_file(input_path)?;
+    let source_width = info
+        .width
+        .ok_or_else(|| format!("'{}' no tiene video", input_path.display()))?;
+    let clip = resolve_clip(request.start_ms, request.end_ms, info.duration_ms)?;
+    let max_width = validate_animation_size(&clip, source_width, request.max_width)?;
+    let config = GifExportConfig { fps, max_width };
+
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            format!(
+                "No se pudo crear carpeta de salida '{}': {err}",
+                parent.display()
+            )
+        })?;
+    }
+
+    let task = PostProcessTask::begin(PostProcessKind::AnimationExport, output_path);
+    let input = input_args(input_path, Some(&clip));
+    let clip_ms = clip.end_ms - clip.start_ms;
+    let progress = Arc::new(ProcessingProgress::start());
+    let result = match request.format {
+        AnimationFormat::Gif => {
+            let palette_dir = TempBuilder::new()
+                .prefix("capturist-palette-")
+                .tempdir()
+                .map_err(|err| {
+                    format!("No se pudo crear carpeta temporal para la paleta: {err}")
+                })?;
+            let palette_path = palette_dir.path().join("palette.png");
+            let total_ms = clip_ms.saturating_mul(2);
+            run_with_progress(
+                palette_pass_args(&input, &palette_path, &config),
+                "generar la paleta del GIF",
+                &task,
+                &progress,
+                output_path,
+                (0, total_ms),
+            )
+            .and_then(|_| {
+                run_with_progress(
+                    encode_pass_args(&input, &palette_path, output_path, &config),
+                    "codificar el GIF",
+                    &task,
+                    &progress,
+                    output_path,
+                    (clip_ms, total_ms),
+                )
+            })
+        }
+        AnimationFormat::Webp => run_with_progress(
+            webp_args(&input, output_path, &config),
+            "codificar el WebP animado",
+            &task,
+            &progress,
+            output_path,
+            (0, clip_ms),
+        ),
+    };
+
+    if let Err(err) = result {
+        let _ = fs::remove_file(output_path);
+        return Err(format!(
+            "No se pudo exportar a {}: {err}",
+            request.format.label()
+        ));
+    }
+    Ok(())
+}
+
+fn run_with_progress(
+    args: Vec<OsString>,
+    step: &str,
+    task: &PostProcessTask,
+    progress: &Arc<ProcessingProgress>,
+    output_path: &Path,
+    (offset_ms, total_ms): (u64, u64),
+) -> Result<(), String> {
+    let mut progress_args: Vec<OsString> =
+        vec!["-progress".into(), "pipe:1".into(), "-nostats".into()];
+    progress_args.extend(args);
+
+    let progress = progress.clone();
+    let output_path = output_path.to_string_lossy().into_owned();
+    run_ffmpeg_cancellable_with_stdout(progress_args, step, task, move |stdout| {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let Some(out_time_ms) = parse_progress_out_time_ms(&line) else {
+                continue;
+            };
+            let processed_ms = offset_ms.saturating_add(out_time_ms).min(total_ms);
+            progress.report(processed_ms, total_ms);
+            notify_progress(AnimationExportProgress {
+                output_path: output_path.clone(),
+                processed_ms,
+                total_ms,
+            });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoder::post_process::args_to_strings;
+
+    #[test]
+    fn tramo_se_acota_a_la_duracion() {
+        assert_eq!(
+            resolve_clip(Some(2_000), Some(90_000), 30_000).unwrap(),
+            ResolvedTrim {
+                start_ms: 2_000,
+                end_ms: 30_000
+            }
+        );
+        assert_eq!(
+            resolve_clip(None, None, 8_000).unwrap(),
+            ResolvedTrim {
+                start_ms: 0,
+                end_ms: 8_000
+            }
+        );
+        assert!(resolve_clip(Some(40_000), None, 30_000).is_err());
+        assert!(resolve_clip(Some(5_000), Some(5_000), 30_000).is_err());
+    }
+
+    #[test]
+    fn rechaza_tramos_largos_a_resolucion_completa() {
+        let long = ResolvedTrim {
+            start_ms: 0,
+            end_ms: 90_000,
+        };
+        let err = validate_animation_size(&long, 1920, None).expect_err("debio fallar");
+        assert!(err.contains("demasiado grande"), "{err}");
+        assert!(validate_animation_size(&long, 1920, Some(4_000)).is_err());
+        assert_eq!(validate_animation_size(&long, 1920, Some(640)), Ok(640));
+
+        let short = ResolvedTrim {
+            start_ms: 10_000,
+            end_ms: 40_000,
+        };
+        assert_eq!(validate_animation_size(&short, 1920, None), Ok(1920));
+    }
+
+    #[test]
+    fn webp_usa_libwebp_anim_sobre_el_tramo() {
+        let clip = ResolvedTrim {
+            start_ms: 1_500,
+            end_ms: 4_000,
+        };
+        let config = GifExportConfig {
+            fps: 12,
+            max_width: 480,
+        };
+        let args = args_to_strings(webp_args(
+            &input_args(Path::new("in.mp4"), Some(&clip)),
+            Path::new("out.webp"),
+            &config,
+        ));
+
+        assert_eq!(&args[..6], ["-ss", "1.500", "-t", "2.500", "-i", "in.mp4"]);
+        assert!(args.contains(&"libwebp_anim".to_string()));
+        assert!(args.contains(&"fps=12,scale='min(480,iw)':-1:flags=lanczos".to_string()));
+        assert_eq!(args.last().map(String::as_str), Some("out.webp"));
+    }
+}
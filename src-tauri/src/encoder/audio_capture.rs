@@ -1,10 +1,22 @@
 #![cfg_attr(not(target_os = "windows"), allow(dead_code))]
 
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc};
 
 use tempfile::TempDir;
 
-use crate::encoder::config::{AudioCaptureConfig, OutputFormat, QualityMode};
+use crate::encoder::{
+    audio_capture::live_tap::LiveAudioTap,
+    config::{AudioCaptureConfig, OutputFormat, QualityMode},
+    processing_status::ProcessingGuard,
+    recording_history::FinishedRecording,
+};
+
+pub(crate) mod device_loss;
+pub(crate) mod live_gain;
+pub(crate) mod live_tap;
+pub(crate) mod monitor_buffer;
+pub(crate) mod track_activity;
+pub(crate) mod wav_writer;
 
 #[derive(Debug, Clone, Default, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -13,6 +25,14 @@ pub struct LiveAudioStatusSnapshot {
     pub capture_microphone_audio: bool,
     pub system_audio_device_name: Option<String>,
     pub microphone_audio_device_name: Option<String>,
+    pub system_audio_enabled_ms: u64,
+    pub system_audio_muted_ms: u64,
+    pub microphone_enabled_ms: u64,
+    pub microphone_muted_ms: u64,
+    pub microphone_monitoring: bool,
+    pub microphone_gain_percent: Option<u16>,
+    pub system_audio_lost_at_ms: Option<u64>,
+    pub microphone_lost_at_ms: Option<u64>,
 }
 
 pub struct AudioCaptureService {
@@ -24,6 +44,7 @@ impl AudioCaptureService {
         config: AudioCaptureConfig,
         format: OutputFormat,
         quality_mode: QualityMode,
+        mp4_faststart: bool,
         output_path: PathBuf,
         final_output_path: PathBuf,
         temp_dir: TempDir,
@@ -33,6 +54,7 @@ impl AudioCaptureService {
                 config,
                 format,
                 quality_mode,
+                mp4_faststart,
                 output_path,
                 final_output_path,
                 temp_dir,
@@ -44,12 +66,47 @@ impl AudioCaptureService {
         self.inner.start()
     }
 
-    pub fn finalize_and_mux_detached(mut self) {
-        std::thread::spawn(move || {
-            if let Err(err) = self.inner.finalize_and_mux() {
-                eprintln!("[audio] Error en mux de audio: {err}");
-            }
-        });
+    /// Corta la captura ya, en el hilo que llama, así no pisa el control en
+    /// vivo de una sesión o parte que arranque enseguida. El mux queda para
+    /// cuando el video del archivo esté cerrado.
+    pub fn stop(mut self) -> StoppedAudioCapture {
+        let processing_guard = ProcessingGuard::start();
+        self.inner.stop_capture();
+        StoppedAudioCapture {
+            inner: self.inner,
+            _processing_guard: processing_guard,
+        }
+    }
+}
+
+pub struct StoppedAudioCapture {
+    inner: platform::AudioCaptureServiceImpl,
+    _processing_guard: ProcessingGuard,
+}
+
+impl StoppedAudioCapture {
+    pub fn mux(mut self, finished: FinishedRecording, after_mux: impl FnOnce()) {
+        match self.inner.finalize_and_mux(&finished) {
+            Ok(()) => finished.record(),
+            Err(err) => eprintln!("[audio] Error en mux de audio: {err}"),
+        }
+        after_mux();
+    }
+}
+
+pub struct LiveAudioCapture {
+    inner: platform::LiveAudioCaptureImpl,
+}
+
+impl LiveAudioCapture {
+    pub fn start(config: AudioCaptureConfig, tap: Arc<LiveAudioTap>) -> Result<Self, String> {
+        Ok(Self {
+            inner: platform::LiveAudioCaptureImpl::start(config, tap)?,
+        })
+    }
+
+    pub fn stop(self) -> Result<(), String> {
+        self.inner.stop()
     }
 }
 
@@ -57,6 +114,18 @@ pub fn list_microphone_input_devices() -> Result<Vec<String>, String> {
     platform::list_microphone_input_devices()
 }
 
+pub fn list_system_audio_output_devices() -> Result<Vec<String>, String> {
+    platform::list_system_audio_output_devices()
+}
+
+pub fn resolve_system_audio_device(preferred: Option<&str>) -> Result<String, String> {
+    platform::resolve_system_audio_device(preferred)
+}
+
+pub fn resolve_microphone_device(preferred: Option<&str>) -> Result<String, String> {
+    platform::resolve_microphone_device(preferred)
+}
+
 pub fn update_live_audio_capture(
     capture_system_audio: bool,
     capture_microphone_audio: bool,
@@ -64,6 +133,14 @@ pub fn update_live_audio_capture(
     platform::update_live_audio_capture(capture_system_audio, capture_microphone_audio)
 }
 
+pub fn set_live_microphone_gain(percent: u16) -> Result<(), String> {
+    platform::set_live_microphone_gain(percent)
+}
+
+pub fn set_live_microphone_monitoring(enabled: bool) -> Result<(), String> {
+    platform::set_live_microphone_monitoring(enabled)
+}
+
 pub fn apply_audio_capture_config(config: &AudioCaptureConfig) {
     platform::apply_audio_capture_config(config);
 }
@@ -72,6 +149,10 @@ pub fn get_live_audio_status() -> LiveAudioStatusSnapshot {
     platform::get_live_audio_status()
 }
 
+pub fn clear_live_audio_capture() {
+    platform::clear_live_audio_capture();
+}
+
 #[cfg(windows)]
 #[path = "audio_capture/platform/windows.rs"]
 mod platform;
@@ -85,7 +166,8 @@ mod tests {
     use tempfile::tempdir;
 
     use super::{
-        get_live_audio_status, list_microphone_input_devices, update_live_audio_capture,
+        get_live_audio_status, list_microphone_input_devices, list_system_audio_output_devices,
+        set_live_microphone_gain, set_live_microphone_monitoring, update_live_audio_capture,
         AudioCaptureService,
     };
     use crate::encoder::config::{AudioCaptureConfig, OutputFormat, QualityMode};
@@ -97,6 +179,13 @@ mod tests {
         assert!(devices.is_empty());
     }
 
+    #[test]
+    fn lista_salidas_de_audio_stub_devuelve_vacia() {
+        let devices =
+            list_system_audio_output_devices().expect("listado de salidas de audio debe responder");
+        assert!(devices.is_empty());
+    }
+
     #[test]
     fn update_audio_en_vivo_stub_devuelve_error_controlado() {
         let err = update_live_audio_capture(true, true)
@@ -104,6 +193,21 @@ mod tests {
         assert!(err.contains("Windows"));
     }
 
+    #[test]
+    fn escucha_de_microfono_stub_devuelve_error_controlado() {
+        let err = set_live_microphone_monitoring(true)
+            .expect_err("en no-windows no debe reproducir el micrófono");
+        assert!(err.contains("Windows"));
+        assert!(!get_live_audio_status().microphone_monitoring);
+    }
+
+    #[test]
+    fn ganancia_en_vivo_stub_devuelve_error_controlado() {
+        let err = set_live_microphone_gain(150).expect_err("en no-windows no hay ganancia en vivo");
+        assert!(err.contains("Windows"));
+        assert!(get_live_audio_status().microphone_gain_percent.is_none());
+    }
+
     #[test]
     fn status_audio_stub_arranca_en_default() {
         let status = get_live_audio_status();
@@ -111,6 +215,8 @@ mod tests {
         assert!(!status.capture_microphone_audio);
         assert!(status.system_audio_device_name.is_none());
         assert!(status.microphone_audio_device_name.is_none());
+        assert_eq!(status.system_audio_muted_ms, 0);
+        assert_eq!(status.microphone_enabled_ms, 0);
     }
 
     #[test]
@@ -127,6 +233,7 @@ mod tests {
             },
             OutputFormat::Mp4,
             QualityMode::Balanced,
+            false,
             output_path,
             final_path,
             temp_dir,
@@ -149,6 +256,7 @@ mod tests {
             AudioCaptureConfig::default(),
             OutputFormat::Mp4,
             QualityMode::Balanced,
+            false,
             output_path,
             final_path,
             temp_dir,
@@ -0,0 +1,105 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+pub const EVENT_AUDIO_DEVICE_LOST: &str = "audio-device-lost";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AudioTrackKind {
+    System,
+    Microphone,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioDeviceLost {
+    pub track: AudioTrackKind,
+    pub device_name: String,
+    pub lost_at_ms: u64,
+    pub message: String,
+}
+
+impl AudioDeviceLost {
+    pub fn new(track: AudioTrackKind, device_name: String, lost_at_ms: u64) -> Self {
+        let source = match track {
+            AudioTrackKind::System => "la salida de audio del sistema",
+            AudioTrackKind::Microphone => "el micrófono",
+        };
+        let message =
+            format!("Se desconectó {source} '{device_name}'; la pista sigue grabando silencio");
+        Self {
+            track,
+            device_name,
+            lost_at_ms,
+            message,
+        }
+    }
+}
+
+pub type AudioDeviceLostSink = Arc<dyn Fn(AudioDeviceLost) + Send + Sync>;
+
+fn device_lost_sink_slot() -> &'static Mutex<Option<AudioDeviceLostSink>> {
+    static SLOT: OnceLock<Mutex<Option<AudioDeviceLostSink>>> = OnceLock::new();
+    SLOT.get_or_init(|| Mutex::new(None))
+}
+
+pub fn set_audio_device_lost_sink(sink: AudioDeviceLostSink) {
+    if let Ok(mut guard) = device_lost_sink_slot().lock() {
+        *guard = Some(sink);
+    }
+}
+
+pub(crate) fn notify_audio_device_lost(event: AudioDeviceLost) {
+    eprintln!("[audio-wasapi] {}", event.message);
+    let sink = device_lost_sink_slot()
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone());
+    if let Some(sink) = sink {
+        sink(event);
+    }
+}
+
+/// Frames de silencio que faltan para que una pista que empezó en
+/// `track_started_ms` llegue hasta `stopped_ms`, descontando lo ya escrito.
+/// Así la pista dura lo mismo que el video y `amix` no se desalinea.
+pub fn silence_padding_frames(
+    sample_rate: u32,
+    track_started_ms: u64,
+    stopped_ms: u64,
+    written_frames: u64,
+) -> u64 {
+    let expected_frames =
+        stopped_ms.saturating_sub(track_started_ms) * u64::from(sample_rate) / 1_000;
+    expected_frames.saturating_sub(written_frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rellena_desde_lo_escrito_hasta_la_detencion() {
+        assert_eq!(
+            silence_padding_frames(48_000, 500, 10_500, 4 * 48_000),
+            6 * 48_000
+        );
+        assert_eq!(silence_padding_frames(44_100, 0, 1_010, 0), 44_541);
+    }
+
+    #[test]
+    fn no_rellena_si_ya_se_escribio_todo_o_nunca_empezo() {
+        assert_eq!(silence_padding_frames(48_000, 0, 2_000, 96_000), 0);
+        assert_eq!(silence_padding_frames(48_000, 0, 2_000, 100_000), 0);
+        assert_eq!(silence_padding_frames(48_000, 3_000, 2_000, 0), 0);
+        assert_eq!(silence_padding_frames(0, 0, 2_000, 0), 0);
+    }
+
+    #[test]
+    fn mensaje_identifica_la_pista_perdida() {
+        let lost = AudioDeviceLost::new(AudioTrackKind::Microphone, "USB Mic".to_string(), 42);
+        assert!(lost.message.contains("micrófono 'USB Mic'"));
+        assert_eq!(lost.lost_at_ms, 42);
+    }
+}
@@ -0,0 +1,170 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+const WAVE_FORMAT_PCM: u16 = 0x0001;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 0x0003;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+// En WAVEFORMATEXTENSIBLE el SubFormat empieza en el byte 24 y sus dos
+// primeros bytes repiten el tag del formato real.
+const EXTENSIBLE_SUBFORMAT_OFFSET: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    F32,
+    I16,
+}
+
+fn read_u16(blob: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes([
+        *blob.get(offset)?,
+        *blob.get(offset + 1)?,
+    ]))
+}
+
+pub fn sample_format_from_wave_format(blob: &[u8]) -> Option<SampleFormat> {
+    let mut tag = read_u16(blob, 0)?;
+    let bits_per_sample = read_u16(blob, 14)?;
+    if tag == WAVE_FORMAT_EXTENSIBLE {
+        tag = read_u16(blob, EXTENSIBLE_SUBFORMAT_OFFSET)?;
+    }
+
+    match (tag, bits_per_sample) {
+        (WAVE_FORMAT_IEEE_FLOAT, 32) => Some(SampleFormat::F32),
+        (WAVE_FORMAT_PCM, 16) => Some(SampleFormat::I16),
+        _ => None,
+    }
+}
+
+/// Aplica la ganancia del micrófono sobre las muestras capturadas. En float se
+/// recorta a ±1.0; en i16 se agrega dither triangular de 1 LSB antes de
+/// redondear para no dejar distorsión de cuantización al bajar el volumen.
+#[derive(Debug)]
+pub struct GainProcessor {
+    format: SampleFormat,
+    dither_state: u32,
+}
+
+impl GainProcessor {
+    pub fn new(format: SampleFormat) -> Self {
+        Self {
+            format,
+            dither_state: 0x9E37_79B9,
+        }
+    }
+
+    fn next_uniform(&mut self) -> f32 {
+        let mut x = self.dither_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.dither_state = x;
+        x as f32 / u32::MAX as f32
+    }
+
+    pub fn apply(&mut self, samples: &mut [u8], gain_percent: u16) {
+        let gain = f32::from(gain_percent) / 100.0;
+        match self.format {
+            SampleFormat::F32 => {
+                for chunk in samples.chunks_exact_mut(4) {
+                    let value = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+                    let scaled = (value * gain).clamp(-1.0, 1.0);
+                    chunk.copy_from_slice(&scaled.to_le_bytes());
+                }
+            }
+            SampleFormat::I16 => {
+                for chunk in samples.chunks_exact_mut(2) {
+                    let value = f32::from(i16::from_le_bytes([chunk[0], chunk[1]]));
+                    let dither = self.next_uniform() - self.next_uniform();
+                    let scaled = (value * gain + dither)
+                        .round()
+                        .clamp(f32::from(i16::MIN), f32::from(i16::MAX));
+                    chunk.copy_from_slice(&(scaled as i16).to_le_bytes());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wave_format(tag: u16, bits: u16) -> Vec<u8> {
+        let mut blob = vec![0_u8; 18];
+        blob[0..2].copy_from_slice(&tag.to_le_bytes());
+        blob[14..16].copy_from_slice(&bits.to_le_bytes());
+        blob
+    }
+
+    #[test]
+    fn detecta_float_y_pcm_incluso_extensible() {
+        assert_eq!(
+            sample_format_from_wave_format(&wave_format(WAVE_FORMAT_IEEE_FLOAT, 32)),
+            Some(SampleFormat::F32)
+        );
+        assert_eq!(
+            sample_format_from_wave_format(&wave_format(WAVE_FORMAT_PCM, 16)),
+            Some(SampleFormat::I16)
+        );
+        assert_eq!(
+            sample_format_from_wave_format(&wave_format(WAVE_FORMAT_PCM, 24)),
+            None
+        );
+
+        let mut extensible = wave_format(WAVE_FORMAT_EXTENSIBLE, 32);
+        extensible.resize(40, 0);
+        extensible[24..26].copy_from_slice(&WAVE_FORMAT_IEEE_FLOAT.to_le_bytes());
+        assert_eq!(
+            sample_format_from_wave_format(&extensible),
+            Some(SampleFormat::F32)
+        );
+        assert_eq!(sample_format_from_wave_format(&extensible[..20]), None);
+    }
+
+    #[test]
+    fn ganancia_float_escala_y_recorta() {
+        let mut samples: Vec<u8> = [0.25_f32, -0.4, 0.8]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+
+        GainProcessor::new(SampleFormat::F32).apply(&mut samples, 200);
+
+        let result: Vec<f32> = samples
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        assert_eq!(result, vec![0.5, -0.8, 1.0]);
+    }
+
+    #[test]
+    fn ganancia_i16_recorta_sin_desbordar_y_dither_no_pasa_de_un_lsb() {
+        let mut samples: Vec<u8> = [20_000_i16, -20_000, 1_000]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+
+        GainProcessor::new(SampleFormat::I16).apply(&mut samples, 400);
+
+        let result: Vec<i16> = samples
+            .chunks_exact(2)
+            .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect();
+        assert_eq!(result[0], i16::MAX);
+        assert_eq!(result[1], i16::MIN);
+        assert!((3_999..=4_001).contains(&result[2]), "{}", result[2]);
+    }
+
+    #[test]
+    fn ganancia_cero_silencia() {
+        let mut samples: Vec<u8> = [12_345_i16, -3]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        GainProcessor::new(SampleFormat::I16).apply(&mut samples, 0);
+        let result: Vec<i16> = samples
+            .chunks_exact(2)
+            .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect();
+        assert!(result.iter().all(|value| value.abs() <= 1));
+    }
+}
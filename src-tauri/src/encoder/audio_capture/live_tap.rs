@@ -0,0 +1,281 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::encoder::audio_capture::live_gain::SampleFormat;
+
+pub const LIVE_AUDIO_SAMPLE_RATE: u32 = 48_000;
+const MAX_QUEUED_MS: u32 = 2_000;
+
+#[derive(Debug, Clone, Copy)]
+struct InputFormat {
+    sample_rate: u32,
+    channels: usize,
+    block_align: usize,
+    sample_format: Option<SampleFormat>,
+}
+
+#[derive(Debug, Default)]
+struct TapState {
+    input: Option<InputFormat>,
+    resample_pos: f64,
+    previous: [f32; 2],
+    left: VecDeque<f32>,
+    right: VecDeque<f32>,
+    consumed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveAudioFrame {
+    pub position: u64,
+    pub left: Vec<f32>,
+    pub right: Vec<f32>,
+}
+
+#[derive(Debug, Default)]
+pub struct LiveAudioTap {
+    state: Mutex<TapState>,
+}
+
+impl LiveAudioTap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TapState> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    pub fn configure(
+        &self,
+        sample_rate: u32,
+        channels: u16,
+        block_align: usize,
+        sample_format: Option<SampleFormat>,
+    ) {
+        let mut state = self.lock();
+        state.input = Some(InputFormat {
+            sample_rate: sample_rate.max(1),
+            channels: usize::from(channels.max(1)),
+            block_align: block_align.max(1),
+            sample_format,
+        });
+        state.resample_pos = 0.0;
+    }
+
+    pub fn push(&self, data: &[u8]) {
+        let mut state = self.lock();
+        let Some(input) = state.input else {
+            return;
+        };
+        let frames = decode_stereo(data, &input);
+        state.append_resampled(&frames, input.sample_rate);
+    }
+
+    pub fn push_silence(&self, byte_len: usize) {
+        let mut state = self.lock();
+        let Some(input) = state.input else {
+            return;
+        };
+        let frames = vec![[0.0_f32; 2]; byte_len / input.block_align];
+        state.append_resampled(&frames, input.sample_rate);
+    }
+
+    pub fn pop_frame(&self, frame_size: usize) -> Option<LiveAudioFrame> {
+        let mut state = self.lock();
+        if frame_size == 0 || state.left.len() < frame_size {
+            return None;
+        }
+        let position = state.consumed;
+        state.consumed += frame_size as u64;
+        let left = state.left.drain(..frame_size).collect();
+        let right = state.right.drain(..frame_size).collect();
+        Some(LiveAudioFrame {
+            position,
+            left,
+            right,
+        })
+    }
+}
+
+impl TapState {
+    fn append_resampled(&mut self, frames: &[[f32; 2]], input_rate: u32) {
+        if frames.is_empty() {
+            return;
+        }
+
+        if input_rate == LIVE_AUDIO_SAMPLE_RATE {
+            for frame in frames {
+                self.push_output(*frame);
+            }
+        } else {
+            let step = f64::from(input_rate) / f64::from(LIVE_AUDIO_SAMPLE_RATE);
+            let last_index = frames.len() as f64 - 1.0;
+            let mut pos = self.resample_pos;
+            while pos < last_index {
+                let base = pos.floor();
+                let frac = (pos - base) as f32;
+                let current = if base < 0.0 {
+                    self.previous
+                } else {
+                    frames[base as usize]
+                };
+                let next = frames[(base + 1.0) as usize];
+                self.push_output([
+                    current[0] + (next[0] - current[0]) * frac,
+                    current[1] + (next[1] - current[1]) * frac,
+                ]);
+                pos += step;
+            }
+            self.resample_pos = pos - frames.len() as f64;
+        }
+        self.previous = frames[frames.len() - 1];
+
+        let capacity = (LIVE_AUDIO_SAMPLE_RATE * MAX_QUEUED_MS / 1_000) as usize;
+        let overflow = self.left.len().saturating_sub(capacity);
+        self.left.drain(..overflow);
+        self.right.drain(..overflow);
+        self.consumed += overflow as u64;
+    }
+
+    fn push_output(&mut self, frame: [f32; 2]) {
+        self.left.push_back(frame[0]);
+        self.right.push_back(frame[1]);
+    }
+}
+
+fn decode_stereo(data: &[u8], input: &InputFormat) -> Vec<[f32; 2]> {
+    let frame_count = data.len() / input.block_align;
+    let Some(sample_format) = input.sample_format else {
+        return vec![[0.0; 2]; frame_count];
+    };
+
+    let sample_bytes = match sample_format {
+        SampleFormat::F32 => 4,
+        SampleFormat::I16 => 2,
+    };
+    let read = |offset: usize| -> f32 {
+        match sample_format {
+            SampleFormat::F32 => f32::from_le_bytes([
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ]),
+            SampleFormat::I16 => {
+                f32::from(i16::from_le_bytes([data[offset], data[offset + 1]])) / 32_768.0
+            }
+        }
+    };
+
+    (0..frame_count)
+        .map(|frame| {
+            let offset = frame * input.block_align;
+            let left = read(offset);
+            let right = if input.channels > 1 {
+                read(offset + sample_bytes)
+            } else {
+                left
+            };
+            [left, right]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn f32_bytes(samples: &[f32]) -> Vec<u8> {
+        samples
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn estereo_a_48k_pasa_sin_cambios() {
+        let tap = LiveAudioTap::new();
+        tap.configure(48_000, 2, 8, Some(SampleFormat::F32));
+        tap.push(&f32_bytes(&[0.1, -0.1, 0.2, -0.2, 0.3, -0.3]));
+
+        assert!(tap.pop_frame(4).is_none());
+        let frame = tap.pop_frame(3).unwrap();
+        assert_eq!(frame.position, 0);
+        assert_eq!(frame.left, vec![0.1, 0.2, 0.3]);
+        assert_eq!(frame.right, vec![-0.1, -0.2, -0.3]);
+    }
+
+    #[test]
+    fn mono_i16_se_duplica_en_ambos_canales() {
+        let tap = LiveAudioTap::new();
+        tap.configure(48_000, 1, 2, Some(SampleFormat::I16));
+        let bytes: Vec<u8> = [16_384_i16, -32_768]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        tap.push(&bytes);
+
+        let frame = tap.pop_frame(2).unwrap();
+        assert_eq!(frame.left, vec![0.5, -1.0]);
+        assert_eq!(frame.left, frame.right);
+    }
+
+    #[test]
+    fn remuestrea_44100_a_48000_sin_perder_continuidad() {
+        let tap = LiveAudioTap::new();
+        tap.configure(44_100, 2, 8, Some(SampleFormat::F32));
+        for block in 0..100 {
+            let samples: Vec<f32> = (0..441)
+                .flat_map(|frame| {
+                    let value = (block * 441 + frame) as f32 / 44_100.0;
+                    [value, value]
+                })
+                .collect();
+            tap.push(&f32_bytes(&samples));
+        }
+
+        let left = tap.pop_frame(47_990).unwrap().left;
+        assert!(tap.pop_frame(20).is_none());
+        assert!(left.windows(2).all(|pair| pair[1] >= pair[0]));
+        assert!((left[24_000] - 0.5).abs() < 0.001, "{}", left[24_000]);
+    }
+
+    #[test]
+    fn formato_no_soportado_y_silencio_empujan_ceros() {
+        let tap = LiveAudioTap::new();
+        tap.push(&[1, 2, 3, 4]);
+        assert!(tap.pop_frame(1).is_none());
+
+        tap.configure(48_000, 2, 6, None);
+        tap.push(&[7; 12]);
+        tap.push_silence(6);
+        let frame = tap.pop_frame(3).unwrap();
+        assert!(frame
+            .left
+            .iter()
+            .chain(&frame.right)
+            .all(|value| *value == 0.0));
+    }
+
+    #[test]
+    fn descarta_lo_mas_viejo_si_el_encoder_se_atrasa() {
+        let tap = LiveAudioTap::new();
+        tap.configure(48_000, 2, 8, Some(SampleFormat::F32));
+        tap.push_silence(8 * 96_000);
+        tap.push(&f32_bytes(&[0.9, 0.9]));
+
+        let frame = tap.pop_frame(96_000).unwrap();
+        assert_eq!(frame.left.last(), Some(&0.9));
+        assert!(tap.pop_frame(1).is_none());
+        assert_eq!(frame.position, 1);
+        tap.push_silence(8 * 10);
+        assert_eq!(tap.pop_frame(10).unwrap().position, 96_001);
+    }
+}
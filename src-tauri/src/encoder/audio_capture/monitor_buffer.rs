@@ -0,0 +1,108 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, MutexGuard},
+};
+
+#[derive(Debug)]
+pub struct MonitorBuffer {
+    block_align: usize,
+    capacity: usize,
+    samples: Mutex<VecDeque<u8>>,
+}
+
+impl MonitorBuffer {
+    pub fn new(sample_rate: u32, block_align: usize, max_queued_ms: u32) -> Self {
+        let block_align = block_align.max(1);
+        let frames = (u64::from(sample_rate) * u64::from(max_queued_ms) / 1_000).max(1) as usize;
+        let capacity = frames * block_align;
+        Self {
+            block_align,
+            capacity,
+            samples: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VecDeque<u8>> {
+        match self.samples.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    pub fn push(&self, data: &[u8]) {
+        // Solo frames completos, para no desalinear los canales al descartar.
+        let usable = data.len() - data.len() % self.block_align;
+        let data = &data[..usable];
+        let data = &data[data.len().saturating_sub(self.capacity)..];
+
+        let mut samples = self.lock();
+        let overflow = (samples.len() + data.len()).saturating_sub(self.capacity);
+        samples.drain(..overflow);
+        samples.extend(data);
+    }
+
+    pub fn pop_into(&self, out: &mut [u8]) -> usize {
+        let mut samples = self.lock();
+        let available = samples.len().min(out.len());
+        let len = available - available % self.block_align;
+        for (dst, src) in out.iter_mut().zip(samples.drain(..len)) {
+            *dst = src;
+        }
+        len
+    }
+
+    pub fn queued_frames(&self) -> usize {
+        self.lock().len() / self.block_align
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MonitorBuffer;
+
+    #[test]
+    fn descarta_lo_mas_viejo_al_superar_la_latencia_maxima() {
+        let buffer = MonitorBuffer::new(1_000, 2, 10);
+        let first: Vec<u8> = (0..16).collect();
+        let second: Vec<u8> = (16..24).collect();
+
+        buffer.push(&first);
+        buffer.push(&second);
+        assert_eq!(buffer.queued_frames(), 10);
+
+        let mut out = [0_u8; 32];
+        assert_eq!(buffer.pop_into(&mut out), 20);
+        assert_eq!(out[..20], (4..24).collect::<Vec<u8>>()[..]);
+        assert_eq!(buffer.queued_frames(), 0);
+    }
+
+    #[test]
+    fn entrega_solo_frames_completos() {
+        let buffer = MonitorBuffer::new(48_000, 4, 20);
+        buffer.push(&[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(buffer.queued_frames(), 2);
+
+        let mut out = [0_u8; 6];
+        assert_eq!(buffer.pop_into(&mut out), 4);
+        assert_eq!(out[..4], [1, 2, 3, 4]);
+
+        buffer.clear();
+        assert_eq!(buffer.pop_into(&mut out), 0);
+    }
+
+    #[test]
+    fn un_paquete_mayor_que_la_cola_conserva_el_final() {
+        let buffer = MonitorBuffer::new(1_000, 1, 4);
+        buffer.push(&[1, 2, 3, 4, 5, 6]);
+
+        let mut out = [0_u8; 8];
+        assert_eq!(buffer.pop_into(&mut out), 4);
+        assert_eq!(out[..4], [3, 4, 5, 6]);
+    }
+}
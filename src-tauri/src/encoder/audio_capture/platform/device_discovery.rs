@@ -5,8 +5,8 @@ use windows::{
         Devices::FunctionDiscovery::PKEY_Device_FriendlyName,
         Foundation::RPC_E_CHANGED_MODE,
         Media::Audio::{
-            eCapture, eConsole, EDataFlow, IMMDevice, IMMDeviceEnumerator, MMDeviceEnumerator,
-            DEVICE_STATE_ACTIVE,
+            eCapture, eConsole, eRender, EDataFlow, IMMDevice, IMMDeviceEnumerator,
+            MMDeviceEnumerator, DEVICE_STATE_ACTIVE,
         },
         System::Com::{
             CoCreateInstance, CoInitializeEx, CoTaskMemFree, CoUninitialize, CLSCTX_ALL,
@@ -22,8 +22,16 @@ pub(super) struct DeviceDescriptor {
 }
 
 pub(super) fn list_microphone_input_devices_impl() -> Result<Vec<String>, String> {
+    list_active_device_names(eCapture)
+}
+
+pub(super) fn list_system_audio_output_devices_impl() -> Result<Vec<String>, String> {
+    list_active_device_names(eRender)
+}
+
+fn list_active_device_names(dataflow: EDataFlow) -> Result<Vec<String>, String> {
     let mut devices = with_com(|| {
-        let list = enumerate_active_devices(eCapture)?;
+        let list = enumerate_active_devices(dataflow)?;
         Ok(list.into_iter().map(|d| d.name).collect::<Vec<_>>())
     })?;
 
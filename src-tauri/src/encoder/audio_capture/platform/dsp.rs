@@ -1,4 +1,4 @@
-use crate::encoder::config::QualityMode;
+use crate::encoder::config::{MicNoiseSuppression, QualityMode};
 
 use super::{AudioTrackInput, AudioTrackSource};
 
@@ -12,7 +12,17 @@ const MIC_GATE_THRESHOLD: f32 = 0.015;
 const MIC_GATE_RATIO: u32 = 3;
 const MIC_GATE_ATTACK_MS: u32 = 20;
 const MIC_GATE_RELEASE_MS: u32 = 250;
+const MIC_LIGHT_NOISE_FLOOR_DB: i32 = -40;
+const MIC_LIGHT_NOISE_REDUCTION_DB: u32 = 8;
+const MIC_LIGHT_GATE_THRESHOLD: f32 = 0.008;
+const MIC_LIGHT_GATE_RATIO: u32 = 2;
 const MAX_GAIN_MULTIPLIER: f64 = 16.0;
+const LOUDNORM_TRUE_PEAK_DB: f32 = -1.5;
+const LOUDNORM_LOUDNESS_RANGE: u32 = 11;
+pub(super) const MIX_SAMPLE_RATE_HZ: u32 = 48_000;
+// Muestras por segundo que `aresample` puede estirar o comprimir para
+// compensar la deriva entre los relojes de dos dispositivos.
+const MIX_RESAMPLE_ASYNC: u32 = 1_000;
 
 fn dsp_filter_chain(quality_mode: &QualityMode) -> Option<String> {
     if matches!(quality_mode, QualityMode::Performance) {
@@ -24,32 +34,36 @@ fn dsp_filter_chain(quality_mode: &QualityMode) -> Option<String> {
     ))
 }
 
-fn microphone_noise_filter_chain(quality_mode: &QualityMode) -> Option<String> {
-    if !matches!(quality_mode, QualityMode::Quality) {
-        return None;
-    }
-
-    Some(format!(
-        "highpass=f={MIC_HIGHPASS_HZ},lowpass=f={MIC_LOWPASS_HZ},afftdn=nf={MIC_NOISE_FLOOR_DB}:nr={MIC_NOISE_REDUCTION_DB}:tn=1,agate=threshold={MIC_GATE_THRESHOLD}:ratio={MIC_GATE_RATIO}:attack={MIC_GATE_ATTACK_MS}:release={MIC_GATE_RELEASE_MS}"
-    ))
+fn loudnorm_filter(target_lufs: i32) -> String {
+    format!("loudnorm=I={target_lufs}:TP={LOUDNORM_TRUE_PEAK_DB}:LRA={LOUDNORM_LOUDNESS_RANGE}")
 }
 
-fn microphone_light_filter_chain(quality_mode: &QualityMode) -> Option<String> {
-    if matches!(quality_mode, QualityMode::Balanced) {
-        return Some(format!(
-            "highpass=f={MIC_HIGHPASS_HZ},lowpass=f={MIC_LOWPASS_HZ}"
-        ));
+fn microphone_noise_filter(level: MicNoiseSuppression) -> Option<String> {
+    match level {
+        MicNoiseSuppression::Off => None,
+        MicNoiseSuppression::Light => Some(format!(
+            "afftdn=nf={MIC_LIGHT_NOISE_FLOOR_DB}:nr={MIC_LIGHT_NOISE_REDUCTION_DB}:tn=1,agate=threshold={MIC_LIGHT_GATE_THRESHOLD}:ratio={MIC_LIGHT_GATE_RATIO}:attack={MIC_GATE_ATTACK_MS}:release={MIC_GATE_RELEASE_MS}"
+        )),
+        MicNoiseSuppression::Strong => Some(format!(
+            "afftdn=nf={MIC_NOISE_FLOOR_DB}:nr={MIC_NOISE_REDUCTION_DB}:tn=1,agate=threshold={MIC_GATE_THRESHOLD}:ratio={MIC_GATE_RATIO}:attack={MIC_GATE_ATTACK_MS}:release={MIC_GATE_RELEASE_MS}"
+        )),
     }
-
-    None
 }
 
-fn microphone_filter_chain(quality_mode: &QualityMode) -> Option<String> {
-    if let Some(chain) = microphone_noise_filter_chain(quality_mode) {
-        return Some(chain);
-    }
+fn microphone_filter_chain(
+    quality_mode: &QualityMode,
+    noise_suppression: Option<MicNoiseSuppression>,
+) -> Option<String> {
+    let level = MicNoiseSuppression::resolve(noise_suppression, quality_mode);
+    let band_filter = (!matches!(quality_mode, QualityMode::Performance)
+        || level != MicNoiseSuppression::Off)
+        .then(|| format!("highpass=f={MIC_HIGHPASS_HZ},lowpass=f={MIC_LOWPASS_HZ}"));
 
-    microphone_light_filter_chain(quality_mode)
+    let segments: Vec<String> = [band_filter, microphone_noise_filter(level)]
+        .into_iter()
+        .flatten()
+        .collect();
+    (!segments.is_empty()).then(|| segments.join(","))
 }
 
 fn format_mic_gain(microphone_gain_percent: u16) -> String {
@@ -66,31 +80,56 @@ fn format_mic_gain(microphone_gain_percent: u16) -> String {
 
 fn requires_resync(quality_mode: &QualityMode, track: &AudioTrackInput) -> bool {
     track.delay_ms > 0
+        || track.trim_start_ms > 0
         || track.source == AudioTrackSource::Microphone
         || !matches!(quality_mode, QualityMode::Performance)
 }
 
-fn build_track_prefix(quality_mode: &QualityMode, track: &AudioTrackInput) -> String {
-    if requires_resync(quality_mode, track) {
+/// `mix_sample_rate` solo se pasa en la mezcla: cada dispositivo escribe su
+/// WAV con su propio formato y `amix` necesita entradas a la misma frecuencia.
+fn build_track_prefix(
+    quality_mode: &QualityMode,
+    track: &AudioTrackInput,
+    mix_sample_rate: Option<u32>,
+) -> String {
+    if let Some(rate) = mix_sample_rate {
+        format!("aresample={rate}:async={MIX_RESAMPLE_ASYNC}:first_pts=0,asetpts=PTS-STARTPTS")
+    } else if requires_resync(quality_mode, track) {
         "aresample=async=1:first_pts=0,asetpts=PTS-STARTPTS".to_string()
     } else {
         "anull".to_string()
     }
 }
 
+fn format_trim_filter(trim_start_ms: u64) -> String {
+    format!(
+        "atrim=start={}.{:03},asetpts=PTS-STARTPTS",
+        trim_start_ms / 1_000,
+        trim_start_ms % 1_000
+    )
+}
+
 fn build_track_chain(
     input_idx: usize,
     track: &AudioTrackInput,
     microphone_gain_percent: u16,
     quality_mode: &QualityMode,
+    noise_suppression: Option<MicNoiseSuppression>,
+    mix_sample_rate: Option<u32>,
     output_label: &str,
 ) -> String {
-    let mut chain = format!("[{input_idx}:a]{}", build_track_prefix(quality_mode, track));
+    let mut chain = format!(
+        "[{input_idx}:a]{}",
+        build_track_prefix(quality_mode, track, mix_sample_rate)
+    );
+    if track.trim_start_ms > 0 {
+        chain.push_str(&format!(",{}", format_trim_filter(track.trim_start_ms)));
+    }
     if track.delay_ms > 0 {
         chain.push_str(&format!(",adelay={}|{}", track.delay_ms, track.delay_ms));
     }
     if track.source == AudioTrackSource::Microphone {
-        if let Some(mic_filter) = microphone_filter_chain(quality_mode) {
+        if let Some(mic_filter) = microphone_filter_chain(quality_mode, noise_suppression) {
             chain.push_str(&format!(",{mic_filter}"));
         }
         if microphone_gain_percent != 100 {
@@ -108,16 +147,30 @@ pub(super) fn build_mix_filter(
     tracks: &[AudioTrackInput],
     microphone_gain_percent: u16,
     quality_mode: &QualityMode,
+    noise_suppression: Option<MicNoiseSuppression>,
+    loudness_target_lufs: Option<i32>,
+    audio_sample_rate: Option<u32>,
 ) -> String {
-    let dsp = dsp_filter_chain(quality_mode);
+    let dsp = match (dsp_filter_chain(quality_mode), loudness_target_lufs) {
+        (Some(chain), Some(target)) => Some(format!("{chain},{}", loudnorm_filter(target))),
+        (chain, None) => chain,
+        (None, Some(target)) => Some(loudnorm_filter(target)),
+    };
     match tracks.len() {
         0 => match dsp {
             Some(chain) => format!("[0:a]anull,{chain}[aout]"),
             None => "[0:a]anull[aout]".to_string(),
         },
         1 => {
-            let mut chain =
-                build_track_chain(1, &tracks[0], microphone_gain_percent, quality_mode, "");
+            let mut chain = build_track_chain(
+                1,
+                &tracks[0],
+                microphone_gain_percent,
+                quality_mode,
+                noise_suppression,
+                None,
+                "",
+            );
             if let Some(dsp_chain) = dsp {
                 chain.push_str(&format!(",{dsp_chain}"));
             }
@@ -125,6 +178,7 @@ pub(super) fn build_mix_filter(
             chain
         }
         _ => {
+            let mix_sample_rate = audio_sample_rate.unwrap_or(MIX_SAMPLE_RATE_HZ);
             let mut parts = Vec::with_capacity(tracks.len() + 2);
             let mut labels = Vec::with_capacity(tracks.len());
 
@@ -137,6 +191,8 @@ pub(super) fn build_mix_filter(
                     track,
                     microphone_gain_percent,
                     quality_mode,
+                    noise_suppression,
+                    Some(mix_sample_rate),
                     &format!("[{}]", label),
                 );
                 parts.push(chain);
@@ -162,18 +218,23 @@ pub(super) fn build_single_track_filter(
     track: &AudioTrackInput,
     microphone_gain_percent: u16,
     quality_mode: &QualityMode,
+    noise_suppression: Option<MicNoiseSuppression>,
+    loudness_target_lufs: Option<i32>,
 ) -> Option<String> {
     let mut segments = Vec::<String>::new();
-    let prefix = build_track_prefix(quality_mode, track);
+    let prefix = build_track_prefix(quality_mode, track, None);
     if prefix != "anull" {
         segments.push(prefix);
     }
 
+    if track.trim_start_ms > 0 {
+        segments.push(format_trim_filter(track.trim_start_ms));
+    }
     if track.delay_ms > 0 {
         segments.push(format!("adelay={}|{}", track.delay_ms, track.delay_ms));
     }
     if track.source == AudioTrackSource::Microphone {
-        if let Some(mic_filter) = microphone_filter_chain(quality_mode) {
+        if let Some(mic_filter) = microphone_filter_chain(quality_mode, noise_suppression) {
             segments.push(mic_filter);
         }
         if microphone_gain_percent != 100 {
@@ -186,6 +247,9 @@ pub(super) fn build_single_track_filter(
     if let Some(dsp_chain) = dsp_filter_chain(quality_mode) {
         segments.push(dsp_chain);
     }
+    if let Some(target) = loudness_target_lufs {
+        segments.push(loudnorm_filter(target));
+    }
 
     if segments.is_empty() {
         None
@@ -193,3 +257,241 @@ pub(super) fn build_single_track_filter(
         Some(segments.join(","))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{
+        build_mix_filter, build_single_track_filter, microphone_filter_chain, AudioTrackInput,
+        AudioTrackSource, MicNoiseSuppression, QualityMode,
+    };
+
+    const BAND: &str = "highpass=f=120,lowpass=f=9000";
+
+    fn track(source: AudioTrackSource) -> AudioTrackInput {
+        AudioTrackInput {
+            path: PathBuf::from("audio.wav"),
+            delay_ms: 0,
+            trim_start_ms: 0,
+            source,
+        }
+    }
+
+    #[test]
+    fn supresion_fuerte_conserva_el_preset_original() {
+        let expected = format!(
+            "{BAND},afftdn=nf=-32:nr=18:tn=1,agate=threshold=0.015:ratio=3:attack=20:release=250"
+        );
+        assert_eq!(
+            microphone_filter_chain(&QualityMode::Performance, Some(MicNoiseSuppression::Strong)),
+            Some(expected.clone())
+        );
+        assert_eq!(
+            microphone_filter_chain(&QualityMode::Quality, None),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn supresion_ligera_usa_reduccion_y_compuerta_suaves() {
+        assert_eq!(
+            microphone_filter_chain(&QualityMode::Balanced, Some(MicNoiseSuppression::Light)),
+            Some(format!(
+                "{BAND},afftdn=nf=-40:nr=8:tn=1,agate=threshold=0.008:ratio=2:attack=20:release=250"
+            ))
+        );
+    }
+
+    #[test]
+    fn supresion_apagada_omite_afftdn() {
+        assert_eq!(
+            microphone_filter_chain(&QualityMode::Quality, Some(MicNoiseSuppression::Off)),
+            Some(BAND.to_string())
+        );
+        assert_eq!(
+            microphone_filter_chain(&QualityMode::Balanced, None),
+            Some(BAND.to_string())
+        );
+        assert_eq!(
+            microphone_filter_chain(&QualityMode::Performance, Some(MicNoiseSuppression::Off)),
+            None
+        );
+    }
+
+    #[test]
+    fn cada_modo_y_override_produce_su_cadena() {
+        let strong = format!(
+            "{BAND},afftdn=nf=-32:nr=18:tn=1,agate=threshold=0.015:ratio=3:attack=20:release=250"
+        );
+        let light = format!(
+            "{BAND},afftdn=nf=-40:nr=8:tn=1,agate=threshold=0.008:ratio=2:attack=20:release=250"
+        );
+        let band = Some(BAND.to_string());
+        let cases = [
+            (QualityMode::Performance, None, None),
+            (
+                QualityMode::Performance,
+                Some(MicNoiseSuppression::Off),
+                None,
+            ),
+            (
+                QualityMode::Performance,
+                Some(MicNoiseSuppression::Light),
+                Some(light.clone()),
+            ),
+            (
+                QualityMode::Performance,
+                Some(MicNoiseSuppression::Strong),
+                Some(strong.clone()),
+            ),
+            (QualityMode::Balanced, None, band.clone()),
+            (
+                QualityMode::Balanced,
+                Some(MicNoiseSuppression::Off),
+                band.clone(),
+            ),
+            (
+                QualityMode::Balanced,
+                Some(MicNoiseSuppression::Light),
+                Some(light.clone()),
+            ),
+            (
+                QualityMode::Balanced,
+                Some(MicNoiseSuppression::Strong),
+                Some(strong.clone()),
+            ),
+            (QualityMode::Quality, None, Some(strong.clone())),
+            (QualityMode::Quality, Some(MicNoiseSuppression::Off), band),
+            (
+                QualityMode::Quality,
+                Some(MicNoiseSuppression::Light),
+                Some(light),
+            ),
+            (
+                QualityMode::Quality,
+                Some(MicNoiseSuppression::Strong),
+                Some(strong),
+            ),
+        ];
+
+        for (mode, configured, expected) in cases {
+            assert_eq!(
+                microphone_filter_chain(&mode, configured),
+                expected,
+                "{mode:?} con {configured:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn modo_de_calidad_decide_la_cadena_dsp() {
+        let system = track(AudioTrackSource::System);
+        let microphone = track(AudioTrackSource::Microphone);
+
+        assert_eq!(
+            build_single_track_filter(&system, 100, &QualityMode::Performance, None, None),
+            None
+        );
+        let performance_mic =
+            build_single_track_filter(&microphone, 100, &QualityMode::Performance, None, None)
+                .expect("el micrófono siempre se resincroniza");
+        assert!(!performance_mic.contains("afftdn"));
+        assert!(!performance_mic.contains("highpass"));
+
+        let quality_mic =
+            build_single_track_filter(&microphone, 100, &QualityMode::Quality, None, None)
+                .expect("filtro de micrófono en calidad");
+        assert!(quality_mic.contains("afftdn=nf=-32"));
+        assert!(quality_mic.ends_with("highpass=f=80,lowpass=f=14000"));
+    }
+
+    #[test]
+    fn loudnorm_va_al_final_de_una_pista() {
+        let system = track(AudioTrackSource::System);
+
+        assert_eq!(
+            build_single_track_filter(&system, 100, &QualityMode::Performance, None, Some(-16)),
+            Some("loudnorm=I=-16:TP=-1.5:LRA=11".to_string())
+        );
+        assert_eq!(
+            build_single_track_filter(&system, 100, &QualityMode::Quality, None, Some(-23)),
+            Some(
+                "aresample=async=1:first_pts=0,asetpts=PTS-STARTPTS,highpass=f=80,lowpass=f=14000,loudnorm=I=-23:TP=-1.5:LRA=11"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn loudnorm_va_despues_de_la_mezcla() {
+        let tracks = [
+            track(AudioTrackSource::System),
+            track(AudioTrackSource::Microphone),
+        ];
+
+        let graph = build_mix_filter(
+            &tracks,
+            100,
+            &QualityMode::Performance,
+            None,
+            Some(-16),
+            None,
+        );
+        assert!(graph.ends_with(
+            "amix=inputs=2:normalize=0:dropout_transition=2[mix];[mix]loudnorm=I=-16:TP=-1.5:LRA=11[aout]"
+        ));
+
+        let graph = build_mix_filter(&tracks, 100, &QualityMode::Balanced, None, Some(-16), None);
+        assert!(graph
+            .ends_with("[mix]highpass=f=80,lowpass=f=14000,loudnorm=I=-16:TP=-1.5:LRA=11[aout]"));
+
+        let graph = build_mix_filter(&tracks, 100, &QualityMode::Balanced, None, None, None);
+        assert!(!graph.contains("loudnorm"));
+    }
+
+    #[test]
+    fn mezcla_lleva_cada_pista_a_la_misma_frecuencia() {
+        let mut microphone = track(AudioTrackSource::Microphone);
+        microphone.delay_ms = 250;
+        let tracks = [track(AudioTrackSource::System), microphone];
+
+        let graph = build_mix_filter(&tracks, 100, &QualityMode::Performance, None, None, None);
+        assert_eq!(
+            graph,
+            "[1:a]aresample=48000:async=1000:first_pts=0,asetpts=PTS-STARTPTS[a1];\
+             [2:a]aresample=48000:async=1000:first_pts=0,asetpts=PTS-STARTPTS,adelay=250|250[a2];\
+             [a1][a2]amix=inputs=2:normalize=0:dropout_transition=2[mix];[mix]anull[aout]"
+        );
+
+        let graph = build_mix_filter(
+            &tracks,
+            100,
+            &QualityMode::Quality,
+            None,
+            None,
+            Some(44_100),
+        );
+        assert_eq!(
+            graph
+                .matches("aresample=44100:async=1000:first_pts=0")
+                .count(),
+            2
+        );
+        assert!(!graph.contains("async=1:"));
+    }
+
+    #[test]
+    fn una_sola_pista_no_fuerza_frecuencia_comun() {
+        let system = track(AudioTrackSource::System);
+        let graph = build_mix_filter(
+            std::slice::from_ref(&system),
+            100,
+            &QualityMode::Balanced,
+            None,
+            None,
+            Some(44_100),
+        );
+        assert!(graph.starts_with("[1:a]aresample=async=1:first_pts=0"));
+    }
+}
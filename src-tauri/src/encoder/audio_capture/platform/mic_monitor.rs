@@ -0,0 +1,262 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::RPC_E_CHANGED_MODE,
+        Media::Audio::{
+            IAudioClient, IAudioRenderClient, AUDCLNT_SHAREMODE_SHARED,
+            AUDCLNT_STREAMFLAGS_AUTOCONVERTPCM, AUDCLNT_STREAMFLAGS_SRC_DEFAULT_QUALITY,
+            WAVEFORMATEX,
+        },
+        System::Com::{CoInitializeEx, CoUninitialize, CLSCTX_ALL, COINIT_MULTITHREADED},
+    },
+};
+
+use crate::encoder::audio_capture::monitor_buffer::MonitorBuffer;
+
+use super::{device_discovery::to_utf16_null, wasapi_capture::create_device_enumerator};
+
+// 20 ms en el buffer del dispositivo más 20 ms en cola dejan la latencia por
+// debajo de 50 ms contando el paquete de captura.
+const RENDER_BUFFER_HNS: i64 = 200_000;
+const MAX_QUEUED_MS: u32 = 20;
+const RENDER_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+#[derive(Clone)]
+pub(super) struct MonitorRoute {
+    pub(super) enabled: Arc<AtomicBool>,
+    pub(super) render_device_id: String,
+    pub(super) feedback_guard: Option<Arc<AtomicBool>>,
+}
+
+impl MonitorRoute {
+    pub(super) fn feedback_risk(&self) -> bool {
+        self.feedback_guard
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::SeqCst))
+    }
+
+    pub(super) fn is_active(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst) && !self.feedback_risk()
+    }
+}
+
+pub(super) struct MicMonitor {
+    route: MonitorRoute,
+    format_blob: Vec<u8>,
+    block_align: usize,
+    sample_rate: u32,
+    renderer: Option<MonitorRenderer>,
+    failed: bool,
+}
+
+impl MicMonitor {
+    pub(super) fn new(
+        route: MonitorRoute,
+        format_blob: Vec<u8>,
+        block_align: usize,
+        sample_rate: u32,
+    ) -> Self {
+        Self {
+            route,
+            format_blob,
+            block_align,
+            sample_rate,
+            renderer: None,
+            failed: false,
+        }
+    }
+
+    pub(super) fn feed(&mut self, data: &[u8]) {
+        if !self.route.is_active() {
+            if let Some(renderer) = self.renderer.as_ref() {
+                renderer.buffer.clear();
+            }
+            return;
+        }
+
+        if self.renderer.is_none() && !self.failed {
+            match MonitorRenderer::spawn(
+                &self.route.render_device_id,
+                self.format_blob.clone(),
+                self.block_align,
+                self.sample_rate,
+            ) {
+                Ok(renderer) => self.renderer = Some(renderer),
+                Err(err) => {
+                    eprintln!("[audio-wasapi] {err}");
+                    self.failed = true;
+                }
+            }
+        }
+
+        if let Some(renderer) = self.renderer.as_ref() {
+            renderer.buffer.push(data);
+        }
+    }
+}
+
+struct MonitorRenderer {
+    buffer: Arc<MonitorBuffer>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MonitorRenderer {
+    fn spawn(
+        device_id: &str,
+        format_blob: Vec<u8>,
+        block_align: usize,
+        sample_rate: u32,
+    ) -> Result<Self, String> {
+        let buffer = Arc::new(MonitorBuffer::new(sample_rate, block_align, MAX_QUEUED_MS));
+        let stop = Arc::new(AtomicBool::new(false));
+        let device_id = device_id.to_string();
+        let thread_buffer = Arc::clone(&buffer);
+        let thread_stop = Arc::clone(&stop);
+
+        let handle = thread::Builder::new()
+            .name("capturist-audio-monitor".to_string())
+            .spawn(move || {
+                if let Err(err) = render_loop(
+                    &device_id,
+                    &format_blob,
+                    block_align,
+                    &thread_buffer,
+                    &thread_stop,
+                ) {
+                    eprintln!("[audio-wasapi] escucha del micrófono detenida: {err}");
+                }
+            })
+            .map_err(|e| format!("No se pudo iniciar la escucha del micrófono: {}", e))?;
+
+        Ok(Self {
+            buffer,
+            stop,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for MonitorRenderer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn render_loop(
+    device_id: &str,
+    format_blob: &[u8],
+    block_align: usize,
+    buffer: &MonitorBuffer,
+    stop: &AtomicBool,
+) -> Result<(), String> {
+    let hr = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+    let should_uninitialize = hr.is_ok();
+    if hr.is_err() && hr != RPC_E_CHANGED_MODE {
+        return Err(format!(
+            "No se pudo inicializar COM para la escucha del micrófono: 0x{:08X}",
+            hr.0 as u32
+        ));
+    }
+
+    let result = (|| -> Result<(), String> {
+        let enumerator = create_device_enumerator()?;
+        let device_id_utf16 = to_utf16_null(device_id);
+        let device = unsafe {
+            enumerator
+                .GetDevice(PCWSTR(device_id_utf16.as_ptr()))
+                .map_err(|e| format!("No se pudo abrir la salida de audio WASAPI: {}", e))?
+        };
+
+        let audio_client: IAudioClient = unsafe {
+            device
+                .Activate(CLSCTX_ALL, None)
+                .map_err(|e| format!("No se pudo activar IAudioClient de salida: {}", e))?
+        };
+
+        // El formato es el del micrófono; WASAPI lo convierte al de la salida.
+        unsafe {
+            audio_client
+                .Initialize(
+                    AUDCLNT_SHAREMODE_SHARED,
+                    AUDCLNT_STREAMFLAGS_AUTOCONVERTPCM | AUDCLNT_STREAMFLAGS_SRC_DEFAULT_QUALITY,
+                    RENDER_BUFFER_HNS,
+                    0,
+                    format_blob.as_ptr() as *const WAVEFORMATEX,
+                    None,
+                )
+                .map_err(|e| format!("No se pudo inicializar stream de salida WASAPI: {}", e))?;
+        }
+
+        let buffer_frames = unsafe {
+            audio_client
+                .GetBufferSize()
+                .map_err(|e| format!("No se pudo leer el buffer de salida WASAPI: {}", e))?
+        };
+
+        let render_client: IAudioRenderClient = unsafe {
+            audio_client
+                .GetService()
+                .map_err(|e| format!("No se pudo inicializar IAudioRenderClient: {}", e))?
+        };
+
+        unsafe {
+            audio_client
+                .Start()
+                .map_err(|e| format!("No se pudo iniciar stream de salida WASAPI: {}", e))?;
+        }
+
+        while !stop.load(Ordering::Relaxed) {
+            let padding = unsafe {
+                audio_client
+                    .GetCurrentPadding()
+                    .map_err(|e| format!("Error consultando buffer de salida WASAPI: {}", e))?
+            };
+
+            let frames = buffer_frames
+                .saturating_sub(padding)
+                .min(buffer.queued_frames() as u32);
+            if frames > 0 {
+                let data_ptr = unsafe {
+                    render_client
+                        .GetBuffer(frames)
+                        .map_err(|e| format!("Error obteniendo buffer de salida WASAPI: {}", e))?
+                };
+                let out = unsafe {
+                    std::slice::from_raw_parts_mut(data_ptr, frames as usize * block_align)
+                };
+                let copied = buffer.pop_into(out);
+                out[copied..].fill(0);
+
+                unsafe {
+                    render_client
+                        .ReleaseBuffer(frames, 0)
+                        .map_err(|e| format!("Error liberando buffer de salida WASAPI: {}", e))?;
+                }
+            }
+
+            thread::sleep(RENDER_POLL_INTERVAL);
+        }
+
+        let _ = unsafe { audio_client.Stop() };
+        Ok(())
+    })();
+
+    if should_uninitialize {
+        unsafe { CoUninitialize() };
+    }
+
+    result
+}
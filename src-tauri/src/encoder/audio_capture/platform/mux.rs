@@ -1,6 +1,6 @@
 use std::{
     env, fs, io,
-    path::{Path, PathBuf},
+    path::Path,
     process::{Command, Stdio},
 };
 
@@ -8,14 +8,22 @@ use std::{
 use std::os::windows::process::CommandExt;
 
 use crate::encoder::{
-    config::{OutputFormat, QualityMode},
-    ffmpeg_paths::resolve_ffmpeg_bin,
-    output_paths::move_temp_to_final,
+    chapters::chapters_ffmetadata,
+    config::{AudioCaptureConfig, OutputFormat, QualityMode, MAX_AUDIO_SYNC_OFFSET_MS},
+    ffmpeg_paths::{ffmpeg_bin_available, resolve_ffmpeg_bin},
+    media_info::{probe_output_file, probe_video_keyframes},
+    output_paths::{ffmpeg_path_arg, finalize_processed_output, move_temp_to_final},
+    processing_status::ProcessingProgress,
+    recording_history::FinishedRecording,
+    trim::ResolvedTrim,
 };
 use ffmpeg_the_third::{ffi, format as ffmpeg_format, media};
 
+use crate::encoder::audio_capture::wav_writer::{concat_list_contents, wav_part_paths};
+
 use super::{
-    dsp::build_mix_filter, dsp::build_single_track_filter, AudioTrackInput, AudioTrackSource,
+    dsp::build_mix_filter, dsp::build_single_track_filter, mux_in_process::mux_in_process,
+    AudioTrackInput, AudioTrackSource,
 };
 
 #[cfg(windows)]
@@ -28,54 +36,121 @@ pub(super) fn audio_file_has_payload(path: &Path) -> bool {
         .unwrap_or(false)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(super) fn mux_audio_into_video(
     format: &OutputFormat,
     quality_mode: &QualityMode,
     video_path: &Path,
     final_output_path: &Path,
     audio_tracks: &[AudioTrackInput],
-    microphone_gain_percent: u16,
+    audio_config: &AudioCaptureConfig,
+    mp4_faststart: bool,
+    recording: &FinishedRecording,
 ) -> Result<(), String> {
+    if *format == OutputFormat::Gif {
+        return Err("GIF no admite pistas de audio".to_string());
+    }
+
     let ffmpeg_bin = resolve_ffmpeg_bin();
-    let original_output = video_path.to_path_buf();
-    let temp_video = make_video_only_path(&original_output);
-    let output_audio_delay_ms =
-        detect_video_start_delay_ms(video_path).saturating_add(read_audio_sync_offset_ms());
+    let microphone_gain_percent = audio_config.microphone_gain_percent;
+    let noise_suppression = audio_config.mic_noise_suppression;
+    let loudness_target_lufs = audio_config.loudness_target();
 
-    if !original_output.exists() {
+    if !video_path.exists() {
         return Err(format!(
             "No existe el video base para mezclar audio: {}",
-            original_output.display()
+            video_path.display()
         ));
     }
 
-    if temp_video.exists() {
-        let _ = fs::remove_file(&temp_video);
+    let full_duration_ms = probe_output_file(video_path)
+        .map(|info| info.duration_ms)
+        .unwrap_or(0);
+    let trim = resolve_video_trim(video_path, recording, full_duration_ms);
+    let trimmed = trimmed_recording(recording, trim, full_duration_ms);
+    let recording = &trimmed;
+    let total_duration_ms = trim.map_or(full_duration_ms, |_| recording.duration_ms);
+    let sync_offset_ms = resolve_audio_sync_offset_ms(audio_config.audio_sync_offset_ms);
+    // El video recortado empieza en 0 en el keyframe de inicio: el audio
+    // pierde ese mismo tramo en vez de esperar el arranque original del video.
+    let output_audio_offset_ms = match trim {
+        Some(trim) => sync_offset_ms.saturating_sub(trim.start_ms as i64),
+        None => (detect_video_start_delay_ms(video_path) as i64).saturating_add(sync_offset_ms),
+    };
+
+    let adjusted_tracks: Vec<AudioTrackInput> = audio_tracks
+        .iter()
+        .map(|track| with_added_delay(track, output_audio_offset_ms))
+        .collect();
+    let filter_spec = in_process_filter_spec(&adjusted_tracks, audio_config, quality_mode);
+    let in_process = finalize_processed_output(video_path, video_path, |muxed_path| {
+        mux_in_process(
+            format,
+            video_path,
+            muxed_path,
+            &adjusted_tracks,
+            &filter_spec,
+            audio_config,
+            resolve_mp4_faststart(mp4_faststart),
+            recording,
+            total_duration_ms,
+            trim,
+        )
+    });
+    match in_process {
+        Ok(()) => {
+            return move_temp_to_final(video_path, final_output_path).map_err(audio_not_merged);
+        }
+        Err(err) => eprintln!(
+            "[audio-wasapi] Falló el mux en proceso; se intenta con FFmpeg CLI: {}",
+            err
+        ),
     }
 
-    fs::rename(&original_output, &temp_video)
-        .map_err(|e| format!("No se pudo preparar el video para mux de audio: {}", e))?;
+    if !ffmpeg_bin_available(&ffmpeg_bin, env::var_os("PATH").as_deref()) {
+        let _ = move_temp_to_final(video_path, final_output_path);
+        return Err(audio_not_merged(format!(
+            "no se encontró FFmpeg CLI (se buscó '{}'). Define CAPTURIST_FFMPEG_BIN o agrega ffmpeg.exe al PATH.",
+            ffmpeg_bin.display()
+        )));
+    }
 
     let mut cmd = Command::new(&ffmpeg_bin);
     cmd.arg("-y")
         .arg("-hide_banner")
         .arg("-loglevel")
         .arg("error")
+        .arg("-progress")
+        .arg("pipe:1")
+        .arg("-nostats")
         .arg("-threads")
-        .arg("0")
-        .arg("-i")
-        .arg(&temp_video);
+        .arg("0");
+    add_trim_seek(&mut cmd, trim);
+    cmd.arg("-i").arg(video_path);
+
+    for track in audio_tracks {
+        add_track_input(&mut cmd, &track.path);
+    }
+    let chapters_input =
+        add_chapters_input(&mut cmd, video_path, recording, 1 + audio_tracks.len());
 
     if audio_tracks.len() == 1 {
-        let adjusted_track = with_added_delay(&audio_tracks[0], output_audio_delay_ms);
-        cmd.arg("-i").arg(&adjusted_track.path);
-        if should_bypass_single_track_filter(&adjusted_track, microphone_gain_percent, quality_mode)
-        {
+        let adjusted_track = with_added_delay(&audio_tracks[0], output_audio_offset_ms);
+        if should_bypass_single_track_filter(
+            &adjusted_track,
+            microphone_gain_percent,
+            quality_mode,
+            loudness_target_lufs,
+        ) {
             cmd.arg("-map").arg("0:v:0").arg("-map").arg("1:a:0");
         } else {
-            if let Some(filter) =
-                build_single_track_filter(&adjusted_track, microphone_gain_percent, quality_mode)
-            {
+            if let Some(filter) = build_single_track_filter(
+                &adjusted_track,
+                microphone_gain_percent,
+                quality_mode,
+                noise_suppression,
+                loudness_target_lufs,
+            ) {
                 cmd.arg("-af").arg(filter);
             }
             cmd.arg("-map").arg("0:v:0").arg("-map").arg("1:a:0");
@@ -83,15 +158,17 @@ pub(super) fn mux_audio_into_video(
     } else {
         let adjusted_tracks: Vec<AudioTrackInput> = audio_tracks
             .iter()
-            .map(|track| with_added_delay(track, output_audio_delay_ms))
+            .map(|track| with_added_delay(track, output_audio_offset_ms))
             .collect();
 
-        for track in audio_tracks {
-            cmd.arg("-i").arg(&track.path);
-        }
-
-        let filter_graph =
-            build_mix_filter(&adjusted_tracks, microphone_gain_percent, quality_mode);
+        let filter_graph = build_mix_filter(
+            &adjusted_tracks,
+            microphone_gain_percent,
+            quality_mode,
+            noise_suppression,
+            loudness_target_lufs,
+            audio_config.audio_sample_rate,
+        );
         cmd.arg("-filter_complex")
             .arg(filter_graph)
             .arg("-filter_threads")
@@ -103,48 +180,240 @@ pub(super) fn mux_audio_into_video(
     }
 
     cmd.arg("-c:v").arg("copy").arg("-shortest");
+    add_trim_duration(&mut cmd, trim, total_duration_ms);
 
-    match format {
-        OutputFormat::WebM => {
-            cmd.arg("-c:a").arg("libopus").arg("-b:a").arg("128k");
-        }
-        OutputFormat::Mp4 => {
-            cmd.arg("-c:a").arg("aac").arg("-b:a").arg("160k");
-            if should_enable_mp4_faststart() {
-                cmd.arg("-movflags").arg("+faststart");
-            }
-        }
-        OutputFormat::Mkv => {
-            cmd.arg("-c:a").arg("aac").arg("-b:a").arg("160k");
-        }
+    cmd.args(audio_config.encoder_args(format));
+    if *format == OutputFormat::Mp4 && resolve_mp4_faststart(mp4_faststart) {
+        cmd.arg("-movflags").arg("+faststart");
+    }
+
+    if let Some(sample_rate) = audio_config.audio_sample_rate {
+        cmd.arg("-ar").arg(sample_rate.to_string());
+    }
+    if let Some(channels) = audio_config.audio_channels {
+        cmd.arg("-ac").arg(channels.to_string());
     }
 
-    cmd.arg(&final_output_path)
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped());
+    add_metadata_args(&mut cmd, recording, chapters_input);
+
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
 
     #[cfg(windows)]
     {
         cmd.creation_flags(CREATE_NO_WINDOW);
     }
 
-    let output = cmd.output().map_err(|e| {
-        restore_video_only_file(&temp_video, &original_output);
-        let _ = move_temp_to_final(&original_output, final_output_path);
+    let result = finalize_processed_output(video_path, final_output_path, |muxed_path| {
+        cmd.arg(muxed_path);
+        run_mux_command(cmd, total_duration_ms, "mux de audio")
+    })
+    .map_err(audio_not_merged);
+    let _ = fs::remove_file(chapters_file_path(video_path));
+    result
+}
+
+pub(super) fn remux_without_audio(
+    video_path: &Path,
+    final_output_path: &Path,
+    recording: &FinishedRecording,
+) -> Result<(), String> {
+    let ffmpeg_bin = resolve_ffmpeg_bin();
+    if !ffmpeg_bin_available(&ffmpeg_bin, env::var_os("PATH").as_deref()) {
+        move_temp_to_final(video_path, final_output_path)?;
+        return Err(remux_not_applied(format!(
+            "no se encontró FFmpeg CLI (se buscó '{}')",
+            ffmpeg_bin.display()
+        )));
+    }
+
+    let full_duration_ms = probe_output_file(video_path)
+        .map(|info| info.duration_ms)
+        .unwrap_or(recording.duration_ms);
+    let trim = resolve_video_trim(video_path, recording, full_duration_ms);
+    let trimmed = trimmed_recording(recording, trim, full_duration_ms);
+    let recording = &trimmed;
+
+    let mut cmd = Command::new(&ffmpeg_bin);
+    cmd.arg("-y")
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-progress")
+        .arg("pipe:1")
+        .arg("-nostats");
+    add_trim_seek(&mut cmd, trim);
+    cmd.arg("-i").arg(video_path);
+    let chapters_input = add_chapters_input(&mut cmd, video_path, recording, 1);
+    cmd.arg("-map").arg("0").arg("-c").arg("copy");
+    add_trim_duration(&mut cmd, trim, recording.duration_ms);
+    add_metadata_args(&mut cmd, recording, chapters_input);
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+    #[cfg(windows)]
+    {
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let result = finalize_processed_output(video_path, final_output_path, |output_path| {
+        cmd.arg(output_path);
+        run_mux_command(cmd, recording.duration_ms, "copiar el video")
+    })
+    .map_err(remux_not_applied);
+    let _ = fs::remove_file(chapters_file_path(video_path));
+    result
+}
+
+fn in_process_filter_spec(
+    tracks: &[AudioTrackInput],
+    audio_config: &AudioCaptureConfig,
+    quality_mode: &QualityMode,
+) -> String {
+    let loudness_target_lufs = audio_config.loudness_target();
+    if let [track] = tracks {
+        let filter = build_single_track_filter(
+            track,
+            audio_config.microphone_gain_percent,
+            quality_mode,
+            audio_config.mic_noise_suppression,
+            loudness_target_lufs,
+        );
+        return format!("[1:a]{}[aout]", filter.as_deref().unwrap_or("anull"));
+    }
+    build_mix_filter(
+        tracks,
+        audio_config.microphone_gain_percent,
+        quality_mode,
+        audio_config.mic_noise_suppression,
+        loudness_target_lufs,
+        audio_config.audio_sample_rate,
+    )
+}
+
+fn audio_not_merged(err: String) -> String {
+    format!("No se combinó el audio; el video se guardó sin audio: {err}")
+}
+
+fn remux_not_applied(err: String) -> String {
+    format!("No se aplicaron capítulos ni recorte; el video se guardó completo: {err}")
+}
+
+fn resolve_video_trim(
+    video_path: &Path,
+    recording: &FinishedRecording,
+    total_duration_ms: u64,
+) -> Option<ResolvedTrim> {
+    if recording.trim.is_empty() {
+        return None;
+    }
+    match recording.trim.resolve(
+        total_duration_ms,
+        &probe_video_keyframes(video_path).keyframes_ms,
+    ) {
+        Ok(trim) => trim,
+        Err(err) => {
+            eprintln!("[audio-wasapi] Se omite el recorte: {}", err);
+            None
+        }
+    }
+}
+
+fn trimmed_recording(
+    recording: &FinishedRecording,
+    trim: Option<ResolvedTrim>,
+    total_duration_ms: u64,
+) -> FinishedRecording {
+    let mut trimmed = recording.clone();
+    if let Some(trim) = trim {
+        trimmed.chapters = trim.shift_chapters(&recording.chapters);
+        trimmed.duration_ms = trim.duration_ms(total_duration_ms);
+    }
+    trimmed
+}
+
+/// `-ss` va antes del `-i` del video: con `-c:v copy` FFmpeg arranca en el
+/// keyframe, que es justo donde ya se ajustó el inicio.
+fn add_trim_seek(cmd: &mut Command, trim: Option<ResolvedTrim>) {
+    if let Some(trim) = trim.filter(|trim| trim.start_ms > 0) {
+        cmd.arg("-ss").arg(ffmpeg_seconds(trim.start_ms));
+    }
+}
+
+fn add_trim_duration(cmd: &mut Command, trim: Option<ResolvedTrim>, trimmed_duration_ms: u64) {
+    if trim.is_some_and(|trim| trim.end_ms > 0) {
+        cmd.arg("-t").arg(ffmpeg_seconds(trimmed_duration_ms));
+    }
+}
+
+fn ffmpeg_seconds(ms: u64) -> String {
+    format!("{}.{:03}", ms / 1_000, ms % 1_000)
+}
+
+fn chapters_file_path(video_path: &Path) -> std::path::PathBuf {
+    video_path.with_extension("chapters.txt")
+}
+
+fn add_chapters_input(
+    cmd: &mut Command,
+    video_path: &Path,
+    recording: &FinishedRecording,
+    input_index: usize,
+) -> Option<usize> {
+    if recording.chapters.is_empty() {
+        return None;
+    }
+
+    let chapters_path = chapters_file_path(video_path);
+    let contents = chapters_ffmetadata(&recording.chapters, recording.duration_ms);
+    if let Err(err) = fs::write(&chapters_path, contents) {
+        eprintln!(
+            "[audio-wasapi] No se pudo preparar el archivo de capítulos; se omiten: {}",
+            err
+        );
+        return None;
+    }
+
+    cmd.arg("-f").arg("ffmetadata").arg("-i").arg(chapters_path);
+    Some(input_index)
+}
+
+fn add_metadata_args(
+    cmd: &mut Command,
+    recording: &FinishedRecording,
+    chapters_input: Option<usize>,
+) {
+    cmd.arg("-map_metadata").arg("0");
+    for (key, value) in recording.container_tags() {
+        cmd.arg("-metadata").arg(format!("{key}={value}"));
+    }
+    if let Some(index) = chapters_input {
+        cmd.arg("-map_chapters").arg(index.to_string());
+    }
+}
+
+fn run_mux_command(mut cmd: Command, total_duration_ms: u64, step: &str) -> Result<(), String> {
+    let mut child = cmd.spawn().map_err(|e| {
         if e.kind() == io::ErrorKind::NotFound {
-            "No se encontró FFmpeg CLI para mux de audio. Define CAPTURIST_FFMPEG_BIN o agrega ffmpeg.exe al PATH."
-                .to_string()
+            format!("No se encontró FFmpeg CLI para {step}. Define CAPTURIST_FFMPEG_BIN o agrega ffmpeg.exe al PATH.")
         } else {
-            format!("No se pudo ejecutar FFmpeg para mux de audio: {}", e)
+            format!("No se pudo ejecutar FFmpeg para {step}: {}", e)
         }
     })?;
 
+    let progress_reader = child.stdout.take().map(|stdout| {
+        std::thread::spawn(move || {
+            ProcessingProgress::start().follow_ffmpeg_output(stdout, 0, total_duration_ms)
+        })
+    });
+    let output = child.wait_with_output();
+    if let Some(reader) = progress_reader {
+        let _ = reader.join();
+    }
+    let output = output.map_err(|e| format!("No se pudo esperar a FFmpeg para {step}: {}", e))?;
+
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        restore_video_only_file(&temp_video, &original_output);
-        let _ = move_temp_to_final(&original_output, final_output_path);
         return Err(format!(
-            "FFmpeg falló al combinar video+audio: {}",
+            "FFmpeg falló en {step}: {}",
             if stderr.is_empty() {
                 "sin salida de error".to_string()
             } else {
@@ -153,66 +422,85 @@ pub(super) fn mux_audio_into_video(
         ));
     }
 
-    let _ = fs::remove_file(&temp_video);
     Ok(())
 }
 
-fn make_video_only_path(output_path: &Path) -> PathBuf {
-    let stem = output_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("recording");
-    let ext = output_path
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("mp4");
-    output_path.with_file_name(format!("{stem}.video_only.{ext}"))
-}
-
-fn restore_video_only_file(video_only: &Path, target_output: &Path) {
-    if target_output.exists() {
-        let _ = fs::remove_file(target_output);
+/// Una pista que superó el límite RIFF quedó repartida en varios WAV; se unen
+/// con el demuxer `concat` para que FFmpeg las lea como una sola entrada.
+fn add_track_input(cmd: &mut Command, wav_path: &Path) {
+    let parts = wav_part_paths(wav_path);
+    if parts.len() > 1 {
+        let list_path = wav_path.with_extension("concat.txt");
+        match fs::write(&list_path, concat_list_contents(&parts)) {
+            Ok(()) => {
+                cmd.arg("-f")
+                    .arg("concat")
+                    .arg("-safe")
+                    .arg("0")
+                    .arg("-i")
+                    .arg(list_path);
+                return;
+            }
+            Err(err) => eprintln!(
+                "[audio-wasapi] No se pudo preparar la lista de partes de audio; solo se usará la primera: {}",
+                err
+            ),
+        }
     }
-    let _ = fs::rename(video_only, target_output);
+
+    cmd.arg("-i").arg(wav_path);
 }
 
-fn should_enable_mp4_faststart() -> bool {
+fn resolve_mp4_faststart(configured: bool) -> bool {
     match env::var("CAPTURIST_MP4_FASTSTART") {
         Ok(value) => {
             let normalized = value.trim().to_ascii_lowercase();
             normalized == "1" || normalized == "true" || normalized == "yes"
         }
-        Err(_) => false,
+        Err(_) => configured,
     }
 }
 
-fn read_audio_sync_offset_ms() -> u64 {
-    match env::var("CAPTURIST_AUDIO_SYNC_OFFSET_MS") {
-        Ok(value) => value
-            .trim()
-            .parse::<u64>()
-            .ok()
-            .map(|parsed| parsed.min(1_000))
-            .unwrap_or(0),
-        Err(_) => 0,
-    }
+fn resolve_audio_sync_offset_ms(configured: i64) -> i64 {
+    env::var("CAPTURIST_AUDIO_SYNC_OFFSET_MS")
+        .ok()
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .unwrap_or(configured)
+        .clamp(-MAX_AUDIO_SYNC_OFFSET_MS, MAX_AUDIO_SYNC_OFFSET_MS)
 }
 
-fn with_added_delay(track: &AudioTrackInput, extra_delay_ms: u64) -> AudioTrackInput {
+/// Aplica el desfase a la pista: positivo se suma al `adelay`, negativo
+/// consume primero el delay y el resto se recorta del inicio con `atrim`.
+fn with_added_delay(track: &AudioTrackInput, offset_ms: i64) -> AudioTrackInput {
+    let (delay_ms, trim_start_ms) = if offset_ms >= 0 {
+        (
+            track.delay_ms.saturating_add(offset_ms as u64),
+            track.trim_start_ms,
+        )
+    } else {
+        let pull_ms = offset_ms.unsigned_abs();
+        let remaining_pull_ms = pull_ms.saturating_sub(track.delay_ms);
+        (
+            track.delay_ms.saturating_sub(pull_ms),
+            track.trim_start_ms.saturating_add(remaining_pull_ms),
+        )
+    };
+
     AudioTrackInput {
         path: track.path.clone(),
-        delay_ms: track.delay_ms.saturating_add(extra_delay_ms),
+        delay_ms,
+        trim_start_ms,
         source: track.source,
     }
 }
 
 fn detect_video_start_delay_ms(video_path: &Path) -> u64 {
-    let Some(path) = video_path.to_str() else {
+    let Ok(path) = ffmpeg_path_arg(video_path) else {
         return 0;
     };
 
     let _ = ffmpeg_the_third::init();
-    let Ok(mut input_ctx) = ffmpeg_format::input(path) else {
+    let Ok(mut input_ctx) = ffmpeg_format::input(&path) else {
         return 0;
     };
     let Some(video_stream) = input_ctx.streams().best(media::Type::Video) else {
@@ -267,12 +555,13 @@ fn should_bypass_single_track_filter(
     track: &AudioTrackInput,
     microphone_gain_percent: u16,
     quality_mode: &QualityMode,
+    loudness_target_lufs: Option<i32>,
 ) -> bool {
-    if track.source != AudioTrackSource::System {
+    if track.source != AudioTrackSource::System || loudness_target_lufs.is_some() {
         return false;
     }
 
-    if track.delay_ms > 0 {
+    if track.delay_ms > 0 || track.trim_start_ms > 0 {
         return false;
     }
 
@@ -289,7 +578,8 @@ fn should_bypass_single_track_filter(
 #[cfg(test)]
 mod tests {
     use super::{
-        should_bypass_single_track_filter, AudioTrackInput, AudioTrackSource, QualityMode,
+        in_process_filter_spec, should_bypass_single_track_filter, with_added_delay,
+        AudioCaptureConfig, AudioTrackInput, AudioTrackSource, QualityMode,
     };
     use std::path::PathBuf;
 
@@ -297,22 +587,77 @@ mod tests {
         AudioTrackInput {
             path: PathBuf::from("system.wav"),
             delay_ms,
+            trim_start_ms: 0,
             source: AudioTrackSource::System,
         }
     }
 
+    #[test]
+    fn grafo_en_proceso_usa_las_mismas_etiquetas_que_la_cli() {
+        let config = AudioCaptureConfig::default();
+        let single = in_process_filter_spec(&[system_track(0)], &config, &QualityMode::Balanced);
+        assert!(single.starts_with("[1:a]"), "{single}");
+        assert!(single.ends_with("[aout]"), "{single}");
+
+        let mut microphone = system_track(0);
+        microphone.source = AudioTrackSource::Microphone;
+        let mix = in_process_filter_spec(
+            &[system_track(0), microphone],
+            &config,
+            &QualityMode::Balanced,
+        );
+        assert!(mix.contains("[1:a]") && mix.contains("[2:a]"), "{mix}");
+        assert!(mix.contains("amix=inputs=2"), "{mix}");
+        assert!(mix.ends_with("[aout]"), "{mix}");
+    }
+
+    #[test]
+    fn desfase_positivo_suma_delay_sin_recortar() {
+        let adjusted = with_added_delay(&system_track(120), 80);
+        assert_eq!(adjusted.delay_ms, 200);
+        assert_eq!(adjusted.trim_start_ms, 0);
+    }
+
+    #[test]
+    fn desfase_negativo_consume_delay_y_recorta_el_resto() {
+        let partial = with_added_delay(&system_track(120), -80);
+        assert_eq!(partial.delay_ms, 40);
+        assert_eq!(partial.trim_start_ms, 0);
+
+        let exceeded = with_added_delay(&system_track(120), -300);
+        assert_eq!(exceeded.delay_ms, 0);
+        assert_eq!(exceeded.trim_start_ms, 180);
+
+        let no_delay = with_added_delay(&system_track(0), -250);
+        assert_eq!(no_delay.delay_ms, 0);
+        assert_eq!(no_delay.trim_start_ms, 250);
+    }
+
+    #[test]
+    fn pista_recortada_no_usa_bypass() {
+        let trimmed = with_added_delay(&system_track(0), -100);
+        assert!(!should_bypass_single_track_filter(
+            &trimmed,
+            100,
+            &QualityMode::Performance,
+            None
+        ));
+    }
+
     #[test]
     fn bypass_single_track_filter_para_sistema_sin_delay_en_modos_rapidos() {
         let track = system_track(0);
         assert!(should_bypass_single_track_filter(
             &track,
             100,
-            &QualityMode::Performance
+            &QualityMode::Performance,
+            None
         ));
         assert!(should_bypass_single_track_filter(
             &track,
             100,
-            &QualityMode::Balanced
+            &QualityMode::Balanced,
+            None
         ));
     }
 
@@ -322,14 +667,26 @@ mod tests {
         assert!(!should_bypass_single_track_filter(
             &delayed,
             100,
-            &QualityMode::Balanced
+            &QualityMode::Balanced,
+            None
         ));
 
         let no_delay = system_track(0);
         assert!(!should_bypass_single_track_filter(
             &no_delay,
             100,
-            &QualityMode::Quality
+            &QualityMode::Quality,
+            None
+        ));
+    }
+
+    #[test]
+    fn normalizar_sonoridad_desactiva_el_bypass() {
+        assert!(!should_bypass_single_track_filter(
+            &system_track(0),
+            100,
+            &QualityMode::Performance,
+            Some(-16)
         ));
     }
 }
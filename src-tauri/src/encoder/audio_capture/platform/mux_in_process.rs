@@ -0,0 +1,584 @@
+use std::{
+    collections::VecDeque,
+    ffi::{c_char, CStr},
+    path::{Path, PathBuf},
+};
+
+use ffmpeg_the_third::{
+    codec::{self, decoder, encoder},
+    ffi, filter,
+    format::{self, flag::Flags},
+    frame, media, packet, Dictionary, Error as FfmpegError, Rational,
+};
+
+use crate::encoder::{
+    audio_capture::wav_writer::wav_part_paths,
+    chapters::chapter_ranges,
+    config::{AudioCaptureConfig, AudioCodec, OutputFormat},
+    output_paths::ffmpeg_path_arg,
+    processing_status::ProcessingProgress,
+    recording_history::FinishedRecording,
+    trim::ResolvedTrim,
+};
+
+use super::{dsp::MIX_SAMPLE_RATE_HZ, AudioTrackInput};
+
+const SINK_NAME: &str = "out";
+
+fn input_label(index: usize) -> String {
+    format!("{}:a", index + 1)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn mux_in_process(
+    format: &OutputFormat,
+    video_path: &Path,
+    output_path: &Path,
+    tracks: &[AudioTrackInput],
+    filter_spec: &str,
+    audio_config: &AudioCaptureConfig,
+    mp4_faststart: bool,
+    recording: &FinishedRecording,
+    total_duration_ms: u64,
+    trim: Option<ResolvedTrim>,
+) -> Result<(), String> {
+    ffmpeg_the_third::init().map_err(|err| format!("No se pudo inicializar FFmpeg: {err}"))?;
+
+    let mut video_input = format::input(&ffmpeg_path_arg(video_path)?)
+        .map_err(|err| format!("No se pudo abrir el video base: {err}"))?;
+    let mut output =
+        format::output_as(&ffmpeg_path_arg(output_path)?, format.capture_format_name())
+            .map_err(|err| format!("No se pudo crear el archivo mezclado: {err}"))?;
+    let needs_global_header = output.format().flags().contains(Flags::GLOBAL_HEADER);
+
+    let (video_in_idx, video_in_tb, video_out_idx) = {
+        let stream = video_input
+            .streams()
+            .best(media::Type::Video)
+            .ok_or_else(|| "El video base no tiene stream de video".to_string())?;
+        let mut video_out = output
+            .add_stream(encoder::find(codec::Id::None))
+            .map_err(|err| format!("No se pudo agregar el stream de video: {err}"))?;
+        video_out.set_parameters(stream.parameters());
+        (stream.index(), stream.time_base(), video_out.index())
+    };
+
+    let mut audio = AudioMixer::open(
+        &mut output,
+        tracks,
+        filter_spec,
+        format,
+        audio_config,
+        needs_global_header,
+    )?;
+
+    let mut metadata = video_input.metadata().to_owned();
+    for (key, value) in recording.container_tags() {
+        metadata.set(key, &value);
+    }
+    output.set_metadata(metadata);
+    for (id, (marker, end_ms)) in chapter_ranges(&recording.chapters, recording.duration_ms)
+        .into_iter()
+        .enumerate()
+    {
+        output
+            .add_chapter(
+                id as i64,
+                Rational::new(1, 1_000),
+                marker.elapsed_ms as i64,
+                end_ms as i64,
+                &marker.label,
+            )
+            .map_err(|err| format!("No se pudo agregar el capítulo '{}': {err}", marker.label))?;
+    }
+
+    let mut muxer_opts = Dictionary::new();
+    if *format == OutputFormat::Mp4 && mp4_faststart {
+        muxer_opts.set("movflags", "+faststart");
+    }
+    output
+        .write_header_with(muxer_opts)
+        .map_err(|err| format!("No se pudo escribir cabecera del contenedor: {err}"))?;
+    let video_out_tb = output
+        .stream(video_out_idx)
+        .map(|stream| stream.time_base())
+        .ok_or_else(|| "No se encontró el stream de video de salida".to_string())?;
+
+    let progress = ProcessingProgress::start();
+    let mut video_cut = trim.map(|trim| VideoCut::new(trim, video_in_tb));
+    let mut video_end_ms = 0;
+    loop {
+        let mut video_packet = packet::Packet::empty();
+        match video_packet.read(&mut video_input) {
+            Ok(()) => {}
+            Err(FfmpegError::Eof) => break,
+            Err(err) => return Err(format!("Error leyendo el video base: {err}")),
+        }
+        if video_packet.stream() != video_in_idx {
+            continue;
+        }
+        if let Some(cut) = video_cut.as_mut() {
+            match cut.apply(&mut video_packet) {
+                CutAction::Keep => {}
+                CutAction::Skip => continue,
+                CutAction::Stop => break,
+            }
+        }
+
+        let packet_ms = video_packet
+            .dts()
+            .or(video_packet.pts())
+            .map_or(0, |ts| timestamp_ms(ts, video_in_tb));
+        if let Some(pts) = video_packet.pts() {
+            video_end_ms =
+                video_end_ms.max(timestamp_ms(pts + video_packet.duration(), video_in_tb));
+        }
+        audio.encode_until(&mut output, packet_ms)?;
+
+        video_packet.set_stream(video_out_idx);
+        video_packet.rescale_ts(video_in_tb, video_out_tb);
+        video_packet.set_position(-1);
+        video_packet
+            .write_interleaved(&mut output)
+            .map_err(|err| format!("Error copiando el video: {err}"))?;
+        progress.report(packet_ms, total_duration_ms);
+    }
+
+    audio.encode_until(&mut output, video_end_ms)?;
+    audio.finish(&mut output)?;
+    output
+        .write_trailer()
+        .map_err(|err| format!("Error escribiendo trailer del contenedor: {err}"))
+}
+
+enum CutAction {
+    Keep,
+    Skip,
+    Stop,
+}
+
+/// Recorte del video copiado: descarta los packets anteriores al keyframe de
+/// inicio y los posteriores al final, y corre los timestamps hacia 0.
+struct VideoCut {
+    trim: ResolvedTrim,
+    time_base: Rational,
+    origin: Option<i64>,
+    offset: Option<i64>,
+}
+
+impl VideoCut {
+    fn new(trim: ResolvedTrim, time_base: Rational) -> Self {
+        Self {
+            trim,
+            time_base,
+            origin: None,
+            offset: None,
+        }
+    }
+
+    fn apply(&mut self, packet: &mut packet::Packet) -> CutAction {
+        let Some(ts) = packet.pts().or(packet.dts()) else {
+            return if self.offset.is_some() {
+                CutAction::Keep
+            } else {
+                CutAction::Skip
+            };
+        };
+        let origin = *self.origin.get_or_insert(ts);
+        let time_base = self.time_base;
+        let relative_ms = |ts: i64| timestamp_ms(ts - origin, time_base);
+
+        let offset = match self.offset {
+            Some(offset) => offset,
+            None if packet.is_key() && relative_ms(ts) >= self.trim.start_ms => {
+                *self.offset.insert(packet.dts().unwrap_or(ts))
+            }
+            None => return CutAction::Skip,
+        };
+        if self.trim.end_ms > 0 && relative_ms(ts) >= self.trim.end_ms {
+            // Con B-frames aún pueden llegar packets anteriores al final.
+            let decode_ms = relative_ms(packet.dts().unwrap_or(ts));
+            return if decode_ms >= self.trim.end_ms {
+                CutAction::Stop
+            } else {
+                CutAction::Skip
+            };
+        }
+
+        packet.set_pts(packet.pts().map(|pts| pts - offset));
+        packet.set_dts(packet.dts().map(|dts| dts - offset));
+        CutAction::Keep
+    }
+}
+
+fn timestamp_ms(timestamp: i64, time_base: Rational) -> u64 {
+    let ms = i128::from(timestamp.max(0)) * i128::from(time_base.numerator()) * 1_000
+        / i128::from(time_base.denominator().max(1));
+    u64::try_from(ms).unwrap_or(0)
+}
+
+fn encoder_sample_format(codec: AudioCodec) -> format::Sample {
+    match codec {
+        AudioCodec::Aac => format::Sample::F32(format::sample::Type::Planar),
+        AudioCodec::Opus => format::Sample::F32(format::sample::Type::Packed),
+        AudioCodec::Flac | AudioCodec::Pcm => format::Sample::I16(format::sample::Type::Packed),
+    }
+}
+
+fn channel_layout_name(layout: &ffi::AVChannelLayout) -> String {
+    let mut buf = [0 as c_char; 64];
+    unsafe {
+        ffi::av_channel_layout_describe(layout, buf.as_mut_ptr(), buf.len());
+        CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned()
+    }
+}
+
+struct PartReader {
+    input: format::context::Input,
+    decoder: decoder::Audio,
+    stream_idx: usize,
+    draining: bool,
+}
+
+struct TrackDecoder {
+    pending_parts: VecDeque<PathBuf>,
+    current: Option<PartReader>,
+    next_pts: i64,
+    finished: bool,
+}
+
+impl TrackDecoder {
+    fn open(path: &Path) -> Result<Self, String> {
+        let mut pending_parts: VecDeque<PathBuf> = wav_part_paths(path).into();
+        if pending_parts.is_empty() {
+            pending_parts.push_back(path.to_path_buf());
+        }
+        let mut track = Self {
+            pending_parts,
+            current: None,
+            next_pts: 0,
+            finished: false,
+        };
+        track.open_next_part()?;
+        Ok(track)
+    }
+
+    fn open_next_part(&mut self) -> Result<(), String> {
+        self.current = None;
+        let Some(part) = self.pending_parts.pop_front() else {
+            return Ok(());
+        };
+        let input = format::input(&ffmpeg_path_arg(&part)?)
+            .map_err(|err| format!("No se pudo abrir '{}': {err}", part.display()))?;
+        let (stream_idx, decoder) = {
+            let stream = input
+                .streams()
+                .best(media::Type::Audio)
+                .ok_or_else(|| format!("'{}' no tiene audio", part.display()))?;
+            let decoder = codec::context::Context::from_parameters(stream.parameters())
+                .and_then(|context| context.decoder().audio())
+                .map_err(|err| format!("No se pudo decodificar '{}': {err}", part.display()))?;
+            (stream.index(), decoder)
+        };
+        self.current = Some(PartReader {
+            input,
+            decoder,
+            stream_idx,
+            draining: false,
+        });
+        Ok(())
+    }
+
+    fn decoder(&self) -> Result<&decoder::Audio, String> {
+        self.current
+            .as_ref()
+            .map(|part| &part.decoder)
+            .ok_or_else(|| "La pista de audio está vacía".to_string())
+    }
+
+    fn abuffer_args(&self) -> Result<String, String> {
+        let decoder = self.decoder()?;
+        let layout = unsafe { channel_layout_name(&(*decoder.as_ptr()).ch_layout) };
+        Ok(format!(
+            "time_base=1/{rate}:sample_rate={rate}:sample_fmt={}:channel_layout={layout}",
+            decoder.format().name(),
+            rate = decoder.rate(),
+        ))
+    }
+
+    fn fed_ms(&self) -> u64 {
+        let rate = self
+            .decoder()
+            .map(|decoder| u64::from(decoder.rate().max(1)))
+            .unwrap_or(1);
+        self.next_pts.max(0) as u64 * 1_000 / rate
+    }
+
+    fn next_frame(&mut self) -> Result<Option<frame::Audio>, String> {
+        let mut decoded = frame::Audio::empty();
+        loop {
+            let Some(part) = self.current.as_mut() else {
+                return Ok(None);
+            };
+            if part.decoder.receive_frame(&mut decoded).is_ok() {
+                // Cada parte empieza en 0: se numeran seguidas para el filtro.
+                decoded.set_pts(Some(self.next_pts));
+                self.next_pts += decoded.samples() as i64;
+                return Ok(Some(decoded));
+            }
+            if part.draining {
+                self.open_next_part()?;
+                continue;
+            }
+
+            let mut wav_packet = packet::Packet::empty();
+            match wav_packet.read(&mut part.input) {
+                Ok(()) if wav_packet.stream() == part.stream_idx => part
+                    .decoder
+                    .send_packet(&wav_packet)
+                    .map_err(|err| format!("Error decodificando audio: {err}"))?,
+                Ok(()) => {}
+                Err(FfmpegError::Eof) => {
+                    part.draining = true;
+                    part.decoder
+                        .send_eof()
+                        .map_err(|err| format!("Error cerrando el decoder de audio: {err}"))?;
+                }
+                Err(err) => return Err(format!("Error leyendo audio temporal: {err}")),
+            }
+        }
+    }
+}
+
+struct AudioMixer {
+    tracks: Vec<TrackDecoder>,
+    graph: filter::Graph,
+    audio_enc: encoder::Audio,
+    stream_idx: usize,
+    sample_rate: i32,
+    next_pts: i64,
+    graph_done: bool,
+}
+
+impl AudioMixer {
+    fn open(
+        output: &mut format::context::Output,
+        tracks: &[AudioTrackInput],
+        filter_spec: &str,
+        format: &OutputFormat,
+        audio_config: &AudioCaptureConfig,
+        needs_global_header: bool,
+    ) -> Result<Self, String> {
+        let decoders = tracks
+            .iter()
+            .map(|track| TrackDecoder::open(&track.path))
+            .collect::<Result<Vec<_>, _>>()?;
+        let first = decoders
+            .first()
+            .ok_or_else(|| "No hay pistas de audio para mezclar".to_string())?
+            .decoder()?;
+
+        let codec_kind = audio_config.effective_codec(format);
+        // Sin valor se conserva la frecuencia de la pista, salvo al mezclar
+        // (`amix` trabaja a 48 kHz) y con Opus, que solo admite 48 kHz.
+        let sample_rate = audio_config.audio_sample_rate.unwrap_or(
+            if decoders.len() > 1 || codec_kind == AudioCodec::Opus {
+                MIX_SAMPLE_RATE_HZ
+            } else {
+                first.rate()
+            },
+        );
+        let channels = audio_config.audio_channels.unwrap_or_else(|| {
+            decoders
+                .iter()
+                .filter_map(|track| track.decoder().ok())
+                .map(|decoder| decoder.ch_layout().channels())
+                .max()
+                .unwrap_or(2)
+                .clamp(1, 2) as u16
+        });
+        let sample_format = encoder_sample_format(codec_kind);
+
+        let audio_codec =
+            encoder::find_by_name(codec_kind.ffmpeg_encoder_name()).ok_or_else(|| {
+                format!(
+                    "FFmpeg no tiene el encoder {}",
+                    codec_kind.ffmpeg_encoder_name()
+                )
+            })?;
+        let mut audio_enc = codec::context::Context::new_with_codec(audio_codec)
+            .encoder()
+            .audio()
+            .map_err(|err| format!("No se pudo crear el encoder de audio: {err}"))?;
+        let time_base = Rational::new(1, sample_rate as i32);
+        audio_enc.set_rate(sample_rate as i32);
+        audio_enc.set_format(sample_format);
+        audio_enc.set_time_base(time_base);
+        if let Some(bitrate_kbps) = audio_config.effective_bitrate_kbps(format) {
+            audio_enc.set_bit_rate(bitrate_kbps as usize * 1_000);
+        }
+        unsafe {
+            ffi::av_channel_layout_default(
+                &mut (*audio_enc.as_mut_ptr()).ch_layout,
+                i32::from(channels),
+            );
+        }
+        if needs_global_header {
+            audio_enc.set_flags(codec::Flags::GLOBAL_HEADER);
+        }
+        let audio_enc = audio_enc
+            .open_as(audio_codec)
+            .map_err(|err| format!("No se pudo abrir el encoder de audio: {err}"))?;
+
+        let mut stream = output
+            .add_stream(audio_codec)
+            .map_err(|err| format!("No se pudo agregar el stream de audio: {err}"))?;
+        stream.copy_parameters_from_context(&audio_enc);
+        stream.set_time_base(time_base);
+        let stream_idx = stream.index();
+
+        let mut graph =
+            build_filter_graph(&decoders, filter_spec, sample_format, sample_rate, channels)?;
+        if audio_enc.frame_size() > 0 {
+            graph
+                .get(SINK_NAME)
+                .ok_or_else(|| "El filtro de audio no tiene salida".to_string())?
+                .sink()
+                .set_frame_size(audio_enc.frame_size());
+        }
+
+        Ok(Self {
+            tracks: decoders,
+            graph,
+            audio_enc,
+            stream_idx,
+            sample_rate: sample_rate as i32,
+            next_pts: 0,
+            graph_done: false,
+        })
+    }
+
+    fn encoded_ms(&self) -> u64 {
+        self.next_pts.max(0) as u64 * 1_000 / self.sample_rate.max(1) as u64
+    }
+
+    fn encode_until(
+        &mut self,
+        output: &mut format::context::Output,
+        limit_ms: u64,
+    ) -> Result<(), String> {
+        let mut filtered = frame::Audio::empty();
+        while !self.graph_done && self.encoded_ms() < limit_ms {
+            let pulled = self
+                .graph
+                .get(SINK_NAME)
+                .ok_or_else(|| "El filtro de audio no tiene salida".to_string())?
+                .sink()
+                .frame(&mut filtered);
+            match pulled {
+                Ok(()) => {
+                    filtered.set_pts(Some(self.next_pts));
+                    self.next_pts += filtered.samples() as i64;
+                    self.audio_enc
+                        .send_frame(&filtered)
+                        .map_err(|err| format!("Error codificando audio: {err}"))?;
+                    self.drain(output)?;
+                }
+                Err(FfmpegError::Eof) => self.graph_done = true,
+                Err(_) => self.feed()?,
+            }
+        }
+        Ok(())
+    }
+
+    fn feed(&mut self) -> Result<(), String> {
+        let Some(index) = self
+            .tracks
+            .iter()
+            .enumerate()
+            .filter(|(_, track)| !track.finished)
+            .min_by_key(|(_, track)| track.fed_ms())
+            .map(|(index, _)| index)
+        else {
+            self.graph_done = true;
+            return Ok(());
+        };
+
+        let next = self.tracks[index].next_frame()?;
+        let mut source = self
+            .graph
+            .get(&input_label(index))
+            .ok_or_else(|| "El filtro de audio no tiene entrada".to_string())?;
+        let result = match next {
+            Some(decoded) => source.source().add(&decoded),
+            None => {
+                self.tracks[index].finished = true;
+                source.source().flush()
+            }
+        };
+        result.map_err(|err| format!("Error filtrando audio: {err}"))
+    }
+
+    fn finish(&mut self, output: &mut format::context::Output) -> Result<(), String> {
+        self.audio_enc
+            .send_eof()
+            .map_err(|err| format!("Error cerrando el encoder de audio: {err}"))?;
+        self.drain(output)
+    }
+
+    fn drain(&mut self, output: &mut format::context::Output) -> Result<(), String> {
+        let stream_time_base = output
+            .stream(self.stream_idx)
+            .map(|stream| stream.time_base())
+            .ok_or_else(|| "No se encontró el stream de audio de salida".to_string())?;
+        let mut encoded = packet::Packet::empty();
+        while self.audio_enc.receive_packet(&mut encoded).is_ok() {
+            encoded.set_stream(self.stream_idx);
+            encoded.rescale_ts(Rational::new(1, self.sample_rate), stream_time_base);
+            encoded
+                .write_interleaved(output)
+                .map_err(|err| format!("Error escribiendo audio: {err}"))?;
+        }
+        Ok(())
+    }
+}
+
+fn build_filter_graph(
+    tracks: &[TrackDecoder],
+    filter_spec: &str,
+    sample_format: format::Sample,
+    sample_rate: u32,
+    channels: u16,
+) -> Result<filter::Graph, String> {
+    let graph_error = |err: FfmpegError| format!("No se pudo armar el filtro de audio: {err}");
+    let abuffer = filter::find("abuffer").ok_or_else(|| "FFmpeg no tiene abuffer".to_string())?;
+    let abuffersink =
+        filter::find("abuffersink").ok_or_else(|| "FFmpeg no tiene abuffersink".to_string())?;
+
+    let mut graph = filter::Graph::new();
+    for (index, track) in tracks.iter().enumerate() {
+        graph
+            .add(&abuffer, &input_label(index), &track.abuffer_args()?)
+            .map_err(graph_error)?;
+    }
+    graph
+        .add(&abuffersink, SINK_NAME, "")
+        .map_err(graph_error)?;
+
+    let spec = format!(
+        "{filter_spec};[aout]aformat=sample_fmts={}:sample_rates={sample_rate}:channel_layouts={}[{SINK_NAME}]",
+        sample_format.name(),
+        if channels == 1 { "mono" } else { "stereo" },
+    );
+    let mut parser = graph.output(&input_label(0), 0).map_err(graph_error)?;
+    for index in 1..tracks.len() {
+        parser = parser.output(&input_label(index), 0).map_err(graph_error)?;
+    }
+    parser
+        .input(SINK_NAME, 0)
+        .map_err(graph_error)?
+        .parse(&spec)
+        .map_err(graph_error)?;
+    graph.validate().map_err(graph_error)?;
+    Ok(graph)
+}
@@ -1,15 +1,16 @@
 #![cfg_attr(not(target_os = "windows"), allow(dead_code))]
 
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc};
 
 use tempfile::TempDir;
 
 use crate::{
-    encoder::audio_capture::LiveAudioStatusSnapshot,
+    encoder::audio_capture::{live_tap::LiveAudioTap, LiveAudioStatusSnapshot},
     encoder::{
         config::{AudioCaptureConfig, OutputFormat, QualityMode},
         output_paths::move_temp_to_final,
         processing_status::ProcessingGuard,
+        recording_history::FinishedRecording,
     },
 };
 
@@ -17,6 +18,7 @@ pub struct AudioCaptureServiceImpl {
     config: AudioCaptureConfig,
     _format: OutputFormat,
     _quality_mode: QualityMode,
+    _mp4_faststart: bool,
     output_path: PathBuf,
     final_output_path: PathBuf,
     _temp_dir: TempDir,
@@ -27,6 +29,7 @@ impl AudioCaptureServiceImpl {
         config: AudioCaptureConfig,
         format: OutputFormat,
         quality_mode: QualityMode,
+        mp4_faststart: bool,
         output_path: PathBuf,
         final_output_path: PathBuf,
         temp_dir: TempDir,
@@ -35,6 +38,7 @@ impl AudioCaptureServiceImpl {
             config,
             _format: format,
             _quality_mode: quality_mode,
+            _mp4_faststart: mp4_faststart,
             output_path,
             final_output_path,
             _temp_dir: temp_dir,
@@ -48,16 +52,45 @@ impl AudioCaptureServiceImpl {
         Ok(())
     }
 
-    pub fn finalize_and_mux(&mut self) -> Result<(), String> {
+    pub fn stop_capture(&mut self) {}
+
+    pub fn finalize_and_mux(&mut self, _recording: &FinishedRecording) -> Result<(), String> {
         let _processing_guard = ProcessingGuard::start();
         move_temp_to_final(&self.output_path, &self.final_output_path)
     }
 }
 
+pub struct LiveAudioCaptureImpl;
+
+impl LiveAudioCaptureImpl {
+    pub fn start(config: AudioCaptureConfig, _tap: Arc<LiveAudioTap>) -> Result<Self, String> {
+        if config.is_enabled() {
+            return Err("La captura de audio WASAPI solo está disponible en Windows.".to_string());
+        }
+        Ok(Self)
+    }
+
+    pub fn stop(self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
 pub fn list_microphone_input_devices() -> Result<Vec<String>, String> {
     Ok(Vec::new())
 }
 
+pub fn list_system_audio_output_devices() -> Result<Vec<String>, String> {
+    Ok(Vec::new())
+}
+
+pub fn resolve_system_audio_device(_preferred: Option<&str>) -> Result<String, String> {
+    Err("La captura de audio solo está disponible en Windows.".to_string())
+}
+
+pub fn resolve_microphone_device(_preferred: Option<&str>) -> Result<String, String> {
+    Err("La captura de audio solo está disponible en Windows.".to_string())
+}
+
 pub fn update_live_audio_capture(
     _capture_system_audio: bool,
     _capture_microphone_audio: bool,
@@ -65,8 +98,18 @@ pub fn update_live_audio_capture(
     Err("La actualización de audio en vivo solo está disponible en Windows.".to_string())
 }
 
+pub fn set_live_microphone_gain(_percent: u16) -> Result<(), String> {
+    Err("La ganancia del micrófono en vivo solo está disponible en Windows.".to_string())
+}
+
+pub fn set_live_microphone_monitoring(_enabled: bool) -> Result<(), String> {
+    Err("La escucha del micrófono solo está disponible en Windows.".to_string())
+}
+
 pub fn apply_audio_capture_config(_config: &AudioCaptureConfig) {}
 
 pub fn get_live_audio_status() -> LiveAudioStatusSnapshot {
     LiveAudioStatusSnapshot::default()
 }
+
+pub fn clear_live_audio_capture() {}
@@ -1,9 +1,8 @@
 use std::{
-    fs::File,
-    io::{self, Seek, SeekFrom, Write},
-    path::{Path, PathBuf},
+    io,
+    path::PathBuf,
     sync::{
-        atomic::{AtomicBool, AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU16, AtomicU64, Ordering},
         Arc,
     },
     thread::{self, JoinHandle},
@@ -16,8 +15,8 @@ use windows::{
         Foundation::RPC_E_CHANGED_MODE,
         Media::Audio::{
             IAudioCaptureClient, IAudioClient, IMMDeviceEnumerator, MMDeviceEnumerator,
-            AUDCLNT_BUFFERFLAGS_SILENT, AUDCLNT_SHAREMODE_SHARED, AUDCLNT_STREAMFLAGS_LOOPBACK,
-            WAVEFORMATEX,
+            AUDCLNT_BUFFERFLAGS_SILENT, AUDCLNT_E_DEVICE_INVALIDATED, AUDCLNT_SHAREMODE_SHARED,
+            AUDCLNT_STREAMFLAGS_LOOPBACK, WAVEFORMATEX,
         },
         System::Com::{
             CoCreateInstance, CoInitializeEx, CoTaskMemFree, CoUninitialize, CLSCTX_ALL,
@@ -26,21 +25,95 @@ use windows::{
     },
 };
 
-use super::device_discovery::{to_utf16_null, DeviceDescriptor};
+use crate::encoder::audio_capture::{
+    device_loss::{
+        notify_audio_device_lost, silence_padding_frames, AudioDeviceLost, AudioTrackKind,
+    },
+    live_gain::{sample_format_from_wave_format, GainProcessor},
+    live_tap::LiveAudioTap,
+    track_activity::TrackActivity,
+    wav_writer::RollingWavWriter,
+};
+
+use super::{
+    device_discovery::{to_utf16_null, DeviceDescriptor},
+    mic_monitor::{MicMonitor, MonitorRoute},
+};
 
 const FIRST_ENABLE_UNSET: u64 = u64::MAX;
+pub(super) const DEVICE_LOST_UNSET: u64 = u64::MAX;
+const DEVICE_LOST_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+pub(super) enum TrackDestination {
+    Wav(PathBuf),
+    Live(Arc<LiveAudioTap>),
+}
+
+enum TrackOutput {
+    Wav(RollingWavWriter),
+    Live(Arc<LiveAudioTap>),
+}
+
+impl TrackOutput {
+    fn write_samples(&mut self, data: &[u8]) -> io::Result<()> {
+        match self {
+            TrackOutput::Wav(writer) => writer.write_samples(data),
+            TrackOutput::Live(tap) => {
+                tap.push(data);
+                Ok(())
+            }
+        }
+    }
+
+    fn write_silence(&mut self, len: usize) -> io::Result<()> {
+        match self {
+            TrackOutput::Wav(writer) => writer.write_silence(len),
+            TrackOutput::Live(tap) => {
+                tap.push_silence(len);
+                Ok(())
+            }
+        }
+    }
+
+    fn finalize(&mut self) -> io::Result<()> {
+        match self {
+            TrackOutput::Wav(writer) => writer.finalize(),
+            TrackOutput::Live(_) => Ok(()),
+        }
+    }
+}
 
 pub(super) struct ActiveCapture {
     pub(super) kind: &'static str,
-    pub(super) wav_path: PathBuf,
+    pub(super) wav_path: Option<PathBuf>,
+    pub(super) device_id: String,
     pub(super) device_name: String,
     pub(super) stop: Arc<AtomicBool>,
     pub(super) enabled: Arc<AtomicBool>,
     pub(super) ever_enabled: Arc<AtomicBool>,
     pub(super) first_enabled_at_ms: Arc<AtomicU64>,
+    pub(super) activity: Arc<TrackActivity>,
+    pub(super) lost_at_ms: Arc<AtomicU64>,
+    pub(super) gain_applied: Arc<AtomicBool>,
     pub(super) handle: Option<JoinHandle<Result<(), String>>>,
 }
 
+pub(super) struct MicrophoneProcessing {
+    pub(super) gain_percent: Arc<AtomicU16>,
+    pub(super) monitor: Option<MonitorRoute>,
+}
+
+struct CaptureSignals {
+    stop: Arc<AtomicBool>,
+    enabled: Arc<AtomicBool>,
+    ever_enabled: Arc<AtomicBool>,
+    first_enabled_at_ms: Arc<AtomicU64>,
+    activity: Arc<TrackActivity>,
+    lost_at_ms: Arc<AtomicU64>,
+    gain_applied: Arc<AtomicBool>,
+    microphone: Option<MicrophoneProcessing>,
+}
+
 pub(super) fn normalized_track_delay(raw_delay: u64) -> u64 {
     if raw_delay == FIRST_ENABLE_UNSET {
         0
@@ -68,11 +141,12 @@ pub(super) fn stop_capture_worker(worker: &mut Option<ActiveCapture>, errors: &m
 
 pub(super) fn spawn_capture_worker(
     kind: &'static str,
-    wav_path: PathBuf,
+    destination: TrackDestination,
     device: DeviceDescriptor,
     loopback: bool,
     initial_enabled: bool,
     recording_started_at: Instant,
+    microphone: Option<MicrophoneProcessing>,
 ) -> Result<ActiveCapture, String> {
     let stop = Arc::new(AtomicBool::new(false));
     let enabled = Arc::new(AtomicBool::new(initial_enabled));
@@ -83,14 +157,28 @@ pub(super) fn spawn_capture_worker(
         FIRST_ENABLE_UNSET
     }));
 
-    let stop_clone = Arc::clone(&stop);
-    let enabled_clone = Arc::clone(&enabled);
-    let ever_enabled_clone = Arc::clone(&ever_enabled);
-    let first_enabled_at_ms_clone = Arc::clone(&first_enabled_at_ms);
+    let activity = Arc::new(TrackActivity::default());
+    let lost_at_ms = Arc::new(AtomicU64::new(DEVICE_LOST_UNSET));
+    let gain_applied = Arc::new(AtomicBool::new(false));
+
+    let signals = CaptureSignals {
+        stop: Arc::clone(&stop),
+        enabled: Arc::clone(&enabled),
+        ever_enabled: Arc::clone(&ever_enabled),
+        first_enabled_at_ms: Arc::clone(&first_enabled_at_ms),
+        activity: Arc::clone(&activity),
+        lost_at_ms: Arc::clone(&lost_at_ms),
+        gain_applied: Arc::clone(&gain_applied),
+        microphone,
+    };
     let id = device.id.clone();
     let name = device.name.clone();
     let name_for_error = name.clone();
-    let worker_path = wav_path.clone();
+    let name_for_worker = name.clone();
+    let wav_path = match &destination {
+        TrackDestination::Wav(path) => Some(path.clone()),
+        TrackDestination::Live(_) => None,
+    };
 
     let thread_name = if loopback {
         "capturist-audio-system"
@@ -103,11 +191,9 @@ pub(super) fn spawn_capture_worker(
         .spawn(move || {
             capture_device_loop(
                 &id,
-                &worker_path,
-                stop_clone,
-                enabled_clone,
-                ever_enabled_clone,
-                first_enabled_at_ms_clone,
+                &name_for_worker,
+                destination,
+                signals,
                 recording_started_at,
                 loopback,
             )
@@ -122,25 +208,38 @@ pub(super) fn spawn_capture_worker(
     Ok(ActiveCapture {
         kind,
         wav_path,
+        device_id: device.id,
         device_name: name,
         stop,
         enabled,
         ever_enabled,
         first_enabled_at_ms,
+        activity,
+        lost_at_ms,
+        gain_applied,
         handle: Some(handle),
     })
 }
 
 fn capture_device_loop(
     device_id: &str,
-    wav_path: &Path,
-    stop: Arc<AtomicBool>,
-    enabled: Arc<AtomicBool>,
-    ever_enabled: Arc<AtomicBool>,
-    first_enabled_at_ms: Arc<AtomicU64>,
+    device_name: &str,
+    destination: TrackDestination,
+    signals: CaptureSignals,
     recording_started_at: Instant,
     loopback: bool,
 ) -> Result<(), String> {
+    let CaptureSignals {
+        stop,
+        enabled,
+        ever_enabled,
+        first_enabled_at_ms,
+        activity,
+        lost_at_ms,
+        gain_applied,
+        microphone,
+    } = signals;
+
     let hr = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
     let should_uninitialize = hr.is_ok();
     if hr.is_err() && hr != RPC_E_CHANGED_MODE {
@@ -172,7 +271,18 @@ fn capture_device_loop(
         };
 
         let format_guard = CoTaskMemPtr(mix_format_ptr as *mut _);
-        let (format_blob, block_align) = parse_wave_format_blob(mix_format_ptr)?;
+        let (format_blob, block_align, sample_rate) = parse_wave_format_blob(mix_format_ptr)?;
+        activity.set_sample_rate(sample_rate);
+        // Sin float de 32 bits ni PCM de 16 la ganancia queda para el mux.
+        let mut gain = microphone.as_ref().and_then(|mic| {
+            sample_format_from_wave_format(&format_blob)
+                .map(|format| (Arc::clone(&mic.gain_percent), GainProcessor::new(format)))
+        });
+        gain_applied.store(gain.is_some(), Ordering::SeqCst);
+        let mut gained_samples = Vec::new();
+        let mut monitor = microphone
+            .and_then(|mic| mic.monitor)
+            .map(|route| MicMonitor::new(route, format_blob.clone(), block_align, sample_rate));
 
         let mut stream_flags = 0u32;
         if loopback {
@@ -198,8 +308,22 @@ fn capture_device_loop(
                 .map_err(|e| format!("No se pudo inicializar IAudioCaptureClient: {}", e))?
         };
 
-        let mut writer = WavFileWriter::create(wav_path, &format_blob)
-            .map_err(|e| format!("No se pudo abrir archivo temporal WAV: {}", e))?;
+        let mut writer = match destination {
+            TrackDestination::Wav(wav_path) => TrackOutput::Wav(
+                RollingWavWriter::create(&wav_path, &format_blob, block_align)
+                    .map_err(|e| format!("No se pudo abrir archivo temporal WAV: {}", e))?,
+            ),
+            TrackDestination::Live(tap) => {
+                let channels = u16::from_le_bytes([format_blob[2], format_blob[3]]);
+                tap.configure(
+                    sample_rate,
+                    channels,
+                    block_align,
+                    sample_format_from_wave_format(&format_blob),
+                );
+                TrackOutput::Live(tap)
+            }
+        };
 
         unsafe {
             audio_client
@@ -207,11 +331,16 @@ fn capture_device_loop(
                 .map_err(|e| format!("No se pudo iniciar stream WASAPI: {}", e))?;
         }
 
-        while !stop.load(Ordering::Relaxed) {
-            let mut frames_in_packet = unsafe {
-                capture_client
-                    .GetNextPacketSize()
-                    .map_err(|e| format!("Error leyendo tamaño de paquete de audio: {}", e))?
+        let mut written_frames = 0u64;
+        let mut device_lost = false;
+        'capture: while !stop.load(Ordering::Relaxed) {
+            let mut frames_in_packet = match unsafe { capture_client.GetNextPacketSize() } {
+                Ok(frames) => frames,
+                Err(e) if is_device_invalidated(&e) => {
+                    device_lost = true;
+                    break 'capture;
+                }
+                Err(e) => return Err(format!("Error leyendo tamaño de paquete de audio: {}", e)),
             };
 
             if frames_in_packet == 0 {
@@ -224,14 +353,29 @@ fn capture_device_loop(
                 let mut frame_count = 0u32;
                 let mut flags = 0u32;
 
-                unsafe {
-                    capture_client
-                        .GetBuffer(&mut data_ptr, &mut frame_count, &mut flags, None, None)
-                        .map_err(|e| format!("Error obteniendo buffer de captura WASAPI: {}", e))?;
+                let buffer_result = unsafe {
+                    capture_client.GetBuffer(
+                        &mut data_ptr,
+                        &mut frame_count,
+                        &mut flags,
+                        None,
+                        None,
+                    )
+                };
+                match buffer_result {
+                    Ok(()) => {}
+                    Err(e) if is_device_invalidated(&e) => {
+                        device_lost = true;
+                        break 'capture;
+                    }
+                    Err(e) => {
+                        return Err(format!("Error obteniendo buffer de captura WASAPI: {}", e))
+                    }
                 }
 
                 let bytes_to_write = (frame_count as usize).saturating_mul(block_align);
                 let is_enabled = enabled.load(Ordering::Relaxed);
+                activity.record_frames(u64::from(frame_count), is_enabled);
                 if is_enabled {
                     let was_enabled_before = ever_enabled.swap(true, Ordering::SeqCst);
                     if !was_enabled_before {
@@ -246,6 +390,9 @@ fn capture_device_loop(
                 }
 
                 let started_track = ever_enabled.load(Ordering::Relaxed);
+                if started_track {
+                    written_frames += u64::from(frame_count);
+                }
                 let write_result = if bytes_to_write == 0 {
                     Ok(())
                 } else if !started_track {
@@ -256,30 +403,94 @@ fn capture_device_loop(
                 {
                     writer.write_silence(bytes_to_write)
                 } else {
-                    let data = unsafe {
+                    let mut data = unsafe {
                         std::slice::from_raw_parts(data_ptr as *const u8, bytes_to_write)
                     };
+                    if let Some((gain_percent, processor)) = gain.as_mut() {
+                        let gain_percent = gain_percent.load(Ordering::Relaxed);
+                        if gain_percent != 100 {
+                            gained_samples.clear();
+                            gained_samples.extend_from_slice(data);
+                            processor.apply(&mut gained_samples, gain_percent);
+                            data = &gained_samples[..];
+                        }
+                    }
+                    if let Some(monitor) = monitor.as_mut() {
+                        monitor.feed(data);
+                    }
                     writer.write_samples(data)
                 };
 
-                let release_result = unsafe { capture_client.ReleaseBuffer(frame_count) };
-                if let Err(e) = release_result {
-                    return Err(format!("Error liberando buffer de captura WASAPI: {}", e));
+                match unsafe { capture_client.ReleaseBuffer(frame_count) } {
+                    Ok(()) => {}
+                    Err(e) if is_device_invalidated(&e) => {
+                        device_lost = true;
+                        break 'capture;
+                    }
+                    Err(e) => {
+                        return Err(format!("Error liberando buffer de captura WASAPI: {}", e))
+                    }
                 }
 
                 if let Err(e) = write_result {
                     return Err(format!("Error escribiendo audio temporal: {}", e));
                 }
 
-                frames_in_packet = unsafe {
-                    capture_client.GetNextPacketSize().map_err(|e| {
-                        format!("Error consultando siguiente paquete de audio: {}", e)
-                    })?
+                frames_in_packet = match unsafe { capture_client.GetNextPacketSize() } {
+                    Ok(frames) => frames,
+                    Err(e) if is_device_invalidated(&e) => {
+                        device_lost = true;
+                        break 'capture;
+                    }
+                    Err(e) => {
+                        return Err(format!(
+                            "Error consultando siguiente paquete de audio: {}",
+                            e
+                        ))
+                    }
                 };
             }
         }
 
+        drop(monitor);
         let _ = unsafe { audio_client.Stop() };
+
+        if device_lost {
+            let lost_ms = recording_started_at.elapsed().as_millis() as u64;
+            lost_at_ms.store(lost_ms, Ordering::SeqCst);
+            let track = if loopback {
+                AudioTrackKind::System
+            } else {
+                AudioTrackKind::Microphone
+            };
+            notify_audio_device_lost(AudioDeviceLost::new(
+                track,
+                device_name.to_string(),
+                lost_ms,
+            ));
+
+            // La sesión sigue; el hueco se rellena al detener para que la
+            // pista dure lo mismo que el video.
+            while !stop.load(Ordering::Relaxed) {
+                thread::sleep(DEVICE_LOST_POLL_INTERVAL);
+            }
+
+            if ever_enabled.load(Ordering::SeqCst) {
+                let padding_frames = silence_padding_frames(
+                    sample_rate,
+                    normalized_track_delay(first_enabled_at_ms.load(Ordering::SeqCst)),
+                    recording_started_at.elapsed().as_millis() as u64,
+                    written_frames,
+                );
+                let padding_bytes = usize::try_from(padding_frames)
+                    .unwrap_or(usize::MAX)
+                    .saturating_mul(block_align);
+                writer
+                    .write_silence(padding_bytes)
+                    .map_err(|e| format!("Error escribiendo audio temporal: {}", e))?;
+            }
+        }
+
         writer
             .finalize()
             .map_err(|e| format!("No se pudo cerrar archivo WAV temporal: {}", e))?;
@@ -294,14 +505,18 @@ fn capture_device_loop(
     result
 }
 
-fn create_device_enumerator() -> Result<IMMDeviceEnumerator, String> {
+fn is_device_invalidated(err: &windows::core::Error) -> bool {
+    err.code() == AUDCLNT_E_DEVICE_INVALIDATED
+}
+
+pub(super) fn create_device_enumerator() -> Result<IMMDeviceEnumerator, String> {
     unsafe {
         CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
             .map_err(|e| format!("No se pudo crear IMMDeviceEnumerator: {}", e))
     }
 }
 
-fn parse_wave_format_blob(format_ptr: *mut WAVEFORMATEX) -> Result<(Vec<u8>, usize), String> {
+fn parse_wave_format_blob(format_ptr: *mut WAVEFORMATEX) -> Result<(Vec<u8>, usize, u32), String> {
     if format_ptr.is_null() {
         return Err("WASAPI devolvió un formato de audio nulo.".to_string());
     }
@@ -311,6 +526,8 @@ fn parse_wave_format_blob(format_ptr: *mut WAVEFORMATEX) -> Result<(Vec<u8>, usi
 
     let cb_size = u16::from_le_bytes([base_slice[16], base_slice[17]]) as usize;
     let block_align = u16::from_le_bytes([base_slice[12], base_slice[13]]) as usize;
+    let sample_rate =
+        u32::from_le_bytes([base_slice[4], base_slice[5], base_slice[6], base_slice[7]]);
     if block_align == 0 {
         return Err("Formato WASAPI inválido: block_align = 0.".to_string());
     }
@@ -323,7 +540,7 @@ fn parse_wave_format_blob(format_ptr: *mut WAVEFORMATEX) -> Result<(Vec<u8>, usi
     }
 
     let full_blob = unsafe { std::slice::from_raw_parts(format_ptr as *const u8, total_len) };
-    Ok((full_blob.to_vec(), block_align))
+    Ok((full_blob.to_vec(), block_align, sample_rate))
 }
 
 struct CoTaskMemPtr<T>(*mut T);
@@ -335,68 +552,3 @@ impl<T> Drop for CoTaskMemPtr<T> {
         }
     }
 }
-
-struct WavFileWriter {
-    file: File,
-    data_size_offset: u64,
-    written_audio_bytes: u64,
-}
-
-impl WavFileWriter {
-    fn create(path: &Path, format_blob: &[u8]) -> io::Result<Self> {
-        let mut file = File::create(path)?;
-        let fmt_size = format_blob.len() as u32;
-
-        file.write_all(b"RIFF")?;
-        file.write_all(&0u32.to_le_bytes())?;
-        file.write_all(b"WAVE")?;
-
-        file.write_all(b"fmt ")?;
-        file.write_all(&fmt_size.to_le_bytes())?;
-        file.write_all(format_blob)?;
-
-        file.write_all(b"data")?;
-        let data_size_offset = file.stream_position()?;
-        file.write_all(&0u32.to_le_bytes())?;
-
-        Ok(Self {
-            file,
-            data_size_offset,
-            written_audio_bytes: 0,
-        })
-    }
-
-    fn write_samples(&mut self, data: &[u8]) -> io::Result<()> {
-        self.file.write_all(data)?;
-        self.written_audio_bytes = self.written_audio_bytes.saturating_add(data.len() as u64);
-        Ok(())
-    }
-
-    fn write_silence(&mut self, len: usize) -> io::Result<()> {
-        const CHUNK: usize = 4096;
-        let zeros = [0u8; CHUNK];
-        let mut remaining = len;
-        while remaining > 0 {
-            let write_now = remaining.min(CHUNK);
-            self.file.write_all(&zeros[..write_now])?;
-            self.written_audio_bytes = self.written_audio_bytes.saturating_add(write_now as u64);
-            remaining -= write_now;
-        }
-        Ok(())
-    }
-
-    fn finalize(&mut self) -> io::Result<()> {
-        let file_size = self.file.seek(SeekFrom::End(0))?;
-        let riff_size = file_size.saturating_sub(8) as u32;
-        let data_size = self.written_audio_bytes.min(u32::MAX as u64) as u32;
-
-        self.file.seek(SeekFrom::Start(4))?;
-        self.file.write_all(&riff_size.to_le_bytes())?;
-
-        self.file.seek(SeekFrom::Start(self.data_size_offset))?;
-        self.file.write_all(&data_size.to_le_bytes())?;
-
-        self.file.flush()?;
-        Ok(())
-    }
-}
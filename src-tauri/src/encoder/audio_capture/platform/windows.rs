@@ -1,7 +1,7 @@
 use std::{
     path::PathBuf,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU16, AtomicU64, Ordering},
         Arc, Mutex, OnceLock,
     },
     time::Instant,
@@ -11,23 +11,32 @@ use tempfile::TempDir;
 use windows::Win32::Media::Audio::{eCapture, eRender, EDataFlow};
 
 use crate::encoder::{
-    audio_capture::LiveAudioStatusSnapshot,
-    config::{AudioCaptureConfig, OutputFormat, QualityMode},
+    audio_capture::{
+        live_tap::LiveAudioTap, track_activity::TrackActivity, LiveAudioStatusSnapshot,
+    },
+    config::{AudioCaptureConfig, OutputFormat, QualityMode, MAX_MICROPHONE_GAIN_PERCENT},
     output_paths::move_temp_to_final,
     processing_status::ProcessingGuard,
+    recording_history::FinishedRecording,
 };
 
 use self::{
-    device_discovery::{list_microphone_input_devices_impl, resolve_device},
-    mux::{audio_file_has_payload, mux_audio_into_video},
+    device_discovery::{
+        list_microphone_input_devices_impl, list_system_audio_output_devices_impl, resolve_device,
+    },
+    mic_monitor::MonitorRoute,
+    mux::{audio_file_has_payload, mux_audio_into_video, remux_without_audio},
     wasapi_capture::{
         normalized_track_delay, spawn_capture_worker, stop_capture_worker, ActiveCapture,
+        MicrophoneProcessing, TrackDestination, DEVICE_LOST_UNSET,
     },
 };
 
 mod device_discovery;
 mod dsp;
+mod mic_monitor;
 mod mux;
+mod mux_in_process;
 mod wasapi_capture;
 
 #[derive(Clone)]
@@ -36,8 +45,40 @@ struct LiveAudioController {
     microphone_enabled: Option<Arc<AtomicBool>>,
     system_device_name: Option<String>,
     microphone_device_name: Option<String>,
+    system_activity: Option<Arc<TrackActivity>>,
+    microphone_activity: Option<Arc<TrackActivity>>,
+    microphone_monitor: Option<MonitorRoute>,
+    microphone_gain_percent: Option<Arc<AtomicU16>>,
+    microphone_gain_applied: Option<Arc<AtomicBool>>,
+    system_lost_at_ms: Option<Arc<AtomicU64>>,
+    microphone_lost_at_ms: Option<Arc<AtomicU64>>,
+}
+
+impl LiveAudioController {
+    fn for_captures(
+        system: Option<&ActiveCapture>,
+        microphone: Option<&ActiveCapture>,
+        monitor_route: Option<MonitorRoute>,
+        microphone_gain_percent: Arc<AtomicU16>,
+    ) -> Self {
+        Self {
+            system_enabled: system.map(|capture| Arc::clone(&capture.enabled)),
+            microphone_enabled: microphone.map(|capture| Arc::clone(&capture.enabled)),
+            system_device_name: system.map(|capture| capture.device_name.clone()),
+            microphone_device_name: microphone.map(|capture| capture.device_name.clone()),
+            system_activity: system.map(|capture| Arc::clone(&capture.activity)),
+            microphone_activity: microphone.map(|capture| Arc::clone(&capture.activity)),
+            microphone_monitor: microphone.and(monitor_route),
+            microphone_gain_applied: microphone.map(|capture| Arc::clone(&capture.gain_applied)),
+            microphone_gain_percent: microphone.map(|_| microphone_gain_percent),
+            system_lost_at_ms: system.map(|capture| Arc::clone(&capture.lost_at_ms)),
+            microphone_lost_at_ms: microphone.map(|capture| Arc::clone(&capture.lost_at_ms)),
+        }
+    }
 }
 
+const MONITOR_FEEDBACK_ERROR: &str = "No se puede escuchar el micrófono: la salida de audio es la misma que se graba como audio del sistema y el micrófono quedaría en esa pista.";
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub(super) enum AudioTrackSource {
     System,
@@ -47,6 +88,7 @@ pub(super) enum AudioTrackSource {
 pub(super) struct AudioTrackInput {
     pub(super) path: PathBuf,
     pub(super) delay_ms: u64,
+    pub(super) trim_start_ms: u64,
     pub(super) source: AudioTrackSource,
 }
 
@@ -54,11 +96,13 @@ pub struct AudioCaptureServiceImpl {
     config: AudioCaptureConfig,
     format: OutputFormat,
     quality_mode: QualityMode,
+    mp4_faststart: bool,
     output_path: PathBuf,
     final_output_path: PathBuf,
     temp_dir: Option<TempDir>,
     system_capture: Option<ActiveCapture>,
     microphone_capture: Option<ActiveCapture>,
+    thread_errors: Vec<String>,
     started: bool,
 }
 
@@ -78,6 +122,7 @@ impl AudioCaptureServiceImpl {
         config: AudioCaptureConfig,
         format: OutputFormat,
         quality_mode: QualityMode,
+        mp4_faststart: bool,
         output_path: PathBuf,
         final_output_path: PathBuf,
         temp_dir: TempDir,
@@ -86,11 +131,13 @@ impl AudioCaptureServiceImpl {
             config,
             format,
             quality_mode,
+            mp4_faststart,
             output_path,
             final_output_path,
             temp_dir: Some(temp_dir),
             system_capture: None,
             microphone_capture: None,
+            thread_errors: Vec::new(),
             started: false,
         }
     }
@@ -120,10 +167,15 @@ impl AudioCaptureServiceImpl {
                 true,
                 self.config.capture_system_audio,
                 self.config.capture_system_audio,
-                temp_base.join("system_audio.wav"),
+                TrackDestination::Wav(temp_base.join("system_audio.wav")),
                 recording_started_at,
+                None,
             )?;
 
+            let monitor_route = self.microphone_monitor_route();
+            let microphone_gain_percent =
+                Arc::new(AtomicU16::new(self.config.microphone_gain_percent));
+
             self.microphone_capture = start_capture_track(
                 "audio de micrófono",
                 eCapture,
@@ -131,29 +183,21 @@ impl AudioCaptureServiceImpl {
                 false,
                 self.config.capture_microphone_audio,
                 self.config.capture_microphone_audio,
-                temp_base.join("microphone_audio.wav"),
+                TrackDestination::Wav(temp_base.join("microphone_audio.wav")),
                 recording_started_at,
+                Some(MicrophoneProcessing {
+                    gain_percent: Arc::clone(&microphone_gain_percent),
+                    monitor: monitor_route.clone(),
+                }),
             )?;
 
             self.started = true;
-            set_live_audio_controller(Some(LiveAudioController {
-                system_enabled: self
-                    .system_capture
-                    .as_ref()
-                    .map(|capture| Arc::clone(&capture.enabled)),
-                microphone_enabled: self
-                    .microphone_capture
-                    .as_ref()
-                    .map(|capture| Arc::clone(&capture.enabled)),
-                system_device_name: self
-                    .system_capture
-                    .as_ref()
-                    .map(|capture| capture.device_name.clone()),
-                microphone_device_name: self
-                    .microphone_capture
-                    .as_ref()
-                    .map(|capture| capture.device_name.clone()),
-            }));
+            set_live_audio_controller(Some(LiveAudioController::for_captures(
+                self.system_capture.as_ref(),
+                self.microphone_capture.as_ref(),
+                monitor_route,
+                microphone_gain_percent,
+            )));
             Ok(())
         })();
 
@@ -168,37 +212,53 @@ impl AudioCaptureServiceImpl {
         Ok(())
     }
 
-    pub fn finalize_and_mux(&mut self) -> Result<(), String> {
+    pub fn stop_capture(&mut self) {
+        if !self.started {
+            return;
+        }
+        stop_capture_worker(&mut self.system_capture, &mut self.thread_errors);
+        stop_capture_worker(&mut self.microphone_capture, &mut self.thread_errors);
+        set_live_audio_controller(None);
+    }
+
+    pub fn finalize_and_mux(&mut self, recording: &FinishedRecording) -> Result<(), String> {
         if !self.started {
             self.reset_state();
             return Ok(());
         }
 
-        let mut thread_errors = Vec::new();
-        stop_capture_worker(&mut self.system_capture, &mut thread_errors);
-        stop_capture_worker(&mut self.microphone_capture, &mut thread_errors);
+        self.stop_capture();
+        let thread_errors = std::mem::take(&mut self.thread_errors);
 
         let mut audio_tracks = Vec::new();
-        if let Some(track) = self.system_capture.as_ref() {
-            if track.ever_enabled.load(Ordering::SeqCst) && audio_file_has_payload(&track.wav_path)
-            {
+        if let Some((track, wav_path)) = self
+            .system_capture
+            .as_ref()
+            .and_then(|track| Some((track, track.wav_path.as_ref()?)))
+        {
+            if track.ever_enabled.load(Ordering::SeqCst) && audio_file_has_payload(wav_path) {
                 audio_tracks.push(AudioTrackInput {
-                    path: track.wav_path.clone(),
+                    path: wav_path.clone(),
                     delay_ms: normalized_track_delay(
                         track.first_enabled_at_ms.load(Ordering::SeqCst),
                     ),
+                    trim_start_ms: 0,
                     source: AudioTrackSource::System,
                 });
             }
         }
-        if let Some(track) = self.microphone_capture.as_ref() {
-            if track.ever_enabled.load(Ordering::SeqCst) && audio_file_has_payload(&track.wav_path)
-            {
+        if let Some((track, wav_path)) = self
+            .microphone_capture
+            .as_ref()
+            .and_then(|track| Some((track, track.wav_path.as_ref()?)))
+        {
+            if track.ever_enabled.load(Ordering::SeqCst) && audio_file_has_payload(wav_path) {
                 audio_tracks.push(AudioTrackInput {
-                    path: track.wav_path.clone(),
+                    path: wav_path.clone(),
                     delay_ms: normalized_track_delay(
                         track.first_enabled_at_ms.load(Ordering::SeqCst),
                     ),
+                    trim_start_ms: 0,
                     source: AudioTrackSource::Microphone,
                 });
             }
@@ -214,7 +274,7 @@ impl AudioCaptureServiceImpl {
                     }
                 }
 
-                let move_err = move_temp_to_final(&self.output_path, &self.final_output_path).err();
+                let move_err = self.finalize_without_audio(recording).err();
                 if let Some(err) = move_err {
                     Err(err)
                 } else if let Some(err) = thread_errors.into_iter().next() {
@@ -228,7 +288,7 @@ impl AudioCaptureServiceImpl {
                         eprintln!("[audio-wasapi] advertencia durante captura: {}", err);
                     }
                 }
-                move_temp_to_final(&self.output_path, &self.final_output_path)
+                self.finalize_without_audio(recording)
             }
         } else {
             if !thread_errors.is_empty() {
@@ -236,13 +296,24 @@ impl AudioCaptureServiceImpl {
                     eprintln!("[audio-wasapi] advertencia durante captura: {}", err);
                 }
             }
+            // Si la ganancia ya quedó en el WAV del micrófono, no se repite en el mux.
+            let gain_applied = self
+                .microphone_capture
+                .as_ref()
+                .is_some_and(|capture| capture.gain_applied.load(Ordering::SeqCst));
+            let mut mux_config = self.config.clone();
+            if gain_applied {
+                mux_config.microphone_gain_percent = 100;
+            }
             mux_audio_into_video(
                 &self.format,
                 &self.quality_mode,
                 &self.output_path,
                 &self.final_output_path,
                 &audio_tracks,
-                self.config.microphone_gain_percent,
+                &mux_config,
+                self.mp4_faststart,
+                recording,
             )
         };
 
@@ -250,8 +321,47 @@ impl AudioCaptureServiceImpl {
         mux_result
     }
 
+    fn microphone_monitor_route(&self) -> Option<MonitorRoute> {
+        let render_device = match resolve_device(eRender, None, "escucha del micrófono") {
+            Ok(device) => device,
+            Err(err) => {
+                eprintln!(
+                    "[audio-wasapi] escucha del micrófono no disponible: {}",
+                    err
+                );
+                return None;
+            }
+        };
+
+        let route = MonitorRoute {
+            enabled: Arc::new(AtomicBool::new(false)),
+            feedback_guard: self
+                .system_capture
+                .as_ref()
+                .filter(|capture| capture.device_id == render_device.id)
+                .map(|capture| Arc::clone(&capture.enabled)),
+            render_device_id: render_device.id,
+        };
+
+        if self.config.monitor_microphone {
+            if route.feedback_risk() {
+                eprintln!("[audio-wasapi] {}", MONITOR_FEEDBACK_ERROR);
+            } else {
+                route.enabled.store(true, Ordering::SeqCst);
+            }
+        }
+        Some(route)
+    }
+
+    fn finalize_without_audio(&self, recording: &FinishedRecording) -> Result<(), String> {
+        if recording.chapters.is_empty() && recording.trim.is_empty() {
+            move_temp_to_final(&self.output_path, &self.final_output_path)
+        } else {
+            remux_without_audio(&self.output_path, &self.final_output_path, recording)
+        }
+    }
+
     fn reset_state(&mut self) {
-        set_live_audio_controller(None);
         self.system_capture = None;
         self.microphone_capture = None;
         self.temp_dir = None;
@@ -259,10 +369,97 @@ impl AudioCaptureServiceImpl {
     }
 }
 
+pub struct LiveAudioCaptureImpl {
+    capture: Option<ActiveCapture>,
+}
+
+impl LiveAudioCaptureImpl {
+    pub fn start(config: AudioCaptureConfig, tap: Arc<LiveAudioTap>) -> Result<Self, String> {
+        let recording_started_at = Instant::now();
+        let microphone_gain_percent = Arc::new(AtomicU16::new(config.microphone_gain_percent));
+        let destination = TrackDestination::Live(tap);
+
+        let (capture, is_microphone, monitor_route) = if config.capture_system_audio {
+            let capture = start_capture_track(
+                "audio del sistema",
+                eRender,
+                config.system_audio_device.as_deref(),
+                true,
+                true,
+                true,
+                destination,
+                recording_started_at,
+                None,
+            )?;
+            (capture, false, None)
+        } else if config.capture_microphone_audio {
+            let monitor_route = resolve_device(eRender, None, "escucha del micrófono")
+                .ok()
+                .map(|render_device| MonitorRoute {
+                    enabled: Arc::new(AtomicBool::new(config.monitor_microphone)),
+                    feedback_guard: None,
+                    render_device_id: render_device.id,
+                });
+            let capture = start_capture_track(
+                "audio de micrófono",
+                eCapture,
+                config.microphone_device.as_deref(),
+                false,
+                true,
+                true,
+                destination,
+                recording_started_at,
+                Some(MicrophoneProcessing {
+                    gain_percent: Arc::clone(&microphone_gain_percent),
+                    monitor: monitor_route.clone(),
+                }),
+            )?;
+            (capture, true, monitor_route)
+        } else {
+            (None, false, None)
+        };
+
+        let (system, microphone) = if is_microphone {
+            (None, capture.as_ref())
+        } else {
+            (capture.as_ref(), None)
+        };
+        set_live_audio_controller(Some(LiveAudioController::for_captures(
+            system,
+            microphone,
+            monitor_route,
+            microphone_gain_percent,
+        )));
+        Ok(Self { capture })
+    }
+
+    pub fn stop(mut self) -> Result<(), String> {
+        let mut errors = Vec::new();
+        stop_capture_worker(&mut self.capture, &mut errors);
+        set_live_audio_controller(None);
+        match errors.into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
 pub fn list_microphone_input_devices() -> Result<Vec<String>, String> {
     list_microphone_input_devices_impl()
 }
 
+pub fn list_system_audio_output_devices() -> Result<Vec<String>, String> {
+    list_system_audio_output_devices_impl()
+}
+
+pub fn resolve_system_audio_device(preferred: Option<&str>) -> Result<String, String> {
+    resolve_device(eRender, preferred, "audio del sistema").map(|device| device.name)
+}
+
+pub fn resolve_microphone_device(preferred: Option<&str>) -> Result<String, String> {
+    resolve_device(eCapture, preferred, "audio de micrófono").map(|device| device.name)
+}
+
 pub fn update_live_audio_capture(
     capture_system_audio: bool,
     capture_microphone_audio: bool,
@@ -290,12 +487,77 @@ pub fn update_live_audio_capture(
     if let Some(flag) = controller.microphone_enabled.as_ref() {
         flag.store(capture_microphone_audio, Ordering::SeqCst);
     }
+    if let Some(route) = controller.microphone_monitor.as_ref() {
+        if route.feedback_risk() {
+            route.enabled.store(false, Ordering::SeqCst);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn set_live_microphone_gain(percent: u16) -> Result<(), String> {
+    if percent > MAX_MICROPHONE_GAIN_PERCENT {
+        return Err(format!(
+            "Ganancia de micrófono inválida: {percent}%. Debe estar entre 0% y {MAX_MICROPHONE_GAIN_PERCENT}%"
+        ));
+    }
+
+    let guard = live_audio_controller_slot()
+        .lock()
+        .map_err(|_| "No se pudo sincronizar la ganancia del micrófono.".to_string())?;
+
+    let controller = guard
+        .as_ref()
+        .ok_or_else(|| "No hay una grabación activa para ajustar el micrófono".to_string())?;
+
+    let (Some(gain_percent), Some(gain_applied)) = (
+        controller.microphone_gain_percent.as_ref(),
+        controller.microphone_gain_applied.as_ref(),
+    ) else {
+        return Err("No hay capturador disponible para micrófono en esta sesión.".to_string());
+    };
+
+    if !gain_applied.load(Ordering::SeqCst) {
+        return Err(
+            "El formato del micrófono no admite ganancia en vivo; se aplica la inicial al terminar."
+                .to_string(),
+        );
+    }
+    gain_percent.store(percent, Ordering::SeqCst);
+    Ok(())
+}
 
+pub fn set_live_microphone_monitoring(enabled: bool) -> Result<(), String> {
+    let guard = live_audio_controller_slot()
+        .lock()
+        .map_err(|_| "No se pudo sincronizar la escucha del micrófono.".to_string())?;
+
+    let controller = guard
+        .as_ref()
+        .ok_or_else(|| "No hay una grabación activa para escuchar el micrófono".to_string())?;
+
+    let route = controller
+        .microphone_monitor
+        .as_ref()
+        .ok_or_else(|| "No hay capturador disponible para micrófono en esta sesión.".to_string())?;
+
+    if enabled && route.feedback_risk() {
+        return Err(MONITOR_FEEDBACK_ERROR.to_string());
+    }
+    route.enabled.store(enabled, Ordering::SeqCst);
     Ok(())
 }
 
 pub fn apply_audio_capture_config(_config: &AudioCaptureConfig) {}
 
+pub fn clear_live_audio_capture() {
+    let mut guard = live_audio_controller_slot()
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    *guard = None;
+}
+
 pub fn get_live_audio_status() -> LiveAudioStatusSnapshot {
     let guard = live_audio_controller_slot().lock();
     let Ok(guard) = guard else {
@@ -319,9 +581,41 @@ pub fn get_live_audio_status() -> LiveAudioStatusSnapshot {
             .unwrap_or(false),
         system_audio_device_name: controller.system_device_name.clone(),
         microphone_audio_device_name: controller.microphone_device_name.clone(),
+        system_audio_enabled_ms: activity_ms(
+            &controller.system_activity,
+            TrackActivity::enabled_ms,
+        ),
+        system_audio_muted_ms: activity_ms(&controller.system_activity, TrackActivity::muted_ms),
+        microphone_enabled_ms: activity_ms(
+            &controller.microphone_activity,
+            TrackActivity::enabled_ms,
+        ),
+        microphone_muted_ms: activity_ms(&controller.microphone_activity, TrackActivity::muted_ms),
+        microphone_monitoring: controller
+            .microphone_monitor
+            .as_ref()
+            .is_some_and(MonitorRoute::is_active),
+        microphone_gain_percent: controller
+            .microphone_gain_percent
+            .as_ref()
+            .map(|gain| gain.load(Ordering::SeqCst)),
+        system_audio_lost_at_ms: lost_at_ms(&controller.system_lost_at_ms),
+        microphone_lost_at_ms: lost_at_ms(&controller.microphone_lost_at_ms),
     }
 }
 
+fn lost_at_ms(lost_at: &Option<Arc<AtomicU64>>) -> Option<u64> {
+    lost_at
+        .as_ref()
+        .map(|value| value.load(Ordering::SeqCst))
+        .filter(|value| *value != DEVICE_LOST_UNSET)
+}
+
+fn activity_ms(activity: &Option<Arc<TrackActivity>>, read: impl Fn(&TrackActivity) -> u64) -> u64 {
+    activity.as_deref().map(read).unwrap_or(0)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn start_capture_track(
     kind: &'static str,
     dataflow: EDataFlow,
@@ -329,8 +623,9 @@ fn start_capture_track(
     loopback: bool,
     required: bool,
     initial_enabled: bool,
-    wav_path: PathBuf,
+    destination: TrackDestination,
     recording_started_at: Instant,
+    microphone: Option<MicrophoneProcessing>,
 ) -> Result<Option<ActiveCapture>, String> {
     let resolved = resolve_device(dataflow, preferred_device, kind);
     let device = match resolved {
@@ -357,11 +652,12 @@ fn start_capture_track(
 
     spawn_capture_worker(
         kind,
-        wav_path,
+        destination,
         device,
         loopback,
         initial_enabled,
         recording_started_at,
+        microphone,
     )
     .map(Some)
 }
@@ -0,0 +1,64 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+#[derive(Debug, Default)]
+pub struct TrackActivity {
+    sample_rate: AtomicU32,
+    enabled_frames: AtomicU64,
+    muted_frames: AtomicU64,
+}
+
+impl TrackActivity {
+    pub fn set_sample_rate(&self, sample_rate: u32) {
+        self.sample_rate.store(sample_rate, Ordering::Relaxed);
+    }
+
+    pub fn record_frames(&self, frames: u64, enabled: bool) {
+        let counter = if enabled {
+            &self.enabled_frames
+        } else {
+            &self.muted_frames
+        };
+        counter.fetch_add(frames, Ordering::Relaxed);
+    }
+
+    pub fn enabled_ms(&self) -> u64 {
+        self.frames_to_ms(self.enabled_frames.load(Ordering::Relaxed))
+    }
+
+    pub fn muted_ms(&self) -> u64 {
+        self.frames_to_ms(self.muted_frames.load(Ordering::Relaxed))
+    }
+
+    fn frames_to_ms(&self, frames: u64) -> u64 {
+        let sample_rate = u64::from(self.sample_rate.load(Ordering::Relaxed));
+        if sample_rate == 0 {
+            return 0;
+        }
+        frames.saturating_mul(1_000) / sample_rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TrackActivity;
+
+    #[test]
+    fn acumula_tiempo_habilitado_y_silenciado_por_separado() {
+        let activity = TrackActivity::default();
+        activity.set_sample_rate(48_000);
+
+        activity.record_frames(48_000 * 5, true);
+        activity.record_frames(24_000, false);
+        activity.record_frames(480, false);
+
+        assert_eq!(activity.enabled_ms(), 5_000);
+        assert_eq!(activity.muted_ms(), 510);
+    }
+
+    #[test]
+    fn sin_sample_rate_reporta_cero() {
+        let activity = TrackActivity::default();
+        activity.record_frames(1_000, true);
+        assert_eq!(activity.enabled_ms(), 0);
+    }
+}
@@ -0,0 +1,244 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    fs::File,
+    io::{self, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+// Los tamaños RIFF son u32: se deja margen para las cabeceras y el formato.
+const WAV_DATA_LIMIT_BYTES: u64 = u32::MAX as u64 - 1024 * 1024;
+
+struct WavFileWriter {
+    file: File,
+    data_size_offset: u64,
+    written_audio_bytes: u64,
+}
+
+impl WavFileWriter {
+    fn create(path: &Path, format_blob: &[u8]) -> io::Result<Self> {
+        let mut file = File::create(path)?;
+        let fmt_size = format_blob.len() as u32;
+
+        file.write_all(b"RIFF")?;
+        file.write_all(&0u32.to_le_bytes())?;
+        file.write_all(b"WAVE")?;
+
+        file.write_all(b"fmt ")?;
+        file.write_all(&fmt_size.to_le_bytes())?;
+        file.write_all(format_blob)?;
+
+        file.write_all(b"data")?;
+        let data_size_offset = file.stream_position()?;
+        file.write_all(&0u32.to_le_bytes())?;
+
+        Ok(Self {
+            file,
+            data_size_offset,
+            written_audio_bytes: 0,
+        })
+    }
+
+    fn write_samples(&mut self, data: &[u8]) -> io::Result<()> {
+        self.file.write_all(data)?;
+        self.written_audio_bytes = self.written_audio_bytes.saturating_add(data.len() as u64);
+        Ok(())
+    }
+
+    fn write_silence(&mut self, len: usize) -> io::Result<()> {
+        const CHUNK: usize = 4096;
+        let zeros = [0u8; CHUNK];
+        let mut remaining = len;
+        while remaining > 0 {
+            let write_now = remaining.min(CHUNK);
+            self.file.write_all(&zeros[..write_now])?;
+            self.written_audio_bytes = self.written_audio_bytes.saturating_add(write_now as u64);
+            remaining -= write_now;
+        }
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> io::Result<()> {
+        let file_size = self.file.seek(SeekFrom::End(0))?;
+        let riff_size = file_size.saturating_sub(8) as u32;
+        let data_size = self.written_audio_bytes.min(u32::MAX as u64) as u32;
+
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file.write_all(&riff_size.to_le_bytes())?;
+
+        self.file.seek(SeekFrom::Start(self.data_size_offset))?;
+        self.file.write_all(&data_size.to_le_bytes())?;
+
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+pub(crate) fn wav_part_path(base_path: &Path, index: usize) -> PathBuf {
+    if index <= 1 {
+        return base_path.to_path_buf();
+    }
+
+    let stem = base_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("audio");
+    let ext = base_path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("wav");
+    base_path.with_file_name(format!("{stem}_{index:03}.{ext}"))
+}
+
+pub(crate) fn wav_part_paths(base_path: &Path) -> Vec<PathBuf> {
+    (1..)
+        .map(|index| wav_part_path(base_path, index))
+        .take_while(|path| path.is_file())
+        .collect()
+}
+
+pub(crate) fn concat_list_contents(parts: &[PathBuf]) -> String {
+    parts
+        .iter()
+        .map(|part| {
+            let escaped = part.to_string_lossy().replace('\'', "'\\''");
+            format!("file '{escaped}'\n")
+        })
+        .collect()
+}
+
+/// Escritor WAV que pasa a un archivo nuevo antes de superar el límite RIFF de
+/// 4 GB; con un solo archivo la cabecera mentiría sobre la longitud y FFmpeg
+/// leería solo los primeros 4 GB.
+pub(crate) struct RollingWavWriter {
+    base_path: PathBuf,
+    format_blob: Vec<u8>,
+    max_data_bytes: u64,
+    part_index: usize,
+    current: WavFileWriter,
+}
+
+impl RollingWavWriter {
+    pub(crate) fn create(path: &Path, format_blob: &[u8], block_align: usize) -> io::Result<Self> {
+        Self::with_limit(path, format_blob, block_align, WAV_DATA_LIMIT_BYTES)
+    }
+
+    fn with_limit(
+        path: &Path,
+        format_blob: &[u8],
+        block_align: usize,
+        limit_bytes: u64,
+    ) -> io::Result<Self> {
+        // El corte cae siempre en un frame completo.
+        let block_align = block_align.max(1) as u64;
+        let max_data_bytes = (limit_bytes / block_align * block_align).max(block_align);
+
+        Ok(Self {
+            base_path: path.to_path_buf(),
+            format_blob: format_blob.to_vec(),
+            max_data_bytes,
+            part_index: 1,
+            current: WavFileWriter::create(path, format_blob)?,
+        })
+    }
+
+    pub(crate) fn part_count(&self) -> usize {
+        self.part_index
+    }
+
+    pub(crate) fn write_samples(&mut self, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            let write_now = self.writable_bytes(data.len())?;
+            self.current.write_samples(&data[..write_now])?;
+            data = &data[write_now..];
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write_silence(&mut self, mut len: usize) -> io::Result<()> {
+        while len > 0 {
+            let write_now = self.writable_bytes(len)?;
+            self.current.write_silence(write_now)?;
+            len -= write_now;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finalize(&mut self) -> io::Result<()> {
+        self.current.finalize()
+    }
+
+    fn writable_bytes(&mut self, wanted: usize) -> io::Result<usize> {
+        let mut room = self
+            .max_data_bytes
+            .saturating_sub(self.current.written_audio_bytes);
+        if room == 0 {
+            self.current.finalize()?;
+            self.part_index += 1;
+            let next_path = wav_part_path(&self.base_path, self.part_index);
+            self.current = WavFileWriter::create(&next_path, &self.format_blob)?;
+            room = self.max_data_bytes;
+        }
+        Ok(wanted.min(room.min(usize::MAX as u64) as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    const FORMAT_BLOB: [u8; 16] = [0; 16];
+    const HEADER_BYTES: u64 = 44;
+
+    fn data_size(path: &Path) -> u32 {
+        let bytes = fs::read(path).unwrap();
+        u32::from_le_bytes([bytes[40], bytes[41], bytes[42], bytes[43]])
+    }
+
+    #[test]
+    fn nombres_de_partes_agregan_sufijo() {
+        let base = Path::new("tmp").join("system_audio.wav");
+        assert_eq!(wav_part_path(&base, 1), base);
+        assert_eq!(
+            wav_part_path(&base, 2),
+            Path::new("tmp").join("system_audio_002.wav")
+        );
+    }
+
+    #[test]
+    fn rota_de_archivo_al_llegar_al_limite() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("system_audio.wav");
+        let mut writer = RollingWavWriter::with_limit(&base, &FORMAT_BLOB, 4, 10).unwrap();
+
+        writer.write_samples(&[1; 12]).unwrap();
+        writer.write_silence(12).unwrap();
+        writer.finalize().unwrap();
+
+        assert_eq!(writer.part_count(), 3);
+        let parts = wav_part_paths(&base);
+        assert_eq!(
+            parts,
+            vec![
+                base.clone(),
+                dir.path().join("system_audio_002.wav"),
+                dir.path().join("system_audio_003.wav"),
+            ]
+        );
+        for part in &parts {
+            assert_eq!(data_size(part), 8);
+            assert_eq!(fs::metadata(part).unwrap().len(), HEADER_BYTES + 8);
+        }
+    }
+
+    #[test]
+    fn lista_concat_escapa_comillas() {
+        let parts = vec![PathBuf::from("a.wav"), PathBuf::from("it's.wav")];
+        assert_eq!(
+            concat_list_contents(&parts),
+            "file 'a.wav'\nfile 'it'\\''s.wav'\n"
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use crate::encoder::config::{QualityMode, VideoCodec};
+
+pub fn estimate_target_bitrate_kbps(
+    width: u32,
+    height: u32,
+    fps: u32,
+    codec: &VideoCodec,
+    quality_mode: &QualityMode,
+) -> u32 {
+    let bpp = match quality_mode {
+        QualityMode::Performance => 0.055_f64,
+        QualityMode::Balanced => 0.075_f64,
+        QualityMode::Quality => 0.1_f64,
+    };
+    let codec_factor = match codec {
+        VideoCodec::H264 => 1.0_f64,
+        VideoCodec::H265 => 0.72_f64,
+        VideoCodec::Vp9 => 0.68_f64,
+    };
+
+    let pixels_per_sec = f64::from(width) * f64::from(height) * f64::from(fps.clamp(1, 240));
+    let estimated_kbps = (pixels_per_sec * bpp * codec_factor / 1_000.0).round();
+    let clamped = estimated_kbps.clamp(2_500.0, 80_000.0);
+    clamped as u32
+}
@@ -0,0 +1,109 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::ops::Range;
+
+/// Lleva los frames de la captura (timestamps variables) a una grilla fija de
+/// `fps`. Cada frame ocupa los slots que faltan hasta el más cercano a su
+/// timestamp: si llegó tarde se repite y si su slot ya se emitió se descarta.
+#[derive(Debug, Clone)]
+pub struct CfrPacer {
+    fps: u64,
+    next_slot: u64,
+    filling: bool,
+}
+
+impl CfrPacer {
+    pub fn new(fps: u32) -> Self {
+        Self {
+            fps: u64::from(fps.max(1)),
+            next_slot: 0,
+            filling: false,
+        }
+    }
+
+    pub fn slots_for(&mut self, rel_ts_ms: u64) -> Range<u64> {
+        let slot = (rel_ts_ms.saturating_mul(self.fps) + 500) / 1_000;
+        let start = self.next_slot;
+        // Un frame nuevo cuyo slot ya se rellenó con el anterior se emite en el
+        // siguiente: descartarlo dejaría el contenido viejo hasta el próximo cambio.
+        let slot = if slot < start && self.filling {
+            start
+        } else {
+            slot
+        };
+        self.filling = false;
+        if slot < start {
+            return start..start;
+        }
+        self.next_slot = slot + 1;
+        start..self.next_slot
+    }
+
+    pub fn fill_until(&mut self, rel_ts_ms: u64) -> Range<u64> {
+        let start = self.next_slot;
+        let due = rel_ts_ms.saturating_mul(self.fps) / 1_000;
+        if start == 0 || due < start {
+            return start..start;
+        }
+        self.next_slot = due + 1;
+        self.filling = true;
+        start..self.next_slot
+    }
+
+    pub fn slot_to_ms(&self, slot: u64) -> u64 {
+        slot.saturating_mul(1_000) / self.fps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CfrPacer;
+
+    #[test]
+    fn frames_puntuales_se_emiten_una_vez() {
+        let mut pacer = CfrPacer::new(30);
+        assert_eq!(pacer.slots_for(0), 0..1);
+        assert_eq!(pacer.slots_for(33), 1..2);
+        assert_eq!(pacer.slots_for(67), 2..3);
+        assert_eq!(pacer.slot_to_ms(3), 100);
+    }
+
+    #[test]
+    fn un_hueco_repite_el_frame_hasta_su_slot() {
+        let mut pacer = CfrPacer::new(60);
+        assert_eq!(pacer.slots_for(0).count(), 1);
+        assert_eq!(pacer.slots_for(100), 1..7);
+    }
+
+    #[test]
+    fn frames_mas_rapidos_que_el_objetivo_se_descartan() {
+        let mut pacer = CfrPacer::new(30);
+        assert_eq!(pacer.slots_for(0), 0..1);
+        assert!(pacer.slots_for(8).is_empty());
+        assert!(pacer.slots_for(16).is_empty());
+        assert_eq!(pacer.slots_for(24), 1..2);
+        assert!(pacer.slots_for(40).is_empty());
+        assert_eq!(pacer.slots_for(70), 2..3);
+    }
+
+    #[test]
+    fn pantalla_quieta_se_rellena_hasta_el_tiempo_transcurrido() {
+        let mut pacer = CfrPacer::new(30);
+        assert!(pacer.fill_until(500).is_empty());
+
+        assert_eq!(pacer.slots_for(0), 0..1);
+        assert!(pacer.fill_until(20).is_empty());
+        assert_eq!(pacer.fill_until(100), 1..4);
+        assert_eq!(pacer.fill_until(1_000), 4..31);
+        assert_eq!(pacer.slot_to_ms(31), 1_033);
+    }
+
+    #[test]
+    fn un_frame_nuevo_no_se_pierde_tras_el_relleno() {
+        let mut pacer = CfrPacer::new(30);
+        assert_eq!(pacer.slots_for(0), 0..1);
+        assert_eq!(pacer.fill_until(100), 1..4);
+        assert_eq!(pacer.slots_for(90), 4..5);
+        assert!(pacer.slots_for(100).is_empty());
+    }
+}
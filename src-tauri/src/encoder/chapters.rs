@@ -0,0 +1,165 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterMarker {
+    pub elapsed_ms: u64,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChapterMarkerLog {
+    markers: Arc<Mutex<Vec<ChapterMarker>>>,
+}
+
+impl ChapterMarkerLog {
+    pub fn push(&self, elapsed_ms: u64, label: Option<String>) -> Result<ChapterMarker, String> {
+        let mut markers = self
+            .markers
+            .lock()
+            .map_err(|_| "No se pudo acceder a los capítulos de la grabación".to_string())?;
+
+        let label = label
+            .map(|label| label.trim().to_string())
+            .filter(|label| !label.is_empty())
+            .unwrap_or_else(|| format!("Capítulo {}", markers.len() + 1));
+        let marker = ChapterMarker { elapsed_ms, label };
+        markers.push(marker.clone());
+        Ok(marker)
+    }
+
+    pub fn snapshot(&self) -> Vec<ChapterMarker> {
+        self.markers
+            .lock()
+            .map(|markers| markers.clone())
+            .unwrap_or_default()
+    }
+}
+
+fn escape_ffmetadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if matches!(ch, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+pub fn chapter_ranges(markers: &[ChapterMarker], duration_ms: u64) -> Vec<(ChapterMarker, u64)> {
+    let mut sorted = markers.to_vec();
+    sorted.sort_by_key(|marker| marker.elapsed_ms);
+
+    let ends: Vec<u64> = sorted
+        .iter()
+        .enumerate()
+        .map(|(index, marker)| {
+            sorted
+                .get(index + 1)
+                .map(|next| next.elapsed_ms)
+                .unwrap_or(duration_ms)
+                .max(marker.elapsed_ms)
+        })
+        .collect();
+    sorted.into_iter().zip(ends).collect()
+}
+
+pub fn markers_for_part(
+    markers: &[ChapterMarker],
+    start_ms: u64,
+    end_ms: Option<u64>,
+) -> Vec<ChapterMarker> {
+    markers
+        .iter()
+        .filter(|marker| {
+            marker.elapsed_ms >= start_ms && end_ms.is_none_or(|end_ms| marker.elapsed_ms < end_ms)
+        })
+        .map(|marker| ChapterMarker {
+            elapsed_ms: marker.elapsed_ms - start_ms,
+            label: marker.label.clone(),
+        })
+        .collect()
+}
+
+pub fn chapters_ffmetadata(markers: &[ChapterMarker], duration_ms: u64) -> String {
+    let mut contents = String::from(";FFMETADATA1\n");
+    for (marker, end_ms) in chapter_ranges(markers, duration_ms) {
+        contents.push_str(&format!(
+            "[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={end_ms}\ntitle={}\n",
+            marker.elapsed_ms,
+            escape_ffmetadata(&marker.label)
+        ));
+    }
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marcadores_sin_etiqueta_se_numeran() {
+        let log = ChapterMarkerLog::default();
+        let shared = log.clone();
+
+        log.push(1_000, None).unwrap();
+        log.push(5_000, Some("  Demo ".to_string())).unwrap();
+        shared.push(9_000, Some(" ".to_string())).unwrap();
+
+        let labels: Vec<String> = log.snapshot().into_iter().map(|m| m.label).collect();
+        assert_eq!(labels, vec!["Capítulo 1", "Demo", "Capítulo 3"]);
+    }
+
+    #[test]
+    fn cada_parte_se_queda_con_sus_marcadores() {
+        let log = ChapterMarkerLog::default();
+        for elapsed_ms in [0, 59_000, 60_000, 90_000] {
+            log.push(elapsed_ms, None).unwrap();
+        }
+        let markers = log.snapshot();
+
+        let first: Vec<u64> = markers_for_part(&markers, 0, Some(60_000))
+            .iter()
+            .map(|marker| marker.elapsed_ms)
+            .collect();
+        assert_eq!(first, vec![0, 59_000]);
+        let second = markers_for_part(&markers, 60_000, None);
+        assert_eq!(second[0].elapsed_ms, 0);
+        assert_eq!(second[1].elapsed_ms, 30_000);
+        assert_eq!(second[1].label, "Capítulo 4");
+    }
+
+    #[test]
+    fn capitulos_terminan_en_el_siguiente_y_escapan_texto() {
+        let markers = vec![
+            ChapterMarker {
+                elapsed_ms: 4_000,
+                label: "Paso 2; a=b".to_string(),
+            },
+            ChapterMarker {
+                elapsed_ms: 0,
+                label: "Intro".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            chapters_ffmetadata(&markers, 10_000),
+            ";FFMETADATA1\n\
+             [CHAPTER]\nTIMEBASE=1/1000\nSTART=0\nEND=4000\ntitle=Intro\n\
+             [CHAPTER]\nTIMEBASE=1/1000\nSTART=4000\nEND=10000\ntitle=Paso 2\\; a\\=b\n"
+        );
+    }
+
+    #[test]
+    fn ultimo_capitulo_no_termina_antes_de_empezar() {
+        let markers = vec![ChapterMarker {
+            elapsed_ms: 3_000,
+            label: "Final".to_string(),
+        }];
+
+        assert!(chapters_ffmetadata(&markers, 0).contains("START=3000\nEND=3000\n"));
+    }
+}
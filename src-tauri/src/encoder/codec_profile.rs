@@ -0,0 +1,241 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::encoder::config::VideoCodec;
+
+const H264_PROFILES: [&str; 3] = ["baseline", "main", "high"];
+const HEVC_PROFILES: [&str; 2] = ["main", "main10"];
+
+const H264_LEVELS: [(u8, u8); 19] = [
+    (1, 0),
+    (1, 1),
+    (1, 2),
+    (1, 3),
+    (2, 0),
+    (2, 1),
+    (2, 2),
+    (3, 0),
+    (3, 1),
+    (3, 2),
+    (4, 0),
+    (4, 1),
+    (4, 2),
+    (5, 0),
+    (5, 1),
+    (5, 2),
+    (6, 0),
+    (6, 1),
+    (6, 2),
+];
+const HEVC_LEVELS: [(u8, u8); 13] = [
+    (1, 0),
+    (2, 0),
+    (2, 1),
+    (3, 0),
+    (3, 1),
+    (4, 0),
+    (4, 1),
+    (5, 0),
+    (5, 1),
+    (5, 2),
+    (6, 0),
+    (6, 1),
+    (6, 2),
+];
+
+fn normalize_profile(profile: &str) -> String {
+    profile.trim().to_ascii_lowercase()
+}
+
+fn parse_level(level: &str) -> Option<(u8, u8)> {
+    let level = level.trim();
+    let (major, minor) = match level.split_once('.') {
+        Some((major, minor)) => (major.parse().ok()?, minor.parse().ok()?),
+        None => match level.len() {
+            1 => (level.parse().ok()?, 0),
+            2 => (level[..1].parse().ok()?, level[1..].parse().ok()?),
+            _ => return None,
+        },
+    };
+    Some((major, minor))
+}
+
+pub fn known_level(codec: &VideoCodec, level: &str) -> Option<(u8, u8)> {
+    let known: &[(u8, u8)] = match codec {
+        VideoCodec::H264 => &H264_LEVELS,
+        VideoCodec::H265 => &HEVC_LEVELS,
+        VideoCodec::Vp9 => return None,
+    };
+    parse_level(level).filter(|level| known.contains(level))
+}
+
+pub fn validate_profile_level(
+    codec: &VideoCodec,
+    ten_bit: bool,
+    profile: Option<&str>,
+    level: Option<&str>,
+) -> Result<(), String> {
+    if *codec == VideoCodec::Vp9 && (profile.is_some() || level.is_some()) {
+        return Err("VP9 no admite elegir perfil ni nivel".to_string());
+    }
+
+    if let Some(profile) = profile {
+        let profile = normalize_profile(profile);
+        let known: &[&str] = match codec {
+            VideoCodec::H264 => &H264_PROFILES,
+            _ => &HEVC_PROFILES,
+        };
+        if !known.contains(&profile.as_str()) {
+            return Err(format!(
+                "Perfil '{profile}' inválido para {}. Usa {}",
+                codec_label(codec),
+                known.join(", ")
+            ));
+        }
+        if ten_bit != (profile == "main10") {
+            return Err(if ten_bit {
+                "La grabación de 10 bits solo admite el perfil main10".to_string()
+            } else {
+                "El perfil main10 solo está disponible con 10 bits".to_string()
+            });
+        }
+    }
+
+    if let Some(level) = level {
+        if known_level(codec, level).is_none() {
+            return Err(format!(
+                "Nivel '{}' inválido para {}. Usa la forma 3.1 o 4.0",
+                level.trim(),
+                codec_label(codec)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn codec_label(codec: &VideoCodec) -> &'static str {
+    match codec {
+        VideoCodec::H264 => "H.264",
+        VideoCodec::H265 => "H.265",
+        VideoCodec::Vp9 => "VP9",
+    }
+}
+
+/// Opciones de perfil y nivel para `encoder_name`. Los valores ya validados
+/// se traducen a lo que espera cada encoder:
+/// - AMF no tiene `baseline`: se pide `constrained_baseline`, que es lo que
+///   decodifican los equipos que piden Baseline.
+/// - libx264, NVENC y AMF reciben el nivel como `3.1`; QSV lo toma numérico
+///   (`31` en H.264 y 30 veces el nivel en HEVC, `93`).
+/// - libx265 no tiene opción `level` y lo recibe por `x265-params`.
+///
+/// Los encoders de respaldo (`h264`, `hevc`, `mpeg4`) no admiten estas
+/// opciones y se abren sin ellas.
+pub fn profile_level_options(
+    encoder_name: &str,
+    codec: &VideoCodec,
+    profile: Option<&str>,
+    level: Option<&str>,
+) -> Vec<(&'static str, String)> {
+    let is_hardware = ["nvenc", "_amf", "_qsv"]
+        .iter()
+        .any(|family| encoder_name.contains(family));
+    if !is_hardware && !encoder_name.starts_with("libx26") {
+        return Vec::new();
+    }
+
+    let mut options = Vec::new();
+    if let Some(profile) = profile.map(normalize_profile) {
+        let profile = if encoder_name.contains("_amf") && profile == "baseline" {
+            "constrained_baseline".to_string()
+        } else {
+            profile
+        };
+        options.push(("profile", profile));
+    }
+
+    if let Some((major, minor)) = level.and_then(|level| known_level(codec, level)) {
+        let dotted = format!("{major}.{minor}");
+        let numeric = u32::from(major) * 10 + u32::from(minor);
+        if encoder_name.contains("_qsv") {
+            let qsv_level = match codec {
+                VideoCodec::H265 => numeric * 3,
+                _ => numeric,
+            };
+            options.push(("level", qsv_level.to_string()));
+        } else if encoder_name == "libx265" {
+            options.push(("x265-params", format!("level-idc={dotted}")));
+        } else {
+            options.push(("level", dotted));
+        }
+    }
+
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valida_perfiles_y_niveles_por_codec() {
+        let h264 = VideoCodec::H264;
+        assert!(validate_profile_level(&h264, false, Some("Baseline"), Some("3.1")).is_ok());
+        assert!(validate_profile_level(&h264, false, Some("main"), Some("31")).is_ok());
+
+        let err = validate_profile_level(&h264, false, Some("high444"), None).unwrap_err();
+        assert!(err.contains("baseline, main, high"), "{err}");
+        assert!(validate_profile_level(&h264, false, None, Some("7.0")).is_err());
+        assert!(validate_profile_level(&VideoCodec::H265, false, None, Some("2.2")).is_err());
+
+        assert!(validate_profile_level(&VideoCodec::H265, true, Some("main10"), None).is_ok());
+        assert!(validate_profile_level(&VideoCodec::H265, true, Some("main"), None).is_err());
+        assert!(validate_profile_level(&VideoCodec::H265, false, Some("main10"), None).is_err());
+        assert!(validate_profile_level(&VideoCodec::Vp9, false, None, Some("3.1")).is_err());
+        assert!(validate_profile_level(&VideoCodec::Vp9, false, None, None).is_ok());
+    }
+
+    #[test]
+    fn traduce_nombres_de_cada_encoder() {
+        let h264 = VideoCodec::H264;
+        let options =
+            |encoder: &str| profile_level_options(encoder, &h264, Some("baseline"), Some("3"));
+
+        assert_eq!(
+            options("libx264"),
+            vec![
+                ("profile", "baseline".to_string()),
+                ("level", "3.0".to_string())
+            ]
+        );
+        assert_eq!(options("h264_nvenc"), options("libx264"));
+        assert_eq!(
+            options("h264_amf"),
+            vec![
+                ("profile", "constrained_baseline".to_string()),
+                ("level", "3.0".to_string())
+            ]
+        );
+        assert_eq!(
+            options("h264_qsv"),
+            vec![
+                ("profile", "baseline".to_string()),
+                ("level", "30".to_string())
+            ]
+        );
+        assert!(options("mpeg4").is_empty());
+
+        let hevc = VideoCodec::H265;
+        assert_eq!(
+            profile_level_options("hevc_qsv", &hevc, None, Some("3.1")),
+            vec![("level", "93".to_string())]
+        );
+        assert_eq!(
+            profile_level_options("libx265", &hevc, Some("main"), Some("4.1")),
+            vec![
+                ("profile", "main".to_string()),
+                ("x265-params", "level-idc=4.1".to_string())
+            ]
+        );
+    }
+}
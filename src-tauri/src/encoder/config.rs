@@ -1,12 +1,46 @@
+use chrono::{DateTime, Local, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::capture::{
+    cursor_overlay::CursorOverlayConfig, frame_snapshot::FrameSnapshotRequest, models::Region,
+};
+use crate::encoder::{
+    chapters::ChapterMarkerLog,
+    codec_profile::validate_profile_level,
+    disk_space::DEFAULT_DISK_SPACE_THRESHOLD_MB,
+    filesystem_limits::SessionParts,
+    gpu_scaler::GpuFrameRoute,
+    hdr_metadata::HdrMasteringMetadata,
+    hls::{DEFAULT_HLS_SEGMENT_SECONDS, MAX_HLS_SEGMENT_SECONDS},
+    output_sink::{
+        is_rtmp_url, redact_stream_url, stream_output_url, OutputSink, MAX_OUTPUT_SINKS,
+    },
+    region_blur::validate_blur_regions,
+    trim::TrimRange,
+};
+
+pub const MAX_AUDIO_SYNC_OFFSET_MS: i64 = 1_000;
+pub const SUPPORTED_AUDIO_SAMPLE_RATES: [u32; 7] =
+    [16_000, 22_050, 24_000, 32_000, 44_100, 48_000, 96_000];
+// libopus solo codifica a estas frecuencias.
+const OPUS_SAMPLE_RATES: [u32; 5] = [8_000, 12_000, 16_000, 24_000, 48_000];
+const MAX_AUDIO_CHANNELS: u16 = 2;
+pub const MAX_INTERPOLATED_FPS: u32 = 120;
+pub const MAX_MICROPHONE_GAIN_PERCENT: u16 = 400;
+pub const MAX_KEYFRAME_INTERVAL_SECONDS: u32 = 60;
+pub const DEFAULT_LOUDNESS_TARGET_LUFS: i32 = -16;
+pub const LOUDNESS_TARGET_LUFS_RANGE: std::ops::RangeInclusive<i32> = -30..=-8;
+pub const AUDIO_BITRATE_KBPS_RANGE: std::ops::RangeInclusive<u32> = 32..=512;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum OutputFormat {
     Mp4,
     Mkv,
     WebM,
+    Gif,
+    Hls,
 }
 
 impl OutputFormat {
@@ -15,15 +49,43 @@ impl OutputFormat {
             OutputFormat::Mp4 => "mp4",
             OutputFormat::Mkv => "matroska",
             OutputFormat::WebM => "webm",
+            OutputFormat::Gif => "gif",
+            OutputFormat::Hls => "hls",
+        }
+    }
+
+    pub fn capture_format_name(&self) -> &str {
+        match self {
+            OutputFormat::Gif => "matroska",
+            _ => self.ffmpeg_format_name(),
+        }
+    }
+
+    pub fn capture_file_extension(&self) -> &str {
+        match self {
+            OutputFormat::Gif => "mkv",
+            _ => self.file_extension(),
         }
     }
 
     pub fn default_codec(&self) -> VideoCodec {
         match self {
-            OutputFormat::Mp4 | OutputFormat::Mkv => VideoCodec::H264,
+            OutputFormat::Mp4 | OutputFormat::Mkv | OutputFormat::Gif | OutputFormat::Hls => {
+                VideoCodec::H264
+            }
             OutputFormat::WebM => VideoCodec::Vp9,
         }
     }
+
+    pub fn file_extension(&self) -> &str {
+        match self {
+            OutputFormat::Mp4 => "mp4",
+            OutputFormat::Mkv => "mkv",
+            OutputFormat::WebM => "webm",
+            OutputFormat::Gif => "gif",
+            OutputFormat::Hls => "m3u8",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -64,6 +126,132 @@ pub enum QualityMode {
     Quality,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MicNoiseSuppression {
+    Off,
+    Light,
+    Strong,
+}
+
+impl MicNoiseSuppression {
+    pub fn resolve(configured: Option<Self>, quality_mode: &QualityMode) -> Self {
+        configured.unwrap_or(match quality_mode {
+            QualityMode::Quality => Self::Strong,
+            QualityMode::Performance | QualityMode::Balanced => Self::Off,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AudioCodec {
+    Aac,
+    Opus,
+    Flac,
+    Pcm,
+}
+
+impl AudioCodec {
+    pub fn default_for(format: &OutputFormat) -> Self {
+        match format {
+            OutputFormat::WebM => Self::Opus,
+            _ => Self::Aac,
+        }
+    }
+
+    pub fn ffmpeg_encoder_name(self) -> &'static str {
+        match self {
+            Self::Aac => "aac",
+            Self::Opus => "libopus",
+            Self::Flac => "flac",
+            Self::Pcm => "pcm_s16le",
+        }
+    }
+
+    pub fn default_bitrate_kbps(self) -> Option<u32> {
+        match self {
+            Self::Aac => Some(160),
+            Self::Opus => Some(128),
+            Self::Flac | Self::Pcm => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Aac => "AAC",
+            Self::Opus => "Opus",
+            Self::Flac => "FLAC",
+            Self::Pcm => "PCM",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ColorRange {
+    #[default]
+    Limited,
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorTags {
+    pub range: &'static str,
+    pub space: &'static str,
+    pub primaries: &'static str,
+    pub transfer: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScalerAlgorithm {
+    FastBilinear,
+    Bilinear,
+    Bicubic,
+    Lanczos,
+}
+
+impl ScalerAlgorithm {
+    pub fn resolve(configured: Option<Self>, quality_mode: &QualityMode) -> Self {
+        configured.unwrap_or(match quality_mode {
+            QualityMode::Performance => Self::FastBilinear,
+            QualityMode::Balanced => Self::Bilinear,
+            QualityMode::Quality => Self::Bicubic,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChromaSubsampling {
+    Yuv420,
+    Yuv444,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BitDepth {
+    Eight,
+    Ten,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum FrameRateMode {
+    #[default]
+    Vfr,
+    Cfr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum Fat32Policy {
+    #[default]
+    Reject,
+    Segment,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum OutputResolution {
@@ -93,6 +281,19 @@ impl OutputResolution {
             OutputResolution::Custom { width, height } => (*width, *height),
         }
     }
+
+    pub fn output_dimensions(
+        &self,
+        source_width: u32,
+        source_height: u32,
+        never_upscale: bool,
+    ) -> (u32, u32) {
+        let (width, height) = self.dimensions(source_width, source_height);
+        if never_upscale && (width > source_width || height > source_height) {
+            return (source_width, source_height);
+        }
+        (width, height)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -117,6 +318,26 @@ fn default_microphone_gain_percent() -> u16 {
     100
 }
 
+fn default_disk_space_threshold_mb() -> u64 {
+    DEFAULT_DISK_SPACE_THRESHOLD_MB
+}
+
+pub fn default_hls_segment_seconds() -> u32 {
+    DEFAULT_HLS_SEGMENT_SECONDS
+}
+
+pub fn default_never_upscale() -> bool {
+    true
+}
+
+pub fn default_gif_fps() -> u32 {
+    15
+}
+
+pub fn default_gif_max_width() -> u32 {
+    800
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AudioCaptureConfig {
@@ -130,6 +351,27 @@ pub struct AudioCaptureConfig {
     pub microphone_device: Option<String>,
     #[serde(default = "default_microphone_gain_percent")]
     pub microphone_gain_percent: u16,
+    #[serde(default)]
+    pub audio_sync_offset_ms: i64,
+    #[serde(default)]
+    pub audio_sample_rate: Option<u32>,
+    #[serde(default)]
+    pub audio_channels: Option<u16>,
+    #[serde(default)]
+    pub mic_noise_suppression: Option<MicNoiseSuppression>,
+    #[serde(default)]
+    pub normalize_loudness: bool,
+    #[serde(default)]
+    pub loudness_target_lufs: Option<i32>,
+    #[serde(default)]
+    pub audio_codec: Option<AudioCodec>,
+    #[serde(default)]
+    pub audio_bitrate_kbps: Option<u32>,
+    /// Opus en MP4 es válido pero varios reproductores no lo abren; hay que pedirlo.
+    #[serde(default)]
+    pub allow_opus_in_mp4: bool,
+    #[serde(default)]
+    pub monitor_microphone: bool,
 }
 
 impl Default for AudioCaptureConfig {
@@ -140,41 +382,296 @@ impl Default for AudioCaptureConfig {
             system_audio_device: None,
             microphone_device: None,
             microphone_gain_percent: default_microphone_gain_percent(),
+            audio_sync_offset_ms: 0,
+            audio_sample_rate: None,
+            audio_channels: None,
+            mic_noise_suppression: None,
+            normalize_loudness: false,
+            loudness_target_lufs: None,
+            audio_codec: None,
+            audio_bitrate_kbps: None,
+            allow_opus_in_mp4: false,
+            monitor_microphone: false,
         }
     }
 }
 
 impl AudioCaptureConfig {
+    pub fn loudness_target(&self) -> Option<i32> {
+        self.normalize_loudness.then(|| {
+            self.loudness_target_lufs
+                .unwrap_or(DEFAULT_LOUDNESS_TARGET_LUFS)
+        })
+    }
+
     pub fn is_enabled(&self) -> bool {
         self.capture_system_audio || self.capture_microphone_audio
     }
+
+    pub fn effective_codec(&self, format: &OutputFormat) -> AudioCodec {
+        self.audio_codec
+            .unwrap_or_else(|| AudioCodec::default_for(format))
+    }
+
+    pub fn effective_bitrate_kbps(&self, format: &OutputFormat) -> Option<u32> {
+        self.effective_codec(format)
+            .default_bitrate_kbps()
+            .map(|default_kbps| self.audio_bitrate_kbps.unwrap_or(default_kbps))
+    }
+
+    pub fn encoder_args(&self, format: &OutputFormat) -> Vec<String> {
+        let codec = self.effective_codec(format);
+        let mut args = vec!["-c:a".to_string(), codec.ffmpeg_encoder_name().to_string()];
+        if let Some(bitrate_kbps) = self.effective_bitrate_kbps(format) {
+            args.extend(["-b:a".to_string(), format!("{bitrate_kbps}k")]);
+        }
+        args
+    }
+
+    fn validate_codec_for(&self, format: &OutputFormat) -> Result<(), String> {
+        let codec = self.effective_codec(format);
+        match (format, codec) {
+            (OutputFormat::WebM, AudioCodec::Opus) => {}
+            (OutputFormat::WebM, _) => {
+                return Err(format!("WebM solo admite audio Opus, no {}", codec.label()));
+            }
+            (OutputFormat::Mkv, _) => {}
+            (_, AudioCodec::Flac | AudioCodec::Pcm) => {
+                return Err(format!(
+                    "El audio {} solo está disponible en MKV",
+                    codec.label()
+                ));
+            }
+            (OutputFormat::Mp4, AudioCodec::Opus) if !self.allow_opus_in_mp4 => {
+                return Err(
+                    "Opus en MP4 no es compatible con todos los reproductores: usa MKV o activa allowOpusInMp4"
+                        .to_string(),
+                );
+            }
+            _ => {}
+        }
+
+        if let Some(bitrate_kbps) = self.audio_bitrate_kbps {
+            if codec.default_bitrate_kbps().is_none() {
+                return Err(format!(
+                    "El audio {} es sin pérdida y no admite bitrate",
+                    codec.label()
+                ));
+            }
+            if !AUDIO_BITRATE_KBPS_RANGE.contains(&bitrate_kbps) {
+                return Err(format!(
+                    "Bitrate de audio inválido: {bitrate_kbps} kbps. Debe estar entre {} y {} kbps",
+                    AUDIO_BITRATE_KBPS_RANGE.start(),
+                    AUDIO_BITRATE_KBPS_RANGE.end()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingMetadata {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
+impl RecordingMetadata {
+    /// Pares clave/valor para FFmpeg. Solo se usan claves que MP4 y MKV
+    /// conservan: el autor va como `artist`, que es lo que muestran los
+    /// reproductores. `creation_time` se escribe siempre con el inicio real.
+    pub fn container_tags(&self, started_at: DateTime<Local>) -> Vec<(&'static str, String)> {
+        let mut tags: Vec<(&'static str, String)> = [
+            ("title", &self.title),
+            ("comment", &self.comment),
+            ("artist", &self.author),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let value = value.as_deref()?.trim();
+            (!value.is_empty()).then(|| (key, value.to_string()))
+        })
+        .collect();
+
+        tags.push((
+            "creation_time",
+            started_at
+                .with_timezone(&Utc)
+                .format("%Y-%m-%dT%H:%M:%S%.6fZ")
+                .to_string(),
+        ));
+        tags
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GifExportConfig {
+    #[serde(default = "default_gif_fps")]
+    pub fps: u32,
+    #[serde(default = "default_gif_max_width")]
+    pub max_width: u32,
+}
+
+impl Default for GifExportConfig {
+    fn default() -> Self {
+        Self {
+            fps: default_gif_fps(),
+            max_width: default_gif_max_width(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EncoderConfig {
     pub output_path: PathBuf,
+    #[serde(default)]
+    pub output_template: Option<String>,
     pub format: OutputFormat,
     pub codec: Option<VideoCodec>,
     #[serde(default)]
     pub video_encoder_preference: VideoEncoderPreference,
     pub resolution: OutputResolution,
+    #[serde(default = "default_never_upscale")]
+    pub never_upscale: bool,
     pub crf: u32,
     pub preset: EncoderPreset,
     #[serde(default)]
     pub quality_mode: QualityMode,
+    #[serde(default)]
+    pub color_range: ColorRange,
+    #[serde(default)]
+    pub hdr: bool,
+    #[serde(default)]
+    pub scaler: Option<ScalerAlgorithm>,
+    #[serde(default)]
+    pub chroma_subsampling: Option<ChromaSubsampling>,
+    #[serde(default)]
+    pub bit_depth: Option<BitDepth>,
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub level: Option<String>,
+    #[serde(default)]
+    pub blur_regions: Vec<Region>,
     pub fps: u32,
     #[serde(default)]
     pub audio: AudioCaptureConfig,
+    #[serde(default = "default_disk_space_threshold_mb")]
+    pub disk_space_threshold_mb: u64,
+    #[serde(default)]
+    pub fat32_policy: Fat32Policy,
+    #[serde(default)]
+    pub gif: GifExportConfig,
+    #[serde(default)]
+    pub fit_to_size_mb: Option<u64>,
+    #[serde(default)]
+    pub interpolate_to_fps: Option<u32>,
+    #[serde(default)]
+    pub mp4_faststart: bool,
+    #[serde(default)]
+    pub keyframe_interval_frames: Option<u32>,
+    #[serde(default)]
+    pub frame_rate_mode: FrameRateMode,
+    #[serde(default)]
+    pub outputs: Vec<OutputSink>,
+    #[serde(default)]
+    pub trim_start_ms: Option<u64>,
+    #[serde(default)]
+    pub trim_end_ms: Option<u64>,
+    #[serde(default = "default_hls_segment_seconds")]
+    pub hls_segment_seconds: u32,
+    #[serde(default)]
+    pub target_name: Option<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub metadata: RecordingMetadata,
+    #[serde(skip)]
+    pub chapter_markers: ChapterMarkerLog,
+    #[serde(skip)]
+    pub frame_snapshot: FrameSnapshotRequest,
+    #[serde(skip)]
+    pub cursor_overlay: Option<CursorOverlayConfig>,
+    #[serde(skip)]
+    pub hdr_metadata: Option<HdrMasteringMetadata>,
+    #[serde(skip)]
+    pub gpu_frame_route: GpuFrameRoute,
+    #[serde(skip)]
+    pub session_parts: SessionParts,
 }
 
 impl EncoderConfig {
+    /// SDR va siempre en BT.709, que es lo que asumen los reproductores con
+    /// video HD aunque falten las etiquetas.
+    pub fn color_tags(&self) -> ColorTags {
+        let range = match self.color_range {
+            ColorRange::Limited => "tv",
+            ColorRange::Full => "pc",
+        };
+        if self.hdr {
+            return ColorTags {
+                range,
+                space: "bt2020nc",
+                primaries: "bt2020",
+                transfer: "smpte2084",
+            };
+        }
+        ColorTags {
+            range,
+            space: "bt709",
+            primaries: "bt709",
+            transfer: "bt709",
+        }
+    }
+
+    pub fn bit_depth(&self) -> BitDepth {
+        if self.hdr {
+            return BitDepth::Ten;
+        }
+        self.bit_depth.unwrap_or(BitDepth::Eight)
+    }
+
+    /// 10 bits va en P010 (4:2:0). HLS, GIF y los streams se quedan en 4:2:0
+    /// porque sus reproductores no decodifican 4:4:4, y un perfil fijo
+    /// tampoco lo admite.
+    pub fn chroma_subsampling(&self) -> ChromaSubsampling {
+        if self.bit_depth() == BitDepth::Ten || !self.allows_yuv444() || self.profile.is_some() {
+            return ChromaSubsampling::Yuv420;
+        }
+        self.chroma_subsampling.unwrap_or(match self.quality_mode {
+            QualityMode::Quality => ChromaSubsampling::Yuv444,
+            QualityMode::Performance | QualityMode::Balanced => ChromaSubsampling::Yuv420,
+        })
+    }
+
+    fn allows_yuv444(&self) -> bool {
+        matches!(
+            self.format,
+            OutputFormat::Mp4 | OutputFormat::Mkv | OutputFormat::WebM
+        ) && self.stream_output_url().is_none()
+            && !self.outputs.iter().any(OutputSink::is_rtmp)
+    }
+
     pub fn effective_codec(&self) -> VideoCodec {
         self.codec
             .clone()
             .unwrap_or_else(|| self.format.default_codec())
     }
 
+    pub fn keyframe_interval(&self) -> u32 {
+        self.keyframe_interval_frames.unwrap_or_else(|| {
+            let safe_fps = self.fps.clamp(1, 240);
+            safe_fps.saturating_mul(2).clamp(30, 300)
+        })
+    }
+
     pub fn validate(&self) -> Result<(), String> {
         if self.fps == 0 || self.fps > 120 {
             return Err(format!(
@@ -196,6 +693,19 @@ impl EncoderConfig {
             }
         }
 
+        if let Some(template) = &self.output_template {
+            if template.trim().is_empty() {
+                return Err("La plantilla de nombre de archivo no puede estar vacía".to_string());
+            }
+
+            if template.contains(['/', '\\']) {
+                return Err(
+                    "La plantilla de nombre de archivo no puede contener separadores de ruta"
+                        .to_string(),
+                );
+            }
+        }
+
         if let Some(device) = &self.audio.system_audio_device {
             if device.trim().is_empty() {
                 return Err(
@@ -213,13 +723,58 @@ impl EncoderConfig {
             }
         }
 
-        if self.audio.microphone_gain_percent > 400 {
+        if self.audio.microphone_gain_percent > MAX_MICROPHONE_GAIN_PERCENT {
             return Err(format!(
-                "Ganancia de micrófono inválida: {}%. Debe estar entre 0% y 400%",
+                "Ganancia de micrófono inválida: {}%. Debe estar entre 0% y {MAX_MICROPHONE_GAIN_PERCENT}%",
                 self.audio.microphone_gain_percent
             ));
         }
 
+        if self.audio.audio_sync_offset_ms.abs() > MAX_AUDIO_SYNC_OFFSET_MS {
+            return Err(format!(
+                "Desfase de audio inválido: {} ms. Debe estar entre -{MAX_AUDIO_SYNC_OFFSET_MS} y {MAX_AUDIO_SYNC_OFFSET_MS} ms",
+                self.audio.audio_sync_offset_ms
+            ));
+        }
+
+        if let Some(target) = self.audio.loudness_target_lufs {
+            if !LOUDNESS_TARGET_LUFS_RANGE.contains(&target) {
+                return Err(format!(
+                    "Objetivo de sonoridad inválido: {target} LUFS. Debe estar entre {} y {} LUFS",
+                    LOUDNESS_TARGET_LUFS_RANGE.start(),
+                    LOUDNESS_TARGET_LUFS_RANGE.end()
+                ));
+            }
+        }
+
+        if let Some(sample_rate) = self.audio.audio_sample_rate {
+            if !SUPPORTED_AUDIO_SAMPLE_RATES.contains(&sample_rate) {
+                return Err(format!(
+                    "Frecuencia de audio no soportada: {sample_rate} Hz. Valores válidos: {:?}",
+                    SUPPORTED_AUDIO_SAMPLE_RATES
+                ));
+            }
+            if self.audio.effective_codec(&self.format) == AudioCodec::Opus
+                && !OPUS_SAMPLE_RATES.contains(&sample_rate)
+            {
+                return Err(format!(
+                    "El audio Opus no admite {sample_rate} Hz. Usa 16000, 24000 o 48000"
+                ));
+            }
+        }
+
+        if self.format != OutputFormat::Gif {
+            self.audio.validate_codec_for(&self.format)?;
+        }
+
+        if let Some(channels) = self.audio.audio_channels {
+            if channels == 0 || channels > MAX_AUDIO_CHANNELS {
+                return Err(format!(
+                    "Canales de audio inválidos: {channels}. Usa 1 (mono) o 2 (estéreo)"
+                ));
+            }
+        }
+
         if self.format == OutputFormat::WebM {
             let codec = self.effective_codec();
             if codec != VideoCodec::Vp9 {
@@ -227,57 +782,344 @@ impl EncoderConfig {
             }
         }
 
-        Ok(())
-    }
-}
-
-impl Default for EncoderConfig {
-    fn default() -> Self {
-        Self {
-            output_path: PathBuf::from("recording.mp4"),
-            format: OutputFormat::Mp4,
-            codec: None,
-            video_encoder_preference: VideoEncoderPreference::Auto,
-            resolution: OutputResolution::Native,
-            crf: 23,
-            preset: EncoderPreset::UltraFast,
-            quality_mode: QualityMode::Balanced,
-            fps: 30,
-            audio: AudioCaptureConfig::default(),
+        // MPEG-TS y GIF no tienen dónde guardar el rango y los reproductores
+        // asumen el limitado.
+        if self.color_range == ColorRange::Full
+            && matches!(self.format, OutputFormat::Gif | OutputFormat::Hls)
+        {
+            return Err(
+                "El rango de color completo solo se puede señalizar en MP4, MKV o WebM".to_string(),
+            );
         }
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::{
-        AudioCaptureConfig, EncoderConfig, OutputFormat, OutputResolution, VideoCodec,
-        VideoEncoderPreference,
-    };
 
-    #[test]
-    fn audio_config_is_enabled_si_hay_fuente_activa() {
-        let system_enabled = AudioCaptureConfig {
-            capture_system_audio: true,
-            ..AudioCaptureConfig::default()
-        };
-        assert!(system_enabled.is_enabled());
+        if self.hdr {
+            if !matches!(self.format, OutputFormat::Mp4 | OutputFormat::Mkv) {
+                return Err("La grabación HDR solo está disponible en MP4 o MKV".to_string());
+            }
+            if self.effective_codec() != VideoCodec::H265 {
+                return Err(
+                    "La grabación HDR requiere el codec H.265 (HEVC de 10 bits)".to_string()
+                );
+            }
+            // PQ se graba siempre en rango limitado.
+            if self.color_range == ColorRange::Full {
+                return Err("La grabación HDR no admite rango de color completo".to_string());
+            }
+        }
 
-        let mic_enabled = AudioCaptureConfig {
-            capture_microphone_audio: true,
-            ..AudioCaptureConfig::default()
-        };
-        assert!(mic_enabled.is_enabled());
+        if self.hdr && self.bit_depth == Some(BitDepth::Eight) {
+            return Err("La grabación HDR necesita 10 bits".to_string());
+        }
 
-        let disabled = AudioCaptureConfig::default();
-        assert!(!disabled.is_enabled());
-    }
+        if self.bit_depth() == BitDepth::Ten && !self.hdr {
+            if !matches!(self.format, OutputFormat::Mp4 | OutputFormat::Mkv) {
+                return Err("La grabación de 10 bits solo está disponible en MP4 o MKV".to_string());
+            }
+            if self.effective_codec() != VideoCodec::H265 {
+                return Err("La grabación de 10 bits requiere el codec H.265 (HEVC)".to_string());
+            }
+            if self.stream_output_url().is_some() || !self.outputs.is_empty() {
+                return Err(
+                    "La grabación de 10 bits no admite stream ni salidas adicionales".to_string(),
+                );
+            }
+        }
 
-    #[test]
-    fn validate_rechaza_fps_fuera_de_rango() {
-        let config = EncoderConfig {
-            fps: 0,
-            ..EncoderConfig::default()
+        if self.chroma_subsampling == Some(ChromaSubsampling::Yuv444) {
+            if self.hdr {
+                return Err("La grabación HDR usa croma 4:2:0; no admite 4:4:4".to_string());
+            }
+            if self.bit_depth() == BitDepth::Ten {
+                return Err("La grabación de 10 bits usa croma 4:2:0; no admite 4:4:4".to_string());
+            }
+            if !self.allows_yuv444() {
+                return Err(
+                    "El croma 4:4:4 solo está disponible al grabar un archivo MP4, MKV o WebM"
+                        .to_string(),
+                );
+            }
+            if self.profile.is_some() {
+                return Err(
+                    "El croma 4:4:4 no admite un perfil fijo; quita el perfil o usa 4:2:0"
+                        .to_string(),
+                );
+            }
+        }
+
+        validate_blur_regions(&self.blur_regions)?;
+        if self.hdr && !self.blur_regions.is_empty() {
+            return Err("No se pueden difuminar regiones en una grabación HDR".to_string());
+        }
+
+        validate_profile_level(
+            &self.effective_codec(),
+            self.bit_depth() == BitDepth::Ten,
+            self.profile.as_deref(),
+            self.level.as_deref(),
+        )?;
+
+        if let Some(interval) = self.keyframe_interval_frames {
+            let max_interval = self.fps.saturating_mul(MAX_KEYFRAME_INTERVAL_SECONDS);
+            if interval == 0 || interval > max_interval {
+                return Err(format!(
+                    "Intervalo de keyframes inválido: {interval} frames. A {} FPS debe estar entre 1 y {max_interval} ({MAX_KEYFRAME_INTERVAL_SECONDS} s)",
+                    self.fps
+                ));
+            }
+        }
+
+        if self.fit_to_size_mb == Some(0) {
+            return Err("El tamaño objetivo (fitToSizeMb) debe ser mayor a 0".to_string());
+        }
+
+        if let Some(target_fps) = self.interpolate_to_fps {
+            if target_fps <= self.fps {
+                return Err(format!(
+                    "Los FPS de interpolación ({target_fps}) deben superar los FPS de grabación ({})",
+                    self.fps
+                ));
+            }
+            if target_fps > MAX_INTERPOLATED_FPS {
+                return Err(format!(
+                    "FPS de interpolación inválido: {target_fps}. El máximo es {MAX_INTERPOLATED_FPS}"
+                ));
+            }
+        }
+
+        if self.format == OutputFormat::Gif {
+            if self.interpolate_to_fps.is_some() {
+                return Err("La interpolación de frames no está disponible para GIF".to_string());
+            }
+
+            if self.fit_to_size_mb.is_some() {
+                return Err("El ajuste de tamaño no está disponible para GIF".to_string());
+            }
+
+            if self.audio.is_enabled() {
+                return Err("GIF no admite audio: desactiva la captura de audio".to_string());
+            }
+
+            if self.effective_codec() != VideoCodec::H264 {
+                return Err("GIF solo admite el codec H264 para el video intermedio".to_string());
+            }
+
+            if self.gif.fps == 0 || self.gif.fps > 50 {
+                return Err(format!(
+                    "FPS de GIF inválido: {}. Debe estar entre 1 y 50",
+                    self.gif.fps
+                ));
+            }
+
+            if self.gif.max_width < 16 {
+                return Err(format!(
+                    "Ancho máximo de GIF inválido: {}. Debe ser al menos 16",
+                    self.gif.max_width
+                ));
+            }
+        }
+
+        if let Some(raw_url) = self.stream_output_url() {
+            let url = redact_stream_url(raw_url);
+            if self.format == OutputFormat::Gif {
+                return Err(format!("GIF no se puede emitir por '{url}'"));
+            }
+            if is_rtmp_url(raw_url) {
+                if self.audio.capture_system_audio && self.audio.capture_microphone_audio {
+                    return Err(
+                        "RTMP admite por ahora una sola pista de audio: elige el sistema o el micrófono"
+                            .to_string(),
+                    );
+                }
+                if self
+                    .audio
+                    .audio_codec
+                    .is_some_and(|codec| codec != AudioCodec::Aac)
+                {
+                    return Err("RTMP solo admite audio AAC".to_string());
+                }
+            } else if self.audio.is_enabled() {
+                return Err(format!(
+                    "El audio se mezcla al terminar sobre el archivo; desactívalo para emitir por '{url}'"
+                ));
+            }
+            if self.fit_to_size_mb.is_some() || self.interpolate_to_fps.is_some() {
+                return Err(format!(
+                    "El ajuste de tamaño y la interpolación necesitan un archivo; no están disponibles al emitir por '{url}'"
+                ));
+            }
+        }
+
+        if !self.outputs.is_empty() {
+            if self.format == OutputFormat::Gif {
+                return Err("GIF no admite salidas extra".to_string());
+            }
+            if self.format == OutputFormat::Hls {
+                return Err("HLS no admite salidas extra".to_string());
+            }
+            if self.outputs.len() > MAX_OUTPUT_SINKS {
+                return Err(format!(
+                    "Demasiadas salidas extra: {}. El máximo es {MAX_OUTPUT_SINKS}",
+                    self.outputs.len()
+                ));
+            }
+            for sink in &self.outputs {
+                sink.validate(&self.output_path)?;
+            }
+        }
+
+        if self.is_live_streaming() && self.effective_codec() != VideoCodec::H264 {
+            return Err("RTMP solo admite H.264 por ahora".to_string());
+        }
+
+        if self.format == OutputFormat::Hls {
+            if !matches!(self.effective_codec(), VideoCodec::H264 | VideoCodec::H265) {
+                return Err("HLS solo admite los codecs H264 y H265".to_string());
+            }
+            if self.audio.is_enabled() {
+                return Err(
+                    "HLS todavía no admite audio: desactiva la captura de audio".to_string()
+                );
+            }
+            if self.stream_output_url().is_some() {
+                return Err("HLS escribe una carpeta: usa una ruta de archivo".to_string());
+            }
+            if self.fit_to_size_mb.is_some()
+                || self.interpolate_to_fps.is_some()
+                || !self.trim_range().is_empty()
+            {
+                return Err(
+                    "El ajuste de tamaño, la interpolación y el recorte no están disponibles para HLS"
+                        .to_string(),
+                );
+            }
+            if self.hls_segment_seconds == 0 || self.hls_segment_seconds > MAX_HLS_SEGMENT_SECONDS {
+                return Err(format!(
+                    "Duración de segmento HLS inválida: {} s. Debe estar entre 1 y {MAX_HLS_SEGMENT_SECONDS}",
+                    self.hls_segment_seconds
+                ));
+            }
+        }
+
+        let trim = self.trim_range();
+        if !trim.is_empty() {
+            trim.validate()?;
+            if self.format == OutputFormat::Gif {
+                return Err("El recorte no está disponible para GIF".to_string());
+            }
+            if let Some(url) = self.stream_output_url() {
+                return Err(format!(
+                    "El recorte necesita un archivo; no está disponible al emitir por '{}'",
+                    redact_stream_url(url)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn trim_range(&self) -> TrimRange {
+        TrimRange {
+            start_ms: self.trim_start_ms,
+            end_ms: self.trim_end_ms,
+        }
+    }
+
+    pub fn stream_output_url(&self) -> Option<&str> {
+        stream_output_url(&self.output_path)
+    }
+
+    pub fn uses_audio_mux(&self) -> bool {
+        !matches!(self.format, OutputFormat::Gif | OutputFormat::Hls)
+            && self.stream_output_url().is_none()
+    }
+
+    pub fn is_live_streaming(&self) -> bool {
+        self.stream_output_url().is_some_and(is_rtmp_url)
+            || self.outputs.iter().any(OutputSink::is_rtmp)
+    }
+}
+
+impl Default for EncoderConfig {
+    fn default() -> Self {
+        Self {
+            output_path: PathBuf::from("recording.mp4"),
+            output_template: None,
+            format: OutputFormat::Mp4,
+            codec: None,
+            video_encoder_preference: VideoEncoderPreference::Auto,
+            resolution: OutputResolution::Native,
+            never_upscale: default_never_upscale(),
+            crf: 23,
+            preset: EncoderPreset::UltraFast,
+            quality_mode: QualityMode::Balanced,
+            color_range: ColorRange::Limited,
+            hdr: false,
+            scaler: None,
+            chroma_subsampling: None,
+            bit_depth: None,
+            profile: None,
+            level: None,
+            blur_regions: Vec::new(),
+            fps: 30,
+            audio: AudioCaptureConfig::default(),
+            disk_space_threshold_mb: default_disk_space_threshold_mb(),
+            fat32_policy: Fat32Policy::Reject,
+            gif: GifExportConfig::default(),
+            fit_to_size_mb: None,
+            interpolate_to_fps: None,
+            mp4_faststart: false,
+            keyframe_interval_frames: None,
+            frame_rate_mode: FrameRateMode::Vfr,
+            outputs: Vec::new(),
+            trim_start_ms: None,
+            trim_end_ms: None,
+            hls_segment_seconds: default_hls_segment_seconds(),
+            target_name: None,
+            project: None,
+            metadata: RecordingMetadata::default(),
+            chapter_markers: ChapterMarkerLog::default(),
+            frame_snapshot: FrameSnapshotRequest::default(),
+            cursor_overlay: None,
+            hdr_metadata: None,
+            gpu_frame_route: GpuFrameRoute::default(),
+            session_parts: SessionParts::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AudioCaptureConfig, AudioCodec, BitDepth, ChromaSubsampling, ColorRange, ColorTags,
+        EncoderConfig, Fat32Policy, OutputFormat, OutputResolution, QualityMode, RecordingMetadata,
+        ScalerAlgorithm, VideoCodec, VideoEncoderPreference, MAX_AUDIO_SYNC_OFFSET_MS,
+        MAX_INTERPOLATED_FPS, MAX_KEYFRAME_INTERVAL_SECONDS,
+    };
+    use crate::encoder::output_sink::{OutputSink, RtmpEndpoint, MAX_OUTPUT_SINKS};
+
+    #[test]
+    fn audio_config_is_enabled_si_hay_fuente_activa() {
+        let system_enabled = AudioCaptureConfig {
+            capture_system_audio: true,
+            ..AudioCaptureConfig::default()
+        };
+        assert!(system_enabled.is_enabled());
+
+        let mic_enabled = AudioCaptureConfig {
+            capture_microphone_audio: true,
+            ..AudioCaptureConfig::default()
+        };
+        assert!(mic_enabled.is_enabled());
+
+        let disabled = AudioCaptureConfig::default();
+        assert!(!disabled.is_enabled());
+    }
+
+    #[test]
+    fn validate_rechaza_fps_fuera_de_rango() {
+        let config = EncoderConfig {
+            fps: 0,
+            ..EncoderConfig::default()
         };
 
         let err = config
@@ -317,6 +1159,699 @@ mod tests {
         assert!(err.contains("WebM solo es compatible"));
     }
 
+    #[test]
+    fn escalador_sigue_al_modo_de_calidad_salvo_que_se_elija() {
+        assert_eq!(
+            ScalerAlgorithm::resolve(None, &QualityMode::Performance),
+            ScalerAlgorithm::FastBilinear
+        );
+        assert_eq!(
+            ScalerAlgorithm::resolve(None, &QualityMode::Quality),
+            ScalerAlgorithm::Bicubic
+        );
+        assert_eq!(
+            ScalerAlgorithm::resolve(Some(ScalerAlgorithm::Lanczos), &QualityMode::Performance),
+            ScalerAlgorithm::Lanczos
+        );
+
+        let config: EncoderConfig = serde_json::from_value(serde_json::json!({
+            "outputPath": "clase.mp4",
+            "format": "mp4",
+            "codec": null,
+            "resolution": "native",
+            "crf": 23,
+            "preset": "ultraFast",
+            "fps": 30,
+            "scaler": "lanczos"
+        }))
+        .unwrap();
+        assert_eq!(config.scaler, Some(ScalerAlgorithm::Lanczos));
+    }
+
+    #[test]
+    fn fat32_partir_se_lee_como_segment() {
+        let policy: Fat32Policy = serde_json::from_value(serde_json::json!("segment")).unwrap();
+        assert_eq!(policy, Fat32Policy::Segment);
+        assert_eq!(
+            serde_json::to_value(Fat32Policy::Segment).unwrap(),
+            serde_json::json!("segment")
+        );
+    }
+
+    #[test]
+    fn never_upscale_deja_la_fuente_si_se_pide_mas() {
+        assert_eq!(
+            OutputResolution::P2160.output_dimensions(1920, 1080, true),
+            (1920, 1080)
+        );
+        assert_eq!(
+            OutputResolution::P2160.output_dimensions(1920, 1080, false),
+            (3840, 2160)
+        );
+        assert_eq!(
+            OutputResolution::Hd.output_dimensions(1920, 1080, true),
+            (1280, 720)
+        );
+        let custom = OutputResolution::Custom {
+            width: 2000,
+            height: 1000,
+        };
+        assert_eq!(custom.output_dimensions(1920, 1080, true), (1920, 1080));
+        assert_eq!(custom.output_dimensions(1920, 1080, false), (2000, 1000));
+    }
+
+    #[test]
+    fn validate_limita_hdr_a_hevc_en_mp4_o_mkv() {
+        let hdr = EncoderConfig {
+            hdr: true,
+            codec: Some(VideoCodec::H265),
+            ..EncoderConfig::default()
+        };
+        assert!(hdr.validate().is_ok());
+        assert!(EncoderConfig {
+            format: OutputFormat::Mkv,
+            ..hdr.clone()
+        }
+        .validate()
+        .is_ok());
+
+        let h264 = EncoderConfig {
+            codec: None,
+            ..hdr.clone()
+        };
+        let err = h264.validate().expect_err("debio fallar");
+        assert!(err.contains("H.265"), "{err}");
+
+        let hls = EncoderConfig {
+            format: OutputFormat::Hls,
+            ..hdr.clone()
+        };
+        assert!(hls
+            .validate()
+            .expect_err("debio fallar")
+            .contains("MP4 o MKV"));
+
+        let full_range = EncoderConfig {
+            color_range: ColorRange::Full,
+            ..hdr
+        };
+        assert!(full_range.validate().is_err());
+    }
+
+    #[test]
+    fn etiquetas_de_color_siguen_al_rango_y_al_hdr() {
+        let sdr = EncoderConfig::default();
+        assert_eq!(
+            sdr.color_tags(),
+            ColorTags {
+                range: "tv",
+                space: "bt709",
+                primaries: "bt709",
+                transfer: "bt709",
+            }
+        );
+
+        let full = EncoderConfig {
+            color_range: ColorRange::Full,
+            ..EncoderConfig::default()
+        };
+        assert_eq!(full.color_tags().range, "pc");
+        assert!(full.validate().is_ok());
+        let err = EncoderConfig {
+            format: OutputFormat::Hls,
+            ..full
+        }
+        .validate()
+        .expect_err("debio fallar");
+        assert!(err.contains("rango de color completo"), "{err}");
+
+        let hdr = EncoderConfig {
+            hdr: true,
+            codec: Some(VideoCodec::H265),
+            ..EncoderConfig::default()
+        };
+        assert_eq!(hdr.color_tags().space, "bt2020nc");
+        assert_eq!(hdr.color_tags().transfer, "smpte2084");
+    }
+
+    #[test]
+    fn croma_444_sigue_al_modo_calidad_solo_en_archivos() {
+        let quality = EncoderConfig {
+            quality_mode: QualityMode::Quality,
+            ..EncoderConfig::default()
+        };
+        assert_eq!(quality.chroma_subsampling(), ChromaSubsampling::Yuv444);
+        assert_eq!(
+            EncoderConfig::default().chroma_subsampling(),
+            ChromaSubsampling::Yuv420
+        );
+
+        let forced_420 = EncoderConfig {
+            chroma_subsampling: Some(ChromaSubsampling::Yuv420),
+            ..quality.clone()
+        };
+        assert_eq!(forced_420.chroma_subsampling(), ChromaSubsampling::Yuv420);
+
+        let hls = EncoderConfig {
+            format: OutputFormat::Hls,
+            ..quality.clone()
+        };
+        assert_eq!(hls.chroma_subsampling(), ChromaSubsampling::Yuv420);
+        assert!(hls.validate().is_ok());
+        let err = EncoderConfig {
+            chroma_subsampling: Some(ChromaSubsampling::Yuv444),
+            ..hls
+        }
+        .validate()
+        .expect_err("debio fallar");
+        assert!(err.contains("4:4:4"), "{err}");
+
+        let hdr = EncoderConfig {
+            hdr: true,
+            codec: Some(VideoCodec::H265),
+            ..quality
+        };
+        assert_eq!(hdr.chroma_subsampling(), ChromaSubsampling::Yuv420);
+        let err = EncoderConfig {
+            chroma_subsampling: Some(ChromaSubsampling::Yuv444),
+            ..hdr
+        }
+        .validate()
+        .expect_err("debio fallar");
+        assert!(err.contains("HDR"), "{err}");
+    }
+
+    #[test]
+    fn diez_bits_sin_hdr_exige_hevc_en_archivo() {
+        let ten_bit = EncoderConfig {
+            codec: Some(VideoCodec::H265),
+            bit_depth: Some(BitDepth::Ten),
+            quality_mode: QualityMode::Quality,
+            ..EncoderConfig::default()
+        };
+        assert!(ten_bit.validate().is_ok());
+        assert_eq!(ten_bit.chroma_subsampling(), ChromaSubsampling::Yuv420);
+
+        let err = EncoderConfig {
+            codec: Some(VideoCodec::H264),
+            ..ten_bit.clone()
+        }
+        .validate()
+        .expect_err("debio fallar");
+        assert!(err.contains("H.265"), "{err}");
+
+        let hdr = EncoderConfig {
+            hdr: true,
+            ..ten_bit
+        };
+        assert_eq!(hdr.bit_depth(), BitDepth::Ten);
+        let err = EncoderConfig {
+            bit_depth: Some(BitDepth::Eight),
+            ..hdr
+        }
+        .validate()
+        .expect_err("debio fallar");
+        assert!(err.contains("10 bits"), "{err}");
+    }
+
+    #[test]
+    fn perfil_fijo_se_valida_y_graba_en_420() {
+        let baseline = EncoderConfig {
+            quality_mode: QualityMode::Quality,
+            profile: Some("baseline".to_string()),
+            level: Some("3.1".to_string()),
+            ..EncoderConfig::default()
+        };
+        assert!(baseline.validate().is_ok());
+        assert_eq!(baseline.chroma_subsampling(), ChromaSubsampling::Yuv420);
+
+        let err = EncoderConfig {
+            chroma_subsampling: Some(ChromaSubsampling::Yuv444),
+            ..baseline.clone()
+        }
+        .validate()
+        .expect_err("debio fallar");
+        assert!(err.contains("perfil fijo"), "{err}");
+
+        let err = EncoderConfig {
+            profile: Some("main10".to_string()),
+            ..baseline
+        }
+        .validate()
+        .expect_err("debio fallar");
+        assert!(err.contains("H.264"), "{err}");
+    }
+
+    #[test]
+    fn validate_rechaza_gif_con_audio() {
+        let config = EncoderConfig {
+            format: OutputFormat::Gif,
+            audio: AudioCaptureConfig {
+                capture_system_audio: true,
+                ..AudioCaptureConfig::default()
+            },
+            ..EncoderConfig::default()
+        };
+
+        let err = config
+            .validate()
+            .expect_err("debio fallar por audio habilitado en gif");
+        assert!(err.contains("GIF no admite audio"));
+    }
+
+    #[test]
+    fn validate_stream_solo_admite_video_en_vivo() {
+        let stream = |format, audio: bool| EncoderConfig {
+            output_path: "pipe:1".into(),
+            format,
+            codec: None,
+            audio: AudioCaptureConfig {
+                capture_microphone_audio: audio,
+                ..AudioCaptureConfig::default()
+            },
+            ..EncoderConfig::default()
+        };
+
+        assert!(stream(OutputFormat::Mkv, false).validate().is_ok());
+        assert!(stream(OutputFormat::Mp4, false).validate().is_ok());
+        assert!(stream(OutputFormat::Gif, false)
+            .validate()
+            .expect_err("GIF no se emite")
+            .contains("pipe:1"));
+        assert!(stream(OutputFormat::Mkv, true)
+            .validate()
+            .expect_err("el audio se mezcla al final")
+            .contains("audio"));
+
+        let mut fit = stream(OutputFormat::Mp4, false);
+        fit.fit_to_size_mb = Some(10);
+        assert!(fit.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rtmp_admite_h264_con_una_pista_aac() {
+        let rtmp = |codec, system: bool, microphone: bool| EncoderConfig {
+            output_path: "rtmp://live.example.com/app/secreta".into(),
+            codec,
+            audio: AudioCaptureConfig {
+                capture_system_audio: system,
+                capture_microphone_audio: microphone,
+                ..AudioCaptureConfig::default()
+            },
+            ..EncoderConfig::default()
+        };
+
+        assert!(rtmp(None, false, true).validate().is_ok());
+        assert!(rtmp(Some(VideoCodec::H264), true, false).validate().is_ok());
+
+        let err = rtmp(Some(VideoCodec::H265), false, false)
+            .validate()
+            .unwrap_err();
+        assert!(err.contains("H.264"));
+        assert!(!err.contains("secreta"), "{err}");
+        assert!(rtmp(None, true, true).validate().is_err());
+
+        let mut flac = rtmp(Some(VideoCodec::H264), false, true);
+        flac.format = OutputFormat::Mkv;
+        flac.audio.audio_codec = Some(AudioCodec::Flac);
+        assert!(flac.validate().unwrap_err().contains("AAC"));
+    }
+
+    #[test]
+    fn salidas_extra_rtmp_fuerzan_h264_y_modo_en_vivo() {
+        let with_outputs = |codec, outputs| EncoderConfig {
+            output_path: "C:/videos/clip.mkv".into(),
+            format: OutputFormat::Mkv,
+            codec,
+            outputs,
+            ..EncoderConfig::default()
+        };
+        let rtmp = OutputSink::Rtmp(RtmpEndpoint {
+            url: "rtmp://127.0.0.1/live".to_string(),
+            key: "clave".to_string(),
+        });
+
+        let config = with_outputs(None, vec![rtmp.clone()]);
+        assert!(config.validate().is_ok());
+        assert!(config.is_live_streaming());
+        assert!(!with_outputs(None, Vec::new()).is_live_streaming());
+
+        let err = with_outputs(Some(VideoCodec::H265), vec![rtmp.clone()])
+            .validate()
+            .unwrap_err();
+        assert!(err.contains("H.264"));
+
+        let too_many = with_outputs(None, vec![rtmp.clone(); MAX_OUTPUT_SINKS + 1]);
+        assert!(too_many.validate().unwrap_err().contains("Demasiadas"));
+
+        let hls = EncoderConfig {
+            output_path: "C:/videos/clip.m3u8".into(),
+            format: OutputFormat::Hls,
+            outputs: vec![rtmp],
+            ..EncoderConfig::default()
+        };
+        assert!(hls.validate().unwrap_err().contains("HLS"));
+    }
+
+    #[test]
+    fn hls_no_espera_el_servicio_de_audio() {
+        let hls = EncoderConfig {
+            output_path: "C:/videos/clip.m3u8".into(),
+            format: OutputFormat::Hls,
+            audio: AudioCaptureConfig {
+                capture_system_audio: false,
+                capture_microphone_audio: false,
+                ..AudioCaptureConfig::default()
+            },
+            ..EncoderConfig::default()
+        };
+        assert!(hls.validate().is_ok());
+        assert!(!hls.uses_audio_mux());
+        assert!(EncoderConfig::default().uses_audio_mux());
+    }
+
+    #[test]
+    fn validate_limita_el_desfase_de_audio() {
+        let pulled = EncoderConfig {
+            audio: AudioCaptureConfig {
+                audio_sync_offset_ms: -MAX_AUDIO_SYNC_OFFSET_MS,
+                ..AudioCaptureConfig::default()
+            },
+            ..EncoderConfig::default()
+        };
+        assert!(pulled.validate().is_ok());
+
+        let excessive = EncoderConfig {
+            audio: AudioCaptureConfig {
+                audio_sync_offset_ms: -(MAX_AUDIO_SYNC_OFFSET_MS + 1),
+                ..AudioCaptureConfig::default()
+            },
+            ..EncoderConfig::default()
+        };
+        let err = excessive
+            .validate()
+            .expect_err("debio fallar por desfase fuera de rango");
+        assert!(err.contains("Desfase de audio"));
+    }
+
+    #[test]
+    fn validate_limita_el_objetivo_de_sonoridad() {
+        let mut config = EncoderConfig {
+            audio: AudioCaptureConfig {
+                normalize_loudness: true,
+                ..AudioCaptureConfig::default()
+            },
+            ..EncoderConfig::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.audio.loudness_target(), Some(-16));
+
+        config.audio.loudness_target_lufs = Some(-30);
+        assert!(config.validate().is_ok());
+        assert_eq!(config.audio.loudness_target(), Some(-30));
+
+        config.audio.loudness_target_lufs = Some(-7);
+        let err = config
+            .validate()
+            .expect_err("debio fallar por objetivo fuera de rango");
+        assert!(err.contains("sonoridad"));
+
+        config.audio.loudness_target_lufs = Some(-20);
+        config.audio.normalize_loudness = false;
+        assert_eq!(config.audio.loudness_target(), None);
+    }
+
+    #[test]
+    fn validate_acepta_solo_frecuencias_y_canales_comunes() {
+        let forced = EncoderConfig {
+            audio: AudioCaptureConfig {
+                audio_sample_rate: Some(48_000),
+                audio_channels: Some(2),
+                ..AudioCaptureConfig::default()
+            },
+            ..EncoderConfig::default()
+        };
+        assert!(forced.validate().is_ok());
+
+        let odd_rate = EncoderConfig {
+            audio: AudioCaptureConfig {
+                audio_sample_rate: Some(47_000),
+                ..AudioCaptureConfig::default()
+            },
+            ..EncoderConfig::default()
+        };
+        assert!(odd_rate
+            .validate()
+            .expect_err("debio fallar por frecuencia rara")
+            .contains("Frecuencia de audio"));
+
+        let opus_44k = EncoderConfig {
+            format: OutputFormat::WebM,
+            codec: Some(VideoCodec::Vp9),
+            audio: AudioCaptureConfig {
+                audio_sample_rate: Some(44_100),
+                ..AudioCaptureConfig::default()
+            },
+            ..EncoderConfig::default()
+        };
+        assert!(opus_44k
+            .validate()
+            .expect_err("opus no admite 44.1 kHz")
+            .contains("Opus"));
+
+        let surround = EncoderConfig {
+            audio: AudioCaptureConfig {
+                audio_channels: Some(6),
+                ..AudioCaptureConfig::default()
+            },
+            ..EncoderConfig::default()
+        };
+        assert!(surround
+            .validate()
+            .expect_err("debio fallar por canales")
+            .contains("Canales de audio"));
+    }
+
+    #[test]
+    fn codec_de_audio_por_defecto_conserva_el_comportamiento_anterior() {
+        let audio = AudioCaptureConfig::default();
+        assert_eq!(
+            audio.encoder_args(&OutputFormat::Mp4),
+            ["-c:a", "aac", "-b:a", "160k"]
+        );
+        assert_eq!(
+            audio.encoder_args(&OutputFormat::WebM),
+            ["-c:a", "libopus", "-b:a", "128k"]
+        );
+
+        let flac = AudioCaptureConfig {
+            audio_codec: Some(AudioCodec::Flac),
+            ..AudioCaptureConfig::default()
+        };
+        assert_eq!(flac.encoder_args(&OutputFormat::Mkv), ["-c:a", "flac"]);
+
+        let aac_256 = AudioCaptureConfig {
+            audio_bitrate_kbps: Some(256),
+            ..AudioCaptureConfig::default()
+        };
+        assert_eq!(
+            aac_256.encoder_args(&OutputFormat::Mkv),
+            ["-c:a", "aac", "-b:a", "256k"]
+        );
+    }
+
+    #[test]
+    fn validate_combina_codec_de_audio_y_contenedor() {
+        let with_audio = |format: OutputFormat, codec: AudioCodec| EncoderConfig {
+            codec: Some(format.default_codec()),
+            format,
+            audio: AudioCaptureConfig {
+                audio_codec: Some(codec),
+                ..AudioCaptureConfig::default()
+            },
+            ..EncoderConfig::default()
+        };
+
+        assert!(with_audio(OutputFormat::Mkv, AudioCodec::Pcm)
+            .validate()
+            .is_ok());
+        assert!(with_audio(OutputFormat::Mp4, AudioCodec::Flac)
+            .validate()
+            .expect_err("flac solo en mkv")
+            .contains("solo está disponible en MKV"));
+        assert!(with_audio(OutputFormat::WebM, AudioCodec::Aac)
+            .validate()
+            .expect_err("webm solo opus")
+            .contains("WebM solo admite audio Opus"));
+
+        let mut opus_mp4 = with_audio(OutputFormat::Mp4, AudioCodec::Opus);
+        assert!(opus_mp4.validate().is_err());
+        opus_mp4.audio.allow_opus_in_mp4 = true;
+        assert!(opus_mp4.validate().is_ok());
+
+        let mut lossless_bitrate = with_audio(OutputFormat::Mkv, AudioCodec::Flac);
+        lossless_bitrate.audio.audio_bitrate_kbps = Some(320);
+        assert!(lossless_bitrate
+            .validate()
+            .expect_err("flac no usa bitrate")
+            .contains("sin pérdida"));
+
+        let mut low_bitrate = with_audio(OutputFormat::Mkv, AudioCodec::Aac);
+        low_bitrate.audio.audio_bitrate_kbps = Some(8);
+        assert!(low_bitrate
+            .validate()
+            .expect_err("bitrate fuera de rango")
+            .contains("Bitrate de audio"));
+    }
+
+    #[test]
+    fn intervalo_de_keyframes_usa_el_override_o_dos_segundos() {
+        let with_interval = |fps, interval| EncoderConfig {
+            fps,
+            keyframe_interval_frames: interval,
+            ..EncoderConfig::default()
+        };
+
+        assert_eq!(with_interval(60, Some(60)).keyframe_interval(), 60);
+        assert_eq!(with_interval(60, None).keyframe_interval(), 120);
+        assert_eq!(with_interval(5, None).keyframe_interval(), 30);
+        assert_eq!(with_interval(120, None).keyframe_interval(), 240);
+
+        assert!(with_interval(30, Some(1)).validate().is_ok());
+        assert!(with_interval(30, Some(30 * MAX_KEYFRAME_INTERVAL_SECONDS))
+            .validate()
+            .is_ok());
+        let err = with_interval(30, Some(0))
+            .validate()
+            .expect_err("intervalo en cero");
+        assert!(err.contains("keyframes"));
+        assert!(
+            with_interval(30, Some(30 * MAX_KEYFRAME_INTERVAL_SECONDS + 1))
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn validate_exige_interpolacion_por_encima_de_los_fps() {
+        let interpolate = |target_fps| EncoderConfig {
+            fps: 30,
+            interpolate_to_fps: Some(target_fps),
+            ..EncoderConfig::default()
+        };
+
+        assert!(interpolate(60).validate().is_ok());
+        assert!(interpolate(MAX_INTERPOLATED_FPS).validate().is_ok());
+        let err = interpolate(30).validate().expect_err("no supera los fps");
+        assert!(err.contains("deben superar"));
+        assert!(interpolate(MAX_INTERPOLATED_FPS + 1).validate().is_err());
+
+        let gif = EncoderConfig {
+            format: OutputFormat::Gif,
+            ..interpolate(60)
+        };
+        assert!(gif.validate().is_err());
+    }
+
+    #[test]
+    fn metadatos_omiten_vacios_y_siempre_llevan_fecha_de_creacion() {
+        use chrono::{Local, TimeZone, Utc};
+
+        let metadata = RecordingMetadata {
+            title: Some(" Demo ".to_string()),
+            comment: Some("  ".to_string()),
+            author: Some("Ana".to_string()),
+        };
+        let started_at = Utc
+            .with_ymd_and_hms(2026, 3, 14, 9, 26, 53)
+            .unwrap()
+            .with_timezone(&Local);
+
+        assert_eq!(
+            metadata.container_tags(started_at),
+            vec![
+                ("title", "Demo".to_string()),
+                ("artist", "Ana".to_string()),
+                ("creation_time", "2026-03-14T09:26:53.000000Z".to_string()),
+            ]
+        );
+        assert_eq!(
+            RecordingMetadata::default()
+                .container_tags(started_at)
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>(),
+            vec!["creation_time"]
+        );
+    }
+
+    #[test]
+    fn validate_rechaza_plantilla_con_separadores() {
+        let config = EncoderConfig {
+            output_template: Some("sub/{date}".to_string()),
+            ..EncoderConfig::default()
+        };
+
+        let err = config
+            .validate()
+            .expect_err("debio fallar por plantilla con separadores");
+        assert!(err.contains("separadores de ruta"));
+    }
+
+    #[test]
+    fn validate_hls_solo_con_h264_o_h265_y_sin_audio() {
+        let hls = EncoderConfig {
+            format: OutputFormat::Hls,
+            ..EncoderConfig::default()
+        };
+        assert!(hls.validate().is_ok());
+
+        let vp9 = EncoderConfig {
+            codec: Some(VideoCodec::Vp9),
+            ..hls.clone()
+        };
+        assert!(vp9.validate().is_err());
+
+        let with_audio = EncoderConfig {
+            audio: AudioCaptureConfig {
+                capture_system_audio: true,
+                ..AudioCaptureConfig::default()
+            },
+            ..hls.clone()
+        };
+        assert!(with_audio.validate().is_err());
+
+        let no_segments = EncoderConfig {
+            hls_segment_seconds: 0,
+            ..hls
+        };
+        assert!(no_segments.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rechaza_recorte_invertido_o_en_gif() {
+        let inverted = EncoderConfig {
+            trim_start_ms: Some(8_000),
+            trim_end_ms: Some(3_000),
+            ..EncoderConfig::default()
+        };
+        assert!(inverted.validate().is_err());
+
+        let gif = EncoderConfig {
+            format: OutputFormat::Gif,
+            trim_start_ms: Some(1_000),
+            ..EncoderConfig::default()
+        };
+        assert!(gif.validate().is_err());
+
+        let valid = EncoderConfig {
+            trim_start_ms: Some(1_000),
+            trim_end_ms: Some(9_000),
+            ..EncoderConfig::default()
+        };
+        assert!(valid.validate().is_ok());
+    }
+
     #[test]
     fn validate_acepta_configuracion_valida() {
         let config = EncoderConfig {
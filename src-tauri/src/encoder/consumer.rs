@@ -1,23 +1,82 @@
 #[cfg(target_os = "windows")]
 mod platform {
-    use std::{ffi::c_void, ptr};
+    use std::{
+        ffi::{c_void, CString},
+        ops::Range,
+        ptr,
+        sync::{
+            mpsc::{self, Receiver, SyncSender, TrySendError},
+            Arc,
+        },
+        thread::{self, JoinHandle},
+        time::Instant,
+    };
 
     use ffmpeg_the_third::{
         codec::{self, encoder},
-        ffi,
+        color, ffi,
         format::{self, flag::Flags, Pixel},
         frame, packet,
         software::scaling::{self, Flags as ScaleFlags},
         Dictionary, Rational,
     };
 
-    use crate::capture::models::RawFrame;
+    use crate::capture::{
+        cursor_overlay::CursorOverlay,
+        models::{RawFrame, StopReason},
+        session_stop::request_session_stop,
+    };
     use crate::encoder::{
-        audio_capture::AudioCaptureService,
-        config::{EncoderConfig, QualityMode, VideoCodec, VideoEncoderPreference},
-        output_paths::prepare_output_paths,
+        audio_capture::{
+            get_live_audio_status,
+            live_tap::{LiveAudioTap, LIVE_AUDIO_SAMPLE_RATE},
+            AudioCaptureService, LiveAudioCapture, StoppedAudioCapture,
+        },
+        bitrate::estimate_target_bitrate_kbps,
+        cfr_pacer::CfrPacer,
+        chapters::markers_for_part,
+        codec_profile::profile_level_options,
+        config::{
+            AudioCodec, BitDepth, ChromaSubsampling, ColorRange, ColorTags, EncoderConfig,
+            FrameRateMode, OutputFormat, QualityMode, ScalerAlgorithm, VideoCodec,
+        },
+        disk_space::DiskSpaceMonitor,
+        encoded_duration::set_live_encoded_duration_ms,
+        encoder_candidates::{
+            encoder_candidates, supports_yuv444, ten_bit_encoder_candidates, yuv444_profile,
+        },
+        filesystem_limits::{
+            audio_bytes_per_second, check_file_size_limits, part_output_path,
+            reached_file_size_limit, SystemVolumeInfo,
+        },
+        fit_to_size::FitToSizeJob,
+        frame_hold::{hold_pts_before, hold_pts_until_stop, HOLD_FRAME_MAX_GAP_MS},
+        frame_interpolation::FrameInterpolationJob,
+        gif_export::GifExportJob,
+        gpu_adapter::{
+            encoder_adapter_for_backend, ensure_same_adapter, enumerate_adapters, texture_adapter,
+        },
+        gpu_scaler::{live_encoder_label, D3d11Scaler, ScalingPath},
+        hdr::{HdrConverter, P010Planes},
+        hdr_metadata::HdrMasteringMetadata,
+        hls::{hls_muxer_options, hls_playlist_path, HlsOutputJob},
+        output_paths::{ffmpeg_path_arg, prepare_output_paths},
+        output_sink::{
+            is_rtmp_url, live_stream_encoder_options, mirror_queue_capacity,
+            notify_output_sink_failed, redact_stream_url, stream_container_name,
+            stream_muxer_options, OutputSink, OutputSinkFailed, MIRROR_CLOSE_TIMEOUT,
+            MIRROR_QUEUE_SECONDS,
+        },
+        pipeline_latency::{
+            record_pipeline_latency_ms, PendingFrameTimes, MAX_PENDING_FRAME_TIMES,
+        },
+        processing_status::ProcessingGuard,
+        recording_history::FinishedRecording,
+        region_blur::RegionBlur,
+        stall_watchdog::EncoderStallWatchdog,
         video_encoder_status::set_live_video_encoder_label,
     };
+    use crate::shutdown::join_thread_until;
 
     enum VideoInputPipeline {
         Cpu {
@@ -25,7 +84,75 @@ mod platform {
             src_frame: frame::Video,
             dst_frame: frame::Video,
         },
-        GpuTextureD3d11,
+        GpuTextureD3d11 {
+            scaler: Option<GpuTextureScaler>,
+        },
+    }
+
+    /// Escalado de la ruta GPU. Si el procesador de video falla a mitad de
+    /// sesión el encoder ya abrió con texturas, así que desde ahí cada frame
+    /// baja a CPU, se escala con swscale y vuelve a subir a la GPU.
+    struct GpuTextureScaler {
+        d3d11: D3d11Scaler,
+        cpu_flags: ScaleFlags,
+        cpu_fallback: Option<CpuTextureFallback>,
+    }
+
+    struct CpuTextureFallback {
+        scaler: scaling::Context,
+        src_frame: frame::Video,
+        dst_frame: frame::Video,
+    }
+
+    impl GpuTextureScaler {
+        fn scale(&mut self, texture_ptr: usize) -> Result<usize, String> {
+            if self.cpu_fallback.is_none() {
+                match self.d3d11.scale(texture_ptr) {
+                    Ok(scaled) => return Ok(scaled),
+                    Err(err) => eprintln!("[encoder] {err}. Se sigue escalando en CPU."),
+                }
+            }
+
+            let (bytes, row_stride_bytes) = self.d3d11.read_back(texture_ptr)?;
+            let fallback = match self.cpu_fallback.take() {
+                Some(fallback) => fallback,
+                None => {
+                    let (src_w, src_h) = self.d3d11.input_size();
+                    let (out_w, out_h) = self.d3d11.output_size();
+                    let scaler = scaling::Context::get(
+                        Pixel::BGRA,
+                        src_w,
+                        src_h,
+                        Pixel::BGRA,
+                        out_w,
+                        out_h,
+                        self.cpu_flags,
+                    )
+                    .map_err(|err| format!("No se pudo crear el escalador CPU: {err}"))?;
+                    CpuTextureFallback {
+                        scaler,
+                        src_frame: frame::Video::new(Pixel::BGRA, src_w, src_h),
+                        dst_frame: frame::Video::new(Pixel::BGRA, out_w, out_h),
+                    }
+                }
+            };
+            let fallback = self.cpu_fallback.insert(fallback);
+
+            let row_bytes = fallback.src_frame.width() as usize * 4;
+            let dst_stride = fallback.src_frame.stride(0);
+            for (src_row, dst_row) in bytes
+                .chunks(row_stride_bytes)
+                .zip(fallback.src_frame.data_mut(0).chunks_mut(dst_stride))
+            {
+                dst_row[..row_bytes].copy_from_slice(&src_row[..row_bytes]);
+            }
+            fallback
+                .scaler
+                .run(&fallback.src_frame, &mut fallback.dst_frame)
+                .map_err(|err| format!("Error escalando el frame en CPU: {err}"))?;
+            self.d3d11
+                .upload(fallback.dst_frame.data(0), fallback.dst_frame.stride(0))
+        }
     }
 
     struct EncoderContext {
@@ -36,12 +163,368 @@ mod platform {
         time_base: Rational,
         first_timestamp_ms: Option<u64>,
         last_pts: i64,
+        cfr: Option<CfrPacer>,
+        pending_frame_times: PendingFrameTimes,
+        bytes_written: u64,
+        live_audio: Option<LiveAudioEncoder>,
+        mirrors: Vec<MirrorOutput>,
+        stall_watchdog: EncoderStallWatchdog,
+    }
+
+    struct MirrorOutput {
+        index: usize,
+        label: String,
+        sender: Option<SyncSender<packet::Packet>>,
+        worker: Option<JoinHandle<()>>,
+    }
+
+    impl MirrorOutput {
+        fn spawn(
+            index: usize,
+            sink: &OutputSink,
+            format_kind: &OutputFormat,
+            video_enc: &encoder::Video,
+            time_base: Rational,
+            fps: u32,
+        ) -> Result<Self, String> {
+            let params = MirrorStreamParams::from_encoder(video_enc, time_base, fps)?;
+            let (sender, receiver) = mpsc::sync_channel(mirror_queue_capacity(fps));
+            let label = sink.label();
+            let worker_label = label.clone();
+            let sink = sink.clone();
+            let format_kind = format_kind.clone();
+            let worker = thread::Builder::new()
+                .name(format!("mirror-output-{index}"))
+                .spawn(move || {
+                    let result = MirrorWriter::open(&sink, &format_kind, &params)
+                        .and_then(|mut writer| writer.run(&receiver));
+                    if let Err(message) = result {
+                        notify_output_sink_failed(OutputSinkFailed {
+                            index,
+                            target: worker_label,
+                            message,
+                        });
+                    }
+                })
+                .map_err(|err| format!("No se pudo crear el hilo de la salida: {err}"))?;
+
+            Ok(Self {
+                index,
+                label,
+                sender: Some(sender),
+                worker: Some(worker),
+            })
+        }
+
+        fn is_open(&self) -> bool {
+            self.sender.is_some()
+        }
+
+        /// No espera nunca: con la cola llena la salida se desconecta, porque
+        /// saltear packets deja el video roto hasta el próximo keyframe.
+        fn write(&mut self, packet: &packet::Packet) {
+            let Some(sender) = self.sender.as_ref() else {
+                return;
+            };
+            match sender.try_send(packet.clone()) {
+                Ok(()) => {}
+                Err(TrySendError::Disconnected(_)) => self.sender = None,
+                Err(TrySendError::Full(_)) => {
+                    self.sender = None;
+                    notify_output_sink_failed(OutputSinkFailed {
+                        index: self.index,
+                        target: self.label.clone(),
+                        message: format!(
+                            "La salida se atrasó más de {MIRROR_QUEUE_SECONDS} s y se desconectó"
+                        ),
+                    });
+                }
+            }
+        }
+
+        fn close(&mut self) {
+            self.sender = None;
+        }
+
+        fn join(&mut self, deadline: Instant) {
+            let Some(worker) = self.worker.take() else {
+                return;
+            };
+            if !join_thread_until(worker, deadline) {
+                notify_output_sink_failed(OutputSinkFailed {
+                    index: self.index,
+                    target: self.label.clone(),
+                    message: "La salida no terminó de cerrarse a tiempo y puede quedar incompleta"
+                        .to_string(),
+                });
+            }
+        }
+    }
+
+    struct MirrorStreamParams {
+        codecpar: *mut ffi::AVCodecParameters,
+        time_base: Rational,
+        fps: u32,
+    }
+
+    // Es una copia propia: nadie más toca `codecpar`.
+    unsafe impl Send for MirrorStreamParams {}
+
+    impl MirrorStreamParams {
+        fn from_encoder(
+            video_enc: &encoder::Video,
+            time_base: Rational,
+            fps: u32,
+        ) -> Result<Self, String> {
+            unsafe {
+                let mut codecpar = ffi::avcodec_parameters_alloc();
+                if codecpar.is_null()
+                    || ffi::avcodec_parameters_from_context(codecpar, video_enc.as_ptr()) < 0
+                {
+                    ffi::avcodec_parameters_free(&mut codecpar);
+                    return Err("No se pudieron copiar los parámetros del encoder".to_string());
+                }
+                Ok(Self {
+                    codecpar,
+                    time_base,
+                    fps,
+                })
+            }
+        }
+    }
+
+    impl Drop for MirrorStreamParams {
+        fn drop(&mut self) {
+            unsafe { ffi::avcodec_parameters_free(&mut self.codecpar) };
+        }
+    }
+
+    struct MirrorWriter {
+        output_ctx: format::context::Output,
+        stream_idx: usize,
+        encoder_time_base: Rational,
+        time_base: Rational,
+    }
+
+    impl MirrorWriter {
+        fn open(
+            sink: &OutputSink,
+            format_kind: &OutputFormat,
+            params: &MirrorStreamParams,
+        ) -> Result<Self, String> {
+            let (target, container) = sink.muxer_target(format_kind)?;
+            let target = match sink {
+                OutputSink::File { path } => {
+                    if let Some(parent) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                        std::fs::create_dir_all(parent).map_err(|err| {
+                            format!("No se pudo crear la carpeta '{}': {err}", parent.display())
+                        })?;
+                    }
+                    ffmpeg_path_arg(&target)?
+                }
+                OutputSink::Rtmp(_) => target.to_string_lossy().into_owned(),
+            };
+            let mut output_ctx = format::output_as(&target, container)
+                .map_err(|err| format!("No se pudo abrir la salida: {err}"))?;
+
+            let mut stream = output_ctx
+                .add_stream(encoder::find(codec::Id::None))
+                .map_err(|err| format!("No se pudo agregar el stream de video: {err}"))?;
+            let stream_idx = stream.index();
+            if unsafe {
+                ffi::avcodec_parameters_copy((*stream.as_mut_ptr()).codecpar, params.codecpar)
+            } < 0
+            {
+                return Err("No se pudieron copiar los parámetros del encoder".to_string());
+            }
+            stream.set_time_base(params.time_base);
+            stream.set_rate(Rational::new(params.fps as i32, 1));
+            stream.set_avg_frame_rate(Rational::new(params.fps as i32, 1));
+
+            output_ctx
+                .write_header()
+                .map_err(|err| format!("No se pudo escribir la cabecera: {err}"))?;
+            // El muxer puede cambiar la base de tiempo al escribir la cabecera.
+            let time_base = output_ctx
+                .stream(stream_idx)
+                .map(|stream| stream.time_base())
+                .unwrap_or(params.time_base);
+
+            Ok(Self {
+                output_ctx,
+                stream_idx,
+                encoder_time_base: params.time_base,
+                time_base,
+            })
+        }
+
+        fn run(&mut self, receiver: &Receiver<packet::Packet>) -> Result<(), String> {
+            for mut packet in receiver.iter() {
+                packet.set_stream(self.stream_idx);
+                packet.rescale_ts(self.encoder_time_base, self.time_base);
+                packet
+                    .write_interleaved(&mut self.output_ctx)
+                    .map_err(|err| format!("Error escribiendo packet: {err}"))?;
+            }
+            self.output_ctx
+                .write_trailer()
+                .map_err(|err| format!("Error escribiendo el trailer: {err}"))
+        }
+    }
+
+    struct LiveAudioEncoder {
+        tap: Arc<LiveAudioTap>,
+        audio_enc: encoder::Audio,
+        stream_idx: usize,
+        time_base: Rational,
+        frame_size: usize,
+    }
+
+    impl LiveAudioEncoder {
+        fn open(
+            output_ctx: &mut format::context::Output,
+            tap: Arc<LiveAudioTap>,
+            bitrate_kbps: u32,
+            needs_global_header: bool,
+        ) -> Result<Self, String> {
+            let aac = encoder::find(codec::Id::AAC)
+                .ok_or_else(|| "FFmpeg no tiene encoder AAC para el stream".to_string())?;
+            let mut audio_enc = codec::context::Context::new_with_codec(aac)
+                .encoder()
+                .audio()
+                .map_err(|err| format!("No se pudo crear el encoder AAC: {err}"))?;
+            let time_base = Rational::new(1, LIVE_AUDIO_SAMPLE_RATE as i32);
+            audio_enc.set_rate(LIVE_AUDIO_SAMPLE_RATE as i32);
+            audio_enc.set_format(format::Sample::F32(format::sample::Type::Planar));
+            audio_enc.set_bit_rate(bitrate_kbps as usize * 1_000);
+            audio_enc.set_time_base(time_base);
+            unsafe {
+                ffi::av_channel_layout_default(&mut (*audio_enc.as_mut_ptr()).ch_layout, 2);
+            }
+            if needs_global_header {
+                audio_enc.set_flags(codec::Flags::GLOBAL_HEADER);
+            }
+            let audio_enc = audio_enc
+                .open_as(aac)
+                .map_err(|err| format!("No se pudo abrir el encoder AAC: {err}"))?;
+
+            let mut stream = output_ctx
+                .add_stream(aac)
+                .map_err(|err| format!("No se pudo agregar el stream de audio: {err}"))?;
+            stream.copy_parameters_from_context(&audio_enc);
+            stream.set_time_base(time_base);
+            let stream_idx = stream.index();
+            let frame_size = match audio_enc.frame_size() {
+                0 => 1_024,
+                size => size as usize,
+            };
+
+            Ok(Self {
+                tap,
+                audio_enc,
+                stream_idx,
+                time_base,
+                frame_size,
+            })
+        }
+
+        fn pump(&mut self, output_ctx: &mut format::context::Output) -> Result<u64, String> {
+            let mut bytes_written = 0;
+            while let Some(tap_frame) = self.tap.pop_frame(self.frame_size) {
+                let mut audio_frame = frame::Audio::empty();
+                unsafe {
+                    let av_frame = audio_frame.as_mut_ptr();
+                    let enc_ctx = self.audio_enc.as_ptr();
+                    (*av_frame).format = (*enc_ctx).sample_fmt as i32;
+                    (*av_frame).sample_rate = (*enc_ctx).sample_rate;
+                    (*av_frame).nb_samples = self.frame_size as i32;
+                    if ffi::av_channel_layout_copy(
+                        &mut (*av_frame).ch_layout,
+                        &(*enc_ctx).ch_layout,
+                    ) < 0
+                        || ffi::av_frame_get_buffer(av_frame, 0) < 0
+                    {
+                        return Err("No se pudo reservar el frame de audio".to_string());
+                    }
+                }
+                for (plane, samples) in [tap_frame.left, tap_frame.right].iter().enumerate() {
+                    let data = audio_frame.data_mut(plane);
+                    for (chunk, sample) in data.chunks_exact_mut(4).zip(samples) {
+                        chunk.copy_from_slice(&sample.to_ne_bytes());
+                    }
+                }
+                // La posición sale del reloj de captura: si el tap descartó
+                // muestras, el audio no se adelanta al video.
+                audio_frame.set_pts(Some(tap_frame.position as i64));
+
+                self.audio_enc
+                    .send_frame(&audio_frame)
+                    .map_err(|err| format!("Error enviando audio al encoder: {err}"))?;
+                bytes_written += self.drain(output_ctx)?;
+            }
+            Ok(bytes_written)
+        }
+
+        fn finish(&mut self, output_ctx: &mut format::context::Output) -> Result<(), String> {
+            self.pump(output_ctx)?;
+            self.audio_enc
+                .send_eof()
+                .map_err(|err| format!("Error enviando EOF al encoder de audio: {err}"))?;
+            self.drain(output_ctx).map(|_| ())
+        }
+
+        fn drain(&mut self, output_ctx: &mut format::context::Output) -> Result<u64, String> {
+            let stream_time_base = output_ctx
+                .stream(self.stream_idx)
+                .map(|stream| stream.time_base())
+                .ok_or_else(|| "No se encontró el stream de audio de salida".to_string())?;
+            let mut bytes_written = 0;
+            let mut encoded_packet = packet::Packet::empty();
+            while self.audio_enc.receive_packet(&mut encoded_packet).is_ok() {
+                encoded_packet.set_stream(self.stream_idx);
+                encoded_packet.rescale_ts(self.time_base, stream_time_base);
+                bytes_written += encoded_packet.size() as u64;
+                encoded_packet
+                    .write_interleaved(output_ctx)
+                    .map_err(|err| format!("Error escribiendo audio en el stream: {err}"))?;
+            }
+            Ok(bytes_written)
+        }
+    }
+
+    impl EncoderContext {
+        fn pts_to_ms(&self, pts: i64) -> u64 {
+            let pts = pts.max(0) as u64;
+            match self.cfr.as_ref() {
+                Some(pacer) => pacer.slot_to_ms(pts),
+                None => pts,
+            }
+        }
     }
 
     pub struct FfmpegEncoderConsumer {
         config: EncoderConfig,
         ctx: Option<EncoderContext>,
         audio_capture: Option<AudioCaptureService>,
+        live_audio_capture: Option<LiveAudioCapture>,
+        gif_export: Option<GifExportJob>,
+        hls_output: Option<HlsOutputJob>,
+        fit_to_size: Option<FitToSizeJob>,
+        frame_interpolation: Option<FrameInterpolationJob>,
+        finished_recording: FinishedRecording,
+        disk_space_monitor: DiskSpaceMonitor,
+        file_size_limit_bytes: Option<u64>,
+        audio_bytes_per_second: u64,
+        segment_base: Option<EncoderConfig>,
+        part: u32,
+        part_offset_ms: u64,
+        part_end_ms: Option<u64>,
+        session_first_timestamp_ms: Option<u64>,
+        stop_requested: bool,
+        cursor_overlay: Option<CursorOverlay>,
+        cursor_base: Option<RawFrame>,
+        snapshot_frame: Option<RawFrame>,
+        region_blur: Option<RegionBlur>,
     }
 
     #[derive(Debug, Clone, Copy, Default)]
@@ -50,6 +533,16 @@ mod platform {
         pub amf: bool,
         pub qsv: bool,
         pub software: bool,
+        pub hevc_nvenc: bool,
+        pub hevc_amf: bool,
+        pub hevc_qsv: bool,
+        pub hevc_software: bool,
+        pub av1_nvenc: bool,
+        pub av1_amf: bool,
+        pub av1_qsv: bool,
+        pub av1_software: bool,
+        pub vp9_qsv: bool,
+        pub vp9_software: bool,
     }
 
     // FFmpeg mantiene estado interno no thread-safe; este consumer se usa con exclusión mutua.
@@ -58,44 +551,412 @@ mod platform {
     impl FfmpegEncoderConsumer {
         pub fn new(mut config: EncoderConfig) -> Result<Self, String> {
             config.validate()?;
+            let requested_config = config.clone();
             ffmpeg_the_third::init()
                 .map_err(|err| format!("No se pudo inicializar FFmpeg: {err}"))?;
             set_live_video_encoder_label(None);
+            set_live_encoded_duration_ms(0);
+            let cursor_overlay = config.cursor_overlay.map(CursorOverlay::new);
+            let region_blur = (!config.blur_regions.is_empty())
+                .then(|| RegionBlur::new(config.blur_regions.clone()));
 
-            let final_output_path = config.output_path.clone();
-            let prepared_paths = prepare_output_paths(final_output_path.clone())?;
-            config.output_path = prepared_paths.temp_output_path.clone();
+            let final_output_path = if config.format == OutputFormat::Hls {
+                hls_playlist_path(&config.output_path)
+            } else {
+                config.output_path.clone()
+            };
+            let finished_recording = FinishedRecording {
+                final_path: final_output_path.clone(),
+                started_at: chrono::Local::now(),
+                duration_ms: 0,
+                target_name: config.target_name.clone(),
+                format: config.format.clone(),
+                project: config.project.clone(),
+                metadata: config.metadata.clone(),
+                chapters: Vec::new(),
+                trim: config.trim_range(),
+            };
+
+            if config.stream_output_url().is_some() {
+                let disk_space_threshold_mb = config.disk_space_threshold_mb;
+                return Ok(Self {
+                    config,
+                    ctx: None,
+                    audio_capture: None,
+                    live_audio_capture: None,
+                    gif_export: None,
+                    hls_output: None,
+                    fit_to_size: None,
+                    frame_interpolation: None,
+                    finished_recording,
+                    disk_space_monitor: DiskSpaceMonitor::new(Vec::new(), disk_space_threshold_mb),
+                    file_size_limit_bytes: None,
+                    audio_bytes_per_second: 0,
+                    segment_base: None,
+                    part: 1,
+                    part_offset_ms: 0,
+                    part_end_ms: None,
+                    session_first_timestamp_ms: None,
+                    stop_requested: false,
+                    cursor_overlay,
+                    cursor_base: None,
+                    snapshot_frame: None,
+                    region_blur,
+                });
+            }
 
-            let audio_capture = AudioCaptureService::new(
-                config.audio.clone(),
-                config.format.clone(),
-                config.quality_mode.clone(),
-                config.output_path.clone(),
-                final_output_path,
-                prepared_paths.temp_dir,
+            let capture_output_path = if config.format == OutputFormat::Gif {
+                final_output_path.with_extension(config.format.capture_file_extension())
+            } else {
+                final_output_path.clone()
+            };
+            let prepared_paths = prepare_output_paths(capture_output_path)?;
+            config.output_path = prepared_paths.temp_output_path.clone();
+            let file_size_limit_bytes = check_file_size_limits(
+                &[
+                    prepared_paths.temp_dir.path().to_path_buf(),
+                    final_output_path.clone(),
+                ],
+                &config.fat32_policy,
+                &SystemVolumeInfo,
+            )?
+            .iter()
+            .map(|warning| warning.limit_bytes)
+            .min();
+            let fit_to_size = config
+                .fit_to_size_mb
+                .map(|target_mb| FitToSizeJob::new(final_output_path.clone(), target_mb));
+            let frame_interpolation = config.interpolate_to_fps.map(|target_fps| {
+                FrameInterpolationJob::new(
+                    final_output_path.clone(),
+                    target_fps,
+                    &config.quality_mode,
+                )
+            });
+            let disk_space_monitor = DiskSpaceMonitor::new(
+                vec![
+                    prepared_paths.temp_dir.path().to_path_buf(),
+                    final_output_path.clone(),
+                ],
+                config.disk_space_threshold_mb,
             );
 
+            let (audio_capture, gif_export, hls_output) = if config.format == OutputFormat::Gif {
+                let gif_export = GifExportJob::new(
+                    config.gif.clone(),
+                    config.output_path.clone(),
+                    final_output_path,
+                    prepared_paths.temp_dir,
+                );
+                (None, Some(gif_export), None)
+            } else if config.format == OutputFormat::Hls {
+                let hls_output = HlsOutputJob::new(
+                    config.output_path.clone(),
+                    final_output_path,
+                    prepared_paths.temp_dir,
+                );
+                (None, None, Some(hls_output))
+            } else {
+                let audio_capture = AudioCaptureService::new(
+                    config.audio.clone(),
+                    config.format.clone(),
+                    config.quality_mode.clone(),
+                    config.mp4_faststart,
+                    config.output_path.clone(),
+                    final_output_path,
+                    prepared_paths.temp_dir,
+                );
+                (Some(audio_capture), None, None)
+            };
+            let audio_bytes_per_second = if audio_capture.is_some() {
+                audio_bytes_per_second(&config.audio, &config.format)
+            } else {
+                0
+            };
+
             Ok(Self {
                 config,
                 ctx: None,
-                audio_capture: Some(audio_capture),
+                audio_capture,
+                live_audio_capture: None,
+                gif_export,
+                hls_output,
+                fit_to_size,
+                frame_interpolation,
+                finished_recording,
+                disk_space_monitor,
+                file_size_limit_bytes,
+                audio_bytes_per_second,
+                segment_base: file_size_limit_bytes.is_some().then_some(requested_config),
+                part: 1,
+                part_offset_ms: 0,
+                part_end_ms: None,
+                session_first_timestamp_ms: None,
+                stop_requested: false,
+                cursor_overlay,
+                cursor_base: None,
+                snapshot_frame: None,
+                region_blur,
             })
         }
 
         pub fn on_frame(&mut self, frame: RawFrame) -> Result<(), String> {
-            if !frame.is_valid() {
+            if !frame.is_valid() || self.stop_requested {
+                return Ok(());
+            }
+
+            if self.check_disk_space() {
                 return Ok(());
             }
 
+            self.session_first_timestamp_ms
+                .get_or_insert(frame.timestamp_ms);
+            if self.reached_file_size_limit() {
+                if let Err(err) = self.start_next_part(&frame) {
+                    eprintln!("[encoder] No se pudo seguir en otra parte: {err}");
+                    self.request_stop(StopReason::FileSizeLimit);
+                    return Ok(());
+                }
+            }
+
             if self.ctx.is_none() {
                 self.initialize(&frame)?;
+                if self.ctx.is_none() {
+                    return Ok(());
+                }
+            }
+
+            let encoded = self.encode_frame(frame);
+            self.serve_frame_snapshot();
+            encoded
+        }
+
+        /// También corre en el tick del worker: con la pantalla quieta no
+        /// llegan frames que lo revisen. Devuelve si pidió detener.
+        pub fn check_disk_space(&mut self) -> bool {
+            if self.stop_requested || !self.disk_space_monitor.poll() {
+                return false;
+            }
+            // Se deja de escribir antes de agotar el disco y se pide cerrar
+            // la sesión; el trailer se escribe en `on_stop` como siempre.
+            self.request_stop(StopReason::DiskFull);
+            true
+        }
+
+        pub fn serve_frame_snapshot(&self) {
+            let request = &self.config.frame_snapshot;
+            if request.is_pending() {
+                request.fulfill(self.snapshot_frame.as_ref());
+            }
+        }
+
+        pub fn on_stop(&mut self, stop_at_ms: Option<u64>) -> Result<(), String> {
+            let held = match stop_at_ms {
+                Some(stop_at_ms) => {
+                    self.hold_last_frame_until_stop(stop_at_ms.saturating_sub(self.part_offset_ms))
+                }
+                None => Ok(()),
+            };
+            self.finalize().and(held)
+        }
+
+        pub fn check_stall(&self, now: Instant) -> Result<(), String> {
+            match self.ctx.as_ref() {
+                Some(ctx) if !self.stop_requested => ctx.stall_watchdog.check(now),
+                _ => Ok(()),
+            }
+        }
+
+        pub fn fill_cfr_gap(&mut self, elapsed_ms: u64) -> Result<(), String> {
+            self.fill_cfr_gap_until(elapsed_ms.saturating_sub(self.part_offset_ms))
+        }
+
+        fn fill_cfr_gap_until(&mut self, part_elapsed_ms: u64) -> Result<(), String> {
+            if self.stop_requested {
+                return Ok(());
+            }
+            let slots = match self.ctx.as_mut().and_then(|ctx| ctx.cfr.as_mut()) {
+                Some(pacer) => pacer.fill_until(part_elapsed_ms),
+                None => return Ok(()),
+            };
+            for pts in slots {
+                self.redraw_stale_cursor()?;
+                self.send_cpu_frame(pts as i64, None)?;
+                self.drain_packets()?;
+            }
+            Ok(())
+        }
+
+        /// En VFR la captura sin cursor no manda frames si solo se mueve el
+        /// puntero: se fuerza uno con el último frame y el cursor en su lugar.
+        /// En CFR lo redibuja `fill_cfr_gap` en cada repetición.
+        pub fn redraw_moved_cursor(&mut self, elapsed_ms: u64) -> Result<(), String> {
+            if self.stop_requested || !self.holds_last_cpu_frame() {
+                return Ok(());
+            }
+            let pts = elapsed_ms.saturating_sub(self.part_offset_ms) as i64;
+            if self.ctx.as_ref().is_none_or(|ctx| pts <= ctx.last_pts) {
+                return Ok(());
+            }
+            if !self.redraw_stale_cursor()? {
+                return Ok(());
+            }
+            self.send_cpu_frame(pts, None)?;
+            self.drain_packets()
+        }
+
+        fn redraw_stale_cursor(&mut self) -> Result<bool, String> {
+            if !self
+                .cursor_overlay
+                .as_ref()
+                .is_some_and(CursorOverlay::is_stale)
+            {
+                return Ok(false);
             }
+            let Some(base) = self.cursor_base.as_ref() else {
+                return Ok(false);
+            };
+            let mut frame = RawFrame::new(
+                base.data.clone(),
+                base.width,
+                base.height,
+                base.row_stride_bytes,
+                base.timestamp_ms,
+            )
+            .with_pixel_format(base.pixel_format);
+            self.decorate_cpu_frame(&mut frame);
+            let hdr = self.config.hdr;
+            let ctx = self
+                .ctx
+                .as_mut()
+                .ok_or_else(|| "El encoder no fue inicializado".to_string())?;
+            Self::convert_cpu_frame(ctx, &frame, hdr)?;
+            Ok(true)
+        }
 
-            self.encode_frame(frame)
+        fn holds_last_cpu_frame(&self) -> bool {
+            self.ctx.as_ref().is_some_and(|ctx| {
+                ctx.cfr.is_none() && matches!(ctx.input_pipeline, VideoInputPipeline::Cpu { .. })
+            })
+        }
+
+        fn hold_last_frame_until_stop(&mut self, stop_at_ms: u64) -> Result<(), String> {
+            if self.stop_requested {
+                return Ok(());
+            }
+            if self.ctx.as_ref().is_some_and(|ctx| ctx.cfr.is_some()) {
+                return self.fill_cfr_gap_until(stop_at_ms);
+            }
+            let Some(ctx) = self.ctx.as_ref().filter(|_| self.holds_last_cpu_frame()) else {
+                return Ok(());
+            };
+            let pts = hold_pts_until_stop(ctx.last_pts, stop_at_ms as i64, HOLD_FRAME_MAX_GAP_MS);
+            self.send_held_frames(pts)
         }
 
-        pub fn on_stop(&mut self) -> Result<(), String> {
-            self.finalize()
+        fn hold_last_frame_before(&mut self, timestamp_ms: u64) -> Result<(), String> {
+            let Some(ctx) = self.ctx.as_ref().filter(|_| self.holds_last_cpu_frame()) else {
+                return Ok(());
+            };
+            let Some(first_ts) = ctx.first_timestamp_ms else {
+                return Ok(());
+            };
+            let rel_ts_ms = timestamp_ms.saturating_sub(first_ts) as i64;
+            let pts = hold_pts_before(ctx.last_pts, rel_ts_ms, HOLD_FRAME_MAX_GAP_MS);
+            self.send_held_frames(pts)
+        }
+
+        fn send_held_frames(&mut self, pts: Vec<i64>) -> Result<(), String> {
+            for pts in pts {
+                self.redraw_stale_cursor()?;
+                self.send_cpu_frame(pts, None)?;
+                self.drain_packets()?;
+            }
+            Ok(())
+        }
+
+        fn request_stop(&mut self, reason: StopReason) {
+            self.stop_requested = true;
+            request_session_stop(reason);
+        }
+
+        fn reached_file_size_limit(&self) -> bool {
+            match (self.file_size_limit_bytes, self.ctx.as_ref()) {
+                (Some(limit), Some(ctx)) => reached_file_size_limit(
+                    limit,
+                    ctx.bytes_written,
+                    self.audio_bytes_per_second,
+                    ctx.pts_to_ms(ctx.last_pts),
+                ),
+                _ => false,
+            }
+        }
+
+        /// Cierra el archivo al llegar al límite de FAT32 y sigue la sesión en
+        /// `<nombre>.partN.<ext>`, que arranca con `frame`. Cada parte hace su
+        /// propio mux y entra sola al historial.
+        fn start_next_part(&mut self, frame: &RawFrame) -> Result<(), String> {
+            let base = self
+                .segment_base
+                .take()
+                .ok_or_else(|| "Este destino no permite partir la grabación".to_string())?;
+            let session_first_ts = self
+                .session_first_timestamp_ms
+                .unwrap_or(frame.timestamp_ms);
+            let part_offset_ms = frame.timestamp_ms.saturating_sub(session_first_ts);
+            let part = self.part + 1;
+            let live_audio = self
+                .audio_capture
+                .is_some()
+                .then(get_live_audio_status)
+                .filter(|status| {
+                    status.system_audio_device_name.is_some()
+                        || status.microphone_audio_device_name.is_some()
+                });
+
+            self.part_end_ms = Some(part_offset_ms);
+            // El trailer y las salidas extra (hasta 5 s) se cierran en otro
+            // hilo para no frenar la captura.
+            let closing = self.close_part();
+            let closing_part = self.part;
+            let processing_guard = ProcessingGuard::start();
+            thread::Builder::new()
+                .name("encoder-part-close".to_string())
+                .spawn(move || {
+                    let _processing_guard = processing_guard;
+                    if let Err(err) = closing.finish() {
+                        eprintln!("[encoder] Error cerrando la parte {closing_part}: {err}");
+                    }
+                })
+                .map_err(|err| format!("No se pudo cerrar la parte {closing_part}: {err}"))?;
+
+            let mut config = base.clone();
+            config.output_path = part_output_path(&base.output_path, part);
+            for output in &mut config.outputs {
+                if let OutputSink::File { path } = output {
+                    *path = part_output_path(path, part);
+                }
+            }
+            config.trim_start_ms = None;
+            config.trim_end_ms = None;
+            if let Some(status) = live_audio {
+                config.audio.capture_system_audio = status.capture_system_audio;
+                config.audio.capture_microphone_audio = status.capture_microphone_audio;
+                config.audio.monitor_microphone = status.microphone_monitoring;
+                if let Some(gain_percent) = status.microphone_gain_percent {
+                    config.audio.microphone_gain_percent = gain_percent;
+                }
+            }
+
+            let mut next = Self::new(config)?;
+            base.session_parts.record(part);
+            next.segment_base = Some(base);
+            next.part = part;
+            next.part_offset_ms = part_offset_ms;
+            next.session_first_timestamp_ms = Some(session_first_ts);
+            set_live_encoded_duration_ms(part_offset_ms);
+            *self = next;
+            Ok(())
         }
 
         fn initialize(&mut self, frame: &RawFrame) -> Result<(), String> {
@@ -108,8 +969,11 @@ mod platform {
                 None => (self.config.format.default_codec(), true),
             };
 
-            let (mut out_w, mut out_h) =
-                self.config.resolution.dimensions(frame_width, frame_height);
+            let (mut out_w, mut out_h) = self.config.resolution.output_dimensions(
+                frame_width,
+                frame_height,
+                self.config.never_upscale,
+            );
             if out_w % 2 == 1 {
                 out_w = out_w.saturating_sub(1);
             }
@@ -122,24 +986,82 @@ mod platform {
                 );
             }
 
-            let path_str =
-                self.config.output_path.to_str().ok_or_else(|| {
-                    "La ruta de salida contiene caracteres no válidos".to_string()
-                })?;
-
-            let mut output_ctx =
-                format::output_as(path_str, self.config.format.ffmpeg_format_name()).map_err(
-                    |err| format!("No se pudo crear el archivo de salida '{path_str}': {err}"),
-                )?;
-
-            let needs_global_header = output_ctx.format().flags().contains(Flags::GLOBAL_HEADER);
-            let time_base = Rational::new(1, 1_000);
-            let candidates = encoder_candidates(
-                &codec_kind,
-                allow_fallback,
-                &self.config.video_encoder_preference,
+            let scaling = ScalingPath::resolve(
+                (frame_width, frame_height),
+                (out_w, out_h),
                 gpu_surface_only,
             );
+            let gpu_scaler = if scaling == ScalingPath::Gpu {
+                let texture_ptr = frame.gpu_texture_ptr.unwrap_or(0);
+                match D3d11Scaler::new(texture_ptr, out_w, out_h) {
+                    Ok(d3d11) => Some(GpuTextureScaler {
+                        d3d11,
+                        cpu_flags: ffmpeg_scale_flags(ScalerAlgorithm::resolve(
+                            self.config.scaler,
+                            &self.config.quality_mode,
+                        )),
+                        cpu_fallback: None,
+                    }),
+                    Err(err) => {
+                        if self.config.gpu_frame_route.fall_back_to_cpu() {
+                            eprintln!(
+                                "[encoder] No se pudo escalar en GPU ({err}). Se usa la ruta CPU."
+                            );
+                        }
+                        return Ok(());
+                    }
+                }
+            } else {
+                None
+            };
+
+            let stream_url = self.config.stream_output_url();
+            let (output_arg, container) = match stream_url {
+                Some(url) => (
+                    url.to_string(),
+                    stream_container_name(url, &self.config.format),
+                ),
+                None => (
+                    ffmpeg_path_arg(&self.config.output_path)?,
+                    self.config.format.capture_format_name(),
+                ),
+            };
+            let mut output_ctx = format::output_as(&output_arg, container).map_err(|err| {
+                let shown = stream_url.map_or_else(
+                    || self.config.output_path.display().to_string(),
+                    redact_stream_url,
+                );
+                format!("No se pudo crear el archivo de salida '{shown}': {err}")
+            })?;
+
+            // Las salidas extra copian los parámetros del encoder, así que el
+            // `extradata` tiene que estar si lo pide cualquiera de los contenedores.
+            let main_global_header = output_ctx.format().flags().contains(Flags::GLOBAL_HEADER);
+            let needs_global_header = main_global_header
+                || self.config.outputs.iter().any(|sink| {
+                    sink.muxer_target(&self.config.format)
+                        .is_ok_and(|(_, container)| container_needs_global_header(container))
+                });
+            let force_cfr = self.config.frame_rate_mode == FrameRateMode::Cfr && !gpu_surface_only;
+            let time_base = if force_cfr {
+                Rational::new(1, self.config.fps as i32)
+            } else {
+                Rational::new(1, 1_000)
+            };
+            let hdr_metadata = self
+                .config
+                .hdr
+                .then(|| self.config.hdr_metadata.unwrap_or_default());
+            let candidates = if self.config.bit_depth() == BitDepth::Ten {
+                ten_bit_encoder_candidates(&self.config.video_encoder_preference)?
+            } else {
+                encoder_candidates(
+                    &codec_kind,
+                    allow_fallback,
+                    &self.config.video_encoder_preference,
+                    gpu_surface_only,
+                )
+            };
             if candidates.is_empty() {
                 return Err(format!(
                     "No hay encoders compatibles para el modo de entrada {} con codec {:?}",
@@ -151,69 +1073,87 @@ mod platform {
             let mut selected_encoder_name: Option<&'static str> = None;
             let mut selected_codec = None;
             let mut selected_video_enc: Option<encoder::Video> = None;
+            let mut selected_output_pixel = Pixel::YUV420P;
             let mut open_failures = Vec::<String>::new();
 
-            for name in &candidates {
+            'candidates: for name in &candidates {
                 let Some(candidate_codec) = encoder::find_by_name(name) else {
                     continue;
                 };
 
-                let (encoder_opts, has_custom_opts) =
-                    self.build_encoder_options(name, &codec_kind, out_w, out_h);
-
-                let mut open_attempt =
-                    |opts: Dictionary| -> Result<encoder::Video, ffmpeg_the_third::Error> {
-                        let mut candidate_enc =
-                            codec::context::Context::new_with_codec(candidate_codec)
-                                .encoder()
-                                .video()
-                                .map_err(|err| {
-                                    open_failures
-                                        .push(format!("{name}: no se pudo crear contexto ({err})"));
-                                    err
-                                })?;
-
-                        candidate_enc.set_width(out_w);
-                        candidate_enc.set_height(out_h);
-                        candidate_enc.set_format(if gpu_surface_only {
-                            Pixel::D3D11
-                        } else {
-                            Pixel::YUV420P
-                        });
-                        candidate_enc.set_time_base(time_base);
-                        candidate_enc
-                            .set_frame_rate(Some(Rational::new(self.config.fps as i32, 1)));
+                let output_pixels = if gpu_surface_only {
+                    vec![Pixel::D3D11]
+                } else {
+                    self.cpu_output_pixels(name)
+                };
+                for output_pixel in output_pixels {
+                    let (encoder_opts, has_custom_opts) =
+                        self.build_encoder_options(name, &codec_kind, out_w, out_h, output_pixel);
+
+                    let mut open_attempt =
+                        |opts: Dictionary| -> Result<encoder::Video, ffmpeg_the_third::Error> {
+                            let mut candidate_enc =
+                                codec::context::Context::new_with_codec(candidate_codec)
+                                    .encoder()
+                                    .video()
+                                    .map_err(|err| {
+                                        open_failures.push(format!(
+                                            "{name}: no se pudo crear contexto ({err})"
+                                        ));
+                                        err
+                                    })?;
+
+                            candidate_enc.set_width(out_w);
+                            candidate_enc.set_height(out_h);
+                            candidate_enc.set_format(output_pixel);
+                            candidate_enc.set_time_base(time_base);
+                            candidate_enc
+                                .set_frame_rate(Some(Rational::new(self.config.fps as i32, 1)));
+                            set_color_metadata(&mut candidate_enc, &self.config.color_tags());
+                            if let Some(metadata) = hdr_metadata {
+                                set_encoder_hdr_metadata(&mut candidate_enc, &metadata).map_err(
+                                    |err| {
+                                        open_failures.push(format!("{name}: {err}"));
+                                        ffmpeg_the_third::Error::Bug
+                                    },
+                                )?;
+                            }
 
-                        if needs_global_header {
-                            candidate_enc.set_flags(codec::Flags::GLOBAL_HEADER);
-                        }
+                            if needs_global_header {
+                                candidate_enc.set_flags(codec::Flags::GLOBAL_HEADER);
+                            }
 
-                        candidate_enc.open_with(opts)
-                    };
+                            candidate_enc.open_with(opts)
+                        };
 
-                match open_attempt(encoder_opts) {
-                    Ok(opened) => {
+                    let opened = match open_attempt(encoder_opts) {
+                        Ok(opened) => Some(opened),
+                        Err(err) => {
+                            if has_custom_opts {
+                                match open_attempt(Dictionary::new()) {
+                                    Ok(opened) => Some(opened),
+                                    Err(fallback_err) => {
+                                        open_failures.push(format!(
+                                            "{name}: {err} | fallback sin opciones: {fallback_err}"
+                                        ));
+                                        None
+                                    }
+                                }
+                            } else {
+                                open_failures.push(format!("{name}: {err}"));
+                                None
+                            }
+                        }
+                    };
+                    if let Some(opened) = opened {
                         selected_encoder_name = Some(*name);
                         selected_codec = Some(candidate_codec);
                         selected_video_enc = Some(opened);
-                        break;
+                        selected_output_pixel = output_pixel;
+                        break 'candidates;
                     }
-                    Err(err) => {
-                        if has_custom_opts {
-                            match open_attempt(Dictionary::new()) {
-                                Ok(opened) => {
-                                    selected_encoder_name = Some(*name);
-                                    selected_codec = Some(candidate_codec);
-                                    selected_video_enc = Some(opened);
-                                    break;
-                                }
-                                Err(fallback_err) => open_failures.push(format!(
-                                    "{name}: {err} | fallback sin opciones: {fallback_err}"
-                                )),
-                            }
-                        } else {
-                            open_failures.push(format!("{name}: {err}"));
-                        }
+                    if output_pixel == Pixel::YUV444P {
+                        eprintln!("[encoder] {name} no aceptó croma 4:4:4; se prueba 4:2:0");
                     }
                 }
             }
@@ -224,6 +1164,13 @@ mod platform {
                 } else {
                     format!(" Detalles: {}", open_failures.join(" | "))
                 };
+                if self.config.bit_depth() == BitDepth::Ten {
+                    return format!(
+                        "Ningún encoder pudo abrir HEVC de 10 bits (main10). Probados: {}.{}",
+                        candidates.join(", "),
+                        details
+                    );
+                }
 
                 format!(
                     "No se pudo abrir un encoder compatible para {}. Probados: {}.{}",
@@ -242,9 +1189,25 @@ mod platform {
                         .to_string(),
                 );
             }
+            if gpu_surface_only {
+                Self::ensure_texture_on_encoder_adapter(frame, backend_label)?;
+            }
+
+            self.disk_space_monitor
+                .set_bitrate_kbps(estimate_target_bitrate_kbps(
+                    out_w,
+                    out_h,
+                    self.config.fps,
+                    &codec_kind,
+                    &self.config.quality_mode,
+                ));
 
             let live_codec_label = selected_codec_label(&codec_kind);
-            set_live_video_encoder_label(Some(format!("{backend_label} / {live_codec_label}")));
+            set_live_video_encoder_label(Some(live_encoder_label(
+                backend_label,
+                live_codec_label,
+                scaling,
+            )));
 
             let mut stream = output_ctx
                 .add_stream(found_codec)
@@ -255,32 +1218,69 @@ mod platform {
             stream.set_time_base(time_base);
             stream.set_rate(Rational::new(self.config.fps as i32, 1));
             stream.set_avg_frame_rate(Rational::new(self.config.fps as i32, 1));
+            if let Some(metadata) = hdr_metadata {
+                set_stream_hdr_metadata(&mut stream, &metadata)?;
+            }
 
-            output_ctx
-                .write_header()
+            let live_audio =
+                if stream_url.is_some_and(is_rtmp_url) && self.config.audio.is_enabled() {
+                    let bitrate_kbps = self.config.audio.audio_bitrate_kbps.unwrap_or_else(|| {
+                        AudioCodec::Aac.default_bitrate_kbps().unwrap_or_default()
+                    });
+                    Some(LiveAudioEncoder::open(
+                        &mut output_ctx,
+                        Arc::new(LiveAudioTap::new()),
+                        bitrate_kbps,
+                        main_global_header,
+                    )?)
+                } else {
+                    None
+                };
+
+            let mut metadata = Dictionary::new();
+            for (key, value) in self.finished_recording.container_tags() {
+                metadata.set(key, &value);
+            }
+            output_ctx.set_metadata(metadata);
+
+            let header_result = if stream_url.is_some_and(|url| !is_rtmp_url(url)) {
+                let mut muxer_opts = Dictionary::new();
+                for (key, value) in stream_muxer_options(&self.config.format) {
+                    muxer_opts.set(key, value);
+                }
+                output_ctx.write_header_with(muxer_opts).map(|_| ())
+            } else if self.config.format == OutputFormat::Hls {
+                let mut muxer_opts = Dictionary::new();
+                for (key, value) in hls_muxer_options(
+                    &self.config.output_path,
+                    &codec_kind,
+                    self.config.hls_segment_seconds,
+                )? {
+                    muxer_opts.set(key, &value);
+                }
+                output_ctx.write_header_with(muxer_opts).map(|_| ())
+            } else {
+                output_ctx.write_header()
+            };
+            header_result
                 .map_err(|err| format!("No se pudo escribir cabecera del contenedor: {err}"))?;
 
             let input_pipeline = if gpu_surface_only {
-                VideoInputPipeline::GpuTextureD3d11
+                VideoInputPipeline::GpuTextureD3d11 { scaler: gpu_scaler }
             } else {
-                let scale_flags = match self.config.quality_mode {
-                    QualityMode::Performance => ScaleFlags::FAST_BILINEAR,
-                    QualityMode::Balanced => ScaleFlags::BILINEAR,
-                    QualityMode::Quality => ScaleFlags::BICUBIC,
-                };
-
-                let scaler = scaling::Context::get(
-                    Pixel::BGRA,
+                let (scaler, src_frame) = self.build_cpu_scaler(
                     frame_width,
                     frame_height,
-                    Pixel::YUV420P,
                     out_w,
                     out_h,
-                    scale_flags,
-                )
-                .map_err(|err| format!("No se pudo crear el escalador de color: {err}"))?;
-                let src_frame = frame::Video::new(Pixel::BGRA, frame_width, frame_height);
-                let dst_frame = frame::Video::new(Pixel::YUV420P, out_w, out_h);
+                    selected_output_pixel,
+                )?;
+                let mut dst_frame = frame::Video::new(selected_output_pixel, out_w, out_h);
+                let color = FfmpegColorTags::from(&self.config.color_tags());
+                dst_frame.set_color_range(color.range);
+                dst_frame.set_color_primaries(color.primaries);
+                dst_frame.set_color_transfer_characteristic(color.transfer);
+                dst_frame.set_color_space(color.space);
 
                 VideoInputPipeline::Cpu {
                     scaler,
@@ -289,6 +1289,34 @@ mod platform {
                 }
             };
 
+            // Recién con la cabecera principal escrita: un error anterior no deja
+            // archivos extra a medias. Una salida que falla (p. ej. el servidor
+            // RTMP no responde) no impide grabar el archivo principal.
+            let mirrors = self
+                .config
+                .outputs
+                .iter()
+                .enumerate()
+                .filter_map(|(index, sink)| {
+                    MirrorOutput::spawn(
+                        index,
+                        sink,
+                        &self.config.format,
+                        &video_enc,
+                        time_base,
+                        self.config.fps,
+                    )
+                    .map_err(|message| {
+                        notify_output_sink_failed(OutputSinkFailed {
+                            index,
+                            target: sink.label(),
+                            message,
+                        })
+                    })
+                    .ok()
+                })
+                .collect();
+
             self.ctx = Some(EncoderContext {
                 output_ctx,
                 video_enc,
@@ -297,12 +1325,27 @@ mod platform {
                 time_base,
                 first_timestamp_ms: None,
                 last_pts: -1,
+                cfr: force_cfr.then(|| CfrPacer::new(self.config.fps)),
+                pending_frame_times: PendingFrameTimes::new(MAX_PENDING_FRAME_TIMES),
+                bytes_written: 0,
+                live_audio,
+                mirrors,
+                stall_watchdog: EncoderStallWatchdog::default(),
             });
 
-            self.audio_capture
-                .as_mut()
-                .ok_or_else(|| "AudioCaptureService no disponible".to_string())?
-                .start()?;
+            let live_tap = self
+                .ctx
+                .as_ref()
+                .and_then(|ctx| ctx.live_audio.as_ref())
+                .map(|live_audio| Arc::clone(&live_audio.tap));
+            if let Some(tap) = live_tap {
+                self.live_audio_capture =
+                    Some(LiveAudioCapture::start(self.config.audio.clone(), tap)?);
+            } else if let Some(audio_capture) = self.audio_capture.as_mut() {
+                audio_capture.start()?;
+            } else if self.config.uses_audio_mux() {
+                return Err("AudioCaptureService no disponible".to_string());
+            }
 
             Ok(())
         }
@@ -313,10 +1356,12 @@ mod platform {
             codec: &VideoCodec,
             out_w: u32,
             out_h: u32,
+            output_pixel: Pixel,
         ) -> (Dictionary<'_>, bool) {
             let mut options = Dictionary::new();
             let mut has_options = false;
-            let gop = recommended_gop_frames(self.config.fps);
+            let live_stream = self.config.is_live_streaming();
+            let gop = self.config.keyframe_interval();
             let target_kbps = estimate_target_bitrate_kbps(
                 out_w,
                 out_h,
@@ -354,7 +1399,8 @@ mod platform {
                             QualityMode::Balanced => "ll",
                             QualityMode::Quality => "hq",
                         };
-                        let use_cbr = matches!(self.config.quality_mode, QualityMode::Performance);
+                        let use_cbr = live_stream
+                            || matches!(self.config.quality_mode, QualityMode::Performance);
 
                         options.set("preset", preset);
                         options.set("rc", if use_cbr { "cbr" } else { "vbr" });
@@ -380,9 +1426,12 @@ mod platform {
                     }
 
                     if encoder_name.starts_with("libx26") {
-                        options.set("crf", &self.config.crf.to_string());
+                        if !live_stream {
+                            options.set("crf", &self.config.crf.to_string());
+                        }
                         options.set("preset", self.config.preset.as_str());
                         options.set("tune", "zerolatency");
+                        options.set("g", &gop.to_string());
                         has_options = true;
                     }
 
@@ -417,8 +1466,10 @@ mod platform {
                         options.set("g", &gop.to_string());
                         has_options = true;
                     } else if encoder_name.contains("_qsv") {
-                        let qsv_quality = self.config.crf.min(40);
-                        options.set("global_quality", &qsv_quality.to_string());
+                        if !live_stream {
+                            let qsv_quality = self.config.crf.min(40);
+                            options.set("global_quality", &qsv_quality.to_string());
+                        }
                         options.set("bf", "0");
                         options.set("async_depth", "1");
                         options.set("g", &gop.to_string());
@@ -431,21 +1482,246 @@ mod platform {
                         options.set("b", "0");
                         options.set("deadline", "realtime");
                         options.set("cpu-used", "8");
+                        options.set("g", &gop.to_string());
                         has_options = true;
                     }
                 }
             }
 
+            if self.config.bit_depth() == BitDepth::Ten {
+                options.set("profile", "main10");
+                has_options = true;
+            } else if output_pixel == Pixel::YUV444P {
+                if let Some(profile) = yuv444_profile(encoder_name) {
+                    options.set("profile", profile);
+                    has_options = true;
+                }
+            }
+
+            for (key, value) in profile_level_options(
+                encoder_name,
+                codec,
+                self.config.profile.as_deref(),
+                self.config.level.as_deref(),
+            ) {
+                options.set(key, &value);
+                has_options = true;
+            }
+
+            if live_stream {
+                for (key, value) in live_stream_encoder_options(encoder_name, target_kbps) {
+                    options.set(key, &value);
+                }
+                has_options = true;
+            }
+
             (options, has_options)
         }
 
-        fn encode_frame(&mut self, frame: RawFrame) -> Result<(), String> {
+        fn build_cpu_scaler(
+            &self,
+            src_w: u32,
+            src_h: u32,
+            out_w: u32,
+            out_h: u32,
+            output_pixel: Pixel,
+        ) -> Result<(scaling::Context, frame::Video), String> {
+            let scale_flags = ffmpeg_scale_flags(ScalerAlgorithm::resolve(
+                self.config.scaler,
+                &self.config.quality_mode,
+            ));
+
+            let src_pixel = if self.config.hdr {
+                Pixel::P010LE
+            } else {
+                Pixel::BGRA
+            };
+            let mut scaler = scaling::Context::get(
+                src_pixel,
+                src_w,
+                src_h,
+                output_pixel,
+                out_w,
+                out_h,
+                scale_flags,
+            )
+            .map_err(|err| format!("No se pudo crear el escalador de color: {err}"))?;
+            if !self.config.hdr {
+                configure_scaler_colorspace(&mut scaler, &self.config.color_range)?;
+            }
+            Ok((scaler, frame::Video::new(src_pixel, src_w, src_h)))
+        }
+
+        fn cpu_output_pixels(&self, encoder_name: &str) -> Vec<Pixel> {
+            if self.config.bit_depth() == BitDepth::Ten {
+                // libx265 no acepta P010; el escalador lo pasa a planar.
+                return if encoder_name == "libx265" {
+                    vec![Pixel::YUV420P10LE]
+                } else {
+                    vec![Pixel::P010LE]
+                };
+            }
+            if self.config.chroma_subsampling() == ChromaSubsampling::Yuv444
+                && supports_yuv444(encoder_name)
+            {
+                return vec![Pixel::YUV444P, Pixel::YUV420P];
+            }
+            vec![Pixel::YUV420P]
+        }
+
+        fn adapt_cpu_scaler_to_frame(&mut self, frame: &RawFrame) -> Result<(), String> {
+            let Some(VideoInputPipeline::Cpu {
+                src_frame,
+                dst_frame,
+                ..
+            }) = self.ctx.as_ref().map(|ctx| &ctx.input_pipeline)
+            else {
+                return Ok(());
+            };
+            if src_frame.width() == frame.width && src_frame.height() == frame.height {
+                return Ok(());
+            }
+
+            let (out_w, out_h) = (dst_frame.width(), dst_frame.height());
+            let output_pixel = dst_frame.format();
+            let (next_scaler, next_src_frame) =
+                self.build_cpu_scaler(frame.width, frame.height, out_w, out_h, output_pixel)?;
+            if let Some(VideoInputPipeline::Cpu {
+                scaler, src_frame, ..
+            }) = self.ctx.as_mut().map(|ctx| &mut ctx.input_pipeline)
+            {
+                *scaler = next_scaler;
+                *src_frame = next_src_frame;
+            }
+            Ok(())
+        }
+
+        fn cpu_output_size(&self) -> Option<(u32, u32)> {
+            match self.ctx.as_ref().map(|ctx| &ctx.input_pipeline) {
+                Some(VideoInputPipeline::Cpu { dst_frame, .. }) => {
+                    Some((dst_frame.width(), dst_frame.height()))
+                }
+                _ => None,
+            }
+        }
+
+        fn store_cursor_base(&mut self, frame: &RawFrame) {
+            let base = self
+                .cursor_base
+                .get_or_insert_with(|| RawFrame::new(Vec::new(), 0, 0, 0, frame.timestamp_ms));
+            base.data.clear();
+            base.data.extend_from_slice(&frame.data);
+            base.width = frame.width;
+            base.height = frame.height;
+            base.row_stride_bytes = frame.row_stride_bytes;
+            base.pixel_format = frame.pixel_format;
+            base.timestamp_ms = frame.timestamp_ms;
+        }
+
+        fn decorate_cpu_frame(&mut self, frame: &mut RawFrame) {
+            if let Some(cursor_overlay) = self.cursor_overlay.as_mut() {
+                cursor_overlay.draw(frame);
+            }
+            if let (Some(region_blur), Some(output)) =
+                (self.region_blur.as_mut(), self.cpu_output_size())
+            {
+                region_blur.apply(frame, output);
+            }
+        }
+
+        fn encode_frame(&mut self, mut frame: RawFrame) -> Result<(), String> {
+            self.snapshot_frame = None;
+            if frame.has_cpu_data() {
+                self.hold_last_frame_before(frame.timestamp_ms)?;
+                if self.cursor_overlay.is_some() {
+                    self.store_cursor_base(&frame);
+                }
+                self.decorate_cpu_frame(&mut frame);
+                self.adapt_cpu_scaler_to_frame(&frame)?;
+            }
+
+            let hdr = self.config.hdr;
             let ctx = self
                 .ctx
                 .as_mut()
                 .ok_or_else(|| "El encoder no fue inicializado".to_string())?;
 
+            if matches!(
+                ctx.input_pipeline,
+                VideoInputPipeline::GpuTextureD3d11 { .. }
+            ) {
+                Self::encode_gpu_texture_frame(ctx, frame, self.part_offset_ms)?;
+                return self.drain_packets();
+            }
+            Self::convert_cpu_frame(ctx, &frame, hdr)?;
+
+            let first_ts = *ctx.first_timestamp_ms.get_or_insert(frame.timestamp_ms);
+            let rel_ts_ms = frame.timestamp_ms.saturating_sub(first_ts);
+            let pts_range: Range<i64> = match ctx.cfr.as_mut() {
+                Some(pacer) => {
+                    let slots = pacer.slots_for(rel_ts_ms);
+                    slots.start as i64..slots.end as i64
+                }
+                None => {
+                    let rel_ts_ms = rel_ts_ms as i64;
+                    let pts = if rel_ts_ms <= ctx.last_pts {
+                        ctx.last_pts + 1
+                    } else {
+                        rel_ts_ms
+                    };
+                    pts..pts + 1
+                }
+            };
+
+            for pts in pts_range {
+                self.send_cpu_frame(pts, Some(frame.captured_at))?;
+                self.drain_packets()?;
+            }
+            self.snapshot_frame = Some(frame);
+            Ok(())
+        }
+
+        fn convert_cpu_frame(
+            ctx: &mut EncoderContext,
+            frame: &RawFrame,
+            hdr: bool,
+        ) -> Result<(), String> {
             match &mut ctx.input_pipeline {
+                VideoInputPipeline::Cpu {
+                    scaler,
+                    src_frame,
+                    dst_frame,
+                } if hdr => {
+                    if !frame.has_cpu_data() {
+                        return Err("Frame inválido para pipeline CPU (HDR)".to_string());
+                    }
+                    let height = src_frame.height() as usize;
+                    // SAFETY: luma y croma son buffers distintos que FFmpeg
+                    // reservó para el frame P010, de `linesize` por fila; se
+                    // arman desde el AVFrame para no tomar dos `&mut` del frame.
+                    let planes = unsafe {
+                        let raw = src_frame.as_mut_ptr();
+                        let luma_stride = (*raw).linesize[0] as usize;
+                        let chroma_stride = (*raw).linesize[1] as usize;
+                        P010Planes {
+                            luma: std::slice::from_raw_parts_mut(
+                                (*raw).data[0],
+                                luma_stride * height,
+                            ),
+                            luma_stride,
+                            chroma: std::slice::from_raw_parts_mut(
+                                (*raw).data[1],
+                                chroma_stride * height.div_ceil(2),
+                            ),
+                            chroma_stride,
+                        }
+                    };
+                    HdrConverter::shared().convert(frame, planes)?;
+
+                    scaler
+                        .run(src_frame, dst_frame)
+                        .map_err(|err| format!("Error escalando el frame HDR: {err}"))?;
+                }
                 VideoInputPipeline::Cpu {
                     scaler,
                     src_frame,
@@ -492,41 +1768,79 @@ mod platform {
                     scaler
                         .run(src_frame, dst_frame)
                         .map_err(|err| format!("Error en conversión de color: {err}"))?;
-
-                    let first_ts = *ctx.first_timestamp_ms.get_or_insert(frame.timestamp_ms);
-                    let rel_ts_ms = frame.timestamp_ms.saturating_sub(first_ts) as i64;
-                    let pts = if rel_ts_ms <= ctx.last_pts {
-                        ctx.last_pts + 1
-                    } else {
-                        rel_ts_ms
-                    };
-                    dst_frame.set_pts(Some(pts));
-                    ctx.last_pts = pts;
-
-                    ctx.video_enc
-                        .send_frame(dst_frame)
-                        .map_err(|err| format!("Error enviando frame al encoder: {err}"))?;
                 }
-                VideoInputPipeline::GpuTextureD3d11 => {
-                    Self::encode_gpu_texture_frame(ctx, frame)?;
+                VideoInputPipeline::GpuTextureD3d11 { .. } => {
+                    return Err("El pipeline de video no es CPU".to_string());
                 }
             }
+            Ok(())
+        }
+
+        fn send_cpu_frame(&mut self, pts: i64, captured_at: Option<Instant>) -> Result<(), String> {
+            let ctx = self
+                .ctx
+                .as_mut()
+                .ok_or_else(|| "El encoder no fue inicializado".to_string())?;
+            let duration_ms = ctx.pts_to_ms(pts);
+            let VideoInputPipeline::Cpu { dst_frame, .. } = &mut ctx.input_pipeline else {
+                return Err("El pipeline de video no es CPU".to_string());
+            };
+
+            dst_frame.set_pts(Some(pts));
+            ctx.last_pts = pts;
+            set_live_encoded_duration_ms(self.part_offset_ms + duration_ms);
+            if let Some(captured_at) = captured_at {
+                ctx.pending_frame_times.insert(pts, captured_at);
+            }
 
-            self.drain_packets()
+            ctx.video_enc
+                .send_frame(dst_frame)
+                .map_err(|err| format!("Error enviando frame al encoder: {err}"))?;
+            ctx.stall_watchdog.on_frame_sent(Instant::now());
+            Ok(())
+        }
+
+        /// Sin esta verificación, una textura de otro adaptador falla dentro de
+        /// FFmpeg con un error genérico.
+        fn ensure_texture_on_encoder_adapter(
+            frame: &RawFrame,
+            backend_label: &str,
+        ) -> Result<(), String> {
+            let Some(texture_ptr) = frame.gpu_texture_ptr else {
+                return Ok(());
+            };
+            let Ok(capture_adapter) = texture_adapter(texture_ptr) else {
+                return Ok(());
+            };
+
+            let adapters = enumerate_adapters();
+            match encoder_adapter_for_backend(&adapters, backend_label) {
+                Some(encoder_adapter) => {
+                    ensure_same_adapter(&capture_adapter, encoder_adapter, backend_label)
+                }
+                None => Ok(()),
+            }
         }
 
         fn encode_gpu_texture_frame(
             ctx: &mut EncoderContext,
             mut frame: RawFrame,
+            part_offset_ms: u64,
         ) -> Result<(), String> {
-            let texture_ptr = frame
+            let mut texture_ptr = frame
                 .take_gpu_texture_ptr()
                 .ok_or_else(|| "Frame GPU recibido sin textura D3D11".to_string())?;
+            if let VideoInputPipeline::GpuTextureD3d11 {
+                scaler: Some(scaler),
+            } = &mut ctx.input_pipeline
+            {
+                texture_ptr = scaler.scale(texture_ptr)?;
+            }
 
             let mut hw_frame = frame::Video::empty();
             hw_frame.set_format(Pixel::D3D11);
-            hw_frame.set_width(frame.width);
-            hw_frame.set_height(frame.height);
+            hw_frame.set_width(ctx.video_enc.width());
+            hw_frame.set_height(ctx.video_enc.height());
 
             let first_ts = *ctx.first_timestamp_ms.get_or_insert(frame.timestamp_ms);
             let rel_ts_ms = frame.timestamp_ms.saturating_sub(first_ts) as i64;
@@ -537,6 +1851,8 @@ mod platform {
             };
             hw_frame.set_pts(Some(pts));
             ctx.last_pts = pts;
+            set_live_encoded_duration_ms(part_offset_ms + pts as u64);
+            ctx.pending_frame_times.insert(pts, frame.captured_at);
 
             unsafe {
                 let av_frame = hw_frame.as_mut_ptr();
@@ -566,6 +1882,7 @@ mod platform {
             ctx.video_enc
                 .send_frame(&hw_frame)
                 .map_err(|err| format!("Error enviando frame GPU al encoder: {err}"))?;
+            ctx.stall_watchdog.on_frame_sent(Instant::now());
 
             Ok(())
         }
@@ -578,7 +1895,18 @@ mod platform {
 
             let mut encoded_packet = packet::Packet::empty();
             while ctx.video_enc.receive_packet(&mut encoded_packet).is_ok() {
+                ctx.stall_watchdog.on_packet();
                 encoded_packet.set_stream(ctx.stream_idx);
+                // El PTS del packet sigue en la base de tiempo del encoder hasta el rescale.
+                let captured_at = encoded_packet
+                    .pts()
+                    .and_then(|pts| ctx.pending_frame_times.take(pts, encoded_packet.dts()));
+                // `write_interleaved` se queda con los datos: la copia va antes.
+                let mirror_packet = ctx
+                    .mirrors
+                    .iter()
+                    .any(MirrorOutput::is_open)
+                    .then(|| encoded_packet.clone());
 
                 let stream = ctx.output_ctx.stream(ctx.stream_idx).ok_or_else(|| {
                     format!(
@@ -587,17 +1915,61 @@ mod platform {
                     )
                 })?;
                 encoded_packet.rescale_ts(ctx.time_base, stream.time_base());
+                ctx.bytes_written = ctx
+                    .bytes_written
+                    .saturating_add(encoded_packet.size() as u64);
 
                 encoded_packet
                     .write_interleaved(&mut ctx.output_ctx)
                     .map_err(|err| format!("Error escribiendo packet en contenedor: {err}"))?;
+
+                if let Some(mirror_packet) = mirror_packet {
+                    for mirror in &mut ctx.mirrors {
+                        mirror.write(&mirror_packet);
+                    }
+                }
+
+                if let Some(captured_at) = captured_at {
+                    record_pipeline_latency_ms(captured_at.elapsed().as_millis() as u64);
+                }
             }
 
+            self.pump_live_audio()
+        }
+
+        pub fn pump_live_audio(&mut self) -> Result<(), String> {
+            let Some(ctx) = self.ctx.as_mut() else {
+                return Ok(());
+            };
+            if let Some(live_audio) = ctx.live_audio.as_mut() {
+                let audio_bytes = live_audio.pump(&mut ctx.output_ctx)?;
+                ctx.bytes_written = ctx.bytes_written.saturating_add(audio_bytes);
+            }
             Ok(())
         }
 
+        fn finish_live_audio(&mut self) -> Result<(), String> {
+            let Some(ctx) = self.ctx.as_mut() else {
+                return Ok(());
+            };
+            match ctx.live_audio.as_mut() {
+                Some(live_audio) => live_audio.finish(&mut ctx.output_ctx),
+                None => Ok(()),
+            }
+        }
+
         fn finalize(&mut self) -> Result<(), String> {
+            self.close_part().finish()
+        }
+
+        /// Vacía el encoder y corta la captura de audio en este hilo; lo que
+        /// sigue (trailer, salidas extra y postproceso) queda en `ClosingPart`.
+        fn close_part(&mut self) -> ClosingPart {
             let mut video_error: Option<String> = None;
+            let live_audio_error = self
+                .live_audio_capture
+                .take()
+                .and_then(|capture| capture.stop().err());
 
             if self.ctx.is_some() {
                 let send_eof_result = self
@@ -611,115 +1983,285 @@ mod platform {
                     video_error = Some(format!("Error enviando EOF al encoder: {err}"));
                 } else if let Err(err) = self.drain_packets() {
                     video_error = Some(err);
-                } else if let Err(err) = self
-                    .ctx
-                    .as_mut()
-                    .expect("contexto de encoder ausente")
-                    .output_ctx
-                    .write_trailer()
-                {
-                    video_error = Some(format!(
-                        "Error escribiendo trailer del contenedor: {err}. El archivo puede quedar corrupto."
-                    ));
+                } else if let Err(err) = self.finish_live_audio() {
+                    video_error = Some(err);
+                }
+            }
+
+            let mut finished = self.finished_recording.clone();
+            finished.duration_ms = self
+                .ctx
+                .as_ref()
+                .map(|ctx| ctx.pts_to_ms(ctx.last_pts))
+                .unwrap_or(0);
+            finished.chapters = markers_for_part(
+                &self.config.chapter_markers.snapshot(),
+                self.part_offset_ms,
+                self.part_end_ms,
+            );
+            let output = self.ctx.take().map(|ctx| (ctx.output_ctx, ctx.mirrors));
+            set_live_video_encoder_label(None);
+
+            ClosingPart {
+                output,
+                video_error,
+                live_audio_error,
+                finished,
+                audio_capture: self.audio_capture.take().map(AudioCaptureService::stop),
+                fit_to_size: self.fit_to_size.take(),
+                frame_interpolation: self.frame_interpolation.take(),
+                hls_output: self.hls_output.take(),
+                gif_export: self.gif_export.take(),
+            }
+        }
+    }
+
+    /// Lo que queda de una parte con el encoder ya vacío. No toca el pipeline
+    /// de video ni la captura, así que al partir la grabación termina en otro
+    /// hilo mientras la parte siguiente ya graba.
+    struct ClosingPart {
+        output: Option<(format::context::Output, Vec<MirrorOutput>)>,
+        video_error: Option<String>,
+        live_audio_error: Option<String>,
+        finished: FinishedRecording,
+        audio_capture: Option<StoppedAudioCapture>,
+        fit_to_size: Option<FitToSizeJob>,
+        frame_interpolation: Option<FrameInterpolationJob>,
+        hls_output: Option<HlsOutputJob>,
+        gif_export: Option<GifExportJob>,
+    }
+
+    impl ClosingPart {
+        fn finish(self) -> Result<(), String> {
+            let mut video_error = self.video_error;
+            if let Some((mut output_ctx, mut mirrors)) = self.output {
+                if video_error.is_none() {
+                    if let Err(err) = output_ctx.write_trailer() {
+                        video_error = Some(format!(
+                            "Error escribiendo trailer del contenedor: {err}. El archivo puede quedar corrupto."
+                        ));
+                    }
+                }
+                for mirror in &mut mirrors {
+                    mirror.close();
+                }
+                let deadline = Instant::now() + MIRROR_CLOSE_TIMEOUT;
+                for mirror in &mut mirrors {
+                    mirror.join(deadline);
                 }
             }
 
-            self.ctx = None;
+            let finished = self.finished;
+            if let Some(audio_capture) = self.audio_capture {
+                let fit_to_size = self.fit_to_size;
+                let frame_interpolation = self.frame_interpolation;
+                let finished = finished.clone();
+                thread::spawn(move || {
+                    audio_capture.mux(finished, move || {
+                        if let Some(job) = fit_to_size {
+                            job.run_logged();
+                        }
+                        if let Some(job) = frame_interpolation {
+                            job.run_logged();
+                        }
+                    })
+                });
+            }
 
-            if let Some(audio_capture) = self.audio_capture.take() {
-                audio_capture.finalize_and_mux_detached();
+            if let Some(hls_output) = self.hls_output {
+                hls_output.finish_detached(finished.clone());
             }
 
-            set_live_video_encoder_label(None);
+            if let Some(gif_export) = self.gif_export {
+                gif_export.run_detached(finished);
+            }
 
-            if let Some(err) = video_error {
-                return Err(err);
+            match video_error.or(self.live_audio_error) {
+                Some(err) => Err(err),
+                None => Ok(()),
             }
+        }
+    }
 
-            Ok(())
+    fn container_needs_global_header(container: &str) -> bool {
+        let Ok(name) = CString::new(container) else {
+            return false;
+        };
+        // SAFETY: av_guess_format solo lee el nombre y devuelve un formato
+        // estático de FFmpeg o null.
+        unsafe {
+            let output_format = ffi::av_guess_format(name.as_ptr(), ptr::null(), ptr::null());
+            !output_format.is_null() && (*output_format).flags & Flags::GLOBAL_HEADER.bits() != 0
+        }
+    }
+
+    fn ffmpeg_scale_flags(algorithm: ScalerAlgorithm) -> ScaleFlags {
+        match algorithm {
+            ScalerAlgorithm::FastBilinear => ScaleFlags::FAST_BILINEAR,
+            ScalerAlgorithm::Bilinear => ScaleFlags::BILINEAR,
+            ScalerAlgorithm::Bicubic => ScaleFlags::BICUBIC,
+            ScalerAlgorithm::Lanczos => ScaleFlags::LANCZOS,
         }
     }
 
-    fn encoder_candidates(
-        codec: &VideoCodec,
-        allow_fallback: bool,
-        preference: &VideoEncoderPreference,
-        gpu_surface_only: bool,
-    ) -> Vec<&'static str> {
-        let push_unique = |list: &mut Vec<&'static str>, candidate: &'static str| {
-            if !list.contains(&candidate) {
-                list.push(candidate);
+    struct FfmpegColorTags {
+        range: color::Range,
+        space: color::Space,
+        primaries: color::Primaries,
+        transfer: color::TransferCharacteristic,
+    }
+
+    impl From<&ColorTags> for FfmpegColorTags {
+        fn from(tags: &ColorTags) -> Self {
+            let hdr = tags.transfer == "smpte2084";
+            Self {
+                range: if tags.range == "pc" {
+                    color::Range::JPEG
+                } else {
+                    color::Range::MPEG
+                },
+                space: if hdr {
+                    color::Space::BT2020NCL
+                } else {
+                    color::Space::BT709
+                },
+                primaries: if hdr {
+                    color::Primaries::BT2020
+                } else {
+                    color::Primaries::BT709
+                },
+                transfer: if hdr {
+                    color::TransferCharacteristic::SMPTE2084
+                } else {
+                    color::TransferCharacteristic::BT709
+                },
             }
+        }
+    }
+
+    /// BGRA (siempre de rango completo) a YUV con la matriz BT.709 y el rango
+    /// de salida elegido. Sin esto swscale usa BT.601.
+    fn configure_scaler_colorspace(
+        scaler: &mut scaling::Context,
+        color_range: &ColorRange,
+    ) -> Result<(), String> {
+        let dst_full_range = i32::from(*color_range == ColorRange::Full);
+        let result = unsafe {
+            let coefficients = ffi::sws_getCoefficients(ffi::SWS_CS_ITU709 as i32);
+            ffi::sws_setColorspaceDetails(
+                scaler.as_mut_ptr(),
+                coefficients,
+                1,
+                coefficients,
+                dst_full_range,
+                0,
+                1 << 16,
+                1 << 16,
+            )
         };
 
-        match codec {
-            VideoCodec::H264 => {
-                let mut list = Vec::new();
-                match preference {
-                    VideoEncoderPreference::Nvenc => {
-                        push_unique(&mut list, "h264_nvenc");
-                        push_unique(&mut list, "h264_amf");
-                        push_unique(&mut list, "h264_qsv");
-                    }
-                    VideoEncoderPreference::Amf => {
-                        push_unique(&mut list, "h264_amf");
-                        push_unique(&mut list, "h264_nvenc");
-                        push_unique(&mut list, "h264_qsv");
-                    }
-                    VideoEncoderPreference::Qsv => {
-                        push_unique(&mut list, "h264_qsv");
-                        push_unique(&mut list, "h264_nvenc");
-                        push_unique(&mut list, "h264_amf");
-                    }
-                    VideoEncoderPreference::Software => {}
-                    VideoEncoderPreference::Auto => {
-                        push_unique(&mut list, "h264_nvenc");
-                        push_unique(&mut list, "h264_amf");
-                        push_unique(&mut list, "h264_qsv");
-                    }
-                }
-                if !gpu_surface_only {
-                    push_unique(&mut list, "libx264");
-                    push_unique(&mut list, "h264");
-                    if allow_fallback {
-                        push_unique(&mut list, "mpeg4");
-                    }
-                }
-                list
+        if result < 0 {
+            return Err("No se pudo configurar la matriz de color en el escalador".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn set_color_metadata(encoder: &mut encoder::video::Video, tags: &ColorTags) {
+        let color = FfmpegColorTags::from(tags);
+        encoder.set_color_range(color.range);
+        encoder.set_colorspace(color.space);
+        unsafe {
+            let raw = encoder.as_mut_ptr();
+            (*raw).color_primaries = color.primaries.into();
+            (*raw).color_trc = color.transfer.into();
+        }
+    }
+
+    fn write_mastering_display(
+        raw: *mut ffi::AVMasteringDisplayMetadata,
+        metadata: &HdrMasteringMetadata,
+    ) {
+        let to_rational = |(num, den): (i32, i32)| ffi::AVRational { num, den };
+        let [red, green, blue, white_point] = metadata.chromaticities();
+        let [min_luminance, max_luminance] = metadata.luminance_range();
+        unsafe {
+            for (target, primary) in (*raw).display_primaries.iter_mut().zip([red, green, blue]) {
+                *target = primary.map(to_rational);
             }
-            VideoCodec::H265 => {
-                let mut list = Vec::new();
-                match preference {
-                    VideoEncoderPreference::Nvenc => {
-                        push_unique(&mut list, "hevc_nvenc");
-                        push_unique(&mut list, "hevc_amf");
-                        push_unique(&mut list, "hevc_qsv");
-                    }
-                    VideoEncoderPreference::Amf => {
-                        push_unique(&mut list, "hevc_amf");
-                        push_unique(&mut list, "hevc_nvenc");
-                        push_unique(&mut list, "hevc_qsv");
-                    }
-                    VideoEncoderPreference::Qsv => {
-                        push_unique(&mut list, "hevc_qsv");
-                        push_unique(&mut list, "hevc_nvenc");
-                        push_unique(&mut list, "hevc_amf");
-                    }
-                    VideoEncoderPreference::Software => {}
-                    VideoEncoderPreference::Auto => {
-                        push_unique(&mut list, "hevc_nvenc");
-                        push_unique(&mut list, "hevc_amf");
-                        push_unique(&mut list, "hevc_qsv");
-                    }
-                }
-                if !gpu_surface_only {
-                    push_unique(&mut list, "libx265");
-                    push_unique(&mut list, "hevc");
-                }
-                list
+            (*raw).white_point = white_point.map(to_rational);
+            (*raw).min_luminance = to_rational(min_luminance);
+            (*raw).max_luminance = to_rational(max_luminance);
+            (*raw).has_primaries = 1;
+            (*raw).has_luminance = 1;
+        }
+    }
+
+    fn write_content_light(raw: *mut ffi::AVContentLightMetadata, metadata: &HdrMasteringMetadata) {
+        unsafe {
+            (*raw).MaxCLL = metadata.max_content_light_level();
+            (*raw).MaxFALL = metadata.max_frame_average_light_level();
+        }
+    }
+
+    /// libx265 y NVENC leen el mastering display y el nivel de luz de aquí
+    /// para escribir los SEI de HDR10.
+    fn set_encoder_hdr_metadata(
+        encoder: &mut encoder::video::Video,
+        metadata: &HdrMasteringMetadata,
+    ) -> Result<(), String> {
+        unsafe {
+            let raw = encoder.as_mut_ptr();
+            let mastering = ffi::av_frame_side_data_new(
+                &mut (*raw).decoded_side_data,
+                &mut (*raw).nb_decoded_side_data,
+                ffi::AVFrameSideDataType::AV_FRAME_DATA_MASTERING_DISPLAY_METADATA,
+                std::mem::size_of::<ffi::AVMasteringDisplayMetadata>(),
+                0,
+            );
+            let light = ffi::av_frame_side_data_new(
+                &mut (*raw).decoded_side_data,
+                &mut (*raw).nb_decoded_side_data,
+                ffi::AVFrameSideDataType::AV_FRAME_DATA_CONTENT_LIGHT_LEVEL,
+                std::mem::size_of::<ffi::AVContentLightMetadata>(),
+                0,
+            );
+            if mastering.is_null() || light.is_null() {
+                return Err("No se pudieron agregar los metadatos HDR al encoder".to_string());
+            }
+            write_mastering_display((*mastering).data.cast(), metadata);
+            write_content_light((*light).data.cast(), metadata);
+        }
+        Ok(())
+    }
+
+    fn set_stream_hdr_metadata(
+        stream: &mut format::stream::StreamMut<'_>,
+        metadata: &HdrMasteringMetadata,
+    ) -> Result<(), String> {
+        unsafe {
+            let codecpar = (*stream.as_mut_ptr()).codecpar;
+            let mastering = ffi::av_packet_side_data_new(
+                &mut (*codecpar).coded_side_data,
+                &mut (*codecpar).nb_coded_side_data,
+                ffi::AVPacketSideDataType::AV_PKT_DATA_MASTERING_DISPLAY_METADATA,
+                std::mem::size_of::<ffi::AVMasteringDisplayMetadata>(),
+                0,
+            );
+            let light = ffi::av_packet_side_data_new(
+                &mut (*codecpar).coded_side_data,
+                &mut (*codecpar).nb_coded_side_data,
+                ffi::AVPacketSideDataType::AV_PKT_DATA_CONTENT_LIGHT_LEVEL,
+                std::mem::size_of::<ffi::AVContentLightMetadata>(),
+                0,
+            );
+            if mastering.is_null() || light.is_null() {
+                return Err("No se pudieron agregar los metadatos HDR al stream".to_string());
             }
-            VideoCodec::Vp9 => vec!["libvpx-vp9", "vp9"],
+            write_mastering_display((*mastering).data.cast(), metadata);
+            write_content_light((*light).data.cast(), metadata);
         }
+        Ok(())
     }
 
     unsafe extern "C" fn release_d3d11_texture_buffer(opaque: *mut c_void, _data: *mut u8) {
@@ -732,35 +2274,6 @@ mod platform {
         let _ = ID3D11Texture2D::from_raw(opaque as *mut _);
     }
 
-    fn recommended_gop_frames(fps: u32) -> u32 {
-        let safe_fps = fps.clamp(1, 240);
-        safe_fps.saturating_mul(2).clamp(30, 300)
-    }
-
-    fn estimate_target_bitrate_kbps(
-        width: u32,
-        height: u32,
-        fps: u32,
-        codec: &VideoCodec,
-        quality_mode: &QualityMode,
-    ) -> u32 {
-        let bpp = match quality_mode {
-            QualityMode::Performance => 0.055_f64,
-            QualityMode::Balanced => 0.075_f64,
-            QualityMode::Quality => 0.1_f64,
-        };
-        let codec_factor = match codec {
-            VideoCodec::H264 => 1.0_f64,
-            VideoCodec::H265 => 0.72_f64,
-            VideoCodec::Vp9 => 0.68_f64,
-        };
-
-        let pixels_per_sec = f64::from(width) * f64::from(height) * f64::from(fps.clamp(1, 240));
-        let estimated_kbps = (pixels_per_sec * bpp * codec_factor / 1_000.0).round();
-        let clamped = estimated_kbps.clamp(2_500.0, 80_000.0);
-        clamped as u32
-    }
-
     fn selected_backend_label(encoder_name: &str) -> &'static str {
         if encoder_name.contains("nvenc") {
             "NVENC"
@@ -782,25 +2295,80 @@ mod platform {
     }
 
     fn can_open_encoder(encoder_name: &str) -> bool {
-        let Some(codec) = encoder::find_by_name(encoder_name) else {
-            return false;
-        };
+        open_encoder(encoder_name, 1280, 720, Pixel::YUV420P, 30).is_ok()
+    }
+
+    fn open_encoder(
+        encoder_name: &str,
+        width: u32,
+        height: u32,
+        pixel: Pixel,
+        fps: u32,
+    ) -> Result<(), String> {
+        let codec = encoder::find_by_name(encoder_name)
+            .ok_or_else(|| "no está en este build de FFmpeg".to_string())?;
 
-        let mut enc = match codec::context::Context::new_with_codec(codec)
+        let mut enc = codec::context::Context::new_with_codec(codec)
             .encoder()
             .video()
-        {
-            Ok(enc) => enc,
-            Err(_) => return false,
-        };
+            .map_err(|err| format!("no se pudo crear contexto ({err})"))?;
 
-        enc.set_width(1280);
-        enc.set_height(720);
-        enc.set_format(Pixel::YUV420P);
+        enc.set_width(width);
+        enc.set_height(height);
+        enc.set_format(pixel);
         enc.set_time_base(Rational::new(1, 1_000));
-        enc.set_frame_rate(Some(Rational::new(30, 1)));
+        enc.set_frame_rate(Some(Rational::new(fps.max(1) as i32, 1)));
+
+        enc.open_with(Dictionary::new())
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn probe_video_encoder(
+        config: &EncoderConfig,
+        width: u32,
+        height: u32,
+    ) -> Result<&'static str, String> {
+        let _ = ffmpeg_the_third::init();
 
-        enc.open_with(Dictionary::new()).is_ok()
+        let (codec_kind, allow_fallback) = match &config.codec {
+            Some(codec) => (codec.clone(), false),
+            None => (config.format.default_codec(), true),
+        };
+        let ten_bit = config.bit_depth() == BitDepth::Ten;
+        let candidates = if ten_bit {
+            ten_bit_encoder_candidates(&config.video_encoder_preference)?
+        } else {
+            encoder_candidates(
+                &codec_kind,
+                allow_fallback,
+                &config.video_encoder_preference,
+                false,
+            )
+        };
+
+        let mut failures = Vec::<String>::new();
+        for name in candidates {
+            let pixel = match (ten_bit, name) {
+                (true, "libx265") => Pixel::YUV420P10LE,
+                (true, _) => Pixel::P010LE,
+                (false, _) => Pixel::YUV420P,
+            };
+            match open_encoder(name, width, height, pixel, config.fps) {
+                Ok(()) => return Ok(name),
+                Err(err) => failures.push(format!("{name}: {err}")),
+            }
+        }
+
+        Err(format!(
+            "No se pudo abrir un encoder para {} a {width}x{height}. Probados: {}",
+            selected_codec_label(&codec_kind),
+            if failures.is_empty() {
+                "(ninguno)".to_string()
+            } else {
+                failures.join(" | ")
+            }
+        ))
     }
 
     pub fn detect_video_encoder_capabilities() -> VideoEncoderCapabilities {
@@ -811,12 +2379,24 @@ mod platform {
             amf: can_open_encoder("h264_amf"),
             qsv: can_open_encoder("h264_qsv"),
             software: can_open_encoder("libx264") || can_open_encoder("h264"),
+            hevc_nvenc: can_open_encoder("hevc_nvenc"),
+            hevc_amf: can_open_encoder("hevc_amf"),
+            hevc_qsv: can_open_encoder("hevc_qsv"),
+            hevc_software: can_open_encoder("libx265"),
+            av1_nvenc: can_open_encoder("av1_nvenc"),
+            av1_amf: can_open_encoder("av1_amf"),
+            av1_qsv: can_open_encoder("av1_qsv"),
+            av1_software: can_open_encoder("libsvtav1") || can_open_encoder("libaom-av1"),
+            vp9_qsv: can_open_encoder("vp9_qsv"),
+            vp9_software: can_open_encoder("libvpx-vp9"),
         }
     }
 }
 
 #[cfg(not(target_os = "windows"))]
 mod platform {
+    use std::time::Instant;
+
     use crate::capture::models::RawFrame;
     use crate::encoder::config::EncoderConfig;
 
@@ -826,6 +2406,16 @@ mod platform {
         pub amf: bool,
         pub qsv: bool,
         pub software: bool,
+        pub hevc_nvenc: bool,
+        pub hevc_amf: bool,
+        pub hevc_qsv: bool,
+        pub hevc_software: bool,
+        pub av1_nvenc: bool,
+        pub av1_amf: bool,
+        pub av1_qsv: bool,
+        pub av1_software: bool,
+        pub vp9_qsv: bool,
+        pub vp9_software: bool,
     }
 
     pub struct FfmpegEncoderConsumer;
@@ -839,19 +2429,47 @@ mod platform {
             Ok(())
         }
 
-        pub fn on_stop(&mut self) -> Result<(), String> {
+        pub fn on_stop(&mut self, _stop_at_ms: Option<u64>) -> Result<(), String> {
+            Ok(())
+        }
+
+        pub fn fill_cfr_gap(&mut self, _elapsed_ms: u64) -> Result<(), String> {
+            Ok(())
+        }
+
+        pub fn redraw_moved_cursor(&mut self, _elapsed_ms: u64) -> Result<(), String> {
+            Ok(())
+        }
+
+        pub fn serve_frame_snapshot(&self) {}
+
+        pub fn check_disk_space(&mut self) -> bool {
+            false
+        }
+
+        pub fn pump_live_audio(&mut self) -> Result<(), String> {
+            Ok(())
+        }
+
+        pub fn check_stall(&self, _now: Instant) -> Result<(), String> {
             Ok(())
         }
     }
 
     pub fn detect_video_encoder_capabilities() -> VideoEncoderCapabilities {
-        VideoEncoderCapabilities {
-            nvenc: false,
-            amf: false,
-            qsv: false,
-            software: false,
-        }
+        VideoEncoderCapabilities::default()
+    }
+
+    pub fn probe_video_encoder(
+        _config: &EncoderConfig,
+        _width: u32,
+        _height: u32,
+    ) -> Result<&'static str, String> {
+        Err("El encoder FFmpeg solo está disponible para Windows".to_string())
     }
 }
 
-pub use platform::{detect_video_encoder_capabilities, FfmpegEncoderConsumer};
+pub use platform::{
+    detect_video_encoder_capabilities, probe_video_encoder, FfmpegEncoderConsumer,
+    VideoEncoderCapabilities,
+};
@@ -0,0 +1,275 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+pub const DEFAULT_DISK_SPACE_THRESHOLD_MB: u64 = 500;
+pub const PREFLIGHT_ASSUMED_DURATION_SECS: u64 = 30 * 60;
+
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskSpaceWarning {
+    pub path: String,
+    pub available_bytes: u64,
+    pub estimated_required_bytes: u64,
+    pub assumed_duration_minutes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskSpaceStatus {
+    pub remaining_bytes: u64,
+    pub estimated_minutes_left: Option<u64>,
+}
+
+pub fn threshold_bytes(threshold_mb: u64) -> u64 {
+    threshold_mb.saturating_mul(BYTES_PER_MB)
+}
+
+pub fn estimate_recording_bytes(bitrate_kbps: u32, duration_secs: u64) -> u64 {
+    (u64::from(bitrate_kbps) * 1_000 / 8).saturating_mul(duration_secs)
+}
+
+pub fn estimate_minutes_left(
+    available_bytes: u64,
+    threshold_bytes: u64,
+    bitrate_kbps: u32,
+) -> Option<u64> {
+    let bytes_per_minute = estimate_recording_bytes(bitrate_kbps, 60);
+    if bytes_per_minute == 0 {
+        return None;
+    }
+
+    Some(available_bytes.saturating_sub(threshold_bytes) / bytes_per_minute)
+}
+
+pub fn preflight_disk_space(
+    paths: &[PathBuf],
+    bitrate_kbps: u32,
+    threshold_mb: u64,
+) -> Result<Vec<DiskSpaceWarning>, String> {
+    let reserve_bytes = threshold_bytes(threshold_mb);
+    let required_bytes = estimate_recording_bytes(bitrate_kbps, PREFLIGHT_ASSUMED_DURATION_SECS)
+        .saturating_add(reserve_bytes);
+    let mut checked_volumes = Vec::<PathBuf>::new();
+    let mut warnings = Vec::new();
+
+    for path in paths {
+        if let Some(volume) = volume_root(path) {
+            if checked_volumes.contains(&volume) {
+                continue;
+            }
+            checked_volumes.push(volume);
+        }
+
+        let Some(available_bytes) = available_space_bytes(path) else {
+            continue;
+        };
+
+        if available_bytes <= reserve_bytes {
+            return Err(format!(
+                "Espacio en disco insuficiente en '{}': quedan {} MB y se requiere una reserva de {} MB",
+                path.display(),
+                available_bytes / BYTES_PER_MB,
+                threshold_mb
+            ));
+        }
+
+        if available_bytes < required_bytes {
+            warnings.push(DiskSpaceWarning {
+                path: path.to_string_lossy().into_owned(),
+                available_bytes,
+                estimated_required_bytes: required_bytes,
+                assumed_duration_minutes: PREFLIGHT_ASSUMED_DURATION_SECS / 60,
+            });
+        }
+    }
+
+    Ok(warnings)
+}
+
+pub struct DiskSpaceMonitor {
+    paths: Vec<PathBuf>,
+    threshold_bytes: u64,
+    bitrate_kbps: u32,
+    last_check_at: Option<Instant>,
+}
+
+impl DiskSpaceMonitor {
+    pub fn new(paths: Vec<PathBuf>, threshold_mb: u64) -> Self {
+        Self {
+            paths,
+            threshold_bytes: threshold_bytes(threshold_mb),
+            bitrate_kbps: 0,
+            last_check_at: None,
+        }
+    }
+
+    pub fn set_bitrate_kbps(&mut self, bitrate_kbps: u32) {
+        self.bitrate_kbps = bitrate_kbps;
+    }
+
+    pub fn poll(&mut self) -> bool {
+        if self
+            .last_check_at
+            .map(|last| last.elapsed() < DISK_CHECK_INTERVAL)
+            .unwrap_or(false)
+        {
+            return false;
+        }
+        self.last_check_at = Some(Instant::now());
+
+        let Some(remaining_bytes) = self
+            .paths
+            .iter()
+            .filter_map(|path| available_space_bytes(path))
+            .min()
+        else {
+            return false;
+        };
+
+        set_live_disk_space_status(Some(DiskSpaceStatus {
+            remaining_bytes,
+            estimated_minutes_left: estimate_minutes_left(
+                remaining_bytes,
+                self.threshold_bytes,
+                self.bitrate_kbps,
+            ),
+        }));
+
+        remaining_bytes <= self.threshold_bytes
+    }
+}
+
+fn live_disk_space_status() -> &'static Mutex<Option<DiskSpaceStatus>> {
+    static LIVE_DISK_SPACE_STATUS: OnceLock<Mutex<Option<DiskSpaceStatus>>> = OnceLock::new();
+    LIVE_DISK_SPACE_STATUS.get_or_init(|| Mutex::new(None))
+}
+
+pub fn get_live_disk_space_status() -> Option<DiskSpaceStatus> {
+    live_disk_space_status()
+        .lock()
+        .ok()
+        .and_then(|value| *value)
+}
+
+pub fn set_live_disk_space_status(status: Option<DiskSpaceStatus>) {
+    if let Ok(mut guard) = live_disk_space_status().lock() {
+        *guard = status;
+    }
+}
+
+fn volume_root(path: &Path) -> Option<PathBuf> {
+    let path = std::path::absolute(path).ok()?;
+    let directory = nearest_existing_ancestor(&path)?.canonicalize().ok()?;
+    platform::volume_root(&directory)
+}
+
+fn nearest_existing_ancestor(path: &Path) -> Option<&Path> {
+    path.ancestors()
+        .find(|ancestor| !ancestor.as_os_str().is_empty() && ancestor.is_dir())
+}
+
+pub fn available_space_bytes(path: &Path) -> Option<u64> {
+    platform::available_space_bytes(nearest_existing_ancestor(path)?)
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{
+        ffi::OsString,
+        os::windows::ffi::{OsStrExt, OsStringExt},
+        path::{Path, PathBuf},
+    };
+
+    use windows::{
+        core::PCWSTR,
+        Win32::Storage::FileSystem::{GetDiskFreeSpaceExW, GetVolumePathNameW},
+    };
+
+    const MAX_PATH_CHARS: usize = 261;
+
+    fn to_wide(path: &Path) -> Vec<u16> {
+        path.as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect()
+    }
+
+    pub fn volume_root(directory: &Path) -> Option<PathBuf> {
+        let wide = to_wide(directory);
+        let mut volume_root = [0_u16; MAX_PATH_CHARS];
+        unsafe { GetVolumePathNameW(PCWSTR(wide.as_ptr()), &mut volume_root) }.ok()?;
+        let len = volume_root
+            .iter()
+            .position(|ch| *ch == 0)
+            .unwrap_or(volume_root.len());
+        Some(PathBuf::from(OsString::from_wide(&volume_root[..len])))
+    }
+
+    pub fn available_space_bytes(directory: &Path) -> Option<u64> {
+        let wide = to_wide(directory);
+        let mut free_bytes_available = 0_u64;
+
+        unsafe {
+            GetDiskFreeSpaceExW(
+                PCWSTR(wide.as_ptr()),
+                Some(&mut free_bytes_available as *mut u64),
+                None,
+                None,
+            )
+        }
+        .ok()?;
+
+        Some(free_bytes_available)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use std::path::{Path, PathBuf};
+
+    pub fn volume_root(directory: &Path) -> Option<PathBuf> {
+        directory.ancestors().last().map(Path::to_path_buf)
+    }
+
+    pub fn available_space_bytes(_directory: &Path) -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{estimate_minutes_left, estimate_recording_bytes, threshold_bytes, volume_root};
+
+    #[test]
+    fn estima_bytes_a_partir_del_bitrate() {
+        assert_eq!(estimate_recording_bytes(8_000, 60), 60_000_000);
+        assert_eq!(estimate_recording_bytes(0, 60), 0);
+    }
+
+    #[test]
+    fn minutos_restantes_descuentan_la_reserva() {
+        let reserve = threshold_bytes(500);
+        let available = reserve + 600_000_000;
+
+        assert_eq!(estimate_minutes_left(available, reserve, 8_000), Some(10));
+        assert_eq!(estimate_minutes_left(reserve / 2, reserve, 8_000), Some(0));
+        assert_eq!(estimate_minutes_left(available, reserve, 0), None);
+    }
+
+    #[test]
+    fn una_ruta_relativa_cae_en_el_volumen_real() {
+        let cwd = std::env::current_dir().unwrap();
+        let relative = volume_root(Path::new("recordings/clip.mp4"));
+        assert!(relative.is_some());
+        assert_eq!(relative, volume_root(&cwd.join("clip.mp4")));
+    }
+}
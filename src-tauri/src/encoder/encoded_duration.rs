@@ -0,0 +1,11 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static LIVE_ENCODED_DURATION_MS: AtomicU64 = AtomicU64::new(0);
+
+pub fn get_live_encoded_duration_ms() -> u64 {
+    LIVE_ENCODED_DURATION_MS.load(Ordering::Relaxed)
+}
+
+pub fn set_live_encoded_duration_ms(duration_ms: u64) {
+    LIVE_ENCODED_DURATION_MS.store(duration_ms, Ordering::Relaxed);
+}
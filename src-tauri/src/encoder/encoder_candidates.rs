@@ -0,0 +1,182 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::encoder::config::{VideoCodec, VideoEncoderPreference};
+
+fn push_unique(list: &mut Vec<&'static str>, candidate: &'static str) {
+    if !list.contains(&candidate) {
+        list.push(candidate);
+    }
+}
+
+pub fn encoder_candidates(
+    codec: &VideoCodec,
+    allow_fallback: bool,
+    preference: &VideoEncoderPreference,
+    gpu_surface_only: bool,
+) -> Vec<&'static str> {
+    let (hardware, software): (&[&'static str], &[&'static str]) = match codec {
+        VideoCodec::H264 => (
+            match preference {
+                VideoEncoderPreference::Amf => &["h264_amf", "h264_nvenc", "h264_qsv"],
+                VideoEncoderPreference::Qsv => &["h264_qsv", "h264_nvenc", "h264_amf"],
+                VideoEncoderPreference::Software => &[],
+                VideoEncoderPreference::Nvenc | VideoEncoderPreference::Auto => {
+                    &["h264_nvenc", "h264_amf", "h264_qsv"]
+                }
+            },
+            &["libx264", "h264"],
+        ),
+        VideoCodec::H265 => (
+            match preference {
+                VideoEncoderPreference::Amf => &["hevc_amf", "hevc_nvenc", "hevc_qsv"],
+                VideoEncoderPreference::Qsv => &["hevc_qsv", "hevc_nvenc", "hevc_amf"],
+                VideoEncoderPreference::Software => &[],
+                VideoEncoderPreference::Nvenc | VideoEncoderPreference::Auto => {
+                    &["hevc_nvenc", "hevc_amf", "hevc_qsv"]
+                }
+            },
+            &["libx265", "hevc"],
+        ),
+        // VP9 no tiene ruta por textura D3D11: siempre va por CPU.
+        VideoCodec::Vp9 => (&[], &["libvpx-vp9", "vp9"]),
+    };
+
+    let mut list = Vec::new();
+    for candidate in hardware {
+        push_unique(&mut list, candidate);
+    }
+    if !gpu_surface_only || *codec == VideoCodec::Vp9 {
+        for candidate in software {
+            push_unique(&mut list, candidate);
+        }
+        if allow_fallback && *codec == VideoCodec::H264 {
+            push_unique(&mut list, "mpeg4");
+        }
+    }
+    list
+}
+
+/// Encoders que aceptan YUV444P. AMF y QSV no tienen 4:4:4 en 8 bits y
+/// `mpeg4` tampoco: con ellos la sesión se graba en 4:2:0.
+pub fn supports_yuv444(encoder_name: &str) -> bool {
+    matches!(
+        encoder_name,
+        "libx264" | "libx265" | "h264_nvenc" | "hevc_nvenc" | "libvpx-vp9"
+    )
+}
+
+pub fn yuv444_profile(encoder_name: &str) -> Option<&'static str> {
+    match encoder_name {
+        "libx264" => Some("high444"),
+        "h264_nvenc" => Some("high444p"),
+        "libx265" => Some("main444-8"),
+        "hevc_nvenc" => Some("rext"),
+        _ => None,
+    }
+}
+
+/// Encoders HEVC que abren perfil `main10` para grabar en 10 bits, con o sin
+/// HDR. `libx265` va al final: no depende de la GPU pero es mucho más lento.
+pub fn ten_bit_encoder_candidates(
+    preference: &VideoEncoderPreference,
+) -> Result<Vec<&'static str>, String> {
+    match preference {
+        VideoEncoderPreference::Nvenc | VideoEncoderPreference::Auto => {
+            Ok(vec!["hevc_nvenc", "hevc_qsv", "libx265"])
+        }
+        VideoEncoderPreference::Qsv => Ok(vec!["hevc_qsv", "hevc_nvenc", "libx265"]),
+        VideoEncoderPreference::Software => Ok(vec!["libx265"]),
+        VideoEncoderPreference::Amf => Err(
+            "La grabación de 10 bits necesita HEVC main10 con NVENC, QSV o libx265; AMF no está soportado"
+                .to_string(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encoder_candidates, supports_yuv444, ten_bit_encoder_candidates, yuv444_profile};
+    use crate::encoder::config::{VideoCodec, VideoEncoderPreference};
+
+    #[test]
+    fn software_pone_el_encoder_de_cpu_primero() {
+        let hevc = encoder_candidates(
+            &VideoCodec::H265,
+            false,
+            &VideoEncoderPreference::Software,
+            false,
+        );
+        assert_eq!(hevc, vec!["libx265", "hevc"]);
+
+        let h264 = encoder_candidates(
+            &VideoCodec::H264,
+            true,
+            &VideoEncoderPreference::Software,
+            false,
+        );
+        assert_eq!(h264, vec!["libx264", "h264", "mpeg4"]);
+
+        let vp9 = encoder_candidates(
+            &VideoCodec::Vp9,
+            false,
+            &VideoEncoderPreference::Software,
+            false,
+        );
+        assert_eq!(vp9.first(), Some(&"libvpx-vp9"));
+    }
+
+    #[test]
+    fn hardware_va_primero_y_cpu_queda_como_respaldo() {
+        assert_eq!(
+            encoder_candidates(
+                &VideoCodec::H265,
+                false,
+                &VideoEncoderPreference::Qsv,
+                false
+            ),
+            vec!["hevc_qsv", "hevc_nvenc", "hevc_amf", "libx265", "hevc"]
+        );
+        assert_eq!(
+            encoder_candidates(&VideoCodec::H264, true, &VideoEncoderPreference::Auto, true),
+            vec!["h264_nvenc", "h264_amf", "h264_qsv"]
+        );
+    }
+
+    #[test]
+    fn diez_bits_solo_prueba_encoders_hevc_main10() {
+        assert_eq!(
+            ten_bit_encoder_candidates(&VideoEncoderPreference::Qsv),
+            Ok(vec!["hevc_qsv", "hevc_nvenc", "libx265"])
+        );
+        assert_eq!(
+            ten_bit_encoder_candidates(&VideoEncoderPreference::Auto),
+            Ok(vec!["hevc_nvenc", "hevc_qsv", "libx265"])
+        );
+        assert_eq!(
+            ten_bit_encoder_candidates(&VideoEncoderPreference::Software),
+            Ok(vec!["libx265"])
+        );
+        let err =
+            ten_bit_encoder_candidates(&VideoEncoderPreference::Amf).expect_err("debio fallar");
+        assert!(err.contains("10 bits"), "{err}");
+    }
+
+    #[test]
+    fn solo_algunos_encoders_graban_444() {
+        let h264 = encoder_candidates(
+            &VideoCodec::H264,
+            true,
+            &VideoEncoderPreference::Auto,
+            false,
+        );
+        let with_444: Vec<_> = h264
+            .into_iter()
+            .filter(|name| supports_yuv444(name))
+            .collect();
+        assert_eq!(with_444, vec!["h264_nvenc", "libx264"]);
+        assert!(supports_yuv444("libvpx-vp9"));
+        assert!(!supports_yuv444("hevc_qsv"));
+        assert_eq!(yuv444_profile("h264_nvenc"), Some("high444p"));
+        assert_eq!(yuv444_profile("libvpx-vp9"), None);
+    }
+}
@@ -0,0 +1,138 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    env,
+    process::{Command, Stdio},
+};
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use crate::encoder::ffmpeg_paths::{ffmpeg_bin_available, resolve_ffmpeg_bin};
+
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FfmpegLibraryVersion {
+    pub name: &'static str,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FfmpegInfo {
+    pub cli_path: String,
+    pub cli_exists: bool,
+    pub cli_version: Option<String>,
+    pub libraries: Vec<FfmpegLibraryVersion>,
+}
+
+pub fn get_ffmpeg_info() -> FfmpegInfo {
+    let cli_path = resolve_ffmpeg_bin();
+    let cli_exists = ffmpeg_bin_available(&cli_path, env::var_os("PATH").as_deref());
+    let cli_version = if cli_exists {
+        read_cli_version(&cli_path)
+    } else {
+        None
+    };
+
+    FfmpegInfo {
+        cli_path: cli_path.to_string_lossy().into_owned(),
+        cli_exists,
+        cli_version,
+        libraries: platform::linked_library_versions(),
+    }
+}
+
+fn read_cli_version(ffmpeg_bin: &std::path::Path) -> Option<String> {
+    let mut cmd = Command::new(ffmpeg_bin);
+    cmd.arg("-hide_banner")
+        .arg("-version")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null());
+
+    #[cfg(windows)]
+    {
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = cmd.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_version_line(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_version_line(stdout: &str) -> Option<String> {
+    stdout
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+fn format_library_version(version: u32) -> String {
+    format!(
+        "{}.{}.{}",
+        version >> 16,
+        (version >> 8) & 0xff,
+        version & 0xff
+    )
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use ffmpeg_the_third::{codec, filter, format, software, util};
+
+    use super::{format_library_version, FfmpegLibraryVersion};
+
+    pub fn linked_library_versions() -> Vec<FfmpegLibraryVersion> {
+        [
+            ("avutil", util::version()),
+            ("avcodec", codec::version()),
+            ("avformat", format::version()),
+            ("avfilter", filter::version()),
+            ("swscale", software::scaling::version()),
+        ]
+        .into_iter()
+        .map(|(name, version)| FfmpegLibraryVersion {
+            name,
+            version: format_library_version(version),
+        })
+        .collect()
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::FfmpegLibraryVersion;
+
+    pub fn linked_library_versions() -> Vec<FfmpegLibraryVersion> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toma_la_primera_linea_de_version() {
+        let stdout = "\nffmpeg version 7.1-full_build Copyright (c) 2000-2024\nbuilt with gcc 14\n";
+        assert_eq!(
+            parse_version_line(stdout).as_deref(),
+            Some("ffmpeg version 7.1-full_build Copyright (c) 2000-2024")
+        );
+        assert_eq!(parse_version_line("  \n"), None);
+    }
+
+    #[test]
+    fn version_de_libreria_se_decodifica() {
+        assert_eq!(
+            format_library_version((61 << 16) | (19 << 8) | 100),
+            "61.19.100"
+        );
+    }
+}
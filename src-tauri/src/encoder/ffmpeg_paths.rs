@@ -1,8 +1,80 @@
 #![cfg_attr(not(target_os = "windows"), allow(dead_code))]
 
-use std::path::PathBuf;
+use std::{
+    env,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+const FFMPEG_EXE: &str = "ffmpeg.exe";
+
+fn resource_dir_slot() -> &'static OnceLock<PathBuf> {
+    static RESOURCE_DIR: OnceLock<PathBuf> = OnceLock::new();
+    &RESOURCE_DIR
+}
+
+fn configured_bin_slot() -> &'static Mutex<Option<PathBuf>> {
+    static CONFIGURED_BIN: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+    CONFIGURED_BIN.get_or_init(|| Mutex::new(None))
+}
+
+fn logged_bin_slot() -> &'static Mutex<Option<PathBuf>> {
+    static LOGGED_BIN: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+    LOGGED_BIN.get_or_init(|| Mutex::new(None))
+}
+
+pub fn set_ffmpeg_resource_dir(dir: PathBuf) {
+    let _ = resource_dir_slot().set(dir);
+}
+
+pub fn set_configured_ffmpeg_bin(path: Option<PathBuf>) {
+    if let Ok(mut slot) = configured_bin_slot().lock() {
+        *slot = path.filter(|path| !path.as_os_str().is_empty());
+    }
+}
+
+fn configured_ffmpeg_bin() -> Option<PathBuf> {
+    configured_bin_slot().lock().ok()?.clone()
+}
 
 pub fn resolve_ffmpeg_bin() -> PathBuf {
+    resolve_ffmpeg_bin_with(configured_ffmpeg_bin().as_deref())
+}
+
+pub fn resolve_ffmpeg_bin_with(configured: Option<&Path>) -> PathBuf {
+    let bin = find_ffmpeg_bin(configured, resource_dir_slot().get().map(PathBuf::as_path));
+    log_chosen_ffmpeg_bin(&bin);
+    bin
+}
+
+fn log_chosen_ffmpeg_bin(bin: &Path) {
+    let Ok(mut logged) = logged_bin_slot().lock() else {
+        return;
+    };
+    if logged.as_deref() != Some(bin) {
+        eprintln!("[ffmpeg] Usando FFmpeg CLI: {}", bin.display());
+        *logged = Some(bin.to_path_buf());
+    }
+}
+
+fn configured_candidate(configured: &Path) -> PathBuf {
+    if configured.is_dir() {
+        configured.join(FFMPEG_EXE)
+    } else {
+        configured.to_path_buf()
+    }
+}
+
+fn resource_dir_candidates(dir: &Path) -> [PathBuf; 3] {
+    [
+        dir.join(FFMPEG_EXE),
+        dir.join("bin").join(FFMPEG_EXE),
+        dir.join("ffmpeg-windows").join("bin").join(FFMPEG_EXE),
+    ]
+}
+
+fn find_ffmpeg_bin(configured: Option<&Path>, resource_dir: Option<&Path>) -> PathBuf {
     if let Ok(explicit) = std::env::var("CAPTURIST_FFMPEG_BIN") {
         let explicit = PathBuf::from(explicit);
         if explicit.exists() {
@@ -25,6 +97,14 @@ pub fn resolve_ffmpeg_bin() -> PathBuf {
 
     let mut candidates = Vec::new();
 
+    if let Some(configured) = configured {
+        candidates.push(configured_candidate(configured));
+    }
+
+    if let Some(dir) = resource_dir {
+        candidates.extend(resource_dir_candidates(dir));
+    }
+
     if let Ok(exe) = std::env::current_exe() {
         if let Some(dir) = exe.parent() {
             candidates.push(dir.join("ffmpeg.exe"));
@@ -60,6 +140,21 @@ pub fn resolve_ffmpeg_bin() -> PathBuf {
     PathBuf::from("ffmpeg")
 }
 
+fn find_in_path(program: &Path, path_var: Option<&OsStr>) -> Option<PathBuf> {
+    let path_var = path_var?;
+    env::split_paths(path_var).find_map(|dir| {
+        [program.to_path_buf(), program.with_extension("exe")]
+            .into_iter()
+            .map(|name| dir.join(name))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+pub fn ffmpeg_bin_available(ffmpeg_bin: &Path, path_var: Option<&OsStr>) -> bool {
+    ffmpeg_bin.is_file()
+        || (ffmpeg_bin.components().count() == 1 && find_in_path(ffmpeg_bin, path_var).is_some())
+}
+
 pub fn resolve_ffmpeg_dir() -> Option<PathBuf> {
     let bin = resolve_ffmpeg_bin();
     let parent = bin.parent()?.to_path_buf();
@@ -69,3 +164,42 @@ pub fn resolve_ffmpeg_dir() -> Option<PathBuf> {
         Some(parent)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    #[test]
+    fn ruta_configurada_como_carpeta_apunta_al_ejecutable() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join(FFMPEG_EXE);
+        fs::write(&exe, b"").unwrap();
+
+        assert_eq!(configured_candidate(dir.path()), exe);
+        assert_eq!(find_ffmpeg_bin(Some(dir.path()), None), exe);
+        assert_eq!(find_ffmpeg_bin(Some(&exe), None), exe);
+    }
+
+    #[test]
+    fn ruta_configurada_tiene_prioridad_sobre_los_recursos_del_bundle() {
+        let configured = tempfile::tempdir().unwrap();
+        let resources = tempfile::tempdir().unwrap();
+        let configured_exe = configured.path().join(FFMPEG_EXE);
+        let bundled_exe = resources.path().join("bin").join(FFMPEG_EXE);
+        fs::write(&configured_exe, b"").unwrap();
+        fs::create_dir_all(bundled_exe.parent().unwrap()).unwrap();
+        fs::write(&bundled_exe, b"").unwrap();
+
+        assert_eq!(
+            find_ffmpeg_bin(Some(&configured_exe), Some(resources.path())),
+            configured_exe
+        );
+        let missing = configured.path().join("otro").join(FFMPEG_EXE);
+        assert_eq!(
+            find_ffmpeg_bin(Some(&missing), Some(resources.path())),
+            bundled_exe
+        );
+    }
+}
@@ -0,0 +1,299 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use crate::encoder::config::{AudioCaptureConfig, AudioCodec, Fat32Policy, OutputFormat};
+
+// FAT32 admite archivos de hasta 4 GiB - 1 byte. Se corta antes para dejar
+// margen al trailer y a los metadatos del mux posterior.
+pub const FAT32_SAFE_FILE_BYTES: u64 = 3_900 * 1024 * 1024;
+const LOSSLESS_FALLBACK_SAMPLE_RATE: u64 = 48_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSizeLimitWarning {
+    pub path: String,
+    pub filesystem: String,
+    pub limit_bytes: u64,
+}
+
+pub trait VolumeInfoSource {
+    fn filesystem_name(&self, path: &Path) -> Option<String>;
+}
+
+pub struct SystemVolumeInfo;
+
+impl VolumeInfoSource for SystemVolumeInfo {
+    fn filesystem_name(&self, path: &Path) -> Option<String> {
+        let directory = path
+            .ancestors()
+            .find(|ancestor| !ancestor.as_os_str().is_empty() && ancestor.is_dir())?;
+        platform::filesystem_name(directory)
+    }
+}
+
+fn has_4gb_file_limit(filesystem: &str) -> bool {
+    // exFAT no tiene el límite de 4 GB; solo las variantes FAT clásicas.
+    matches!(
+        filesystem.trim().to_ascii_uppercase().as_str(),
+        "FAT" | "FAT12" | "FAT16" | "FAT32"
+    )
+}
+
+pub fn detect_file_size_limits(
+    paths: &[PathBuf],
+    source: &impl VolumeInfoSource,
+) -> Vec<FileSizeLimitWarning> {
+    paths
+        .iter()
+        .filter_map(|path| {
+            let filesystem = source.filesystem_name(path)?;
+            has_4gb_file_limit(&filesystem).then(|| FileSizeLimitWarning {
+                path: path.to_string_lossy().into_owned(),
+                filesystem,
+                limit_bytes: FAT32_SAFE_FILE_BYTES,
+            })
+        })
+        .collect()
+}
+
+pub fn check_file_size_limits(
+    paths: &[PathBuf],
+    policy: &Fat32Policy,
+    source: &impl VolumeInfoSource,
+) -> Result<Vec<FileSizeLimitWarning>, String> {
+    let warnings = detect_file_size_limits(paths, source);
+
+    if *policy == Fat32Policy::Reject {
+        if let Some(warning) = warnings.first() {
+            return Err(format!(
+                "El destino '{}' usa {} y no admite archivos de más de 4 GB. Elige otra carpeta o permite partir la grabación al llegar al límite",
+                warning.path, warning.filesystem
+            ));
+        }
+    }
+
+    Ok(warnings)
+}
+
+/// Bytes por segundo que el mux final agrega al video. El contador del
+/// encoder solo ve los packets de video, así que el audio se descuenta del
+/// límite por adelantado. FLAC se cuenta sin comprimir y en 32 bits, su peor caso.
+pub fn audio_bytes_per_second(audio: &AudioCaptureConfig, format: &OutputFormat) -> u64 {
+    if !audio.is_enabled() {
+        return 0;
+    }
+    if let Some(bitrate_kbps) = audio.effective_bitrate_kbps(format) {
+        return u64::from(bitrate_kbps) * 1_000 / 8;
+    }
+    let bytes_per_sample = match audio.effective_codec(format) {
+        AudioCodec::Pcm => 2,
+        _ => 4,
+    };
+    audio
+        .audio_sample_rate
+        .map_or(LOSSLESS_FALLBACK_SAMPLE_RATE, u64::from)
+        * u64::from(audio.audio_channels.unwrap_or(2))
+        * bytes_per_sample
+}
+
+pub fn reached_file_size_limit(
+    limit_bytes: u64,
+    video_bytes: u64,
+    audio_bytes_per_second: u64,
+    duration_ms: u64,
+) -> bool {
+    let audio_bytes = audio_bytes_per_second.saturating_mul(duration_ms) / 1_000;
+    video_bytes.saturating_add(audio_bytes) >= limit_bytes
+}
+
+pub fn part_output_path(path: &Path, part: u32) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{stem}.part{part}.{}", extension.to_string_lossy()),
+        None => format!("{stem}.part{part}"),
+    };
+    path.with_file_name(file_name)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SessionParts {
+    opened: Arc<AtomicU32>,
+}
+
+impl SessionParts {
+    pub fn record(&self, part: u32) {
+        self.opened.fetch_max(part, Ordering::AcqRel);
+    }
+
+    pub fn paths(&self, output_path: &Path) -> Vec<PathBuf> {
+        let opened = self.opened.load(Ordering::Acquire).max(1);
+        std::iter::once(output_path.to_path_buf())
+            .chain((2..=opened).map(|part| part_output_path(output_path, part)))
+            .collect()
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{os::windows::ffi::OsStrExt, path::Path};
+
+    use windows::{
+        core::PCWSTR,
+        Win32::Storage::FileSystem::{GetVolumeInformationW, GetVolumePathNameW},
+    };
+
+    const MAX_PATH_CHARS: usize = 261;
+
+    pub fn filesystem_name(directory: &Path) -> Option<String> {
+        let wide: Vec<u16> = directory
+            .as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+        let mut volume_root = [0_u16; MAX_PATH_CHARS];
+        unsafe { GetVolumePathNameW(PCWSTR(wide.as_ptr()), &mut volume_root) }.ok()?;
+
+        let mut filesystem = [0_u16; MAX_PATH_CHARS];
+        unsafe {
+            GetVolumeInformationW(
+                PCWSTR(volume_root.as_ptr()),
+                None,
+                None,
+                None,
+                None,
+                Some(&mut filesystem),
+            )
+        }
+        .ok()?;
+
+        let len = filesystem
+            .iter()
+            .position(|ch| *ch == 0)
+            .unwrap_or(filesystem.len());
+        Some(String::from_utf16_lossy(&filesystem[..len]))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use std::path::Path;
+
+    pub fn filesystem_name(_directory: &Path) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeVolumes(Vec<(&'static str, &'static str)>);
+
+    impl VolumeInfoSource for FakeVolumes {
+        fn filesystem_name(&self, path: &Path) -> Option<String> {
+            self.0
+                .iter()
+                .find(|(prefix, _)| path.starts_with(prefix))
+                .map(|(_, filesystem)| filesystem.to_string())
+        }
+    }
+
+    fn paths() -> Vec<PathBuf> {
+        vec![PathBuf::from("E:/videos/clip.mp4"), PathBuf::from("C:/tmp")]
+    }
+
+    #[test]
+    fn detecta_solo_volumenes_fat_clasicos() {
+        let volumes = FakeVolumes(vec![("E:/", "FAT32"), ("C:/", "NTFS")]);
+        let warnings = detect_file_size_limits(&paths(), &volumes);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].path, "E:/videos/clip.mp4");
+        assert_eq!(warnings[0].filesystem, "FAT32");
+
+        let exfat = FakeVolumes(vec![("E:/", "exFAT"), ("C:/", "NTFS")]);
+        assert!(detect_file_size_limits(&paths(), &exfat).is_empty());
+    }
+
+    #[test]
+    fn la_politica_decide_entre_rechazar_y_advertir() {
+        let volumes = FakeVolumes(vec![("C:/", "FAT32")]);
+
+        let err = check_file_size_limits(&paths(), &Fat32Policy::Reject, &volumes)
+            .expect_err("debio rechazar destino FAT32");
+        assert!(err.contains("C:/tmp"));
+
+        let warnings = check_file_size_limits(&paths(), &Fat32Policy::Segment, &volumes)
+            .expect("debio permitir partiendo la grabación");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].limit_bytes, FAT32_SAFE_FILE_BYTES);
+    }
+
+    #[test]
+    fn el_audio_que_se_agrega_al_final_cuenta_para_el_limite() {
+        let format = OutputFormat::Mkv;
+        let mut audio = AudioCaptureConfig::default();
+        assert_eq!(audio_bytes_per_second(&audio, &format), 0);
+
+        audio.capture_microphone_audio = true;
+        assert_eq!(audio_bytes_per_second(&audio, &format), 20_000);
+        audio.audio_codec = Some(AudioCodec::Pcm);
+        assert_eq!(audio_bytes_per_second(&audio, &format), 192_000);
+        audio.audio_codec = Some(AudioCodec::Flac);
+        audio.audio_sample_rate = Some(96_000);
+        assert_eq!(audio_bytes_per_second(&audio, &format), 768_000);
+
+        let limit = FAT32_SAFE_FILE_BYTES;
+        let video = limit - 500 * 1024 * 1024;
+        assert!(!reached_file_size_limit(limit, video, 0, 3_600_000));
+        assert!(reached_file_size_limit(limit, video, 192_000, 3_600_000));
+    }
+
+    #[test]
+    fn las_partes_numeran_el_nombre_del_archivo() {
+        assert_eq!(
+            part_output_path(Path::new("E:/videos/clip.mp4"), 2),
+            PathBuf::from("E:/videos/clip.part2.mp4")
+        );
+        assert_eq!(
+            part_output_path(Path::new("E:/videos/take_01.final.mkv"), 3),
+            PathBuf::from("E:/videos/take_01.final.part3.mkv")
+        );
+    }
+
+    #[test]
+    fn las_rutas_de_la_sesion_incluyen_cada_parte_abierta() {
+        let output = Path::new("E:/videos/clip.mp4");
+        let parts = SessionParts::default();
+        assert_eq!(parts.paths(output), vec![output.to_path_buf()]);
+
+        parts.clone().record(3);
+        parts.record(2);
+        assert_eq!(
+            parts.paths(output),
+            vec![
+                output.to_path_buf(),
+                PathBuf::from("E:/videos/clip.part2.mp4"),
+                PathBuf::from("E:/videos/clip.part3.mp4"),
+            ]
+        );
+    }
+
+    #[test]
+    fn sin_informacion_de_volumen_no_advierte() {
+        let unknown = FakeVolumes(Vec::new());
+        assert_eq!(
+            check_file_size_limits(&paths(), &Fat32Policy::Reject, &unknown),
+            Ok(Vec::new())
+        );
+    }
+}
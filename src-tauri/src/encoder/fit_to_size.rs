@@ -0,0 +1,284 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use tempfile::Builder as TempBuilder;
+
+use crate::encoder::{
+    media_info::probe_output_file,
+    post_process::{
+        run_ffmpeg_cancellable_with_progress, PostProcessKind, PostProcessTask,
+        POST_PROCESS_CANCELLED_ERR,
+    },
+    processing_status::ProcessingProgress,
+};
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+const CONTAINER_OVERHEAD_PERCENT: u64 = 4;
+const DEFAULT_AUDIO_KBPS: u32 = 128;
+const MIN_AUDIO_KBPS: u32 = 64;
+const MIN_VIDEO_KBPS: u32 = 150;
+const COMPRESSED_SUFFIX: &str = "compressed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FitBitrates {
+    pub video_kbps: u32,
+    pub audio_kbps: u32,
+}
+
+pub fn compute_fit_bitrates(
+    target_mb: u64,
+    duration_secs: f64,
+    has_audio: bool,
+) -> Result<FitBitrates, String> {
+    if !duration_secs.is_finite() || duration_secs <= 0.0 {
+        return Err("No se pudo determinar la duración de la grabación".to_string());
+    }
+
+    let budget_bits = target_mb.saturating_mul(BYTES_PER_MB).saturating_mul(8)
+        * (100 - CONTAINER_OVERHEAD_PERCENT)
+        / 100;
+    let total_kbps = (budget_bits as f64 / duration_secs / 1_000.0).floor() as u64;
+    let total_kbps = u32::try_from(total_kbps).unwrap_or(u32::MAX);
+
+    let mut audio_kbps = if has_audio { DEFAULT_AUDIO_KBPS } else { 0 };
+    if has_audio && total_kbps.saturating_sub(audio_kbps) < MIN_VIDEO_KBPS * 2 {
+        audio_kbps = MIN_AUDIO_KBPS;
+    }
+
+    let video_kbps = total_kbps.saturating_sub(audio_kbps);
+    if video_kbps < MIN_VIDEO_KBPS {
+        return Err(format!(
+            "El tamaño objetivo de {target_mb} MB es demasiado pequeño para {:.0} s de grabación",
+            duration_secs
+        ));
+    }
+
+    Ok(FitBitrates {
+        video_kbps,
+        audio_kbps,
+    })
+}
+
+pub fn compressed_output_path(original_path: &Path) -> PathBuf {
+    let stem = original_path
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .unwrap_or("recording");
+    original_path.with_file_name(format!("{stem}.{COMPRESSED_SUFFIX}.mp4"))
+}
+
+fn null_output() -> &'static str {
+    if cfg!(windows) {
+        "NUL"
+    } else {
+        "/dev/null"
+    }
+}
+
+fn first_pass_args(input: &Path, passlog: &Path, bitrates: &FitBitrates) -> Vec<OsString> {
+    vec![
+        "-i".into(),
+        input.into(),
+        "-c:v".into(),
+        "libx264".into(),
+        "-preset".into(),
+        "medium".into(),
+        "-b:v".into(),
+        format!("{}k", bitrates.video_kbps).into(),
+        "-pass".into(),
+        "1".into(),
+        "-passlogfile".into(),
+        passlog.into(),
+        "-an".into(),
+        "-f".into(),
+        "null".into(),
+        null_output().into(),
+    ]
+}
+
+fn second_pass_args(
+    input: &Path,
+    passlog: &Path,
+    output: &Path,
+    bitrates: &FitBitrates,
+) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec![
+        "-i".into(),
+        input.into(),
+        "-c:v".into(),
+        "libx264".into(),
+        "-preset".into(),
+        "medium".into(),
+        "-b:v".into(),
+        format!("{}k", bitrates.video_kbps).into(),
+        "-pass".into(),
+        "2".into(),
+        "-passlogfile".into(),
+        passlog.into(),
+    ];
+
+    if bitrates.audio_kbps > 0 {
+        args.extend([
+            "-c:a".into(),
+            "aac".into(),
+            "-b:a".into(),
+            format!("{}k", bitrates.audio_kbps).into(),
+        ]);
+    } else {
+        args.push("-an".into());
+    }
+
+    args.extend(["-movflags".into(), "+faststart".into(), output.into()]);
+    args
+}
+
+pub struct FitToSizeJob {
+    original_path: PathBuf,
+    target_mb: u64,
+}
+
+impl FitToSizeJob {
+    pub fn new(original_path: PathBuf, target_mb: u64) -> Self {
+        Self {
+            original_path,
+            target_mb,
+        }
+    }
+
+    pub fn run_logged(self) {
+        let output_path = compressed_output_path(&self.original_path);
+        let task = PostProcessTask::begin(PostProcessKind::FitToSize, &output_path);
+        match self.run(&output_path, &task) {
+            Ok(()) => {}
+            Err(err) if err == POST_PROCESS_CANCELLED_ERR => {
+                eprintln!("[fit-to-size] {err}");
+            }
+            Err(err) => eprintln!("[fit-to-size] Error ajustando tamaño: {err}"),
+        }
+    }
+
+    fn run(&self, output_path: &Path, task: &PostProcessTask) -> Result<(), String> {
+        let Ok(metadata) = fs::metadata(&self.original_path) else {
+            return Ok(());
+        };
+        if metadata.len() <= self.target_mb.saturating_mul(BYTES_PER_MB) {
+            return Ok(());
+        }
+
+        let info = probe_output_file(&self.original_path)?;
+        let bitrates = compute_fit_bitrates(
+            self.target_mb,
+            info.duration_ms as f64 / 1_000.0,
+            !info.audio_codecs.is_empty(),
+        )?;
+
+        let passlog_dir = TempBuilder::new()
+            .prefix("capturist-2pass-")
+            .tempdir()
+            .map_err(|err| format!("No se pudo crear carpeta temporal para 2 pasadas: {err}"))?;
+        let passlog = passlog_dir.path().join("ffmpeg2pass");
+
+        let progress = Arc::new(ProcessingProgress::start());
+        let total_ms = info.duration_ms.saturating_mul(2);
+        let result = run_ffmpeg_cancellable_with_progress(
+            first_pass_args(&self.original_path, &passlog, &bitrates),
+            "analizar la primera pasada",
+            task,
+            &progress,
+            0,
+            total_ms,
+        )
+        .and_then(|_| {
+            run_ffmpeg_cancellable_with_progress(
+                second_pass_args(&self.original_path, &passlog, output_path, &bitrates),
+                "re-codificar la segunda pasada",
+                task,
+                &progress,
+                info.duration_ms,
+                total_ms,
+            )
+        });
+
+        if result.is_err() {
+            let _ = fs::remove_file(output_path);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoder::post_process::args_to_strings;
+
+    #[test]
+    fn bitrate_para_25_mb_en_un_minuto() {
+        let bitrates = compute_fit_bitrates(25, 60.0, true).unwrap();
+
+        assert_eq!(bitrates.audio_kbps, 128);
+        assert_eq!(bitrates.video_kbps, 3_355 - 128);
+    }
+
+    #[test]
+    fn sin_audio_todo_el_presupuesto_va_al_video() {
+        let bitrates = compute_fit_bitrates(25, 60.0, false).unwrap();
+
+        assert_eq!(bitrates.audio_kbps, 0);
+        assert_eq!(bitrates.video_kbps, 3_355);
+    }
+
+    #[test]
+    fn recorta_audio_con_presupuesto_chico_y_rechaza_imposibles() {
+        let tight = compute_fit_bitrates(5, 120.0, true).unwrap();
+        assert_eq!(tight.audio_kbps, 64);
+        assert!(tight.video_kbps >= 150);
+
+        let err = compute_fit_bitrates(1, 600.0, true).expect_err("objetivo imposible");
+        assert!(err.contains("demasiado pequeño"));
+        assert!(compute_fit_bitrates(25, 0.0, true).is_err());
+    }
+
+    #[test]
+    fn arma_ambas_pasadas_con_el_mismo_passlog() {
+        let bitrates = FitBitrates {
+            video_kbps: 2_000,
+            audio_kbps: 96,
+        };
+        let first = args_to_strings(first_pass_args(
+            Path::new("in.mp4"),
+            Path::new("log"),
+            &bitrates,
+        ));
+        let second = args_to_strings(second_pass_args(
+            Path::new("in.mp4"),
+            Path::new("log"),
+            Path::new("in.compressed.mp4"),
+            &bitrates,
+        ));
+
+        assert!(first.windows(2).any(|pair| pair == ["-pass", "1"]));
+        assert!(first.windows(2).any(|pair| pair == ["-b:v", "2000k"]));
+        assert!(first.contains(&"-an".to_string()));
+        assert!(second.windows(2).any(|pair| pair == ["-pass", "2"]));
+        assert!(second
+            .windows(2)
+            .any(|pair| pair == ["-passlogfile", "log"]));
+        assert!(second.windows(2).any(|pair| pair == ["-b:a", "96k"]));
+        assert_eq!(second.last().map(String::as_str), Some("in.compressed.mp4"));
+    }
+
+    #[test]
+    fn ruta_comprimida_junto_al_original() {
+        assert_eq!(
+            compressed_output_path(Path::new("videos/clip.mkv")),
+            PathBuf::from("videos/clip.compressed.mp4")
+        );
+    }
+}
@@ -0,0 +1,63 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+/// Distancia máxima entre dos frames del video en VFR. Windows.Graphics.Capture
+/// no entrega frames con la pantalla quieta; sin repetir el último, algunos
+/// editores calculan mal la duración o el seek de esos tramos.
+pub const HOLD_FRAME_MAX_GAP_MS: i64 = 1_000;
+
+pub fn hold_pts_before(last_pts: i64, next_pts: i64, max_gap_ms: i64) -> Vec<i64> {
+    if last_pts < 0 || max_gap_ms <= 0 {
+        return Vec::new();
+    }
+    (1..)
+        .map(|step| last_pts + step * max_gap_ms)
+        .take_while(|&pts| pts < next_pts)
+        .collect()
+}
+
+pub fn hold_pts_until_stop(last_pts: i64, stop_pts: i64, max_gap_ms: i64) -> Vec<i64> {
+    if last_pts < 0 || stop_pts <= last_pts {
+        return Vec::new();
+    }
+    let mut pts = hold_pts_before(last_pts, stop_pts, max_gap_ms);
+    pts.push(stop_pts);
+    pts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huecos_cortos_no_repiten_frames() {
+        assert!(hold_pts_before(400, 1_400, HOLD_FRAME_MAX_GAP_MS).is_empty());
+        assert!(hold_pts_before(400, 433, HOLD_FRAME_MAX_GAP_MS).is_empty());
+    }
+
+    #[test]
+    fn un_tramo_quieto_largo_se_rellena_cada_segundo() {
+        assert_eq!(
+            hold_pts_before(400, 3_500, HOLD_FRAME_MAX_GAP_MS),
+            vec![1_400, 2_400, 3_400]
+        );
+    }
+
+    #[test]
+    fn al_detener_el_video_llega_al_tiempo_de_parada() {
+        assert_eq!(
+            hold_pts_until_stop(40_000, 42_500, HOLD_FRAME_MAX_GAP_MS),
+            vec![41_000, 42_000, 42_500]
+        );
+        assert_eq!(
+            hold_pts_until_stop(40_000, 40_300, HOLD_FRAME_MAX_GAP_MS),
+            vec![40_300]
+        );
+    }
+
+    #[test]
+    fn sin_frames_o_con_parada_anterior_no_hay_nada_que_repetir() {
+        assert!(hold_pts_until_stop(-1, 5_000, HOLD_FRAME_MAX_GAP_MS).is_empty());
+        assert!(hold_pts_until_stop(5_000, 5_000, HOLD_FRAME_MAX_GAP_MS).is_empty());
+        assert!(hold_pts_until_stop(5_000, 4_000, HOLD_FRAME_MAX_GAP_MS).is_empty());
+    }
+}
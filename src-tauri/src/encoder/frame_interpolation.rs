@@ -0,0 +1,252 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::encoder::{
+    config::QualityMode,
+    media_info::probe_output_file,
+    post_process::{
+        run_ffmpeg_cancellable_with_progress, PostProcessKind, PostProcessTask,
+        POST_PROCESS_CANCELLED_ERR,
+    },
+    processing_status::ProcessingProgress,
+};
+
+const INTERPOLATED_SUFFIX: &str = "interpolated";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpolationMethod {
+    MotionCompensated,
+    Blend,
+}
+
+impl InterpolationMethod {
+    pub fn for_quality_mode(quality_mode: &QualityMode) -> Self {
+        match quality_mode {
+            QualityMode::Performance => Self::Blend,
+            QualityMode::Balanced | QualityMode::Quality => Self::MotionCompensated,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::MotionCompensated => "interpolar frames con minterpolate",
+            Self::Blend => "interpolar frames con tblend",
+        }
+    }
+}
+
+pub fn interpolation_filter(method: InterpolationMethod, target_fps: u32) -> String {
+    match method {
+        InterpolationMethod::MotionCompensated => {
+            format!("minterpolate=fps={target_fps}:mi_mode=mci:mc_mode=aobmc:me_mode=bidir:vsbmc=1")
+        }
+        InterpolationMethod::Blend => format!("fps={target_fps},tblend=all_mode=average"),
+    }
+}
+
+pub fn interpolated_output_path(original_path: &Path) -> PathBuf {
+    let stem = original_path
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .unwrap_or("recording");
+    let ext = original_path
+        .extension()
+        .and_then(|value| value.to_str())
+        .unwrap_or("mp4");
+    original_path.with_file_name(format!("{stem}.{INTERPOLATED_SUFFIX}.{ext}"))
+}
+
+fn is_webm(path: &Path) -> bool {
+    path.extension()
+        .and_then(|value| value.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("webm"))
+}
+
+pub fn interpolation_args(
+    input: &Path,
+    output: &Path,
+    method: InterpolationMethod,
+    target_fps: u32,
+) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec![
+        "-i".into(),
+        input.into(),
+        "-vf".into(),
+        interpolation_filter(method, target_fps).into(),
+        "-r".into(),
+        target_fps.to_string().into(),
+    ];
+
+    if is_webm(output) {
+        args.extend([
+            "-c:v".into(),
+            "libvpx-vp9".into(),
+            "-crf".into(),
+            "32".into(),
+            "-b:v".into(),
+            "0".into(),
+        ]);
+    } else {
+        args.extend([
+            "-c:v".into(),
+            "libx264".into(),
+            "-preset".into(),
+            "medium".into(),
+            "-crf".into(),
+            "18".into(),
+        ]);
+    }
+
+    args.extend(["-c:a".into(), "copy".into(), output.into()]);
+    args
+}
+
+pub struct FrameInterpolationJob {
+    original_path: PathBuf,
+    target_fps: u32,
+    method: InterpolationMethod,
+}
+
+impl FrameInterpolationJob {
+    pub fn new(original_path: PathBuf, target_fps: u32, quality_mode: &QualityMode) -> Self {
+        Self {
+            original_path,
+            target_fps,
+            method: InterpolationMethod::for_quality_mode(quality_mode),
+        }
+    }
+
+    pub fn run_logged(self) {
+        let output_path = interpolated_output_path(&self.original_path);
+        let task = PostProcessTask::begin(PostProcessKind::FrameInterpolation, &output_path);
+        match self.run(&output_path, &task) {
+            Ok(()) => {}
+            Err(err) if err == POST_PROCESS_CANCELLED_ERR => {
+                eprintln!("[interpolation] {err}");
+            }
+            Err(err) => eprintln!("[interpolation] Error interpolando frames: {err}"),
+        }
+    }
+
+    fn run(&self, output_path: &Path, task: &PostProcessTask) -> Result<(), String> {
+        if !self.original_path.is_file() {
+            return Ok(());
+        }
+
+        let info = probe_output_file(&self.original_path)?;
+        let progress = Arc::new(ProcessingProgress::start());
+        let mut result =
+            self.run_method(self.method, output_path, task, &progress, info.duration_ms);
+
+        // `minterpolate` falla con algunas resoluciones y se queda sin memoria con
+        // capturas grandes; se reintenta con la mezcla simple.
+        if self.method == InterpolationMethod::MotionCompensated {
+            if let Err(err) = &result {
+                if err != POST_PROCESS_CANCELLED_ERR {
+                    eprintln!("[interpolation] minterpolate falló, se usa tblend: {err}");
+                    result = self.run_method(
+                        InterpolationMethod::Blend,
+                        output_path,
+                        task,
+                        &progress,
+                        info.duration_ms,
+                    );
+                }
+            }
+        }
+
+        if result.is_err() {
+            let _ = fs::remove_file(output_path);
+        }
+
+        result
+    }
+
+    fn run_method(
+        &self,
+        method: InterpolationMethod,
+        output_path: &Path,
+        task: &PostProcessTask,
+        progress: &Arc<ProcessingProgress>,
+        duration_ms: u64,
+    ) -> Result<(), String> {
+        run_ffmpeg_cancellable_with_progress(
+            interpolation_args(&self.original_path, output_path, method, self.target_fps),
+            method.label(),
+            task,
+            progress,
+            0,
+            duration_ms,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoder::post_process::args_to_strings;
+
+    #[test]
+    fn metodo_segun_modo_de_calidad() {
+        assert_eq!(
+            InterpolationMethod::for_quality_mode(&QualityMode::Performance),
+            InterpolationMethod::Blend
+        );
+        assert_eq!(
+            InterpolationMethod::for_quality_mode(&QualityMode::Quality),
+            InterpolationMethod::MotionCompensated
+        );
+    }
+
+    #[test]
+    fn arma_minterpolate_con_h264_y_audio_copiado() {
+        let args = args_to_strings(interpolation_args(
+            Path::new("clip.mp4"),
+            Path::new("clip.interpolated.mp4"),
+            InterpolationMethod::MotionCompensated,
+            60,
+        ));
+
+        assert!(args.windows(2).any(|pair| pair
+            == [
+                "-vf",
+                "minterpolate=fps=60:mi_mode=mci:mc_mode=aobmc:me_mode=bidir:vsbmc=1"
+            ]));
+        assert!(args.windows(2).any(|pair| pair == ["-r", "60"]));
+        assert!(args.windows(2).any(|pair| pair == ["-c:v", "libx264"]));
+        assert!(args.windows(2).any(|pair| pair == ["-c:a", "copy"]));
+        assert_eq!(
+            args.last().map(String::as_str),
+            Some("clip.interpolated.mp4")
+        );
+    }
+
+    #[test]
+    fn webm_se_recodifica_en_vp9_con_tblend() {
+        let args = args_to_strings(interpolation_args(
+            Path::new("clip.webm"),
+            Path::new("clip.interpolated.webm"),
+            InterpolationMethod::Blend,
+            50,
+        ));
+
+        assert!(args
+            .windows(2)
+            .any(|pair| pair == ["-vf", "fps=50,tblend=all_mode=average"]));
+        assert!(args.windows(2).any(|pair| pair == ["-c:v", "libvpx-vp9"]));
+    }
+
+    #[test]
+    fn ruta_interpolada_conserva_la_extension() {
+        assert_eq!(
+            interpolated_output_path(Path::new("videos/clip.mkv")),
+            PathBuf::from("videos/clip.interpolated.mkv")
+        );
+    }
+}
@@ -0,0 +1,199 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use tempfile::TempDir;
+
+use crate::encoder::{
+    config::GifExportConfig,
+    output_paths::move_temp_to_final,
+    post_process::{ffmpeg_seconds, run_ffmpeg_cancellable, PostProcessKind, PostProcessTask},
+    recording_history::FinishedRecording,
+    trim::ResolvedTrim,
+};
+
+const PALETTE_FILE_NAME: &str = "palette.png";
+
+pub struct GifExportJob {
+    config: GifExportConfig,
+    video_path: PathBuf,
+    final_output_path: PathBuf,
+    temp_dir: TempDir,
+}
+
+impl GifExportJob {
+    pub fn new(
+        config: GifExportConfig,
+        video_path: PathBuf,
+        final_output_path: PathBuf,
+        temp_dir: TempDir,
+    ) -> Self {
+        Self {
+            config,
+            video_path,
+            final_output_path,
+            temp_dir,
+        }
+    }
+
+    pub fn run_detached(self, finished: FinishedRecording) {
+        let task = PostProcessTask::begin(PostProcessKind::GifExport, &self.final_output_path);
+        std::thread::spawn(move || match self.run(&task) {
+            Ok(()) => finished.record(),
+            Err(err) => eprintln!("[gif] Error exportando GIF: {err}"),
+        });
+    }
+
+    fn run(self, task: &PostProcessTask) -> Result<(), String> {
+        if !self.video_path.exists() {
+            return Ok(());
+        }
+
+        let palette_path = self.temp_dir.path().join(PALETTE_FILE_NAME);
+        let result = export_gif(
+            &self.video_path,
+            &palette_path,
+            &self.final_output_path,
+            &self.config,
+            task,
+        );
+
+        if result.is_err() {
+            let _ = fs::remove_file(&self.final_output_path);
+            let fallback_path = self.final_output_path.with_extension("mkv");
+            let _ = move_temp_to_final(&self.video_path, &fallback_path);
+        }
+
+        result
+    }
+}
+
+pub fn export_gif(
+    video_path: &Path,
+    palette_path: &Path,
+    final_output_path: &Path,
+    config: &GifExportConfig,
+    task: &PostProcessTask,
+) -> Result<(), String> {
+    if let Some(parent) = final_output_path.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            format!(
+                "No se pudo crear carpeta de salida '{}': {err}",
+                parent.display()
+            )
+        })?;
+    }
+
+    let input = input_args(video_path, None);
+    run_ffmpeg_cancellable(
+        palette_pass_args(&input, palette_path, config),
+        "generar la paleta del GIF",
+        task,
+    )?;
+    run_ffmpeg_cancellable(
+        encode_pass_args(&input, palette_path, final_output_path, config),
+        "codificar el GIF",
+        task,
+    )
+}
+
+pub fn input_args(video_path: &Path, clip: Option<&ResolvedTrim>) -> Vec<OsString> {
+    let mut args: Vec<OsString> = Vec::new();
+    if let Some(clip) = clip {
+        args.extend([
+            "-ss".into(),
+            ffmpeg_seconds(clip.start_ms).into(),
+            "-t".into(),
+            ffmpeg_seconds(clip.end_ms.saturating_sub(clip.start_ms)).into(),
+        ]);
+    }
+    args.extend(["-i".into(), video_path.into()]);
+    args
+}
+
+pub fn gif_scale_filter(config: &GifExportConfig) -> String {
+    format!(
+        "fps={},scale='min({},iw)':-1:flags=lanczos",
+        config.fps, config.max_width
+    )
+}
+
+pub fn palette_pass_args(
+    input: &[OsString],
+    palette_path: &Path,
+    config: &GifExportConfig,
+) -> Vec<OsString> {
+    let mut args = input.to_vec();
+    args.extend([
+        "-vf".into(),
+        format!("{},palettegen=stats_mode=diff", gif_scale_filter(config)).into(),
+        "-frames:v".into(),
+        "1".into(),
+        palette_path.into(),
+    ]);
+    args
+}
+
+pub fn encode_pass_args(
+    input: &[OsString],
+    palette_path: &Path,
+    final_output_path: &Path,
+    config: &GifExportConfig,
+) -> Vec<OsString> {
+    let mut args = input.to_vec();
+    args.extend([
+        "-i".into(),
+        palette_path.into(),
+        "-filter_complex".into(),
+        format!(
+            "[0:v]{}[x];[x][1:v]paletteuse=dither=bayer:bayer_scale=5:diff_mode=rectangle",
+            gif_scale_filter(config)
+        )
+        .into(),
+        "-loop".into(),
+        "0".into(),
+        "-f".into(),
+        "gif".into(),
+        final_output_path.into(),
+    ]);
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoder::post_process::args_to_strings;
+
+    #[test]
+    fn filtro_de_escala_no_amplia_el_video() {
+        let config = GifExportConfig {
+            fps: 12,
+            max_width: 640,
+        };
+
+        assert_eq!(
+            gif_scale_filter(&config),
+            "fps=12,scale='min(640,iw)':-1:flags=lanczos"
+        );
+    }
+
+    #[test]
+    fn segunda_pasada_usa_la_paleta_generada() {
+        let config = GifExportConfig::default();
+        let args = args_to_strings(encode_pass_args(
+            &input_args(Path::new("in.mkv"), None),
+            Path::new("palette.png"),
+            Path::new("out.gif"),
+            &config,
+        ));
+
+        assert_eq!(&args[..4], ["-i", "in.mkv", "-i", "palette.png"]);
+        assert!(args[5].starts_with("[0:v]fps=15,"));
+        assert!(args[5].contains("[x][1:v]paletteuse="));
+        assert_eq!(args.last().map(String::as_str), Some("out.gif"));
+    }
+}
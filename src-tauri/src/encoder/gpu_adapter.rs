@@ -0,0 +1,196 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+const VENDOR_NVIDIA: u32 = 0x10DE;
+const VENDOR_AMD: u32 = 0x1002;
+const VENDOR_INTEL: u32 = 0x8086;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuAdapter {
+    pub luid: u64,
+    pub vendor_id: u32,
+    pub description: String,
+}
+
+pub fn luid_to_u64(low_part: u32, high_part: i32) -> u64 {
+    (u64::from(high_part as u32) << 32) | u64::from(low_part)
+}
+
+fn backend_vendor_id(backend_label: &str) -> Option<u32> {
+    match backend_label {
+        "NVENC" => Some(VENDOR_NVIDIA),
+        "AMF" => Some(VENDOR_AMD),
+        "QSV" => Some(VENDOR_INTEL),
+        _ => None,
+    }
+}
+
+pub fn encoder_adapter_for_backend<'a>(
+    adapters: &'a [GpuAdapter],
+    backend_label: &str,
+) -> Option<&'a GpuAdapter> {
+    let vendor_id = backend_vendor_id(backend_label)?;
+    adapters
+        .iter()
+        .find(|adapter| adapter.vendor_id == vendor_id)
+}
+
+/// La textura capturada solo puede entregarse al encoder si ambos viven en el
+/// mismo adaptador; en laptops híbridas la captura suele quedar en la iGPU.
+pub fn ensure_same_adapter(
+    capture: &GpuAdapter,
+    encoder: &GpuAdapter,
+    backend_label: &str,
+) -> Result<(), String> {
+    if capture.luid == encoder.luid {
+        return Ok(());
+    }
+
+    Err(format!(
+        "La captura usa la GPU '{}' pero el encoder {backend_label} está en la GPU '{}'. \
+         En equipos con GPU híbrida la textura no puede pasar entre adaptadores: desactiva \
+         la entrada GPU o asigna Capturist a una sola GPU en la configuración de gráficos de Windows",
+        capture.description, encoder.description
+    ))
+}
+
+/// Anticipa el cruce de adaptadores antes de iniciar: windows-capture crea su
+/// device D3D11 en el adaptador por defecto (el primero enumerado).
+pub fn predict_adapter_mismatch(adapters: &[GpuAdapter], backend_label: &str) -> Option<String> {
+    let capture = adapters.first()?;
+    let encoder = encoder_adapter_for_backend(adapters, backend_label)?;
+    ensure_same_adapter(capture, encoder, backend_label).err()
+}
+
+#[cfg_attr(not(target_os = "windows"), allow(unused_imports))]
+pub use platform::{enumerate_adapters, texture_adapter};
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::mem::ManuallyDrop;
+
+    use windows::{
+        core::Interface,
+        Win32::Graphics::{
+            Direct3D11::ID3D11Texture2D,
+            Dxgi::{CreateDXGIFactory1, IDXGIDevice, IDXGIFactory1},
+        },
+    };
+
+    use super::{luid_to_u64, GpuAdapter};
+
+    fn description_from_utf16(raw: &[u16]) -> String {
+        let len = raw.iter().position(|ch| *ch == 0).unwrap_or(raw.len());
+        String::from_utf16_lossy(&raw[..len]).trim().to_string()
+    }
+
+    pub fn enumerate_adapters() -> Vec<GpuAdapter> {
+        let Ok(factory) = (unsafe { CreateDXGIFactory1::<IDXGIFactory1>() }) else {
+            return Vec::new();
+        };
+
+        let mut adapters = Vec::new();
+        let mut index = 0;
+        while let Ok(adapter) = unsafe { factory.EnumAdapters1(index) } {
+            if let Ok(desc) = unsafe { adapter.GetDesc1() } {
+                adapters.push(GpuAdapter {
+                    luid: luid_to_u64(desc.AdapterLuid.LowPart, desc.AdapterLuid.HighPart),
+                    vendor_id: desc.VendorId,
+                    description: description_from_utf16(&desc.Description),
+                });
+            }
+            index += 1;
+        }
+
+        adapters
+    }
+
+    pub fn texture_adapter(texture_ptr: usize) -> Result<GpuAdapter, String> {
+        if texture_ptr == 0 {
+            return Err("Textura D3D11 nula".to_string());
+        }
+
+        let texture =
+            ManuallyDrop::new(unsafe { ID3D11Texture2D::from_raw(texture_ptr as *mut _) });
+        let device = unsafe { texture.GetDevice() }
+            .map_err(|err| format!("No se pudo obtener el device de la textura: {err}"))?;
+        let dxgi_device: IDXGIDevice = device
+            .cast()
+            .map_err(|err| format!("El device de la textura no expone IDXGIDevice: {err}"))?;
+        let adapter = unsafe { dxgi_device.GetAdapter() }
+            .map_err(|err| format!("No se pudo obtener el adaptador de la textura: {err}"))?;
+        let desc = unsafe { adapter.GetDesc() }
+            .map_err(|err| format!("No se pudo leer el adaptador de la textura: {err}"))?;
+
+        Ok(GpuAdapter {
+            luid: luid_to_u64(desc.AdapterLuid.LowPart, desc.AdapterLuid.HighPart),
+            vendor_id: desc.VendorId,
+            description: description_from_utf16(&desc.Description),
+        })
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::GpuAdapter;
+
+    pub fn enumerate_adapters() -> Vec<GpuAdapter> {
+        Vec::new()
+    }
+
+    pub fn texture_adapter(_texture_ptr: usize) -> Result<GpuAdapter, String> {
+        Err("Las texturas D3D11 solo están disponibles en Windows".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(luid: u64, vendor_id: u32, description: &str) -> GpuAdapter {
+        GpuAdapter {
+            luid,
+            vendor_id,
+            description: description.to_string(),
+        }
+    }
+
+    fn hybrid_laptop() -> Vec<GpuAdapter> {
+        vec![
+            adapter(0x1_0000_0010, VENDOR_INTEL, "Intel(R) UHD Graphics"),
+            adapter(
+                0x1_0000_0020,
+                VENDOR_NVIDIA,
+                "NVIDIA GeForce RTX 3060 Laptop GPU",
+            ),
+        ]
+    }
+
+    #[test]
+    fn combina_las_dos_mitades_del_luid() {
+        assert_eq!(luid_to_u64(0x10, 1), 0x1_0000_0010);
+        assert_eq!(luid_to_u64(u32::MAX, -1), u64::MAX);
+        assert_ne!(luid_to_u64(5, 0), luid_to_u64(5, 1));
+    }
+
+    #[test]
+    fn error_de_gpu_hibrida_nombra_ambos_adaptadores() {
+        let adapters = hybrid_laptop();
+        let encoder = encoder_adapter_for_backend(&adapters, "NVENC").unwrap();
+
+        let err = ensure_same_adapter(&adapters[0], encoder, "NVENC").expect_err("cruce");
+        assert!(err.contains("Intel(R) UHD Graphics"));
+        assert!(err.contains("NVIDIA GeForce RTX 3060 Laptop GPU"));
+
+        assert!(ensure_same_adapter(encoder, encoder, "NVENC").is_ok());
+    }
+
+    #[test]
+    fn anticipa_el_cruce_solo_si_el_encoder_esta_en_otra_gpu() {
+        let adapters = hybrid_laptop();
+
+        assert!(predict_adapter_mismatch(&adapters, "NVENC").is_some());
+        assert_eq!(predict_adapter_mismatch(&adapters, "QSV"), None);
+        assert_eq!(predict_adapter_mismatch(&adapters, "AMF"), None);
+        assert_eq!(predict_adapter_mismatch(&[], "NVENC"), None);
+    }
+}
@@ -0,0 +1,455 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+#[cfg(target_os = "windows")]
+pub use platform::{prepare_capture_device, D3d11Scaler};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalingPath {
+    None,
+    Gpu,
+    Cpu,
+}
+
+impl ScalingPath {
+    pub fn resolve(source: (u32, u32), output: (u32, u32), gpu_input: bool) -> Self {
+        if source == output {
+            ScalingPath::None
+        } else if gpu_input {
+            ScalingPath::Gpu
+        } else {
+            ScalingPath::Cpu
+        }
+    }
+}
+
+pub fn live_encoder_label(backend_label: &str, codec_label: &str, scaling: ScalingPath) -> String {
+    match scaling {
+        ScalingPath::None => format!("{backend_label} / {codec_label}"),
+        ScalingPath::Gpu => format!("{backend_label} / {codec_label}, escala GPU"),
+        ScalingPath::Cpu => format!("{backend_label} / {codec_label}, escala CPU"),
+    }
+}
+
+/// Si la captura entrega texturas D3D11 o bytes BGRA. La comparten captura y
+/// encoder: cualquiera de los dos la pasa a CPU si la GPU no puede escalar, y
+/// desde el frame siguiente llegan bytes. Clonar comparte la misma ruta.
+#[derive(Debug, Clone, Default)]
+pub struct GpuFrameRoute {
+    gpu: Arc<AtomicBool>,
+    needs_scaling: bool,
+}
+
+impl GpuFrameRoute {
+    pub fn new(gpu: bool, needs_scaling: bool) -> Self {
+        Self {
+            gpu: Arc::new(AtomicBool::new(gpu)),
+            needs_scaling,
+        }
+    }
+
+    pub fn is_gpu(&self) -> bool {
+        self.gpu.load(Ordering::Acquire)
+    }
+
+    pub fn needs_scaling(&self) -> bool {
+        self.needs_scaling
+    }
+
+    pub fn fall_back_to_cpu(&self) -> bool {
+        self.gpu.swap(false, Ordering::AcqRel)
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{ffi::c_void, mem::ManuallyDrop};
+
+    use windows::{
+        core::Interface,
+        Win32::{
+            Foundation::RECT,
+            Graphics::{
+                Direct3D11::{
+                    ID3D11Device, ID3D11DeviceContext, ID3D11Multithread, ID3D11Texture2D,
+                    ID3D11VideoContext, ID3D11VideoDevice, ID3D11VideoProcessor,
+                    ID3D11VideoProcessorEnumerator, ID3D11VideoProcessorInputView,
+                    ID3D11VideoProcessorOutputView, D3D11_BIND_RENDER_TARGET,
+                    D3D11_CPU_ACCESS_READ, D3D11_MAPPED_SUBRESOURCE, D3D11_MAP_READ,
+                    D3D11_SUBRESOURCE_DATA, D3D11_TEX2D_VPIV, D3D11_TEX2D_VPOV,
+                    D3D11_TEXTURE2D_DESC, D3D11_USAGE_DEFAULT, D3D11_USAGE_STAGING,
+                    D3D11_VIDEO_FRAME_FORMAT_PROGRESSIVE, D3D11_VIDEO_PROCESSOR_CONTENT_DESC,
+                    D3D11_VIDEO_PROCESSOR_FORMAT_SUPPORT_INPUT,
+                    D3D11_VIDEO_PROCESSOR_FORMAT_SUPPORT_OUTPUT,
+                    D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC, D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC_0,
+                    D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC,
+                    D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC_0, D3D11_VIDEO_PROCESSOR_STREAM,
+                    D3D11_VIDEO_USAGE_OPTIMAL_SPEED, D3D11_VPIV_DIMENSION_TEXTURE2D,
+                    D3D11_VPOV_DIMENSION_TEXTURE2D,
+                },
+                Dxgi::Common::{
+                    DXGI_FORMAT, DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_RATIONAL, DXGI_SAMPLE_DESC,
+                },
+            },
+        },
+    };
+
+    use super::GpuFrameRoute;
+
+    /// Se llama con el device de windows-capture antes del primer frame. El
+    /// encoder usa su contexto desde otro hilo, así que se protege antes de
+    /// que la captura lo use; si hace falta escalar, el procesador de video
+    /// se prueba en ese mismo adaptador y sin él la ruta pasa a CPU.
+    pub fn prepare_capture_device(device: &ID3D11Device, route: &GpuFrameRoute) {
+        if !route.is_gpu() {
+            return;
+        }
+        if let Ok(context) = unsafe { device.GetImmediateContext() } {
+            if let Ok(multithread) = context.cast::<ID3D11Multithread>() {
+                unsafe {
+                    let _ = multithread.SetMultithreadProtected(true);
+                }
+            }
+        }
+        if !route.needs_scaling() {
+            return;
+        }
+        if let Err(err) = probe_video_processor(device) {
+            if route.fall_back_to_cpu() {
+                eprintln!("[capture] La GPU de la captura no puede reescalar ({err}). Se usa la ruta CPU.");
+            }
+        }
+    }
+
+    pub struct D3d11Scaler {
+        device: ID3D11Device,
+        context: ID3D11DeviceContext,
+        video_device: ID3D11VideoDevice,
+        video_context: ID3D11VideoContext,
+        enumerator: ID3D11VideoProcessorEnumerator,
+        processor: ID3D11VideoProcessor,
+        input_desc: D3D11_TEXTURE2D_DESC,
+        output_desc: D3D11_TEXTURE2D_DESC,
+        staging: Option<ID3D11Texture2D>,
+    }
+
+    impl D3d11Scaler {
+        pub fn new(texture_ptr: usize, out_w: u32, out_h: u32) -> Result<Self, String> {
+            if texture_ptr == 0 {
+                return Err("Textura D3D11 nula".to_string());
+            }
+            let texture =
+                ManuallyDrop::new(unsafe { ID3D11Texture2D::from_raw(texture_ptr as *mut _) });
+            let mut input_desc = D3D11_TEXTURE2D_DESC::default();
+            unsafe { texture.GetDesc(&mut input_desc) };
+            let device = unsafe { texture.GetDevice() }
+                .map_err(|err| format!("No se pudo obtener el device de la textura: {err}"))?;
+
+            let video_device: ID3D11VideoDevice = device
+                .cast()
+                .map_err(|err| format!("El device D3D11 no admite video: {err}"))?;
+            let (enumerator, processor) = create_processor(
+                &video_device,
+                (input_desc.Width, input_desc.Height),
+                (out_w, out_h),
+                input_desc.Format,
+            )?;
+            let context = unsafe { device.GetImmediateContext() }
+                .map_err(|err| format!("No se pudo obtener el contexto D3D11: {err}"))?;
+            let video_context: ID3D11VideoContext = context
+                .cast()
+                .map_err(|err| format!("El contexto D3D11 no admite video: {err}"))?;
+
+            let source_rect = rect(input_desc.Width, input_desc.Height);
+            let target_rect = rect(out_w, out_h);
+            unsafe {
+                video_context.VideoProcessorSetStreamFrameFormat(
+                    &processor,
+                    0,
+                    D3D11_VIDEO_FRAME_FORMAT_PROGRESSIVE,
+                );
+                video_context.VideoProcessorSetStreamSourceRect(
+                    &processor,
+                    0,
+                    true,
+                    Some(&source_rect),
+                );
+                video_context.VideoProcessorSetStreamDestRect(
+                    &processor,
+                    0,
+                    true,
+                    Some(&target_rect),
+                );
+                video_context.VideoProcessorSetOutputTargetRect(
+                    &processor,
+                    true,
+                    Some(&target_rect),
+                );
+                video_context.VideoProcessorSetStreamAutoProcessingMode(&processor, 0, false);
+            }
+
+            Ok(Self {
+                device,
+                context,
+                video_device,
+                video_context,
+                enumerator,
+                processor,
+                input_desc,
+                output_desc: D3D11_TEXTURE2D_DESC {
+                    Width: out_w,
+                    Height: out_h,
+                    MipLevels: 1,
+                    ArraySize: 1,
+                    Format: input_desc.Format,
+                    SampleDesc: DXGI_SAMPLE_DESC {
+                        Count: 1,
+                        Quality: 0,
+                    },
+                    Usage: D3D11_USAGE_DEFAULT,
+                    BindFlags: D3D11_BIND_RENDER_TARGET.0 as u32,
+                    CPUAccessFlags: 0,
+                    MiscFlags: 0,
+                },
+                staging: None,
+            })
+        }
+
+        pub fn input_size(&self) -> (u32, u32) {
+            (self.input_desc.Width, self.input_desc.Height)
+        }
+
+        pub fn output_size(&self) -> (u32, u32) {
+            (self.output_desc.Width, self.output_desc.Height)
+        }
+
+        /// Devuelve una textura nueva del tamaño de salida, con una referencia
+        /// propia para el encoder, y consume la de `texture_ptr` solo si el
+        /// escalado sale bien: si falla, el frame se puede bajar a CPU.
+        /// Cada frame usa su textura: el encoder puede retener varias a la vez.
+        pub fn scale(&self, texture_ptr: usize) -> Result<usize, String> {
+            let input =
+                ManuallyDrop::new(unsafe { ID3D11Texture2D::from_raw(texture_ptr as *mut c_void) });
+
+            let mut output: Option<ID3D11Texture2D> = None;
+            unsafe {
+                self.device
+                    .CreateTexture2D(&self.output_desc, None, Some(&mut output))
+                    .map_err(|err| format!("No se pudo crear la textura escalada: {err}"))?;
+            }
+            let output =
+                output.ok_or_else(|| "No se pudo crear la textura escalada".to_string())?;
+
+            let mut input_view: Option<ID3D11VideoProcessorInputView> = None;
+            let mut output_view: Option<ID3D11VideoProcessorOutputView> = None;
+            unsafe {
+                self.video_device
+                    .CreateVideoProcessorInputView(
+                        &*input,
+                        &self.enumerator,
+                        &D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC {
+                            FourCC: 0,
+                            ViewDimension: D3D11_VPIV_DIMENSION_TEXTURE2D,
+                            Anonymous: D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC_0 {
+                                Texture2D: D3D11_TEX2D_VPIV {
+                                    MipSlice: 0,
+                                    ArraySlice: 0,
+                                },
+                            },
+                        },
+                        Some(&mut input_view),
+                    )
+                    .map_err(|err| format!("No se pudo preparar la textura a escalar: {err}"))?;
+                self.video_device
+                    .CreateVideoProcessorOutputView(
+                        &output,
+                        &self.enumerator,
+                        &D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC {
+                            ViewDimension: D3D11_VPOV_DIMENSION_TEXTURE2D,
+                            Anonymous: D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC_0 {
+                                Texture2D: D3D11_TEX2D_VPOV { MipSlice: 0 },
+                            },
+                        },
+                        Some(&mut output_view),
+                    )
+                    .map_err(|err| format!("No se pudo preparar la textura escalada: {err}"))?;
+            }
+            let output_view =
+                output_view.ok_or_else(|| "No se pudo preparar la textura escalada".to_string())?;
+
+            let mut stream = D3D11_VIDEO_PROCESSOR_STREAM {
+                Enable: true.into(),
+                pInputSurface: ManuallyDrop::new(input_view),
+                ..Default::default()
+            };
+            let result = unsafe {
+                self.video_context.VideoProcessorBlt(
+                    &self.processor,
+                    &output_view,
+                    0,
+                    std::slice::from_ref(&stream),
+                )
+            };
+            unsafe { ManuallyDrop::drop(&mut stream.pInputSurface) };
+            result.map_err(|err| format!("Error escalando el frame en GPU: {err}"))?;
+
+            drop(ManuallyDrop::into_inner(input));
+            Ok(output.into_raw() as usize)
+        }
+
+        pub fn read_back(&mut self, texture_ptr: usize) -> Result<(Vec<u8>, usize), String> {
+            let input = unsafe { ID3D11Texture2D::from_raw(texture_ptr as *mut c_void) };
+            let staging = match &self.staging {
+                Some(staging) => staging.clone(),
+                None => {
+                    let desc = D3D11_TEXTURE2D_DESC {
+                        Usage: D3D11_USAGE_STAGING,
+                        BindFlags: 0,
+                        CPUAccessFlags: D3D11_CPU_ACCESS_READ.0 as u32,
+                        MiscFlags: 0,
+                        ..self.input_desc
+                    };
+                    let mut staging: Option<ID3D11Texture2D> = None;
+                    unsafe { self.device.CreateTexture2D(&desc, None, Some(&mut staging)) }
+                        .map_err(|err| format!("No se pudo crear la textura de lectura: {err}"))?;
+                    let staging = staging
+                        .ok_or_else(|| "No se pudo crear la textura de lectura".to_string())?;
+                    self.staging = Some(staging.clone());
+                    staging
+                }
+            };
+
+            let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+            unsafe {
+                self.context.CopyResource(&staging, &input);
+                self.context
+                    .Map(&staging, 0, D3D11_MAP_READ, 0, Some(&mut mapped))
+                    .map_err(|err| format!("No se pudo leer el frame de la GPU: {err}"))?;
+            }
+            let row_stride_bytes = mapped.RowPitch as usize;
+            let len = row_stride_bytes * self.input_desc.Height as usize;
+            let bytes =
+                unsafe { std::slice::from_raw_parts(mapped.pData as *const u8, len) }.to_vec();
+            unsafe { self.context.Unmap(&staging, 0) };
+            Ok((bytes, row_stride_bytes))
+        }
+
+        pub fn upload(&self, data: &[u8], row_stride_bytes: usize) -> Result<usize, String> {
+            let initial_data = D3D11_SUBRESOURCE_DATA {
+                pSysMem: data.as_ptr() as *const c_void,
+                SysMemPitch: row_stride_bytes as u32,
+                SysMemSlicePitch: 0,
+            };
+            let mut output: Option<ID3D11Texture2D> = None;
+            unsafe {
+                self.device
+                    .CreateTexture2D(&self.output_desc, Some(&initial_data), Some(&mut output))
+                    .map_err(|err| format!("No se pudo subir el frame escalado: {err}"))?;
+            }
+            let output = output.ok_or_else(|| "No se pudo subir el frame escalado".to_string())?;
+            Ok(output.into_raw() as usize)
+        }
+    }
+
+    fn rect(width: u32, height: u32) -> RECT {
+        RECT {
+            left: 0,
+            top: 0,
+            right: width as i32,
+            bottom: height as i32,
+        }
+    }
+
+    fn create_processor(
+        video_device: &ID3D11VideoDevice,
+        (src_w, src_h): (u32, u32),
+        (out_w, out_h): (u32, u32),
+        format: DXGI_FORMAT,
+    ) -> Result<(ID3D11VideoProcessorEnumerator, ID3D11VideoProcessor), String> {
+        let frame_rate = DXGI_RATIONAL {
+            Numerator: 60,
+            Denominator: 1,
+        };
+        let enumerator = unsafe {
+            video_device.CreateVideoProcessorEnumerator(&D3D11_VIDEO_PROCESSOR_CONTENT_DESC {
+                InputFrameFormat: D3D11_VIDEO_FRAME_FORMAT_PROGRESSIVE,
+                InputFrameRate: frame_rate,
+                InputWidth: src_w,
+                InputHeight: src_h,
+                OutputFrameRate: frame_rate,
+                OutputWidth: out_w,
+                OutputHeight: out_h,
+                Usage: D3D11_VIDEO_USAGE_OPTIMAL_SPEED,
+            })
+        }
+        .map_err(|err| format!("No se pudo crear el procesador de video: {err}"))?;
+
+        let support = unsafe { enumerator.CheckVideoProcessorFormat(format) }.unwrap_or(0);
+        let required = (D3D11_VIDEO_PROCESSOR_FORMAT_SUPPORT_INPUT.0
+            | D3D11_VIDEO_PROCESSOR_FORMAT_SUPPORT_OUTPUT.0) as u32;
+        if support & required != required {
+            return Err(format!(
+                "El procesador de video no admite el formato {:?}",
+                format.0
+            ));
+        }
+
+        let processor = unsafe { video_device.CreateVideoProcessor(&enumerator, 0) }
+            .map_err(|err| format!("No se pudo crear el procesador de video: {err}"))?;
+        Ok((enumerator, processor))
+    }
+
+    fn probe_video_processor(device: &ID3D11Device) -> Result<(), String> {
+        let video_device: ID3D11VideoDevice = device
+            .cast()
+            .map_err(|err| format!("El device D3D11 no admite video: {err}"))?;
+
+        create_processor(
+            &video_device,
+            (3840, 2160),
+            (1920, 1080),
+            DXGI_FORMAT_B8G8R8A8_UNORM,
+        )
+        .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn etiqueta_indica_donde_se_escala() {
+        let gpu = ScalingPath::resolve((3840, 2160), (1920, 1080), true);
+        assert_eq!(
+            live_encoder_label("NVENC", "H.264", gpu),
+            "NVENC / H.264, escala GPU"
+        );
+
+        let cpu = ScalingPath::resolve((3840, 2160), (1920, 1080), false);
+        assert_eq!(
+            live_encoder_label("QSV", "H.265", cpu),
+            "QSV / H.265, escala CPU"
+        );
+
+        let native = ScalingPath::resolve((1920, 1080), (1920, 1080), true);
+        assert_eq!(native, ScalingPath::None);
+        assert_eq!(live_encoder_label("AMF", "H.264", native), "AMF / H.264");
+    }
+
+    #[test]
+    fn la_ruta_pasa_a_cpu_una_sola_vez_para_todos_los_clones() {
+        let route = GpuFrameRoute::new(true, true);
+        let capture = route.clone();
+
+        assert!(capture.is_gpu());
+        assert!(route.fall_back_to_cpu());
+        assert!(!capture.is_gpu());
+        assert!(!capture.fall_back_to_cpu());
+        assert!(!GpuFrameRoute::default().is_gpu());
+    }
+}
@@ -0,0 +1,319 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{num::NonZeroUsize, ops::Range, sync::OnceLock, thread};
+
+use crate::capture::models::{FramePixelFormat, RawFrame};
+
+const SCRGB_WHITE_NITS: f32 = 80.0;
+const SDR_WHITE_NITS: f32 = 203.0;
+const PQ_MAX_NITS: f32 = 10_000.0;
+
+const PQ_M1: f32 = 2610.0 / 16384.0;
+const PQ_M2: f32 = 2523.0 / 4096.0 * 128.0;
+const PQ_C1: f32 = 3424.0 / 4096.0;
+const PQ_C2: f32 = 2413.0 / 4096.0 * 32.0;
+const PQ_C3: f32 = 2392.0 / 4096.0 * 32.0;
+
+const BT709_TO_BT2020: [[f32; 3]; 3] = [
+    [0.6274, 0.3293, 0.0433],
+    [0.0691, 0.9195, 0.0114],
+    [0.0164, 0.0880, 0.8956],
+];
+const KR: f32 = 0.2627;
+const KB: f32 = 0.0593;
+const MAX_CONVERT_THREADS: usize = 8;
+
+/// Planos de un frame P010: 10 bits en la parte alta de cada u16 LE, con el
+/// croma intercalado (Cb, Cr) a la mitad de resolución.
+pub struct P010Planes<'a> {
+    pub luma: &'a mut [u8],
+    pub luma_stride: usize,
+    pub chroma: &'a mut [u8],
+    pub chroma_stride: usize,
+}
+
+/// Convierte frames de la captura a BT.2020 con curva PQ en rango limitado.
+/// Las curvas van en tablas indexadas por el half float del valor lineal, así
+/// no hay `powf` por píxel.
+pub struct HdrConverter {
+    half_to_linear: Vec<f32>,
+    pq_by_half: Vec<f32>,
+    srgb_to_linear: [f32; 256],
+}
+
+impl HdrConverter {
+    pub fn shared() -> &'static HdrConverter {
+        static CONVERTER: OnceLock<HdrConverter> = OnceLock::new();
+        CONVERTER.get_or_init(HdrConverter::new)
+    }
+
+    fn new() -> Self {
+        let half_to_linear: Vec<f32> = (0..=u16::MAX).map(f16_to_f32).collect();
+        let pq_by_half = half_to_linear
+            .iter()
+            .map(|linear| pq_encode(linear * SCRGB_WHITE_NITS / PQ_MAX_NITS))
+            .collect();
+        let mut srgb_to_linear = [0.0; 256];
+        for (code, linear) in srgb_to_linear.iter_mut().enumerate() {
+            *linear = srgb_eotf(code as f32 / 255.0) * SDR_WHITE_NITS / SCRGB_WHITE_NITS;
+        }
+
+        Self {
+            half_to_linear,
+            pq_by_half,
+            srgb_to_linear,
+        }
+    }
+
+    pub fn convert(&self, frame: &RawFrame, planes: P010Planes<'_>) -> Result<(), String> {
+        if !frame.is_cpu_layout_valid() {
+            return Err("Frame inválido para la conversión HDR".to_string());
+        }
+        let (width, height) = (frame.width as usize, frame.height as usize);
+        let (chroma_w, chroma_h) = (width.div_ceil(2), height.div_ceil(2));
+        if planes.luma.len() < (height - 1) * planes.luma_stride + width * 2
+            || planes.chroma.len() < (chroma_h - 1) * planes.chroma_stride + chroma_w * 4
+        {
+            return Err("El frame P010 de destino es más chico que la captura".to_string());
+        }
+
+        // Cada banda de filas de croma escribe sus dos filas de luma, así que
+        // los hilos no comparten bytes del destino.
+        let threads = thread::available_parallelism()
+            .map_or(1, NonZeroUsize::get)
+            .min(MAX_CONVERT_THREADS);
+        let band_rows = chroma_h.div_ceil(threads);
+        let (luma_stride, chroma_stride) = (planes.luma_stride, planes.chroma_stride);
+        thread::scope(|scope| {
+            let bands = planes
+                .luma
+                .chunks_mut(band_rows * 2 * luma_stride)
+                .zip(planes.chroma.chunks_mut(band_rows * chroma_stride));
+            for (band, (luma, chroma)) in bands.enumerate() {
+                let first_row = band * band_rows;
+                let rows = first_row.min(chroma_h)..(first_row + band_rows).min(chroma_h);
+                let planes = P010Planes {
+                    luma,
+                    luma_stride,
+                    chroma,
+                    chroma_stride,
+                };
+                scope.spawn(move || self.convert_rows(frame, planes, rows));
+            }
+        });
+        Ok(())
+    }
+
+    fn convert_rows(&self, frame: &RawFrame, planes: P010Planes<'_>, chroma_rows: Range<usize>) {
+        let (width, height) = (frame.width as usize, frame.height as usize);
+        let chroma_w = width.div_ceil(2);
+        let first_row = chroma_rows.start;
+        for chroma_y in chroma_rows {
+            let band_y = chroma_y - first_row;
+            for chroma_x in 0..chroma_w {
+                let (mut cb_sum, mut cr_sum, mut samples) = (0.0, 0.0, 0.0);
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let (x, y) = (chroma_x * 2 + dx, chroma_y * 2 + dy);
+                    if x >= width || y >= height {
+                        continue;
+                    }
+                    let [r, g, b] = self.encode_pixel(self.linear_pixel(frame, x, y));
+                    let luma = KR * r + (1.0 - KR - KB) * g + KB * b;
+                    write_p010(
+                        planes.luma,
+                        (band_y * 2 + dy) * planes.luma_stride + x * 2,
+                        64.0 + 876.0 * luma,
+                    );
+                    cb_sum += (b - luma) / (2.0 * (1.0 - KB));
+                    cr_sum += (r - luma) / (2.0 * (1.0 - KR));
+                    samples += 1.0;
+                }
+                let offset = band_y * planes.chroma_stride + chroma_x * 4;
+                write_p010(planes.chroma, offset, 512.0 + 896.0 * cb_sum / samples);
+                write_p010(planes.chroma, offset + 2, 512.0 + 896.0 * cr_sum / samples);
+            }
+        }
+    }
+
+    fn linear_pixel(&self, frame: &RawFrame, x: usize, y: usize) -> [f32; 3] {
+        let offset =
+            y * frame.row_stride_bytes as usize + x * frame.pixel_format.bytes_per_pixel() as usize;
+        let data = &frame.data[offset..];
+        match frame.pixel_format {
+            FramePixelFormat::Rgba16F => {
+                let channel = |index: usize| {
+                    let bits = u16::from_le_bytes([data[index * 2], data[index * 2 + 1]]);
+                    self.half_to_linear[bits as usize]
+                };
+                [channel(0), channel(1), channel(2)]
+            }
+            FramePixelFormat::Bgra8 => [
+                self.srgb_to_linear[data[2] as usize],
+                self.srgb_to_linear[data[1] as usize],
+                self.srgb_to_linear[data[0] as usize],
+            ],
+        }
+    }
+
+    fn encode_pixel(&self, linear: [f32; 3]) -> [f32; 3] {
+        BT709_TO_BT2020.map(|row| {
+            let mixed = row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2];
+            self.pq_by_half[f32_to_f16_bits(mixed) as usize]
+        })
+    }
+}
+
+fn write_p010(plane: &mut [u8], offset: usize, code: f32) {
+    let code = code.round().clamp(0.0, 1023.0) as u16;
+    plane[offset..offset + 2].copy_from_slice(&(code << 6).to_le_bytes());
+}
+
+fn pq_encode(normalized: f32) -> f32 {
+    if normalized.is_nan() || normalized <= 0.0 {
+        return 0.0;
+    }
+    let power = normalized.min(1.0).powf(PQ_M1);
+    ((PQ_C1 + PQ_C2 * power) / (1.0 + PQ_C3 * power)).powf(PQ_M2)
+}
+
+fn srgb_eotf(encoded: f32) -> f32 {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f32::from(bits & 0x3ff);
+    let magnitude = match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        31 if mantissa == 0.0 => f32::INFINITY,
+        31 => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    };
+    sign * magnitude
+}
+
+/// Trunca la mantisa y lleva los subnormales a cero: la tabla PQ no necesita
+/// más precisión que el half float de la captura.
+fn f32_to_f16_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    if exponent <= 0 {
+        return sign;
+    }
+    if exponent >= 31 {
+        return sign | 0x7c00;
+    }
+    sign | ((exponent as u16) << 10) | ((bits & 0x7f_ffff) >> 13) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_p010(plane: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes([plane[offset], plane[offset + 1]]) >> 6
+    }
+
+    fn convert_2x2(frame: &RawFrame) -> (Vec<u8>, Vec<u8>) {
+        let (mut luma, mut chroma) = (vec![0; 8], vec![0; 4]);
+        HdrConverter::shared()
+            .convert(
+                frame,
+                P010Planes {
+                    luma: &mut luma,
+                    luma_stride: 4,
+                    chroma: &mut chroma,
+                    chroma_stride: 4,
+                },
+            )
+            .unwrap();
+        (luma, chroma)
+    }
+
+    #[test]
+    fn half_float_ida_y_vuelta() {
+        assert_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_eq!(f16_to_f32(0xb800), -0.5);
+        assert_eq!(f32_to_f16_bits(1.0), 0x3c00);
+        assert_eq!(f32_to_f16_bits(12.5), 0x4a40);
+        assert_eq!(f32_to_f16_bits(1.0e-9), 0);
+    }
+
+    #[test]
+    fn pq_sigue_la_curva_st_2084() {
+        assert_eq!(pq_encode(0.0), 0.0);
+        assert_eq!(pq_encode(-1.0), 0.0);
+        assert!((pq_encode(1.0) - 1.0).abs() < 1e-4);
+        assert!((pq_encode(0.01) - 0.508).abs() < 0.002);
+    }
+
+    #[test]
+    fn blanco_sdr_va_a_203_nits_y_el_gris_no_tiene_croma() {
+        let sdr_white = RawFrame::new(vec![255; 16], 2, 2, 8, 0);
+        let (luma, chroma) = convert_2x2(&sdr_white);
+        let white_code = read_p010(&luma, 0);
+        assert!((570..=580).contains(&white_code), "{white_code}");
+        assert_eq!(read_p010(&luma, 6), white_code);
+        assert_eq!(read_p010(&chroma, 0), 512);
+        assert_eq!(read_p010(&chroma, 2), 512);
+
+        let one = 0x3c00_u16.to_le_bytes();
+        let pixel = [one, one, one, one].concat();
+        let scrgb = RawFrame::new(pixel.repeat(4), 2, 2, 16, 0)
+            .with_pixel_format(FramePixelFormat::Rgba16F);
+        let (luma, chroma) = convert_2x2(&scrgb);
+        assert!(read_p010(&luma, 0) < white_code);
+        assert_eq!(read_p010(&chroma, 0), 512);
+    }
+
+    #[test]
+    fn repartir_las_filas_entre_hilos_no_cambia_el_resultado() {
+        let (width, height): (usize, usize) = (6, 37);
+        let data: Vec<u8> = (0..width * 4 * height)
+            .map(|i| (i * 7 % 256) as u8)
+            .collect();
+        let frame = RawFrame::new(data, width as u32, height as u32, width as u32 * 4, 0);
+        let (luma_stride, chroma_stride) = (16, 16);
+        let chroma_h = height.div_ceil(2);
+        let planes = |luma: &mut Vec<u8>, chroma: &mut Vec<u8>| {
+            luma.resize(luma_stride * height, 0);
+            chroma.resize(chroma_stride * chroma_h, 0);
+        };
+
+        let (mut luma, mut chroma) = (Vec::new(), Vec::new());
+        planes(&mut luma, &mut chroma);
+        let converter = HdrConverter::shared();
+        converter
+            .convert(
+                &frame,
+                P010Planes {
+                    luma: &mut luma,
+                    luma_stride,
+                    chroma: &mut chroma,
+                    chroma_stride,
+                },
+            )
+            .unwrap();
+
+        let (mut single_luma, mut single_chroma) = (Vec::new(), Vec::new());
+        planes(&mut single_luma, &mut single_chroma);
+        converter.convert_rows(
+            &frame,
+            P010Planes {
+                luma: &mut single_luma,
+                luma_stride,
+                chroma: &mut single_chroma,
+                chroma_stride,
+            },
+            0..chroma_h,
+        );
+        assert_eq!(luma, single_luma);
+        assert_eq!(chroma, single_chroma);
+    }
+}
@@ -0,0 +1,125 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+const CHROMATICITY_DENOMINATOR: i32 = 50_000;
+const LUMINANCE_DENOMINATOR: i32 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HdrMasteringMetadata {
+    pub red: [f32; 2],
+    pub green: [f32; 2],
+    pub blue: [f32; 2],
+    pub white_point: [f32; 2],
+    pub min_luminance_nits: f32,
+    pub max_luminance_nits: f32,
+    pub max_frame_average_nits: f32,
+}
+
+impl Default for HdrMasteringMetadata {
+    fn default() -> Self {
+        Self {
+            red: [0.708, 0.292],
+            green: [0.170, 0.797],
+            blue: [0.131, 0.046],
+            white_point: [0.3127, 0.3290],
+            min_luminance_nits: 0.005,
+            max_luminance_nits: 1_000.0,
+            max_frame_average_nits: 400.0,
+        }
+    }
+}
+
+impl HdrMasteringMetadata {
+    /// Valores que informa Windows para la pantalla. Algunos drivers devuelven
+    /// ceros o luminancias invertidas; en ese caso `None`.
+    pub fn from_display(
+        primaries: [[f32; 2]; 4],
+        min_luminance_nits: f32,
+        max_luminance_nits: f32,
+        max_full_frame_nits: f32,
+    ) -> Option<Self> {
+        let valid_primaries = primaries
+            .iter()
+            .flatten()
+            .all(|value| *value > 0.0 && *value < 1.0);
+        if !valid_primaries || !(0.0..max_luminance_nits).contains(&min_luminance_nits) {
+            return None;
+        }
+        let [red, green, blue, white_point] = primaries;
+        Some(Self {
+            red,
+            green,
+            blue,
+            white_point,
+            min_luminance_nits,
+            max_luminance_nits,
+            max_frame_average_nits: if max_full_frame_nits > 0.0 {
+                max_full_frame_nits.min(max_luminance_nits)
+            } else {
+                max_luminance_nits
+            },
+        })
+    }
+
+    pub fn max_content_light_level(&self) -> u32 {
+        self.max_luminance_nits.round() as u32
+    }
+
+    pub fn max_frame_average_light_level(&self) -> u32 {
+        self.max_frame_average_nits.round() as u32
+    }
+
+    pub fn chromaticities(&self) -> [[(i32, i32); 2]; 4] {
+        [self.red, self.green, self.blue, self.white_point].map(|[x, y]| {
+            [
+                rational(x, CHROMATICITY_DENOMINATOR),
+                rational(y, CHROMATICITY_DENOMINATOR),
+            ]
+        })
+    }
+
+    pub fn luminance_range(&self) -> [(i32, i32); 2] {
+        [
+            rational(self.min_luminance_nits, LUMINANCE_DENOMINATOR),
+            rational(self.max_luminance_nits, LUMINANCE_DENOMINATOR),
+        ]
+    }
+}
+
+fn rational(value: f32, denominator: i32) -> (i32, i32) {
+    (
+        (f64::from(value) * f64::from(denominator)).round() as i32,
+        denominator,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn datos_de_la_pantalla_se_validan() {
+        let primaries = [[0.68, 0.32], [0.265, 0.69], [0.15, 0.06], [0.3127, 0.329]];
+        let metadata = HdrMasteringMetadata::from_display(primaries, 0.05, 600.0, 350.0).unwrap();
+        assert_eq!(metadata.max_content_light_level(), 600);
+        assert_eq!(metadata.max_frame_average_light_level(), 350);
+
+        let metadata = HdrMasteringMetadata::from_display(primaries, 0.05, 600.0, 0.0).unwrap();
+        assert_eq!(metadata.max_frame_average_light_level(), 600);
+
+        assert!(HdrMasteringMetadata::from_display([[0.0; 2]; 4], 0.05, 600.0, 350.0).is_none());
+        assert!(HdrMasteringMetadata::from_display(primaries, 0.05, 0.0, 0.0).is_none());
+        assert!(HdrMasteringMetadata::from_display(primaries, 800.0, 600.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn valores_en_unidades_de_los_sei() {
+        let metadata = HdrMasteringMetadata::default();
+        let [red, _, _, white_point] = metadata.chromaticities();
+        assert_eq!(red, [(35_400, 50_000), (14_600, 50_000)]);
+        assert_eq!(white_point, [(15_635, 50_000), (16_450, 50_000)]);
+        assert_eq!(
+            metadata.luminance_range(),
+            [(50, 10_000), (10_000_000, 10_000)]
+        );
+    }
+}
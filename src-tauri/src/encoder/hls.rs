@@ -0,0 +1,176 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use tempfile::TempDir;
+
+use crate::encoder::{
+    config::VideoCodec,
+    output_paths::{ffmpeg_path_arg, move_temp_dir_to_final},
+    processing_status::ProcessingGuard,
+    recording_history::FinishedRecording,
+};
+
+pub const DEFAULT_HLS_SEGMENT_SECONDS: u32 = 6;
+pub const MAX_HLS_SEGMENT_SECONDS: u32 = 60;
+const HLS_ENDLIST_TAG: &str = "#EXT-X-ENDLIST";
+
+pub fn hls_playlist_path(output_path: &Path) -> PathBuf {
+    let stem = output_path
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .filter(|value| !value.is_empty())
+        .unwrap_or("recording");
+    output_path
+        .with_file_name(stem)
+        .join(format!("{stem}.m3u8"))
+}
+
+/// H.264 va en segmentos MPEG-TS; H.265 en fMP4 (`.m4s`), que es lo que
+/// aceptan los reproductores HLS para HEVC.
+fn segment_extension(codec: &VideoCodec) -> &'static str {
+    match codec {
+        VideoCodec::H265 => "m4s",
+        _ => "ts",
+    }
+}
+
+pub fn hls_muxer_options(
+    playlist_path: &Path,
+    codec: &VideoCodec,
+    segment_seconds: u32,
+) -> Result<Vec<(&'static str, String)>, String> {
+    let stem = playlist_path
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .unwrap_or("recording");
+    let segment_pattern =
+        playlist_path.with_file_name(format!("{stem}_%05d.{}", segment_extension(codec)));
+
+    let mut options = vec![
+        ("hls_time", segment_seconds.to_string()),
+        ("hls_list_size", "0".to_string()),
+        ("hls_playlist_type", "event".to_string()),
+        ("hls_segment_filename", ffmpeg_path_arg(&segment_pattern)?),
+    ];
+    if *codec == VideoCodec::H265 {
+        options.push(("hls_segment_type", "fmp4".to_string()));
+        options.push(("hls_fmp4_init_filename", format!("{stem}_init.mp4")));
+    }
+    Ok(options)
+}
+
+/// El muxer escribe `#EXT-X-ENDLIST` con el trailer; si el trailer falló la
+/// playlist queda abierta y los reproductores la tratan como un vivo.
+pub fn ensure_playlist_endlist(playlist_path: &Path) -> Result<(), String> {
+    let contents = fs::read_to_string(playlist_path)
+        .map_err(|err| format!("No se pudo leer la playlist HLS: {err}"))?;
+    if contents.lines().any(|line| line.trim() == HLS_ENDLIST_TAG) {
+        return Ok(());
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .append(true)
+        .open(playlist_path)
+        .map_err(|err| format!("No se pudo cerrar la playlist HLS: {err}"))?;
+    let separator = if contents.ends_with('\n') { "" } else { "\n" };
+    writeln!(file, "{separator}{HLS_ENDLIST_TAG}")
+        .map_err(|err| format!("No se pudo cerrar la playlist HLS: {err}"))
+}
+
+pub struct HlsOutputJob {
+    playlist_path: PathBuf,
+    final_playlist_path: PathBuf,
+    temp_dir: TempDir,
+}
+
+impl HlsOutputJob {
+    pub fn new(playlist_path: PathBuf, final_playlist_path: PathBuf, temp_dir: TempDir) -> Self {
+        Self {
+            playlist_path,
+            final_playlist_path,
+            temp_dir,
+        }
+    }
+
+    pub fn finish_detached(self, finished: FinishedRecording) {
+        let processing_guard = ProcessingGuard::start();
+        std::thread::spawn(move || {
+            let _processing_guard = processing_guard;
+            match self.finish() {
+                Ok(()) => finished.record(),
+                Err(err) => eprintln!("[hls] Error cerrando la salida HLS: {err}"),
+            }
+        });
+    }
+
+    fn finish(self) -> Result<(), String> {
+        if !self.playlist_path.exists() {
+            return Ok(());
+        }
+        ensure_playlist_endlist(&self.playlist_path)?;
+        let final_dir = self
+            .final_playlist_path
+            .parent()
+            .ok_or_else(|| "La playlist HLS no tiene carpeta de destino".to_string())?;
+        move_temp_dir_to_final(self.temp_dir.path(), final_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn playlist_hls_va_en_su_propia_carpeta() {
+        assert_eq!(
+            hls_playlist_path(Path::new("clases/fisica-01.m3u8")),
+            PathBuf::from("clases/fisica-01/fisica-01.m3u8")
+        );
+        assert_eq!(
+            hls_playlist_path(Path::new("clases/fisica-01.hls")),
+            PathBuf::from("clases/fisica-01/fisica-01.m3u8")
+        );
+    }
+
+    #[test]
+    fn opciones_hls_segun_el_codec() {
+        let playlist = Path::new("clase/clase.m3u8");
+        let h264 = hls_muxer_options(playlist, &VideoCodec::H264, 6).unwrap();
+        assert!(h264.contains(&("hls_time", "6".to_string())));
+        assert!(h264.contains(&("hls_list_size", "0".to_string())));
+        let pattern = &h264
+            .iter()
+            .find(|(key, _)| *key == "hls_segment_filename")
+            .unwrap()
+            .1;
+        assert!(pattern.ends_with("clase_%05d.ts"), "{pattern}");
+
+        let h265 = hls_muxer_options(playlist, &VideoCodec::H265, 4).unwrap();
+        assert!(h265.contains(&("hls_segment_type", "fmp4".to_string())));
+        assert!(h265
+            .iter()
+            .any(|(key, value)| *key == "hls_segment_filename" && value.ends_with(".m4s")));
+    }
+
+    #[test]
+    fn playlist_sin_cierre_recibe_endlist_una_sola_vez() {
+        let dir = tempfile::tempdir().unwrap();
+        let playlist = dir.path().join("clase.m3u8");
+        fs::write(&playlist, "#EXTM3U\n#EXTINF:6.0,\nclase_00000.ts").unwrap();
+
+        ensure_playlist_endlist(&playlist).unwrap();
+        ensure_playlist_endlist(&playlist).unwrap();
+
+        let contents = fs::read_to_string(&playlist).unwrap();
+        assert!(
+            contents.ends_with("clase_00000.ts\n#EXT-X-ENDLIST\n"),
+            "{contents}"
+        );
+        assert_eq!(contents.matches(HLS_ENDLIST_TAG).count(), 1);
+    }
+}
@@ -0,0 +1,337 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{fs, path::Path};
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputFileInfo {
+    pub path: String,
+    pub duration_ms: u64,
+    pub container: String,
+    pub video_codec: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fps: Option<f64>,
+    pub audio_codecs: Vec<String>,
+    pub audio_channels: Option<u16>,
+    pub file_size_bytes: u64,
+    pub color_range: Option<String>,
+    pub color_space: Option<String>,
+    pub color_primaries: Option<String>,
+    pub color_transfer: Option<String>,
+}
+
+/// Keyframes del video en ms desde su primer packet. `-ss` cuenta desde el
+/// inicio del contenedor, que puede ser anterior al video (p. ej. si el audio
+/// arranca antes), así que los cortes pasan por `seek_ms`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VideoKeyframes {
+    pub keyframes_ms: Vec<u64>,
+    pub start_offset_ms: u64,
+}
+
+impl VideoKeyframes {
+    pub fn seek_ms(&self, video_ms: u64) -> u64 {
+        self.start_offset_ms.saturating_add(video_ms)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoStreamFormat {
+    pub codec: String,
+    pub pixel_format: Option<String>,
+    pub profile: Option<String>,
+    pub level: Option<u32>,
+}
+
+pub fn probe_output_file(path: &Path) -> Result<OutputFileInfo, String> {
+    let metadata = fs::metadata(path)
+        .map_err(|err| format!("No se pudo leer el archivo '{}': {err}", path.display()))?;
+    if !metadata.is_file() {
+        return Err(format!("'{}' no es un archivo", path.display()));
+    }
+
+    platform::probe_output_file(path, metadata.len())
+}
+
+pub fn probe_video_keyframes(path: &Path) -> VideoKeyframes {
+    platform::probe_video_keyframes(path)
+}
+
+pub fn probe_video_stream_format(path: &Path) -> Result<VideoStreamFormat, String> {
+    platform::probe_video_stream_format(path)
+}
+
+fn known_color_name(name: &str) -> Option<String> {
+    (!matches!(name, "" | "unknown" | "unspecified" | "reserved")).then(|| name.to_string())
+}
+
+fn rational_to_fps(numerator: i32, denominator: i32) -> Option<f64> {
+    if numerator <= 0 || denominator <= 0 {
+        return None;
+    }
+
+    Some(f64::from(numerator) / f64::from(denominator))
+}
+
+fn timestamp_to_ms(duration: i64, time_base_den: i64) -> u64 {
+    if duration <= 0 || time_base_den <= 0 {
+        return 0;
+    }
+
+    (i128::from(duration) * 1_000 / i128::from(time_base_den)) as u64
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{ffi::CStr, os::raw::c_char, path::Path};
+
+    use ffmpeg_the_third::{codec, ffi, format, media, Rational};
+
+    use super::{
+        known_color_name, rational_to_fps, timestamp_to_ms, OutputFileInfo, VideoKeyframes,
+        VideoStreamFormat,
+    };
+    use crate::encoder::output_paths::ffmpeg_path_arg;
+
+    pub fn probe_output_file(path: &Path, file_size_bytes: u64) -> Result<OutputFileInfo, String> {
+        let _ = ffmpeg_the_third::init();
+        let input = format::input(path).map_err(|err| {
+            format!(
+                "No se pudo abrir '{}' como archivo multimedia (formato no soportado o archivo dañado): {err}",
+                path.display()
+            )
+        })?;
+
+        let mut info = OutputFileInfo {
+            path: path.to_string_lossy().into_owned(),
+            duration_ms: timestamp_to_ms(input.duration(), i64::from(ffi::AV_TIME_BASE)),
+            container: input.format().name().to_string(),
+            video_codec: None,
+            width: None,
+            height: None,
+            fps: None,
+            audio_codecs: Vec::new(),
+            audio_channels: None,
+            file_size_bytes,
+            color_range: None,
+            color_space: None,
+            color_primaries: None,
+            color_transfer: None,
+        };
+
+        for stream in input.streams() {
+            let parameters = stream.parameters();
+            match parameters.medium() {
+                media::Type::Video if info.video_codec.is_none() => {
+                    info.video_codec = Some(parameters.id().name().to_string());
+                    let rate = stream.avg_frame_rate();
+                    info.fps = rational_to_fps(rate.numerator(), rate.denominator());
+                    // SAFETY: `codecpar` vive mientras el input esté abierto y
+                    // los nombres de FFmpeg son cadenas estáticas.
+                    unsafe {
+                        let codecpar = &*parameters.as_ptr();
+                        info.color_range =
+                            color_name(ffi::av_color_range_name(codecpar.color_range));
+                        info.color_space =
+                            color_name(ffi::av_color_space_name(codecpar.color_space));
+                        info.color_primaries =
+                            color_name(ffi::av_color_primaries_name(codecpar.color_primaries));
+                        info.color_transfer =
+                            color_name(ffi::av_color_transfer_name(codecpar.color_trc));
+                    }
+
+                    if let Ok(video) = codec::context::Context::from_parameters(parameters)
+                        .and_then(|context| context.decoder().video())
+                    {
+                        info.width = Some(video.width());
+                        info.height = Some(video.height());
+                    }
+                }
+                media::Type::Audio => {
+                    info.audio_codecs.push(parameters.id().name().to_string());
+                    if info.audio_channels.is_none() {
+                        info.audio_channels = codec::context::Context::from_parameters(parameters)
+                            .and_then(|context| context.decoder().audio())
+                            .ok()
+                            .and_then(|audio| u16::try_from(audio.ch_layout().channels()).ok());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if info.video_codec.is_none() && info.audio_codecs.is_empty() {
+            return Err(format!(
+                "'{}' no contiene streams de video ni de audio",
+                path.display()
+            ));
+        }
+
+        Ok(info)
+    }
+
+    pub fn probe_video_keyframes(path: &Path) -> VideoKeyframes {
+        let Ok(path) = ffmpeg_path_arg(path) else {
+            return VideoKeyframes::default();
+        };
+        let _ = ffmpeg_the_third::init();
+        let Ok(mut input) = format::input(&path) else {
+            return VideoKeyframes::default();
+        };
+        let Some(video_stream) = input.streams().best(media::Type::Video) else {
+            return VideoKeyframes::default();
+        };
+        let stream_index = video_stream.index();
+        let time_base = video_stream.time_base();
+        // SAFETY: lectura de un campo del contexto abierto.
+        let container_start = unsafe { (*input.as_ptr()).start_time };
+
+        let mut origin = None;
+        let mut probed = VideoKeyframes::default();
+        for (stream, packet) in input.packets().filter_map(Result::ok) {
+            if stream.index() != stream_index {
+                continue;
+            }
+            let Some(ts) = packet.pts().or_else(|| packet.dts()) else {
+                continue;
+            };
+            let origin = *origin.get_or_insert(ts);
+            if packet.is_key() {
+                probed
+                    .keyframes_ms
+                    .push(ticks_to_ms(ts - origin, time_base));
+            }
+        }
+
+        if let Some(origin) = origin.filter(|_| container_start != ffi::AV_NOPTS_VALUE) {
+            // SAFETY: av_rescale_q solo hace aritmética sobre los valores.
+            let origin_us = unsafe {
+                ffi::av_rescale_q(
+                    origin,
+                    time_base.into(),
+                    ffi::AVRational {
+                        num: 1,
+                        den: ffi::AV_TIME_BASE,
+                    },
+                )
+            };
+            probed.start_offset_ms =
+                u64::try_from((origin_us - container_start) / 1_000).unwrap_or(0);
+        }
+        probed
+    }
+
+    pub fn probe_video_stream_format(path: &Path) -> Result<VideoStreamFormat, String> {
+        let _ = ffmpeg_the_third::init();
+        let input = format::input(path)
+            .map_err(|err| format!("No se pudo abrir '{}': {err}", path.display()))?;
+        let stream = input
+            .streams()
+            .best(media::Type::Video)
+            .ok_or_else(|| "El archivo no tiene video".to_string())?;
+        let parameters = stream.parameters();
+        let codec = parameters.id().name().to_string();
+        // SAFETY: `codecpar` vive mientras el input esté abierto y el nombre
+        // del perfil es una cadena estática de FFmpeg.
+        let (profile, level) = unsafe {
+            let codecpar = &*parameters.as_ptr();
+            let profile = ffi::avcodec_profile_name(codecpar.codec_id, codecpar.profile);
+            let profile = (!profile.is_null())
+                .then(|| CStr::from_ptr(profile).to_string_lossy().into_owned());
+            (
+                profile,
+                u32::try_from(codecpar.level)
+                    .ok()
+                    .filter(|&level| level > 0),
+            )
+        };
+        let pixel_format = codec::context::Context::from_parameters(parameters)
+            .and_then(|context| context.decoder().video())
+            .ok()
+            .and_then(|video| video.format().descriptor())
+            .map(|descriptor| descriptor.name().to_string());
+
+        Ok(VideoStreamFormat {
+            codec,
+            pixel_format,
+            profile,
+            level,
+        })
+    }
+
+    unsafe fn color_name(name: *const c_char) -> Option<String> {
+        if name.is_null() {
+            return None;
+        }
+        known_color_name(&CStr::from_ptr(name).to_string_lossy())
+    }
+
+    fn ticks_to_ms(ticks: i64, time_base: Rational) -> u64 {
+        let (num, den) = (
+            i128::from(time_base.numerator()),
+            i128::from(time_base.denominator()),
+        );
+        if ticks <= 0 || num <= 0 || den <= 0 {
+            return 0;
+        }
+        u64::try_from(i128::from(ticks) * num * 1_000 / den).unwrap_or(u64::MAX)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use std::path::Path;
+
+    use super::{OutputFileInfo, VideoKeyframes, VideoStreamFormat};
+
+    pub fn probe_output_file(
+        _path: &Path,
+        _file_size_bytes: u64,
+    ) -> Result<OutputFileInfo, String> {
+        Err("La inspección de archivos solo está disponible en Windows".to_string())
+    }
+
+    pub fn probe_video_keyframes(_path: &Path) -> VideoKeyframes {
+        VideoKeyframes::default()
+    }
+
+    pub fn probe_video_stream_format(_path: &Path) -> Result<VideoStreamFormat, String> {
+        Err("La inspección de archivos solo está disponible en Windows".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falla_con_mensaje_claro_si_el_archivo_no_existe() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("no-existe.mp4");
+
+        let err = probe_output_file(&missing).expect_err("debio fallar");
+        assert!(err.contains("No se pudo leer el archivo"));
+
+        let err = probe_output_file(dir.path()).expect_err("una carpeta no es archivo");
+        assert!(err.contains("no es un archivo"));
+    }
+
+    #[test]
+    fn convierte_tiempos_y_fps() {
+        assert_eq!(timestamp_to_ms(2_500_000, 1_000_000), 2_500);
+        assert_eq!(timestamp_to_ms(-1, 1_000_000), 0);
+        assert_eq!(
+            rational_to_fps(30_000, 1_001).map(|fps| fps.round()),
+            Some(30.0)
+        );
+        assert_eq!(rational_to_fps(0, 1), None);
+    }
+
+    #[test]
+    fn descarta_etiquetas_de_color_sin_especificar() {
+        assert_eq!(known_color_name("bt709"), Some("bt709".to_string()));
+        assert_eq!(known_color_name("unknown"), None);
+        assert_eq!(known_color_name("reserved"), None);
+    }
+}
@@ -1,7 +1,43 @@
+pub mod animation_export;
 pub mod audio_capture;
+pub mod bitrate;
+pub mod cfr_pacer;
+pub mod chapters;
+pub mod codec_profile;
 pub mod config;
 pub mod consumer;
+pub mod disk_space;
+pub mod encoded_duration;
+pub mod encoder_candidates;
+pub mod ffmpeg_info;
 pub mod ffmpeg_paths;
+pub mod filesystem_limits;
+pub mod fit_to_size;
+pub mod frame_hold;
+pub mod frame_interpolation;
+pub mod gif_export;
+pub mod gpu_adapter;
+pub mod gpu_scaler;
+pub mod hdr;
+pub mod hdr_metadata;
+pub mod hls;
+pub mod media_info;
+pub mod output_naming;
 pub mod output_paths;
+pub mod output_sink;
+pub mod pipeline_latency;
+pub mod post_process;
+pub mod preflight;
 pub mod processing_status;
+pub mod progress_eta;
+pub mod project_takes;
+pub mod quality_analysis;
+pub mod recording_history;
+pub mod recording_trim;
+pub mod region_blur;
+pub mod remux;
+pub mod session_recovery;
+pub mod stall_watchdog;
+pub mod thumbnail;
+pub mod trim;
 pub mod video_encoder_status;
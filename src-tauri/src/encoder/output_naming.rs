@@ -0,0 +1,180 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+
+pub const DEFAULT_OUTPUT_TEMPLATE: &str = "Capturist_{date}_{time}";
+
+const MAX_SEQUENCE: u32 = 9_999;
+const ILLEGAL_FILE_NAME_CHARS: [char; 9] = ['\\', '/', ':', '*', '?', '"', '<', '>', '|'];
+
+pub struct OutputNameContext<'a> {
+    pub target_name: &'a str,
+    pub fps: u32,
+    pub width: u32,
+    pub height: u32,
+    pub now: DateTime<Local>,
+}
+
+pub fn is_directory_output_path(path: &Path) -> bool {
+    if path.is_dir() {
+        return true;
+    }
+
+    path.to_str()
+        .map(|value| value.ends_with('/') || value.ends_with('\\'))
+        .unwrap_or(false)
+}
+
+pub fn sanitize_file_name_component(value: &str) -> String {
+    let replaced: String = value
+        .chars()
+        .map(|ch| {
+            if ILLEGAL_FILE_NAME_CHARS.contains(&ch) || ch.is_control() {
+                '_'
+            } else {
+                ch
+            }
+        })
+        .collect();
+
+    // Windows no admite nombres terminados en punto o espacio.
+    replaced.trim().trim_end_matches(['.', ' ']).to_string()
+}
+
+pub fn expand_output_template(template: &str, context: &OutputNameContext, seq: u32) -> String {
+    let expanded = template
+        .replace("{date}", &context.now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &context.now.format("%H-%M-%S").to_string())
+        .replace("{target}", context.target_name)
+        .replace("{fps}", &context.fps.to_string())
+        .replace(
+            "{resolution}",
+            &format!("{}x{}", context.width, context.height),
+        )
+        .replace("{seq}", &format!("{seq:03}"));
+
+    sanitize_file_name_component(&expanded)
+}
+
+pub fn resolve_templated_output_path(
+    directory: &Path,
+    template: &str,
+    extension: &str,
+    context: &OutputNameContext,
+) -> Result<PathBuf, String> {
+    fs::create_dir_all(directory).map_err(|err| {
+        format!(
+            "No se pudo crear carpeta de salida '{}': {err}",
+            directory.display()
+        )
+    })?;
+
+    let template = if template.trim().is_empty() {
+        DEFAULT_OUTPUT_TEMPLATE
+    } else {
+        template
+    };
+    let has_seq_token = template.contains("{seq}");
+
+    for seq in 1..=MAX_SEQUENCE {
+        let mut stem = expand_output_template(template, context, seq);
+        if stem.is_empty() {
+            stem = "recording".to_string();
+        }
+        if !has_seq_token && seq > 1 {
+            stem = format!("{stem}_{seq:03}");
+        }
+
+        let candidate = directory.join(format!("{stem}.{extension}"));
+        if !candidate.exists() {
+            return Ok(candidate);
+        }
+    }
+
+    Err(format!(
+        "No se encontró un nombre de archivo libre en '{}' tras {MAX_SEQUENCE} intentos",
+        directory.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn make_context(target_name: &str) -> OutputNameContext<'_> {
+        OutputNameContext {
+            target_name,
+            fps: 60,
+            width: 1920,
+            height: 1080,
+            now: Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap(),
+        }
+    }
+
+    #[test]
+    fn expande_todos_los_tokens() {
+        let context = make_context("Monitor 1");
+
+        let name = expand_output_template(
+            "{date}_{time}_{target}_{fps}fps_{resolution}_{seq}",
+            &context,
+            4,
+        );
+
+        assert_eq!(name, "2024-03-09_14-05-07_Monitor 1_60fps_1920x1080_004");
+    }
+
+    #[test]
+    fn sanitiza_caracteres_ilegales_del_target() {
+        let context = make_context("chrome.exe - a\\b/c:d*e?f\"g<h>i|j.");
+
+        let name = expand_output_template("{target}", &context, 1);
+
+        assert_eq!(name, "chrome.exe - a_b_c_d_e_f_g_h_i_j");
+    }
+
+    #[test]
+    fn incrementa_seq_si_el_archivo_ya_existe() {
+        let dir = tempfile::tempdir().unwrap();
+        let context = make_context("Pantalla");
+
+        let first =
+            resolve_templated_output_path(dir.path(), "clip_{seq}", "mp4", &context).unwrap();
+        assert_eq!(first, dir.path().join("clip_001.mp4"));
+        fs::write(&first, b"").unwrap();
+
+        let second =
+            resolve_templated_output_path(dir.path(), "clip_{seq}", "mp4", &context).unwrap();
+        assert_eq!(second, dir.path().join("clip_002.mp4"));
+    }
+
+    #[test]
+    fn agrega_sufijo_si_la_plantilla_no_tiene_seq() {
+        let dir = tempfile::tempdir().unwrap();
+        let context = make_context("Pantalla");
+        fs::write(dir.path().join("clip.mkv"), b"").unwrap();
+
+        let path = resolve_templated_output_path(dir.path(), "clip", "mkv", &context).unwrap();
+
+        assert_eq!(path, dir.path().join("clip_002.mkv"));
+    }
+
+    #[test]
+    fn crea_la_carpeta_de_salida() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("a").join("b");
+        let context = make_context("Pantalla");
+
+        let path = resolve_templated_output_path(&nested, "", "mp4", &context).unwrap();
+
+        assert!(nested.is_dir());
+        assert_eq!(
+            path.file_name().and_then(|name| name.to_str()),
+            Some("Capturist_2024-03-09_14-05-07.mp4")
+        );
+    }
+}
@@ -9,11 +9,23 @@ use tempfile::{Builder as TempBuilder, TempDir};
 
 use crate::encoder::ffmpeg_paths::resolve_ffmpeg_dir;
 
+pub const SESSION_TEMP_PREFIX: &str = "session-";
+pub const FALLBACK_SESSION_TEMP_PREFIX: &str = "capturist-temp-";
+pub const SESSION_OWNER_FILE_NAME: &str = ".capturist-owner";
+const VERBATIM_PREFIX: &str = r"\\?\";
+const VERBATIM_UNC_PREFIX: &str = r"\\?\UNC\";
+
 pub struct PreparedOutputPaths {
     pub temp_dir: TempDir,
     pub temp_output_path: PathBuf,
 }
 
+pub fn temp_output_base_dir() -> PathBuf {
+    resolve_ffmpeg_dir()
+        .map(|ffmpeg_dir| ffmpeg_dir.join("capturist-temp"))
+        .unwrap_or_else(std::env::temp_dir)
+}
+
 pub fn prepare_output_paths(final_output_path: PathBuf) -> Result<PreparedOutputPaths, String> {
     let file_name = final_output_path
         .file_name()
@@ -24,7 +36,10 @@ pub fn prepare_output_paths(final_output_path: PathBuf) -> Result<PreparedOutput
     if let Some(ffmpeg_dir) = resolve_ffmpeg_dir() {
         let base = ffmpeg_dir.join("capturist-temp");
         if fs::create_dir_all(&base).is_ok() {
-            if let Ok(dir) = TempBuilder::new().prefix("session-").tempdir_in(&base) {
+            if let Ok(dir) = TempBuilder::new()
+                .prefix(SESSION_TEMP_PREFIX)
+                .tempdir_in(&base)
+            {
                 temp_dir = Some(dir);
             }
         }
@@ -33,11 +48,17 @@ pub fn prepare_output_paths(final_output_path: PathBuf) -> Result<PreparedOutput
     let temp_dir = match temp_dir {
         Some(value) => value,
         None => TempBuilder::new()
-            .prefix("capturist-temp-")
+            .prefix(FALLBACK_SESSION_TEMP_PREFIX)
             .tempdir()
             .map_err(|err| format!("No se pudo crear carpeta temporal para grabación: {err}"))?,
     };
 
+    if let Err(err) = fs::write(
+        temp_dir.path().join(SESSION_OWNER_FILE_NAME),
+        std::process::id().to_string(),
+    ) {
+        eprintln!("[output] No se pudo marcar la carpeta temporal de la sesión: {err}");
+    }
     let temp_output_path = temp_dir.path().join(file_name);
 
     Ok(PreparedOutputPaths {
@@ -46,11 +67,96 @@ pub fn prepare_output_paths(final_output_path: PathBuf) -> Result<PreparedOutput
     })
 }
 
+/// Ruta absoluta de Windows con el prefijo `\\?\` (`\\?\UNC\` en red), que
+/// no tiene el límite de MAX_PATH. Con ese prefijo Windows ya no interpreta
+/// `/`, `.` ni `..`, así que se resuelven antes. `None` si es relativa, ya es
+/// verbatim o es de dispositivo (`\\.\`).
+fn extended_length_path_str(path: &str) -> Option<String> {
+    let (prefix, rest, root_parts) =
+        match path.strip_prefix(r"\\").or_else(|| path.strip_prefix("//")) {
+            Some(rest) if rest.starts_with(['?', '.']) => return None,
+            // `servidor\recurso` no se pueden quitar con `..`.
+            Some(rest) => (VERBATIM_UNC_PREFIX.to_string(), rest, 2),
+            None => {
+                let bytes = path.as_bytes();
+                let is_drive_absolute = bytes.len() >= 3
+                    && bytes[0].is_ascii_alphabetic()
+                    && bytes[1] == b':'
+                    && matches!(bytes[2], b'\\' | b'/');
+                if !is_drive_absolute {
+                    return None;
+                }
+                (format!("{VERBATIM_PREFIX}{}\\", &path[..2]), &path[2..], 0)
+            }
+        };
+
+    let mut parts: Vec<&str> = Vec::new();
+    for part in rest.split(['\\', '/']) {
+        match part {
+            "" | "." => {}
+            ".." if parts.len() > root_parts => {
+                parts.pop();
+            }
+            ".." => {}
+            part => parts.push(part),
+        }
+    }
+    if parts.len() < root_parts {
+        return None;
+    }
+
+    Some(format!("{prefix}{}", parts.join("\\")))
+}
+
+pub fn extended_length_path(path: &Path) -> PathBuf {
+    if !cfg!(target_os = "windows") {
+        return path.to_path_buf();
+    }
+    path.to_str()
+        .and_then(extended_length_path_str)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Ruta como la recibe FFmpeg, que solo acepta UTF-8. Una ruta de Windows que
+/// no se puede representar así se pasa con el nombre corto 8.3 de su carpeta.
+pub fn ffmpeg_path_arg(path: &Path) -> Result<String, String> {
+    let path = match path.to_str() {
+        Some(_) => path.to_path_buf(),
+        None => platform::short_parent_path(path).ok_or_else(|| {
+            format!(
+                "La ruta '{}' tiene caracteres que FFmpeg no puede abrir y el volumen no tiene nombres cortos",
+                path.display()
+            )
+        })?,
+    };
+    let path = extended_length_path(&path);
+    path.to_str().map(str::to_string).ok_or_else(|| {
+        format!(
+            "La ruta '{}' tiene caracteres que FFmpeg no puede abrir",
+            path.display()
+        )
+    })
+}
+
+pub fn is_same_file(first: &Path, second: &Path) -> bool {
+    if first == second {
+        return true;
+    }
+    match (fs::canonicalize(first), fs::canonicalize(second)) {
+        (Ok(first), Ok(second)) => first == second,
+        _ => false,
+    }
+}
+
 pub fn move_temp_to_final(temp_path: &Path, final_path: &Path) -> Result<(), String> {
+    let shown_temp_path = temp_path;
+    let temp_path = &extended_length_path(temp_path);
+    let final_path = &extended_length_path(final_path);
     if !temp_path.exists() {
         return Err(format!(
             "No existe el archivo temporal para mover: {}",
-            temp_path.display()
+            shown_temp_path.display()
         ));
     }
 
@@ -67,6 +173,7 @@ pub fn move_temp_to_final(temp_path: &Path, final_path: &Path) -> Result<(), Str
         let _ = fs::remove_file(final_path);
     }
 
+    // Entre volúmenes (p. ej. a un recurso de red) `rename` falla y se copia.
     if fs::rename(temp_path, final_path).is_ok() {
         return Ok(());
     }
@@ -77,10 +184,238 @@ pub fn move_temp_to_final(temp_path: &Path, final_path: &Path) -> Result<(), Str
     if let Err(err) = fs::remove_file(temp_path) {
         eprintln!(
             "[output] No se pudo limpiar temporal '{}': {}",
-            temp_path.display(),
+            shown_temp_path.display(),
             err
         );
     }
 
     Ok(())
 }
+
+pub fn move_temp_dir_to_final(temp_dir: &Path, final_dir: &Path) -> Result<(), String> {
+    let entries = fs::read_dir(extended_length_path(temp_dir)).map_err(|err| {
+        format!(
+            "No se pudo leer la carpeta temporal '{}': {err}",
+            temp_dir.display()
+        )
+    })?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_file() && entry.file_name() != SESSION_OWNER_FILE_NAME {
+            move_temp_to_final(&path, &final_dir.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{
+        ffi::OsString,
+        os::windows::ffi::{OsStrExt, OsStringExt},
+        path::{Path, PathBuf},
+    };
+
+    use windows::{core::PCWSTR, Win32::Storage::FileSystem::GetShortPathNameW};
+
+    pub fn short_parent_path(path: &Path) -> Option<PathBuf> {
+        let parent = path.parent()?;
+        let file_name = path.file_name()?;
+        let wide: Vec<u16> = parent
+            .as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        let len = unsafe { GetShortPathNameW(PCWSTR(wide.as_ptr()), None) } as usize;
+        if len == 0 {
+            return None;
+        }
+        let mut buffer = vec![0_u16; len];
+        let written =
+            unsafe { GetShortPathNameW(PCWSTR(wide.as_ptr()), Some(&mut buffer)) } as usize;
+        if written == 0 || written >= len {
+            return None;
+        }
+
+        Some(PathBuf::from(OsString::from_wide(&buffer[..written])).join(file_name))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use std::path::{Path, PathBuf};
+
+    pub fn short_parent_path(_path: &Path) -> Option<PathBuf> {
+        None
+    }
+}
+
+fn processed_temp_path(source_path: &Path) -> PathBuf {
+    let stem = source_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("recording");
+    let ext = source_path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("mp4");
+    source_path.with_file_name(format!("{stem}.processing.{ext}"))
+}
+
+/// `process` escribe en un temporal junto a `source_path` y solo si termina bien
+/// ese resultado reemplaza a `final_path`. Si falla, `source_path` se entrega
+/// intacto en `final_path` y se devuelve el error del proceso.
+pub fn finalize_processed_output(
+    source_path: &Path,
+    final_path: &Path,
+    process: impl FnOnce(&Path) -> Result<(), String>,
+) -> Result<(), String> {
+    let processed_path = processed_temp_path(source_path);
+    if processed_path.exists() {
+        let _ = fs::remove_file(&processed_path);
+    }
+
+    let result =
+        process(&processed_path).and_then(|()| move_temp_to_final(&processed_path, final_path));
+    match result {
+        Ok(()) => {
+            if source_path != final_path {
+                let _ = fs::remove_file(source_path);
+            }
+            Ok(())
+        }
+        Err(err) => {
+            let _ = fs::remove_file(&processed_path);
+            if source_path != final_path {
+                if let Err(move_err) = move_temp_to_final(source_path, final_path) {
+                    return Err(format!("{err}; además, {move_err}"));
+                }
+            }
+            Err(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    fn dir_entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn failing_ffmpeg(dir: &Path) -> PathBuf {
+        #[cfg(windows)]
+        {
+            let path = dir.join("ffmpeg.cmd");
+            fs::write(
+                &path,
+                "@echo off\r\necho error de prueba 1>&2\r\nexit /b 1\r\n",
+            )
+            .unwrap();
+            path
+        }
+        #[cfg(not(windows))]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let path = dir.join("ffmpeg");
+            fs::write(&path, "#!/bin/sh\necho 'error de prueba' >&2\nexit 1\n").unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        }
+    }
+
+    #[test]
+    fn rutas_de_windows_llevan_el_prefijo_extendido() {
+        assert_eq!(
+            extended_length_path_str(r"C:\Videos\.\tomas\..\clip.mp4").as_deref(),
+            Some(r"\\?\C:\Videos\clip.mp4")
+        );
+        assert_eq!(
+            extended_length_path_str("D:/grabaciones/clip.mkv").as_deref(),
+            Some(r"\\?\D:\grabaciones\clip.mkv")
+        );
+        assert_eq!(
+            extended_length_path_str(r"\\nas\videos\..\..\clip.mp4").as_deref(),
+            Some(r"\\?\UNC\nas\videos\clip.mp4")
+        );
+    }
+
+    #[test]
+    fn rutas_relativas_o_ya_verbatim_no_cambian() {
+        assert_eq!(extended_length_path_str(r"videos\clip.mp4"), None);
+        assert_eq!(extended_length_path_str(r"\\?\C:\clip.mp4"), None);
+        assert_eq!(extended_length_path_str(r"\\.\pipe\capturist"), None);
+        assert_eq!(extended_length_path_str(r"\\nas"), None);
+    }
+
+    #[test]
+    fn resultado_procesado_reemplaza_al_final() {
+        let temp = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let source = temp.path().join("grabacion.mp4");
+        let final_path = output.path().join("grabacion.mp4");
+        fs::write(&source, b"video").unwrap();
+
+        finalize_processed_output(&source, &final_path, |processed| {
+            fs::write(processed, b"video+audio").map_err(|err| err.to_string())
+        })
+        .unwrap();
+
+        assert_eq!(fs::read(&final_path).unwrap(), b"video+audio");
+        assert!(dir_entries(temp.path()).is_empty());
+        assert_eq!(dir_entries(output.path()), vec!["grabacion.mp4"]);
+    }
+
+    #[test]
+    fn fallo_de_ffmpeg_entrega_el_video_original() {
+        let bin_dir = tempfile::tempdir().unwrap();
+        let temp = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let source = temp.path().join("grabacion.mp4");
+        let final_path = output.path().join("grabacion.mp4");
+        fs::write(&source, b"video").unwrap();
+        let ffmpeg = failing_ffmpeg(bin_dir.path());
+
+        let err = finalize_processed_output(&source, &final_path, |processed| {
+            fs::write(processed, b"parcial").map_err(|err| err.to_string())?;
+            let output = Command::new(&ffmpeg)
+                .output()
+                .map_err(|err| err.to_string())?;
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+            }
+        })
+        .expect_err("ffmpeg falso debe fallar");
+
+        assert!(err.contains("error de prueba"));
+        assert_eq!(fs::read(&final_path).unwrap(), b"video");
+        assert!(dir_entries(temp.path()).is_empty());
+        assert_eq!(dir_entries(output.path()), vec!["grabacion.mp4"]);
+    }
+
+    #[test]
+    fn fallo_sobre_el_mismo_archivo_no_lo_toca() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("grabacion.mkv");
+        fs::write(&path, b"video").unwrap();
+
+        let err = finalize_processed_output(&path, &path, |_| Err("sin audio".to_string()))
+            .expect_err("debe propagar el error");
+
+        assert_eq!(err, "sin audio");
+        assert_eq!(fs::read(&path).unwrap(), b"video");
+        assert_eq!(dir_entries(dir.path()), vec!["grabacion.mkv"]);
+    }
+}
@@ -0,0 +1,376 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use crate::encoder::config::OutputFormat;
+
+pub const EVENT_OUTPUT_SINK_FAILED: &str = "output-sink-failed";
+pub const MAX_OUTPUT_SINKS: usize = 4;
+pub const MIRROR_QUEUE_SECONDS: u32 = 5;
+pub const MIRROR_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const STREAM_OUTPUT_PREFIXES: &[&str] = &["pipe:", "tcp://", "udp://", "rtmp://", "rtmps://"];
+const RTMP_PREFIXES: &[&str] = &["rtmp://", "rtmps://"];
+pub const RTMP_CONTAINER: &str = "flv";
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RtmpEndpoint {
+    pub url: String,
+    pub key: String,
+}
+
+impl RtmpEndpoint {
+    pub fn publish_url(&self) -> Result<String, String> {
+        let url = self.url.trim();
+        let key = self.key.trim();
+        if !is_rtmp_url(url) {
+            return Err(format!(
+                "URL RTMP inválida: '{url}'. Debe empezar con rtmp:// o rtmps://"
+            ));
+        }
+        if key.is_empty() || key.chars().any(char::is_whitespace) {
+            return Err("La clave de transmisión RTMP está vacía o tiene espacios".to_string());
+        }
+        Ok(format!(
+            "{}/{}",
+            url.trim_end_matches('/'),
+            key.trim_start_matches('/')
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum OutputTarget {
+    #[default]
+    File,
+    Rtmp(RtmpEndpoint),
+}
+
+impl OutputTarget {
+    pub fn publish_url(&self) -> Result<Option<String>, String> {
+        match self {
+            OutputTarget::File => Ok(None),
+            OutputTarget::Rtmp(endpoint) => endpoint.publish_url().map(Some),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum OutputSink {
+    File { path: PathBuf },
+    Rtmp(RtmpEndpoint),
+}
+
+impl OutputSink {
+    pub fn is_rtmp(&self) -> bool {
+        matches!(self, OutputSink::Rtmp(_))
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            OutputSink::File { path } => path.display().to_string(),
+            OutputSink::Rtmp(endpoint) => {
+                format!("{}/***", endpoint.url.trim().trim_end_matches('/'))
+            }
+        }
+    }
+
+    pub fn muxer_target<'a>(&self, format: &'a OutputFormat) -> Result<(PathBuf, &'a str), String> {
+        match self {
+            OutputSink::File { path } => Ok((path.clone(), format.capture_format_name())),
+            OutputSink::Rtmp(endpoint) => {
+                Ok((PathBuf::from(endpoint.publish_url()?), RTMP_CONTAINER))
+            }
+        }
+    }
+
+    pub fn validate(&self, main_output_path: &Path) -> Result<(), String> {
+        match self {
+            OutputSink::File { path } => {
+                if path.as_os_str().is_empty() || path.file_name().is_none() {
+                    return Err("Una salida extra de archivo no tiene ruta".to_string());
+                }
+                if path == main_output_path {
+                    return Err(format!(
+                        "La salida extra '{}' es el mismo archivo de la grabación",
+                        path.display()
+                    ));
+                }
+                if stream_output_url(path).is_some() {
+                    return Err(format!(
+                        "La salida extra '{}' debe ser un archivo; para emitir usa kind: rtmp",
+                        self.label()
+                    ));
+                }
+            }
+            OutputSink::Rtmp(endpoint) => {
+                endpoint.publish_url()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputSinkFailed {
+    pub index: usize,
+    pub target: String,
+    pub message: String,
+}
+
+pub type OutputSinkFailedSink = Arc<dyn Fn(OutputSinkFailed) + Send + Sync>;
+
+fn output_sink_failed_slot() -> &'static Mutex<Option<OutputSinkFailedSink>> {
+    static SLOT: OnceLock<Mutex<Option<OutputSinkFailedSink>>> = OnceLock::new();
+    SLOT.get_or_init(|| Mutex::new(None))
+}
+
+pub fn set_output_sink_failed_sink(sink: OutputSinkFailedSink) {
+    if let Ok(mut guard) = output_sink_failed_slot().lock() {
+        *guard = Some(sink);
+    }
+}
+
+pub(crate) fn notify_output_sink_failed(event: OutputSinkFailed) {
+    eprintln!(
+        "[output] La salida extra '{}' falló: {}",
+        event.target, event.message
+    );
+    let sink = output_sink_failed_slot()
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone());
+    if let Some(sink) = sink {
+        sink(event);
+    }
+}
+
+pub fn mirror_queue_capacity(fps: u32) -> usize {
+    fps.max(1).saturating_mul(MIRROR_QUEUE_SECONDS) as usize
+}
+
+pub fn stream_output_url(output_path: &Path) -> Option<&str> {
+    let value = output_path.to_str()?;
+    STREAM_OUTPUT_PREFIXES
+        .iter()
+        .any(|prefix| value.starts_with(prefix))
+        .then_some(value)
+}
+
+pub fn is_rtmp_url(url: &str) -> bool {
+    RTMP_PREFIXES.iter().any(|prefix| url.starts_with(prefix))
+}
+
+pub fn stream_container_name<'a>(url: &str, format: &'a OutputFormat) -> &'a str {
+    if is_rtmp_url(url) {
+        RTMP_CONTAINER
+    } else {
+        format.capture_format_name()
+    }
+}
+
+/// URL apta para mensajes y logs: en RTMP el último tramo es la clave.
+pub fn redact_stream_url(url: &str) -> String {
+    if !is_rtmp_url(url) {
+        return url.to_string();
+    }
+    match url.rsplit_once('/') {
+        Some((base, _key)) if !base.ends_with('/') => format!("{base}/***"),
+        _ => url.to_string(),
+    }
+}
+
+pub fn live_stream_encoder_options(
+    encoder_name: &str,
+    target_kbps: u32,
+) -> Vec<(&'static str, String)> {
+    let bitrate = format!("{target_kbps}k");
+    let mut options = vec![
+        ("b:v", bitrate.clone()),
+        ("minrate", bitrate.clone()),
+        ("maxrate", bitrate.clone()),
+        ("bufsize", bitrate),
+        ("bf", "0".to_string()),
+    ];
+    if encoder_name.starts_with("libx26") {
+        options.extend([
+            ("tune", "zerolatency".to_string()),
+            ("nal-hrd", "cbr".to_string()),
+        ]);
+    } else if encoder_name.contains("nvenc") {
+        options.extend([
+            ("rc", "cbr".to_string()),
+            ("zerolatency", "1".to_string()),
+            ("rc-lookahead", "0".to_string()),
+        ]);
+    } else if encoder_name.contains("_amf") {
+        options.extend([
+            ("rc", "cbr".to_string()),
+            ("usage", "lowlatency".to_string()),
+        ]);
+    } else if encoder_name.contains("_qsv") {
+        options.extend([
+            ("look_ahead", "0".to_string()),
+            ("async_depth", "1".to_string()),
+        ]);
+    }
+    options
+}
+
+/// Opciones del muxer para una salida sin seek. MP4 se fragmenta para no
+/// tener que volver al inicio a escribir el `moov`; Matroska ya lo soporta.
+pub fn stream_muxer_options(format: &OutputFormat) -> &'static [(&'static str, &'static str)] {
+    match format {
+        OutputFormat::Mp4 => &[("movflags", "frag_keyframe+empty_moov+default_base_moof")],
+        OutputFormat::Mkv | OutputFormat::WebM | OutputFormat::Gif | OutputFormat::Hls => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn detecta_protocolos_de_stream() {
+        assert_eq!(stream_output_url(Path::new("pipe:1")), Some("pipe:1"));
+        assert_eq!(
+            stream_output_url(Path::new("tcp://127.0.0.1:9000")),
+            Some("tcp://127.0.0.1:9000")
+        );
+        assert_eq!(
+            stream_output_url(Path::new("udp://239.0.0.1:1234")),
+            Some("udp://239.0.0.1:1234")
+        );
+    }
+
+    #[test]
+    fn rutas_de_archivo_no_son_stream() {
+        assert_eq!(stream_output_url(Path::new("C:/videos/pipe.mp4")), None);
+        assert_eq!(stream_output_url(&PathBuf::from("grabacion.mkv")), None);
+        assert_eq!(stream_output_url(Path::new("")), None);
+    }
+
+    #[test]
+    fn rtmp_arma_la_url_de_publicacion_y_la_oculta_en_mensajes() {
+        let target = OutputTarget::Rtmp(RtmpEndpoint {
+            url: "rtmp://live.example.com/app/".to_string(),
+            key: " abc123 ".to_string(),
+        });
+        let url = target.publish_url().unwrap().unwrap();
+        assert_eq!(url, "rtmp://live.example.com/app/abc123");
+        assert!(stream_output_url(Path::new(&url)).is_some());
+        assert_eq!(stream_container_name(&url, &OutputFormat::Mp4), "flv");
+        assert_eq!(redact_stream_url(&url), "rtmp://live.example.com/app/***");
+        assert_eq!(
+            redact_stream_url("udp://239.0.0.1:1234"),
+            "udp://239.0.0.1:1234"
+        );
+        assert_eq!(OutputTarget::File.publish_url(), Ok(None));
+    }
+
+    #[test]
+    fn rtmp_rechaza_url_o_clave_invalidas() {
+        let http = OutputTarget::Rtmp(RtmpEndpoint {
+            url: "https://live.example.com/app".to_string(),
+            key: "abc".to_string(),
+        });
+        assert!(http.publish_url().unwrap_err().contains("rtmp://"));
+
+        let no_key = OutputTarget::Rtmp(RtmpEndpoint {
+            url: "rtmps://live.example.com/app".to_string(),
+            key: "  ".to_string(),
+        });
+        assert!(no_key.publish_url().unwrap_err().contains("clave"));
+    }
+
+    #[test]
+    fn opciones_en_vivo_fijan_cbr_y_baja_latencia() {
+        let x264 = live_stream_encoder_options("libx264", 6_000);
+        assert!(x264.contains(&("tune", "zerolatency".to_string())));
+        assert!(x264.contains(&("maxrate", "6000k".to_string())));
+        assert!(x264.contains(&("minrate", "6000k".to_string())));
+
+        let nvenc = live_stream_encoder_options("h264_nvenc", 4_500);
+        assert!(nvenc.contains(&("rc", "cbr".to_string())));
+        assert!(nvenc.contains(&("bf", "0".to_string())));
+    }
+
+    #[test]
+    fn salidas_extra_validan_su_destino() {
+        let main = Path::new("C:/videos/clip.mp4");
+        let rtmp = OutputSink::Rtmp(RtmpEndpoint {
+            url: "rtmp://127.0.0.1/live".to_string(),
+            key: "secreta".to_string(),
+        });
+        assert!(rtmp.validate(main).is_ok());
+        assert_eq!(rtmp.label(), "rtmp://127.0.0.1/live/***");
+        assert_eq!(
+            rtmp.muxer_target(&OutputFormat::Mp4).unwrap(),
+            (
+                PathBuf::from("rtmp://127.0.0.1/live/secreta"),
+                RTMP_CONTAINER
+            )
+        );
+
+        let copy = OutputSink::File {
+            path: PathBuf::from("D:/respaldo/clip.mp4"),
+        };
+        assert!(copy.validate(main).is_ok());
+
+        let same = OutputSink::File {
+            path: main.to_path_buf(),
+        };
+        assert!(same.validate(main).is_err());
+
+        let http = OutputSink::Rtmp(RtmpEndpoint {
+            url: "http://127.0.0.1/live".to_string(),
+            key: "secreta".to_string(),
+        });
+        assert!(http.validate(main).unwrap_err().contains("rtmp://"));
+
+        let udp = OutputSink::File {
+            path: PathBuf::from("udp://239.0.0.1:1234"),
+        };
+        assert!(udp.validate(main).is_err());
+    }
+
+    #[test]
+    fn destino_y_salida_extra_rtmp_se_leen_igual() {
+        let json = r#"{ "kind": "rtmp", "url": "rtmp://127.0.0.1/live", "key": "abc" }"#;
+        let endpoint = RtmpEndpoint {
+            url: "rtmp://127.0.0.1/live".to_string(),
+            key: "abc".to_string(),
+        };
+        assert_eq!(
+            serde_json::from_str::<OutputTarget>(json).unwrap(),
+            OutputTarget::Rtmp(endpoint.clone())
+        );
+        assert_eq!(
+            serde_json::from_str::<OutputSink>(json).unwrap(),
+            OutputSink::Rtmp(endpoint)
+        );
+    }
+
+    #[test]
+    fn cola_de_salida_extra_alcanza_para_unos_segundos() {
+        assert_eq!(mirror_queue_capacity(60), 300);
+        assert_eq!(mirror_queue_capacity(0), MIRROR_QUEUE_SECONDS as usize);
+    }
+
+    #[test]
+    fn mp4_en_stream_se_fragmenta() {
+        assert!(stream_muxer_options(&OutputFormat::Mp4)[0]
+            .1
+            .contains("empty_moov"));
+        assert!(stream_muxer_options(&OutputFormat::Mkv).is_empty());
+    }
+}
@@ -0,0 +1,207 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
+
+const LATENCY_WINDOW_SAMPLES: usize = 240;
+pub const MAX_PENDING_FRAME_TIMES: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineLatencySnapshot {
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+}
+
+pub struct PendingFrameTimes<T = Instant> {
+    entries: VecDeque<(i64, T)>,
+    capacity: usize,
+}
+
+impl<T> PendingFrameTimes<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn insert(&mut self, pts: i64, captured_at: T) {
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((pts, captured_at));
+    }
+
+    /// Devuelve el instante asociado a `pts`. Con B-frames los packets no
+    /// salen en orden de PTS; solo se descartan las entradas con PTS menor
+    /// que `dts`, que ningún packet posterior puede traer.
+    pub fn take(&mut self, pts: i64, dts: Option<i64>) -> Option<T> {
+        if let Some(dts) = dts {
+            self.entries
+                .retain(|(entry_pts, _)| *entry_pts >= dts || *entry_pts == pts);
+        }
+        let index = self
+            .entries
+            .iter()
+            .position(|(entry_pts, _)| *entry_pts == pts)?;
+        self.entries
+            .remove(index)
+            .map(|(_, captured_at)| captured_at)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+pub struct LatencyWindow {
+    samples: VecDeque<u64>,
+    capacity: usize,
+}
+
+impl LatencyWindow {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn push(&mut self, latency_ms: u64) {
+        if self.samples.len() >= self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency_ms);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn snapshot(&self) -> Option<PipelineLatencySnapshot> {
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted: Vec<u64> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+
+        Some(PipelineLatencySnapshot {
+            p50_ms: nearest_rank_percentile(&sorted, 50),
+            p95_ms: nearest_rank_percentile(&sorted, 95),
+        })
+    }
+}
+
+fn nearest_rank_percentile(sorted: &[u64], percentile: usize) -> u64 {
+    let rank = (percentile * sorted.len()).div_ceil(100).max(1);
+    sorted[rank.min(sorted.len()) - 1]
+}
+
+fn latency_window() -> &'static Mutex<LatencyWindow> {
+    static LATENCY_WINDOW: OnceLock<Mutex<LatencyWindow>> = OnceLock::new();
+    LATENCY_WINDOW.get_or_init(|| Mutex::new(LatencyWindow::new(LATENCY_WINDOW_SAMPLES)))
+}
+
+pub fn record_pipeline_latency_ms(latency_ms: u64) {
+    if let Ok(mut guard) = latency_window().lock() {
+        guard.push(latency_ms);
+    }
+}
+
+pub fn get_pipeline_latency() -> Option<PipelineLatencySnapshot> {
+    latency_window()
+        .lock()
+        .ok()
+        .and_then(|guard| guard.snapshot())
+}
+
+pub fn reset_pipeline_latency() {
+    if let Ok(mut guard) = latency_window().lock() {
+        guard.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LatencyWindow, PendingFrameTimes, PipelineLatencySnapshot};
+
+    #[test]
+    fn pending_descarta_la_entrada_mas_antigua_al_llenarse() {
+        let mut pending = PendingFrameTimes::new(3);
+        for pts in 0..5 {
+            pending.insert(pts, pts * 10);
+        }
+
+        assert_eq!(pending.len(), 3);
+        assert_eq!(pending.take(1, Some(1)), None);
+        assert_eq!(pending.take(2, Some(2)), Some(20));
+        assert_eq!(pending.take(4, Some(3)), Some(40));
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending.take(5, Some(5)), None);
+        assert_eq!(pending.len(), 0);
+    }
+
+    #[test]
+    fn pending_descarta_pts_anteriores_al_dts() {
+        let mut pending = PendingFrameTimes::new(8);
+        pending.insert(0, "a");
+        pending.insert(33, "b");
+        pending.insert(66, "c");
+
+        assert_eq!(pending.take(66, Some(66)), Some("c"));
+        assert_eq!(pending.len(), 0);
+    }
+
+    #[test]
+    fn pending_con_b_frames_conserva_los_pts_que_salen_despues() {
+        let mut pending = PendingFrameTimes::new(8);
+        for pts in 0..4 {
+            pending.insert(pts, pts * 10);
+        }
+
+        assert_eq!(pending.take(0, Some(-1)), Some(0));
+        assert_eq!(pending.take(3, Some(0)), Some(30));
+        assert_eq!(pending.take(1, Some(1)), Some(10));
+        assert_eq!(pending.take(2, Some(2)), Some(20));
+        assert_eq!(pending.len(), 0);
+    }
+
+    #[test]
+    fn percentiles_con_datos_sinteticos() {
+        let mut window = LatencyWindow::new(100);
+        for latency in 1..=100 {
+            window.push(latency);
+        }
+
+        assert_eq!(
+            window.snapshot(),
+            Some(PipelineLatencySnapshot {
+                p50_ms: 50,
+                p95_ms: 95,
+            })
+        );
+    }
+
+    #[test]
+    fn ventana_conserva_solo_las_muestras_recientes() {
+        let mut window = LatencyWindow::new(4);
+        assert_eq!(window.snapshot(), None);
+
+        for latency in [500, 500, 10, 20, 30, 40] {
+            window.push(latency);
+        }
+
+        assert_eq!(
+            window.snapshot(),
+            Some(PipelineLatencySnapshot {
+                p50_ms: 20,
+                p95_ms: 40,
+            })
+        );
+    }
+}
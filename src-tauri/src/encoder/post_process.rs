@@ -0,0 +1,269 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    ffi::OsString,
+    io,
+    path::Path,
+    process::{ChildStdout, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::Duration,
+};
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use crate::encoder::{
+    ffmpeg_paths::resolve_ffmpeg_bin,
+    processing_status::{ProcessingGuard, ProcessingProgress},
+};
+
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+const CHILD_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub const POST_PROCESS_CANCELLED_ERR: &str = "Post-procesamiento cancelado por el usuario";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PostProcessKind {
+    GifExport,
+    FitToSize,
+    FrameInterpolation,
+    QualityAnalysis,
+    AnimationExport,
+    Trim,
+    Remux,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostProcessJobSnapshot {
+    pub id: u64,
+    pub kind: PostProcessKind,
+    pub output_path: String,
+}
+
+struct ActiveJob {
+    snapshot: PostProcessJobSnapshot,
+    cancelled: Arc<AtomicBool>,
+}
+
+fn active_jobs() -> &'static Mutex<Vec<ActiveJob>> {
+    static ACTIVE_JOBS: OnceLock<Mutex<Vec<ActiveJob>>> = OnceLock::new();
+    ACTIVE_JOBS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+fn next_job_id() -> u64 {
+    static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+    NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+pub struct PostProcessTask {
+    id: u64,
+    cancelled: Arc<AtomicBool>,
+    _processing_guard: ProcessingGuard,
+}
+
+impl PostProcessTask {
+    pub fn begin(kind: PostProcessKind, output_path: &Path) -> Self {
+        let id = next_job_id();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        if let Ok(mut jobs) = active_jobs().lock() {
+            jobs.push(ActiveJob {
+                snapshot: PostProcessJobSnapshot {
+                    id,
+                    kind,
+                    output_path: output_path.to_string_lossy().into_owned(),
+                },
+                cancelled: cancelled.clone(),
+            });
+        }
+
+        Self {
+            id,
+            cancelled,
+            _processing_guard: ProcessingGuard::start(),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for PostProcessTask {
+    fn drop(&mut self) {
+        if let Ok(mut jobs) = active_jobs().lock() {
+            jobs.retain(|job| job.snapshot.id != self.id);
+        }
+    }
+}
+
+pub fn get_post_process_jobs() -> Vec<PostProcessJobSnapshot> {
+    active_jobs()
+        .lock()
+        .map(|jobs| jobs.iter().map(|job| job.snapshot.clone()).collect())
+        .unwrap_or_default()
+}
+
+pub fn cancel_post_process_jobs(id: Option<u64>) -> Result<usize, String> {
+    let jobs = active_jobs()
+        .lock()
+        .map_err(|_| "No se pudo acceder a la cola de post-procesamiento".to_string())?;
+
+    let mut cancelled = 0;
+    for job in jobs
+        .iter()
+        .filter(|job| id.map(|id| job.snapshot.id == id).unwrap_or(true))
+    {
+        job.cancelled.store(true, Ordering::SeqCst);
+        cancelled += 1;
+    }
+
+    if cancelled == 0 {
+        if let Some(id) = id {
+            return Err(format!("No hay un post-procesamiento activo con id {id}"));
+        }
+    }
+
+    Ok(cancelled)
+}
+
+pub fn ffmpeg_seconds(ms: u64) -> String {
+    format!("{}.{:03}", ms / 1_000, ms % 1_000)
+}
+
+pub fn run_ffmpeg_cancellable(
+    args: Vec<OsString>,
+    step: &str,
+    task: &PostProcessTask,
+) -> Result<(), String> {
+    run_ffmpeg(args, step, task, None::<fn(ChildStdout)>).map(|_| ())
+}
+
+pub fn run_ffmpeg_cancellable_with_stdout<T: Send + 'static>(
+    args: Vec<OsString>,
+    step: &str,
+    task: &PostProcessTask,
+    read_stdout: impl FnOnce(ChildStdout) -> T + Send + 'static,
+) -> Result<T, String> {
+    run_ffmpeg(args, step, task, Some(read_stdout))?
+        .ok_or_else(|| format!("No se pudo leer la salida de FFmpeg para {step}"))
+}
+
+pub fn run_ffmpeg_cancellable_with_progress(
+    args: Vec<OsString>,
+    step: &str,
+    task: &PostProcessTask,
+    progress: &Arc<ProcessingProgress>,
+    offset_ms: u64,
+    total_ms: u64,
+) -> Result<(), String> {
+    let mut progress_args: Vec<OsString> =
+        vec!["-progress".into(), "pipe:1".into(), "-nostats".into()];
+    progress_args.extend(args);
+
+    let progress = progress.clone();
+    run_ffmpeg_cancellable_with_stdout(progress_args, step, task, move |stdout| {
+        progress.follow_ffmpeg_output(stdout, offset_ms, total_ms)
+    })
+}
+
+fn run_ffmpeg<T: Send + 'static>(
+    args: Vec<OsString>,
+    step: &str,
+    task: &PostProcessTask,
+    read_stdout: Option<impl FnOnce(ChildStdout) -> T + Send + 'static>,
+) -> Result<Option<T>, String> {
+    if task.is_cancelled() {
+        return Err(POST_PROCESS_CANCELLED_ERR.to_string());
+    }
+
+    let mut cmd = Command::new(resolve_ffmpeg_bin());
+    cmd.arg("-y")
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(if read_stdout.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stderr(Stdio::piped());
+
+    #[cfg(windows)]
+    {
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = cmd.spawn().map_err(|err| {
+        if err.kind() == io::ErrorKind::NotFound {
+            "No se encontró FFmpeg CLI para post-procesar. Define CAPTURIST_FFMPEG_BIN o agrega ffmpeg.exe al PATH."
+                .to_string()
+        } else {
+            format!("No se pudo ejecutar FFmpeg para {step}: {err}")
+        }
+    })?;
+
+    // stdout y stderr se leen en paralelo para que los pipes no se llenen y bloqueen FFmpeg.
+    let stdout_reader = read_stdout.and_then(|read_stdout| {
+        child
+            .stdout
+            .take()
+            .map(|stdout| std::thread::spawn(move || read_stdout(stdout)))
+    });
+    let stderr_reader = child.stderr.take().map(|mut stderr| {
+        std::thread::spawn(move || {
+            let mut buffer = String::new();
+            let _ = io::Read::read_to_string(&mut stderr, &mut buffer);
+            buffer
+        })
+    });
+
+    let status = loop {
+        if task.is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(POST_PROCESS_CANCELLED_ERR.to_string());
+        }
+
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => std::thread::sleep(CHILD_POLL_INTERVAL),
+            Err(err) => {
+                let _ = child.kill();
+                return Err(format!("No se pudo esperar a FFmpeg para {step}: {err}"));
+            }
+        }
+    };
+
+    if !status.success() {
+        let stderr = stderr_reader
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        return Err(format!(
+            "FFmpeg falló al {step}: {}",
+            if stderr.is_empty() {
+                "sin salida de error".to_string()
+            } else {
+                stderr
+            }
+        ));
+    }
+
+    Ok(stdout_reader.and_then(|reader| reader.join().ok()))
+}
+
+#[cfg(test)]
+pub(crate) fn args_to_strings(args: Vec<OsString>) -> Vec<String> {
+    args.into_iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect()
+}
@@ -0,0 +1,339 @@
+use std::path::Path;
+
+use crate::encoder::{
+    config::{EncoderConfig, VideoCodec, VideoEncoderPreference},
+    consumer::VideoEncoderCapabilities,
+    ffmpeg_paths::ffmpeg_bin_available,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IssueSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    pub severity: IssueSeverity,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: IssueSeverity::Error,
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: IssueSeverity::Warning,
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCheck {
+    pub check: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl SessionCheck {
+    pub fn from_result(check: &'static str, result: Result<String, String>) -> Self {
+        let (passed, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(err) => (false, err),
+        };
+        Self {
+            check,
+            passed,
+            detail,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCheckReport {
+    pub passed: bool,
+    pub checks: Vec<SessionCheck>,
+}
+
+impl SessionCheckReport {
+    pub fn new(checks: Vec<SessionCheck>) -> Self {
+        Self {
+            passed: checks.iter().all(|check| check.passed),
+            checks,
+        }
+    }
+}
+
+pub fn encoder_probe_size(
+    config: &EncoderConfig,
+    source: (u32, u32),
+) -> Result<(u32, u32), String> {
+    let (width, height) =
+        config
+            .resolution
+            .output_dimensions(source.0, source.1, config.never_upscale);
+    let (width, height) = (width & !1, height & !1);
+    if width < 2 || height < 2 {
+        return Err("La resolución resultante es demasiado pequeña (mínimo 2x2)".to_string());
+    }
+    Ok((width, height))
+}
+
+fn preference_label(preference: &VideoEncoderPreference) -> &'static str {
+    match preference {
+        VideoEncoderPreference::Auto => "automático",
+        VideoEncoderPreference::Nvenc => "NVENC",
+        VideoEncoderPreference::Amf => "AMF",
+        VideoEncoderPreference::Qsv => "QSV",
+        VideoEncoderPreference::Software => "software",
+    }
+}
+
+fn codec_label(codec: &VideoCodec) -> &'static str {
+    match codec {
+        VideoCodec::H264 => "H.264",
+        VideoCodec::H265 => "H.265",
+        VideoCodec::Vp9 => "VP9",
+    }
+}
+
+fn preferred_encoder_available(
+    codec: &VideoCodec,
+    preference: &VideoEncoderPreference,
+    capabilities: &VideoEncoderCapabilities,
+) -> Option<bool> {
+    let available = match (preference, codec) {
+        (VideoEncoderPreference::Auto, _) => return None,
+        (VideoEncoderPreference::Nvenc, VideoCodec::H264) => capabilities.nvenc,
+        (VideoEncoderPreference::Nvenc, VideoCodec::H265) => capabilities.hevc_nvenc,
+        (VideoEncoderPreference::Amf, VideoCodec::H264) => capabilities.amf,
+        (VideoEncoderPreference::Amf, VideoCodec::H265) => capabilities.hevc_amf,
+        (VideoEncoderPreference::Nvenc | VideoEncoderPreference::Amf, VideoCodec::Vp9) => false,
+        (VideoEncoderPreference::Qsv, VideoCodec::H264) => capabilities.qsv,
+        (VideoEncoderPreference::Qsv, VideoCodec::H265) => capabilities.hevc_qsv,
+        (VideoEncoderPreference::Qsv, VideoCodec::Vp9) => capabilities.vp9_qsv,
+        (VideoEncoderPreference::Software, VideoCodec::H264) => capabilities.software,
+        (VideoEncoderPreference::Software, VideoCodec::H265) => capabilities.hevc_software,
+        (VideoEncoderPreference::Software, VideoCodec::Vp9) => capabilities.vp9_software,
+    };
+    Some(available)
+}
+
+pub fn check_preferred_encoder(
+    codec: &VideoCodec,
+    preference: &VideoEncoderPreference,
+    capabilities: &VideoEncoderCapabilities,
+) -> Option<ValidationIssue> {
+    if preferred_encoder_available(codec, preference, capabilities)? {
+        return None;
+    }
+
+    Some(ValidationIssue::error(
+        "encoderUnavailable",
+        format!(
+            "El encoder {} no está disponible para {} en este equipo; elige otro o usa el modo automático",
+            preference_label(preference),
+            codec_label(codec)
+        ),
+    ))
+}
+
+pub fn check_output_dir_writable(output_dir: &Path) -> Option<ValidationIssue> {
+    let probe_dir = output_dir.ancestors().find(|dir| dir.is_dir());
+    let Some(probe_dir) = probe_dir else {
+        return Some(ValidationIssue::error(
+            "outputNotWritable",
+            format!(
+                "La carpeta de salida '{}' no existe y no se puede crear",
+                output_dir.display()
+            ),
+        ));
+    };
+
+    match tempfile::NamedTempFile::new_in(probe_dir) {
+        Ok(_) => None,
+        Err(err) => Some(ValidationIssue::error(
+            "outputNotWritable",
+            format!(
+                "No se puede escribir en la carpeta de salida '{}': {err}",
+                probe_dir.display()
+            ),
+        )),
+    }
+}
+
+pub fn check_audio_device(
+    code: &'static str,
+    source_label: &str,
+    requested: Option<&str>,
+    available: &[String],
+) -> Option<ValidationIssue> {
+    let requested = requested.map(str::trim).filter(|name| !name.is_empty())?;
+    let needle = requested.to_lowercase();
+    let found = available
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(requested) || name.to_lowercase().contains(&needle));
+    if found {
+        return None;
+    }
+
+    Some(ValidationIssue::error(
+        code,
+        format!("No se encontró el dispositivo de {source_label} '{requested}'"),
+    ))
+}
+
+pub fn check_ffmpeg_bin(
+    ffmpeg_bin: &Path,
+    path_var: Option<&std::ffi::OsStr>,
+) -> Option<ValidationIssue> {
+    if ffmpeg_bin_available(ffmpeg_bin, path_var) {
+        return None;
+    }
+
+    Some(ValidationIssue::error(
+        "ffmpegNotFound",
+        "No se encontró FFmpeg CLI, necesario para GIF, el ajuste de tamaño y la interpolación. Define CAPTURIST_FFMPEG_BIN o agrega ffmpeg.exe al PATH",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+
+    #[test]
+    fn encoder_preferido_se_valida_por_codec() {
+        let capabilities = VideoEncoderCapabilities {
+            nvenc: true,
+            software: true,
+            ..VideoEncoderCapabilities::default()
+        };
+
+        assert_eq!(
+            check_preferred_encoder(
+                &VideoCodec::H264,
+                &VideoEncoderPreference::Nvenc,
+                &capabilities
+            ),
+            None
+        );
+        assert_eq!(
+            check_preferred_encoder(
+                &VideoCodec::H265,
+                &VideoEncoderPreference::Auto,
+                &capabilities
+            ),
+            None
+        );
+
+        let issue = check_preferred_encoder(
+            &VideoCodec::H265,
+            &VideoEncoderPreference::Nvenc,
+            &capabilities,
+        )
+        .expect("HEVC NVENC no disponible");
+        assert_eq!(issue.severity, IssueSeverity::Error);
+        assert_eq!(issue.code, "encoderUnavailable");
+        assert!(issue.message.contains("H.265"));
+
+        assert!(check_preferred_encoder(
+            &VideoCodec::Vp9,
+            &VideoEncoderPreference::Amf,
+            &VideoEncoderCapabilities::default()
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn carpeta_inexistente_se_prueba_en_el_ancestro() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(check_output_dir_writable(dir.path()), None);
+        assert_eq!(
+            check_output_dir_writable(&dir.path().join("nueva").join("sub")),
+            None
+        );
+        assert!(!dir.path().join("nueva").exists());
+    }
+
+    #[test]
+    fn dispositivo_de_audio_acepta_nombre_parcial() {
+        let devices = vec!["Altavoces (Realtek(R) Audio)".to_string()];
+
+        assert_eq!(
+            check_audio_device(
+                "systemAudioDeviceNotFound",
+                "audio del sistema",
+                Some("realtek"),
+                &devices
+            ),
+            None
+        );
+        assert_eq!(
+            check_audio_device(
+                "systemAudioDeviceNotFound",
+                "audio del sistema",
+                None,
+                &devices
+            ),
+            None
+        );
+
+        let issue = check_audio_device(
+            "microphoneDeviceNotFound",
+            "micrófono",
+            Some("USB Mic"),
+            &devices,
+        )
+        .expect("no existe");
+        assert_eq!(issue.code, "microphoneDeviceNotFound");
+        assert!(issue.message.contains("USB Mic"));
+    }
+
+    #[test]
+    fn reporte_de_sesion_falla_si_falla_un_paso() {
+        let config = EncoderConfig::default();
+        assert_eq!(encoder_probe_size(&config, (1281, 721)), Ok((1280, 720)));
+        assert!(encoder_probe_size(&config, (1, 1)).is_err());
+
+        let ok = SessionCheck::from_result("target", Ok("Monitor 1".to_string()));
+        assert!(SessionCheckReport::new(vec![ok.clone()]).passed);
+
+        let report = SessionCheckReport::new(vec![
+            ok,
+            SessionCheck::from_result(
+                "videoEncoder",
+                Err("h264_nvenc: límite de sesiones".to_string()),
+            ),
+        ]);
+        assert!(!report.passed);
+        assert_eq!(report.checks[1].detail, "h264_nvenc: límite de sesiones");
+    }
+
+    #[test]
+    fn ffmpeg_se_busca_en_path_cuando_no_hay_ruta() {
+        let dir = tempfile::tempdir().unwrap();
+        let path_var = env::join_paths([dir.path()]).unwrap();
+
+        assert!(check_ffmpeg_bin(Path::new("ffmpeg"), Some(&path_var)).is_some());
+
+        fs::write(dir.path().join("ffmpeg.exe"), b"").unwrap();
+        assert_eq!(check_ffmpeg_bin(Path::new("ffmpeg"), Some(&path_var)), None);
+        assert_eq!(check_ffmpeg_bin(&dir.path().join("ffmpeg.exe"), None), None);
+        assert!(check_ffmpeg_bin(&dir.path().join("falta.exe"), None).is_some());
+    }
+}
@@ -1,12 +1,27 @@
 #![cfg_attr(not(target_os = "windows"), allow(dead_code))]
 
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Condvar, Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::encoder::progress_eta::{parse_progress_out_time_ms, EtaEstimator};
 
 fn processing_counter() -> &'static AtomicUsize {
     static PROCESSING_COUNTER: AtomicUsize = AtomicUsize::new(0);
     &PROCESSING_COUNTER
 }
 
+fn idle_signal() -> &'static (Mutex<()>, Condvar) {
+    static IDLE_SIGNAL: OnceLock<(Mutex<()>, Condvar)> = OnceLock::new();
+    IDLE_SIGNAL.get_or_init(|| (Mutex::new(()), Condvar::new()))
+}
+
 fn processing_override_flag() -> &'static AtomicBool {
     static PROCESSING_OVERRIDE_FLAG: AtomicBool = AtomicBool::new(false);
     &PROCESSING_OVERRIDE_FLAG
@@ -23,7 +38,13 @@ impl ProcessingGuard {
 
 impl Drop for ProcessingGuard {
     fn drop(&mut self) {
-        processing_counter().fetch_sub(1, Ordering::SeqCst);
+        if processing_counter().fetch_sub(1, Ordering::SeqCst) == 1 {
+            // Se notifica con el lock tomado para que nadie se pierda el aviso
+            // entre revisar el contador y empezar a esperar.
+            let (lock, idle) = idle_signal();
+            let _idle_guard = lock.lock();
+            idle.notify_all();
+        }
     }
 }
 
@@ -32,24 +53,98 @@ pub fn is_processing() -> bool {
         || processing_counter().load(Ordering::SeqCst) > 0
 }
 
+pub fn wait_for_processing_idle(timeout: Duration) -> bool {
+    let (lock, idle) = idle_signal();
+    let Ok(idle_guard) = lock.lock() else {
+        return false;
+    };
+    idle.wait_timeout_while(idle_guard, timeout, |_| {
+        processing_counter().load(Ordering::SeqCst) > 0
+    })
+    .map(|(_, result)| !result.timed_out())
+    .unwrap_or(false)
+}
+
 pub fn set_processing(value: bool) {
     processing_override_flag().store(value, Ordering::SeqCst);
 }
 
-#[cfg(test)]
-mod tests {
-    use std::sync::{Mutex, OnceLock};
+fn processing_etas() -> &'static Mutex<HashMap<u64, u64>> {
+    static PROCESSING_ETAS: OnceLock<Mutex<HashMap<u64, u64>>> = OnceLock::new();
+    PROCESSING_ETAS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub struct ProcessingProgress {
+    id: u64,
+    estimator: Mutex<EtaEstimator>,
+}
 
-    use super::{is_processing, set_processing, ProcessingGuard};
+impl ProcessingProgress {
+    pub fn start() -> Self {
+        static NEXT_PROGRESS_ID: AtomicU64 = AtomicU64::new(1);
+        Self {
+            id: NEXT_PROGRESS_ID.fetch_add(1, Ordering::Relaxed),
+            estimator: Mutex::new(EtaEstimator::default()),
+        }
+    }
+
+    pub fn report(&self, processed_ms: u64, total_ms: u64) {
+        let eta_ms =
+            self.estimator.lock().ok().and_then(|mut estimator| {
+                estimator.observe(Instant::now(), processed_ms, total_ms)
+            });
+        if let Ok(mut etas) = processing_etas().lock() {
+            match eta_ms {
+                Some(eta_ms) => etas.insert(self.id, eta_ms),
+                None => etas.remove(&self.id),
+            };
+        }
+    }
 
-    fn test_lock() -> &'static Mutex<()> {
-        static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
-        LOCK.get_or_init(|| Mutex::new(()))
+    pub fn follow_ffmpeg_output(&self, output: impl Read, offset_ms: u64, total_ms: u64) {
+        for line in BufReader::new(output).lines().map_while(Result::ok) {
+            if let Some(out_time_ms) = parse_progress_out_time_ms(&line) {
+                self.report(offset_ms.saturating_add(out_time_ms), total_ms);
+            }
+        }
+    }
+}
+
+impl Drop for ProcessingProgress {
+    fn drop(&mut self) {
+        if let Ok(mut etas) = processing_etas().lock() {
+            etas.remove(&self.id);
+        }
     }
+}
+
+pub fn processing_eta_ms() -> Option<u64> {
+    processing_etas()
+        .lock()
+        .ok()
+        .and_then(|etas| etas.values().copied().max())
+}
+
+#[cfg(test)]
+pub(crate) fn processing_test_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{
+        is_processing, processing_eta_ms, processing_test_lock, set_processing,
+        wait_for_processing_idle, ProcessingGuard, ProcessingProgress,
+    };
 
     #[test]
     fn processing_guard_y_override_controlan_estado() {
-        let _guard = test_lock().lock().expect("lock de test poisoned");
+        let _guard = processing_test_lock()
+            .lock()
+            .expect("lock de test poisoned");
 
         set_processing(false);
         assert!(!is_processing());
@@ -65,4 +160,37 @@ mod tests {
         set_processing(false);
         assert!(!is_processing());
     }
+
+    #[test]
+    fn espera_de_procesamiento_termina_al_soltar_el_ultimo_guard() {
+        let _guard = processing_test_lock()
+            .lock()
+            .expect("lock de test poisoned");
+        assert!(wait_for_processing_idle(Duration::ZERO));
+
+        let processing_guard = ProcessingGuard::start();
+        assert!(!wait_for_processing_idle(Duration::from_millis(20)));
+
+        let worker = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(30));
+            drop(processing_guard);
+        });
+        assert!(wait_for_processing_idle(Duration::from_secs(5)));
+        assert!(!is_processing());
+        worker.join().unwrap();
+    }
+
+    #[test]
+    fn progreso_publica_eta_hasta_soltarse() {
+        let progress = ProcessingProgress::start();
+        progress.follow_ffmpeg_output(&b"out_time_us=0\nprogress=continue\n"[..], 0, 60_000);
+        assert_eq!(processing_eta_ms(), None);
+
+        std::thread::sleep(Duration::from_millis(20));
+        progress.report(30_000, 60_000);
+        assert!(processing_eta_ms().is_some());
+
+        drop(progress);
+        assert_eq!(processing_eta_ms(), None);
+    }
 }
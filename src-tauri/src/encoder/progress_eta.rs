@@ -0,0 +1,108 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::time::Instant;
+
+/// No se reporta ETA hasta procesar este porcentaje: las primeras muestras
+/// incluyen el arranque de FFmpeg y dan estimaciones muy ruidosas.
+pub const MIN_PROGRESS_FOR_ETA: f64 = 0.05;
+const RATE_SMOOTHING: f64 = 0.3;
+
+pub fn smooth_rate(previous: Option<f64>, sample: f64) -> f64 {
+    match previous {
+        Some(previous) => previous + RATE_SMOOTHING * (sample - previous),
+        None => sample,
+    }
+}
+
+pub fn estimate_remaining_ms(rate: f64, processed_ms: u64, total_ms: u64) -> Option<u64> {
+    if total_ms == 0 || !rate.is_finite() || rate <= 0.0 {
+        return None;
+    }
+    if (processed_ms as f64) < total_ms as f64 * MIN_PROGRESS_FOR_ETA {
+        return None;
+    }
+
+    let remaining_ms = total_ms.saturating_sub(processed_ms) as f64;
+    Some((remaining_ms / rate).round() as u64)
+}
+
+pub fn parse_progress_out_time_ms(line: &str) -> Option<u64> {
+    line.trim()
+        .strip_prefix("out_time_us=")?
+        .parse::<u64>()
+        .ok()
+        .map(|micros| micros / 1_000)
+}
+
+#[derive(Debug, Default)]
+pub struct EtaEstimator {
+    last_sample: Option<(Instant, u64)>,
+    smoothed_rate: Option<f64>,
+}
+
+impl EtaEstimator {
+    pub fn observe(&mut self, now: Instant, processed_ms: u64, total_ms: u64) -> Option<u64> {
+        let processed_ms = processed_ms.min(total_ms);
+        match self.last_sample {
+            Some((sampled_at, previous_ms)) => {
+                let wall_ms = now.saturating_duration_since(sampled_at).as_secs_f64() * 1_000.0;
+                if wall_ms > 0.0 && processed_ms >= previous_ms {
+                    let rate = (processed_ms - previous_ms) as f64 / wall_ms;
+                    self.smoothed_rate = Some(smooth_rate(self.smoothed_rate, rate));
+                    self.last_sample = Some((now, processed_ms));
+                }
+            }
+            None => self.last_sample = Some((now, processed_ms)),
+        }
+
+        estimate_remaining_ms(self.smoothed_rate?, processed_ms, total_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn suaviza_la_velocidad_con_media_movil() {
+        assert_eq!(smooth_rate(None, 2.0), 2.0);
+        let smoothed = smooth_rate(Some(2.0), 4.0);
+        assert!((smoothed - 2.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_estima_antes_del_cinco_por_ciento() {
+        assert_eq!(estimate_remaining_ms(2.0, 400, 10_000), None);
+        assert_eq!(estimate_remaining_ms(2.0, 500, 10_000), Some(4_750));
+        assert_eq!(estimate_remaining_ms(0.0, 5_000, 10_000), None);
+        assert_eq!(estimate_remaining_ms(2.0, 5_000, 0), None);
+    }
+
+    #[test]
+    fn estimador_usa_muestras_sucesivas() {
+        let start = Instant::now();
+        let mut estimator = EtaEstimator::default();
+
+        assert_eq!(estimator.observe(start, 0, 60_000), None);
+        assert_eq!(
+            estimator.observe(start + Duration::from_millis(500), 2_000, 60_000),
+            None
+        );
+        let eta = estimator
+            .observe(start + Duration::from_millis(1_000), 4_000, 60_000)
+            .expect("eta tras superar el 5%");
+        assert_eq!(eta, 14_000);
+    }
+
+    #[test]
+    fn interpreta_la_salida_de_progress() {
+        assert_eq!(
+            parse_progress_out_time_ms("out_time_us=1534000"),
+            Some(1_534)
+        );
+        assert_eq!(parse_progress_out_time_ms("out_time_us=N/A"), None);
+        assert_eq!(parse_progress_out_time_ms("progress=continue"), None);
+    }
+}
@@ -0,0 +1,280 @@
+use std::{
+    fs::{self, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use chrono::NaiveDate;
+
+use crate::encoder::{media_info::probe_output_file, output_naming::sanitize_file_name_component};
+
+const TAKE_PREFIX: &str = "take_";
+const MAX_TAKE: u32 = 9_999;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTake {
+    pub path: String,
+    pub date: String,
+    pub take: u32,
+    pub size_bytes: u64,
+    pub duration_ms: Option<u64>,
+}
+
+pub fn project_folder_name(project: &str) -> Result<String, String> {
+    let folder = sanitize_file_name_component(project);
+    if folder.is_empty() || folder.chars().all(|ch| ch == '.') {
+        return Err(format!("Nombre de proyecto inválido: '{project}'"));
+    }
+
+    Ok(folder)
+}
+
+pub fn project_date_dir(
+    base_dir: &Path,
+    project: &str,
+    date: NaiveDate,
+) -> Result<PathBuf, String> {
+    Ok(base_dir
+        .join(project_folder_name(project)?)
+        .join(date.format("%Y-%m-%d").to_string()))
+}
+
+fn parse_take_number(file_name: &str) -> Option<u32> {
+    let (stem, _extension) = file_name.split_once('.')?;
+    let digits = stem.strip_prefix(TAKE_PREFIX)?;
+    if digits.is_empty() || !digits.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+
+    digits.parse().ok()
+}
+
+fn take_file_name(take: u32, extension: &str) -> String {
+    format!("{TAKE_PREFIX}{take:02}.{extension}")
+}
+
+fn next_take_number(date_dir: &Path) -> u32 {
+    let Ok(entries) = fs::read_dir(date_dir) else {
+        return 1;
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| parse_take_number(&entry.file_name().to_string_lossy()))
+        .max()
+        .map(|take| take.saturating_add(1))
+        .unwrap_or(1)
+}
+
+pub fn peek_next_take_path(date_dir: &Path, extension: &str) -> PathBuf {
+    date_dir.join(take_file_name(next_take_number(date_dir), extension))
+}
+
+fn take_reservation_lock() -> &'static Mutex<()> {
+    static TAKE_RESERVATION_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    TAKE_RESERVATION_LOCK.get_or_init(|| Mutex::new(()))
+}
+
+/// Reserva la próxima toma creando el archivo vacío con `create_new`, así dos
+/// grabaciones seguidas nunca comparten número aunque se inicien a la vez.
+pub fn reserve_take_path(date_dir: &Path, extension: &str) -> Result<PathBuf, String> {
+    let _guard = take_reservation_lock()
+        .lock()
+        .map_err(|_| "No se pudo reservar la toma (lock interno en estado inválido)".to_string())?;
+
+    fs::create_dir_all(date_dir).map_err(|err| {
+        format!(
+            "No se pudo crear carpeta del proyecto '{}': {err}",
+            date_dir.display()
+        )
+    })?;
+
+    let mut take = next_take_number(date_dir);
+    while take <= MAX_TAKE {
+        let candidate = date_dir.join(take_file_name(take, extension));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&candidate)
+        {
+            Ok(_) => return Ok(candidate),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => take += 1,
+            Err(err) => {
+                return Err(format!(
+                    "No se pudo reservar la toma '{}': {err}",
+                    candidate.display()
+                ))
+            }
+        }
+    }
+
+    Err(format!(
+        "No quedan números de toma libres en '{}'",
+        date_dir.display()
+    ))
+}
+
+pub fn release_take_reservation(path: &Path) {
+    if fs::metadata(path)
+        .map(|metadata| metadata.is_file() && metadata.len() == 0)
+        .unwrap_or(false)
+    {
+        let _ = fs::remove_file(path);
+    }
+}
+
+pub fn list_project_takes(base_dir: &Path, project: &str) -> Result<Vec<ProjectTake>, String> {
+    let project_dir = base_dir.join(project_folder_name(project)?);
+    let date_dirs = match fs::read_dir(&project_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(format!(
+                "No se pudo leer la carpeta del proyecto '{}': {err}",
+                project_dir.display()
+            ))
+        }
+    };
+
+    let mut takes = Vec::new();
+    for date_entry in date_dirs.filter_map(|entry| entry.ok()) {
+        let date = date_entry.file_name().to_string_lossy().into_owned();
+        if NaiveDate::parse_from_str(&date, "%Y-%m-%d").is_err() {
+            continue;
+        }
+
+        let Ok(files) = fs::read_dir(date_entry.path()) else {
+            continue;
+        };
+
+        for file in files.filter_map(|entry| entry.ok()) {
+            let file_name = file.file_name().to_string_lossy().into_owned();
+            if file_name.matches('.').count() != 1 {
+                continue;
+            }
+            let Some(take) = parse_take_number(&file_name) else {
+                continue;
+            };
+            let Ok(metadata) = file.metadata() else {
+                continue;
+            };
+            if !metadata.is_file() || metadata.len() == 0 {
+                continue;
+            }
+
+            let path = file.path();
+            takes.push(ProjectTake {
+                path: path.to_string_lossy().into_owned(),
+                date: date.clone(),
+                take,
+                size_bytes: metadata.len(),
+                duration_ms: probe_output_file(&path).ok().map(|info| info.duration_ms),
+            });
+        }
+    }
+
+    takes.sort_by(|a, b| a.date.cmp(&b.date).then(a.take.cmp(&b.take)));
+    Ok(takes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, 20).unwrap()
+    }
+
+    #[test]
+    fn compone_la_ruta_del_proyecto_por_fecha() {
+        let dir = project_date_dir(Path::new("base"), "Demo: intro", date()).unwrap();
+        assert_eq!(
+            dir,
+            Path::new("base").join("Demo_ intro").join("2024-05-20")
+        );
+
+        assert!(project_date_dir(Path::new("base"), "..", date()).is_err());
+        assert!(project_date_dir(Path::new("base"), "  ", date()).is_err());
+    }
+
+    #[test]
+    fn continua_la_numeracion_de_tomas_existentes() {
+        let base = tempfile::tempdir().unwrap();
+        let date_dir = project_date_dir(base.path(), "demo", date()).unwrap();
+        fs::create_dir_all(&date_dir).unwrap();
+        fs::write(date_dir.join("take_01.mp4"), b"a").unwrap();
+        fs::write(date_dir.join("take_07.mkv"), b"b").unwrap();
+        fs::write(date_dir.join("notas.txt"), b"c").unwrap();
+
+        assert_eq!(
+            peek_next_take_path(&date_dir, "mp4"),
+            date_dir.join("take_08.mp4")
+        );
+        assert_eq!(
+            reserve_take_path(&date_dir, "mp4").unwrap(),
+            date_dir.join("take_08.mp4")
+        );
+        assert_eq!(
+            reserve_take_path(&date_dir, "mp4").unwrap(),
+            date_dir.join("take_09.mp4")
+        );
+    }
+
+    #[test]
+    fn reservas_concurrentes_no_colisionan() {
+        let base = tempfile::tempdir().unwrap();
+        let date_dir = project_date_dir(base.path(), "demo", date()).unwrap();
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let date_dir = date_dir.clone();
+                std::thread::spawn(move || reserve_take_path(&date_dir, "mp4").unwrap())
+            })
+            .collect();
+        let mut paths: Vec<PathBuf> = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+        paths.sort();
+        paths.dedup();
+
+        assert_eq!(paths.len(), 8);
+        assert_eq!(paths[7], date_dir.join("take_08.mp4"));
+    }
+
+    #[test]
+    fn lista_tomas_ordenadas_e_ignora_derivados() {
+        let base = tempfile::tempdir().unwrap();
+        for (day, take) in [(21, 1), (20, 2), (20, 1)] {
+            let date_dir = project_date_dir(
+                base.path(),
+                "demo",
+                NaiveDate::from_ymd_opt(2024, 5, day).unwrap(),
+            )
+            .unwrap();
+            fs::create_dir_all(&date_dir).unwrap();
+            fs::write(date_dir.join(take_file_name(take, "mp4")), b"video").unwrap();
+        }
+        let first_day = project_date_dir(base.path(), "demo", date()).unwrap();
+        fs::write(first_day.join("take_01.compressed.mp4"), b"x").unwrap();
+        fs::write(first_day.join("take_03.mp4"), b"").unwrap();
+
+        let takes = list_project_takes(base.path(), "demo").unwrap();
+        let summary: Vec<_> = takes
+            .iter()
+            .map(|take| (take.date.as_str(), take.take, take.size_bytes))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("2024-05-20", 1, 5),
+                ("2024-05-20", 2, 5),
+                ("2024-05-21", 1, 5)
+            ]
+        );
+        assert!(list_project_takes(base.path(), "otro").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,540 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    ffi::OsString,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    process::{ChildStdout, Command, Stdio},
+    sync::{Mutex, OnceLock},
+    time::UNIX_EPOCH,
+};
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use tempfile::Builder as TempBuilder;
+
+use crate::encoder::{
+    ffmpeg_paths::resolve_ffmpeg_bin,
+    media_info::probe_output_file,
+    post_process::{
+        run_ffmpeg_cancellable, run_ffmpeg_cancellable_with_stdout, PostProcessKind,
+        PostProcessTask,
+    },
+};
+
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+pub const DEFAULT_REFERENCE_SECONDS: u32 = 10;
+const MAX_REFERENCE_SECONDS: u32 = 60;
+const SAMPLE_FPS: u32 = 2;
+const MAX_ANALYSIS_WIDTH: u32 = 960;
+const CODEC_BLOCK_SIZE: usize = 8;
+const HASH_SAMPLE_BYTES: u64 = 1024 * 1024;
+const MAX_CACHED_REPORTS: usize = 32;
+const REFERENCE_SUFFIX: &str = "reference";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QualityScoreSource {
+    Heuristic,
+    Vmaf,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QualityMetrics {
+    pub spatial_information_mean: f64,
+    pub spatial_information_max: f64,
+    pub blockiness_mean: f64,
+    pub blockiness_max: f64,
+    pub vmaf: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QualityReport {
+    pub path: String,
+    pub reference_seconds: u32,
+    pub frames_analyzed: u32,
+    pub score: f64,
+    pub score_source: QualityScoreSource,
+    pub metrics: QualityMetrics,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FrameMetrics {
+    spatial_information: f64,
+    blockiness: f64,
+}
+
+fn spatial_information(luma: &[u8], width: usize, height: usize) -> f64 {
+    if width < 3 || height < 3 || luma.len() < width * height {
+        return 0.0;
+    }
+
+    let px = |x: usize, y: usize| f64::from(luma[y * width + x]);
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+    let mut count = 0.0;
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let gx = px(x + 1, y - 1) + 2.0 * px(x + 1, y) + px(x + 1, y + 1)
+                - px(x - 1, y - 1)
+                - 2.0 * px(x - 1, y)
+                - px(x - 1, y + 1);
+            let gy = px(x - 1, y + 1) + 2.0 * px(x, y + 1) + px(x + 1, y + 1)
+                - px(x - 1, y - 1)
+                - 2.0 * px(x, y - 1)
+                - px(x + 1, y - 1);
+            let magnitude = (gx * gx + gy * gy).sqrt();
+            sum += magnitude;
+            sum_sq += magnitude * magnitude;
+            count += 1.0;
+        }
+    }
+
+    let mean = sum / count;
+    (sum_sq / count - mean * mean).max(0.0).sqrt()
+}
+
+fn blockiness(luma: &[u8], width: usize, height: usize) -> f64 {
+    if width <= CODEC_BLOCK_SIZE || height <= CODEC_BLOCK_SIZE || luma.len() < width * height {
+        return 1.0;
+    }
+
+    let px = |x: usize, y: usize| i32::from(luma[y * width + x]);
+    let (mut edge_sum, mut edge_count) = (0_u64, 0_u64);
+    let (mut inner_sum, mut inner_count) = (0_u64, 0_u64);
+    let mut accumulate = |offset: usize, diff: u32| {
+        if offset.is_multiple_of(CODEC_BLOCK_SIZE) {
+            edge_sum += u64::from(diff);
+            edge_count += 1;
+        } else {
+            inner_sum += u64::from(diff);
+            inner_count += 1;
+        }
+    };
+
+    for y in 0..height {
+        for x in 1..width {
+            accumulate(x, px(x, y).abs_diff(px(x - 1, y)));
+        }
+    }
+    for y in 1..height {
+        for x in 0..width {
+            accumulate(y, px(x, y).abs_diff(px(x, y - 1)));
+        }
+    }
+
+    let edge_mean = edge_sum as f64 / edge_count.max(1) as f64;
+    let inner_mean = inner_sum as f64 / inner_count.max(1) as f64;
+    if inner_mean < 0.5 {
+        return if edge_mean < 0.5 {
+            1.0
+        } else {
+            1.0 + edge_mean
+        };
+    }
+
+    edge_mean / inner_mean
+}
+
+fn heuristic_score(blockiness_mean: f64) -> f64 {
+    let penalty = ((blockiness_mean - 1.0).max(0.0) * 150.0).min(100.0);
+    100.0 - penalty
+}
+
+fn summarize(
+    frames: &[FrameMetrics],
+    vmaf: Option<f64>,
+) -> (f64, QualityScoreSource, QualityMetrics) {
+    let count = frames.len().max(1) as f64;
+    let metrics = QualityMetrics {
+        spatial_information_mean: frames.iter().map(|f| f.spatial_information).sum::<f64>() / count,
+        spatial_information_max: frames
+            .iter()
+            .map(|f| f.spatial_information)
+            .fold(0.0, f64::max),
+        blockiness_mean: frames.iter().map(|f| f.blockiness).sum::<f64>() / count,
+        blockiness_max: frames.iter().map(|f| f.blockiness).fold(0.0, f64::max),
+        vmaf,
+    };
+
+    match vmaf {
+        Some(vmaf) => (vmaf.clamp(0.0, 100.0), QualityScoreSource::Vmaf, metrics),
+        None => (
+            heuristic_score(metrics.blockiness_mean),
+            QualityScoreSource::Heuristic,
+            metrics,
+        ),
+    }
+}
+
+fn analysis_dimensions(width: u32, height: u32) -> (u32, u32) {
+    let (width, height) = if width > MAX_ANALYSIS_WIDTH {
+        let scaled = u64::from(height) * u64::from(MAX_ANALYSIS_WIDTH) / u64::from(width);
+        (MAX_ANALYSIS_WIDTH, scaled as u32)
+    } else {
+        (width, height)
+    };
+
+    ((width & !1).max(2), (height & !1).max(2))
+}
+
+fn sample_args(path: &Path, reference_seconds: u32, width: u32, height: u32) -> Vec<OsString> {
+    vec![
+        "-t".into(),
+        reference_seconds.to_string().into(),
+        "-i".into(),
+        path.into(),
+        "-an".into(),
+        "-vf".into(),
+        format!("fps={SAMPLE_FPS},scale={width}:{height},format=gray").into(),
+        "-f".into(),
+        "rawvideo".into(),
+        "-pix_fmt".into(),
+        "gray".into(),
+        "-".into(),
+    ]
+}
+
+fn read_frame_metrics(mut stdout: ChildStdout, width: usize, height: usize) -> Vec<FrameMetrics> {
+    let mut frames = Vec::new();
+    let mut buffer = vec![0_u8; width * height];
+    while stdout.read_exact(&mut buffer).is_ok() {
+        frames.push(FrameMetrics {
+            spatial_information: spatial_information(&buffer, width, height),
+            blockiness: blockiness(&buffer, width, height),
+        });
+    }
+    frames
+}
+
+pub fn reference_stream_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .unwrap_or("recording");
+    path.with_file_name(format!("{stem}.{REFERENCE_SUFFIX}.mkv"))
+}
+
+fn escape_filter_path(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('\\', "/")
+        .replace(':', "\\:")
+        .replace('\'', "\\'")
+}
+
+fn vmaf_args(
+    path: &Path,
+    reference: &Path,
+    log_path: &Path,
+    reference_seconds: u32,
+) -> Vec<OsString> {
+    vec![
+        "-t".into(),
+        reference_seconds.to_string().into(),
+        "-i".into(),
+        path.into(),
+        "-t".into(),
+        reference_seconds.to_string().into(),
+        "-i".into(),
+        reference.into(),
+        "-lavfi".into(),
+        format!(
+            "[0:v][1:v]scale2ref[dist][ref];[dist][ref]libvmaf=log_fmt=json:log_path='{}'",
+            escape_filter_path(log_path)
+        )
+        .into(),
+        "-f".into(),
+        "null".into(),
+        "-".into(),
+    ]
+}
+
+fn parse_vmaf_log(raw: &[u8]) -> Option<f64> {
+    let log: serde_json::Value = serde_json::from_slice(raw).ok()?;
+    log.pointer("/pooled_metrics/vmaf/mean")?.as_f64()
+}
+
+fn is_vmaf_available() -> bool {
+    static VMAF_AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *VMAF_AVAILABLE.get_or_init(|| {
+        let mut cmd = Command::new(resolve_ffmpeg_bin());
+        cmd.arg("-hide_banner")
+            .arg("-filters")
+            .stdin(Stdio::null())
+            .stderr(Stdio::null());
+
+        #[cfg(windows)]
+        {
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        cmd.output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).contains(" libvmaf "))
+            .unwrap_or(false)
+    })
+}
+
+fn compute_vmaf(
+    path: &Path,
+    reference_seconds: u32,
+    task: &PostProcessTask,
+) -> Result<Option<f64>, String> {
+    let reference = reference_stream_path(path);
+    if !reference.is_file() || !is_vmaf_available() {
+        return Ok(None);
+    }
+
+    let log_dir = TempBuilder::new()
+        .prefix("capturist-vmaf-")
+        .tempdir()
+        .map_err(|err| format!("No se pudo crear carpeta temporal para VMAF: {err}"))?;
+    let log_path = log_dir.path().join("vmaf.json");
+    run_ffmpeg_cancellable(
+        vmaf_args(path, &reference, &log_path, reference_seconds),
+        "calcular VMAF",
+        task,
+    )?;
+
+    Ok(fs::read(&log_path)
+        .ok()
+        .and_then(|raw| parse_vmaf_log(&raw)))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct QualityCacheKey {
+    path: PathBuf,
+    size_bytes: u64,
+    modified_ms: u128,
+    content_hash: u64,
+    reference_seconds: u32,
+}
+
+/// Hash del inicio y el final del archivo junto con tamaño y mtime: detecta
+/// reemplazos sin leer grabaciones de varios GB completas.
+fn cache_key(path: &Path, reference_seconds: u32) -> Result<QualityCacheKey, String> {
+    let metadata = fs::metadata(path)
+        .map_err(|err| format!("No se pudo leer el archivo '{}': {err}", path.display()))?;
+    let modified_ms = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or(0);
+
+    let mut file = File::open(path)
+        .map_err(|err| format!("No se pudo abrir el archivo '{}': {err}", path.display()))?;
+    let mut hasher = DefaultHasher::new();
+    let mut chunk = Vec::new();
+    (&mut file)
+        .take(HASH_SAMPLE_BYTES)
+        .read_to_end(&mut chunk)
+        .map_err(|err| format!("No se pudo leer el archivo '{}': {err}", path.display()))?;
+    chunk.hash(&mut hasher);
+    if metadata.len() > HASH_SAMPLE_BYTES * 2 {
+        chunk.clear();
+        file.seek(SeekFrom::End(-(HASH_SAMPLE_BYTES as i64)))
+            .and_then(|_| file.read_to_end(&mut chunk))
+            .map_err(|err| format!("No se pudo leer el archivo '{}': {err}", path.display()))?;
+        chunk.hash(&mut hasher);
+    }
+
+    Ok(QualityCacheKey {
+        path: path.to_path_buf(),
+        size_bytes: metadata.len(),
+        modified_ms,
+        content_hash: hasher.finish(),
+        reference_seconds,
+    })
+}
+
+fn report_cache() -> &'static Mutex<HashMap<QualityCacheKey, QualityReport>> {
+    static REPORT_CACHE: OnceLock<Mutex<HashMap<QualityCacheKey, QualityReport>>> = OnceLock::new();
+    REPORT_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cached_report(key: &QualityCacheKey) -> Option<QualityReport> {
+    report_cache()
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(key).cloned())
+}
+
+fn store_report(key: QualityCacheKey, report: QualityReport) {
+    if let Ok(mut cache) = report_cache().lock() {
+        if cache.len() >= MAX_CACHED_REPORTS {
+            cache.clear();
+        }
+        cache.insert(key, report);
+    }
+}
+
+pub fn analyze_recording_quality(
+    path: &Path,
+    reference_seconds: u32,
+) -> Result<QualityReport, String> {
+    if reference_seconds == 0 || reference_seconds > MAX_REFERENCE_SECONDS {
+        return Err(format!(
+            "La muestra debe durar entre 1 y {MAX_REFERENCE_SECONDS} segundos"
+        ));
+    }
+
+    let key = cache_key(path, reference_seconds)?;
+    if let Some(report) = cached_report(&key) {
+        return Ok(report);
+    }
+
+    let info = probe_output_file(path)?;
+    let (Some(width), Some(height)) = (info.width, info.height) else {
+        return Err(format!(
+            "'{}' no contiene video para analizar",
+            path.display()
+        ));
+    };
+    let (width, height) = analysis_dimensions(width, height);
+
+    let task = PostProcessTask::begin(PostProcessKind::QualityAnalysis, path);
+    let frames = run_ffmpeg_cancellable_with_stdout(
+        sample_args(path, reference_seconds, width, height),
+        "decodificar la muestra de calidad",
+        &task,
+        move |stdout| read_frame_metrics(stdout, width as usize, height as usize),
+    )?;
+    if frames.is_empty() {
+        return Err(format!(
+            "No se pudieron decodificar frames de '{}'",
+            path.display()
+        ));
+    }
+
+    let vmaf = compute_vmaf(path, reference_seconds, &task)?;
+    let (score, score_source, metrics) = summarize(&frames, vmaf);
+    let report = QualityReport {
+        path: path.to_string_lossy().into_owned(),
+        reference_seconds,
+        frames_analyzed: frames.len() as u32,
+        score,
+        score_source,
+        metrics,
+    };
+
+    store_report(key, report.clone());
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient_frame(width: usize, height: usize) -> Vec<u8> {
+        (0..height)
+            .flat_map(|_| (0..width).map(move |x| (x * 255 / width) as u8))
+            .collect()
+    }
+
+    fn blocky_frame(width: usize, height: usize) -> Vec<u8> {
+        (0..height)
+            .flat_map(|y| {
+                (0..width).map(move |x| {
+                    let block = (x / CODEC_BLOCK_SIZE + y / CODEC_BLOCK_SIZE) % 2;
+                    (block * 120 + (x % 2) * 3) as u8
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn informacion_espacial_distingue_plano_de_texturizado() {
+        let flat = vec![128_u8; 64 * 64];
+        assert_eq!(spatial_information(&flat, 64, 64), 0.0);
+
+        let noise: Vec<u8> = (0..64 * 64_u64)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        assert!(
+            spatial_information(&noise, 64, 64)
+                > spatial_information(&gradient_frame(64, 64), 64, 64)
+        );
+    }
+
+    #[test]
+    fn bloques_de_8_px_suben_la_metrica_y_bajan_el_puntaje() {
+        let smooth = gradient_frame(64, 64);
+        let blocky = blocky_frame(64, 64);
+
+        let smooth_blockiness = blockiness(&smooth, 64, 64);
+        let blocky_blockiness = blockiness(&blocky, 64, 64);
+        assert!((smooth_blockiness - 1.0).abs() < 0.5);
+        assert!(blocky_blockiness > 5.0);
+        assert!(heuristic_score(smooth_blockiness) > heuristic_score(blocky_blockiness));
+        assert_eq!(heuristic_score(1.0), 100.0);
+        assert_eq!(heuristic_score(10.0), 0.0);
+    }
+
+    #[test]
+    fn resumen_prefiere_vmaf_si_existe() {
+        let frames = [
+            FrameMetrics {
+                spatial_information: 10.0,
+                blockiness: 1.0,
+            },
+            FrameMetrics {
+                spatial_information: 30.0,
+                blockiness: 1.2,
+            },
+        ];
+
+        let (score, source, metrics) = summarize(&frames, None);
+        assert_eq!(source, QualityScoreSource::Heuristic);
+        assert_eq!(metrics.spatial_information_mean, 20.0);
+        assert_eq!(metrics.spatial_information_max, 30.0);
+        assert!((score - 85.0).abs() < 1e-9);
+
+        let (score, source, _) = summarize(&frames, Some(93.5));
+        assert_eq!((score, source), (93.5, QualityScoreSource::Vmaf));
+    }
+
+    #[test]
+    fn arma_muestra_y_vmaf_con_rutas_escapadas() {
+        assert_eq!(analysis_dimensions(1920, 1080), (960, 540));
+        assert_eq!(analysis_dimensions(641, 359), (640, 358));
+        assert_eq!(
+            reference_stream_path(Path::new("videos/clip.mp4")),
+            PathBuf::from("videos/clip.reference.mkv")
+        );
+        assert_eq!(
+            escape_filter_path(Path::new(r"C:\tmp\vmaf.json")),
+            r"C\:/tmp/vmaf.json"
+        );
+        assert_eq!(
+            parse_vmaf_log(br#"{"pooled_metrics":{"vmaf":{"mean":91.25}}}"#),
+            Some(91.25)
+        );
+        assert_eq!(parse_vmaf_log(b"{}"), None);
+    }
+
+    #[test]
+    fn la_clave_de_cache_cambia_si_cambia_el_archivo() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("clip.mp4");
+        fs::write(&file, b"primera version").unwrap();
+        let first = cache_key(&file, 10).unwrap();
+
+        assert_eq!(cache_key(&file, 10).unwrap(), first);
+        assert_ne!(cache_key(&file, 5).unwrap(), first);
+
+        fs::write(&file, b"segunda version").unwrap();
+        assert_ne!(
+            cache_key(&file, 10).unwrap().content_hash,
+            first.content_hash
+        );
+        assert!(analyze_recording_quality(&file, 0).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use chrono::{DateTime, Local};
+
+use crate::encoder::{
+    chapters::ChapterMarker,
+    config::{OutputFormat, RecordingMetadata},
+    thumbnail::generate_recording_thumbnail,
+    trim::TrimRange,
+};
+
+const HISTORY_FILE_NAME: &str = "recording-history.json";
+pub const MAX_HISTORY_ENTRIES: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingHistoryEntry {
+    pub path: String,
+    pub started_at: String,
+    pub duration_ms: u64,
+    pub size_bytes: u64,
+    #[serde(default)]
+    pub target_name: Option<String>,
+    pub format: OutputFormat,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub thumbnail_path: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FinishedRecording {
+    pub final_path: PathBuf,
+    pub started_at: DateTime<Local>,
+    pub duration_ms: u64,
+    pub target_name: Option<String>,
+    pub format: OutputFormat,
+    pub project: Option<String>,
+    pub metadata: RecordingMetadata,
+    pub chapters: Vec<ChapterMarker>,
+    pub trim: TrimRange,
+}
+
+impl FinishedRecording {
+    pub fn container_tags(&self) -> Vec<(&'static str, String)> {
+        self.metadata.container_tags(self.started_at)
+    }
+
+    pub fn record(self) {
+        let Some(history_path) = history_file_path() else {
+            return;
+        };
+        let Ok(metadata) = fs::metadata(&self.final_path) else {
+            return;
+        };
+        if !metadata.is_file() || metadata.len() == 0 {
+            return;
+        }
+
+        let thumbnail_path = generate_recording_thumbnail(&self.final_path, self.duration_ms);
+        let entry = RecordingHistoryEntry {
+            path: self.final_path.to_string_lossy().into_owned(),
+            started_at: self.started_at.to_rfc3339(),
+            duration_ms: self.duration_ms,
+            size_bytes: metadata.len(),
+            target_name: self.target_name,
+            format: self.format,
+            project: self.project,
+            thumbnail_path: thumbnail_path.map(|path| path.to_string_lossy().into_owned()),
+        };
+
+        let _guard = history_lock().lock();
+        if let Err(err) = append_history_entry(history_path, entry) {
+            eprintln!("[history] No se pudo guardar el historial de grabaciones: {err}");
+        }
+    }
+}
+
+fn history_dir() -> &'static OnceLock<PathBuf> {
+    static HISTORY_DIR: OnceLock<PathBuf> = OnceLock::new();
+    &HISTORY_DIR
+}
+
+fn history_lock() -> &'static Mutex<()> {
+    static HISTORY_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    HISTORY_LOCK.get_or_init(|| Mutex::new(()))
+}
+
+pub fn init_recording_history(app_data_dir: PathBuf) {
+    let _ = history_dir().set(app_data_dir);
+}
+
+fn history_file_path() -> Option<PathBuf> {
+    history_dir().get().map(|dir| dir.join(HISTORY_FILE_NAME))
+}
+
+fn load_history(history_path: &Path) -> Vec<RecordingHistoryEntry> {
+    // Un historial dañado no debe impedir grabar; se reemplaza en la próxima escritura.
+    fs::read(history_path)
+        .ok()
+        .and_then(|raw| serde_json::from_slice(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_history(history_path: &Path, entries: &[RecordingHistoryEntry]) -> Result<(), String> {
+    if let Some(parent) = history_path.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            format!(
+                "No se pudo crear la carpeta del historial '{}': {err}",
+                parent.display()
+            )
+        })?;
+    }
+
+    let raw = serde_json::to_vec_pretty(entries)
+        .map_err(|err| format!("No se pudo serializar el historial: {err}"))?;
+    let staging_path = history_path.with_extension("json.tmp");
+    fs::write(&staging_path, raw)
+        .and_then(|_| fs::rename(&staging_path, history_path))
+        .map_err(|err| {
+            format!(
+                "No se pudo escribir el historial '{}': {err}",
+                history_path.display()
+            )
+        })
+}
+
+fn prune_missing(entries: &mut Vec<RecordingHistoryEntry>) -> bool {
+    let before = entries.len();
+    entries.retain(|entry| Path::new(&entry.path).is_file());
+    entries.len() != before
+}
+
+fn append_history_entry(history_path: PathBuf, entry: RecordingHistoryEntry) -> Result<(), String> {
+    let mut entries = load_history(&history_path);
+    entries.retain(|existing| existing.path != entry.path);
+    entries.insert(0, entry);
+    prune_missing(&mut entries);
+    entries.truncate(MAX_HISTORY_ENTRIES);
+    save_history(&history_path, &entries)
+}
+
+fn recent_history_entries(
+    history_path: &Path,
+    limit: usize,
+) -> Result<Vec<RecordingHistoryEntry>, String> {
+    let mut entries = load_history(history_path);
+    if prune_missing(&mut entries) {
+        save_history(history_path, &entries)?;
+    }
+
+    entries.truncate(limit);
+    Ok(entries)
+}
+
+pub fn get_recent_recordings(limit: usize) -> Result<Vec<RecordingHistoryEntry>, String> {
+    let Some(history_path) = history_file_path() else {
+        return Ok(Vec::new());
+    };
+
+    let _guard = history_lock().lock();
+    recent_history_entries(&history_path, limit)
+}
+
+pub fn clear_recording_history() -> Result<(), String> {
+    let Some(history_path) = history_file_path() else {
+        return Ok(());
+    };
+
+    let _guard = history_lock().lock();
+    match fs::remove_file(&history_path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(format!(
+            "No se pudo borrar el historial de grabaciones: {err}"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &Path) -> RecordingHistoryEntry {
+        RecordingHistoryEntry {
+            path: path.to_string_lossy().into_owned(),
+            started_at: "2024-05-20T10:00:00+00:00".to_string(),
+            duration_ms: 1_000,
+            size_bytes: 5,
+            target_name: Some("Pantalla 1".to_string()),
+            format: OutputFormat::Mp4,
+            project: None,
+            thumbnail_path: None,
+        }
+    }
+
+    #[test]
+    fn agrega_al_inicio_sin_duplicar_y_poda_faltantes() {
+        let dir = tempfile::tempdir().unwrap();
+        let history_path = dir.path().join(HISTORY_FILE_NAME);
+        let first = dir.path().join("a.mp4");
+        let second = dir.path().join("b.mp4");
+        fs::write(&first, b"video").unwrap();
+        fs::write(&second, b"video").unwrap();
+
+        append_history_entry(history_path.clone(), entry(&first)).unwrap();
+        append_history_entry(history_path.clone(), entry(&second)).unwrap();
+        append_history_entry(history_path.clone(), entry(&first)).unwrap();
+
+        let recent = recent_history_entries(&history_path, 10).unwrap();
+        assert_eq!(recent, vec![entry(&first), entry(&second)]);
+
+        fs::remove_file(&first).unwrap();
+        let recent = recent_history_entries(&history_path, 10).unwrap();
+        assert_eq!(recent, vec![entry(&second)]);
+        assert_eq!(load_history(&history_path), vec![entry(&second)]);
+    }
+
+    #[test]
+    fn respeta_el_limite_de_entradas() {
+        let dir = tempfile::tempdir().unwrap();
+        let history_path = dir.path().join(HISTORY_FILE_NAME);
+        let file = dir.path().join("clip.mp4");
+        fs::write(&file, b"video").unwrap();
+
+        let entries: Vec<_> = (0..MAX_HISTORY_ENTRIES + 5).map(|_| entry(&file)).collect();
+        save_history(&history_path, &entries).unwrap();
+
+        let other = dir.path().join("otro.mp4");
+        fs::write(&other, b"video").unwrap();
+        append_history_entry(history_path.clone(), entry(&other)).unwrap();
+
+        let stored = load_history(&history_path);
+        assert_eq!(stored.len(), MAX_HISTORY_ENTRIES);
+        assert_eq!(stored[0], entry(&other));
+        assert_eq!(recent_history_entries(&history_path, 0).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn historial_danado_se_trata_como_vacio() {
+        let dir = tempfile::tempdir().unwrap();
+        let history_path = dir.path().join(HISTORY_FILE_NAME);
+        fs::write(&history_path, b"{no es json").unwrap();
+
+        assert!(load_history(&history_path).is_empty());
+    }
+}
@@ -0,0 +1,571 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use tempfile::Builder as TempBuilder;
+
+use crate::encoder::{
+    audio_capture::wav_writer::concat_list_contents,
+    codec_profile::{known_level, profile_level_options},
+    config::VideoCodec,
+    media_info::{
+        probe_output_file, probe_video_keyframes, probe_video_stream_format, VideoKeyframes,
+        VideoStreamFormat,
+    },
+    output_paths::is_same_file,
+    post_process::{ffmpeg_seconds, run_ffmpeg_cancellable, PostProcessKind, PostProcessTask},
+    trim::{ResolvedTrim, TrimRange},
+};
+
+pub const EVENT_RECORDING_TRIM_COMPLETED: &str = "recording-trim-completed";
+pub const EVENT_RECORDING_TRIM_FAILED: &str = "recording-trim-failed";
+const PRECISE_HEAD_CRF: &str = "18";
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrimResult {
+    pub input_path: String,
+    pub output_path: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub reencoded_until_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrimFailed {
+    pub input_path: String,
+    pub output_path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrimEvent {
+    Completed(TrimResult),
+    Failed(TrimFailed),
+}
+
+pub type TrimEventSink = Arc<dyn Fn(TrimEvent) + Send + Sync>;
+
+fn trim_event_sink_slot() -> &'static Mutex<Option<TrimEventSink>> {
+    static SLOT: OnceLock<Mutex<Option<TrimEventSink>>> = OnceLock::new();
+    SLOT.get_or_init(|| Mutex::new(None))
+}
+
+pub fn set_trim_event_sink(sink: TrimEventSink) {
+    if let Ok(mut guard) = trim_event_sink_slot().lock() {
+        *guard = Some(sink);
+    }
+}
+
+fn notify_trim_event(event: TrimEvent) {
+    let sink = trim_event_sink_slot()
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone());
+    if let Some(sink) = sink {
+        sink(event);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrimPlan {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub head_end_ms: Option<u64>,
+}
+
+pub fn plan_trim(
+    range: TrimRange,
+    total_ms: u64,
+    keyframes_ms: &[u64],
+    precise: bool,
+) -> Result<TrimPlan, String> {
+    range.validate()?;
+    if total_ms == 0 {
+        return Err("No se pudo determinar la duración del video".to_string());
+    }
+    let requested_start_ms = range.start_ms.unwrap_or(0);
+    let ResolvedTrim { start_ms, end_ms } = match range.resolve(total_ms, keyframes_ms)? {
+        Some(resolved) => resolved,
+        None if precise && requested_start_ms > 0 => ResolvedTrim {
+            start_ms: 0,
+            end_ms: total_ms,
+        },
+        None => return Err("El recorte no quita nada: el tramo cubre todo el video".to_string()),
+    };
+
+    let mut plan = TrimPlan {
+        start_ms,
+        end_ms,
+        head_end_ms: None,
+    };
+    if precise && plan.start_ms != requested_start_ms {
+        plan.start_ms = requested_start_ms;
+        let next_keyframe_ms = keyframes_ms
+            .iter()
+            .copied()
+            .filter(|&keyframe_ms| keyframe_ms > requested_start_ms)
+            .min();
+        plan.head_end_ms =
+            Some(next_keyframe_ms.map_or(end_ms, |keyframe_ms| keyframe_ms.min(end_ms)));
+    }
+    Ok(plan)
+}
+
+/// Cómo se recodifica el tramo inicial. El resto se copia tal cual, así que
+/// el tramo debe repetir codec, formato de píxel, perfil y nivel del video
+/// (que pudo salir de NVENC, en 4:4:4 o en 10 bits) para que la unión sea
+/// válida.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HeadEncoding {
+    encoder: &'static str,
+    pixel_format: String,
+    options: Vec<(&'static str, String)>,
+}
+
+fn precise_head_encoding(stream: &VideoStreamFormat) -> Result<HeadEncoding, String> {
+    let (encoder, codec) = match stream.codec.as_str() {
+        "h264" => ("libx264", VideoCodec::H264),
+        "hevc" => ("libx265", VideoCodec::H265),
+        other => {
+            return Err(format!(
+                "El recorte preciso solo admite H.264 y H.265; el video es {other}"
+            ))
+        }
+    };
+    let pixel_format = stream
+        .pixel_format
+        .clone()
+        .ok_or_else(|| "No se pudo leer el formato de píxel del video".to_string())?;
+    let profile = match stream.profile.as_deref() {
+        Some(profile) => Some(encoder_profile(&codec, profile, &pixel_format).ok_or_else(|| {
+            format!(
+                "El recorte preciso no puede recodificar con el perfil {profile} del video; recorta sin precise"
+            )
+        })?),
+        None => None,
+    };
+    let level = match stream.level {
+        Some(level) => Some(level_name(&codec, level).ok_or_else(|| {
+            format!(
+                "El recorte preciso no puede recodificar con el nivel {level} del video; recorta sin precise"
+            )
+        })?),
+        None => None,
+    };
+
+    Ok(HeadEncoding {
+        encoder,
+        options: profile_level_options(encoder, &codec, profile, level.as_deref()),
+        pixel_format,
+    })
+}
+
+fn encoder_profile(codec: &VideoCodec, profile: &str, pixel_format: &str) -> Option<&'static str> {
+    match (codec, profile) {
+        (VideoCodec::H264, "Baseline" | "Constrained Baseline") => Some("baseline"),
+        (VideoCodec::H264, "Main") => Some("main"),
+        (VideoCodec::H264, "High") => Some("high"),
+        (VideoCodec::H264, "High 10") => Some("high10"),
+        (VideoCodec::H264, "High 4:2:2") => Some("high422"),
+        (VideoCodec::H264, "High 4:4:4 Predictive") => Some("high444"),
+        (VideoCodec::H265, "Main") => Some("main"),
+        (VideoCodec::H265, "Main 10") => Some("main10"),
+        (VideoCodec::H265, "Rext") => match pixel_format {
+            "yuv444p" => Some("main444-8"),
+            "yuv444p10le" => Some("main444-10"),
+            "yuv422p10le" => Some("main422-10"),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// H.264 guarda el nivel por 10 (`41`) y HEVC por 30 (`123`); ambos son 4.1.
+fn level_name(codec: &VideoCodec, level: u32) -> Option<String> {
+    let (major, minor) = match codec {
+        VideoCodec::H264 => (level / 10, level % 10),
+        _ if level.is_multiple_of(3) => (level / 30, level % 30 / 3),
+        _ => return None,
+    };
+    let name = format!("{major}.{minor}");
+    known_level(codec, &name).map(|_| name)
+}
+
+fn copy_args(input_path: &Path, start_ms: u64, end_ms: u64, output_path: &Path) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec![
+        "-ss".into(),
+        ffmpeg_seconds(start_ms).into(),
+        "-i".into(),
+        input_path.into(),
+        "-t".into(),
+        ffmpeg_seconds(end_ms.saturating_sub(start_ms)).into(),
+        "-map".into(),
+        "0:v".into(),
+        "-map".into(),
+        "0:a?".into(),
+        "-c".into(),
+        "copy".into(),
+        "-avoid_negative_ts".into(),
+        "make_zero".into(),
+    ];
+    args.extend(faststart_args(output_path));
+    args.push(output_path.into());
+    args
+}
+
+fn head_args(
+    input_path: &Path,
+    seek_ms: u64,
+    duration_ms: u64,
+    encoding: &HeadEncoding,
+    output_path: &Path,
+) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec![
+        "-ss".into(),
+        ffmpeg_seconds(seek_ms).into(),
+        "-i".into(),
+        input_path.into(),
+        "-t".into(),
+        ffmpeg_seconds(duration_ms).into(),
+        "-map".into(),
+        "0:v:0".into(),
+        "-map".into(),
+        "0:a?".into(),
+        "-c:v".into(),
+        encoding.encoder.into(),
+        "-crf".into(),
+        PRECISE_HEAD_CRF.into(),
+        "-pix_fmt".into(),
+        encoding.pixel_format.as_str().into(),
+    ];
+    for (key, value) in &encoding.options {
+        args.push(format!("-{key}:v").into());
+        args.push(value.into());
+    }
+    args.extend(["-c:a".into(), "copy".into(), output_path.into()]);
+    args
+}
+
+fn concat_args(list_path: &Path, output_path: &Path) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec![
+        "-f".into(),
+        "concat".into(),
+        "-safe".into(),
+        "0".into(),
+        "-i".into(),
+        list_path.into(),
+        "-c".into(),
+        "copy".into(),
+    ];
+    args.extend(faststart_args(output_path));
+    args.push(output_path.into());
+    args
+}
+
+fn faststart_args(output_path: &Path) -> Vec<OsString> {
+    let is_mp4 = output_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mp4"));
+    if is_mp4 {
+        vec!["-movflags".into(), "+faststart".into()]
+    } else {
+        Vec::new()
+    }
+}
+
+pub fn trim_recording(
+    input_path: &Path,
+    output_path: &Path,
+    range: TrimRange,
+    precise: bool,
+) -> Result<TrimResult, String> {
+    let result = run_trim(input_path, output_path, range, precise);
+    match &result {
+        Ok(trimmed) => notify_trim_event(TrimEvent::Completed(trimmed.clone())),
+        Err(message) => notify_trim_event(TrimEvent::Failed(TrimFailed {
+            input_path: input_path.to_string_lossy().into_owned(),
+            output_path: output_path.to_string_lossy().into_owned(),
+            message: message.clone(),
+        })),
+    }
+    result
+}
+
+fn run_trim(
+    input_path: &Path,
+    output_path: &Path,
+    range: TrimRange,
+    precise: bool,
+) -> Result<TrimResult, String> {
+    if is_same_file(input_path, output_path) {
+        return Err("El recorte no puede sobrescribir el archivo original".to_string());
+    }
+    let info = probe_output_file(input_path)?;
+    let keyframes = probe_video_keyframes(input_path);
+    let plan = plan_trim(range, info.duration_ms, &keyframes.keyframes_ms, precise)?;
+    let head_encoding = match plan.head_end_ms {
+        Some(_) => Some(precise_head_encoding(&probe_video_stream_format(
+            input_path,
+        )?)?),
+        None => None,
+    };
+
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            format!(
+                "No se pudo crear carpeta de salida '{}': {err}",
+                parent.display()
+            )
+        })?;
+    }
+
+    let task = PostProcessTask::begin(PostProcessKind::Trim, output_path);
+    let written = match (plan.head_end_ms, &head_encoding) {
+        (Some(head_end_ms), Some(encoding)) => write_precise_trim(
+            input_path,
+            output_path,
+            &plan,
+            &keyframes,
+            head_end_ms,
+            encoding,
+            &task,
+        ),
+        _ => run_ffmpeg_cancellable(
+            copy_args(
+                input_path,
+                keyframes.seek_ms(plan.start_ms),
+                keyframes.seek_ms(plan.end_ms),
+                output_path,
+            ),
+            "recortar la grabación",
+            &task,
+        ),
+    };
+    if let Err(err) = written {
+        let _ = fs::remove_file(output_path);
+        return Err(err);
+    }
+
+    Ok(TrimResult {
+        input_path: input_path.to_string_lossy().into_owned(),
+        output_path: output_path.to_string_lossy().into_owned(),
+        start_ms: plan.start_ms,
+        end_ms: plan.end_ms,
+        reencoded_until_ms: plan.head_end_ms,
+    })
+}
+
+fn write_precise_trim(
+    input_path: &Path,
+    output_path: &Path,
+    plan: &TrimPlan,
+    keyframes: &VideoKeyframes,
+    head_end_ms: u64,
+    encoding: &HeadEncoding,
+    task: &PostProcessTask,
+) -> Result<(), String> {
+    let parts_dir = TempBuilder::new()
+        .prefix("capturist-trim-")
+        .tempdir()
+        .map_err(|err| format!("No se pudo crear carpeta temporal para el recorte: {err}"))?;
+    let head_path = parts_dir.path().join("head.mkv");
+    run_ffmpeg_cancellable(
+        head_args(
+            input_path,
+            keyframes.seek_ms(plan.start_ms),
+            head_end_ms - plan.start_ms,
+            encoding,
+            &head_path,
+        ),
+        "recodificar el inicio del recorte",
+        task,
+    )?;
+
+    let mut parts: Vec<PathBuf> = vec![head_path];
+    if head_end_ms < plan.end_ms {
+        let tail_path = parts_dir.path().join("tail.mkv");
+        run_ffmpeg_cancellable(
+            copy_args(
+                input_path,
+                keyframes.seek_ms(head_end_ms),
+                keyframes.seek_ms(plan.end_ms),
+                &tail_path,
+            ),
+            "copiar el resto del recorte",
+            task,
+        )?;
+        parts.push(tail_path);
+    }
+
+    let list_path = parts_dir.path().join("parts.txt");
+    fs::write(&list_path, concat_list_contents(&parts))
+        .map_err(|err| format!("No se pudo preparar la unión del recorte: {err}"))?;
+    run_ffmpeg_cancellable(
+        concat_args(&list_path, output_path),
+        "unir las partes del recorte",
+        task,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoder::post_process::args_to_strings;
+
+    const KEYFRAMES: [u64; 4] = [0, 2_000, 4_000, 6_000];
+
+    fn range(start_ms: Option<u64>, end_ms: Option<u64>) -> TrimRange {
+        TrimRange { start_ms, end_ms }
+    }
+
+    #[test]
+    fn copia_directa_arranca_en_el_keyframe_anterior() {
+        let plan = plan_trim(range(Some(4_900), None), 8_000, &KEYFRAMES, false).unwrap();
+        assert_eq!(
+            plan,
+            TrimPlan {
+                start_ms: 4_000,
+                end_ms: 8_000,
+                head_end_ms: None
+            }
+        );
+    }
+
+    #[test]
+    fn modo_preciso_recodifica_solo_hasta_el_keyframe_siguiente() {
+        let plan = plan_trim(range(Some(4_900), Some(7_000)), 8_000, &KEYFRAMES, true).unwrap();
+        assert_eq!(
+            plan,
+            TrimPlan {
+                start_ms: 4_900,
+                end_ms: 7_000,
+                head_end_ms: Some(6_000)
+            }
+        );
+
+        let on_keyframe = plan_trim(range(Some(2_000), None), 8_000, &KEYFRAMES, true).unwrap();
+        assert_eq!(on_keyframe.head_end_ms, None);
+
+        let short = plan_trim(range(Some(4_100), Some(5_000)), 8_000, &KEYFRAMES, true).unwrap();
+        assert_eq!(short.head_end_ms, Some(5_000));
+    }
+
+    #[test]
+    fn rechaza_recortes_invalidos_o_vacios() {
+        assert!(plan_trim(range(Some(9_000), None), 8_000, &KEYFRAMES, false).is_err());
+        assert!(plan_trim(range(Some(3_000), Some(3_000)), 8_000, &KEYFRAMES, false).is_err());
+        let err = plan_trim(range(Some(1_500), None), 8_000, &KEYFRAMES, false)
+            .expect_err("debio fallar");
+        assert!(err.contains("no quita nada"), "{err}");
+    }
+
+    #[test]
+    fn no_permite_sobrescribir_el_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("clase.mp4");
+        fs::write(&input, b"x").unwrap();
+        let same = dir.path().join(".").join("clase.mp4");
+
+        let err = trim_recording(&input, &same, range(Some(1_000), None), false)
+            .expect_err("debio fallar");
+        assert!(err.contains("sobrescribir"), "{err}");
+    }
+
+    fn stream(codec: &str, pixel_format: &str, profile: &str, level: u32) -> VideoStreamFormat {
+        VideoStreamFormat {
+            codec: codec.to_string(),
+            pixel_format: Some(pixel_format.to_string()),
+            profile: Some(profile.to_string()),
+            level: Some(level),
+        }
+    }
+
+    #[test]
+    fn el_tramo_recodificado_repite_el_formato_del_video() {
+        let nvenc_444 =
+            precise_head_encoding(&stream("h264", "yuv444p", "High 4:4:4 Predictive", 42)).unwrap();
+        assert_eq!(
+            nvenc_444,
+            HeadEncoding {
+                encoder: "libx264",
+                pixel_format: "yuv444p".to_string(),
+                options: vec![
+                    ("profile", "high444".to_string()),
+                    ("level", "4.2".to_string())
+                ],
+            }
+        );
+
+        let ten_bit =
+            precise_head_encoding(&stream("hevc", "yuv420p10le", "Main 10", 123)).unwrap();
+        assert_eq!(ten_bit.encoder, "libx265");
+        assert_eq!(
+            ten_bit.options,
+            vec![
+                ("profile", "main10".to_string()),
+                ("x265-params", "level-idc=4.1".to_string())
+            ]
+        );
+
+        let args = args_to_strings(head_args(
+            Path::new("in.mkv"),
+            4_900,
+            1_100,
+            &ten_bit,
+            Path::new("head.mkv"),
+        ));
+        assert!(args.join(" ").contains(
+            "-pix_fmt yuv420p10le -profile:v main10 -x265-params:v level-idc=4.1 -c:a copy"
+        ));
+    }
+
+    #[test]
+    fn rechaza_recodificar_lo_que_no_puede_repetir() {
+        let err = precise_head_encoding(&stream("hevc", "yuv420p12le", "Rext", 123))
+            .expect_err("debio fallar");
+        assert!(err.contains("perfil Rext"), "{err}");
+        let err =
+            precise_head_encoding(&stream("h264", "yuv420p", "High", 7)).expect_err("debio fallar");
+        assert!(err.contains("nivel 7"), "{err}");
+        let err = precise_head_encoding(&stream("vp9", "yuv420p", "Profile 0", 0))
+            .expect_err("debio fallar");
+        assert!(err.contains("vp9"), "{err}");
+    }
+
+    #[test]
+    fn los_cortes_cuentan_desde_el_inicio_del_contenedor() {
+        let keyframes = VideoKeyframes {
+            keyframes_ms: KEYFRAMES.to_vec(),
+            start_offset_ms: 120,
+        };
+        let args = args_to_strings(copy_args(
+            Path::new("in.mp4"),
+            keyframes.seek_ms(4_000),
+            keyframes.seek_ms(6_000),
+            Path::new("out.mkv"),
+        ));
+        assert_eq!(&args[..6], ["-ss", "4.120", "-i", "in.mp4", "-t", "2.000"]);
+    }
+
+    #[test]
+    fn copia_con_faststart_en_mp4() {
+        let args = args_to_strings(copy_args(
+            Path::new("in.mp4"),
+            4_000,
+            9_500,
+            Path::new("out.mp4"),
+        ));
+        assert_eq!(&args[..6], ["-ss", "4.000", "-i", "in.mp4", "-t", "5.500"]);
+        assert!(args
+            .join(" ")
+            .ends_with("-c copy -avoid_negative_ts make_zero -movflags +faststart out.mp4"));
+    }
+}
@@ -0,0 +1,203 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::capture::models::{FramePixelFormat, RawFrame, Region};
+
+pub const MAX_BLUR_REGIONS: usize = 16;
+const BLUR_RADIUS_PX: usize = 12;
+const BLUR_PASSES: usize = 2;
+
+pub fn validate_blur_regions(regions: &[Region]) -> Result<(), String> {
+    if regions.len() > MAX_BLUR_REGIONS {
+        return Err(format!(
+            "Se pueden difuminar hasta {MAX_BLUR_REGIONS} regiones"
+        ));
+    }
+    if regions
+        .iter()
+        .any(|region| region.width == 0 || region.height == 0)
+    {
+        return Err("Las regiones a difuminar deben tener ancho y alto".to_string());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelRect {
+    pub left: usize,
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+}
+
+pub fn source_rect(region: &Region, output: (u32, u32), source: (u32, u32)) -> Option<PixelRect> {
+    let scale = |value: u32, from: u32, to: u32, round_up: bool| -> usize {
+        let scaled = u64::from(value) * u64::from(to);
+        let from = u64::from(from.max(1));
+        let value = if round_up {
+            scaled.div_ceil(from)
+        } else {
+            scaled / from
+        };
+        value.min(u64::from(to)) as usize
+    };
+    let (out_w, out_h) = output;
+    let (src_w, src_h) = source;
+    let rect = PixelRect {
+        left: scale(region.x, out_w, src_w, false),
+        top: scale(region.y, out_h, src_h, false),
+        right: scale(region.x.saturating_add(region.width), out_w, src_w, true),
+        bottom: scale(region.y.saturating_add(region.height), out_h, src_h, true),
+    };
+    (rect.left < rect.right && rect.top < rect.bottom).then_some(rect)
+}
+
+#[derive(Debug, Default)]
+pub struct RegionBlur {
+    regions: Vec<Region>,
+    line: Vec<u8>,
+    scratch: Vec<u8>,
+}
+
+impl RegionBlur {
+    pub fn new(regions: Vec<Region>) -> Self {
+        Self {
+            regions,
+            ..Self::default()
+        }
+    }
+
+    pub fn apply(&mut self, frame: &mut RawFrame, output: (u32, u32)) {
+        if frame.pixel_format != FramePixelFormat::Bgra8 || !frame.is_cpu_layout_valid() {
+            return;
+        }
+        let stride = frame.row_stride_bytes as usize;
+        for index in 0..self.regions.len() {
+            let Some(rect) = source_rect(&self.regions[index], output, (frame.width, frame.height))
+            else {
+                continue;
+            };
+            for _ in 0..BLUR_PASSES {
+                self.blur_rows(&mut frame.data, stride, rect);
+                self.blur_columns(&mut frame.data, stride, rect);
+            }
+        }
+    }
+
+    fn blur_rows(&mut self, data: &mut [u8], stride: usize, rect: PixelRect) {
+        for y in rect.top..rect.bottom {
+            let start = y * stride + rect.left * 4;
+            let row = &mut data[start..start + (rect.right - rect.left) * 4];
+            box_blur_line(row, BLUR_RADIUS_PX, &mut self.scratch);
+        }
+    }
+
+    fn blur_columns(&mut self, data: &mut [u8], stride: usize, rect: PixelRect) {
+        for x in rect.left..rect.right {
+            self.line.clear();
+            for y in rect.top..rect.bottom {
+                let offset = y * stride + x * 4;
+                self.line.extend_from_slice(&data[offset..offset + 4]);
+            }
+            box_blur_line(&mut self.line, BLUR_RADIUS_PX, &mut self.scratch);
+            for (y, pixel) in (rect.top..rect.bottom).zip(self.line.chunks_exact(4)) {
+                let offset = y * stride + x * 4;
+                data[offset..offset + 4].copy_from_slice(pixel);
+            }
+        }
+    }
+}
+
+/// Promedio de caja de `2 * radius + 1` píxeles con suma corrida, así el
+/// costo no depende del radio. En los bordes se repite el último píxel de la
+/// región para no mezclar lo que está afuera.
+fn box_blur_line(pixels: &mut [u8], radius: usize, scratch: &mut Vec<u8>) {
+    let count = pixels.len() / 4;
+    if count < 2 {
+        return;
+    }
+    scratch.clear();
+    scratch.extend_from_slice(pixels);
+    let window = (2 * radius + 1) as u32;
+    let at = |index: isize, channel: usize| -> u32 {
+        let clamped = index.clamp(0, count as isize - 1) as usize;
+        u32::from(scratch[clamped * 4 + channel])
+    };
+
+    for channel in 0..3 {
+        let mut sum: u32 = (-(radius as isize)..=radius as isize)
+            .map(|index| at(index, channel))
+            .sum();
+        for x in 0..count {
+            pixels[x * 4 + channel] = ((sum + window / 2) / window) as u8;
+            let x = x as isize;
+            sum = sum + at(x + radius as isize + 1, channel) - at(x - radius as isize, channel);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(x: u32, y: u32, width: u32, height: u32) -> Region {
+        Region {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn regiones_de_la_salida_se_llevan_a_la_fuente() {
+        assert_eq!(
+            source_rect(&region(100, 50, 200, 100), (1920, 1080), (3840, 2160)),
+            Some(PixelRect {
+                left: 200,
+                top: 100,
+                right: 600,
+                bottom: 300,
+            })
+        );
+        assert_eq!(
+            source_rect(&region(1800, 0, 400, 10), (1920, 1080), (1920, 1080))
+                .map(|rect| rect.right),
+            Some(1920)
+        );
+        assert_eq!(
+            source_rect(&region(2000, 0, 10, 10), (1920, 1080), (1920, 1080)),
+            None
+        );
+
+        assert!(validate_blur_regions(&[region(0, 0, 10, 10)]).is_ok());
+        assert!(validate_blur_regions(&[region(0, 0, 0, 10)]).is_err());
+        assert!(validate_blur_regions(&vec![region(0, 0, 1, 1); 17]).is_err());
+    }
+
+    #[test]
+    fn difumina_solo_dentro_de_la_region() {
+        let (width, height) = (128_u32, 8_u32);
+        let mut data = Vec::new();
+        for _ in 0..height {
+            for x in 0..width {
+                let value = if x % 2 == 0 { 255 } else { 0 };
+                data.extend_from_slice(&[value, value, value, 255]);
+            }
+        }
+        let mut frame = RawFrame::new(data, width, height, width * 4, 0);
+        let mut blur = RegionBlur::new(vec![region(32, 0, 96, 8)]);
+        blur.apply(&mut frame, (width, height));
+
+        let pixel = |x: u32, y: u32| {
+            let offset = ((y * width + x) * 4) as usize;
+            frame.data[offset..offset + 4].to_vec()
+        };
+        assert_eq!(pixel(0, 4), vec![255, 255, 255, 255]);
+        assert_eq!(pixel(31, 4), vec![0, 0, 0, 255]);
+        for x in 60..100 {
+            let blurred = pixel(x, 4);
+            assert!((120..=135).contains(&blurred[0]), "{x}: {blurred:?}");
+            assert_eq!(blurred[3], 255);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use crate::encoder::{
+    config::OutputFormat,
+    media_info::probe_output_file,
+    output_paths::is_same_file,
+    post_process::{PostProcessKind, PostProcessTask},
+    processing_status::ProcessingProgress,
+};
+
+pub const EVENT_RECORDING_REMUX_PROGRESS: &str = "recording-remux-progress";
+const PROGRESS_EVENT_STEP_MS: u64 = 500;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemuxResult {
+    pub output_path: String,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemuxProgress {
+    pub output_path: String,
+    pub processed_ms: u64,
+    pub total_ms: u64,
+}
+
+pub type RemuxProgressSink = Arc<dyn Fn(RemuxProgress) + Send + Sync>;
+
+fn progress_sink_slot() -> &'static Mutex<Option<RemuxProgressSink>> {
+    static SLOT: OnceLock<Mutex<Option<RemuxProgressSink>>> = OnceLock::new();
+    SLOT.get_or_init(|| Mutex::new(None))
+}
+
+pub fn set_remux_progress_sink(sink: RemuxProgressSink) {
+    if let Ok(mut guard) = progress_sink_slot().lock() {
+        *guard = Some(sink);
+    }
+}
+
+fn notify_progress(event: RemuxProgress) {
+    let sink = progress_sink_slot()
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone());
+    if let Some(sink) = sink {
+        sink(event);
+    }
+}
+
+pub fn check_remux_compatibility(
+    target: &OutputFormat,
+    video_codec: Option<&str>,
+    audio_codecs: &[String],
+) -> Result<Vec<String>, String> {
+    let mut warnings = Vec::new();
+    match target {
+        OutputFormat::Mkv => {}
+        OutputFormat::WebM => {
+            if let Some(codec) =
+                video_codec.filter(|codec| !matches!(*codec, "vp8" | "vp9" | "av1"))
+            {
+                return Err(format!(
+                    "WebM solo admite video VP8, VP9 o AV1; este archivo tiene {codec}. Usa MKV o MP4"
+                ));
+            }
+            if let Some(codec) = audio_codecs
+                .iter()
+                .find(|codec| !matches!(codec.as_str(), "opus" | "vorbis"))
+            {
+                return Err(format!(
+                    "WebM solo admite audio Opus o Vorbis; este archivo tiene {codec}. Usa MKV"
+                ));
+            }
+        }
+        OutputFormat::Mp4 => {
+            match video_codec {
+                Some("h264" | "hevc" | "av1") | None => {}
+                Some("vp9") => warnings.push(
+                    "VP9 en MP4 no se reproduce en todos los reproductores (p. ej. QuickTime)"
+                        .to_string(),
+                ),
+                Some(codec) => {
+                    return Err(format!(
+                        "MP4 no admite video {codec} sin recodificar. Usa MKV"
+                    ))
+                }
+            }
+            for codec in audio_codecs {
+                match codec.as_str() {
+                    "aac" | "mp3" => {}
+                    "opus" => warnings
+                        .push("Opus en MP4 no se reproduce en algunos reproductores".to_string()),
+                    other => {
+                        return Err(format!(
+                            "MP4 no admite audio {other} sin recodificar. Usa MKV"
+                        ))
+                    }
+                }
+            }
+        }
+        OutputFormat::Gif | OutputFormat::Hls => {
+            return Err(
+                "El cambio de contenedor solo admite MP4, MKV o WebM como destino".to_string(),
+            )
+        }
+    }
+    Ok(warnings)
+}
+
+pub fn remux_recording(
+    input_path: &Path,
+    target: &OutputFormat,
+    output_path: &Path,
+) -> Result<RemuxResult, String> {
+    if is_same_file(input_path, output_path) {
+        return Err(
+            "El cambio de contenedor no puede sobrescribir el archivo original".to_string(),
+        );
+    }
+    let info = probe_output_file(input_path)?;
+    let warnings =
+        check_remux_compatibility(target, info.video_codec.as_deref(), &info.audio_codecs)?;
+
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            format!(
+                "No se pudo crear carpeta de salida '{}': {err}",
+                parent.display()
+            )
+        })?;
+    }
+
+    let task = PostProcessTask::begin(PostProcessKind::Remux, output_path);
+    let progress = ProcessingProgress::start();
+    let shown_output_path = output_path.to_string_lossy().into_owned();
+    let total_ms = info.duration_ms;
+    let mut last_event_ms = None;
+    let mut on_progress = |processed_ms: u64| {
+        progress.report(processed_ms, total_ms);
+        if last_event_ms.is_none_or(|last_ms| processed_ms >= last_ms + PROGRESS_EVENT_STEP_MS) {
+            last_event_ms = Some(processed_ms);
+            notify_progress(RemuxProgress {
+                output_path: shown_output_path.clone(),
+                processed_ms: processed_ms.min(total_ms),
+                total_ms,
+            });
+        }
+    };
+
+    if let Err(err) = platform::remux(input_path, output_path, target, &task, &mut on_progress) {
+        let _ = fs::remove_file(output_path);
+        return Err(err);
+    }
+    Ok(RemuxResult {
+        output_path: shown_output_path,
+        warnings,
+    })
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::path::Path;
+
+    use ffmpeg_the_third::{
+        codec::{self, encoder},
+        format, media, packet, Dictionary, Error as FfmpegError, Rational,
+    };
+
+    use crate::encoder::{
+        config::OutputFormat,
+        output_paths::ffmpeg_path_arg,
+        post_process::{PostProcessTask, POST_PROCESS_CANCELLED_ERR},
+    };
+
+    pub fn remux(
+        input_path: &Path,
+        output_path: &Path,
+        target: &OutputFormat,
+        task: &PostProcessTask,
+        on_progress: &mut dyn FnMut(u64),
+    ) -> Result<(), String> {
+        ffmpeg_the_third::init().map_err(|err| format!("No se pudo inicializar FFmpeg: {err}"))?;
+
+        let mut input = format::input(&ffmpeg_path_arg(input_path)?)
+            .map_err(|err| format!("No se pudo abrir '{}': {err}", input_path.display()))?;
+        let mut output =
+            format::output_as(&ffmpeg_path_arg(output_path)?, target.ffmpeg_format_name())
+                .map_err(|err| format!("No se pudo crear '{}': {err}", output_path.display()))?;
+
+        let mut stream_map: Vec<Option<(usize, Rational)>> = vec![None; input.streams().count()];
+        for stream in input.streams() {
+            let parameters = stream.parameters();
+            if !matches!(parameters.medium(), media::Type::Video | media::Type::Audio) {
+                continue;
+            }
+            let mut out_stream = output
+                .add_stream(encoder::find(codec::Id::None))
+                .map_err(|err| format!("No se pudo agregar un stream a la salida: {err}"))?;
+            out_stream.set_parameters(parameters);
+            // El codec_tag del contenedor de origen (p. ej. `avc1` de MP4) no
+            // siempre vale en el destino; el muxer elige el suyo.
+            unsafe {
+                (*(*out_stream.as_mut_ptr()).codecpar).codec_tag = 0;
+            }
+            stream_map[stream.index()] = Some((out_stream.index(), stream.time_base()));
+        }
+
+        output.set_metadata(input.metadata().to_owned());
+        for chapter in input.chapters() {
+            let title = chapter
+                .metadata()
+                .get("title")
+                .unwrap_or_default()
+                .to_string();
+            output
+                .add_chapter(
+                    chapter.id(),
+                    chapter.time_base(),
+                    chapter.start(),
+                    chapter.end(),
+                    &title,
+                )
+                .map_err(|err| format!("No se pudo copiar el capítulo '{title}': {err}"))?;
+        }
+
+        let mut muxer_opts = Dictionary::new();
+        if *target == OutputFormat::Mp4 {
+            muxer_opts.set("movflags", "+faststart");
+        }
+        output
+            .write_header_with(muxer_opts)
+            .map_err(|err| format!("No se pudo escribir cabecera del contenedor: {err}"))?;
+        let out_time_bases: Vec<Rational> =
+            output.streams().map(|stream| stream.time_base()).collect();
+
+        loop {
+            if task.is_cancelled() {
+                return Err(POST_PROCESS_CANCELLED_ERR.to_string());
+            }
+            let mut packet = packet::Packet::empty();
+            match packet.read(&mut input) {
+                Ok(()) => {}
+                Err(FfmpegError::Eof) => break,
+                Err(err) => return Err(format!("Error leyendo '{}': {err}", input_path.display())),
+            }
+            let Some((out_index, in_time_base)) =
+                stream_map.get(packet.stream()).copied().flatten()
+            else {
+                continue;
+            };
+
+            if let Some(ts) = packet.dts().or(packet.pts()) {
+                on_progress(timestamp_ms(ts, in_time_base));
+            }
+            packet.set_stream(out_index);
+            packet.rescale_ts(in_time_base, out_time_bases[out_index]);
+            packet.set_position(-1);
+            packet
+                .write_interleaved(&mut output)
+                .map_err(|err| format!("Error copiando al nuevo contenedor: {err}"))?;
+        }
+
+        output
+            .write_trailer()
+            .map_err(|err| format!("Error escribiendo trailer del contenedor: {err}"))
+    }
+
+    fn timestamp_ms(timestamp: i64, time_base: Rational) -> u64 {
+        let ms = i128::from(timestamp.max(0)) * i128::from(time_base.numerator()) * 1_000
+            / i128::from(time_base.denominator().max(1));
+        u64::try_from(ms).unwrap_or(0)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use std::path::Path;
+
+    use crate::encoder::{config::OutputFormat, post_process::PostProcessTask};
+
+    pub fn remux(
+        _input_path: &Path,
+        _output_path: &Path,
+        _target: &OutputFormat,
+        _task: &PostProcessTask,
+        _on_progress: &mut dyn FnMut(u64),
+    ) -> Result<(), String> {
+        Err("El cambio de contenedor solo está disponible en Windows".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codecs(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn mkv_a_mp4_con_h264_y_aac_no_avisa() {
+        assert_eq!(
+            check_remux_compatibility(&OutputFormat::Mp4, Some("h264"), &codecs(&["aac"])),
+            Ok(Vec::new())
+        );
+    }
+
+    #[test]
+    fn vp9_u_opus_en_mp4_solo_avisan() {
+        let warnings =
+            check_remux_compatibility(&OutputFormat::Mp4, Some("vp9"), &codecs(&["opus"])).unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert!(
+            check_remux_compatibility(&OutputFormat::Mp4, Some("h264"), &codecs(&["flac"]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn webm_rechaza_h264_y_aac() {
+        let err = check_remux_compatibility(&OutputFormat::WebM, Some("h264"), &[])
+            .expect_err("debio fallar");
+        assert!(err.contains("WebM"), "{err}");
+        assert!(
+            check_remux_compatibility(&OutputFormat::WebM, Some("vp9"), &codecs(&["aac"])).is_err()
+        );
+        assert!(check_remux_compatibility(&OutputFormat::Gif, Some("h264"), &[]).is_err());
+    }
+
+    #[test]
+    fn no_permite_sobrescribir_el_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("clase.mkv");
+        fs::write(&input, b"x").unwrap();
+
+        let err = remux_recording(&input, &OutputFormat::Mp4, &input).expect_err("debio fallar");
+        assert!(err.contains("sobrescribir"), "{err}");
+    }
+}
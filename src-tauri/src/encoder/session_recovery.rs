@@ -0,0 +1,548 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    env, fs,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{Duration, SystemTime},
+};
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+use chrono::{DateTime, Local};
+
+use crate::encoder::{
+    config::{AudioCodec, OutputFormat},
+    ffmpeg_paths::{ffmpeg_bin_available, resolve_ffmpeg_bin},
+    output_paths::{
+        move_temp_to_final, temp_output_base_dir, FALLBACK_SESSION_TEMP_PREFIX,
+        SESSION_OWNER_FILE_NAME, SESSION_TEMP_PREFIX,
+    },
+};
+
+pub const RECOVERY_DIR_NAME: &str = "Capturist recuperado";
+pub const STALE_SESSION_AGE: Duration = Duration::from_secs(60 * 60);
+pub const RECOVERABLE_SESSION_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+const WAV_HEADER_BYTES: u64 = 44;
+const VIDEO_EXTENSIONS: [&str; 3] = ["mp4", "mkv", "webm"];
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverableSession {
+    pub session_dir: String,
+    pub video_path: Option<String>,
+    pub audio_paths: Vec<String>,
+    pub size_bytes: u64,
+    pub modified_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveredSession {
+    pub session_dir: String,
+    pub output_path: Option<String>,
+    pub raw_files: Vec<String>,
+    pub error: Option<String>,
+}
+
+pub fn session_temp_roots() -> Vec<(PathBuf, &'static str)> {
+    let mut roots = vec![(env::temp_dir(), FALLBACK_SESSION_TEMP_PREFIX)];
+    let base = temp_output_base_dir();
+    if base != env::temp_dir() {
+        roots.push((base, SESSION_TEMP_PREFIX));
+    }
+    roots
+}
+
+pub fn find_recoverable_sessions(roots: &[(PathBuf, &str)]) -> Vec<RecoverableSession> {
+    let mut sessions: Vec<RecoverableSession> = orphaned_session_dirs(roots)
+        .iter()
+        .filter_map(|dir| inspect_session_dir(dir))
+        .collect();
+    sessions.sort_by(|a, b| b.modified_at.cmp(&a.modified_at));
+    sessions
+}
+
+pub fn sweep_stale_session_dirs(roots: &[(PathBuf, &str)], now: SystemTime) -> Vec<PathBuf> {
+    orphaned_session_dirs(roots)
+        .into_iter()
+        .filter(|dir| {
+            let Some(age) = last_write(dir).and_then(|time| now.duration_since(time).ok()) else {
+                return false;
+            };
+            let max_age = if inspect_session_dir(dir).is_some() {
+                RECOVERABLE_SESSION_MAX_AGE
+            } else {
+                STALE_SESSION_AGE
+            };
+            age >= max_age
+        })
+        .filter(|dir| match fs::remove_dir_all(dir) {
+            Ok(()) => true,
+            Err(err) => {
+                eprintln!(
+                    "[recovery] No se pudo borrar la carpeta temporal '{}': {err}",
+                    dir.display()
+                );
+                false
+            }
+        })
+        .collect()
+}
+
+fn orphaned_session_dirs(roots: &[(PathBuf, &str)]) -> Vec<PathBuf> {
+    roots
+        .iter()
+        .filter_map(|(root, prefix)| fs::read_dir(root).ok().map(|entries| (entries, prefix)))
+        .flat_map(|(entries, prefix)| {
+            entries.flatten().filter_map(move |entry| {
+                let name = entry.file_name();
+                let is_session = name.to_str().is_some_and(|name| name.starts_with(*prefix));
+                let path = entry.path();
+                (is_session && path.is_dir()).then_some(path)
+            })
+        })
+        .filter(|dir| !session_owner_alive(dir))
+        .collect()
+}
+
+/// Sin archivo de dueño (sesiones de versiones anteriores) se decide solo
+/// por antigüedad.
+fn session_owner_alive(dir: &Path) -> bool {
+    let Some(pid) = fs::read_to_string(dir.join(SESSION_OWNER_FILE_NAME))
+        .ok()
+        .and_then(|contents| contents.trim().parse::<u32>().ok())
+    else {
+        return false;
+    };
+    pid == std::process::id() || platform::process_alive(pid)
+}
+
+fn last_write(dir: &Path) -> Option<SystemTime> {
+    let own = fs::metadata(dir)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .fold(own, |latest, modified| latest.max(Some(modified)))
+}
+
+fn inspect_session_dir(dir: &Path) -> Option<RecoverableSession> {
+    let mut video: Option<(PathBuf, u64)> = None;
+    let mut audio_paths = Vec::new();
+    let mut size_bytes = 0;
+    let mut modified: Option<SystemTime> = None;
+
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        let useful = match extension.as_deref() {
+            Some(ext) if VIDEO_EXTENSIONS.contains(&ext) && metadata.len() > 0 => {
+                if video.as_ref().is_none_or(|(_, len)| metadata.len() > *len) {
+                    video = Some((path.clone(), metadata.len()));
+                }
+                true
+            }
+            Some("wav") if metadata.len() > WAV_HEADER_BYTES => {
+                audio_paths.push(path.to_string_lossy().into_owned());
+                true
+            }
+            _ => false,
+        };
+        if useful {
+            size_bytes += metadata.len();
+            modified = modified.max(metadata.modified().ok());
+        }
+    }
+
+    if video.is_none() && audio_paths.is_empty() {
+        return None;
+    }
+    audio_paths.sort();
+    Some(RecoverableSession {
+        session_dir: dir.to_string_lossy().into_owned(),
+        video_path: video.map(|(path, _)| path.to_string_lossy().into_owned()),
+        audio_paths,
+        size_bytes,
+        modified_at: modified.map(|time| DateTime::<Local>::from(time).to_rfc3339()),
+    })
+}
+
+/// Corrige los tamaños RIFF y `data` de un WAV que quedó sin cerrar; sin eso
+/// FFmpeg lee la pista como vacía. `true` si hubo que cambiar algo.
+pub fn repair_wav_header(path: &Path) -> io::Result<bool> {
+    let mut file = fs::OpenOptions::new().read(true).write(true).open(path)?;
+    let file_len = file.metadata()?.len();
+
+    let mut riff = [0u8; 12];
+    file.read_exact(&mut riff)?;
+    if &riff[..4] != b"RIFF" || &riff[8..] != b"WAVE" {
+        return Ok(false);
+    }
+
+    let mut offset = 12u64;
+    while offset + 8 <= file_len {
+        let mut chunk = [0u8; 8];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut chunk)?;
+        let chunk_size = u64::from(u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]));
+        if &chunk[..4] != b"data" {
+            offset += 8 + chunk_size + (chunk_size & 1);
+            continue;
+        }
+
+        let data_size = (file_len - offset - 8).min(u64::from(u32::MAX)) as u32;
+        let riff_size = file_len.saturating_sub(8).min(u64::from(u32::MAX)) as u32;
+        if u64::from(data_size) == chunk_size {
+            return Ok(false);
+        }
+        file.seek(SeekFrom::Start(4))?;
+        file.write_all(&riff_size.to_le_bytes())?;
+        file.seek(SeekFrom::Start(offset + 4))?;
+        file.write_all(&data_size.to_le_bytes())?;
+        file.flush()?;
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+fn unique_destination(dir: &Path, file_name: &str) -> PathBuf {
+    let candidate = dir.join(file_name);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let path = Path::new(file_name);
+    let stem = path
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .unwrap_or("recording");
+    let extension = path.extension().and_then(|value| value.to_str());
+    (2..)
+        .map(|index| {
+            let name = match extension {
+                Some(ext) => format!("{stem} ({index}).{ext}"),
+                None => format!("{stem} ({index})"),
+            };
+            dir.join(name)
+        })
+        .find(|candidate| !candidate.exists())
+        .expect("siempre hay un nombre libre")
+}
+
+pub fn recover_session(session: &RecoverableSession, destination: &Path) -> RecoveredSession {
+    let mut recovered = RecoveredSession {
+        session_dir: session.session_dir.clone(),
+        output_path: None,
+        raw_files: Vec::new(),
+        error: None,
+    };
+    if let Err(err) = fs::create_dir_all(destination) {
+        recovered.error = Some(format!(
+            "No se pudo crear la carpeta de recuperación '{}': {err}",
+            destination.display()
+        ));
+        return recovered;
+    }
+
+    for audio_path in &session.audio_paths {
+        if let Err(err) = repair_wav_header(Path::new(audio_path)) {
+            eprintln!("[recovery] No se pudo reparar '{audio_path}': {err}");
+        }
+    }
+
+    if let Some(video_path) = session.video_path.as_deref().map(Path::new) {
+        let file_name = video_path
+            .file_name()
+            .and_then(|value| value.to_str())
+            .unwrap_or("recording.mp4");
+        let output_path = unique_destination(destination, file_name);
+        match remux_recovered_video(video_path, &session.audio_paths, &output_path) {
+            Ok(()) => {
+                recovered.output_path = Some(output_path.to_string_lossy().into_owned());
+                let _ = fs::remove_dir_all(&session.session_dir);
+                return recovered;
+            }
+            Err(err) => {
+                let _ = fs::remove_file(&output_path);
+                recovered.error = Some(err);
+            }
+        }
+    }
+
+    let raw_paths = session
+        .video_path
+        .iter()
+        .chain(session.audio_paths.iter())
+        .map(PathBuf::from);
+    for raw_path in raw_paths {
+        let file_name = raw_path
+            .file_name()
+            .and_then(|value| value.to_str())
+            .unwrap_or("recording");
+        let target = unique_destination(destination, file_name);
+        match move_temp_to_final(&raw_path, &target) {
+            Ok(()) => recovered
+                .raw_files
+                .push(target.to_string_lossy().into_owned()),
+            Err(err) => {
+                recovered.error = Some(err);
+                return recovered;
+            }
+        }
+    }
+    let _ = fs::remove_dir_all(&session.session_dir);
+    recovered
+}
+
+fn remux_recovered_video(
+    video_path: &Path,
+    audio_paths: &[String],
+    output_path: &Path,
+) -> Result<(), String> {
+    let ffmpeg_bin = resolve_ffmpeg_bin();
+    if !ffmpeg_bin_available(&ffmpeg_bin, env::var_os("PATH").as_deref()) {
+        return Err(format!(
+            "no se encontró FFmpeg CLI (se buscó '{}')",
+            ffmpeg_bin.display()
+        ));
+    }
+
+    let mut cmd = Command::new(&ffmpeg_bin);
+    cmd.args(remux_args(video_path, audio_paths, output_path))
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    #[cfg(windows)]
+    {
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = cmd
+        .output()
+        .map_err(|err| format!("No se pudo ejecutar FFmpeg para recuperar: {err}"))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    Err(format!(
+        "FFmpeg no pudo leer el video (si es MP4, la grabación se cortó antes de escribir el índice): {}",
+        if stderr.is_empty() {
+            "sin salida de error"
+        } else {
+            &stderr
+        }
+    ))
+}
+
+fn remux_args(video_path: &Path, audio_paths: &[String], output_path: &Path) -> Vec<String> {
+    let format = match output_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("mkv") => OutputFormat::Mkv,
+        Some("webm") => OutputFormat::WebM,
+        _ => OutputFormat::Mp4,
+    };
+
+    let mut args: Vec<String> = ["-y", "-hide_banner", "-loglevel", "error", "-i"]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    args.push(video_path.to_string_lossy().into_owned());
+    for audio_path in audio_paths {
+        args.extend(["-i".to_string(), audio_path.clone()]);
+    }
+    args.extend(["-map".to_string(), "0:v:0".to_string()]);
+    for index in 1..=audio_paths.len() {
+        args.extend(["-map".to_string(), format!("{index}:a:0")]);
+    }
+    args.extend(["-c:v".to_string(), "copy".to_string()]);
+    if !audio_paths.is_empty() {
+        args.extend([
+            "-c:a".to_string(),
+            AudioCodec::default_for(&format)
+                .ffmpeg_encoder_name()
+                .to_string(),
+        ]);
+    }
+    if format == OutputFormat::Mp4 {
+        args.extend(["-movflags".to_string(), "+faststart".to_string()]);
+    }
+    args.push(output_path.to_string_lossy().into_owned());
+    args
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows_sys::Win32::{
+        Foundation::{CloseHandle, GetLastError, ERROR_ACCESS_DENIED, STILL_ACTIVE},
+        System::Threading::{GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION},
+    };
+
+    pub fn process_alive(pid: u32) -> bool {
+        unsafe {
+            let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if handle.is_null() {
+                // Existe pero es de otro usuario: se trata como vivo.
+                return GetLastError() == ERROR_ACCESS_DENIED;
+            }
+            let mut exit_code = 0u32;
+            let queried = GetExitCodeProcess(handle, &mut exit_code) != 0;
+            CloseHandle(handle);
+            queried && exit_code == STILL_ACTIVE as u32
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use std::path::Path;
+
+    /// Sin `/proc` no hay cómo saberlo y se asume vivo para no borrar nada.
+    pub fn process_alive(pid: u32) -> bool {
+        let proc_dir = Path::new("/proc");
+        !proc_dir.is_dir() || proc_dir.join(pid.to_string()).exists()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav_with_open_header(path: &Path, data_bytes: usize) {
+        let mut raw = Vec::new();
+        raw.extend_from_slice(b"RIFF");
+        raw.extend_from_slice(&0u32.to_le_bytes());
+        raw.extend_from_slice(b"WAVE");
+        raw.extend_from_slice(b"fmt ");
+        raw.extend_from_slice(&16u32.to_le_bytes());
+        raw.extend_from_slice(&[0u8; 16]);
+        raw.extend_from_slice(b"data");
+        raw.extend_from_slice(&0u32.to_le_bytes());
+        raw.extend(std::iter::repeat_n(7u8, data_bytes));
+        fs::write(path, raw).unwrap();
+    }
+
+    #[test]
+    fn wav_sin_cerrar_recupera_sus_tamanos() {
+        let dir = tempfile::tempdir().unwrap();
+        let wav = dir.path().join("system_audio.wav");
+        wav_with_open_header(&wav, 1_000);
+
+        assert!(repair_wav_header(&wav).unwrap());
+        let raw = fs::read(&wav).unwrap();
+        assert_eq!(u32::from_le_bytes(raw[4..8].try_into().unwrap()), 1_036);
+        assert_eq!(u32::from_le_bytes(raw[40..44].try_into().unwrap()), 1_000);
+        assert!(!repair_wav_header(&wav).unwrap());
+    }
+
+    #[test]
+    fn detecta_solo_sesiones_con_video_o_audio() {
+        let root = tempfile::tempdir().unwrap();
+        let crashed = root.path().join("session-abc");
+        fs::create_dir(&crashed).unwrap();
+        fs::write(crashed.join("clase.mp4"), [1u8; 64]).unwrap();
+        wav_with_open_header(&crashed.join("microphone_audio.wav"), 10);
+        let empty = root.path().join("session-vacia");
+        fs::create_dir(&empty).unwrap();
+        fs::write(empty.join("palette.png"), [1u8; 8]).unwrap();
+        let foreign = root.path().join("otra-app");
+        fs::create_dir(&foreign).unwrap();
+        fs::write(foreign.join("video.mp4"), [1u8; 8]).unwrap();
+
+        let sessions = find_recoverable_sessions(&[(root.path().to_path_buf(), "session-")]);
+        assert_eq!(sessions.len(), 1);
+        let session = &sessions[0];
+        assert!(session
+            .video_path
+            .as_deref()
+            .unwrap()
+            .ends_with("clase.mp4"));
+        assert_eq!(session.audio_paths.len(), 1);
+        assert_eq!(session.size_bytes, 64 + 54);
+    }
+
+    #[test]
+    fn remux_mapea_cada_pista_y_usa_faststart_en_mp4() {
+        let args = remux_args(
+            Path::new("clase.mp4"),
+            &[
+                "system_audio.wav".to_string(),
+                "microphone_audio.wav".to_string(),
+            ],
+            Path::new("out/clase.mp4"),
+        );
+        let joined = args.join(" ");
+        assert!(
+            joined.contains("-map 0:v:0 -map 1:a:0 -map 2:a:0"),
+            "{joined}"
+        );
+        assert!(joined.contains("-c:v copy -c:a aac"), "{joined}");
+        assert!(
+            joined.ends_with("-movflags +faststart out/clase.mp4"),
+            "{joined}"
+        );
+    }
+
+    #[test]
+    fn limpieza_respeta_antiguedad_dueno_vivo_y_sesiones_recuperables() {
+        let root = tempfile::tempdir().unwrap();
+        let roots = [(root.path().to_path_buf(), "session-")];
+        let orphan = root.path().join("session-huerfana");
+        fs::create_dir(&orphan).unwrap();
+        fs::write(orphan.join("palette.png"), [1u8; 8]).unwrap();
+        let owned = root.path().join("session-propia");
+        fs::create_dir(&owned).unwrap();
+        fs::write(
+            owned.join(SESSION_OWNER_FILE_NAME),
+            std::process::id().to_string(),
+        )
+        .unwrap();
+        let crashed = root.path().join("session-con-video");
+        fs::create_dir(&crashed).unwrap();
+        fs::write(crashed.join("clase.mkv"), [1u8; 64]).unwrap();
+
+        let now = SystemTime::now();
+        assert!(sweep_stale_session_dirs(&roots, now).is_empty());
+
+        let removed = sweep_stale_session_dirs(&roots, now + STALE_SESSION_AGE * 2);
+        assert_eq!(removed, vec![orphan.clone()]);
+        assert!(owned.exists() && crashed.exists());
+
+        let removed = sweep_stale_session_dirs(&roots, now + RECOVERABLE_SESSION_MAX_AGE * 2);
+        assert_eq!(removed, vec![crashed]);
+        assert!(owned.exists());
+    }
+
+    #[test]
+    fn destino_ocupado_recibe_un_numero() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("clase.mp4"), b"x").unwrap();
+        assert_eq!(
+            unique_destination(dir.path(), "clase.mp4"),
+            dir.path().join("clase (2).mp4")
+        );
+    }
+}
@@ -0,0 +1,89 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::time::{Duration, Instant};
+
+pub const ENCODER_STALL_TIMEOUT: Duration = Duration::from_secs(10);
+/// Frames enviados sin salida a partir de los cuales se desconfía del
+/// encoder; queda muy por encima del lookahead de cualquier preset.
+pub const ENCODER_STALL_MIN_PENDING_FRAMES: u32 = 120;
+
+#[derive(Debug, Clone)]
+pub struct EncoderStallWatchdog {
+    timeout: Duration,
+    min_pending_frames: u32,
+    waiting_since: Option<Instant>,
+    pending_frames: u32,
+}
+
+impl EncoderStallWatchdog {
+    pub fn new(timeout: Duration, min_pending_frames: u32) -> Self {
+        Self {
+            timeout,
+            min_pending_frames,
+            waiting_since: None,
+            pending_frames: 0,
+        }
+    }
+
+    pub fn on_frame_sent(&mut self, now: Instant) {
+        self.waiting_since.get_or_insert(now);
+        self.pending_frames = self.pending_frames.saturating_add(1);
+    }
+
+    pub fn on_packet(&mut self) {
+        self.waiting_since = None;
+        self.pending_frames = 0;
+    }
+
+    pub fn check(&self, now: Instant) -> Result<(), String> {
+        let Some(waiting_since) = self.waiting_since else {
+            return Ok(());
+        };
+        let waited = now.saturating_duration_since(waiting_since);
+        if waited >= self.timeout && self.pending_frames >= self.min_pending_frames {
+            return Err(format!(
+                "El encoder de video dejó de producir datos: {} frames enviados sin salida en {} s. Prueba con otro encoder o actualiza el driver de la GPU",
+                self.pending_frames,
+                waited.as_secs()
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for EncoderStallWatchdog {
+    fn default() -> Self {
+        Self::new(ENCODER_STALL_TIMEOUT, ENCODER_STALL_MIN_PENDING_FRAMES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoder_sin_packets_se_da_por_trabado() {
+        let start = Instant::now();
+        let mut watchdog = EncoderStallWatchdog::default();
+        for index in 0..300 {
+            watchdog.on_frame_sent(start + Duration::from_millis(index * 33));
+        }
+        assert!(watchdog.check(start + Duration::from_secs(9)).is_ok());
+
+        let err = watchdog.check(start + Duration::from_secs(10)).unwrap_err();
+        assert!(err.contains("300 frames"), "{err}");
+
+        watchdog.on_packet();
+        assert!(watchdog.check(start + Duration::from_secs(30)).is_ok());
+    }
+
+    #[test]
+    fn pocos_frames_pendientes_no_disparan_el_watchdog() {
+        let start = Instant::now();
+        let mut watchdog = EncoderStallWatchdog::default();
+        for _ in 0..5 {
+            watchdog.on_frame_sent(start);
+        }
+        assert!(watchdog.check(start + Duration::from_secs(60)).is_ok());
+    }
+}
@@ -0,0 +1,357 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::capture::models::{FramePixelFormat, RawFrame};
+
+pub const DEFAULT_THUMBNAIL_MAX_WIDTH: u32 = 480;
+const THUMBNAIL_DIR_NAME: &str = "thumbnails";
+
+fn thumbnail_dir() -> &'static OnceLock<PathBuf> {
+    static THUMBNAIL_DIR: OnceLock<PathBuf> = OnceLock::new();
+    &THUMBNAIL_DIR
+}
+
+pub fn init_thumbnail_cache(app_cache_dir: PathBuf) {
+    let _ = thumbnail_dir().set(app_cache_dir.join(THUMBNAIL_DIR_NAME));
+}
+
+pub fn default_thumbnail_at_ms(duration_ms: u64) -> u64 {
+    duration_ms / 10
+}
+
+pub fn thumbnail_dimensions(width: u32, height: u32, max_width: u32) -> (u32, u32) {
+    let target_width = width.min(max_width.max(2));
+    let target_height =
+        (u64::from(height) * u64::from(target_width) / u64::from(width.max(1))) as u32;
+    ((target_width & !1).max(2), (target_height & !1).max(2))
+}
+
+pub fn thumbnail_path_for(video_path: &Path, at_ms: u64, max_width: u32) -> PathBuf {
+    let stem = video_path
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .unwrap_or("recording");
+    let mut hasher = DefaultHasher::new();
+    video_path.hash(&mut hasher);
+    at_ms.hash(&mut hasher);
+    max_width.hash(&mut hasher);
+    let file_name = format!("{stem}-{:016x}.jpg", hasher.finish());
+
+    match thumbnail_dir().get() {
+        Some(dir) => dir.join(file_name),
+        None => video_path.with_file_name(file_name),
+    }
+}
+
+pub fn target_thumbnail_path(target_id: u32, max_width: u32) -> PathBuf {
+    let file_name = format!("target-{target_id}-{max_width}.jpg");
+    match thumbnail_dir().get() {
+        Some(dir) => dir.join(file_name),
+        None => std::env::temp_dir()
+            .join(THUMBNAIL_DIR_NAME)
+            .join(file_name),
+    }
+}
+
+pub fn write_frame_thumbnail(
+    frame: &RawFrame,
+    max_width: u32,
+    output_path: &Path,
+) -> Result<(), String> {
+    if frame.pixel_format != FramePixelFormat::Bgra8
+        || frame.width == 0
+        || frame.row_stride_bytes < RawFrame::min_row_stride_bytes(frame.width)
+        || frame.data.len() < RawFrame::expected_size(frame.height, frame.row_stride_bytes)
+    {
+        return Err("El frame capturado no sirve para una miniatura".to_string());
+    }
+    if max_width < 2 {
+        return Err("El ancho máximo de la miniatura debe ser al menos 2 px".to_string());
+    }
+    create_parent_dir(output_path)?;
+    platform::write_frame_thumbnail(frame, max_width, output_path)
+}
+
+fn create_parent_dir(output_path: &Path) -> Result<(), String> {
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            format!(
+                "No se pudo crear la carpeta de miniaturas '{}': {err}",
+                parent.display()
+            )
+        })?;
+    }
+    Ok(())
+}
+
+pub fn generate_thumbnail(
+    video_path: &Path,
+    at_ms: u64,
+    max_width: u32,
+) -> Result<PathBuf, String> {
+    if !video_path.is_file() {
+        return Err(format!("No existe el video '{}'", video_path.display()));
+    }
+    if max_width < 2 {
+        return Err("El ancho máximo de la miniatura debe ser al menos 2 px".to_string());
+    }
+
+    let output_path = thumbnail_path_for(video_path, at_ms, max_width);
+    create_parent_dir(&output_path)?;
+    platform::write_thumbnail(video_path, at_ms, max_width, &output_path)?;
+    Ok(output_path)
+}
+
+pub fn generate_recording_thumbnail(video_path: &Path, duration_ms: u64) -> Option<PathBuf> {
+    match generate_thumbnail(
+        video_path,
+        default_thumbnail_at_ms(duration_ms),
+        DEFAULT_THUMBNAIL_MAX_WIDTH,
+    ) {
+        Ok(path) => Some(path),
+        Err(err) => {
+            eprintln!(
+                "[thumbnail] No se pudo generar la miniatura de '{}': {err}",
+                video_path.display()
+            );
+            None
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{fs, path::Path};
+
+    use ffmpeg_the_third::{
+        codec::{self, decoder, encoder},
+        ffi, format, frame, media, packet,
+        software::scaling,
+        util::{color, format::Pixel},
+        Rational,
+    };
+
+    use super::thumbnail_dimensions;
+    use crate::{capture::models::RawFrame, encoder::output_paths::ffmpeg_path_arg};
+
+    const JPEG_QSCALE: i32 = 3;
+
+    pub fn write_thumbnail(
+        video_path: &Path,
+        at_ms: u64,
+        max_width: u32,
+        output_path: &Path,
+    ) -> Result<(), String> {
+        ffmpeg_the_third::init().map_err(|err| format!("No se pudo inicializar FFmpeg: {err}"))?;
+
+        let mut input = format::input(&ffmpeg_path_arg(video_path)?)
+            .map_err(|err| format!("No se pudo abrir '{}': {err}", video_path.display()))?;
+        let (stream_idx, time_base, mut decoder) = {
+            let stream = input
+                .streams()
+                .best(media::Type::Video)
+                .ok_or_else(|| format!("'{}' no tiene video", video_path.display()))?;
+            let decoder = codec::context::Context::from_parameters(stream.parameters())
+                .and_then(|context| context.decoder().video())
+                .map_err(|err| format!("No se pudo decodificar el video: {err}"))?;
+            (stream.index(), stream.time_base(), decoder)
+        };
+
+        // El seek deja el demuxer en el keyframe anterior; de ahí se decodifica
+        // hasta llegar a `at_ms`.
+        let seek_ts = i64::try_from(at_ms)
+            .unwrap_or(i64::MAX / 1_000)
+            .saturating_mul(i64::from(ffi::AV_TIME_BASE) / 1_000);
+        if seek_ts > 0 {
+            let _ = input.seek(seek_ts, ..seek_ts);
+        }
+        let target_pts = ms_to_timestamp(at_ms, time_base);
+
+        let mut picked: Option<frame::Video> = None;
+        let mut done = false;
+        for (stream, video_packet) in input.packets().filter_map(Result::ok) {
+            if stream.index() != stream_idx {
+                continue;
+            }
+            if decoder.send_packet(&video_packet).is_err() {
+                continue;
+            }
+            if receive_until(&mut decoder, target_pts, &mut picked) {
+                done = true;
+                break;
+            }
+        }
+        if !done {
+            let _ = decoder.send_eof();
+            receive_until(&mut decoder, target_pts, &mut picked);
+        }
+        let source = picked.ok_or_else(|| "El video no tiene frames decodificables".to_string())?;
+        save_jpeg(&source, max_width, output_path)
+    }
+
+    pub fn write_frame_thumbnail(
+        frame: &RawFrame,
+        max_width: u32,
+        output_path: &Path,
+    ) -> Result<(), String> {
+        ffmpeg_the_third::init().map_err(|err| format!("No se pudo inicializar FFmpeg: {err}"))?;
+
+        let mut source = frame::Video::new(Pixel::BGRA, frame.width, frame.height);
+        let row_bytes = RawFrame::min_row_stride_bytes(frame.width) as usize;
+        let src_stride = frame.row_stride_bytes as usize;
+        let dst_stride = source.stride(0);
+        let dst = source.data_mut(0);
+        for (row, src_row) in frame
+            .data
+            .chunks(src_stride)
+            .take(frame.height as usize)
+            .enumerate()
+        {
+            let start = row * dst_stride;
+            dst[start..start + row_bytes].copy_from_slice(&src_row[..row_bytes]);
+        }
+        save_jpeg(&source, max_width, output_path)
+    }
+
+    fn save_jpeg(source: &frame::Video, max_width: u32, output_path: &Path) -> Result<(), String> {
+        let (width, height) = thumbnail_dimensions(source.width(), source.height(), max_width);
+        let mut scaler = scaling::Context::get(
+            source.format(),
+            source.width(),
+            source.height(),
+            Pixel::YUVJ420P,
+            width,
+            height,
+            scaling::Flags::BILINEAR,
+        )
+        .map_err(|err| format!("No se pudo escalar la miniatura: {err}"))?;
+        let mut scaled = frame::Video::empty();
+        scaler
+            .run(source, &mut scaled)
+            .map_err(|err| format!("No se pudo escalar la miniatura: {err}"))?;
+        scaled.set_pts(Some(0));
+
+        let jpeg = encode_jpeg(&scaled, width, height)?;
+        fs::write(output_path, jpeg).map_err(|err| {
+            format!(
+                "No se pudo guardar la miniatura '{}': {err}",
+                output_path.display()
+            )
+        })
+    }
+
+    fn receive_until(
+        decoder: &mut decoder::Video,
+        target_pts: i64,
+        picked: &mut Option<frame::Video>,
+    ) -> bool {
+        let mut decoded = frame::Video::empty();
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            let pts = decoded.pts().unwrap_or(i64::MIN);
+            *picked = Some(decoded.clone());
+            if pts >= target_pts {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn encode_jpeg(scaled: &frame::Video, width: u32, height: u32) -> Result<Vec<u8>, String> {
+        let jpeg_codec = encoder::find(codec::Id::MJPEG)
+            .ok_or_else(|| "FFmpeg no tiene el encoder MJPEG".to_string())?;
+        let mut jpeg_enc = codec::context::Context::new_with_codec(jpeg_codec)
+            .encoder()
+            .video()
+            .map_err(|err| format!("No se pudo crear el encoder JPEG: {err}"))?;
+        jpeg_enc.set_width(width);
+        jpeg_enc.set_height(height);
+        jpeg_enc.set_format(Pixel::YUVJ420P);
+        jpeg_enc.set_color_range(color::Range::JPEG);
+        jpeg_enc.set_time_base(Rational::new(1, 1));
+        jpeg_enc.set_flags(codec::Flags::QSCALE);
+        unsafe {
+            (*jpeg_enc.as_mut_ptr()).global_quality = JPEG_QSCALE * ffi::FF_QP2LAMBDA as i32;
+        }
+        let mut jpeg_enc = jpeg_enc
+            .open_as(jpeg_codec)
+            .map_err(|err| format!("No se pudo abrir el encoder JPEG: {err}"))?;
+
+        jpeg_enc
+            .send_frame(scaled)
+            .and_then(|_| jpeg_enc.send_eof())
+            .map_err(|err| format!("Error codificando la miniatura: {err}"))?;
+        let mut encoded = packet::Packet::empty();
+        jpeg_enc
+            .receive_packet(&mut encoded)
+            .map_err(|err| format!("Error codificando la miniatura: {err}"))?;
+        Ok(encoded.data().unwrap_or_default().to_vec())
+    }
+
+    fn ms_to_timestamp(ms: u64, time_base: Rational) -> i64 {
+        let ticks = i128::from(ms) * i128::from(time_base.denominator())
+            / (1_000 * i128::from(time_base.numerator().max(1)));
+        i64::try_from(ticks).unwrap_or(i64::MAX)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use std::path::Path;
+
+    use crate::capture::models::RawFrame;
+
+    pub fn write_thumbnail(
+        _video_path: &Path,
+        _at_ms: u64,
+        _max_width: u32,
+        _output_path: &Path,
+    ) -> Result<(), String> {
+        Err("Las miniaturas solo están disponibles en Windows".to_string())
+    }
+
+    pub fn write_frame_thumbnail(
+        _frame: &RawFrame,
+        _max_width: u32,
+        _output_path: &Path,
+    ) -> Result<(), String> {
+        Err("Las miniaturas solo están disponibles en Windows".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn miniatura_conserva_proporcion_con_lados_pares() {
+        assert_eq!(thumbnail_dimensions(1920, 1080, 480), (480, 270));
+        assert_eq!(thumbnail_dimensions(2560, 1080, 480), (480, 202));
+        assert_eq!(thumbnail_dimensions(320, 241, 480), (320, 240));
+    }
+
+    #[test]
+    fn cada_video_y_momento_tiene_su_propia_miniatura() {
+        let first = thumbnail_path_for(Path::new("C:/videos/clase.mp4"), 1_000, 480);
+        let same = thumbnail_path_for(Path::new("C:/videos/clase.mp4"), 1_000, 480);
+        let later = thumbnail_path_for(Path::new("C:/videos/clase.mp4"), 5_000, 480);
+        assert_eq!(first, same);
+        assert_ne!(first, later);
+        assert_eq!(first.extension().and_then(|ext| ext.to_str()), Some("jpg"));
+        assert_eq!(default_thumbnail_at_ms(60_000), 6_000);
+    }
+
+    #[test]
+    fn falla_si_el_video_no_existe() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = generate_thumbnail(&dir.path().join("no-existe.mp4"), 0, 480)
+            .expect_err("debio fallar");
+        assert!(err.contains("No existe el video"));
+    }
+}
@@ -0,0 +1,184 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::encoder::chapters::ChapterMarker;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrimRange {
+    pub start_ms: Option<u64>,
+    pub end_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedTrim {
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+impl TrimRange {
+    pub fn is_empty(&self) -> bool {
+        self.start_ms.unwrap_or(0) == 0 && self.end_ms.is_none()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let (Some(start_ms), Some(end_ms)) = (self.start_ms, self.end_ms) {
+            if start_ms >= end_ms {
+                return Err(format!(
+                    "Recorte inválido: el inicio ({start_ms} ms) debe ser anterior al final ({end_ms} ms)"
+                ));
+            }
+        }
+        if self.end_ms == Some(0) {
+            return Err("Recorte inválido: el final debe ser mayor a 0 ms".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn resolve(
+        &self,
+        total_duration_ms: u64,
+        keyframes_ms: &[u64],
+    ) -> Result<Option<ResolvedTrim>, String> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let requested_start_ms = self.start_ms.unwrap_or(0);
+        if total_duration_ms > 0 && requested_start_ms >= total_duration_ms {
+            return Err(format!(
+                "El inicio del recorte ({requested_start_ms} ms) supera la duración del video ({total_duration_ms} ms)"
+            ));
+        }
+
+        let start_ms = snap_to_keyframe(keyframes_ms, requested_start_ms);
+        let end_ms = match (self.end_ms, total_duration_ms) {
+            (Some(end_ms), 0) => end_ms,
+            (Some(end_ms), total) => end_ms.min(total),
+            (None, total) => total,
+        };
+        if start_ms == 0 && (self.end_ms.is_none() || end_ms == total_duration_ms) {
+            return Ok(None);
+        }
+        Ok(Some(ResolvedTrim { start_ms, end_ms }))
+    }
+}
+
+impl ResolvedTrim {
+    pub fn duration_ms(&self, total_duration_ms: u64) -> u64 {
+        let end_ms = if self.end_ms == 0 {
+            total_duration_ms
+        } else {
+            self.end_ms
+        };
+        end_ms.saturating_sub(self.start_ms)
+    }
+
+    pub fn shift_chapters(&self, markers: &[ChapterMarker]) -> Vec<ChapterMarker> {
+        let mut sorted = markers.to_vec();
+        sorted.sort_by_key(|marker| marker.elapsed_ms);
+        let first_inside = sorted
+            .iter()
+            .position(|marker| marker.elapsed_ms > self.start_ms)
+            .unwrap_or(sorted.len());
+        let skip = first_inside.saturating_sub(1);
+
+        sorted
+            .into_iter()
+            .skip(skip)
+            .filter(|marker| self.end_ms == 0 || marker.elapsed_ms < self.end_ms)
+            .map(|marker| ChapterMarker {
+                elapsed_ms: marker.elapsed_ms.saturating_sub(self.start_ms),
+                label: marker.label,
+            })
+            .collect()
+    }
+}
+
+pub fn snap_to_keyframe(keyframes_ms: &[u64], target_ms: u64) -> u64 {
+    keyframes_ms
+        .iter()
+        .copied()
+        .filter(|&keyframe_ms| keyframe_ms <= target_ms)
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(elapsed_ms: u64, label: &str) -> ChapterMarker {
+        ChapterMarker {
+            elapsed_ms,
+            label: label.to_string(),
+        }
+    }
+
+    #[test]
+    fn el_inicio_se_ajusta_al_keyframe_anterior() {
+        let keyframes = [0, 2_000, 4_000, 6_000];
+        assert_eq!(snap_to_keyframe(&keyframes, 4_900), 4_000);
+        assert_eq!(snap_to_keyframe(&keyframes, 4_000), 4_000);
+        assert_eq!(snap_to_keyframe(&keyframes, 1_999), 0);
+        assert_eq!(snap_to_keyframe(&[], 3_000), 0);
+    }
+
+    #[test]
+    fn recorte_valida_inicio_contra_final_y_duracion() {
+        let inverted = TrimRange {
+            start_ms: Some(5_000),
+            end_ms: Some(5_000),
+        };
+        assert!(inverted.validate().is_err());
+
+        let past_end = TrimRange {
+            start_ms: Some(12_000),
+            end_ms: None,
+        };
+        assert!(past_end.validate().is_ok());
+        assert!(past_end.resolve(10_000, &[0, 2_000]).is_err());
+    }
+
+    #[test]
+    fn recorte_resuelto_usa_keyframe_y_acota_el_final() {
+        let trim = TrimRange {
+            start_ms: Some(4_900),
+            end_ms: Some(60_000),
+        };
+        let resolved = trim
+            .resolve(20_000, &[0, 2_000, 4_000, 6_000])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            resolved,
+            ResolvedTrim {
+                start_ms: 4_000,
+                end_ms: 20_000
+            }
+        );
+        assert_eq!(resolved.duration_ms(20_000), 16_000);
+    }
+
+    #[test]
+    fn recorte_que_no_quita_nada_se_omite() {
+        assert_eq!(TrimRange::default().resolve(10_000, &[0]).unwrap(), None);
+        let to_end = TrimRange {
+            start_ms: Some(500),
+            end_ms: Some(10_000),
+        };
+        assert_eq!(to_end.resolve(10_000, &[0, 2_000]).unwrap(), None);
+    }
+
+    #[test]
+    fn capitulos_se_desplazan_al_video_recortado() {
+        let trim = ResolvedTrim {
+            start_ms: 4_000,
+            end_ms: 9_000,
+        };
+        let shifted = trim.shift_chapters(&[
+            marker(0, "Intro"),
+            marker(3_000, "Demo"),
+            marker(6_000, "Cierre"),
+            marker(9_500, "Extra"),
+        ]);
+        assert_eq!(shifted, vec![marker(0, "Demo"), marker(2_000, "Cierre")]);
+    }
+}
@@ -1,5 +1,7 @@
 use std::sync::{Mutex, OnceLock};
 
+use crate::encoder::consumer::{detect_video_encoder_capabilities, VideoEncoderCapabilities};
+
 fn video_encoder_label() -> &'static Mutex<Option<String>> {
     static VIDEO_ENCODER_LABEL: OnceLock<Mutex<Option<String>>> = OnceLock::new();
     VIDEO_ENCODER_LABEL.get_or_init(|| Mutex::new(None))
@@ -17,3 +19,59 @@ pub fn set_live_video_encoder_label(label: Option<String>) {
         *guard = label;
     }
 }
+
+fn video_encoder_capabilities_cache() -> &'static Mutex<Option<VideoEncoderCapabilities>> {
+    static VIDEO_ENCODER_CAPABILITIES: OnceLock<Mutex<Option<VideoEncoderCapabilities>>> =
+        OnceLock::new();
+    VIDEO_ENCODER_CAPABILITIES.get_or_init(|| Mutex::new(None))
+}
+
+pub fn get_video_encoder_capabilities_cached(force_refresh: bool) -> VideoEncoderCapabilities {
+    cached_or_detect(
+        video_encoder_capabilities_cache(),
+        force_refresh,
+        detect_video_encoder_capabilities,
+    )
+}
+
+// El lock se mantiene durante la detección: llamadas concurrentes esperan el
+// mismo resultado en lugar de abrir los encoders dos veces.
+fn cached_or_detect<T: Copy>(
+    cache: &Mutex<Option<T>>,
+    force_refresh: bool,
+    detect: impl FnOnce() -> T,
+) -> T {
+    let mut guard = match cache.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    if force_refresh || guard.is_none() {
+        *guard = Some(detect());
+    }
+
+    guard.expect("cache de capacidades inicializada")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, sync::Mutex};
+
+    use super::cached_or_detect;
+
+    #[test]
+    fn detecta_una_sola_vez_salvo_refresco_forzado() {
+        let cache = Mutex::new(None);
+        let calls = Cell::new(0);
+        let detect = || {
+            calls.set(calls.get() + 1);
+            calls.get()
+        };
+
+        assert_eq!(cached_or_detect(&cache, false, detect), 1);
+        assert_eq!(cached_or_detect(&cache, false, detect), 1);
+        assert_eq!(cached_or_detect(&cache, true, detect), 2);
+        assert_eq!(cached_or_detect(&cache, false, detect), 2);
+        assert_eq!(calls.get(), 2);
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevealPlatform {
+    Windows,
+    MacOs,
+    Linux,
+}
+
+impl RevealPlatform {
+    pub fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            Self::Windows
+        } else if cfg!(target_os = "macos") {
+            Self::MacOs
+        } else {
+            Self::Linux
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevealCommand {
+    pub program: &'static str,
+    pub args: Vec<OsString>,
+}
+
+/// Quita el prefijo `\\?\` que agrega `canonicalize` en Windows (explorer no
+/// lo entiende) y rechaza rutas UNC o de dispositivo.
+fn normalize_canonical_path(canonical: &str) -> Result<String, String> {
+    let path = match canonical.strip_prefix(r"\\?\") {
+        Some(rest) if rest.len() >= 2 && rest.as_bytes()[1] == b':' => rest,
+        Some(_) => return Err("No se permiten rutas de red ni de dispositivo".to_string()),
+        None => canonical,
+    };
+
+    if path.starts_with(r"\\") || path.starts_with("//") {
+        return Err("No se permiten rutas de red ni de dispositivo".to_string());
+    }
+
+    Ok(path.to_string())
+}
+
+pub fn validate_reveal_path(path: &str) -> Result<PathBuf, String> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err("La ruta del archivo está vacía".to_string());
+    }
+    if trimmed.starts_with(r"\\") || trimmed.starts_with("//") {
+        return Err("No se permiten rutas de red ni de dispositivo".to_string());
+    }
+
+    let requested = Path::new(trimmed);
+    if !requested.is_absolute() {
+        return Err(format!("La ruta '{trimmed}' debe ser absoluta"));
+    }
+
+    let canonical = fs::canonicalize(requested).map_err(|_| {
+        format!("El archivo '{trimmed}' ya no existe; puede haberse movido o eliminado")
+    })?;
+    if !canonical.is_file() {
+        return Err(format!("'{trimmed}' no es un archivo"));
+    }
+
+    normalize_canonical_path(&canonical.to_string_lossy()).map(PathBuf::from)
+}
+
+pub fn reveal_command(platform: RevealPlatform, path: &Path) -> RevealCommand {
+    match platform {
+        RevealPlatform::Windows => RevealCommand {
+            program: "explorer",
+            args: vec!["/select,".into(), path.into()],
+        },
+        RevealPlatform::MacOs => RevealCommand {
+            program: "open",
+            args: vec!["-R".into(), path.into()],
+        },
+        RevealPlatform::Linux => RevealCommand {
+            program: "xdg-open",
+            args: vec![path.parent().unwrap_or(path).into()],
+        },
+    }
+}
+
+pub fn reveal_in_file_manager(path: &str) -> Result<(), String> {
+    let path = validate_reveal_path(path)?;
+    let reveal = reveal_command(RevealPlatform::current(), &path);
+
+    // explorer devuelve código 1 aunque funcione, así que solo se valida el spawn.
+    Command::new(reveal.program)
+        .args(&reveal.args)
+        .spawn()
+        .map(|_| ())
+        .map_err(|err| format!("No se pudo abrir el explorador de archivos: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args_to_strings(reveal: &RevealCommand) -> Vec<String> {
+        reveal
+            .args
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn arma_el_comando_de_cada_plataforma() {
+        let file = Path::new("/videos/clip final.mp4");
+
+        let windows = reveal_command(RevealPlatform::Windows, file);
+        assert_eq!(windows.program, "explorer");
+        assert_eq!(
+            args_to_strings(&windows),
+            ["/select,", "/videos/clip final.mp4"]
+        );
+
+        let macos = reveal_command(RevealPlatform::MacOs, file);
+        assert_eq!(macos.program, "open");
+        assert_eq!(args_to_strings(&macos), ["-R", "/videos/clip final.mp4"]);
+
+        let linux = reveal_command(RevealPlatform::Linux, file);
+        assert_eq!(linux.program, "xdg-open");
+        assert_eq!(args_to_strings(&linux), ["/videos"]);
+    }
+
+    #[test]
+    fn normaliza_prefijo_verbatim_y_rechaza_unc() {
+        assert_eq!(
+            normalize_canonical_path(r"\\?\C:\videos\clip.mp4"),
+            Ok(r"C:\videos\clip.mp4".to_string())
+        );
+        assert!(normalize_canonical_path(r"\\?\UNC\server\share\clip.mp4").is_err());
+        assert!(normalize_canonical_path(r"\\?\GLOBALROOT\Device\clip.mp4").is_err());
+        assert!(normalize_canonical_path(r"\\server\share\clip.mp4").is_err());
+    }
+
+    #[test]
+    fn valida_que_el_archivo_exista() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("clip.mp4");
+        fs::write(&file, b"video").unwrap();
+
+        let validated = validate_reveal_path(&file.to_string_lossy()).unwrap();
+        assert!(validated.is_absolute());
+        assert!(validated.ends_with("clip.mp4"));
+
+        let missing = dir.path().join("movido.mp4");
+        let err = validate_reveal_path(&missing.to_string_lossy()).expect_err("no existe");
+        assert!(err.contains("movido o eliminado"));
+
+        assert!(validate_reveal_path(&dir.path().to_string_lossy()).is_err());
+        assert!(validate_reveal_path("clip.mp4").is_err());
+        assert!(validate_reveal_path(r"\\server\share\clip.mp4").is_err());
+    }
+}
@@ -1,18 +1,50 @@
-use std::sync::Mutex;
+use std::{sync::Mutex, time::Duration};
 
 mod capture;
 mod commands;
 mod encoder;
+mod file_manager;
 mod region;
+mod scheduler;
 mod shortcuts;
+mod shutdown;
 
-use capture::manager::CaptureManager;
+pub use shutdown::{ShutdownIssue, ShutdownReport, ShutdownStep};
+
+use capture::{
+    backpressure::{
+        set_encoder_backpressure_sink, EncoderBackpressure, EVENT_ENCODER_BACKPRESSURE,
+    },
+    display_watch::{
+        set_display_change_sink, watch_display_changes, EVENT_CAPTURE_TARGETS_CHANGED,
+    },
+    manager::CaptureManager,
+    recording_clock::{RecordingTick, EVENT_RECORDING_TICK},
+};
+use encoder::{
+    animation_export::{
+        set_animation_export_progress_sink, AnimationExportProgress,
+        EVENT_ANIMATION_EXPORT_PROGRESS,
+    },
+    audio_capture::device_loss::{
+        set_audio_device_lost_sink, AudioDeviceLost, EVENT_AUDIO_DEVICE_LOST,
+    },
+    output_sink::{set_output_sink_failed_sink, OutputSinkFailed, EVENT_OUTPUT_SINK_FAILED},
+    recording_trim::{
+        set_trim_event_sink, TrimEvent, EVENT_RECORDING_TRIM_COMPLETED, EVENT_RECORDING_TRIM_FAILED,
+    },
+    remux::{set_remux_progress_sink, RemuxProgress, EVENT_RECORDING_REMUX_PROGRESS},
+};
+use scheduler::RecordingScheduler;
 use shortcuts::GlobalShortcutManager;
-use tauri::Manager;
+use tauri::{Emitter, Manager};
+
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub struct AppState {
     pub capture: Mutex<CaptureManager>,
     pub global_shortcuts: Mutex<Option<GlobalShortcutManager>>,
+    pub recording_scheduler: Mutex<Option<RecordingScheduler>>,
 }
 
 impl AppState {
@@ -20,6 +52,7 @@ impl AppState {
         Self {
             capture: Mutex::new(CaptureManager::new()),
             global_shortcuts: Mutex::new(None),
+            recording_scheduler: Mutex::new(None),
         }
     }
 
@@ -31,6 +64,26 @@ impl AppState {
         *guard = Some(manager);
         Ok(())
     }
+
+    pub fn set_recording_scheduler(&self, scheduler: RecordingScheduler) -> Result<(), String> {
+        let mut guard = self
+            .recording_scheduler
+            .lock()
+            .map_err(|_| "No se pudo guardar el programador de grabaciones".to_string())?;
+        *guard = Some(scheduler);
+        Ok(())
+    }
+
+    pub fn set_recording_tick_sink(&self, app: tauri::AppHandle) -> Result<(), String> {
+        let mut capture = self
+            .capture
+            .lock()
+            .map_err(|_| "No se pudo acceder al estado de captura".to_string())?;
+        capture.set_tick_sink(std::sync::Arc::new(move |tick: RecordingTick| {
+            let _ = app.emit(EVENT_RECORDING_TICK, tick);
+        }));
+        Ok(())
+    }
 }
 
 impl Default for AppState {
@@ -55,24 +108,155 @@ pub fn run() {
                 .set_global_shortcuts(manager)
                 .map_err(std::io::Error::other)?;
 
+            let scheduler = RecordingScheduler::new(app.handle().clone()).map_err(|err| {
+                std::io::Error::other(format!(
+                    "No se pudo iniciar el programador de grabaciones: {err}"
+                ))
+            })?;
+
+            app.state::<AppState>()
+                .set_recording_scheduler(scheduler)
+                .map_err(std::io::Error::other)?;
+
+            app.state::<AppState>()
+                .set_recording_tick_sink(app.handle().clone())
+                .map_err(std::io::Error::other)?;
+
+            let handle = app.handle().clone();
+            set_audio_device_lost_sink(std::sync::Arc::new(move |event: AudioDeviceLost| {
+                let _ = handle.emit(EVENT_AUDIO_DEVICE_LOST, event);
+            }));
+
+            let handle = app.handle().clone();
+            set_encoder_backpressure_sink(std::sync::Arc::new(
+                move |event: EncoderBackpressure| {
+                    let _ = handle.emit(EVENT_ENCODER_BACKPRESSURE, event);
+                },
+            ));
+
+            let handle = app.handle().clone();
+            set_display_change_sink(std::sync::Arc::new(move || {
+                if let Ok(mut capture) = handle.state::<AppState>().capture.lock() {
+                    if capture.handle_display_change() {
+                        eprintln!("[capture] Se desconectó el monitor que se grababa");
+                    }
+                }
+                let _ = handle.emit(EVENT_CAPTURE_TARGETS_CHANGED, ());
+            }));
+            if let Err(err) = watch_display_changes() {
+                eprintln!("[capture] {err}");
+            }
+
+            if let Ok(dir) = app.path().resource_dir() {
+                encoder::ffmpeg_paths::set_ffmpeg_resource_dir(dir);
+            }
+
+            std::thread::spawn(|| {
+                let roots = encoder::session_recovery::session_temp_roots();
+                for dir in encoder::session_recovery::sweep_stale_session_dirs(
+                    &roots,
+                    std::time::SystemTime::now(),
+                ) {
+                    eprintln!(
+                        "[recovery] Carpeta temporal huérfana borrada: {}",
+                        dir.display()
+                    );
+                }
+            });
+
+            let handle = app.handle().clone();
+            set_output_sink_failed_sink(std::sync::Arc::new(move |event: OutputSinkFailed| {
+                let _ = handle.emit(EVENT_OUTPUT_SINK_FAILED, event);
+            }));
+
+            let handle = app.handle().clone();
+            set_animation_export_progress_sink(std::sync::Arc::new(
+                move |event: AnimationExportProgress| {
+                    let _ = handle.emit(EVENT_ANIMATION_EXPORT_PROGRESS, event);
+                },
+            ));
+
+            let handle = app.handle().clone();
+            set_remux_progress_sink(std::sync::Arc::new(move |event: RemuxProgress| {
+                let _ = handle.emit(EVENT_RECORDING_REMUX_PROGRESS, event);
+            }));
+
+            let handle = app.handle().clone();
+            set_trim_event_sink(std::sync::Arc::new(move |event: TrimEvent| {
+                let _ = match event {
+                    TrimEvent::Completed(result) => {
+                        handle.emit(EVENT_RECORDING_TRIM_COMPLETED, result)
+                    }
+                    TrimEvent::Failed(failure) => handle.emit(EVENT_RECORDING_TRIM_FAILED, failure),
+                };
+            }));
+
+            match app.path().app_data_dir() {
+                Ok(dir) => encoder::recording_history::init_recording_history(dir),
+                Err(err) => eprintln!("[history] Sin carpeta de datos de la app: {err}"),
+            }
+            match app.path().app_cache_dir() {
+                Ok(dir) => encoder::thumbnail::init_thumbnail_cache(dir),
+                Err(err) => eprintln!("[thumbnail] Sin carpeta de caché de la app: {err}"),
+            }
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::is_capture_supported,
             commands::get_targets,
+            commands::get_target_thumbnail,
             commands::get_audio_input_devices,
+            commands::get_audio_output_devices,
+            commands::get_ffmpeg_info,
+            commands::estimate_bitrate_kbps,
             commands::get_video_encoder_capabilities,
+            commands::redetect_video_encoder_capabilities,
             commands::get_recording_audio_status,
             commands::set_global_shortcuts,
+            commands::resolve_output_path,
+            commands::validate_recording_config,
+            commands::validate_session,
             commands::start_recording,
+            commands::schedule_recording,
+            commands::cancel_scheduled_recording,
             commands::update_recording_audio_capture,
+            commands::set_recording_microphone_monitoring,
+            commands::set_microphone_gain,
             commands::pause_recording,
             commands::resume_recording,
             commands::stop_recording,
+            commands::stop_recording_blocking,
+            commands::get_last_output_path,
+            commands::get_last_output_paths,
             commands::cancel_recording,
+            commands::add_chapter_marker,
+            commands::copy_frame_to_clipboard,
+            commands::cancel_post_processing,
+            commands::get_output_file_info,
+            commands::generate_thumbnail,
+            commands::analyze_recording_quality,
+            commands::export_animation,
+            commands::trim_recording,
+            commands::remux_recording,
+            commands::get_project_takes,
+            commands::reveal_in_file_manager,
+            commands::get_recent_recordings,
+            commands::clear_recording_history,
+            commands::list_recoverable_sessions,
+            commands::recover_sessions,
             commands::get_recording_status,
             commands::select_region_native,
         ])
-        .run(tauri::generate_context!())
-        .expect("Error al iniciar la aplicación Tauri");
+        .build(tauri::generate_context!())
+        .expect("Error al iniciar la aplicación Tauri")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                let report = app.state::<AppState>().shutdown(SHUTDOWN_TIMEOUT);
+                for issue in &report.incomplete {
+                    eprintln!("[shutdown] {:?}: {}", issue.step, issue.message);
+                }
+                region::unregister_overlay_classes();
+            }
+        });
 }
@@ -0,0 +1,114 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::capture::models::{CaptureTarget, Region};
+
+pub const RECORDING_BORDER_THICKNESS_PX: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreenRect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl ScreenRect {
+    pub fn of_region(target: &CaptureTarget, region: &Region) -> Self {
+        let left = target.origin_x.saturating_add(region.x as i32);
+        let top = target.origin_y.saturating_add(region.y as i32);
+        Self {
+            left,
+            top,
+            right: left.saturating_add(region.width as i32),
+            bottom: top.saturating_add(region.height as i32),
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorderFrame {
+    pub outer: ScreenRect,
+    pub inner: ScreenRect,
+}
+
+pub fn border_frame(region: ScreenRect, thickness: i32) -> BorderFrame {
+    BorderFrame {
+        outer: ScreenRect {
+            left: region.left - thickness,
+            top: region.top - thickness,
+            right: region.right + thickness,
+            bottom: region.bottom + thickness,
+        },
+        inner: ScreenRect {
+            left: thickness,
+            top: thickness,
+            right: thickness + region.width(),
+            bottom: thickness + region.height(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::models::TargetKind;
+
+    #[test]
+    fn marco_rodea_la_region_sin_taparla() {
+        let target = CaptureTarget {
+            id: 2,
+            name: "Monitor 2".to_string(),
+            width: 1920,
+            height: 1080,
+            origin_x: -1920,
+            origin_y: 0,
+            screen_width: 1920,
+            screen_height: 1080,
+            is_primary: false,
+            kind: TargetKind::Monitor,
+            process_name: None,
+            process_id: None,
+            window_title: None,
+            refresh_rate_hz: None,
+            dpi_scale_percent: None,
+            is_minimized: false,
+        };
+        let region = Region {
+            x: 100,
+            y: 50,
+            width: 640,
+            height: 360,
+        };
+        let screen = ScreenRect::of_region(&target, &region);
+        assert_eq!(
+            screen,
+            ScreenRect {
+                left: -1820,
+                top: 50,
+                right: -1180,
+                bottom: 410,
+            }
+        );
+
+        let frame = border_frame(screen, RECORDING_BORDER_THICKNESS_PX);
+        assert_eq!((frame.outer.left, frame.outer.top), (-1822, 48));
+        assert_eq!((frame.outer.width(), frame.outer.height()), (644, 364));
+        assert_eq!(
+            frame.inner,
+            ScreenRect {
+                left: 2,
+                top: 2,
+                right: 642,
+                bottom: 362,
+            }
+        );
+    }
+}
@@ -0,0 +1,160 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::region::SelectionBounds;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonitorScale {
+    pub logical_x: i32,
+    pub logical_y: i32,
+    pub logical_width: i32,
+    pub logical_height: i32,
+    pub physical_x: i32,
+    pub physical_y: i32,
+    pub scale_factor: f64,
+}
+
+impl MonitorScale {
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.logical_x
+            && x < self.logical_x + self.logical_width
+            && y >= self.logical_y
+            && y < self.logical_y + self.logical_height
+    }
+
+    fn to_physical(self, x: i32, y: i32) -> (i32, i32) {
+        (
+            self.physical_x + scale_length(x - self.logical_x, self.scale_factor),
+            self.physical_y + scale_length(y - self.logical_y, self.scale_factor),
+        )
+    }
+}
+
+fn scale_length(value: i32, scale_factor: f64) -> i32 {
+    (value as f64 * scale_factor).round() as i32
+}
+
+fn monitor_at(layout: &[MonitorScale], x: i32, y: i32) -> Option<&MonitorScale> {
+    layout
+        .iter()
+        .find(|monitor| monitor.contains(x, y))
+        .or_else(|| layout.first())
+}
+
+pub fn scale_factor_at(layout: &[MonitorScale], x: i32, y: i32) -> f64 {
+    monitor_at(layout, x, y)
+        .map(|monitor| monitor.scale_factor)
+        .unwrap_or(1.0)
+}
+
+impl SelectionBounds {
+    pub fn to_physical(self, layout: &[MonitorScale]) -> SelectionBounds {
+        let (origin_x, origin_y) = monitor_at(layout, self.origin_x, self.origin_y)
+            .map(|monitor| monitor.to_physical(self.origin_x, self.origin_y))
+            .unwrap_or((self.origin_x, self.origin_y));
+        SelectionBounds {
+            origin_x,
+            origin_y,
+            width: (self.width as f64 * self.scale_factor).round().max(1.0) as u32,
+            height: (self.height as f64 * self.scale_factor).round().max(1.0) as u32,
+            scale_factor: 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mixed_layout() -> Vec<MonitorScale> {
+        vec![
+            MonitorScale {
+                logical_x: 0,
+                logical_y: 0,
+                logical_width: 1920,
+                logical_height: 1080,
+                physical_x: 0,
+                physical_y: 0,
+                scale_factor: 1.0,
+            },
+            MonitorScale {
+                logical_x: 1920,
+                logical_y: 0,
+                logical_width: 1280,
+                logical_height: 720,
+                physical_x: 1920,
+                physical_y: 0,
+                scale_factor: 1.5,
+            },
+        ]
+    }
+
+    #[test]
+    fn limites_de_cada_monitor_pasan_a_pixeles_fisicos() {
+        let layout = mixed_layout();
+        let second = SelectionBounds {
+            origin_x: 1920,
+            origin_y: 0,
+            width: 1280,
+            height: 720,
+            scale_factor: scale_factor_at(&layout, 1920, 0),
+        };
+        let physical = second.to_physical(&layout);
+        assert_eq!(
+            (
+                physical.origin_x,
+                physical.origin_y,
+                physical.width,
+                physical.height
+            ),
+            (1920, 0, 1920, 1080)
+        );
+
+        let first = SelectionBounds {
+            origin_x: 0,
+            origin_y: 0,
+            width: 1920,
+            height: 1080,
+            scale_factor: scale_factor_at(&layout, 10, 10),
+        };
+        let physical = first.to_physical(&layout);
+        assert_eq!((physical.width, physical.height), (1920, 1080));
+        assert_eq!(physical.scale_factor, 1.0);
+    }
+
+    #[test]
+    fn origen_dentro_del_monitor_escala_desde_su_esquina() {
+        for (scale_factor, logical_width, expected_x) in [
+            (1.0, 1920, 2020),
+            (1.25, 1536, 2045),
+            (1.5, 1280, 2070),
+            (2.0, 960, 2120),
+        ] {
+            let layout = [MonitorScale {
+                logical_x: 1920,
+                logical_y: 0,
+                logical_width,
+                logical_height: 1080,
+                physical_x: 1920,
+                physical_y: 0,
+                scale_factor,
+            }];
+            let bounds = SelectionBounds {
+                origin_x: 2020,
+                origin_y: 40,
+                width: 400,
+                height: 200,
+                scale_factor,
+            };
+            let physical = bounds.to_physical(&layout);
+            assert_eq!(physical.origin_x, expected_x, "escala {scale_factor}");
+            assert_eq!(
+                physical.origin_y,
+                (40.0 * scale_factor) as i32,
+                "escala {scale_factor}"
+            );
+            assert_eq!(physical.width, (400.0 * scale_factor) as u32);
+        }
+
+        assert_eq!(scale_factor_at(&[], 0, 0), 1.0);
+    }
+}
@@ -1,4 +1,8 @@
+mod border;
+mod dpi;
+mod overlay_style;
 mod overlay_win;
+mod selection_state;
 
 #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
 #[derive(Debug, Clone, Copy)]
@@ -7,17 +11,25 @@ pub struct SelectionBounds {
     pub origin_y: i32,
     pub width: u32,
     pub height: u32,
+    pub scale_factor: f64,
 }
 
-pub use overlay_win::{select_region, select_region_with_bounds};
+pub use border::ScreenRect;
+pub use dpi::{scale_factor_at, MonitorScale};
+pub use overlay_style::OverlayStyle;
+pub use overlay_win::{
+    monitor_layout, select_region, select_region_with_bounds, unregister_overlay_classes,
+    RecordingBorder,
+};
 
 #[cfg(all(test, not(target_os = "windows")))]
 mod tests {
-    use super::select_region;
+    use super::{select_region, OverlayStyle};
 
     #[test]
     fn select_region_fuera_de_windows_devuelve_error_de_plataforma() {
-        let err = select_region().expect_err("fuera de windows debe devolver error controlado");
+        let err = select_region(OverlayStyle::default())
+            .expect_err("fuera de windows debe devolver error controlado");
         assert!(err.contains("Windows"));
     }
 }
@@ -0,0 +1,99 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+pub const MIN_OVERLAY_DIM_ALPHA: u8 = 40;
+pub const MAX_OVERLAY_DIM_ALPHA: u8 = 200;
+pub const MAX_OVERLAY_BORDER_THICKNESS_PX: u8 = 6;
+pub const OVERLAY_DIM_COLOR: u32 = 0x0000_0000;
+
+const HOLE_COLOR_CANDIDATES: [u32; 3] = [0x0003_0201, 0x0001_0203, 0x0002_0103];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OverlayStyle {
+    pub dim_alpha: u8,
+    pub border_rgb: (u8, u8, u8),
+    pub border_thickness_px: u8,
+}
+
+impl Default for OverlayStyle {
+    fn default() -> Self {
+        Self {
+            dim_alpha: 120,
+            border_rgb: (255, 255, 255),
+            border_thickness_px: 2,
+        }
+    }
+}
+
+impl OverlayStyle {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_OVERLAY_DIM_ALPHA..=MAX_OVERLAY_DIM_ALPHA).contains(&self.dim_alpha) {
+            return Err(format!(
+                "La opacidad del overlay debe estar entre {MIN_OVERLAY_DIM_ALPHA} y {MAX_OVERLAY_DIM_ALPHA}"
+            ));
+        }
+        if !(1..=MAX_OVERLAY_BORDER_THICKNESS_PX).contains(&self.border_thickness_px) {
+            return Err(format!(
+                "El grosor del borde del overlay debe estar entre 1 y {MAX_OVERLAY_BORDER_THICKNESS_PX} px"
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn border_colorref(&self) -> u32 {
+        let (r, g, b) = self.border_rgb;
+        u32::from(r) | u32::from(g) << 8 | u32::from(b) << 16
+    }
+
+    /// Color-key del hueco: lo que se pinte con él queda transparente, así
+    /// que nunca puede coincidir con el borde ni con el fondo oscurecido.
+    pub fn hole_colorref(&self) -> u32 {
+        let border = self.border_colorref();
+        HOLE_COLOR_CANDIDATES
+            .into_iter()
+            .find(|color| *color != border && *color != OVERLAY_DIM_COLOR)
+            .unwrap_or(HOLE_COLOR_CANDIDATES[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valida_opacidad_y_grosor() {
+        assert!(OverlayStyle::default().validate().is_ok());
+
+        let faint = OverlayStyle {
+            dim_alpha: 30,
+            ..OverlayStyle::default()
+        };
+        assert!(faint.validate().unwrap_err().contains("opacidad"));
+
+        for border_thickness_px in [0, 7] {
+            let style = OverlayStyle {
+                border_thickness_px,
+                ..OverlayStyle::default()
+            };
+            assert!(style.validate().unwrap_err().contains("grosor"));
+        }
+
+        let style: OverlayStyle =
+            serde_json::from_str(r#"{ "dimAlpha": 180, "borderRgb": [30, 144, 255] }"#).unwrap();
+        assert_eq!(style.border_thickness_px, 2);
+        assert_eq!(style.border_colorref(), 0x00FF_901E);
+    }
+
+    #[test]
+    fn hueco_nunca_coincide_con_el_borde() {
+        assert_eq!(OverlayStyle::default().hole_colorref(), 0x0003_0201);
+
+        let style = OverlayStyle {
+            border_rgb: (1, 2, 3),
+            ..OverlayStyle::default()
+        };
+        assert_eq!(style.border_colorref(), 0x0003_0201);
+        assert_ne!(style.hole_colorref(), style.border_colorref());
+        assert_ne!(style.hole_colorref(), OVERLAY_DIM_COLOR);
+    }
+}
@@ -1,60 +1,309 @@
 #[cfg(target_os = "windows")]
 mod win {
-    use std::sync::{Mutex, OnceLock};
+    use std::{
+        cell::RefCell,
+        collections::HashSet,
+        ffi::c_void,
+        sync::{mpsc, Mutex, OnceLock},
+        thread::{self, JoinHandle},
+    };
 
     use std::ptr;
 
-    use windows::core::PCWSTR;
-    use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM};
+    use windows::core::{BOOL, PCWSTR};
+    use windows::Win32::Foundation::{
+        COLORREF, HINSTANCE, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM,
+    };
+    use windows::Win32::Graphics::Dwm::{
+        DwmGetWindowAttribute, DWMWA_CLOAKED, DWMWA_EXTENDED_FRAME_BOUNDS,
+    };
     use windows::Win32::Graphics::Gdi::{
-        BeginPaint, CreateSolidBrush, DeleteObject, EndPaint, FillRect, FrameRect, InvalidateRect,
-        HBRUSH, PAINTSTRUCT,
+        BeginPaint, ClientToScreen, CombineRgn, CreateRectRgn, CreateSolidBrush, DeleteObject,
+        EndPaint, EnumDisplayMonitors, FillRect, FrameRect, GetMonitorInfoW, InvalidateRect,
+        SetWindowRgn, HBRUSH, HDC, HMONITOR, MONITORINFO, PAINTSTRUCT, RGN_DIFF,
+    };
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::UI::HiDpi::{
+        SetThreadDpiAwarenessContext, DPI_AWARENESS_CONTEXT,
+        DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
     };
     use windows::Win32::UI::WindowsAndMessaging::{
-        CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetClientRect,
-        GetMessageW, GetSystemMetrics, LoadCursorW, RegisterClassW, SetCursor, SetForegroundWindow,
-        SetLayeredWindowAttributes, ShowWindow, TranslateMessage, HMENU, IDC_CROSS, LWA_ALPHA,
-        LWA_COLORKEY, MSG, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN,
-        SM_YVIRTUALSCREEN, SW_SHOW, WM_ERASEBKGND, WM_KEYDOWN, WM_LBUTTONDOWN, WM_LBUTTONUP,
-        WM_MOUSEMOVE, WM_PAINT, WM_RBUTTONDOWN, WNDCLASSW, WS_EX_LAYERED, WS_EX_TOOLWINDOW,
-        WS_EX_TOPMOST, WS_POPUP,
+        ClipCursor, CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, EnumWindows,
+        GetClientRect, GetMessageW, GetShellWindow, GetSystemMetrics, GetWindowLongPtrW,
+        GetWindowTextLengthW, IsIconic, IsWindowVisible, LoadCursorW, PostMessageW,
+        PostQuitMessage, RegisterClassW, SetCursor, SetForegroundWindow,
+        SetLayeredWindowAttributes, SetWindowDisplayAffinity, SetWindowLongPtrW, ShowWindow,
+        TranslateMessage, UnregisterClassW, CREATESTRUCTW, GWLP_USERDATA, GWL_EXSTYLE, HMENU,
+        IDC_CROSS, LWA_ALPHA, LWA_COLORKEY, MSG, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN,
+        SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN, SW_SHOW, SW_SHOWNOACTIVATE, WDA_EXCLUDEFROMCAPTURE,
+        WM_CLOSE, WM_DESTROY, WM_ERASEBKGND, WM_KEYDOWN, WM_LBUTTONDOWN, WM_LBUTTONUP,
+        WM_MOUSEMOVE, WM_NCCREATE, WM_NCDESTROY, WM_PAINT, WM_RBUTTONDOWN, WNDCLASSW,
+        WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_EX_TRANSPARENT,
+        WS_POPUP,
     };
 
-    use crate::{capture::models::Region, region::SelectionBounds};
+    use crate::{
+        capture::models::Region,
+        region::{
+            border::{border_frame, ScreenRect, RECORDING_BORDER_THICKNESS_PX},
+            overlay_style::{OverlayStyle, OVERLAY_DIM_COLOR},
+            selection_state::{snap_target_at, SelectionPoint, SelectionRect, SelectionState},
+            MonitorScale, SelectionBounds,
+        },
+    };
 
     const MIN_SELECTION_EDGE_PX: i32 = 5;
-    const OVERLAY_DIM_ALPHA: u8 = 120;
-    const OVERLAY_COLOR: COLORREF = COLORREF(0x00000000);
-    const SELECTION_HOLE_COLOR: COLORREF = COLORREF(0x00030201);
-    const SELECTION_BORDER_THICKNESS_PX: i32 = 2;
+    const VK_TAB: u32 = 0x09;
+    const VK_ESCAPE: u32 = 0x1B;
+    const RECORDING_BORDER_COLOR: COLORREF = COLORREF(0x000000FF);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum OverlayKind {
+        RegionSelection,
+        RecordingBorder,
+    }
 
-    #[derive(Default, Copy, Clone)]
-    struct State {
-        selecting: bool,
-        start: POINT,
-        current: POINT,
-        rect: RECT,
-        cancelled: bool,
-        done: bool,
+    impl OverlayKind {
+        fn class_name(self) -> &'static str {
+            match self {
+                OverlayKind::RegionSelection => "CapturistRegionSelectionOverlay",
+                OverlayKind::RecordingBorder => "CapturistRecordingBorderOverlay",
+            }
+        }
+
+        fn wnd_proc(self) -> unsafe extern "system" fn(HWND, u32, WPARAM, LPARAM) -> LRESULT {
+            match self {
+                OverlayKind::RegionSelection => wnd_proc,
+                OverlayKind::RecordingBorder => border_wnd_proc,
+            }
+        }
     }
 
-    static STATE: OnceLock<Mutex<State>> = OnceLock::new();
+    struct OverlayState {
+        selection: RefCell<SelectionState>,
+        windows: Vec<SelectionRect>,
+        bounds: SelectionBounds,
+        style: OverlayStyle,
+    }
 
-    fn state() -> &'static Mutex<State> {
-        STATE.get_or_init(|| Mutex::new(State::default()))
+    fn registered_classes() -> &'static Mutex<HashSet<OverlayKind>> {
+        static REGISTERED_CLASSES: OnceLock<Mutex<HashSet<OverlayKind>>> = OnceLock::new();
+        REGISTERED_CLASSES.get_or_init(|| Mutex::new(HashSet::new()))
     }
 
-    fn update_rect(s: &mut State) {
-        let left = s.start.x.min(s.current.x);
-        let top = s.start.y.min(s.current.y);
-        let right = s.start.x.max(s.current.x);
-        let bottom = s.start.y.max(s.current.y);
-        s.rect = RECT {
-            left,
-            top,
-            right,
-            bottom,
+    fn wide_class_name(kind: OverlayKind) -> Vec<u16> {
+        kind.class_name().encode_utf16().chain([0]).collect()
+    }
+
+    fn module_instance() -> HINSTANCE {
+        unsafe { GetModuleHandleW(PCWSTR::null()) }
+            .map(HINSTANCE::from)
+            .unwrap_or_default()
+    }
+
+    fn ensure_class_registered(kind: OverlayKind) -> Result<(), String> {
+        let mut registered = registered_classes()
+            .lock()
+            .map_err(|_| "Registro de clases de overlay bloqueado".to_string())?;
+        if registered.contains(&kind) {
+            return Ok(());
+        }
+
+        let class_name = wide_class_name(kind);
+        let wc = WNDCLASSW {
+            lpfnWndProc: Some(kind.wnd_proc()),
+            hInstance: module_instance(),
+            hCursor: unsafe { LoadCursorW(None, IDC_CROSS) }.unwrap_or_default(),
+            hbrBackground: HBRUSH::default(),
+            lpszClassName: PCWSTR(class_name.as_ptr()),
+            ..Default::default()
+        };
+
+        if unsafe { RegisterClassW(&wc) } == 0 {
+            return Err(format!(
+                "No se pudo registrar la clase de ventana '{}'",
+                kind.class_name()
+            ));
+        }
+
+        registered.insert(kind);
+        Ok(())
+    }
+
+    pub fn unregister_overlay_classes() {
+        let Ok(mut registered) = registered_classes().lock() else {
+            return;
+        };
+
+        let instance = module_instance();
+        for kind in registered.drain() {
+            let class_name = wide_class_name(kind);
+            let _ = unsafe { UnregisterClassW(PCWSTR(class_name.as_ptr()), Some(instance)) };
+        }
+    }
+
+    /// Mientras vive, el hilo ve píxeles físicos en todos los monitores. La
+    /// ventana toma el contexto del hilo al crearse, así el overlay y el mouse
+    /// no pasan por la virtualización de DPI aunque el proceso no sea
+    /// DPI-aware por monitor.
+    struct PerMonitorDpiScope {
+        previous: DPI_AWARENESS_CONTEXT,
+    }
+
+    impl PerMonitorDpiScope {
+        fn enter() -> Self {
+            Self {
+                previous: unsafe {
+                    SetThreadDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2)
+                },
+            }
+        }
+    }
+
+    impl Drop for PerMonitorDpiScope {
+        fn drop(&mut self) {
+            if !self.previous.is_invalid() {
+                unsafe { SetThreadDpiAwarenessContext(self.previous) };
+            }
+        }
+    }
+
+    unsafe extern "system" fn collect_monitor(
+        monitor: HMONITOR,
+        _hdc: HDC,
+        _rect: *mut RECT,
+        l: LPARAM,
+    ) -> BOOL {
+        let monitors = &mut *(l.0 as *mut Vec<HMONITOR>);
+        monitors.push(monitor);
+        BOOL(1)
+    }
+
+    unsafe fn monitor_rect(monitor: HMONITOR) -> Option<RECT> {
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
         };
+        GetMonitorInfoW(monitor, &mut info)
+            .as_bool()
+            .then_some(info.rcMonitor)
+            .filter(has_area)
+    }
+
+    pub fn monitor_layout() -> Vec<MonitorScale> {
+        let mut monitors: Vec<HMONITOR> = Vec::new();
+        unsafe {
+            let _ = EnumDisplayMonitors(
+                None,
+                None,
+                Some(collect_monitor),
+                LPARAM(&mut monitors as *mut Vec<HMONITOR> as isize),
+            );
+        }
+
+        monitors
+            .into_iter()
+            .filter_map(|monitor| {
+                let logical = unsafe { monitor_rect(monitor) }?;
+                let physical = {
+                    let _dpi_scope = PerMonitorDpiScope::enter();
+                    unsafe { monitor_rect(monitor) }?
+                };
+                Some(MonitorScale {
+                    logical_x: logical.left,
+                    logical_y: logical.top,
+                    logical_width: logical.right - logical.left,
+                    logical_height: logical.bottom - logical.top,
+                    physical_x: physical.left,
+                    physical_y: physical.top,
+                    scale_factor: f64::from(physical.right - physical.left)
+                        / f64::from(logical.right - logical.left),
+                })
+            })
+            .collect()
+    }
+
+    unsafe fn window_state<'a>(hwnd: HWND) -> Option<&'a OverlayState> {
+        (GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *const OverlayState).as_ref()
+    }
+
+    fn to_rect(rect: SelectionRect) -> RECT {
+        RECT {
+            left: rect.left,
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+        }
+    }
+
+    unsafe extern "system" fn collect_window(hwnd: HWND, l: LPARAM) -> BOOL {
+        let handles = &mut *(l.0 as *mut Vec<HWND>);
+        handles.push(hwnd);
+        BOOL(1)
+    }
+
+    unsafe fn is_snap_candidate(hwnd: HWND) -> bool {
+        if !IsWindowVisible(hwnd).as_bool()
+            || IsIconic(hwnd).as_bool()
+            || GetWindowTextLengthW(hwnd) == 0
+            || hwnd == GetShellWindow()
+        {
+            return false;
+        }
+
+        let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE) as u32;
+        if ex_style & (WS_EX_TOOLWINDOW.0 | WS_EX_TRANSPARENT.0) != 0 {
+            return false;
+        }
+
+        let mut cloaked: u32 = 0;
+        let result = DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_CLOAKED,
+            &mut cloaked as *mut u32 as *mut c_void,
+            std::mem::size_of::<u32>() as u32,
+        );
+        !(result.is_ok() && cloaked != 0)
+    }
+
+    unsafe fn window_frame_bounds(hwnd: HWND) -> Option<RECT> {
+        let mut rect = RECT::default();
+        DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_EXTENDED_FRAME_BOUNDS,
+            &mut rect as *mut RECT as *mut c_void,
+            std::mem::size_of::<RECT>() as u32,
+        )
+        .ok()?;
+        has_area(&rect).then_some(rect)
+    }
+
+    unsafe fn snap_candidates(
+        bounds: &SelectionBounds,
+        width: i32,
+        height: i32,
+    ) -> Vec<SelectionRect> {
+        let mut handles: Vec<HWND> = Vec::new();
+        let _ = EnumWindows(
+            Some(collect_window),
+            LPARAM(&mut handles as *mut Vec<HWND> as isize),
+        );
+
+        handles
+            .into_iter()
+            .filter(|hwnd| is_snap_candidate(*hwnd))
+            .filter_map(|hwnd| window_frame_bounds(hwnd))
+            .map(|rect| {
+                SelectionRect {
+                    left: rect.left - bounds.origin_x,
+                    top: rect.top - bounds.origin_y,
+                    right: rect.right - bounds.origin_x,
+                    bottom: rect.bottom - bounds.origin_y,
+                }
+                .clip_to(width, height)
+            })
+            .filter(SelectionRect::has_area)
+            .collect()
     }
 
     fn has_area(rect: &RECT) -> bool {
@@ -68,21 +317,31 @@ mod win {
         }
     }
 
-    unsafe fn clamp_point_to_client(hwnd: HWND, point: POINT) -> POINT {
-        let mut client_rect = RECT::default();
-        let _ = GetClientRect(hwnd, &mut client_rect);
+    unsafe fn overlay_point(hwnd: HWND, state: &OverlayState, l: LPARAM) -> SelectionPoint {
+        let mut point = point_from_lparam(l);
+        let _ = ClientToScreen(hwnd, &mut point);
+        state.bounds.local_point(point.x, point.y)
+    }
 
-        let max_x = (client_rect.right - 1).max(0);
-        let max_y = (client_rect.bottom - 1).max(0);
+    struct CursorClip;
 
-        POINT {
-            x: point.x.clamp(0, max_x),
-            y: point.y.clamp(0, max_y),
+    impl CursorClip {
+        unsafe fn confine(bounds: &SelectionBounds, width: i32, height: i32) -> Self {
+            let rect = RECT {
+                left: bounds.origin_x,
+                top: bounds.origin_y,
+                right: bounds.origin_x + width,
+                bottom: bounds.origin_y + height,
+            };
+            let _ = ClipCursor(Some(&rect as *const RECT));
+            Self
         }
     }
 
-    fn same_rect(a: &RECT, b: &RECT) -> bool {
-        a.left == b.left && a.top == b.top && a.right == b.right && a.bottom == b.bottom
+    impl Drop for CursorClip {
+        fn drop(&mut self) {
+            let _ = unsafe { ClipCursor(None) };
+        }
     }
 
     fn rect_intersection(a: &RECT, b: &RECT) -> Option<RECT> {
@@ -120,7 +379,7 @@ mod win {
         let _ = InvalidateRect(Some(hwnd), Some(rect), false);
     }
 
-    unsafe fn paint_overlay(hwnd: HWND) {
+    unsafe fn paint_overlay(hwnd: HWND, selection: RECT, style: &OverlayStyle) {
         let mut ps = PAINTSTRUCT::default();
         let hdc = BeginPaint(hwnd, &mut ps);
         if hdc.is_invalid() {
@@ -136,21 +395,16 @@ mod win {
             client_rect
         };
 
-        let base_brush = CreateSolidBrush(OVERLAY_COLOR);
+        let base_brush = CreateSolidBrush(COLORREF(OVERLAY_DIM_COLOR));
         if !base_brush.0.is_null() {
             let _ = FillRect(hdc, &paint_rect, base_brush);
             let _ = DeleteObject(base_brush.into());
         }
 
-        let selection = {
-            let s = state().lock().expect("estado overlay poisoned");
-            s.rect
-        };
-
         if has_area(&selection) {
             // La región seleccionada usa un color-key transparente para imitar Snipping Tool:
             // fuera de la selección queda oscurecido y dentro se ve el contenido real.
-            let hole_brush = CreateSolidBrush(SELECTION_HOLE_COLOR);
+            let hole_brush = CreateSolidBrush(COLORREF(style.hole_colorref()));
             if !hole_brush.0.is_null() {
                 if let Some(hole_region) = rect_intersection(&selection, &paint_rect) {
                     let _ = FillRect(hdc, &hole_region, hole_brush);
@@ -158,18 +412,20 @@ mod win {
                 let _ = DeleteObject(hole_brush.into());
             }
 
-            let border_brush = CreateSolidBrush(COLORREF(0x00FFFFFF));
+            let border_brush = CreateSolidBrush(COLORREF(style.border_colorref()));
             if !border_brush.0.is_null() {
-                let border_bounds = expand_rect(selection, SELECTION_BORDER_THICKNESS_PX);
+                let thickness = i32::from(style.border_thickness_px);
+                let border_bounds = expand_rect(selection, thickness);
                 if rect_intersection(&border_bounds, &paint_rect).is_some() {
-                    let mut inner = selection;
-                    let _ = FrameRect(hdc, &selection, border_brush);
-                    if inner.right - inner.left > 2 && inner.bottom - inner.top > 2 {
-                        inner.left += 1;
-                        inner.top += 1;
-                        inner.right -= 1;
-                        inner.bottom -= 1;
-                        let _ = FrameRect(hdc, &inner, border_brush);
+                    let mut frame = selection;
+                    for ring in 0..thickness {
+                        if ring > 0 {
+                            if frame.right - frame.left <= 2 || frame.bottom - frame.top <= 2 {
+                                break;
+                            }
+                            frame = expand_rect(frame, -1);
+                        }
+                        let _ = FrameRect(hdc, &frame, border_brush);
                     }
                 }
                 let _ = DeleteObject(border_brush.into());
@@ -180,77 +436,69 @@ mod win {
     }
 
     unsafe extern "system" fn wnd_proc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESULT {
+        if msg == WM_NCCREATE {
+            let create = &*(l.0 as *const CREATESTRUCTW);
+            SetWindowLongPtrW(hwnd, GWLP_USERDATA, create.lpCreateParams as isize);
+            return DefWindowProcW(hwnd, msg, w, l);
+        }
+        if msg == WM_NCDESTROY {
+            SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+            return DefWindowProcW(hwnd, msg, w, l);
+        }
+
+        let Some(state) = window_state(hwnd) else {
+            return DefWindowProcW(hwnd, msg, w, l);
+        };
+
         match msg {
             WM_LBUTTONDOWN => {
-                let point = clamp_point_to_client(hwnd, point_from_lparam(l));
-                let mut s = state().lock().expect("estado overlay poisoned");
-                s.selecting = true;
-                s.start = point;
-                s.current = s.start;
-                update_rect(&mut s);
+                let point = overlay_point(hwnd, state, l);
+                state.selection.borrow_mut().begin(point);
                 windows_sys::Win32::UI::Input::KeyboardAndMouse::SetCapture(hwnd.0);
                 request_repaint(hwnd);
                 LRESULT(0)
             }
             WM_MOUSEMOVE => {
-                let point = clamp_point_to_client(hwnd, point_from_lparam(l));
-                let mut dirty_old = None;
-                let mut dirty_new = None;
-                {
-                    let mut s = state().lock().expect("estado overlay poisoned");
-                    if s.selecting {
-                        s.current = point;
-                        let old_rect = s.rect;
-                        update_rect(&mut s);
-                        if same_rect(&old_rect, &s.rect) {
-                            return LRESULT(0);
-                        }
-                        let dirty_padding = SELECTION_BORDER_THICKNESS_PX + 1;
-                        dirty_old = Some(expand_rect(old_rect, dirty_padding));
-                        dirty_new = Some(expand_rect(s.rect, dirty_padding));
-                    }
-                }
-                if let Some(old_rect) = dirty_old {
-                    request_repaint_rect(hwnd, &old_rect);
-                }
-                if let Some(new_rect) = dirty_new {
-                    request_repaint_rect(hwnd, &new_rect);
+                let point = overlay_point(hwnd, state, l);
+                let mut selection = state.selection.borrow_mut();
+                let changed = selection
+                    .drag_to(point)
+                    .or_else(|| selection.hover(snap_target_at(&state.windows, point)));
+                if let Some((old_rect, new_rect)) = changed {
+                    let dirty_padding = i32::from(state.style.border_thickness_px) + 1;
+                    request_repaint_rect(hwnd, &expand_rect(to_rect(old_rect), dirty_padding));
+                    request_repaint_rect(hwnd, &expand_rect(to_rect(new_rect), dirty_padding));
                 }
                 LRESULT(0)
             }
             WM_LBUTTONUP => {
-                let point = clamp_point_to_client(hwnd, point_from_lparam(l));
-                let mut s = state().lock().expect("estado overlay poisoned");
-                if s.selecting {
-                    s.selecting = false;
-                    s.done = true;
-                    s.current = point;
-                    update_rect(&mut s);
-                    windows_sys::Win32::UI::Input::KeyboardAndMouse::ReleaseCapture();
-
-                    let width = (s.rect.right - s.rect.left).abs();
-                    let height = (s.rect.bottom - s.rect.top).abs();
-                    if width < MIN_SELECTION_EDGE_PX || height < MIN_SELECTION_EDGE_PX {
-                        s.done = false;
-                        s.rect = RECT::default();
-                        request_repaint(hwnd);
-                        return LRESULT(0);
-                    }
+                let point = overlay_point(hwnd, state, l);
+                let discarded = state
+                    .selection
+                    .borrow_mut()
+                    .finish(point, MIN_SELECTION_EDGE_PX);
+                windows_sys::Win32::UI::Input::KeyboardAndMouse::ReleaseCapture();
+                if discarded {
+                    request_repaint(hwnd);
                 }
                 LRESULT(0)
             }
+            WM_KEYDOWN if w.0 as u32 == VK_TAB => {
+                state.selection.borrow_mut().toggle_snap();
+                request_repaint(hwnd);
+                LRESULT(0)
+            }
             WM_RBUTTONDOWN | WM_KEYDOWN => {
-                if msg == WM_KEYDOWN && w.0 as u32 != 0x1B {
+                if msg == WM_KEYDOWN && w.0 as u32 != VK_ESCAPE {
                     return DefWindowProcW(hwnd, msg, w, l);
                 }
-                let mut s = state().lock().expect("estado overlay poisoned");
-                s.cancelled = true;
-                s.done = true;
+                state.selection.borrow_mut().cancel();
                 LRESULT(0)
             }
             WM_ERASEBKGND => LRESULT(1),
             WM_PAINT => {
-                paint_overlay(hwnd);
+                let selection = to_rect(state.selection.borrow().rect());
+                paint_overlay(hwnd, selection, &state.style);
                 LRESULT(0)
             }
             _ => DefWindowProcW(hwnd, msg, w, l),
@@ -260,7 +508,9 @@ mod win {
     fn select_region_internal(
         bounds: SelectionBounds,
         return_absolute_coordinates: bool,
+        style: OverlayStyle,
     ) -> Result<Option<Region>, String> {
+        let _dpi_scope = PerMonitorDpiScope::enter();
         unsafe {
             let overlay_width = i32::try_from(bounds.width).map_err(|_| {
                 "El ancho del area seleccionable excede el limite soportado".to_string()
@@ -272,21 +522,17 @@ mod win {
                 return Err("El area seleccionable debe tener dimensiones validas".to_string());
             }
 
-            {
-                let mut s = state().lock().expect("estado overlay poisoned");
-                *s = State::default();
-            }
-
-            let class_name: Vec<u16> = "RegionOverlay".encode_utf16().chain([0]).collect();
-            let wc = WNDCLASSW {
-                lpfnWndProc: Some(wnd_proc),
-                hCursor: LoadCursorW(None, IDC_CROSS).unwrap_or_default(),
-                hbrBackground: HBRUSH::default(),
-                lpszClassName: PCWSTR(class_name.as_ptr()),
-                ..Default::default()
-            };
+            let kind = OverlayKind::RegionSelection;
+            ensure_class_registered(kind)?;
+            let class_name = wide_class_name(kind);
 
-            RegisterClassW(&wc);
+            // Vive hasta después de DestroyWindow; la ventana solo guarda el puntero.
+            let state: Box<OverlayState> = Box::new(OverlayState {
+                selection: RefCell::new(SelectionState::default()),
+                windows: snap_candidates(&bounds, overlay_width, overlay_height),
+                bounds,
+                style,
+            });
 
             let hwnd = CreateWindowExW(
                 WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_LAYERED,
@@ -299,8 +545,8 @@ mod win {
                 overlay_height,
                 Some(HWND(ptr::null_mut())),
                 Some(HMENU(ptr::null_mut())),
-                None,
-                None,
+                Some(module_instance()),
+                Some(&*state as *const OverlayState as *const c_void),
             )
             .map_err(|e| e.to_string())?;
 
@@ -308,11 +554,12 @@ mod win {
                 return Err("No se pudo crear la ventana overlay".to_string());
             }
 
+            let _cursor_clip = CursorClip::confine(&bounds, overlay_width, overlay_height);
             SetCursor(Some(LoadCursorW(None, IDC_CROSS).unwrap_or_default()));
             let _ = SetLayeredWindowAttributes(
                 hwnd,
-                SELECTION_HOLE_COLOR,
-                OVERLAY_DIM_ALPHA,
+                COLORREF(style.hole_colorref()),
+                style.dim_alpha,
                 LWA_ALPHA | LWA_COLORKEY,
             );
             let _ = ShowWindow(hwnd, SW_SHOW);
@@ -328,80 +575,237 @@ mod win {
                 let _ = TranslateMessage(&msg);
                 DispatchMessageW(&msg);
 
-                let done = {
-                    let s = state().lock().expect("estado overlay poisoned");
-                    s.done
-                };
-                if done {
+                if state.selection.borrow().is_done() {
                     break;
                 }
             }
 
             let _ = DestroyWindow(hwnd);
 
-            let s = state().lock().expect("estado overlay poisoned");
-            if s.cancelled {
+            let Some(rect) = state.selection.borrow().result() else {
                 return Ok(None);
+            };
+
+            let region = bounds.local_region(rect);
+            if !return_absolute_coordinates {
+                return Ok(Some(region));
             }
 
-            let rect = s.rect;
-            let width = (rect.right - rect.left).max(1) as u32;
-            let height = (rect.bottom - rect.top).max(1) as u32;
-            let region = Region {
-                x: if return_absolute_coordinates {
-                    (bounds.origin_x + rect.left).max(0) as u32
-                } else {
-                    rect.left.max(0) as u32
-                },
-                y: if return_absolute_coordinates {
-                    (bounds.origin_y + rect.top).max(0) as u32
-                } else {
-                    rect.top.max(0) as u32
-                },
-                width,
-                height,
-            };
+            Ok(Some(Region {
+                x: (bounds.origin_x + region.x as i32).max(0) as u32,
+                y: (bounds.origin_y + region.y as i32).max(0) as u32,
+                ..region
+            }))
+        }
+    }
 
-            Ok(Some(region))
+    unsafe extern "system" fn border_wnd_proc(
+        hwnd: HWND,
+        msg: u32,
+        w: WPARAM,
+        l: LPARAM,
+    ) -> LRESULT {
+        match msg {
+            WM_ERASEBKGND => LRESULT(1),
+            WM_PAINT => {
+                let mut ps = PAINTSTRUCT::default();
+                let hdc = BeginPaint(hwnd, &mut ps);
+                if !hdc.is_invalid() {
+                    let brush = CreateSolidBrush(RECORDING_BORDER_COLOR);
+                    if !brush.0.is_null() {
+                        let _ = FillRect(hdc, &ps.rcPaint, brush);
+                        let _ = DeleteObject(brush.into());
+                    }
+                }
+                let _ = EndPaint(hwnd, &ps);
+                LRESULT(0)
+            }
+            WM_DESTROY => {
+                PostQuitMessage(0);
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, msg, w, l),
         }
     }
 
-    pub fn select_region() -> Result<Option<Region>, String> {
+    pub struct RecordingBorder {
+        hwnd: isize,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl RecordingBorder {
+        pub fn show(region: ScreenRect) -> Result<Self, String> {
+            let (ready_tx, ready_rx) = mpsc::channel::<Result<isize, String>>();
+            let thread = thread::Builder::new()
+                .name("recording-border".to_string())
+                .spawn(move || {
+                    let _dpi_scope = PerMonitorDpiScope::enter();
+                    let hwnd = match unsafe { create_border_window(region) } {
+                        Ok(hwnd) => hwnd,
+                        Err(err) => {
+                            let _ = ready_tx.send(Err(err));
+                            return;
+                        }
+                    };
+                    let _ = ready_tx.send(Ok(hwnd.0 as isize));
+
+                    let mut msg = MSG::default();
+                    unsafe {
+                        while GetMessageW(&mut msg, None, 0, 0).0 > 0 {
+                            let _ = TranslateMessage(&msg);
+                            DispatchMessageW(&msg);
+                        }
+                    }
+                })
+                .map_err(|err| format!("No se pudo iniciar el marco de grabación: {err}"))?;
+
+            match ready_rx.recv() {
+                Ok(Ok(hwnd)) => Ok(Self {
+                    hwnd,
+                    thread: Some(thread),
+                }),
+                Ok(Err(err)) => {
+                    let _ = thread.join();
+                    Err(err)
+                }
+                Err(_) => {
+                    let _ = thread.join();
+                    Err("El marco de grabación se cerró al crearse".to_string())
+                }
+            }
+        }
+    }
+
+    impl Drop for RecordingBorder {
+        fn drop(&mut self) {
+            let hwnd = HWND(self.hwnd as *mut c_void);
+            let _ = unsafe { PostMessageW(Some(hwnd), WM_CLOSE, WPARAM(0), LPARAM(0)) };
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    unsafe fn create_border_window(region: ScreenRect) -> Result<HWND, String> {
+        let kind = OverlayKind::RecordingBorder;
+        ensure_class_registered(kind)?;
+        let class_name = wide_class_name(kind);
+        let frame = border_frame(region, RECORDING_BORDER_THICKNESS_PX);
+
+        let hwnd = CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_NOACTIVATE,
+            PCWSTR(class_name.as_ptr()),
+            PCWSTR(class_name.as_ptr()),
+            WS_POPUP,
+            frame.outer.left,
+            frame.outer.top,
+            frame.outer.width(),
+            frame.outer.height(),
+            None,
+            None,
+            Some(module_instance()),
+            None,
+        )
+        .map_err(|e| format!("No se pudo crear el marco de grabación: {e}"))?;
+
+        let ring = CreateRectRgn(0, 0, frame.outer.width(), frame.outer.height());
+        let hole = CreateRectRgn(
+            frame.inner.left,
+            frame.inner.top,
+            frame.inner.right,
+            frame.inner.bottom,
+        );
+        let _ = CombineRgn(Some(ring), Some(ring), Some(hole), RGN_DIFF);
+        let _ = DeleteObject(hole.into());
+        // Con éxito la región pasa a ser del sistema.
+        if SetWindowRgn(hwnd, Some(ring), false) == 0 {
+            let _ = DeleteObject(ring.into());
+        }
+
+        let _ = SetLayeredWindowAttributes(hwnd, COLORREF(0), 255, LWA_ALPHA);
+        let _ = SetWindowDisplayAffinity(hwnd, WDA_EXCLUDEFROMCAPTURE);
+        let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+        Ok(hwnd)
+    }
+
+    pub fn select_region(style: OverlayStyle) -> Result<Option<Region>, String> {
+        let _dpi_scope = PerMonitorDpiScope::enter();
         let bounds = SelectionBounds {
             origin_x: unsafe { GetSystemMetrics(SM_XVIRTUALSCREEN) },
             origin_y: unsafe { GetSystemMetrics(SM_YVIRTUALSCREEN) },
             width: unsafe { GetSystemMetrics(SM_CXVIRTUALSCREEN).max(1) as u32 },
             height: unsafe { GetSystemMetrics(SM_CYVIRTUALSCREEN).max(1) as u32 },
+            scale_factor: 1.0,
         };
 
-        select_region_internal(bounds, true)
+        select_region_internal(bounds, true, style)
     }
 
-    pub fn select_region_with_bounds(bounds: SelectionBounds) -> Result<Option<Region>, String> {
-        select_region_internal(bounds, false)
+    pub fn select_region_with_bounds(
+        bounds: SelectionBounds,
+        style: OverlayStyle,
+    ) -> Result<Option<Region>, String> {
+        select_region_internal(bounds.to_physical(&monitor_layout()), false, style)
     }
 }
 
 #[cfg(target_os = "windows")]
-pub fn select_region() -> Result<Option<crate::capture::models::Region>, String> {
-    win::select_region()
+pub fn select_region(
+    style: crate::region::OverlayStyle,
+) -> Result<Option<crate::capture::models::Region>, String> {
+    win::select_region(style)
 }
 
 #[cfg(target_os = "windows")]
 pub fn select_region_with_bounds(
     bounds: crate::region::SelectionBounds,
+    style: crate::region::OverlayStyle,
 ) -> Result<Option<crate::capture::models::Region>, String> {
-    win::select_region_with_bounds(bounds)
+    win::select_region_with_bounds(bounds, style)
+}
+
+#[cfg(target_os = "windows")]
+pub use win::RecordingBorder;
+
+#[cfg(not(target_os = "windows"))]
+pub struct RecordingBorder;
+
+#[cfg(not(target_os = "windows"))]
+impl RecordingBorder {
+    pub fn show(_region: crate::region::ScreenRect) -> Result<Self, String> {
+        Err("El marco de grabación solo está disponible en Windows".to_string())
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn monitor_layout() -> Vec<crate::region::MonitorScale> {
+    win::monitor_layout()
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn monitor_layout() -> Vec<crate::region::MonitorScale> {
+    Vec::new()
+}
+
+#[cfg(target_os = "windows")]
+pub fn unregister_overlay_classes() {
+    win::unregister_overlay_classes();
 }
 
 #[cfg(not(target_os = "windows"))]
-pub fn select_region() -> Result<Option<crate::capture::models::Region>, String> {
+pub fn unregister_overlay_classes() {}
+
+#[cfg(not(target_os = "windows"))]
+pub fn select_region(
+    _style: crate::region::OverlayStyle,
+) -> Result<Option<crate::capture::models::Region>, String> {
     Err("Overlay solo disponible en Windows".to_string())
 }
 
 #[cfg(not(target_os = "windows"))]
 pub fn select_region_with_bounds(
     _bounds: crate::region::SelectionBounds,
+    _style: crate::region::OverlayStyle,
 ) -> Result<Option<crate::capture::models::Region>, String> {
     Err("Overlay solo disponible en Windows".to_string())
 }
@@ -0,0 +1,349 @@
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::{capture::models::Region, region::SelectionBounds};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionPoint {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionRect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl SelectionRect {
+    fn from_corners(a: SelectionPoint, b: SelectionPoint) -> Self {
+        Self {
+            left: a.x.min(b.x),
+            top: a.y.min(b.y),
+            right: a.x.max(b.x),
+            bottom: a.y.max(b.y),
+        }
+    }
+
+    pub fn has_area(&self) -> bool {
+        self.right > self.left && self.bottom > self.top
+    }
+
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+
+    fn contains(&self, point: SelectionPoint) -> bool {
+        point.x >= self.left && point.x < self.right && point.y >= self.top && point.y < self.bottom
+    }
+
+    pub fn clip_to(&self, width: i32, height: i32) -> Self {
+        Self {
+            left: self.left.max(0),
+            top: self.top.max(0),
+            right: self.right.min(width),
+            bottom: self.bottom.min(height),
+        }
+    }
+}
+
+pub fn snap_target_at(windows: &[SelectionRect], point: SelectionPoint) -> Option<SelectionRect> {
+    windows
+        .iter()
+        .copied()
+        .find(|rect| rect.has_area() && rect.contains(point))
+}
+
+impl SelectionBounds {
+    pub fn local_point(&self, screen_x: i32, screen_y: i32) -> SelectionPoint {
+        let max_x = (self.width.min(i32::MAX as u32) as i32 - 1).max(0);
+        let max_y = (self.height.min(i32::MAX as u32) as i32 - 1).max(0);
+        SelectionPoint {
+            x: screen_x.saturating_sub(self.origin_x).clamp(0, max_x),
+            y: screen_y.saturating_sub(self.origin_y).clamp(0, max_y),
+        }
+    }
+
+    pub fn local_region(&self, rect: SelectionRect) -> Region {
+        let width = self.width.min(i32::MAX as u32) as i32;
+        let height = self.height.min(i32::MAX as u32) as i32;
+        let clipped = rect.clip_to(width, height);
+        let left = clipped.left.min(width - 1).max(0);
+        let top = clipped.top.min(height - 1).max(0);
+        Region {
+            x: left as u32,
+            y: top as u32,
+            width: (clipped.right - left).max(1) as u32,
+            height: (clipped.bottom - top).max(1) as u32,
+        }
+    }
+}
+
+/// Estado de selección de un overlay. Cada ventana tiene el suyo (se guarda en
+/// `GWLP_USERDATA`), así dos overlays abiertos no se pisan entre sí.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SelectionState {
+    selecting: bool,
+    start: SelectionPoint,
+    current: SelectionPoint,
+    rect: SelectionRect,
+    freeform: bool,
+    snapped: Option<SelectionRect>,
+    cancelled: bool,
+    done: bool,
+}
+
+impl SelectionState {
+    pub fn rect(&self) -> SelectionRect {
+        self.rect
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn is_snapping(&self) -> bool {
+        !self.freeform
+    }
+
+    pub fn hover(
+        &mut self,
+        window: Option<SelectionRect>,
+    ) -> Option<(SelectionRect, SelectionRect)> {
+        if self.selecting || self.freeform || self.snapped == window {
+            return None;
+        }
+
+        self.snapped = window;
+        let old_rect = self.rect;
+        self.rect = window.unwrap_or_default();
+        (old_rect != self.rect).then_some((old_rect, self.rect))
+    }
+
+    pub fn toggle_snap(&mut self) {
+        if self.selecting {
+            return;
+        }
+        self.freeform = !self.freeform;
+        self.snapped = None;
+        self.rect = SelectionRect::default();
+    }
+
+    pub fn begin(&mut self, point: SelectionPoint) {
+        self.selecting = true;
+        self.start = point;
+        self.current = point;
+        self.rect = SelectionRect::from_corners(point, point);
+    }
+
+    pub fn drag_to(&mut self, point: SelectionPoint) -> Option<(SelectionRect, SelectionRect)> {
+        if !self.selecting {
+            return None;
+        }
+
+        self.current = point;
+        let old_rect = self.rect;
+        self.rect = SelectionRect::from_corners(self.start, self.current);
+        (old_rect != self.rect).then_some((old_rect, self.rect))
+    }
+
+    pub fn finish(&mut self, point: SelectionPoint, min_edge: i32) -> bool {
+        if !self.selecting {
+            return false;
+        }
+
+        self.selecting = false;
+        self.current = point;
+        self.rect = SelectionRect::from_corners(self.start, self.current);
+        if self.rect.width() < min_edge || self.rect.height() < min_edge {
+            if let Some(window) = self.snapped.filter(SelectionRect::has_area) {
+                self.rect = window;
+                self.done = true;
+                return false;
+            }
+            self.rect = SelectionRect::default();
+            return true;
+        }
+
+        self.done = true;
+        false
+    }
+
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+        self.done = true;
+    }
+
+    pub fn result(&self) -> Option<SelectionRect> {
+        if self.cancelled {
+            None
+        } else {
+            Some(self.rect)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: i32, y: i32) -> SelectionPoint {
+        SelectionPoint { x, y }
+    }
+
+    #[test]
+    fn arrastre_normaliza_el_rectangulo_y_reporta_cambios() {
+        let mut state = SelectionState::default();
+        assert_eq!(state.drag_to(point(10, 10)), None);
+
+        state.begin(point(100, 80));
+        let (old, new) = state.drag_to(point(40, 20)).expect("cambio de selección");
+        assert!(!old.has_area());
+        assert_eq!(
+            new,
+            SelectionRect {
+                left: 40,
+                top: 20,
+                right: 100,
+                bottom: 80,
+            }
+        );
+        assert_eq!(state.drag_to(point(40, 20)), None);
+
+        assert!(!state.finish(point(40, 20), 5));
+        assert!(state.is_done());
+        assert_eq!(state.result(), Some(new));
+    }
+
+    #[test]
+    fn seleccion_minima_se_descarta_sin_cerrar() {
+        let mut state = SelectionState::default();
+        state.begin(point(10, 10));
+
+        assert!(state.finish(point(12, 40), 5));
+        assert!(!state.is_done());
+        assert!(!state.rect().has_area());
+    }
+
+    #[test]
+    fn click_sobre_ventana_resaltada_la_selecciona() {
+        let front = SelectionRect {
+            left: 100,
+            top: 100,
+            right: 400,
+            bottom: 300,
+        };
+        let back = SelectionRect {
+            left: 0,
+            top: 0,
+            right: 800,
+            bottom: 600,
+        };
+        let windows = [front, back];
+        assert_eq!(snap_target_at(&windows, point(150, 150)), Some(front));
+        assert_eq!(snap_target_at(&windows, point(500, 500)), Some(back));
+        assert_eq!(snap_target_at(&windows, point(900, 10)), None);
+
+        let mut state = SelectionState::default();
+        assert!(state.is_snapping());
+        let (_, highlighted) = state.hover(Some(front)).expect("resalta la ventana");
+        assert_eq!(highlighted, front);
+        assert_eq!(state.hover(Some(front)), None);
+
+        state.begin(point(150, 150));
+        state.drag_to(point(152, 151));
+        assert!(!state.finish(point(152, 151), 5));
+        assert_eq!(state.result(), Some(front));
+    }
+
+    #[test]
+    fn modo_libre_ignora_ventanas_y_arrastrar_sigue_funcionando() {
+        let window = SelectionRect {
+            left: 0,
+            top: 0,
+            right: 300,
+            bottom: 200,
+        };
+        let mut state = SelectionState::default();
+        state.hover(Some(window));
+        state.toggle_snap();
+        assert!(!state.is_snapping());
+        assert!(!state.rect().has_area());
+        assert_eq!(state.hover(Some(window)), None);
+
+        state.begin(point(10, 10));
+        assert!(state.finish(point(11, 10), 5));
+        assert!(!state.is_done());
+
+        state.toggle_snap();
+        state.hover(Some(window));
+        state.begin(point(10, 10));
+        state.drag_to(point(60, 40));
+        assert!(!state.finish(point(60, 40), 5));
+        assert_eq!(
+            state.result().map(|rect| (rect.width(), rect.height())),
+            Some((50, 30))
+        );
+    }
+
+    #[test]
+    fn monitor_a_la_izquierda_devuelve_coordenadas_relativas() {
+        let bounds = SelectionBounds {
+            origin_x: -1920,
+            origin_y: -200,
+            width: 1920,
+            height: 1080,
+            scale_factor: 1.0,
+        };
+        assert_eq!(bounds.local_point(-1000, 300), point(920, 500));
+        assert_eq!(bounds.local_point(150, -900), point(1919, 0));
+        assert_eq!(bounds.local_point(-2500, 2000), point(0, 1079));
+
+        let mut state = SelectionState::default();
+        state.toggle_snap();
+        state.begin(bounds.local_point(-1000, 300));
+        state.drag_to(bounds.local_point(400, 100));
+        assert!(!state.finish(bounds.local_point(400, 100), 5));
+        let region = bounds.local_region(state.result().unwrap());
+        assert_eq!(
+            (region.x, region.y, region.width, region.height),
+            (920, 300, 999, 200)
+        );
+
+        let outside = SelectionRect {
+            left: -50,
+            top: 1000,
+            right: 30,
+            bottom: 1200,
+        };
+        let region = bounds.local_region(outside);
+        assert_eq!(
+            (region.x, region.y, region.width, region.height),
+            (0, 1000, 30, 80)
+        );
+    }
+
+    #[test]
+    fn instancias_independientes_no_se_pisan() {
+        let mut first = SelectionState::default();
+        let mut second = SelectionState::default();
+
+        first.begin(point(0, 0));
+        second.begin(point(500, 500));
+        second.cancel();
+        first.drag_to(point(50, 60));
+        assert!(!first.finish(point(50, 60), 5));
+
+        assert_eq!(
+            first.result().map(|rect| (rect.width(), rect.height())),
+            Some((50, 60))
+        );
+        assert_eq!(second.result(), None);
+        assert!(second.is_done());
+    }
+}
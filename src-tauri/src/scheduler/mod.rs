@@ -0,0 +1,365 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+    commands::{
+        lock_capture, start_recording_session, stop_recording_session, RecordingSessionConfig,
+    },
+    shutdown::join_thread_until,
+    AppState,
+};
+
+pub const EVENT_SCHEDULED_RECORDING_STARTED: &str = "scheduled-recording-started";
+pub const EVENT_SCHEDULED_RECORDING_FAILED: &str = "scheduled-recording-failed";
+pub const EVENT_SCHEDULED_RECORDING_COMPLETED: &str = "scheduled-recording-completed";
+
+const IDLE_WAIT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledRecordingEvent {
+    pub id: u64,
+    pub error: Option<String>,
+}
+
+pub struct RecordingScheduler {
+    tx: mpsc::Sender<SchedulerCommand>,
+    next_id: AtomicU64,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RecordingScheduler {
+    pub fn new(app: AppHandle) -> Result<Self, String> {
+        let (tx, rx) = mpsc::channel::<SchedulerCommand>();
+        let thread = std::thread::Builder::new()
+            .name("capturist-recording-scheduler".into())
+            .spawn(move || run_scheduler_loop(app, rx))
+            .map_err(|err| {
+                format!("No se pudo iniciar el hilo de grabaciones programadas: {err}")
+            })?;
+
+        Ok(Self {
+            tx,
+            next_id: AtomicU64::new(1),
+            thread: Some(thread),
+        })
+    }
+
+    pub fn shutdown(mut self, deadline: Instant) -> bool {
+        let _ = self.tx.send(SchedulerCommand::Shutdown);
+        self.thread
+            .take()
+            .is_none_or(|thread| join_thread_until(thread, deadline))
+    }
+
+    pub fn schedule(
+        &self,
+        config: RecordingSessionConfig,
+        start_at_epoch_ms: u64,
+        max_duration_ms: Option<u64>,
+    ) -> Result<u64, String> {
+        if start_at_epoch_ms <= now_epoch_ms() {
+            return Err("La hora de inicio programada debe estar en el futuro".to_string());
+        }
+
+        if max_duration_ms == Some(0) {
+            return Err("La duración máxima programada debe ser mayor a 0".to_string());
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.tx
+            .send(SchedulerCommand::Schedule {
+                id,
+                start_at_epoch_ms,
+                max_duration_ms,
+                config: Box::new(config),
+            })
+            .map_err(|_| "No se pudo enviar la grabación programada".to_string())?;
+
+        Ok(id)
+    }
+
+    pub fn cancel(&self, id: u64) -> Result<(), String> {
+        let (ack_tx, ack_rx) = mpsc::channel();
+        self.tx
+            .send(SchedulerCommand::Cancel(id, ack_tx))
+            .map_err(|_| {
+                "No se pudo enviar la cancelación de la grabación programada".to_string()
+            })?;
+
+        ack_rx
+            .recv()
+            .map_err(|_| "No se recibió confirmación de la cancelación programada".to_string())?
+    }
+}
+
+impl Drop for RecordingScheduler {
+    fn drop(&mut self) {
+        let _ = self.tx.send(SchedulerCommand::Shutdown);
+    }
+}
+
+enum SchedulerCommand {
+    Schedule {
+        id: u64,
+        start_at_epoch_ms: u64,
+        max_duration_ms: Option<u64>,
+        config: Box<RecordingSessionConfig>,
+    },
+    Cancel(u64, mpsc::Sender<Result<(), String>>),
+    Shutdown,
+}
+
+enum ScheduledAction {
+    Start {
+        max_duration_ms: Option<u64>,
+        config: Box<RecordingSessionConfig>,
+    },
+    Stop {
+        session_serial: u64,
+    },
+}
+
+struct ScheduleEntry<T> {
+    id: u64,
+    due_at_epoch_ms: u64,
+    payload: T,
+}
+
+struct ScheduleQueue<T> {
+    entries: Vec<ScheduleEntry<T>>,
+}
+
+impl<T> Default for ScheduleQueue<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T> ScheduleQueue<T> {
+    fn push(&mut self, id: u64, due_at_epoch_ms: u64, payload: T) {
+        self.entries.push(ScheduleEntry {
+            id,
+            due_at_epoch_ms,
+            payload,
+        });
+    }
+
+    fn remove_where(&mut self, id: u64, predicate: impl Fn(&T) -> bool) -> bool {
+        let before = self.entries.len();
+        self.entries
+            .retain(|entry| entry.id != id || !predicate(&entry.payload));
+        self.entries.len() != before
+    }
+
+    fn wait_duration(&self, now_epoch_ms: u64) -> Option<Duration> {
+        self.entries
+            .iter()
+            .map(|entry| entry.due_at_epoch_ms)
+            .min()
+            .map(|due| Duration::from_millis(due.saturating_sub(now_epoch_ms)))
+    }
+
+    fn take_due(&mut self, now_epoch_ms: u64) -> Vec<ScheduleEntry<T>> {
+        let (mut due, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|entry| entry.due_at_epoch_ms <= now_epoch_ms);
+        self.entries = pending;
+        due.sort_by_key(|entry| entry.due_at_epoch_ms);
+        due
+    }
+}
+
+fn run_scheduler_loop(app: AppHandle, rx: mpsc::Receiver<SchedulerCommand>) {
+    let mut queue = ScheduleQueue::<ScheduledAction>::default();
+
+    loop {
+        let timeout = queue.wait_duration(now_epoch_ms()).unwrap_or(IDLE_WAIT);
+        match rx.recv_timeout(timeout) {
+            Ok(SchedulerCommand::Schedule {
+                id,
+                start_at_epoch_ms,
+                max_duration_ms,
+                config,
+            }) => queue.push(
+                id,
+                start_at_epoch_ms,
+                ScheduledAction::Start {
+                    max_duration_ms,
+                    config,
+                },
+            ),
+            Ok(SchedulerCommand::Cancel(id, ack)) => {
+                let removed = queue
+                    .remove_where(id, |action| matches!(action, ScheduledAction::Start { .. }));
+                let _ = ack.send(if removed {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "No hay una grabación programada pendiente con id {id}"
+                    ))
+                });
+            }
+            Ok(SchedulerCommand::Shutdown) | Err(RecvTimeoutError::Disconnected) => return,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+
+        for entry in queue.take_due(now_epoch_ms()) {
+            match entry.payload {
+                ScheduledAction::Start {
+                    max_duration_ms,
+                    config,
+                } => match trigger_scheduled_start(&app, *config) {
+                    Ok(session_serial) => {
+                        emit_scheduled_event(
+                            &app,
+                            EVENT_SCHEDULED_RECORDING_STARTED,
+                            entry.id,
+                            None,
+                        );
+                        if let Some(max_duration_ms) = max_duration_ms {
+                            queue.push(
+                                entry.id,
+                                now_epoch_ms().saturating_add(max_duration_ms),
+                                ScheduledAction::Stop { session_serial },
+                            );
+                        }
+                    }
+                    Err(err) => {
+                        emit_scheduled_event(
+                            &app,
+                            EVENT_SCHEDULED_RECORDING_FAILED,
+                            entry.id,
+                            Some(err),
+                        );
+                    }
+                },
+                ScheduledAction::Stop { session_serial } => {
+                    match trigger_scheduled_stop(&app, session_serial) {
+                        Ok(true) => emit_scheduled_event(
+                            &app,
+                            EVENT_SCHEDULED_RECORDING_COMPLETED,
+                            entry.id,
+                            None,
+                        ),
+                        Ok(false) => {}
+                        Err(err) => emit_scheduled_event(
+                            &app,
+                            EVENT_SCHEDULED_RECORDING_COMPLETED,
+                            entry.id,
+                            Some(err),
+                        ),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn trigger_scheduled_start(app: &AppHandle, config: RecordingSessionConfig) -> Result<u64, String> {
+    let state = app.state::<AppState>();
+
+    // Los ids de ventana dependen del HWND vigente y el monitor puede estar
+    // desconectado; se vuelve a resolver justo antes de arrancar.
+    let target_available = lock_capture(&state)?
+        .get_targets()?
+        .iter()
+        .any(|target| target.id == config.target_id);
+    if !target_available {
+        return Err(format!(
+            "El target programado (id {}) ya no está disponible",
+            config.target_id
+        ));
+    }
+
+    // Las advertencias de espacio no bloquean el arranque programado. El
+    // serial sale del mismo lock que arrancó la sesión: un inicio manual en
+    // el medio no lo cambia.
+    start_recording_session(&state, config).map(|report| report.session_serial)
+}
+
+fn trigger_scheduled_stop(app: &AppHandle, session_serial: u64) -> Result<bool, String> {
+    let state = app.state::<AppState>();
+
+    let is_same_session = {
+        let mut manager = lock_capture(&state)?;
+        manager.refresh_runtime_state();
+        manager.is_active() && manager.session_serial() == session_serial
+    };
+
+    if !is_same_session {
+        return Ok(false);
+    }
+
+    stop_recording_session(&state).map(|_| true)
+}
+
+fn emit_scheduled_event(app: &AppHandle, event: &str, id: u64, error: Option<String>) {
+    if let Err(err) = app.emit(event, ScheduledRecordingEvent { id, error }) {
+        eprintln!("[scheduler] No se pudo emitir el evento {event}: {err}");
+    }
+}
+
+fn now_epoch_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entrega_solo_las_tareas_vencidas_en_orden() {
+        let mut queue = ScheduleQueue::default();
+        queue.push(1, 3_000, "c");
+        queue.push(2, 1_000, "a");
+        queue.push(3, 9_000, "z");
+        queue.push(4, 2_000, "b");
+
+        let due: Vec<_> = queue
+            .take_due(3_000)
+            .into_iter()
+            .map(|entry| entry.payload)
+            .collect();
+
+        assert_eq!(due, vec!["a", "b", "c"]);
+        assert_eq!(
+            queue.wait_duration(3_000),
+            Some(Duration::from_millis(6_000))
+        );
+    }
+
+    #[test]
+    fn espera_cero_si_una_tarea_ya_vencio() {
+        let mut queue = ScheduleQueue::default();
+        assert_eq!(queue.wait_duration(5_000), None);
+
+        queue.push(1, 4_000, ());
+        assert_eq!(queue.wait_duration(5_000), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn cancelar_solo_elimina_entradas_que_cumplen_el_filtro() {
+        let mut queue = ScheduleQueue::default();
+        queue.push(7, 1_000, true);
+        queue.push(7, 2_000, false);
+        queue.push(8, 1_000, true);
+
+        assert!(queue.remove_where(7, |is_start| *is_start));
+        assert!(!queue.remove_where(7, |is_start| *is_start));
+        assert_eq!(queue.entries.len(), 2);
+    }
+}
@@ -1,5 +1,9 @@
+use std::{thread::JoinHandle, time::Instant};
+
 use tauri::AppHandle;
 
+use crate::shutdown::join_thread_until;
+
 #[cfg(windows)]
 pub const EVENT_GLOBAL_SHORTCUT_TRIGGERED: &str = "global-shortcut-triggered";
 
@@ -9,21 +13,36 @@ pub struct ShortcutBindings {
     pub start: String,
     pub pause_resume: String,
     pub stop: String,
+    #[serde(default)]
+    pub marker: Option<String>,
+    #[serde(default)]
+    pub snapshot: Option<String>,
 }
 
 pub struct GlobalShortcutManager {
     tx: std::sync::mpsc::Sender<PlatformCommand>,
+    thread: Option<JoinHandle<()>>,
 }
 
 impl GlobalShortcutManager {
     pub fn new(app: AppHandle) -> Result<Self, String> {
         let (tx, rx) = std::sync::mpsc::channel::<PlatformCommand>();
-        std::thread::Builder::new()
+        let thread = std::thread::Builder::new()
             .name("capturist-global-shortcuts".into())
             .spawn(move || run_hotkey_loop(app, rx))
             .map_err(|err| format!("No se pudo iniciar el hilo de atajos globales: {err}"))?;
 
-        Ok(Self { tx })
+        Ok(Self {
+            tx,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn shutdown(mut self, deadline: Instant) -> bool {
+        let _ = self.tx.send(PlatformCommand::Shutdown);
+        self.thread
+            .take()
+            .is_none_or(|thread| join_thread_until(thread, deadline))
     }
 
     pub fn update(&self, bindings: ShortcutBindings) -> Result<(), String> {
@@ -57,11 +76,17 @@ enum PlatformCommand {
 fn validate_bindings_shape(bindings: &ShortcutBindings) -> Result<(), String> {
     use std::collections::HashSet;
 
-    let shortcuts = [
+    let mut shortcuts = vec![
         bindings.start.trim(),
         bindings.pause_resume.trim(),
         bindings.stop.trim(),
     ];
+    for optional in [bindings.marker.as_deref(), bindings.snapshot.as_deref()]
+        .into_iter()
+        .flatten()
+    {
+        shortcuts.push(optional.trim());
+    }
 
     if shortcuts.iter().any(|value| value.is_empty()) {
         return Err("Todos los atajos deben tener una combinación válida".to_string());
@@ -84,8 +109,13 @@ enum ShortcutAction {
     Start,
     PauseResume,
     Stop,
+    Marker,
+    Snapshot,
 }
 
+#[cfg(windows)]
+const SHORTCUT_ACTION_COUNT: usize = 5;
+
 #[cfg(windows)]
 impl ShortcutAction {
     fn event_payload(self) -> &'static str {
@@ -93,6 +123,8 @@ impl ShortcutAction {
             ShortcutAction::Start => "start",
             ShortcutAction::PauseResume => "pauseResume",
             ShortcutAction::Stop => "stop",
+            ShortcutAction::Marker => "marker",
+            ShortcutAction::Snapshot => "snapshot",
         }
     }
 
@@ -101,6 +133,8 @@ impl ShortcutAction {
             ShortcutAction::Start => 0,
             ShortcutAction::PauseResume => 1,
             ShortcutAction::Stop => 2,
+            ShortcutAction::Marker => 3,
+            ShortcutAction::Snapshot => 4,
         }
     }
 }
@@ -111,13 +145,13 @@ fn run_hotkey_loop(app: AppHandle, rx: std::sync::mpsc::Receiver<PlatformCommand
         thread,
         time::{Duration, Instant},
     };
-    use tauri::Emitter;
+    use tauri::{Emitter, Manager};
 
     const TRIGGER_COOLDOWN_MS: u64 = 220;
 
     let mut bindings: Vec<ParsedBinding> = Vec::new();
-    let mut pressed_state = [false; 3];
-    let mut last_trigger_at = [None::<Instant>; 3];
+    let mut pressed_state = [false; SHORTCUT_ACTION_COUNT];
+    let mut last_trigger_at = [None::<Instant>; SHORTCUT_ACTION_COUNT];
 
     loop {
         while let Ok(command) = rx.try_recv() {
@@ -127,8 +161,8 @@ fn run_hotkey_loop(app: AppHandle, rx: std::sync::mpsc::Receiver<PlatformCommand
                     match result {
                         Ok(parsed_bindings) => {
                             bindings = parsed_bindings;
-                            pressed_state = [false; 3];
-                            last_trigger_at = [None, None, None];
+                            pressed_state = [false; SHORTCUT_ACTION_COUNT];
+                            last_trigger_at = [None; SHORTCUT_ACTION_COUNT];
                             let _ = ack.send(Ok(()));
                         }
                         Err(err) => {
@@ -152,6 +186,31 @@ fn run_hotkey_loop(app: AppHandle, rx: std::sync::mpsc::Receiver<PlatformCommand
             if (combo_just_pressed || (combo_down && !was_down))
                 && can_emit_now(last_trigger_at[index], TRIGGER_COOLDOWN_MS)
             {
+                // El marcador se registra aquí mismo: la ventana puede estar
+                // oculta y no debe depender del evento para llegar a la sesión.
+                match binding.action {
+                    ShortcutAction::Marker => {
+                        let state = app.state::<crate::AppState>();
+                        if let Err(err) =
+                            crate::commands::add_chapter_marker_to_session(&state, None)
+                        {
+                            eprintln!("[shortcuts] No se pudo marcar el capítulo: {err}");
+                        }
+                    }
+                    ShortcutAction::Snapshot => {
+                        let app = app.clone();
+                        thread::spawn(move || {
+                            let state = app.state::<crate::AppState>();
+                            if let Err(err) =
+                                crate::commands::copy_frame_of_session_to_clipboard(&state)
+                            {
+                                eprintln!("[shortcuts] No se pudo copiar el frame: {err}");
+                            }
+                        });
+                    }
+                    _ => {}
+                }
+
                 if app
                     .emit(
                         EVENT_GLOBAL_SHORTCUT_TRIGGERED,
@@ -193,11 +252,17 @@ struct ParsedBinding {
 
 #[cfg(windows)]
 fn parse_bindings(bindings: &ShortcutBindings) -> Result<Vec<ParsedBinding>, String> {
-    let entries = [
+    let mut entries = vec![
         (ShortcutAction::Start, bindings.start.as_str()),
         (ShortcutAction::PauseResume, bindings.pause_resume.as_str()),
         (ShortcutAction::Stop, bindings.stop.as_str()),
     ];
+    if let Some(marker) = bindings.marker.as_deref() {
+        entries.push((ShortcutAction::Marker, marker));
+    }
+    if let Some(snapshot) = bindings.snapshot.as_deref() {
+        entries.push((ShortcutAction::Snapshot, snapshot));
+    }
 
     let mut parsed_bindings = Vec::with_capacity(entries.len());
 
@@ -430,6 +495,8 @@ mod tests {
             start: "Ctrl+Alt+R".to_string(),
             pause_resume: "Ctrl+Alt+P".to_string(),
             stop: "Ctrl+Alt+S".to_string(),
+            marker: None,
+            snapshot: None,
         };
 
         assert!(validate_bindings_shape(&bindings).is_ok());
@@ -441,6 +508,8 @@ mod tests {
             start: " ".to_string(),
             pause_resume: "Ctrl+Alt+P".to_string(),
             stop: "Ctrl+Alt+S".to_string(),
+            marker: None,
+            snapshot: None,
         };
 
         let err = validate_bindings_shape(&bindings).expect_err("debio fallar por atajo vacio");
@@ -453,10 +522,44 @@ mod tests {
             start: "Ctrl+Alt+R".to_string(),
             pause_resume: "ctrl+alt+r".to_string(),
             stop: "Ctrl+Alt+S".to_string(),
+            marker: None,
+            snapshot: None,
         };
 
         let err =
             validate_bindings_shape(&bindings).expect_err("debio fallar por atajos duplicados");
         assert!(err.contains("atajo distinto"));
     }
+
+    #[test]
+    fn atajo_de_marcador_es_opcional_pero_no_se_repite() {
+        let mut bindings = ShortcutBindings {
+            start: "Ctrl+Alt+R".to_string(),
+            pause_resume: "Ctrl+Alt+P".to_string(),
+            stop: "Ctrl+Alt+S".to_string(),
+            marker: Some("Ctrl+Alt+M".to_string()),
+            snapshot: None,
+        };
+        assert!(validate_bindings_shape(&bindings).is_ok());
+
+        bindings.marker = Some("ctrl+alt+s".to_string());
+        let err = validate_bindings_shape(&bindings).expect_err("debio fallar por duplicado");
+        assert!(err.contains("atajo distinto"));
+    }
+
+    #[test]
+    fn atajo_de_captura_de_frame_no_repite_el_del_marcador() {
+        let mut bindings = ShortcutBindings {
+            start: "Ctrl+Alt+R".to_string(),
+            pause_resume: "Ctrl+Alt+P".to_string(),
+            stop: "Ctrl+Alt+S".to_string(),
+            marker: Some("Ctrl+Alt+M".to_string()),
+            snapshot: Some("Ctrl+Alt+C".to_string()),
+        };
+        assert!(validate_bindings_shape(&bindings).is_ok());
+
+        bindings.snapshot = Some("ctrl+alt+m".to_string());
+        let err = validate_bindings_shape(&bindings).expect_err("debio fallar por duplicado");
+        assert!(err.contains("atajo distinto"));
+    }
 }
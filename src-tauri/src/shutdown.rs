@@ -0,0 +1,257 @@
+use std::{
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    commands::{lock_capture, stop_recording_session},
+    encoder::{
+        audio_capture::clear_live_audio_capture,
+        disk_space::set_live_disk_space_status,
+        encoded_duration::set_live_encoded_duration_ms,
+        pipeline_latency::reset_pipeline_latency,
+        post_process::{cancel_post_process_jobs, get_post_process_jobs},
+        processing_status::{is_processing, set_processing},
+        video_encoder_status::set_live_video_encoder_label,
+    },
+    AppState,
+};
+
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShutdownStep {
+    GlobalShortcuts,
+    RecordingScheduler,
+    ActiveSession,
+    ProcessingQueue,
+    LiveState,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShutdownIssue {
+    pub step: ShutdownStep,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShutdownReport {
+    pub completed: Vec<ShutdownStep>,
+    pub incomplete: Vec<ShutdownIssue>,
+}
+
+impl ShutdownReport {
+    fn record(&mut self, step: ShutdownStep, result: Result<(), String>) {
+        match result {
+            Ok(()) => self.completed.push(step),
+            Err(message) => self.incomplete.push(ShutdownIssue { step, message }),
+        }
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.incomplete.is_empty()
+    }
+}
+
+pub(crate) fn wait_until(deadline: Instant, mut done: impl FnMut() -> bool) -> bool {
+    loop {
+        if done() {
+            return true;
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return false;
+        }
+        thread::sleep(SHUTDOWN_POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+pub(crate) fn join_thread_until(handle: JoinHandle<()>, deadline: Instant) -> bool {
+    wait_until(deadline, || handle.is_finished()) && handle.join().is_ok()
+}
+
+impl AppState {
+    pub fn shutdown(&self, timeout: Duration) -> ShutdownReport {
+        let deadline = Instant::now() + timeout;
+        let mut report = ShutdownReport::default();
+
+        report.record(
+            ShutdownStep::GlobalShortcuts,
+            self.shutdown_global_shortcuts(deadline),
+        );
+        report.record(
+            ShutdownStep::RecordingScheduler,
+            self.shutdown_recording_scheduler(deadline),
+        );
+        report.record(ShutdownStep::ActiveSession, self.stop_active_session());
+        report.record(
+            ShutdownStep::ProcessingQueue,
+            drain_processing_queue(deadline),
+        );
+        clear_live_state();
+        report.record(ShutdownStep::LiveState, Ok(()));
+
+        report
+    }
+
+    fn shutdown_global_shortcuts(&self, deadline: Instant) -> Result<(), String> {
+        let manager = self
+            .global_shortcuts
+            .lock()
+            .map_err(|_| "No se pudo acceder al gestor de atajos globales".to_string())?
+            .take();
+
+        if manager.is_some_and(|manager| !manager.shutdown(deadline)) {
+            return Err("El hilo de atajos globales no terminó a tiempo".to_string());
+        }
+        Ok(())
+    }
+
+    fn shutdown_recording_scheduler(&self, deadline: Instant) -> Result<(), String> {
+        let scheduler = self
+            .recording_scheduler
+            .lock()
+            .map_err(|_| "No se pudo acceder al programador de grabaciones".to_string())?
+            .take();
+
+        if scheduler.is_some_and(|scheduler| !scheduler.shutdown(deadline)) {
+            return Err("El hilo de grabaciones programadas no terminó a tiempo".to_string());
+        }
+        Ok(())
+    }
+
+    fn stop_active_session(&self) -> Result<(), String> {
+        let is_active = {
+            let mut manager = lock_capture(self)?;
+            manager.refresh_runtime_state();
+            manager.is_active()
+        };
+
+        if is_active {
+            stop_recording_session(self)?;
+        }
+        Ok(())
+    }
+}
+
+fn drain_processing_queue(deadline: Instant) -> Result<(), String> {
+    if wait_until(deadline, || !is_processing()) {
+        return Ok(());
+    }
+
+    let pending_jobs = get_post_process_jobs().len();
+    let _ = cancel_post_process_jobs(None);
+    Err(format!(
+        "El procesamiento no terminó a tiempo; se cancelaron {pending_jobs} trabajo(s) de post-procesamiento"
+    ))
+}
+
+fn clear_live_state() {
+    set_live_video_encoder_label(None);
+    set_live_disk_space_status(None);
+    set_live_encoded_duration_ms(0);
+    reset_pipeline_latency();
+    clear_live_audio_capture();
+    set_processing(false);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{
+        capture::{
+            manager::CaptureManager,
+            test_support::{make_mock_manager, make_session_config},
+        },
+        encoder::processing_status::{processing_test_lock, ProcessingGuard},
+    };
+
+    fn app_state_with(capture: CaptureManager) -> AppState {
+        AppState {
+            capture: Mutex::new(capture),
+            global_shortcuts: Mutex::new(None),
+            recording_scheduler: Mutex::new(None),
+        }
+    }
+
+    #[test]
+    fn apagado_detiene_la_sesion_en_orden() {
+        let _lock = processing_test_lock()
+            .lock()
+            .expect("lock de test poisoned");
+        let mut capture = make_mock_manager();
+        capture.start(make_session_config(1)).unwrap();
+        let state = app_state_with(capture);
+
+        let report = state.shutdown(Duration::from_secs(1));
+
+        assert!(report.is_clean(), "{:?}", report.incomplete);
+        assert_eq!(
+            report.completed,
+            vec![
+                ShutdownStep::GlobalShortcuts,
+                ShutdownStep::RecordingScheduler,
+                ShutdownStep::ActiveSession,
+                ShutdownStep::ProcessingQueue,
+                ShutdownStep::LiveState,
+            ]
+        );
+        assert!(!state.capture.lock().unwrap().is_active());
+    }
+
+    #[test]
+    fn apagado_reporta_procesamiento_pendiente_al_vencer_el_plazo() {
+        let _lock = processing_test_lock()
+            .lock()
+            .expect("lock de test poisoned");
+        let state = app_state_with(make_mock_manager());
+        let processing_guard = ProcessingGuard::start();
+
+        let started_at = Instant::now();
+        let report = state.shutdown(Duration::from_millis(100));
+
+        assert!(started_at.elapsed() < Duration::from_secs(2));
+        assert_eq!(report.incomplete.len(), 1);
+        assert_eq!(report.incomplete[0].step, ShutdownStep::ProcessingQueue);
+        assert_eq!(report.completed.last(), Some(&ShutdownStep::LiveState));
+        drop(processing_guard);
+    }
+
+    #[test]
+    fn espera_respeta_el_plazo() {
+        let deadline = Instant::now() + Duration::from_millis(30);
+        assert!(!wait_until(deadline, || false));
+        assert!(Instant::now() >= deadline);
+
+        let mut polls = 0;
+        assert!(wait_until(Instant::now() + Duration::from_secs(1), || {
+            polls += 1;
+            polls == 3
+        }));
+    }
+
+    #[test]
+    fn join_de_hilo_terminado_y_colgado() {
+        let finished = thread::spawn(|| {});
+        assert!(join_thread_until(
+            finished,
+            Instant::now() + Duration::from_secs(1)
+        ));
+
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let stuck = thread::spawn(move || {
+            let _ = rx.recv();
+        });
+        assert!(!join_thread_until(
+            stuck,
+            Instant::now() + Duration::from_millis(30)
+        ));
+        drop(tx);
+    }
+}
@@ -126,6 +126,8 @@ const SHORTCUT_CARD_TITLES: Record<ShortcutAction, string> = {
   start: "Iniciar grabación",
   pauseResume: "Pausar grabación",
   stop: "Detener grabación",
+  marker: "Marcar capítulo",
+  snapshot: "Copiar frame al portapapeles",
 };
 
 function splitShortcutTokens(shortcut: string): string[] {
@@ -336,9 +338,9 @@ export function AdvancedSettingsModal({
                 disabled={format === "webM" || isRecording}
               >
                 <option value="auto">Auto (recomendado)</option>
-                {videoEncoderCapabilities.nvenc && <option value="nvenc">NVENC (NVIDIA)</option>}
-                {videoEncoderCapabilities.amf && <option value="amf">AMF (AMD)</option>}
-                {videoEncoderCapabilities.qsv && <option value="qsv">QSV (Intel)</option>}
+                {videoEncoderCapabilities.h264.nvenc && <option value="nvenc">NVENC (NVIDIA)</option>}
+                {videoEncoderCapabilities.h264.amf && <option value="amf">AMF (AMD)</option>}
+                {videoEncoderCapabilities.h264.qsv && <option value="qsv">QSV (Intel)</option>}
                 <option value="h264">H.264</option>
                 <option value="h265">H.265</option>
                 <option value="vp9">VP9</option>
@@ -519,6 +521,24 @@ export function AdvancedSettingsModal({
                 onCaptureCancel={handleShortcutCaptureCancel}
                 onShortcutChange={handleShortcutCaptureSave}
               />
+              <ShortcutBindingCard
+                action="marker"
+                value={shortcuts.marker}
+                disabled={isRecording}
+                isCapturing={capturingShortcut === "marker"}
+                onCaptureStart={handleShortcutCaptureStart}
+                onCaptureCancel={handleShortcutCaptureCancel}
+                onShortcutChange={handleShortcutCaptureSave}
+              />
+              <ShortcutBindingCard
+                action="snapshot"
+                value={shortcuts.snapshot}
+                disabled={isRecording}
+                isCapturing={capturingShortcut === "snapshot"}
+                onCaptureStart={handleShortcutCaptureStart}
+                onCaptureCancel={handleShortcutCaptureCancel}
+                onShortcutChange={handleShortcutCaptureSave}
+              />
             </div>
           </section>
         </div>
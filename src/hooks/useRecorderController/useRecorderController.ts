@@ -5,7 +5,7 @@ import { open } from "@tauri-apps/plugin-dialog";
 import { join, homeDir } from "@tauri-apps/api/path";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { exists, mkdir } from "@tauri-apps/plugin-fs";
-import { Grabador } from "../../recorder/Grabador";
+import { Grabador, RECORDING_TICK_EVENT } from "../../recorder/Grabador";
 import type {
   CaptureManagerSnapshot,
   CaptureState,
@@ -17,6 +17,7 @@ import type {
   RecordingQualityMode,
   RecordingAudioStatus,
   RecordingSessionConfig,
+  RecordingTick,
   VideoCodec,
   VideoEncoderCapabilities,
   VideoEncoderPreference,
@@ -64,16 +65,20 @@ const RESOLUTION_DIMENSIONS: Record<Exclude<ResolutionChoice, "custom">, { width
   p2160: { width: 3840, height: 2160 },
 };
 
+const NO_CODEC_BACKENDS = { nvenc: false, amf: false, qsv: false, software: false };
+
 const DEFAULT_VIDEO_ENCODER_CAPABILITIES: VideoEncoderCapabilities = {
-  nvenc: false,
-  amf: false,
-  qsv: false,
-  software: true,
+  h264: { ...NO_CODEC_BACKENDS, software: true },
+  hevc: { ...NO_CODEC_BACKENDS },
+  vp9: { ...NO_CODEC_BACKENDS },
+  av1: { ...NO_CODEC_BACKENDS },
 };
 
 const DEBUG_REGION = true;
 const SHORTCUTS_STORAGE_KEY = "capturist.shortcuts.v1";
-const LEGACY_DEFAULT_SHORTCUTS: RecorderShortcuts = {
+// Sin ticks recientes el tiempo vuelve a salir del polling.
+const RECORDING_TICK_STALE_MS = 1000;
+const LEGACY_DEFAULT_SHORTCUTS: Omit<RecorderShortcuts, "marker" | "snapshot"> = {
   start: "Ctrl+Shift+R",
   pauseResume: "Ctrl+Shift+P",
   stop: "Ctrl+Shift+S",
@@ -135,11 +140,11 @@ function resolveCodecSelection(choice: CodecChoice): {
 function isCodecChoiceAvailable(choice: CodecChoice, capabilities: VideoEncoderCapabilities): boolean {
   switch (choice) {
     case "nvenc":
-      return capabilities.nvenc;
+      return capabilities.h264.nvenc;
     case "amf":
-      return capabilities.amf;
+      return capabilities.h264.amf;
     case "qsv":
-      return capabilities.qsv;
+      return capabilities.h264.qsv;
     default:
       return true;
   }
@@ -207,6 +212,14 @@ export function useRecorderController() {
     captureMicrophoneAudio: false,
     systemAudioDeviceName: null,
     microphoneAudioDeviceName: null,
+    systemAudioEnabledMs: 0,
+    systemAudioMutedMs: 0,
+    microphoneEnabledMs: 0,
+    microphoneMutedMs: 0,
+    microphoneMonitoring: false,
+    microphoneGainPercent: null,
+    systemAudioLostAtMs: null,
+    microphoneLostAtMs: null,
   });
   const [audioInputDevices, setAudioInputDevices] = useState<string[]>([]);
   const [selectedMicrophoneDevice, setSelectedMicrophoneDevice] = useState<string | null>(null);
@@ -312,9 +325,14 @@ export function useRecorderController() {
   const systemCaptureLabel = effectiveSystemAudioEnabled ? "Activo" : "Inactivo";
   const microphoneCaptureLabel = effectiveMicrophoneAudioEnabled ? `${microphoneGainPercent}%` : "0%";
 
+  const lastTickAtRef = useRef(0);
+
   const applyStatusSnapshot = (snapshot: CaptureManagerSnapshot) => {
     setStatus(snapshot.state);
-    setElapsedMs(snapshot.elapsedMs);
+    const ticking = Date.now() - lastTickAtRef.current < RECORDING_TICK_STALE_MS;
+    if (snapshot.state !== "running" || !ticking) {
+      setElapsedMs(snapshot.elapsedMs);
+    }
     setLastError(snapshot.lastError ?? null);
     setActiveVideoEncoderLabel(snapshot.videoEncoderLabel ?? null);
     setIsProcessing(snapshot.isProcessing ?? false);
@@ -392,6 +410,24 @@ export function useRecorderController() {
     };
   }, []);
 
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+
+    const bind = async () => {
+      unlisten = await listen<RecordingTick>(RECORDING_TICK_EVENT, (event) => {
+        lastTickAtRef.current = Date.now();
+        setElapsedMs(event.payload.elapsedMs);
+      });
+    };
+
+    void bind();
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, []);
+
   useEffect(() => {
     const interval = setInterval(async () => {
       try {
@@ -740,6 +776,24 @@ export function useRecorderController() {
       if (current.status === "running" || current.status === "paused") {
         void actions.stopRecording();
       }
+      return;
+    }
+
+    if (action === "marker") {
+      if (current.status === "running" || current.status === "paused") {
+        Grabador.addChapterMarker().catch((err) => {
+          console.warn("[shortcuts] no se pudo marcar el capítulo", err);
+        });
+      }
+      return;
+    }
+
+    if (action === "snapshot") {
+      if (current.status === "running" || current.status === "paused") {
+        Grabador.copyFrameToClipboard().catch((err) => {
+          console.warn("[shortcuts] no se pudo copiar el frame", err);
+        });
+      }
     }
   }, []);
 
@@ -749,6 +803,8 @@ export function useRecorderController() {
     const bind = async () => {
       unlisten = await listen<string>(GLOBAL_SHORTCUT_TRIGGERED_EVENT, (event) => {
         const action = event.payload;
+        // "marker" y "snapshot" no se reenvían: el backend ya los resolvió al
+        // detectar el atajo.
         if (action === "start" || action === "pauseResume" || action === "stop") {
           runShortcutAction(action);
         }
@@ -814,6 +870,18 @@ export function useRecorderController() {
       if (pressedShortcut === keyboardShortcuts.stop) {
         event.preventDefault();
         runShortcutAction("stop");
+        return;
+      }
+
+      if (pressedShortcut === keyboardShortcuts.marker) {
+        event.preventDefault();
+        runShortcutAction("marker");
+        return;
+      }
+
+      if (pressedShortcut === keyboardShortcuts.snapshot) {
+        event.preventDefault();
+        runShortcutAction("snapshot");
       }
     };
 
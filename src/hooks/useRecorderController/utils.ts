@@ -25,7 +25,7 @@ export function withExtension(path: string, ext: string) {
   const safe = path.trim();
   if (!safe) return safe;
 
-  const known = ["mp4", "mkv", "webm"];
+  const known = ["mp4", "mkv", "webm", "gif", "hls", "m3u8"];
   const match = safe.match(/\.([a-z0-9]+)$/i);
   if (match && known.includes(match[1].toLowerCase())) {
     return safe.replace(/\.[a-z0-9]+$/i, `.${ext}`);
@@ -3,31 +3,112 @@ import { homeDir, join } from "@tauri-apps/api/path";
 import type {
   CaptureManagerSnapshot,
   CaptureTarget,
+  ChapterMarker,
+  ExportAnimationOptions,
+  FfmpegInfo,
+  OutputFileInfo,
+  ProjectTake,
+  QualityReport,
+  RecordingHistoryEntry,
   OutputFormat,
+  OverlayStyle,
+  RecordingQualityMode,
   RecordingAudioStatus,
   RecordingSessionConfig,
+  RecordingStartReport,
+  RecoverableSession,
+  RecoveredSession,
+  RemuxResult,
+  SessionCheckReport,
+  TargetFilter,
+  TrimResult,
+  ValidationIssue,
+  VideoCodec,
   VideoEncoderCapabilities,
 } from "./types";
 
+export const RECORDING_TICK_EVENT = "recording-tick";
+export const AUDIO_DEVICE_LOST_EVENT = "audio-device-lost";
+export const ENCODER_BACKPRESSURE_EVENT = "encoder-backpressure";
+export const OUTPUT_SINK_FAILED_EVENT = "output-sink-failed";
+export const ANIMATION_EXPORT_PROGRESS_EVENT = "animation-export-progress";
+export const RECORDING_TRIM_COMPLETED_EVENT = "recording-trim-completed";
+export const RECORDING_TRIM_FAILED_EVENT = "recording-trim-failed";
+export const RECORDING_REMUX_PROGRESS_EVENT = "recording-remux-progress";
+export const CAPTURE_TARGETS_CHANGED_EVENT = "capture-targets-changed";
+
 export class Grabador {
   static async isCaptureSupported(): Promise<boolean> {
     return invoke("is_capture_supported");
   }
 
-  static async getTargets(): Promise<CaptureTarget[]> {
-    return invoke("get_targets");
+  static async getTargets(filter?: TargetFilter): Promise<CaptureTarget[]> {
+    return invoke("get_targets", { filter: filter ?? null });
+  }
+
+  static async getTargetThumbnail(
+    targetId: number,
+    maxWidth?: number,
+  ): Promise<string> {
+    return invoke("get_target_thumbnail", { targetId, maxWidth });
   }
 
   static async getAudioInputDevices(): Promise<string[]> {
     return invoke("get_audio_input_devices");
   }
 
-  static async getVideoEncoderCapabilities(): Promise<VideoEncoderCapabilities> {
-    return invoke("get_video_encoder_capabilities");
+  static async getAudioOutputDevices(): Promise<string[]> {
+    return invoke("get_audio_output_devices");
+  }
+
+  static async getFfmpegInfo(): Promise<FfmpegInfo> {
+    return invoke("get_ffmpeg_info");
+  }
+
+  static async estimateBitrateKbps(
+    width: number,
+    height: number,
+    fps: number,
+    codec: VideoCodec,
+    qualityMode: RecordingQualityMode,
+  ): Promise<number> {
+    return invoke("estimate_bitrate_kbps", { width, height, fps, codec, qualityMode });
+  }
+
+  static async generateThumbnail(
+    path: string,
+    atMs?: number,
+    maxWidth?: number,
+  ): Promise<string> {
+    return invoke("generate_thumbnail", { path, atMs, maxWidth });
+  }
+
+  static async listRecoverableSessions(): Promise<RecoverableSession[]> {
+    return invoke("list_recoverable_sessions");
+  }
+
+  static async recoverSessions(destination?: string): Promise<RecoveredSession[]> {
+    return invoke("recover_sessions", { destination });
+  }
+
+  static async getVideoEncoderCapabilities(refresh = false): Promise<VideoEncoderCapabilities> {
+    return invoke("get_video_encoder_capabilities", { refresh });
   }
 
-  static async start(config: RecordingSessionConfig): Promise<void> {
-    await invoke("start_recording", { config });
+  static async redetectVideoEncoderCapabilities(): Promise<VideoEncoderCapabilities> {
+    return invoke("redetect_video_encoder_capabilities");
+  }
+
+  static async start(config: RecordingSessionConfig): Promise<RecordingStartReport> {
+    return invoke("start_recording", { config });
+  }
+
+  static async validateConfig(config: RecordingSessionConfig): Promise<ValidationIssue[]> {
+    return invoke("validate_recording_config", { config });
+  }
+
+  static async validateSession(config: RecordingSessionConfig): Promise<SessionCheckReport> {
+    return invoke("validate_session", { config });
   }
 
   static async updateRecordingAudioCapture(
@@ -39,6 +120,14 @@ export class Grabador {
     });
   }
 
+  static async setMicrophoneMonitoring(enabled: boolean): Promise<void> {
+    await invoke("set_recording_microphone_monitoring", { enabled });
+  }
+
+  static async setMicrophoneGain(percent: number): Promise<void> {
+    await invoke("set_microphone_gain", { percent });
+  }
+
   static async pause(): Promise<void> {
     await invoke("pause_recording");
   }
@@ -47,14 +136,102 @@ export class Grabador {
     await invoke("resume_recording");
   }
 
-  static async stop(): Promise<void> {
-    await invoke("stop_recording");
+  static async stop(): Promise<string> {
+    return invoke("stop_recording");
+  }
+
+  static async stopAndWait(timeoutMs?: number | null): Promise<string> {
+    return invoke("stop_recording_blocking", { timeoutMs: timeoutMs ?? null });
+  }
+
+  static async getLastOutputPath(): Promise<string | null> {
+    return invoke("get_last_output_path");
+  }
+
+  static async getLastOutputPaths(): Promise<string[]> {
+    return invoke("get_last_output_paths");
+  }
+
+  static async addChapterMarker(label?: string | null): Promise<ChapterMarker> {
+    return invoke("add_chapter_marker", { label: label ?? null });
+  }
+
+  static async copyFrameToClipboard(): Promise<void> {
+    return invoke("copy_frame_to_clipboard");
   }
 
   static async cancel(): Promise<void> {
     await invoke("cancel_recording");
   }
 
+  static async cancelPostProcessing(id?: number): Promise<number> {
+    return invoke("cancel_post_processing", { id: id ?? null });
+  }
+
+  static async outputFileInfo(path: string): Promise<OutputFileInfo> {
+    return invoke("get_output_file_info", { path });
+  }
+
+  static async exportAnimation(
+    inputPath: string,
+    outputPath: string,
+    options: ExportAnimationOptions,
+  ): Promise<string> {
+    return invoke("export_animation", {
+      inputPath,
+      outputPath,
+      startMs: options.startMs ?? null,
+      endMs: options.endMs ?? null,
+      format: options.format,
+      fps: options.fps ?? null,
+      maxWidth: options.maxWidth ?? null,
+    });
+  }
+
+  static async trimRecording(
+    inputPath: string,
+    startMs: number | null,
+    endMs: number | null,
+    outputPath: string,
+    precise = false,
+  ): Promise<TrimResult> {
+    return invoke("trim_recording", { inputPath, startMs, endMs, outputPath, precise });
+  }
+
+  static async remuxRecording(
+    inputPath: string,
+    targetFormat: OutputFormat,
+    outputPath: string,
+  ): Promise<RemuxResult> {
+    return invoke("remux_recording", { inputPath, targetFormat, outputPath });
+  }
+
+  static async analyzeRecordingQuality(
+    path: string,
+    referenceSeconds?: number | null,
+  ): Promise<QualityReport> {
+    return invoke("analyze_recording_quality", {
+      path,
+      referenceSeconds: referenceSeconds ?? null,
+    });
+  }
+
+  static async projectTakes(project: string, baseDir?: string | null): Promise<ProjectTake[]> {
+    return invoke("get_project_takes", { project, baseDir: baseDir ?? null });
+  }
+
+  static async recentRecordings(limit?: number | null): Promise<RecordingHistoryEntry[]> {
+    return invoke("get_recent_recordings", { limit: limit ?? null });
+  }
+
+  static async clearRecordingHistory(): Promise<void> {
+    await invoke("clear_recording_history");
+  }
+
+  static async revealInFileManager(path: string): Promise<void> {
+    await invoke("reveal_in_file_manager", { path });
+  }
+
   static async status(): Promise<CaptureManagerSnapshot> {
     return invoke("get_recording_status");
   }
@@ -65,8 +242,9 @@ export class Grabador {
 
   static async selectRegionNative(
     target: CaptureTarget | null,
+    style?: OverlayStyle,
   ): Promise<import("./types").CropRegion | null> {
-    return invoke("select_region_native", { target });
+    return invoke("select_region_native", { target, style: style ?? null });
   }
 
   static async defaultOutputPath(format: OutputFormat) {
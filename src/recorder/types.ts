@@ -11,9 +11,45 @@ export interface CaptureTarget {
   screenHeight: number;
   isPrimary: boolean;
   kind: TargetKind;
+  processName?: string | null;
+  processId?: number | null;
+  windowTitle?: string | null;
+  refreshRateHz?: number | null;
+  dpiScalePercent?: number | null;
+  isMinimized?: boolean;
+}
+
+export type TargetKindFilter = "monitors" | "windows" | "all";
+
+export interface TargetFilter {
+  kind?: TargetKindFilter;
+  processNameContains?: string | null;
+  includeMinimized?: boolean;
 }
 
 export type CaptureState = "idle" | "running" | "paused" | "stopped";
+export type StopReason = "diskFull" | "fileSizeLimit" | "sourceLost";
+export type Fat32Policy = "reject" | "segment";
+
+export interface PipelineLatency {
+  p50Ms: number;
+  p95Ms: number;
+}
+
+export type PostProcessKind =
+  | "gifExport"
+  | "fitToSize"
+  | "frameInterpolation"
+  | "qualityAnalysis"
+  | "animationExport"
+  | "trim"
+  | "remux";
+
+export interface PostProcessJob {
+  id: number;
+  kind: PostProcessKind;
+  outputPath: string;
+}
 
 export interface CaptureManagerSnapshot {
   state: CaptureState;
@@ -21,6 +57,182 @@ export interface CaptureManagerSnapshot {
   lastError?: string | null;
   videoEncoderLabel?: string | null;
   isProcessing: boolean;
+  processingEtaMs?: number | null;
+  pipelineLatencyMs?: PipelineLatency | null;
+  stopReason?: StopReason | null;
+  diskSpaceRemainingBytes?: number | null;
+  estimatedMinutesLeft?: number | null;
+  postProcessingJobs: PostProcessJob[];
+  autoPaused: boolean;
+  warning?: string | null;
+  appliedFps?: number | null;
+  capturedFrames?: number;
+}
+
+export interface RecordingTick {
+  elapsedMs: number;
+  encodedDurationMs: number;
+}
+
+export type AudioTrackKind = "system" | "microphone";
+
+export interface AudioDeviceLost {
+  track: AudioTrackKind;
+  deviceName: string;
+  lostAtMs: number;
+  message: string;
+}
+
+export interface OutputSinkFailed {
+  index: number;
+  target: string;
+  message: string;
+}
+
+export interface EncoderBackpressure {
+  totalDropped: number;
+  droppedInWindow: number;
+  queueDepth: number;
+  finished: boolean;
+  suggestion: string;
+}
+
+export interface ChapterMarker {
+  elapsedMs: number;
+  label: string;
+}
+
+export interface DiskSpaceWarning {
+  path: string;
+  availableBytes: number;
+  estimatedRequiredBytes: number;
+  assumedDurationMinutes: number;
+}
+
+export interface FileSizeLimitWarning {
+  path: string;
+  filesystem: string;
+  limitBytes: number;
+}
+
+export interface FpsClampWarning {
+  requestedFps: number;
+  appliedFps: number;
+  refreshRateHz: number;
+}
+
+export interface RecordingStartReport {
+  diskSpaceWarnings: DiskSpaceWarning[];
+  fileSizeLimitWarnings: FileSizeLimitWarning[];
+  fpsWarning?: FpsClampWarning | null;
+}
+
+export type ValidationIssueSeverity = "error" | "warning";
+
+export interface ValidationIssue {
+  severity: ValidationIssueSeverity;
+  code: string;
+  message: string;
+}
+
+export type SessionCheckName =
+  | "config"
+  | "target"
+  | "videoEncoder"
+  | "systemAudio"
+  | "microphone";
+
+export interface SessionCheck {
+  check: SessionCheckName;
+  passed: boolean;
+  detail: string;
+}
+
+export interface SessionCheckReport {
+  passed: boolean;
+  checks: SessionCheck[];
+}
+
+export interface FfmpegLibraryVersion {
+  name: string;
+  version: string;
+}
+
+export interface FfmpegInfo {
+  cliPath: string;
+  cliExists: boolean;
+  cliVersion: string | null;
+  libraries: FfmpegLibraryVersion[];
+}
+
+export interface RecordingHistoryEntry {
+  path: string;
+  startedAt: string;
+  durationMs: number;
+  sizeBytes: number;
+  targetName?: string | null;
+  format: OutputFormat;
+  project?: string | null;
+  thumbnailPath?: string | null;
+}
+
+export interface RecoverableSession {
+  sessionDir: string;
+  videoPath?: string | null;
+  audioPaths: string[];
+  sizeBytes: number;
+  modifiedAt?: string | null;
+}
+
+export interface RecoveredSession {
+  sessionDir: string;
+  outputPath?: string | null;
+  rawFiles: string[];
+  error?: string | null;
+}
+
+export interface ProjectTake {
+  path: string;
+  date: string;
+  take: number;
+  sizeBytes: number;
+  durationMs: number | null;
+}
+
+export type QualityScoreSource = "heuristic" | "vmaf";
+
+export interface QualityMetrics {
+  spatialInformationMean: number;
+  spatialInformationMax: number;
+  blockinessMean: number;
+  blockinessMax: number;
+  vmaf?: number | null;
+}
+
+export interface QualityReport {
+  path: string;
+  referenceSeconds: number;
+  framesAnalyzed: number;
+  score: number;
+  scoreSource: QualityScoreSource;
+  metrics: QualityMetrics;
+}
+
+export interface OutputFileInfo {
+  path: string;
+  durationMs: number;
+  container: string;
+  videoCodec?: string | null;
+  width?: number | null;
+  height?: number | null;
+  fps?: number | null;
+  audioCodecs: string[];
+  audioChannels?: number | null;
+  fileSizeBytes: number;
+  colorRange?: string | null;
+  colorSpace?: string | null;
+  colorPrimaries?: string | null;
+  colorTransfer?: string | null;
 }
 
 export interface RecordingAudioStatus {
@@ -28,16 +240,72 @@ export interface RecordingAudioStatus {
   captureMicrophoneAudio: boolean;
   systemAudioDeviceName?: string | null;
   microphoneAudioDeviceName?: string | null;
+  systemAudioEnabledMs: number;
+  systemAudioMutedMs: number;
+  microphoneEnabledMs: number;
+  microphoneMutedMs: number;
+  microphoneMonitoring: boolean;
+  microphoneGainPercent?: number | null;
+  systemAudioLostAtMs?: number | null;
+  microphoneLostAtMs?: number | null;
 }
 
-export interface VideoEncoderCapabilities {
+export interface CodecBackendSupport {
   nvenc: boolean;
   amf: boolean;
   qsv: boolean;
   software: boolean;
 }
 
-export type OutputFormat = "mp4" | "mkv" | "webM";
+export interface VideoEncoderCapabilities {
+  h264: CodecBackendSupport;
+  hevc: CodecBackendSupport;
+  vp9: CodecBackendSupport;
+  av1: CodecBackendSupport;
+}
+
+export type OutputFormat = "mp4" | "mkv" | "webM" | "gif" | "hls";
+
+export type AnimationFormat = "gif" | "webp";
+
+export interface ExportAnimationOptions {
+  startMs?: number | null;
+  endMs?: number | null;
+  format: AnimationFormat;
+  fps?: number | null;
+  maxWidth?: number | null;
+}
+
+export interface TrimResult {
+  inputPath: string;
+  outputPath: string;
+  startMs: number;
+  endMs: number;
+  reencodedUntilMs?: number | null;
+}
+
+export interface RemuxResult {
+  outputPath: string;
+  warnings: string[];
+}
+
+export interface RemuxProgress {
+  outputPath: string;
+  processedMs: number;
+  totalMs: number;
+}
+
+export interface TrimFailed {
+  inputPath: string;
+  outputPath: string;
+  message: string;
+}
+
+export interface AnimationExportProgress {
+  outputPath: string;
+  processedMs: number;
+  totalMs: number;
+}
 
 export type VideoCodec = "h264" | "h265" | "vp9";
 export type VideoEncoderPreference = "auto" | "nvenc" | "amf" | "qsv" | "software";
@@ -53,6 +321,19 @@ export type OutputResolution =
 
 export type EncoderPreset = "ultraFast" | "fast" | "medium";
 export type RecordingQualityMode = "performance" | "balanced" | "quality";
+export type MicNoiseSuppression = "off" | "light" | "strong";
+export type AudioCodec = "aac" | "opus" | "flac" | "pcm";
+export type ColorRange = "limited" | "full";
+
+export type ScalerAlgorithm = "fastBilinear" | "bilinear" | "bicubic" | "lanczos";
+export type ChromaSubsampling = "yuv420" | "yuv444";
+export type BitDepth = "eight" | "ten";
+
+export interface OverlayStyle {
+  dimAlpha?: number;
+  borderRgb?: [number, number, number];
+  borderThicknessPx?: number;
+}
 
 export interface CropRegion {
   x: number;
@@ -65,17 +346,66 @@ export interface RecordingSessionConfig {
   targetId: number;
   fps: number;
   cropRegion?: CropRegion | null;
+  autoPauseIdleMs?: number | null;
+  followWindowMonitor?: boolean;
+  secondaryTargetId?: number | null;
+  cursorScale?: number;
+  showRegionBorder?: boolean;
+  excludeWindowIds?: number[];
+  includeSecondaryWindows?: boolean | null;
+  restoreIfMinimized?: boolean;
+  adaptiveFps?: boolean;
+  outputTarget?: { kind: "file" } | { kind: "rtmp"; url: string; key: string } | null;
+  outputs?: Array<{ kind: "file"; path: string } | { kind: "rtmp"; url: string; key: string }> | null;
+  trimStartMs?: number | null;
+  trimEndMs?: number | null;
+  hlsSegmentSeconds?: number;
+  ffmpegBinPath?: string | null;
   outputPath: string;
+  outputTemplate?: string | null;
+  project?: string | null;
+  title?: string | null;
+  comment?: string | null;
+  author?: string | null;
   format: OutputFormat;
   codec?: VideoCodec | null;
   videoEncoderPreference?: VideoEncoderPreference;
   resolution: OutputResolution;
+  neverUpscale?: boolean;
   crf: number;
   preset: EncoderPreset;
   qualityMode?: RecordingQualityMode;
+  colorRange?: ColorRange;
+  hdr?: boolean;
+  scaler?: ScalerAlgorithm | null;
+  chromaSubsampling?: ChromaSubsampling | null;
+  bitDepth?: BitDepth | null;
+  profile?: string | null;
+  level?: string | null;
+  blurRegions?: CropRegion[] | null;
   captureSystemAudio?: boolean;
   captureMicrophoneAudio?: boolean;
   systemAudioDevice?: string | null;
   microphoneDevice?: string | null;
   microphoneGainPercent?: number;
+  audioSyncOffsetMs?: number;
+  audioSampleRate?: number | null;
+  audioChannels?: number | null;
+  micNoiseSuppression?: MicNoiseSuppression | null;
+  normalizeLoudness?: boolean;
+  loudnessTargetLufs?: number | null;
+  audioCodec?: AudioCodec | null;
+  audioBitrateKbps?: number | null;
+  allowOpusInMp4?: boolean;
+  monitorMicrophone?: boolean;
+  diskSpaceThresholdMb?: number;
+  fat32Policy?: Fat32Policy;
+  gifFps?: number;
+  gifMaxWidth?: number;
+  fitToSizeMb?: number | null;
+  interpolateToFps?: number | null;
+  mp4Faststart?: boolean;
+  keyframeIntervalFrames?: number | null;
+  forceCfr?: boolean;
+  frameRateMode?: "vfr" | "cfr" | null;
 }
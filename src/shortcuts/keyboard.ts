@@ -1,22 +1,28 @@
-export type ShortcutAction = "start" | "pauseResume" | "stop";
+export type ShortcutAction = "start" | "pauseResume" | "stop" | "marker" | "snapshot";
 export const GLOBAL_SHORTCUT_TRIGGERED_EVENT = "global-shortcut-triggered";
 
 export interface RecorderShortcuts {
   start: string;
   pauseResume: string;
   stop: string;
+  marker: string;
+  snapshot: string;
 }
 
 export const DEFAULT_SHORTCUTS: RecorderShortcuts = {
   start: "Ctrl+Alt+R",
   pauseResume: "Ctrl+Alt+P",
   stop: "Ctrl+Alt+S",
+  marker: "Ctrl+Alt+M",
+  snapshot: "Ctrl+Alt+C",
 };
 
 export const SHORTCUT_LABELS: Record<ShortcutAction, string> = {
   start: "Iniciar",
   pauseResume: "Pausar/Reanudar",
   stop: "Detener",
+  marker: "Marcador",
+  snapshot: "Copiar frame",
 };
 
 const MODIFIER_ALIASES: Record<string, "Ctrl" | "Alt" | "Shift" | "Meta"> = {
@@ -148,18 +154,28 @@ export function hydrateShortcuts(raw: unknown) {
     typeof data.pauseResume === "string" ? data.pauseResume : "",
   );
   const stop = normalizeShortcut(typeof data.stop === "string" ? data.stop : "");
+  const marker = normalizeShortcut(typeof data.marker === "string" ? data.marker : "");
+  const snapshot = normalizeShortcut(typeof data.snapshot === "string" ? data.snapshot : "");
 
   const hydrated: RecorderShortcuts = {
     start: start ?? DEFAULT_SHORTCUTS.start,
     pauseResume: pauseResume ?? DEFAULT_SHORTCUTS.pauseResume,
     stop: stop ?? DEFAULT_SHORTCUTS.stop,
+    marker: marker ?? DEFAULT_SHORTCUTS.marker,
+    snapshot: snapshot ?? DEFAULT_SHORTCUTS.snapshot,
   };
 
   return ensureUniqueShortcuts(hydrated);
 }
 
 function ensureUniqueShortcuts(shortcuts: RecorderShortcuts): RecorderShortcuts {
-  const orderedActions: ShortcutAction[] = ["start", "pauseResume", "stop"];
+  const orderedActions: ShortcutAction[] = [
+    "start",
+    "pauseResume",
+    "stop",
+    "marker",
+    "snapshot",
+  ];
   const normalized: RecorderShortcuts = { ...shortcuts };
   const used = new Set<string>();
 